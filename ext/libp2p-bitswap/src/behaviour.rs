@@ -60,6 +60,26 @@ pub enum BitswapEvent {
     Progress(QueryId, usize),
     /// A get or sync query completed.
     Complete(QueryId, Result<()>),
+    /// Received a request from a peer, which has to be passed back to
+    /// [`Bitswap::accept`] or [`Bitswap::reject`]. Only emitted when
+    /// [`BitswapConfig::intercept_requests`] is enabled.
+    Request(InboundRequest),
+    /// Sent a block to a peer in response to an accepted request. Only emitted
+    /// when [`BitswapConfig::intercept_requests`] is enabled.
+    BlockSent(PeerId, usize),
+    /// Received a valid block from a peer in response to one of our requests.
+    /// Only emitted when [`BitswapConfig::intercept_requests`] is enabled.
+    BlockReceived(PeerId, usize),
+}
+
+/// A request received from a peer, waiting for a decision whether to serve it.
+#[derive(Debug)]
+pub struct InboundRequest {
+    /// The peer the request came from.
+    pub peer_id: PeerId,
+    /// The request itself.
+    pub request: BitswapRequest,
+    channel: Channel,
 }
 
 /// Trait implemented by a block store.
@@ -81,6 +101,10 @@ pub trait BitswapStore: Send + Sync + 'static {
 pub struct BitswapConfig {
     /// Timeout of a request.
     pub request_timeout: Duration,
+    /// Raise inbound requests as [`BitswapEvent::Request`] instead of serving them
+    /// right away, so they can be rate limited. Requests over the compat protocol
+    /// are always served.
+    pub intercept_requests: bool,
 }
 
 impl BitswapConfig {
@@ -88,6 +112,7 @@ impl BitswapConfig {
     pub fn new() -> Self {
         Self {
             request_timeout: Duration::from_secs(10),
+            intercept_requests: false,
         }
     }
 }
@@ -106,7 +131,7 @@ enum BitswapId {
 }
 
 enum BitswapChannel {
-    Bitswap(Channel),
+    Bitswap(PeerId, Channel),
     #[cfg(feature = "compat")]
     Compat(PeerId, Cid),
 }
@@ -123,6 +148,8 @@ pub struct Bitswap<P: StoreParams> {
     db_tx: mpsc::UnboundedSender<DbRequest<P>>,
     /// Db response channel.
     db_rx: mpsc::UnboundedReceiver<DbResponse>,
+    /// Whether inbound requests are raised as events.
+    intercept_requests: bool,
     /// Compat peers.
     #[cfg(feature = "compat")]
     compat: FnvHashSet<PeerId>,
@@ -146,6 +173,7 @@ impl<P: StoreParams> Bitswap<P> {
             requests: Default::default(),
             db_tx,
             db_rx,
+            intercept_requests: config.intercept_requests,
             #[cfg(feature = "compat")]
             compat: Default::default(),
         }
@@ -187,6 +215,21 @@ impl<P: StoreParams> Bitswap<P> {
         res
     }
 
+    /// Serves a request raised by [`BitswapEvent::Request`].
+    pub fn accept(&mut self, request: InboundRequest) {
+        let channel = BitswapChannel::Bitswap(request.peer_id, request.channel);
+        self.inject_request(channel, request.request);
+    }
+
+    /// Refuses a request raised by [`BitswapEvent::Request`], responding
+    /// as if we didn't have the block.
+    pub fn reject(&mut self, request: InboundRequest) {
+        REJECTED_INBOUND.inc();
+        self.inner
+            .send_response(request.channel, BitswapResponse::Have(false))
+            .ok();
+    }
+
     /// Registers prometheus metrics.
     pub fn register_metrics(&self, registry: &Registry) -> Result<()> {
         registry.register(Box::new(REQUESTS_TOTAL.clone()))?;
@@ -201,6 +244,7 @@ impl<P: StoreParams> Bitswap<P> {
         registry.register(Box::new(RESPONSES_TOTAL.clone()))?;
         registry.register(Box::new(THROTTLED_INBOUND.clone()))?;
         registry.register(Box::new(THROTTLED_OUTBOUND.clone()))?;
+        registry.register(Box::new(REJECTED_INBOUND.clone()))?;
         registry.register(Box::new(OUTBOUND_FAILURE.clone()))?;
         registry.register(Box::new(INBOUND_FAILURE.clone()))?;
         Ok(())
@@ -286,7 +330,14 @@ impl<P: StoreParams> Bitswap<P> {
     }
 
     /// Processes an incoming bitswap response.
-    fn inject_response(&mut self, id: BitswapId, peer: PeerId, response: BitswapResponse) {
+    /// Handle the response to one of our requests, returning the size of the block if it was a valid one.
+    fn inject_response(
+        &mut self,
+        id: BitswapId,
+        peer: PeerId,
+        response: BitswapResponse,
+    ) -> Option<usize> {
+        let mut received = None;
         if let Some(id) = self.requests.remove(&id) {
             match response {
                 BitswapResponse::Have(have) => {
@@ -301,6 +352,7 @@ impl<P: StoreParams> Bitswap<P> {
                             self.db_tx.unbounded_send(DbRequest::Insert(block)).ok();
                             self.query_manager
                                 .inject_response(id, Response::Block(peer, true));
+                            received = Some(len);
                         } else {
                             tracing::error!("received invalid block");
                             RECEIVED_INVALID_BLOCK_BYTES.inc_by(len as u64);
@@ -311,6 +363,7 @@ impl<P: StoreParams> Bitswap<P> {
                 }
             }
         }
+        received
     }
 
     fn inject_outbound_failure(
@@ -541,7 +594,7 @@ impl<P: StoreParams> NetworkBehaviour for Bitswap<P> {
                         }
                         CompatMessage::Response(cid, res) => {
                             tracing::trace!("received compat response");
-                            self.inject_response(BitswapId::Compat(cid), peer_id, res);
+                            let _ = self.inject_response(BitswapId::Compat(cid), peer_id, res);
                         }
                     }
                 }
@@ -557,8 +610,19 @@ impl<P: StoreParams> NetworkBehaviour for Bitswap<P> {
                 exit = false;
                 match response {
                     DbResponse::Bitswap(channel, response) => match channel {
-                        BitswapChannel::Bitswap(channel) => {
-                            self.inner.send_response(channel, response).ok();
+                        BitswapChannel::Bitswap(peer_id, channel) => {
+                            let sent = match &response {
+                                BitswapResponse::Block(data) => Some(data.len()),
+                                BitswapResponse::Have(_) => None,
+                            };
+                            if self.inner.send_response(channel, response).is_ok()
+                                && self.intercept_requests
+                            {
+                                if let Some(bytes) = sent {
+                                    let event = BitswapEvent::BlockSent(peer_id, bytes);
+                                    return Poll::Ready(ToSwarm::GenerateEvent(event));
+                                }
+                            }
                         }
                         #[cfg(feature = "compat")]
                         BitswapChannel::Compat(peer_id, cid) => {
@@ -640,11 +704,31 @@ impl<P: StoreParams> NetworkBehaviour for Bitswap<P> {
                             request_id: _,
                             request,
                             channel,
-                        } => self.inject_request(BitswapChannel::Bitswap(channel), request),
+                        } => {
+                            if self.intercept_requests {
+                                let event = BitswapEvent::Request(InboundRequest {
+                                    peer_id: peer,
+                                    request,
+                                    channel,
+                                });
+                                return Poll::Ready(ToSwarm::GenerateEvent(event));
+                            }
+                            self.inject_request(BitswapChannel::Bitswap(peer, channel), request)
+                        }
                         request_response::Message::Response {
                             request_id,
                             response,
-                        } => self.inject_response(BitswapId::Bitswap(request_id), peer, response),
+                        } => {
+                            let received = self.inject_response(
+                                BitswapId::Bitswap(request_id),
+                                peer,
+                                response,
+                            );
+                            if let (Some(bytes), true) = (received, self.intercept_requests) {
+                                let event = BitswapEvent::BlockReceived(peer, bytes);
+                                return Poll::Ready(ToSwarm::GenerateEvent(event));
+                            }
+                        }
                     },
                     request_response::Event::ResponseSent { .. } => {}
                     request_response::Event::OutboundFailure {
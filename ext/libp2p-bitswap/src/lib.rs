@@ -17,6 +17,8 @@ mod protocol;
 mod query;
 mod stats;
 
-pub use crate::behaviour::{Bitswap, BitswapConfig, BitswapEvent, BitswapStore, Channel};
+pub use crate::behaviour::{
+    Bitswap, BitswapConfig, BitswapEvent, BitswapStore, Channel, InboundRequest,
+};
 pub use crate::protocol::{BitswapRequest, BitswapResponse};
 pub use crate::query::QueryId;
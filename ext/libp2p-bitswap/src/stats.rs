@@ -74,6 +74,11 @@ lazy_static! {
         "Number of resume send events.",
     )
     .unwrap();
+    pub static ref REJECTED_INBOUND: IntCounter = IntCounter::new(
+        "bitswap_rejected_inbound_total",
+        "Number of inbound requests rejected by the application.",
+    )
+    .unwrap();
     pub static ref OUTBOUND_FAILURE: IntCounterVec = IntCounterVec::new(
         Opts::new(
             "bitswap_outbound_failures_total",
@@ -2,20 +2,22 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use fendermint_actor_blobs_shared::state::Hash;
+use fendermint_actor_blobs_shared::state::{Credit, Hash};
+use fendermint_actor_blobs_shared::transfer_credit;
+use fendermint_actor_recall_config_shared::get_config;
 use fil_actors_runtime::{
     actor_dispatch, actor_error,
     runtime::{ActorCode, Runtime},
     ActorError, FIRST_EXPORTED_METHOD_NUMBER, SYSTEM_ACTOR_ADDR,
 };
 use fvm_ipld_encoding::ipld_block::IpldBlock;
-use fvm_shared::MethodNum;
+use fvm_shared::{address::Address, MethodNum};
 use recall_actor_sdk::emit_evm_event;
 
 use crate::shared::{
     CloseReadRequestParams, GetOpenReadRequestsParams, GetReadRequestStatusParams, Method,
     OpenReadRequestParams, OpenReadRequestTuple, ReadRequestStatus, SetReadRequestPendingParams,
-    State, BLOB_READER_ACTOR_NAME,
+    State, BLOB_READER_ACTOR_ADDR, BLOB_READER_ACTOR_NAME,
 };
 use crate::sol_facade::{ReadRequestClosed, ReadRequestOpened, ReadRequestPending};
 
@@ -37,14 +39,38 @@ impl ReadReqActor {
     ) -> Result<Hash, ActorError> {
         rt.validate_immediate_caller_accept_any()?;
 
+        // Escrow the fee with this actor until the request is fulfilled or expires
+        let requester = rt.message().origin();
+        let fee = params.fee.clone().unwrap_or_default();
+        if fee.is_negative() {
+            return Err(ActorError::illegal_argument(
+                "read request fee must not be negative".into(),
+            ));
+        }
+        if fee.is_positive() {
+            transfer_credit(
+                rt,
+                requester,
+                BLOB_READER_ACTOR_ADDR,
+                Some(fee.clone()),
+                Some("read request fee".into()),
+            )?;
+        }
+
+        let config = get_config(rt)?;
         let id = rt.transaction(|st: &mut State, _rt| {
             st.open_read_request(
                 rt.store(),
+                rt.curr_epoch(),
                 params.hash,
                 params.offset,
                 params.len,
                 params.callback_addr,
                 params.callback_method,
+                params.ttl,
+                config.read_request_max_ttl,
+                requester,
+                fee,
             )
         })?;
 
@@ -88,10 +114,31 @@ impl ReadReqActor {
         params: CloseReadRequestParams,
     ) -> Result<(), ActorError> {
         rt.validate_immediate_caller_is(std::iter::once(&SYSTEM_ACTOR_ADDR))?;
-        rt.transaction(|st: &mut State, _| st.close_read_request(rt.store(), params.0))?;
+        rt.transaction(|st: &mut State, _| {
+            st.close_read_request(rt.store(), rt.curr_epoch(), params.0)
+        })?;
         emit_evm_event(rt, ReadRequestClosed::new(&params.0))
     }
 
+    /// Expires read requests that were not fulfilled before their TTL elapsed.
+    /// This is called by the system actor every debit interval.
+    fn expire_read_requests(rt: &impl Runtime) -> Result<Vec<Hash>, ActorError> {
+        rt.validate_immediate_caller_is(std::iter::once(&SYSTEM_ACTOR_ADDR))?;
+        let expired = rt.transaction(|st: &mut State, _| {
+            st.expire_read_requests(rt.store(), rt.curr_epoch())
+        })?;
+
+        // Return escrowed fees to the requesters
+        let mut ids = Vec::with_capacity(expired.len());
+        for (id, request) in expired {
+            if request.fee.is_positive() {
+                refund_fee(rt, request.requester, request.fee)?;
+            }
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
     fn set_read_request_pending(
         rt: &impl Runtime,
         params: SetReadRequestPendingParams,
@@ -117,6 +164,16 @@ impl ReadReqActor {
     }
 }
 
+fn refund_fee(rt: &impl Runtime, requester: Address, fee: Credit) -> Result<(), ActorError> {
+    transfer_credit(
+        rt,
+        BLOB_READER_ACTOR_ADDR,
+        requester,
+        Some(fee),
+        Some("read request fee refund".into()),
+    )
+}
+
 impl ActorCode for ReadReqActor {
     type Methods = Method;
 
@@ -131,6 +188,7 @@ impl ActorCode for ReadReqActor {
         GetReadRequestStatus => get_read_request_status,
         CloseReadRequest => close_read_request,
         SetReadRequestPending => set_read_request_pending,
+        ExpireReadRequests => expire_read_requests,
         _ => fallback,
     }
 }
@@ -139,13 +197,21 @@ impl ActorCode for ReadReqActor {
 mod tests {
     use super::*;
     use crate::sol_facade::ReadRequestClosed;
+    use crate::state::CLOSED_READ_REQUEST_RETENTION;
+
+    use fendermint_actor_blobs_shared::params::TransferCreditParams;
+    use fendermint_actor_blobs_shared::{Method as BlobMethod, BLOBS_ACTOR_ADDR};
+    use fendermint_actor_recall_config_shared::{RecallConfig, RECALL_CONFIG_ACTOR_ADDR};
 
     use fil_actors_evm_shared::address::EthAddress;
     use fil_actors_runtime::test_utils::{
         expect_empty, MockRuntime, ETHACCOUNT_ACTOR_CODE_ID, SYSTEM_ACTOR_CODE_ID,
     };
     use fvm_ipld_encoding::ipld_block::IpldBlock;
-    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::error::ExitCode;
+    use fvm_shared::sys::SendFlags;
+    use num_traits::Zero;
     use rand::RngCore;
     use recall_actor_sdk::to_actor_event;
 
@@ -175,6 +241,20 @@ mod tests {
         rt
     }
 
+    fn expect_get_config(rt: &MockRuntime) {
+        rt.expect_send(
+            RECALL_CONFIG_ACTOR_ADDR,
+            fendermint_actor_recall_config_shared::Method::GetConfig as MethodNum,
+            None,
+            TokenAmount::zero(),
+            None,
+            SendFlags::READ_ONLY,
+            IpldBlock::serialize_cbor(&RecallConfig::default()).unwrap(),
+            ExitCode::OK,
+            None,
+        );
+    }
+
     fn expect_emitted_open_event(rt: &MockRuntime, params: &OpenReadRequestParams, id: &Hash) {
         let event = to_actor_event(ReadRequestOpened {
             id,
@@ -227,8 +307,11 @@ mod tests {
             len,
             callback_addr: f4_eth_addr,
             callback_method,
+            ttl: None,
+            fee: None,
         };
         let expected_id = Hash::from(1);
+        expect_get_config(&rt);
         expect_emitted_open_event(&rt, &open_params, &expected_id);
         let request_id = rt
             .call::<ReadReqActor>(
@@ -320,7 +403,7 @@ mod tests {
         assert!(result.is_ok());
         rt.verify();
 
-        // Verify request no longer exists
+        // Verify request is now fulfilled
         rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr); // Reset caller
         rt.expect_validate_caller_any();
         let status_params = GetReadRequestStatusParams(request_id);
@@ -333,8 +416,124 @@ mod tests {
             .unwrap()
             .deserialize::<Option<ReadRequestStatus>>()
             .unwrap();
-        assert!(result.is_none());
+        assert!(matches!(result, Some(ReadRequestStatus::Fulfilled)));
+        rt.verify();
+
+        // Closing it again fails
+        rt.set_caller(*SYSTEM_ACTOR_CODE_ID, SYSTEM_ACTOR_ADDR);
+        rt.expect_validate_caller_addr(vec![SYSTEM_ACTOR_ADDR]);
+        let result = rt.call::<ReadReqActor>(
+            Method::CloseReadRequest as u64,
+            IpldBlock::serialize_cbor(&close_params).unwrap(),
+        );
+        assert!(result.is_err());
+        rt.verify();
+    }
+
+    #[test]
+    fn test_read_request_expiry() {
+        let rt = construct_and_verify();
+
+        let id_addr = Address::new_id(110);
+        let eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let f4_eth_addr = Address::new_delegated(10, &eth_addr.0).unwrap();
+        rt.set_delegated_address(id_addr.id().unwrap(), f4_eth_addr);
+
+        let get_status = |request_id: Hash| {
+            rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr);
+            rt.expect_validate_caller_any();
+            let result = rt
+                .call::<ReadReqActor>(
+                    Method::GetReadRequestStatus as u64,
+                    IpldBlock::serialize_cbor(&GetReadRequestStatusParams(request_id)).unwrap(),
+                )
+                .unwrap()
+                .unwrap()
+                .deserialize::<Option<ReadRequestStatus>>()
+                .unwrap();
+            rt.verify();
+            result
+        };
+        let expire = || {
+            rt.set_caller(*SYSTEM_ACTOR_CODE_ID, SYSTEM_ACTOR_ADDR);
+            rt.expect_validate_caller_addr(vec![SYSTEM_ACTOR_ADDR]);
+            let result = rt
+                .call::<ReadReqActor>(Method::ExpireReadRequests as u64, None)
+                .unwrap()
+                .unwrap()
+                .deserialize::<Vec<Hash>>()
+                .unwrap();
+            rt.verify();
+            result
+        };
+
+        // A ttl above the maximum is rejected
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr);
+        rt.expect_validate_caller_any();
+        let mut open_params = OpenReadRequestParams {
+            hash: new_hash(1024).0,
+            offset: 0,
+            len: 1024,
+            callback_addr: f4_eth_addr,
+            callback_method: 42,
+            ttl: Some(RecallConfig::default().read_request_max_ttl + 1),
+            fee: None,
+        };
+        expect_get_config(&rt);
+        let result = rt.call::<ReadReqActor>(
+            Method::OpenReadRequest as u64,
+            IpldBlock::serialize_cbor(&open_params).unwrap(),
+        );
+        assert!(result.is_err());
+        rt.verify();
+
+        // Open a request with a short ttl
+        rt.expect_validate_caller_any();
+        open_params.ttl = Some(10);
+        let expected_id = Hash::from(1);
+        expect_get_config(&rt);
+        expect_emitted_open_event(&rt, &open_params, &expected_id);
+        let request_id = rt
+            .call::<ReadReqActor>(
+                Method::OpenReadRequest as u64,
+                IpldBlock::serialize_cbor(&open_params).unwrap(),
+            )
+            .unwrap()
+            .unwrap()
+            .deserialize::<Hash>()
+            .unwrap();
+        rt.verify();
+
+        // Nothing expires before the ttl elapses
+        rt.set_epoch(9);
+        assert!(expire().is_empty());
+        assert_eq!(get_status(request_id), Some(ReadRequestStatus::Open));
+
+        // The request expires once the ttl elapses
+        rt.set_epoch(10);
+        assert_eq!(expire(), vec![request_id]);
+        assert_eq!(get_status(request_id), Some(ReadRequestStatus::Expired));
+
+        // Expired requests are no longer returned as open
+        rt.expect_validate_caller_any();
+        let result = rt
+            .call::<ReadReqActor>(
+                Method::GetOpenReadRequests as u64,
+                IpldBlock::serialize_cbor(&GetOpenReadRequestsParams(10)).unwrap(),
+            )
+            .unwrap()
+            .unwrap()
+            .deserialize::<Vec<OpenReadRequestTuple>>()
+            .unwrap();
+        assert!(result.is_empty());
         rt.verify();
+
+        // The expired request is removed after the retention period
+        rt.set_epoch(10 + CLOSED_READ_REQUEST_RETENTION);
+        assert!(expire().is_empty());
+        assert_eq!(get_status(request_id), None);
     }
 
     #[test]
@@ -372,4 +571,113 @@ mod tests {
         assert!(result.is_err());
         rt.verify();
     }
+
+    fn expect_fee_transfer(rt: &MockRuntime, from: Address, to: Address, fee: Credit, memo: &str) {
+        rt.expect_send_simple(
+            BLOBS_ACTOR_ADDR,
+            BlobMethod::TransferCredit as MethodNum,
+            IpldBlock::serialize_cbor(&TransferCreditParams {
+                from,
+                to,
+                amount: Some(fee),
+                memo: Some(memo.into()),
+            })
+            .unwrap(),
+            TokenAmount::zero(),
+            None,
+            ExitCode::OK,
+        );
+    }
+
+    #[test]
+    fn test_read_request_fee() {
+        let rt = construct_and_verify();
+
+        let id_addr = Address::new_id(110);
+        let eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let f4_eth_addr = Address::new_delegated(10, &eth_addr.0).unwrap();
+        rt.set_delegated_address(id_addr.id().unwrap(), f4_eth_addr);
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr);
+        rt.set_origin(id_addr);
+
+        let open = |fee: Option<Credit>, expected_id: Hash| {
+            rt.expect_validate_caller_any();
+            let open_params = OpenReadRequestParams {
+                hash: new_hash(1024).0,
+                offset: 0,
+                len: 1024,
+                callback_addr: f4_eth_addr,
+                callback_method: 42,
+                ttl: Some(10),
+                fee: fee.clone(),
+            };
+            if let Some(fee) = fee {
+                expect_fee_transfer(
+                    &rt,
+                    id_addr,
+                    BLOB_READER_ACTOR_ADDR,
+                    fee,
+                    "read request fee",
+                );
+            }
+            expect_get_config(&rt);
+            expect_emitted_open_event(&rt, &open_params, &expected_id);
+            let id = rt
+                .call::<ReadReqActor>(
+                    Method::OpenReadRequest as u64,
+                    IpldBlock::serialize_cbor(&open_params).unwrap(),
+                )
+                .unwrap()
+                .unwrap()
+                .deserialize::<Hash>()
+                .unwrap();
+            rt.verify();
+            id
+        };
+
+        // Open a request without a fee, then one with a fee
+        let free_id = open(None, Hash::from(1));
+        let fee = Credit::from_whole(5);
+        let paid_id = open(Some(fee.clone()), Hash::from(2));
+
+        // The paid request is served first
+        rt.expect_validate_caller_any();
+        let result = rt
+            .call::<ReadReqActor>(
+                Method::GetOpenReadRequests as u64,
+                IpldBlock::serialize_cbor(&GetOpenReadRequestsParams(2)).unwrap(),
+            )
+            .unwrap()
+            .unwrap()
+            .deserialize::<Vec<OpenReadRequestTuple>>()
+            .unwrap();
+        let ids: Vec<Hash> = result.iter().map(|r| r.0).collect();
+        assert_eq!(ids, vec![paid_id, free_id]);
+        rt.verify();
+
+        // The fee is returned to the requester when the request expires
+        rt.set_epoch(10);
+        rt.set_caller(*SYSTEM_ACTOR_CODE_ID, SYSTEM_ACTOR_ADDR);
+        rt.expect_validate_caller_addr(vec![SYSTEM_ACTOR_ADDR]);
+        expect_fee_transfer(
+            &rt,
+            BLOB_READER_ACTOR_ADDR,
+            id_addr,
+            fee,
+            "read request fee refund",
+        );
+        let mut expired = rt
+            .call::<ReadReqActor>(Method::ExpireReadRequests as u64, None)
+            .unwrap()
+            .unwrap()
+            .deserialize::<Vec<Hash>>()
+            .unwrap();
+        expired.sort_by_key(|id| id.0);
+        let mut expected = vec![free_id, paid_id];
+        expected.sort_by_key(|id| id.0);
+        assert_eq!(expired, expected);
+        rt.verify();
+    }
 }
@@ -4,9 +4,9 @@
 
 use std::fmt;
 
-use fendermint_actor_blobs_shared::state::Hash;
+use fendermint_actor_blobs_shared::state::{Credit, Hash};
 use fvm_ipld_encoding::tuple::*;
-use fvm_shared::{address::Address, ActorID, MethodNum, METHOD_CONSTRUCTOR};
+use fvm_shared::{address::Address, clock::ChainEpoch, ActorID, MethodNum, METHOD_CONSTRUCTOR};
 use num_derive::FromPrimitive;
 use serde::{Deserialize, Serialize};
 
@@ -24,6 +24,17 @@ pub enum ReadRequestStatus {
     Open,
     /// Read request is being processed
     Pending,
+    /// Read request was fulfilled and its callback delivered
+    Fulfilled,
+    /// Read request was not fulfilled before it expired
+    Expired,
+}
+
+impl ReadRequestStatus {
+    /// Returns whether the read request can still be fulfilled.
+    pub fn is_active(&self) -> bool {
+        matches!(self, ReadRequestStatus::Open | ReadRequestStatus::Pending)
+    }
 }
 
 impl fmt::Display for ReadRequestStatus {
@@ -31,6 +42,8 @@ impl fmt::Display for ReadRequestStatus {
         match self {
             ReadRequestStatus::Open => write!(f, "open"),
             ReadRequestStatus::Pending => write!(f, "pending"),
+            ReadRequestStatus::Fulfilled => write!(f, "fulfilled"),
+            ReadRequestStatus::Expired => write!(f, "expired"),
         }
     }
 }
@@ -50,6 +63,13 @@ pub struct ReadRequest {
     pub callback_method: MethodNum,
     /// Status of the read request
     pub status: ReadRequestStatus,
+    /// Epoch at which an active request expires, or at which a closed request is removed.
+    pub expiry: ChainEpoch,
+    /// The address that opened the read request.
+    pub requester: Address,
+    /// Credit paid by the requester to prioritize the request.
+    /// The fee is returned to the requester if the request expires unfulfilled.
+    pub fee: Credit,
 }
 
 #[derive(FromPrimitive)]
@@ -61,6 +81,7 @@ pub enum Method {
     GetOpenReadRequests = frc42_dispatch::method_hash!("GetOpenReadRequests"),
     OpenReadRequest = frc42_dispatch::method_hash!("OpenReadRequest"),
     SetReadRequestPending = frc42_dispatch::method_hash!("SetReadRequestPending"),
+    ExpireReadRequests = frc42_dispatch::method_hash!("ExpireReadRequests"),
 }
 
 /// Params for adding a read request.
@@ -76,6 +97,12 @@ pub struct OpenReadRequestParams {
     pub callback_addr: Address,
     /// The method to call back when the read is complete.
     pub callback_method: MethodNum,
+    /// Read request time-to-live epochs.
+    /// If not specified, the default TTL is used.
+    pub ttl: Option<ChainEpoch>,
+    /// Optional credit fee paid to prioritize the request.
+    /// Open requests are served in order of highest fee first.
+    pub fee: Option<Credit>,
 }
 
 /// Params for closing a read request. The ID of the read request.
@@ -2,11 +2,12 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use fendermint_actor_blobs_shared::state::Hash;
+use fendermint_actor_blobs_shared::state::{Credit, Hash};
 use fil_actors_runtime::ActorError;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
 use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
 use log::info;
 
 use crate::shared::{OpenReadRequestTuple, ReadRequest, ReadRequestStatus};
@@ -14,6 +15,9 @@ use recall_ipld::hamt;
 use recall_ipld::hamt::map::TrackedFlushResult;
 
 const MAX_READ_REQUEST_LEN: u32 = 1024 * 1024; // 1MB
+const DEFAULT_READ_REQUEST_TTL: ChainEpoch = 60 * 60; // ~1 hour
+/// How long a fulfilled or expired request is kept around so its status can be queried.
+pub(crate) const CLOSED_READ_REQUEST_RETENTION: ChainEpoch = 60 * 60; // ~1 hour
 
 /// The state represents all read requests.
 #[derive(Debug, Serialize_tuple, Deserialize_tuple)]
@@ -33,14 +37,20 @@ impl State {
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn open_read_request<BS: Blockstore>(
         &mut self,
         store: &BS,
+        current_epoch: ChainEpoch,
         blob_hash: Hash,
         offset: u32,
         len: u32,
         callback_addr: Address,
         callback_method: u64,
+        ttl: Option<ChainEpoch>,
+        max_ttl: ChainEpoch,
+        requester: Address,
+        fee: Credit,
     ) -> Result<Hash, ActorError> {
         // Validate length is not greater than the maximum allowed
         if len > MAX_READ_REQUEST_LEN {
@@ -49,6 +59,13 @@ impl State {
                 len, MAX_READ_REQUEST_LEN
             )));
         }
+        let ttl = ttl.unwrap_or(DEFAULT_READ_REQUEST_TTL.min(max_ttl));
+        if ttl <= 0 || ttl > max_ttl {
+            return Err(ActorError::illegal_argument(format!(
+                "read request ttl must be between 1 and {} epochs (got {})",
+                max_ttl, ttl
+            )));
+        }
 
         let request_id = self.next_request_id();
         let read_request = ReadRequest {
@@ -58,6 +75,9 @@ impl State {
             callback_addr,
             callback_method,
             status: ReadRequestStatus::Open,
+            expiry: current_epoch + ttl,
+            requester,
+            fee,
         };
         info!("opening a read request onchain: {:?}", request_id);
         // will create a new request even if the request parameters are the same
@@ -67,24 +87,75 @@ impl State {
         Ok(request_id)
     }
 
+    /// Marks a read request as fulfilled.
+    /// The request is kept for a retention period so callers can query its status.
     pub fn close_read_request<BS: Blockstore>(
         &mut self,
         store: &BS,
+        current_epoch: ChainEpoch,
         request_id: Hash,
     ) -> Result<(), ActorError> {
-        if self.get_read_request_status(store, request_id)?.is_none() {
-            return Err(ActorError::not_found(
-                "cannot close read request, it does not exist".to_string(),
-            ));
+        let mut read_requests = self.read_requests.hamt(store)?;
+        let mut request = read_requests.get(&request_id)?.ok_or_else(|| {
+            ActorError::not_found("cannot close read request, it does not exist".to_string())
+        })?;
+        if !request.status.is_active() {
+            return Err(ActorError::illegal_state(format!(
+                "cannot close read request {}, it is already {}",
+                request_id, request.status
+            )));
         }
 
-        // remove the closed request
-        let mut read_requests = self.read_requests.hamt(store)?;
+        request.status = ReadRequestStatus::Fulfilled;
+        request.expiry = current_epoch + CLOSED_READ_REQUEST_RETENTION;
         self.read_requests
-            .save_tracked(read_requests.delete_and_flush_tracked(&request_id)?.0);
+            .save_tracked(read_requests.set_and_flush_tracked(&request_id, request)?);
         Ok(())
     }
 
+    /// Expires active read requests that have reached their expiry and removes closed requests
+    /// whose retention period has elapsed.
+    /// Returns the requests that were expired.
+    pub fn expire_read_requests<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        current_epoch: ChainEpoch,
+    ) -> Result<Vec<(Hash, ReadRequest)>, ActorError> {
+        let mut read_requests = self.read_requests.hamt(store)?;
+
+        let mut to_expire = Vec::new();
+        let mut to_remove = Vec::new();
+        read_requests.for_each(|id, request| {
+            if request.expiry <= current_epoch {
+                if request.status.is_active() {
+                    to_expire.push((id, request.clone()));
+                } else {
+                    to_remove.push(id);
+                }
+            }
+            Ok(())
+        })?;
+        if to_expire.is_empty() && to_remove.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut expired = Vec::with_capacity(to_expire.len());
+        for (id, mut request) in to_expire {
+            info!("expiring read request {} at epoch {}", id, current_epoch);
+            request.status = ReadRequestStatus::Expired;
+            request.expiry = current_epoch + CLOSED_READ_REQUEST_RETENTION;
+            read_requests.set(&id, request.clone())?;
+            expired.push((id, request));
+        }
+        for id in to_remove {
+            read_requests.delete(&id)?;
+        }
+        self.read_requests
+            .save_tracked(read_requests.flush_tracked()?);
+        Ok(expired)
+    }
+
+    /// Returns up to `size` open read requests, highest fee first.
     pub fn get_open_read_requests<BS: Blockstore>(
         &self,
         store: BS,
@@ -92,21 +163,30 @@ impl State {
     ) -> Result<Vec<OpenReadRequestTuple>, ActorError> {
         let read_requests = self.read_requests.hamt(store)?;
 
-        let mut requests = Vec::new();
+        let mut open = Vec::new();
         read_requests.for_each(|id, request| {
-            if matches!(request.status, ReadRequestStatus::Open) && (requests.len() as u32) < size {
-                requests.push((
+            if matches!(request.status, ReadRequestStatus::Open) {
+                open.push((id, request.clone()));
+            }
+            Ok(())
+        })?;
+        // Stable sort keeps the map order for requests with equal fees
+        open.sort_by(|(_, a), (_, b)| b.fee.cmp(&a.fee));
+
+        let requests = open
+            .into_iter()
+            .take(size as usize)
+            .map(|(id, request)| {
+                (
                     id,
                     request.blob_hash,
                     request.offset,
                     request.len,
                     request.callback_addr,
                     request.callback_method,
-                ))
-            }
-
-            Ok(())
-        })?;
+                )
+            })
+            .collect();
         Ok(requests)
     }
 
@@ -13,6 +13,7 @@ use fvm_shared::econ::TokenAmount;
 use fvm_shared::sys::SendFlags;
 use fvm_shared::{ActorID, MethodNum, METHOD_CONSTRUCTOR};
 use num_derive::FromPrimitive;
+use num_traits::Zero;
 
 use crate::state::{Account, Credit, CreditApproval, Subscription};
 
@@ -21,6 +22,12 @@ pub mod state;
 
 pub const BLOBS_ACTOR_ID: ActorID = 66;
 pub const BLOBS_ACTOR_ADDR: Address = Address::new_id(BLOBS_ACTOR_ID);
+pub const MAX_BLOB_METADATA_ENTRIES: u32 = 20;
+pub const MAX_BLOB_METADATA_KEY_SIZE: u32 = 32;
+pub const MAX_BLOB_METADATA_VALUE_SIZE: u32 = 128;
+pub const MAX_BLOB_REPLICATION: u32 = 10;
+pub const MAX_SPONSOR_GROUP_NAME_SIZE: u32 = 64;
+pub const MAX_SPONSOR_GROUP_MEMBERS: u32 = 1000;
 
 #[derive(FromPrimitive)]
 #[repr(u64)]
@@ -29,15 +36,35 @@ pub enum Method {
 
     // User methods
     BuyCredit = frc42_dispatch::method_hash!("BuyCredit"),
+    WithdrawCredit = frc42_dispatch::method_hash!("WithdrawCredit"),
+    CloseAccount = frc42_dispatch::method_hash!("CloseAccount"),
     ApproveCredit = frc42_dispatch::method_hash!("ApproveCredit"),
     RevokeCredit = frc42_dispatch::method_hash!("RevokeCredit"),
+    TransferCredit = frc42_dispatch::method_hash!("TransferCredit"),
     SetAccountSponsor = frc42_dispatch::method_hash!("SetAccountSponsor"),
+    CreateSponsorGroup = frc42_dispatch::method_hash!("CreateSponsorGroup"),
+    DeleteSponsorGroup = frc42_dispatch::method_hash!("DeleteSponsorGroup"),
+    AddSponsorGroupMember = frc42_dispatch::method_hash!("AddSponsorGroupMember"),
+    RemoveSponsorGroupMember = frc42_dispatch::method_hash!("RemoveSponsorGroupMember"),
+    GetSponsorGroup = frc42_dispatch::method_hash!("GetSponsorGroup"),
     GetAccount = frc42_dispatch::method_hash!("GetAccount"),
+    GetAccountStatement = frc42_dispatch::method_hash!("GetAccountStatement"),
     GetCreditApproval = frc42_dispatch::method_hash!("GetCreditApproval"),
+    GetCreditApprovals = frc42_dispatch::method_hash!("GetCreditApprovals"),
+    GetOrphanedSubscriptions = frc42_dispatch::method_hash!("GetOrphanedSubscriptions"),
     AddBlob = frc42_dispatch::method_hash!("AddBlob"),
     GetBlob = frc42_dispatch::method_hash!("GetBlob"),
+    GetBlobProof = frc42_dispatch::method_hash!("GetBlobProof"),
+    ListBlobs = frc42_dispatch::method_hash!("ListBlobs"),
+    GetBlobSubscribers = frc42_dispatch::method_hash!("GetBlobSubscribers"),
     DeleteBlob = frc42_dispatch::method_hash!("DeleteBlob"),
+    DeleteBlobs = frc42_dispatch::method_hash!("DeleteBlobs"),
+    UndeleteBlob = frc42_dispatch::method_hash!("UndeleteBlob"),
     OverwriteBlob = frc42_dispatch::method_hash!("OverwriteBlob"),
+    ExtendSubscription = frc42_dispatch::method_hash!("ExtendSubscription"),
+    TransferSubscription = frc42_dispatch::method_hash!("TransferSubscription"),
+    SetBlobMetadata = frc42_dispatch::method_hash!("SetBlobMetadata"),
+    GetBlobMetadata = frc42_dispatch::method_hash!("GetBlobMetadata"),
 
     // System methods
     GetGasAllowance = frc42_dispatch::method_hash!("GetGasAllowance"),
@@ -51,10 +78,15 @@ pub enum Method {
 
     // Admin methods
     SetAccountStatus = frc42_dispatch::method_hash!("SetAccountStatus"),
+    SetAccountQuota = frc42_dispatch::method_hash!("SetAccountQuota"),
+    Pause = frc42_dispatch::method_hash!("Pause"),
+    Resume = frc42_dispatch::method_hash!("Resume"),
     TrimBlobExpiries = frc42_dispatch::method_hash!("TrimBlobExpiries"),
 
     // Metrics methods
     GetStats = frc42_dispatch::method_hash!("GetStats"),
+    GetUsageReport = frc42_dispatch::method_hash!("GetUsageReport"),
+    VerifyInvariants = frc42_dispatch::method_hash!("VerifyInvariants"),
     // EVM Interop
     InvokeContract = frc42_dispatch::method_hash!("InvokeEVM"),
 }
@@ -138,12 +170,35 @@ pub fn revoke_credit(
             from,
             to,
             for_caller,
+            terminate_subscriptions: false,
         })?,
         rt.message().value_received(),
     ))?;
     Ok(())
 }
 
+pub fn transfer_credit(
+    rt: &impl Runtime,
+    from: Address,
+    to: Address,
+    amount: Option<Credit>,
+    memo: Option<String>,
+) -> Result<(), ActorError> {
+    extract_send_result(rt.send_simple(
+        &BLOBS_ACTOR_ADDR,
+        Method::TransferCredit as MethodNum,
+        IpldBlock::serialize_cbor(&params::TransferCreditParams {
+            from,
+            to,
+            amount,
+            memo,
+        })?,
+        // Transfers move credit, not tokens, so nothing is sent along
+        TokenAmount::zero(),
+    ))?;
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn add_blob(
     rt: &impl Runtime,
@@ -165,6 +220,8 @@ pub fn add_blob(
         size,
         ttl,
         from,
+        auto_renew: false,
+        replication: None,
     })?;
     deserialize_block(extract_send_result(rt.send_simple(
         &BLOBS_ACTOR_ADDR,
@@ -203,6 +260,7 @@ pub fn delete_blob(
             hash,
             id: sub_id,
             from,
+            trash: false,
         })?,
         rt.message().value_received(),
     ))?;
@@ -237,6 +295,8 @@ pub fn overwrite_blob(
                 size,
                 ttl,
                 from,
+                auto_renew: false,
+                replication: None,
             },
         })?,
         rt.message().value_received(),
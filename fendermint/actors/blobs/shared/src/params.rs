@@ -2,15 +2,17 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use fvm_ipld_encoding::tuple::*;
+use cid::Cid;
+use fvm_ipld_encoding::{strict_bytes, tuple::*};
 use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::state::{
-    BlobStatus, Credit, Hash, PublicKey, SubscriptionId, TokenCreditRate, TtlStatus,
+    BlobRequest, BlobStatus, Credit, CreditApproval, Hash, PendingBlobRequest, PublicKey,
+    StatementEntry, SubscriptionId, TokenCreditRate, TtlStatus,
 };
 
 /// Params for buying credits.
@@ -18,6 +20,17 @@ use crate::state::{
 #[serde(transparent)]
 pub struct BuyCreditParams(pub Address);
 
+/// Params for withdrawing credit.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct WithdrawCreditParams {
+    /// Account address that is withdrawing credit.
+    /// The corresponding tokens are sent to this address.
+    pub from: Address,
+    /// Optional amount of free credit to withdraw.
+    /// If not present, all of the account's free credit is withdrawn.
+    pub amount: Option<Credit>,
+}
+
 /// Params for updating credit.
 #[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
 pub struct UpdateGasAllowanceParams {
@@ -29,6 +42,12 @@ pub struct UpdateGasAllowanceParams {
     pub add_amount: TokenAmount,
 }
 
+/// Params for closing an account.
+/// The address of the account to close. Refunded tokens are sent to this address.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CloseAccountParams(pub Address);
+
 /// Params for approving credit.
 #[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
 pub struct ApproveCreditParams {
@@ -63,6 +82,30 @@ pub struct RevokeCreditParams {
     /// Optional caller address to remove from the caller allowlist.
     /// If not present, the entire approval is revoked.
     pub for_caller: Option<Address>,
+    /// Whether to delete the subscriptions that `to` created under the approval.
+    /// Only allowed when the entire approval is revoked.
+    /// If false, the subscriptions keep running and can be found with
+    /// `GetOrphanedSubscriptions`.
+    pub terminate_subscriptions: bool,
+}
+
+/// Params for transferring credit.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct TransferCreditParams {
+    /// Account address that is sending credit.
+    pub from: Address,
+    /// Account address that is receiving credit.
+    pub to: Address,
+    /// Optional amount of free credit to transfer.
+    /// If not present, all of the sender's free credit is transferred.
+    pub amount: Option<Credit>,
+    /// Optional memo used for off-chain record keeping.
+    pub memo: Option<String>,
+}
+
+impl TransferCreditParams {
+    /// Maximum allowed memo length in bytes.
+    pub const MAX_MEMO_LEN: usize = 256;
 }
 
 /// Params for setting sponsor.
@@ -84,6 +127,71 @@ pub struct SetAccountStatusParams {
     pub status: TtlStatus,
 }
 
+/// Params for setting an account storage quota.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct SetAccountQuotaParams {
+    /// Address to set the quota for.
+    pub subscriber: Address,
+    /// Maximum storage capacity in bytes.
+    /// If not present, the quota is removed.
+    pub max_capacity: Option<u64>,
+}
+
+/// Params for creating a sponsor group.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct CreateSponsorGroupParams {
+    /// Account address that is sponsoring the group.
+    pub from: Address,
+    /// Group name, unique per sponsor.
+    pub name: String,
+    /// Optional limit on the total credit used by all members.
+    pub budget: Option<Credit>,
+    /// Optional limit on the credit used by each member.
+    pub member_credit_limit: Option<Credit>,
+}
+
+/// Params for deleting a sponsor group.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct DeleteSponsorGroupParams {
+    /// Account address that is sponsoring the group.
+    pub from: Address,
+    /// Group name.
+    pub name: String,
+}
+
+/// Params for adding a member to, or removing a member from, a sponsor group.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct SponsorGroupMemberParams {
+    /// Account address that is sponsoring the group.
+    pub from: Address,
+    /// Group name.
+    pub name: String,
+    /// Member account address.
+    pub member: Address,
+}
+
+/// Params for getting a sponsor group.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetSponsorGroupParams {
+    /// Account address that is sponsoring the group.
+    pub sponsor: Address,
+    /// Group name.
+    pub name: String,
+}
+
+/// The return type used for GetSponsorGroup.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct SponsorGroupInfo {
+    /// Optional limit on the total credit used by all members.
+    pub budget: Option<Credit>,
+    /// Optional limit on the credit used by each member.
+    pub member_credit_limit: Option<Credit>,
+    /// Total credit used by all members.
+    pub credit_used: Credit,
+    /// Group members and the credit each has used.
+    pub members: Vec<(Address, Credit)>,
+}
+
 /// Params for getting an account.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -98,6 +206,53 @@ pub struct GetCreditApprovalParams {
     pub to: Address,
 }
 
+/// Params for listing the credit approvals of an account.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetCreditApprovalsParams {
+    /// Account address to list approvals for.
+    pub address: Address,
+    /// Receiver address to start listing granted approvals from (inclusive).
+    pub granted_start_key: Option<Address>,
+    /// Sender address to start listing received approvals from (inclusive).
+    pub received_start_key: Option<Address>,
+    /// Maximum number of approvals to return in each direction.
+    pub limit: Option<u32>,
+}
+
+/// The return type used when listing the credit approvals of an account.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetCreditApprovalsReturn {
+    /// Credit approvals granted by the account, keyed by receiver.
+    pub granted: Vec<(Address, CreditApproval)>,
+    /// The key to pass as `granted_start_key` to continue listing granted approvals.
+    pub granted_next_key: Option<Address>,
+    /// Credit approvals received by the account, keyed by sender.
+    pub received: Vec<(Address, CreditApproval)>,
+    /// The key to pass as `received_start_key` to continue listing received approvals.
+    pub received_next_key: Option<Address>,
+}
+
+/// Params for listing the credit statement of an account.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetAccountStatementParams {
+    /// Account address to list the statement for.
+    pub address: Address,
+    /// Sequence number to start listing entries from (inclusive).
+    /// Defaults to the oldest retained entry.
+    pub start_seq: Option<u64>,
+    /// Maximum number of entries to return.
+    pub limit: Option<u32>,
+}
+
+/// The return type used when listing the credit statement of an account.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetAccountStatementReturn {
+    /// Statement entries, keyed by sequence number, from oldest to newest.
+    pub entries: Vec<(u64, StatementEntry)>,
+    /// The sequence number to pass as `start_seq` to continue listing.
+    pub next_seq: Option<u64>,
+}
+
 /// Params for looking up credit allowance.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -124,6 +279,12 @@ pub struct AddBlobParams {
     pub ttl: Option<ChainEpoch>,
     /// Address of the entity adding the blob.
     pub from: Address,
+    /// Whether the subscription should be automatically renewed by its TTL on expiry.
+    pub auto_renew: bool,
+    /// Number of independent Iroh nodes that should hold the blob.
+    /// If not specified, a single replica is targeted.
+    /// Each additional replica is charged at the base credit rate.
+    pub replication: Option<u32>,
 }
 
 /// Params for getting a blob.
@@ -131,6 +292,153 @@ pub struct AddBlobParams {
 #[serde(transparent)]
 pub struct GetBlobParams(pub Hash);
 
+/// Params for getting an inclusion proof for a blob.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GetBlobProofParams(pub Hash);
+
+/// An IPLD block in a blob inclusion proof.
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct ProofBlock {
+    /// Block CID.
+    pub cid: Cid,
+    /// Raw block data.
+    #[serde(with = "strict_bytes")]
+    pub data: Vec<u8>,
+}
+
+/// The return type used for GetBlobProof.
+///
+/// The blocks form the IPLD path from the actor state root to the HAMT node holding the blob.
+/// A client that knows the actor state root at a given height can check each block against
+/// its CID, follow the links, and decode the blob from the last block.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetBlobProofReturn {
+    /// The blobs actor state root the proof starts from.
+    pub state_root: Cid,
+    /// The epoch at which the proof was created.
+    pub epoch: ChainEpoch,
+    /// Blocks along the path from the state root to the blob, in order.
+    pub blocks: Vec<ProofBlock>,
+}
+
+/// Params for listing blobs.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct ListBlobsParams {
+    /// Hash to start listing from (inclusive).
+    /// If not present, listing starts from the first blob.
+    pub start_key: Option<Hash>,
+    /// Maximum number of blobs to return.
+    /// Defaults to [`Self::DEFAULT_LIMIT`] and is capped at [`Self::MAX_LIMIT`].
+    pub limit: Option<u32>,
+}
+
+impl ListBlobsParams {
+    /// Number of blobs returned when no limit is given.
+    pub const DEFAULT_LIMIT: u32 = 100;
+    /// Maximum number of blobs returned in a single page.
+    pub const MAX_LIMIT: u32 = 1000;
+}
+
+/// Params for getting subscriptions that a delegate created under a since revoked approval.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetOrphanedSubscriptionsParams {
+    /// The delegate whose approval was revoked.
+    pub delegate: Address,
+    /// Hash to start scanning from (inclusive).
+    /// If not present, scanning starts from the first blob.
+    pub start_key: Option<Hash>,
+    /// Maximum number of blobs to scan.
+    pub limit: Option<u32>,
+}
+
+/// A subscription created by a delegate whose credit approval has been revoked.
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct OrphanedSubscription {
+    /// The subscriber that revoked the approval.
+    pub subscriber: Address,
+    /// Blob hash.
+    pub hash: Hash,
+    /// Subscription ID.
+    pub id: SubscriptionId,
+}
+
+/// The return type used when getting orphaned subscriptions.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetOrphanedSubscriptionsReturn {
+    /// The orphaned subscriptions found in the scanned blobs.
+    pub subscriptions: Vec<OrphanedSubscription>,
+    /// The hash to continue scanning from, if there are more blobs.
+    pub next_key: Option<Hash>,
+}
+
+/// A summary of a blob returned when listing blobs.
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct BlobSummary {
+    /// Blob hash.
+    pub hash: Hash,
+    /// Blob size.
+    pub size: u64,
+    /// Blob status.
+    pub status: BlobStatus,
+    /// Number of accounts subscribed to the blob.
+    pub num_subscribers: u64,
+}
+
+/// The return type used when listing blobs.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct ListBlobsReturn {
+    /// The listed blobs, in key order.
+    pub blobs: Vec<BlobSummary>,
+    /// The key to pass as `start_key` to continue listing.
+    /// If not present, there are no more blobs.
+    pub next_key: Option<Hash>,
+}
+
+/// Params for listing the subscribers of a blob.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetBlobSubscribersParams {
+    /// Blob blake3 hash.
+    pub hash: Hash,
+    /// Subscriber address to start listing from (inclusive).
+    /// If not present, listing starts from the first subscriber.
+    pub start_key: Option<Address>,
+    /// Maximum number of subscribers to return.
+    pub limit: Option<u32>,
+}
+
+/// A summary of a blob subscription returned when listing blob subscribers.
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct SubscriptionSummary {
+    /// Subscription ID.
+    pub id: SubscriptionId,
+    /// Expiry block.
+    pub expiry: ChainEpoch,
+    /// Source Iroh node ID used for ingestion.
+    pub source: PublicKey,
+    /// The delegate origin that may have created the subscription via a credit approval.
+    pub delegate: Option<Address>,
+}
+
+/// The subscription group of a single blob subscriber.
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct BlobSubscriberGroup {
+    /// Subscriber address.
+    pub subscriber: Address,
+    /// The subscriber's subscriptions to the blob.
+    pub subscriptions: Vec<SubscriptionSummary>,
+}
+
+/// The return type used when listing blob subscribers.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetBlobSubscribersReturn {
+    /// The listed subscriber groups, in key order.
+    pub subscribers: Vec<BlobSubscriberGroup>,
+    /// The key to pass as `start_key` to continue listing.
+    /// If not present, there are no more subscribers.
+    pub next_key: Option<Address>,
+}
+
 /// Params for getting blob status.
 #[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
 pub struct GetBlobStatusParams {
@@ -144,14 +452,44 @@ pub struct GetBlobStatusParams {
 }
 
 /// Params for getting added blobs.
-#[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(transparent)]
-pub struct GetAddedBlobsParams(pub u32);
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetAddedBlobsParams {
+    /// Maximum number of blobs to return.
+    pub size: u32,
+    /// Blob hash to start from (inclusive), as returned in `next_key` by a previous call.
+    /// If not present, the page starts from the first blob in the queue.
+    pub start_key: Option<Hash>,
+}
+
+/// The return type used when getting added blobs.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetAddedBlobsReturn {
+    /// Added blobs that have not yet been picked up by validators.
+    pub blobs: Vec<BlobRequest>,
+    /// The key to pass as `start_key` to get the next page.
+    /// If not present, there are no more blobs in the queue.
+    pub next_key: Option<Hash>,
+}
 
 /// Params for getting pending blobs.
-#[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(transparent)]
-pub struct GetPendingBlobsParams(pub u32);
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetPendingBlobsParams {
+    /// Maximum number of blobs to return.
+    pub size: u32,
+    /// Blob hash to start from (inclusive), as returned in `next_key` by a previous call.
+    /// If not present, the page starts from the first blob in the queue.
+    pub start_key: Option<Hash>,
+}
+
+/// The return type used when getting pending blobs.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetPendingBlobsReturn {
+    /// Blobs that validators are actively resolving.
+    pub blobs: Vec<PendingBlobRequest>,
+    /// The key to pass as `start_key` to get the next page.
+    /// If not present, there are no more blobs in the queue.
+    pub next_key: Option<Hash>,
+}
 
 /// Params for setting a blob to pending.
 #[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
@@ -180,6 +518,9 @@ pub struct FinalizeBlobParams {
     pub id: SubscriptionId,
     /// The status to set as final.
     pub status: BlobStatus,
+    /// The blob size verified by the validator resolver, if known.
+    /// A resolved blob whose size differs from the declared size is failed.
+    pub size: Option<u64>,
 }
 
 /// Params for deleting a blob.
@@ -195,6 +536,117 @@ pub struct DeleteBlobParams {
     pub id: SubscriptionId,
     /// Account address that initiated the deletion.
     pub from: Address,
+    /// Whether to move the subscription to the trash instead of deleting it outright.
+    /// A trashed subscription stops accruing charges and can be restored with `UndeleteBlob`
+    /// until the configured trash period has passed, after which its data is removed.
+    pub trash: bool,
+}
+
+/// Params for restoring a trashed blob subscription.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct UndeleteBlobParams {
+    /// Optional sponsor address.
+    /// Origin or caller must still have a delegation from sponsor.
+    pub sponsor: Option<Address>,
+    /// Blob blake3 hash.
+    pub hash: Hash,
+    /// Identifier used to differentiate blob additions for the same subscriber.
+    pub id: SubscriptionId,
+    /// Account address that initiated the restore.
+    pub from: Address,
+}
+
+/// Params for deleting all of a subscriber's blob subscriptions that match an ID prefix.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct DeleteBlobsParams {
+    /// Optional sponsor address.
+    /// Origin or caller must still have a delegation from sponsor.
+    pub sponsor: Option<Address>,
+    /// Subscription ID prefix to match.
+    /// An empty prefix matches all of the subscriber's subscriptions.
+    pub id_prefix: String,
+    /// Blob hash to resume from (inclusive), as returned in `next_key` by a previous call.
+    /// If not present, deletion starts from the first blob.
+    pub start_key: Option<Hash>,
+    /// Maximum number of blobs to scan.
+    /// Capped by the configured blob delete batch size.
+    pub limit: Option<u32>,
+    /// Account address that initiated the deletion.
+    pub from: Address,
+}
+
+/// The return type used when deleting blob subscriptions by ID prefix.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct DeleteBlobsReturn {
+    /// Number of subscriptions deleted.
+    pub deleted: u64,
+    /// Committed credit returned to the subscriber's free credit.
+    pub credit_refunded: Credit,
+    /// The key to pass as `start_key` to continue deleting.
+    /// If not present, all blobs have been scanned.
+    pub next_key: Option<Hash>,
+}
+
+/// Params for extending the expiry of a blob subscription.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct ExtendSubscriptionParams {
+    /// Optional sponsor address.
+    /// Origin or caller must still have a delegation from sponsor.
+    pub sponsor: Option<Address>,
+    /// Blob blake3 hash.
+    pub hash: Hash,
+    /// Identifier used to differentiate blob additions for the same subscriber.
+    pub id: SubscriptionId,
+    /// New blob time-to-live epochs, counted from the current epoch.
+    /// If not specified, the current default TTL from the config actor is used.
+    pub ttl: Option<ChainEpoch>,
+    /// Account address that initiated the extension.
+    pub from: Address,
+}
+
+/// Params for transferring a blob subscription to another account.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct TransferSubscriptionParams {
+    /// Account address that currently owns the subscription.
+    pub from: Address,
+    /// Account address that will own the subscription.
+    /// Must have a credit approval for `from`.
+    pub to: Address,
+    /// Blob blake3 hash.
+    pub hash: Hash,
+    /// Identifier used to differentiate blob additions for the same subscriber.
+    pub id: SubscriptionId,
+}
+
+/// Params for updating the metadata of a blob subscription.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct SetBlobMetadataParams {
+    /// Optional sponsor address.
+    /// Origin or caller must still have a delegation from sponsor.
+    /// Must be used if the caller is the delegate who added the blob.
+    pub sponsor: Option<Address>,
+    /// Blob blake3 hash.
+    pub hash: Hash,
+    /// Identifier used to differentiate blob additions for the same subscriber.
+    pub id: SubscriptionId,
+    /// Metadata entries to be inserted/updated/deleted.
+    ///
+    /// If a key-value is present, the entry is updated (or inserted if it does not exist).
+    /// If only the key is present, the entry is deleted.
+    pub metadata: HashMap<String, Option<String>>,
+    /// Account address that initiated the update.
+    pub from: Address,
+}
+
+/// Params for getting the metadata of a blob subscription.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetBlobMetadataParams {
+    /// Subscriber address.
+    pub subscriber: Address,
+    /// Blob blake3 hash.
+    pub hash: Hash,
+    /// Identifier used to differentiate blob additions for the same subscriber.
+    pub id: SubscriptionId,
 }
 
 /// Params for overwriting a blob, i.e., deleting one and adding another.
@@ -248,3 +700,94 @@ pub struct GetStatsReturn {
     /// Total bytes of all currently resolving blobs.
     pub bytes_resolving: u64,
 }
+
+/// Params for getting a usage report.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetUsageReportParams {
+    /// Number of accounts with the most capacity used to return.
+    pub limit: u32,
+    /// Only blobs first added at or after this epoch are counted.
+    pub since: ChainEpoch,
+}
+
+impl GetUsageReportParams {
+    /// Maximum number of accounts returned in a usage report.
+    pub const MAX_LIMIT: u32 = 100;
+}
+
+/// Usage of a single account in a usage report.
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct AccountUsage {
+    /// The account address.
+    pub address: Address,
+    /// Total size of all blobs managed by the account.
+    pub capacity_used: u64,
+    /// Current committed credit of the account.
+    pub credit_committed: Credit,
+    /// Credit debited from the account over the last debit interval,
+    /// as recorded in the account statement.
+    pub credit_debited: Credit,
+}
+
+/// The return type used for GetUsageReport.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct GetUsageReportReturn {
+    /// Accounts with the most capacity used, in descending order.
+    pub top_accounts: Vec<AccountUsage>,
+    /// Number of blobs in the added status.
+    pub num_added: u64,
+    /// Number of blobs in the pending status.
+    pub num_pending: u64,
+    /// Number of blobs in the resolved status.
+    pub num_resolved: u64,
+    /// Number of blobs in the failed status.
+    pub num_failed: u64,
+}
+
+/// An account whose recorded usage differs from the usage recomputed from its subscriptions.
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct AccountDrift {
+    /// The account address.
+    pub address: Address,
+    /// The capacity used recorded on the account.
+    pub capacity_used: u64,
+    /// The capacity used recomputed from the account's subscriptions.
+    pub capacity_used_expected: u64,
+    /// The committed credit recorded on the account.
+    pub credit_committed: Credit,
+    /// The committed credit recomputed from the account's subscriptions.
+    pub credit_committed_expected: Credit,
+}
+
+/// The return type used for VerifyInvariants.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct VerifyInvariantsReturn {
+    /// The total used storage capacity recorded in state.
+    pub capacity_used: u64,
+    /// The total used storage capacity recomputed from the blobs table.
+    pub capacity_used_expected: u64,
+    /// The total committed credit recorded in state.
+    pub credit_committed: Credit,
+    /// The total committed credit recomputed from all subscriptions.
+    pub credit_committed_expected: Credit,
+    /// The total credit sold recorded in state.
+    pub credit_sold: Credit,
+    /// The total credit sold recomputed from account balances and debited credit.
+    pub credit_sold_expected: Credit,
+    /// Number of accounts audited.
+    pub num_accounts: u64,
+    /// Number of blobs audited.
+    pub num_blobs: u64,
+    /// Accounts whose recorded usage has drifted.
+    pub accounts_drifted: Vec<AccountDrift>,
+}
+
+impl VerifyInvariantsReturn {
+    /// Returns whether all recorded totals match the recomputed totals.
+    pub fn is_consistent(&self) -> bool {
+        self.capacity_used == self.capacity_used_expected
+            && self.credit_committed == self.credit_committed_expected
+            && self.credit_sold == self.credit_sold_expected
+            && self.accounts_drifted.is_empty()
+    }
+}
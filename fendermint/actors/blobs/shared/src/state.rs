@@ -11,7 +11,8 @@ use fvm_shared::address::Address;
 use fvm_shared::bigint::{BigInt, BigUint};
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
-use recall_ipld::{hamt, hamt::map::TrackedFlushResult, hamt::MapKey};
+use recall_actor_sdk::to_delegated_address;
+use recall_ipld::{amt, hamt, hamt::map::TrackedFlushResult, hamt::MapKey};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::fmt;
@@ -26,6 +27,16 @@ pub type Credit = TokenAmount;
 /// See `get_added_blobs` and `get_pending_blobs` for more information.
 pub type BlobRequest = (Hash, u64, HashSet<(Address, SubscriptionId, PublicKey)>);
 
+/// The return type used when fetching "pending" blobs.
+/// Includes the replication target, which is the highest of the blob's pending subscriptions.
+/// See `get_pending_blobs` for more information.
+pub type PendingBlobRequest = (
+    Hash,
+    u64,
+    u32,
+    HashSet<(Address, SubscriptionId, PublicKey)>,
+);
+
 /// TokenCreditRate determines how much atto credits can be bought by a certain amount of RECALL.
 #[derive(Clone, Default, Debug, serde::Serialize, serde::Deserialize, Eq, PartialEq)]
 pub struct TokenCreditRate {
@@ -101,6 +112,18 @@ pub struct Account {
     pub max_ttl: ChainEpoch,
     /// The total token value an account has used to buy credits.
     pub gas_allowance: TokenAmount,
+    /// Optional maximum storage capacity the account may use, regardless of available credit.
+    pub max_capacity: Option<u64>,
+    /// Bounded log of recent credit events.
+    pub statement: AccountStatement,
+    /// Named groups of accounts sponsored by this account, keyed by group name.
+    pub sponsor_groups: HashMap<String, SponsorGroup>,
+    /// Start epoch of the current window used to rate limit new blob subscriptions.
+    pub add_window_start: ChainEpoch,
+    /// Number of new blob subscriptions added in the current window.
+    pub add_window_count: u64,
+    /// Number of blob bytes added in new subscriptions in the current window.
+    pub add_window_bytes: u64,
 }
 
 impl Account {
@@ -119,10 +142,93 @@ impl Account {
             approvals_to: CreditApprovals::new(store)?,
             approvals_from: CreditApprovals::new(store)?,
             gas_allowance: TokenAmount::default(),
+            max_capacity: None,
+            statement: AccountStatement::new(store)?,
+            sponsor_groups: HashMap::new(),
+            add_window_start: current_epoch,
+            add_window_count: 0,
+            add_window_bytes: 0,
         })
     }
 }
 
+/// The return type used for Account.
+#[derive(Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct AccountInfo {
+    /// Total size of all blobs managed by the account.
+    pub capacity_used: u64,
+    /// Current free credit in byte-blocks that can be used for new commitments.
+    pub credit_free: Credit,
+    /// Current committed credit in byte-blocks that will be used for debits.
+    pub credit_committed: Credit,
+    /// Optional default sponsor account address.
+    pub credit_sponsor: Option<Address>,
+    /// The chain epoch of the last debit.
+    pub last_debit_epoch: ChainEpoch,
+    /// Credit approvals to other accounts from this account, keyed by receiver.
+    pub approvals_to: HashMap<Address, CreditApproval>,
+    /// Credit approvals to this account from other accounts, keyed by sender.
+    pub approvals_from: HashMap<Address, CreditApproval>,
+    /// The maximum allowed TTL for actor's blobs.
+    pub max_ttl: ChainEpoch,
+    /// The total token value an account has used to buy credits.
+    pub gas_allowance: TokenAmount,
+    /// Optional maximum storage capacity the account may use, regardless of available credit.
+    pub max_capacity: Option<u64>,
+}
+
+impl AccountInfo {
+    pub fn from(rt: &impl Runtime, account: Account) -> Result<Self, ActorError> {
+        let store = rt.store();
+        let mut approvals_to = HashMap::new();
+        account
+            .approvals_to
+            .hamt(store)?
+            .for_each(|address, approval| {
+                let external_account_address = to_delegated_address(rt, address)?;
+                approvals_to.insert(external_account_address, approval.clone());
+                Ok(())
+            })?;
+
+        let mut approvals_from = HashMap::new();
+        account
+            .approvals_from
+            .hamt(store)?
+            .for_each(|address, approval| {
+                let external_account_address = to_delegated_address(rt, address)?;
+                approvals_from.insert(external_account_address, approval.clone());
+                Ok(())
+            })?;
+
+        Ok(AccountInfo {
+            capacity_used: account.capacity_used,
+            credit_free: account.credit_free,
+            credit_committed: account.credit_committed,
+            credit_sponsor: account.credit_sponsor,
+            last_debit_epoch: account.last_debit_epoch,
+            approvals_to,
+            approvals_from,
+            max_ttl: account.max_ttl,
+            gas_allowance: account.gas_allowance,
+            max_capacity: account.max_capacity,
+        })
+    }
+}
+
+/// A named group of accounts whose blob additions are funded by a single sponsor.
+///
+/// Each member holds a regular credit approval from the sponsor, limited to the group's
+/// per-member credit limit. The group budget caps the credit used across all members.
+#[derive(Debug, Clone, Default, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct SponsorGroup {
+    /// Optional limit on the total credit used by all members.
+    pub budget: Option<Credit>,
+    /// Optional limit on the credit used by each member.
+    pub member_credit_limit: Option<Credit>,
+    /// Group members.
+    pub members: HashSet<Address>,
+}
+
 /// A credit approval from one account to another.
 #[derive(Debug, Clone, PartialEq, Serialize_tuple, Deserialize_tuple)]
 pub struct CreditApproval {
@@ -136,6 +242,18 @@ pub struct CreditApproval {
     pub credit_used: Credit,
     /// Used to track gas fees paid for by the delegation
     pub gas_fee_used: TokenAmount,
+    /// Optional restriction on caller addresses, e.g., a bucket.
+    /// The receiver can only use the approval via an allowlisted caller.
+    pub caller_allowlist: Option<HashSet<Address>>,
+}
+
+impl CreditApproval {
+    /// Returns whether the approval may be used via the given caller.
+    pub fn is_caller_allowed(&self, caller: &Address) -> bool {
+        self.caller_allowlist
+            .as_ref()
+            .map_or(true, |allowlist| allowlist.contains(caller))
+    }
 }
 
 /// Gas allowance for an account.
@@ -290,6 +408,26 @@ impl TryFrom<String> for PublicKey {
     }
 }
 
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(
+            &data_encoding::BASE32_NOPAD
+                .encode(&self.0)
+                .to_ascii_lowercase(),
+        )
+    }
+}
+
+impl MapKey for PublicKey {
+    fn from_bytes(b: &[u8]) -> Result<Self, String> {
+        b.try_into().map_err(|e: anyhow::Error| e.to_string())
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        Ok(self.0.to_vec())
+    }
+}
+
 /// The stored representation of a blob.
 #[derive(Clone, PartialEq, Debug, Serialize_tuple, Deserialize_tuple)]
 pub struct Blob {
@@ -391,6 +529,30 @@ pub struct Subscription {
     pub delegate: Option<Address>,
     /// Whether the subscription failed due to an issue resolving the target blob.
     pub failed: bool,
+    /// Whether the subscription should be renewed by its TTL when it expires,
+    /// as long as the subscriber has enough free credit.
+    pub auto_renew: bool,
+    /// The TTL the subscription was last added with. Used for renewal.
+    pub ttl: ChainEpoch,
+    /// Small user-defined key/value metadata, e.g., content-type or filename.
+    pub metadata: HashMap<String, String>,
+    /// Set if the subscription expired but could not be renewed due to insufficient credit.
+    /// The subscription is delinquent and will be deleted at this epoch unless it's renewed.
+    pub delinquent_until: Option<ChainEpoch>,
+    /// Number of independent Iroh nodes that should hold the blob.
+    pub replication: u32,
+}
+
+impl Subscription {
+    /// Returns whether the subscription is delinquent, i.e., in its grace period.
+    pub fn is_delinquent(&self) -> bool {
+        self.delinquent_until.is_some()
+    }
+
+    /// Returns the epoch at which the subscription is indexed for expiry processing.
+    pub fn expiry_index_epoch(&self) -> ChainEpoch {
+        self.delinquent_until.unwrap_or(self.expiry)
+    }
 }
 
 /// User-defined identifier used to differentiate blob subscriptions for the same subscriber.
@@ -414,6 +576,11 @@ impl SubscriptionId {
             inner: value.to_string(),
         })
     }
+
+    /// Returns the ID as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.inner
+    }
 }
 
 impl From<SubscriptionId> for String {
@@ -451,6 +618,63 @@ impl MapKey for SubscriptionId {
     }
 }
 
+/// Key of a subscription across subscribers.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SubscriptionKey {
+    pub subscriber: Address,
+    pub id: SubscriptionId,
+}
+
+impl SubscriptionKey {
+    pub fn new(subscriber: Address, id: &SubscriptionId) -> Self {
+        Self {
+            subscriber,
+            id: id.clone(),
+        }
+    }
+}
+
+impl fmt::Display for SubscriptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.subscriber, self.id)
+    }
+}
+
+impl From<(Address, SubscriptionId)> for SubscriptionKey {
+    fn from((subscriber, id): (Address, SubscriptionId)) -> Self {
+        Self { subscriber, id }
+    }
+}
+
+/// Keys are encoded as the length of the subscriber's address bytes,
+/// followed by the address bytes and the ID bytes.
+/// Addresses are at most 65 bytes, so the length fits in a single byte.
+impl MapKey for SubscriptionKey {
+    fn from_bytes(b: &[u8]) -> Result<Self, String> {
+        let (len, rest) = b
+            .split_first()
+            .ok_or_else(|| "subscription key is empty".to_string())?;
+        if rest.len() < *len as usize {
+            return Err("subscription key is too short".into());
+        }
+        let (subscriber, id) = rest.split_at(*len as usize);
+        Ok(Self {
+            subscriber: <Address as MapKey>::from_bytes(subscriber)?,
+            id: <SubscriptionId as MapKey>::from_bytes(id)?,
+        })
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let subscriber = self.subscriber.to_bytes();
+        let len = u8::try_from(subscriber.len()).map_err(|e| e.to_string())?;
+        let mut bytes = Vec::with_capacity(1 + subscriber.len() + self.id.as_str().len());
+        bytes.push(len);
+        bytes.extend_from_slice(&subscriber);
+        bytes.extend_from_slice(self.id.as_str().as_bytes());
+        Ok(bytes)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize_tuple, Deserialize_tuple)]
 pub struct SubscriptionGroup {
     pub root: hamt::Root<SubscriptionId, Subscription>,
@@ -670,10 +894,168 @@ impl CreditApprovals {
     }
 }
 
+/// The kind of credit event recorded in an [`AccountStatement`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StatementEntryKind {
+    /// Credit was bought with token.
+    Buy,
+    /// Credit was sold back for token.
+    Withdraw,
+    /// Credit was received from another account.
+    TransferIn,
+    /// Credit was sent to another account.
+    TransferOut,
+    /// Free credit was committed to storage.
+    Commit,
+    /// Free credit was committed to storage via a credit approval.
+    ApprovalUsed,
+    /// Over-debited credit was returned.
+    Refund,
+    /// Committed credit was released back to free credit.
+    Release,
+    /// Committed credit was debited for storage usage.
+    Debit,
+}
+
+/// A credit event recorded in an [`AccountStatement`].
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct StatementEntry {
+    /// The epoch at which the event occurred.
+    pub epoch: ChainEpoch,
+    /// The kind of event.
+    pub kind: StatementEntryKind,
+    /// The amount of credit involved.
+    pub amount: Credit,
+    /// The other account involved, e.g., the transfer recipient or approval origin.
+    pub counterparty: Option<Address>,
+}
+
+/// A bounded log of credit events for an account.
+///
+/// Entries are keyed by a monotonically increasing sequence number.
+/// Once the log is full, the oldest entries are dropped first.
+#[derive(Debug, Clone, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct AccountStatement {
+    pub root: amt::Root<StatementEntry>,
+    /// Sequence number of the oldest retained entry.
+    pub first_seq: u64,
+    /// Sequence number that will be assigned to the next entry.
+    pub next_seq: u64,
+}
+
+impl AccountStatement {
+    pub fn new<BS: Blockstore>(store: &BS) -> Result<Self, ActorError> {
+        let root = amt::Root::<StatementEntry>::new(store)?;
+        Ok(Self {
+            root,
+            first_seq: 0,
+            next_seq: 0,
+        })
+    }
+
+    pub fn amt<BS: Blockstore>(
+        &self,
+        store: BS,
+    ) -> Result<amt::vec::Amt<BS, StatementEntry>, ActorError> {
+        self.root.amt(store)
+    }
+
+    /// Appends an entry, dropping the oldest entries so that at most `max_len` are retained.
+    /// Nothing is recorded if `max_len` is zero.
+    pub fn push<BS: Blockstore>(
+        &mut self,
+        store: BS,
+        entry: StatementEntry,
+        max_len: u64,
+    ) -> Result<(), ActorError> {
+        if max_len == 0 {
+            return Ok(());
+        }
+        let mut amt = self.amt(store)?;
+        amt.set(self.next_seq, entry)?;
+        self.next_seq += 1;
+        while self.next_seq - self.first_seq > max_len {
+            amt.delete(self.first_seq)?;
+            self.first_seq += 1;
+        }
+        self.root = amt.flush()?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> u64 {
+        self.next_seq - self.first_seq
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_map_keys_round_trip() {
+        let public_key = PublicKey([7; 32]);
+        let bytes = MapKey::to_bytes(&public_key).unwrap();
+        assert_eq!(
+            <PublicKey as MapKey>::from_bytes(&bytes).unwrap(),
+            public_key
+        );
+        assert!(<PublicKey as MapKey>::from_bytes(&bytes[1..]).is_err());
+
+        for subscriber in [
+            Address::new_id(1001),
+            Address::new_delegated(10, &[0xab; 20]).unwrap(),
+        ] {
+            for id in ["", "foo"] {
+                let key = SubscriptionKey::new(subscriber, &SubscriptionId::new(id).unwrap());
+                let bytes = MapKey::to_bytes(&key).unwrap();
+                assert_eq!(
+                    <SubscriptionKey as MapKey>::from_bytes(&bytes).unwrap(),
+                    key
+                );
+            }
+        }
+        assert!(<SubscriptionKey as MapKey>::from_bytes(&[]).is_err());
+        assert!(<SubscriptionKey as MapKey>::from_bytes(&[30, 0]).is_err());
+    }
+
+    #[test]
+    fn test_account_statement_is_bounded() {
+        let store = fvm_ipld_blockstore::MemoryBlockstore::default();
+        let mut statement = AccountStatement::new(&store).unwrap();
+        let entry = |epoch: ChainEpoch| StatementEntry {
+            epoch,
+            kind: StatementEntryKind::Buy,
+            amount: Credit::from_whole(1),
+            counterparty: None,
+        };
+        for epoch in 0..5 {
+            statement.push(&store, entry(epoch), 3).unwrap();
+        }
+        assert_eq!(statement.len(), 3);
+        assert_eq!(statement.first_seq, 2);
+        assert_eq!(statement.next_seq, 5);
+
+        let amt = statement.amt(&store).unwrap();
+        assert!(amt.get(1).unwrap().is_none());
+        assert_eq!(amt.get(2).unwrap(), Some(entry(2)));
+        assert_eq!(amt.get(4).unwrap(), Some(entry(4)));
+
+        // Shrinking the length drops the oldest entries on the next push
+        statement.push(&store, entry(5), 1).unwrap();
+        assert_eq!(statement.len(), 1);
+        assert_eq!(statement.first_seq, 5);
+        let amt = statement.amt(&store).unwrap();
+        assert_eq!(amt.count(), 1);
+
+        // A zero length disables recording
+        statement.push(&store, entry(6), 0).unwrap();
+        assert_eq!(statement.next_seq, 6);
+    }
+
     #[test]
     fn test_subscription_id_length() {
         let id_str = |len: usize| "a".repeat(len);
@@ -2,40 +2,59 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::collections::{HashMap, HashSet};
+
 use fendermint_actor_blobs_shared::params::{
-    AddBlobParams, ApproveCreditParams, BuyCreditParams, DeleteBlobParams, FinalizeBlobParams,
-    GetAccountParams, GetAddedBlobsParams, GetBlobParams, GetBlobStatusParams,
-    GetCreditApprovalParams, GetGasAllowanceParams, GetPendingBlobsParams, GetStatsReturn,
-    OverwriteBlobParams, RevokeCreditParams, SetAccountStatusParams, SetBlobPendingParams,
-    SetSponsorParams, TrimBlobExpiriesParams, UpdateGasAllowanceParams,
+    AddBlobParams, ApproveCreditParams, BuyCreditParams, CloseAccountParams,
+    CreateSponsorGroupParams, DeleteBlobParams, DeleteBlobsParams, DeleteBlobsReturn,
+    DeleteSponsorGroupParams, ExtendSubscriptionParams, FinalizeBlobParams, GetAccountParams,
+    GetAccountStatementParams, GetAccountStatementReturn, GetAddedBlobsParams, GetAddedBlobsReturn,
+    GetBlobMetadataParams, GetBlobParams, GetBlobProofParams, GetBlobProofReturn,
+    GetBlobStatusParams, GetBlobSubscribersParams, GetBlobSubscribersReturn,
+    GetCreditApprovalParams, GetCreditApprovalsParams, GetCreditApprovalsReturn,
+    GetGasAllowanceParams, GetOrphanedSubscriptionsParams, GetOrphanedSubscriptionsReturn,
+    GetPendingBlobsParams, GetPendingBlobsReturn, GetSponsorGroupParams, GetStatsReturn,
+    GetUsageReportParams, GetUsageReportReturn, ListBlobsParams, ListBlobsReturn,
+    OverwriteBlobParams, ProofBlock, RevokeCreditParams, SetAccountQuotaParams,
+    SetAccountStatusParams, SetBlobMetadataParams, SetBlobPendingParams, SetSponsorParams,
+    SponsorGroupInfo, SponsorGroupMemberParams, TransferCreditParams, TransferSubscriptionParams,
+    TrimBlobExpiriesParams, UndeleteBlobParams, UpdateGasAllowanceParams, VerifyInvariantsReturn,
 };
 use fendermint_actor_blobs_shared::state::{
-    BlobInfo, BlobRequest, BlobStatus, Credit, CreditApproval, GasAllowance, Hash, Subscription,
+    AccountInfo, BlobInfo, BlobRequest, BlobStatus, Credit, CreditApproval, GasAllowance, Hash,
+    PendingBlobRequest, Subscription,
 };
 use fendermint_actor_blobs_shared::Method;
-use fendermint_actor_recall_config_shared::{get_config, require_caller_is_admin};
+use fendermint_actor_recall_config_shared::{
+    get_config, get_config_cached, require_caller_has_role, require_caller_is_admin, Role,
+};
 use fil_actors_runtime::{
     actor_dispatch, actor_error, extract_send_result,
     runtime::{ActorCode, Runtime},
     ActorError, FIRST_EXPORTED_METHOD_NUMBER, SYSTEM_ACTOR_ADDR,
 };
 use fvm_ipld_encoding::ipld_block::IpldBlock;
-use fvm_shared::{econ::TokenAmount, error::ExitCode, MethodNum, METHOD_SEND};
+use fvm_ipld_encoding::CborStore;
+use fvm_shared::{address::Address, econ::TokenAmount, error::ExitCode, MethodNum, METHOD_SEND};
 use num_traits::Zero;
 use recall_actor_sdk::{
     emit_evm_event, require_addr_is_origin_or_caller, to_delegated_address, to_id_address,
     to_id_and_delegated_address, InputData, InvokeContractParams, InvokeContractReturn,
 };
+use recall_ipld::recording::RecordingBlockstore;
 
 use crate::sol_facade::credit::{CreditApproved, CreditDebited, CreditPurchased, CreditRevoked};
 use crate::sol_facade::gas::{GasSponsorSet, GasSponsorUnset};
 use crate::sol_facade::{blobs as sol_blobs, credit as sol_credit, AbiCall, AbiCallRuntime};
-use crate::state::AccountInfo;
 use crate::{State, BLOBS_ACTOR_NAME};
 
 #[cfg(feature = "fil-actor")]
 fil_actors_runtime::wasm_trampoline!(BlobsActor);
 
+/// The maximum number of blobs deleted from disc with a single syscall.
+#[cfg(feature = "fil-actor")]
+const DELETE_FROM_DISC_BATCH_SIZE: usize = 10_000;
+
 /// Singleton actor for managing blob storage.
 ///
 /// The [`Address`]es stored in this actor's state _must_ be ID-based addresses for
@@ -67,6 +86,47 @@ impl BlobsActor {
         Ok(stats)
     }
 
+    /// Returns the accounts with the most capacity used, their recent debits, and blob counts
+    /// by status, so operators can find heavy users without exporting state.
+    ///
+    /// Account addresses are returned as delegated addresses.
+    fn get_usage_report(
+        rt: &impl Runtime,
+        params: GetUsageReportParams,
+    ) -> Result<GetUsageReportReturn, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        if params.limit > GetUsageReportParams::MAX_LIMIT {
+            return Err(ActorError::illegal_argument(format!(
+                "limit must be less than or equal to {}",
+                GetUsageReportParams::MAX_LIMIT
+            )));
+        }
+
+        let config = get_config(rt)?;
+
+        let mut report = rt.state::<State>()?.get_usage_report(
+            &config,
+            rt.store(),
+            rt.curr_epoch(),
+            params.limit,
+            params.since,
+        )?;
+        for usage in report.top_accounts.iter_mut() {
+            usage.address = to_delegated_address(rt, usage.address)?;
+        }
+        Ok(report)
+    }
+
+    /// Recomputes capacity and credit totals from the blob and subscription tables and
+    /// reports any drift from the recorded totals.
+    ///
+    /// This method is read-only and walks the entire state; it's intended for audits.
+    fn verify_invariants(rt: &impl Runtime) -> Result<VerifyInvariantsReturn, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        rt.state::<State>()?.verify_invariants(rt.store())
+    }
+
     /// Buy credit with token.
     ///
     /// The recipient address must be delegated (only delegated addresses can own credit).
@@ -79,6 +139,7 @@ impl BlobsActor {
 
         let mut credit_amount = Credit::zero();
         let account = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
             let pre_buy = st.credit_sold.clone();
             let account = st.buy_credit(
                 &config,
@@ -96,6 +157,59 @@ impl BlobsActor {
         AccountInfo::from(rt, account)
     }
 
+    /// Withdraw free credit back to token.
+    ///
+    /// Credit is sold back at the current token credit rate, and the tokens are sent to `from`.
+    /// Committed credit cannot be withdrawn.
+    /// The `from` address must be delegated (only delegated addresses can own credit).
+    /// The `from` address must be the message origin or caller.
+    fn withdraw_credit(
+        rt: &impl Runtime,
+        params: WithdrawCreditParams,
+    ) -> Result<AccountInfo, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let from = to_id_address(rt, params.from, true)?;
+        require_addr_is_origin_or_caller(rt, from)?;
+
+        let config = get_config(rt)?;
+
+        let (account, tokens) = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
+            st.withdraw_credit(&config, rt.store(), from, params.amount, rt.curr_epoch())
+        })?;
+
+        extract_send_result(rt.send_simple(&from, METHOD_SEND, None, tokens))?;
+
+        AccountInfo::from(rt, account)
+    }
+
+    /// Closes an account, sending back tokens for its remaining free credit.
+    ///
+    /// The account must not have any active subscriptions or credit approvals.
+    fn close_account(
+        rt: &impl Runtime,
+        params: CloseAccountParams,
+    ) -> Result<TokenAmount, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let from = to_id_address(rt, params.0, false)?;
+        require_addr_is_origin_or_caller(rt, from)?;
+
+        let config = get_config(rt)?;
+
+        let tokens = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
+            st.close_account(&config, rt.store(), from)
+        })?;
+
+        if tokens.is_positive() {
+            extract_send_result(rt.send_simple(&from, METHOD_SEND, None, tokens.clone()))?;
+        }
+
+        Ok(tokens)
+    }
+
     /// Updates gas allowance for the `from` address.
     ///
     /// The allowance update is applied to `sponsor` if it exists.
@@ -131,7 +245,8 @@ impl BlobsActor {
     /// The `from` address must be delegated (only delegated addresses can own credit).
     /// The `from` address must be the message origin or caller.
     /// The `to` address must be delegated (only delegated addresses can use credit).
-    /// TODO: Remove the `caller_allowlist` parameter.
+    /// If a `caller_allowlist` is given, the `to` address can only use the approval via one of
+    /// the allowlisted callers, e.g., a bucket.
     fn approve_credit(
         rt: &impl Runtime,
         params: ApproveCreditParams,
@@ -141,10 +256,23 @@ impl BlobsActor {
         let (from_id_addr, from_delegated_addr) = to_id_and_delegated_address(rt, params.from)?;
         require_addr_is_origin_or_caller(rt, from_id_addr)?;
 
+        // An empty allowlist is treated as no restriction
+        let caller_allowlist = params
+            .caller_allowlist
+            .filter(|allowlist| !allowlist.is_empty())
+            .map(|allowlist| {
+                allowlist
+                    .into_iter()
+                    .map(|caller| to_id_address(rt, caller, false))
+                    .collect::<Result<HashSet<_>, ActorError>>()
+            })
+            .transpose()?;
+
         let config = get_config(rt)?;
 
         let (approval, to_delegated_addr) = match to_id_and_delegated_address(rt, params.to) {
             Ok((to_id_addr, to_delegated_addr)) => rt.transaction(|st: &mut State, rt| {
+                st.ensure_not_paused()?;
                 let approval = st.approve_credit(
                     &config,
                     rt.store(),
@@ -154,6 +282,7 @@ impl BlobsActor {
                     params.credit_limit,
                     params.gas_fee_limit,
                     params.ttl,
+                    caller_allowlist,
                 )?;
                 Ok((approval, to_delegated_addr))
             }),
@@ -167,6 +296,7 @@ impl BlobsActor {
                 ))?;
                 let (to_id_addr, to_delegated_addr) = to_id_and_delegated_address(rt, params.to)?;
                 let approval = rt.transaction(|st: &mut State, rt| {
+                    st.ensure_not_paused()?;
                     let approval = st.approve_credit(
                         &config,
                         rt.store(),
@@ -176,6 +306,7 @@ impl BlobsActor {
                         params.credit_limit,
                         params.gas_fee_limit,
                         params.ttl,
+                        caller_allowlist,
                     );
                     st.set_account_sponsor(
                         &config,
@@ -210,6 +341,8 @@ impl BlobsActor {
     /// The `from` address must be delegated (only delegated addresses can own credit).
     /// The `from` address must be the message origin or caller.
     /// The `to` address must be delegated (only delegated addresses can use credit).
+    /// If `terminate_subscriptions` is set, the subscriptions that `to` created under the
+    /// approval are deleted. This scans every blob, so it can be expensive for large subnets.
     fn revoke_credit(rt: &impl Runtime, params: RevokeCreditParams) -> Result<(), ActorError> {
         rt.validate_immediate_caller_accept_any()?;
 
@@ -217,18 +350,110 @@ impl BlobsActor {
         require_addr_is_origin_or_caller(rt, from_id_addr)?;
         let (to_id_addr, to_delegated_addr) = to_id_and_delegated_address(rt, params.to)?;
 
-        rt.transaction(|st: &mut State, rt| {
-            st.revoke_credit(rt.store(), from_id_addr, to_id_addr)
+        let for_caller = params
+            .for_caller
+            .map(|caller| to_id_address(rt, caller, false))
+            .transpose()?;
+        // Subscriptions only record the delegate origin, not the caller it used
+        if for_caller.is_some() && params.terminate_subscriptions {
+            return Err(actor_error!(
+                illegal_argument,
+                "cannot terminate subscriptions when revoking a single caller"
+            ));
+        }
+
+        let config = get_config(rt)?;
+
+        let terminated = rt.transaction(|st: &mut State, rt| {
+            if let Some(caller) = for_caller {
+                st.revoke_credit_caller(rt.store(), from_id_addr, to_id_addr, caller)?;
+                return Ok(Vec::new());
+            }
+            st.revoke_credit(rt.store(), from_id_addr, to_id_addr)?;
+            if !params.terminate_subscriptions {
+                return Ok(Vec::new());
+            }
+            st.ensure_not_paused()?;
+            st.delete_delegated_subscriptions(
+                &config,
+                rt.store(),
+                from_id_addr,
+                to_id_addr,
+                rt.curr_epoch(),
+            )
         })?;
 
         emit_evm_event(
             rt,
             CreditRevoked::new(from_delegated_addr, to_delegated_addr),
         )?;
+        for deleted in &terminated {
+            if deleted.delete_from_disc {
+                delete_from_disc(deleted.hash)?;
+            }
+            emit_evm_event(
+                rt,
+                sol_blobs::BlobDeleted {
+                    subscriber: from_delegated_addr,
+                    hash: &deleted.hash,
+                    size: deleted.size,
+                    bytes_released: deleted.bytes_released,
+                },
+            )?;
+        }
 
         Ok(())
     }
 
+    /// Transfers free credit from one account to another.
+    ///
+    /// Committed credit is never transferred.
+    /// The `from` address must be delegated (only delegated addresses can own credit).
+    /// The `from` address must be the message origin or caller.
+    /// The `to` address must be delegated (only delegated addresses can own credit).
+    fn transfer_credit(
+        rt: &impl Runtime,
+        params: TransferCreditParams,
+    ) -> Result<AccountInfo, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        if let Some(memo) = &params.memo {
+            if memo.len() > TransferCreditParams::MAX_MEMO_LEN {
+                return Err(ActorError::illegal_argument(format!(
+                    "memo exceeds maximum length of {} bytes",
+                    TransferCreditParams::MAX_MEMO_LEN
+                )));
+            }
+        }
+
+        let from = to_id_address(rt, params.from, true)?;
+        require_addr_is_origin_or_caller(rt, from)?;
+        let to = to_id_address(rt, params.to, true)?;
+
+        let config = get_config(rt)?;
+
+        let (account, amount) = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
+            st.transfer_credit(
+                &config,
+                rt.store(),
+                from,
+                to,
+                params.amount,
+                rt.curr_epoch(),
+            )
+        })?;
+        log::debug!(
+            "transferred {} credits from {} to {} (memo: {:?})",
+            amount,
+            from,
+            to,
+            params.memo
+        );
+
+        AccountInfo::from(rt, account)
+    }
+
     /// Sets or unsets a default credit and gas sponsor from one account to another.
     ///
     /// If `sponsor` does not exist, the default sponsor is unset.
@@ -250,6 +475,7 @@ impl BlobsActor {
         let config = get_config(rt)?;
 
         rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
             st.set_account_sponsor(&config, rt.store(), from, sponsor_id_addr, rt.curr_epoch())
         })?;
 
@@ -284,6 +510,52 @@ impl BlobsActor {
         })
     }
 
+    /// Sets or removes the maximum storage capacity an account may use.
+    ///
+    /// The quota is enforced when adding blobs, independent of available credit.
+    /// Existing usage is not affected if it already exceeds a new quota.
+    fn set_account_quota(
+        rt: &impl Runtime,
+        params: SetAccountQuotaParams,
+    ) -> Result<(), ActorError> {
+        require_caller_is_admin(rt)?;
+
+        let subscriber = to_id_address(rt, params.subscriber, true)?;
+
+        let config = get_config(rt)?;
+
+        rt.transaction(|st: &mut State, rt| {
+            st.set_account_quota(
+                &config,
+                rt.store(),
+                subscriber,
+                params.max_capacity,
+                rt.curr_epoch(),
+            )
+        })
+    }
+
+    /// Pauses user methods that add blobs, delete blobs, or buy credit.
+    ///
+    /// Read methods and system methods, like debiting accounts and finalizing blobs,
+    /// continue to work while paused.
+    fn pause(rt: &impl Runtime) -> Result<(), ActorError> {
+        require_caller_has_role(rt, Role::Pauser)?;
+        rt.transaction(|st: &mut State, _| {
+            st.paused = true;
+            Ok(())
+        })
+    }
+
+    /// Resumes user methods paused with `pause`.
+    fn resume(rt: &impl Runtime) -> Result<(), ActorError> {
+        require_caller_has_role(rt, Role::Pauser)?;
+        rt.transaction(|st: &mut State, _| {
+            st.paused = false;
+            Ok(())
+        })
+    }
+
     /// Returns the account for an address.
     ///
     /// Only delegated addresses can own or use credit, but we don't need to waste gas enforcing
@@ -312,6 +584,197 @@ impl BlobsActor {
         account.transpose()
     }
 
+    /// Returns a page of the credit statement for an account.
+    ///
+    /// Counterparty addresses are returned as delegated addresses.
+    /// Only the most recent entries are retained, up to the configured statement length.
+    fn get_account_statement(
+        rt: &impl Runtime,
+        params: GetAccountStatementParams,
+    ) -> Result<Option<GetAccountStatementReturn>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let address = to_id_address(rt, params.address, false)?;
+
+        let statement = rt.state::<State>()?.get_account_statement(
+            rt.store(),
+            address,
+            params.start_seq,
+            params.limit,
+        )?;
+        let (entries, next_seq) = match statement {
+            Some(statement) => statement,
+            None => return Ok(None),
+        };
+        let entries = entries
+            .into_iter()
+            .map(|(seq, mut entry)| {
+                entry.counterparty = entry
+                    .counterparty
+                    .map(|address| to_delegated_address(rt, address))
+                    .transpose()?;
+                Ok((seq, entry))
+            })
+            .collect::<Result<Vec<_>, ActorError>>()?;
+        Ok(Some(GetAccountStatementReturn { entries, next_seq }))
+    }
+
+    /// Creates a named group of accounts whose blob additions are funded by the sponsor.
+    ///
+    /// The `from` address must be delegated (only delegated addresses can own credit).
+    /// The `from` address must be the message origin or caller.
+    fn create_sponsor_group(
+        rt: &impl Runtime,
+        params: CreateSponsorGroupParams,
+    ) -> Result<(), ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let from = to_id_address(rt, params.from, true)?;
+        require_addr_is_origin_or_caller(rt, from)?;
+
+        let config = get_config(rt)?;
+
+        rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
+            st.create_sponsor_group(
+                &config,
+                rt.store(),
+                from,
+                rt.curr_epoch(),
+                params.name,
+                params.budget,
+                params.member_credit_limit,
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Deletes a sponsor group, revoking the credit approvals of all its members.
+    ///
+    /// The `from` address must be the message origin or caller.
+    fn delete_sponsor_group(
+        rt: &impl Runtime,
+        params: DeleteSponsorGroupParams,
+    ) -> Result<(), ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let (from_id_addr, from_delegated_addr) = to_id_and_delegated_address(rt, params.from)?;
+        require_addr_is_origin_or_caller(rt, from_id_addr)?;
+
+        let group = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
+            st.delete_sponsor_group(rt.store(), from_id_addr, &params.name)
+        })?;
+
+        for member in group.members {
+            emit_evm_event(
+                rt,
+                CreditRevoked::new(from_delegated_addr, to_delegated_address(rt, member)?),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds a member to a sponsor group.
+    ///
+    /// The member receives a credit approval from the sponsor that is limited to the group's
+    /// per-member credit limit, and its blob additions also count against the group budget.
+    /// The `from` address must be the message origin or caller.
+    /// The `member` address must be delegated (only delegated addresses can use credit).
+    fn add_sponsor_group_member(
+        rt: &impl Runtime,
+        params: SponsorGroupMemberParams,
+    ) -> Result<CreditApproval, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let (from_id_addr, from_delegated_addr) = to_id_and_delegated_address(rt, params.from)?;
+        require_addr_is_origin_or_caller(rt, from_id_addr)?;
+        let (member_id_addr, member_delegated_addr) =
+            to_id_and_delegated_address(rt, params.member)?;
+
+        let config = get_config(rt)?;
+
+        let approval = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
+            st.add_sponsor_group_member(
+                &config,
+                rt.store(),
+                from_id_addr,
+                rt.curr_epoch(),
+                &params.name,
+                member_id_addr,
+            )
+        })?;
+
+        emit_evm_event(
+            rt,
+            CreditApproved {
+                from: from_delegated_addr,
+                to: member_delegated_addr,
+                credit_limit: approval.credit_limit.clone(),
+                gas_fee_limit: approval.gas_fee_limit.clone(),
+                expiry: approval.expiry,
+            },
+        )?;
+
+        Ok(approval)
+    }
+
+    /// Removes a member from a sponsor group, revoking its credit approval.
+    ///
+    /// The `from` address must be the message origin or caller.
+    fn remove_sponsor_group_member(
+        rt: &impl Runtime,
+        params: SponsorGroupMemberParams,
+    ) -> Result<(), ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let (from_id_addr, from_delegated_addr) = to_id_and_delegated_address(rt, params.from)?;
+        require_addr_is_origin_or_caller(rt, from_id_addr)?;
+        let (member_id_addr, member_delegated_addr) =
+            to_id_and_delegated_address(rt, params.member)?;
+
+        rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
+            st.remove_sponsor_group_member(rt.store(), from_id_addr, &params.name, member_id_addr)
+        })?;
+
+        emit_evm_event(
+            rt,
+            CreditRevoked::new(from_delegated_addr, member_delegated_addr),
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns a sponsor group and the credit used by its members, if it exists.
+    ///
+    /// Member addresses are returned as delegated addresses.
+    fn get_sponsor_group(
+        rt: &impl Runtime,
+        params: GetSponsorGroupParams,
+    ) -> Result<Option<SponsorGroupInfo>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let sponsor = to_id_address(rt, params.sponsor, false)?;
+
+        let group = rt
+            .state::<State>()?
+            .get_sponsor_group(rt.store(), sponsor, &params.name)?;
+        group
+            .map(|mut group| {
+                group.members = group
+                    .members
+                    .into_iter()
+                    .map(|(member, used)| Ok((to_delegated_address(rt, member)?, used)))
+                    .collect::<Result<Vec<_>, ActorError>>()?;
+                Ok(group)
+            })
+            .transpose()
+    }
+
     /// Returns the credit approval from one account to another if it exists.
     ///
     /// Only delegated addresses can own or use credit, but we don't need to waste gas enforcing
@@ -332,6 +795,82 @@ impl BlobsActor {
         Ok(approval)
     }
 
+    /// Returns a page of the credit approvals granted and received by an account.
+    ///
+    /// Approval keys are returned as delegated addresses.
+    /// The returned next keys are opaque cursors for continuing each listing.
+    fn get_credit_approvals(
+        rt: &impl Runtime,
+        params: GetCreditApprovalsParams,
+    ) -> Result<Option<GetCreditApprovalsReturn>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let address = to_id_address(rt, params.address, false)?;
+        let granted_start_key = params
+            .granted_start_key
+            .map(|key| to_id_address(rt, key, false))
+            .transpose()?;
+        let received_start_key = params
+            .received_start_key
+            .map(|key| to_id_address(rt, key, false))
+            .transpose()?;
+
+        let approvals = rt.state::<State>()?.get_credit_approvals(
+            rt.store(),
+            address,
+            granted_start_key,
+            received_start_key,
+            params.limit,
+        )?;
+        let ((granted, granted_next_key), (received, received_next_key)) = match approvals {
+            Some(approvals) => approvals,
+            None => return Ok(None),
+        };
+
+        let to_delegated = |approvals: Vec<(Address, CreditApproval)>| {
+            approvals
+                .into_iter()
+                .map(|(address, approval)| Ok((to_delegated_address(rt, address)?, approval)))
+                .collect::<Result<Vec<_>, ActorError>>()
+        };
+        Ok(Some(GetCreditApprovalsReturn {
+            granted: to_delegated(granted)?,
+            granted_next_key,
+            received: to_delegated(received)?,
+            received_next_key,
+        }))
+    }
+
+    /// Returns a page of subscriptions that a delegate created under credit approvals that
+    /// have since been revoked.
+    ///
+    /// Orphaned subscriptions keep running on the subscriber's credit until they expire or
+    /// are deleted by the subscriber.
+    fn get_orphaned_subscriptions(
+        rt: &impl Runtime,
+        params: GetOrphanedSubscriptionsParams,
+    ) -> Result<GetOrphanedSubscriptionsReturn, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        let delegate = to_id_address(rt, params.delegate, false)?;
+        let (subscriptions, next_key) = rt.state::<State>()?.get_orphaned_subscriptions(
+            rt.store(),
+            delegate,
+            params.start_key,
+            params.limit,
+        )?;
+        let subscriptions = subscriptions
+            .into_iter()
+            .map(|mut sub| {
+                sub.subscriber = to_delegated_address(rt, sub.subscriber)?;
+                Ok(sub)
+            })
+            .collect::<Result<Vec<_>, ActorError>>()?;
+        Ok(GetOrphanedSubscriptionsReturn {
+            subscriptions,
+            next_key,
+        })
+    }
+
     /// Returns the gas allowance from a credit purchase for an address.
     ///
     /// Only delegated addresses can own or use credit, but we don't need to waste gas enforcing
@@ -370,21 +909,46 @@ impl BlobsActor {
         rt.validate_immediate_caller_is(std::iter::once(&SYSTEM_ACTOR_ADDR))?;
         let config = get_config(rt)?;
         let mut credit_debited = Credit::zero();
-        let (deletes, num_accounts) = rt.transaction(|st: &mut State, rt| {
+        let (outcome, num_accounts) = rt.transaction(|st: &mut State, rt| {
             let initial_credit_debited = st.credit_debited.clone();
-            let deletes = st.debit_accounts(
-                rt.store(),
-                rt.curr_epoch(),
-                config.blob_delete_batch_size,
-                config.account_debit_batch_size,
-            )?;
+            let outcome = st.debit_accounts(&config, rt.store(), rt.curr_epoch())?;
             credit_debited = &st.credit_debited - initial_credit_debited;
             let num_accounts = st.accounts.len();
-            Ok((deletes, num_accounts))
+            Ok((outcome, num_accounts))
         })?;
 
-        for hash in deletes {
-            delete_from_disc(hash)?;
+        delete_many_from_disc(outcome.delete_from_disc)?;
+
+        // Renewed and expired subscriptions are reported with the same events used for
+        // user-initiated adds and deletes.
+        // Subscribers without a delegated address are skipped, so that a single bad account
+        // cannot fail the debit cycle.
+        for renewed in outcome.renewed {
+            if let Ok(subscriber) = to_delegated_address(rt, renewed.subscriber) {
+                emit_evm_event(
+                    rt,
+                    sol_blobs::BlobAdded {
+                        subscriber,
+                        hash: &renewed.hash,
+                        size: renewed.size,
+                        expiry: renewed.expiry,
+                        bytes_used: 0,
+                    },
+                )?;
+            }
+        }
+        for expired in outcome.expired {
+            if let Ok(subscriber) = to_delegated_address(rt, expired.subscriber) {
+                emit_evm_event(
+                    rt,
+                    sol_blobs::BlobDeleted {
+                        subscriber,
+                        hash: &expired.hash,
+                        size: expired.size,
+                        bytes_released: expired.bytes_released,
+                    },
+                )?;
+            }
         }
 
         // TODO: Wire more_accounts param when pagination work is done.
@@ -423,11 +987,23 @@ impl BlobsActor {
 
         let tokens_received = rt.message().value_received();
 
-        let config = get_config(rt)?;
+        // Blobs are added often, so avoid calling the config actor more than once per epoch
+        let mut config_cache = rt.state::<State>()?.config_cache;
+        let config = get_config_cached(rt, &mut config_cache)?;
 
         let mut capacity_used = 0;
+        let mut credit_amount = Credit::zero();
         let (sub, tokens_unspent) = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
+            st.config_cache = config_cache;
+            st.ensure_approval_caller_allowed(
+                rt.store(),
+                subscriber_id_addr,
+                from_id_addr,
+                rt.message().caller(),
+            )?;
             let initial_capacity_used = st.capacity_used;
+            let pre_buy = st.credit_sold.clone();
             let res = st.add_blob(
                 &config,
                 rt.store(),
@@ -441,8 +1017,11 @@ impl BlobsActor {
                 params.ttl,
                 params.source,
                 tokens_received,
+                params.auto_renew,
+                params.replication.unwrap_or(1),
             )?;
             capacity_used = st.capacity_used - initial_capacity_used;
+            credit_amount = &st.credit_sold - &pre_buy;
             Ok(res)
         })?;
 
@@ -451,6 +1030,14 @@ impl BlobsActor {
             extract_send_result(rt.send_simple(&from_id_addr, METHOD_SEND, None, tokens_unspent))?;
         }
 
+        // Credit may have been bought inline with the tokens received
+        if credit_amount.is_positive() {
+            emit_evm_event(
+                rt,
+                CreditPurchased::new(subscriber_delegated_addr, credit_amount),
+            )?;
+        }
+
         emit_evm_event(
             rt,
             sol_blobs::BlobAdded {
@@ -474,6 +1061,93 @@ impl BlobsActor {
         }
     }
 
+    /// Returns an inclusion proof for a blob by [`Hash`] if it exists.
+    ///
+    /// The state is read through a recording blockstore, so the returned blocks are exactly
+    /// those on the path from the state root to the blob entry.
+    fn get_blob_proof(
+        rt: &impl Runtime,
+        params: GetBlobProofParams,
+    ) -> Result<Option<GetBlobProofReturn>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        let state_root = rt.get_state_root()?;
+        let store = RecordingBlockstore::new(rt.store());
+        let state = store
+            .get_cbor::<State>(&state_root)
+            .map_err(|e| ActorError::illegal_state(format!("failed to load state: {}", e)))?
+            .ok_or(ActorError::illegal_state("state not found".into()))?;
+        if state.get_blob(&store, params.0)?.is_none() {
+            return Ok(None);
+        }
+        let blocks = store
+            .into_blocks()
+            .into_iter()
+            .map(|(cid, data)| ProofBlock { cid, data })
+            .collect();
+        Ok(Some(GetBlobProofReturn {
+            state_root,
+            epoch: rt.curr_epoch(),
+            blocks,
+        }))
+    }
+
+    /// Returns a page of blob summaries in key order, starting from `start_key`.
+    ///
+    /// Pages hold at most [`ListBlobsParams::MAX_LIMIT`] blobs.
+    /// Pass the returned `next_key` as `start_key` to walk the full blob set.
+    fn list_blobs(
+        rt: &impl Runtime,
+        params: ListBlobsParams,
+    ) -> Result<ListBlobsReturn, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        let (blobs, next_key) =
+            rt.state::<State>()?
+                .list_blobs(rt.store(), params.start_key, params.limit)?;
+        Ok(ListBlobsReturn { blobs, next_key })
+    }
+
+    /// Returns a page of the subscribers of a blob, including each subscription's expiry,
+    /// source, and delegate.
+    ///
+    /// Unlike `get_blob`, this is safe to use for blobs with many subscribers.
+    fn get_blob_subscribers(
+        rt: &impl Runtime,
+        params: GetBlobSubscribersParams,
+    ) -> Result<Option<GetBlobSubscribersReturn>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        let start_key = params
+            .start_key
+            .map(|a| to_id_address(rt, a, false))
+            .transpose()?;
+        let page = rt.state::<State>()?.get_blob_subscribers(
+            rt.store(),
+            params.hash,
+            start_key,
+            params.limit,
+        )?;
+        let (groups, next_key) = match page {
+            Some(page) => page,
+            None => return Ok(None),
+        };
+        let subscribers = groups
+            .into_iter()
+            .map(|mut group| {
+                group.subscriber = to_delegated_address(rt, group.subscriber)?;
+                for sub in group.subscriptions.iter_mut() {
+                    sub.delegate = sub
+                        .delegate
+                        .map(|address| to_delegated_address(rt, address))
+                        .transpose()?;
+                }
+                Ok(group)
+            })
+            .collect::<Result<Vec<_>, ActorError>>()?;
+        Ok(Some(GetBlobSubscribersReturn {
+            subscribers,
+            next_key,
+        }))
+    }
+
     /// Returns the current [`BlobStatus`] for a blob by [`Hash`].
     fn get_blob_status(
         rt: &impl Runtime,
@@ -492,24 +1166,28 @@ impl BlobsActor {
     fn get_added_blobs(
         rt: &impl Runtime,
         params: GetAddedBlobsParams,
-    ) -> Result<Vec<BlobRequest>, ActorError> {
+    ) -> Result<GetAddedBlobsReturn, ActorError> {
         rt.validate_immediate_caller_accept_any()?;
-        rt.state::<State>()?.get_added_blobs(rt.store(), params.0)
+        rt.state::<State>()?
+            .get_added_blobs(rt.store(), params.size, params.start_key)
     }
 
-    /// Returns a list of [`BlobRequest`]s that are currenlty in the [`BlobStatus::Pending`] state.
+    /// Returns a list of [`PendingBlobRequest`]s that are currenlty in the [`BlobStatus::Pending`]
+    /// state.
     ///
     /// All blobs that have been added and picked up by validators for download are in the
     /// [`BlobStatus::Pending`] state.
     /// These are the blobs that validators are currently coordinating to download. They will
     /// vote on the final status ([`BlobStatus::Resolved`] or [`BlobStatus::Failed`]), which is
     /// recorded on-chain with the `finalize_blob` method.
+    /// Each request includes the number of independent Iroh nodes that should hold the blob.
     fn get_pending_blobs(
         rt: &impl Runtime,
         params: GetPendingBlobsParams,
-    ) -> Result<Vec<BlobRequest>, ActorError> {
+    ) -> Result<GetPendingBlobsReturn, ActorError> {
         rt.validate_immediate_caller_accept_any()?;
-        rt.state::<State>()?.get_pending_blobs(rt.store(), params.0)
+        rt.state::<State>()?
+            .get_pending_blobs(rt.store(), params.size, params.start_key)
     }
 
     /// Sets a blob to the [`BlobStatus::Pending`] state.
@@ -550,11 +1228,10 @@ impl BlobsActor {
 
         let (subscriber_id_addr, subscriber_delegated_addr) =
             to_id_and_delegated_address(rt, params.subscriber)?;
-        let event_resolved = matches!(params.status, BlobStatus::Resolved);
 
         let config = get_config(rt)?;
 
-        rt.transaction(|st: &mut State, rt| {
+        let status = rt.transaction(|st: &mut State, rt| {
             st.finalize_blob(
                 &config,
                 rt.store(),
@@ -563,8 +1240,13 @@ impl BlobsActor {
                 params.hash,
                 params.id,
                 params.status,
+                params.size,
             )
         })?;
+        let event_resolved = matches!(status, BlobStatus::Resolved);
+        if event_resolved {
+            pin_on_disc(params.hash)?;
+        }
 
         emit_evm_event(
             rt,
@@ -594,17 +1276,35 @@ impl BlobsActor {
             (from_id_addr, from_delegated_addr)
         };
 
+        let config = get_config(rt)?;
+
         let mut capacity_released = 0;
         let (delete, size) = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
             let initial_capacity_used = st.capacity_used;
-            let res = st.delete_blob(
-                rt.store(),
-                from_id_addr,
-                subscriber_id_addr,
-                rt.curr_epoch(),
-                params.hash,
-                params.id,
-            )?;
+            let res = if params.trash {
+                // Trashed blob data is deleted from disc once the trash period has passed
+                let size = st.trash_blob(
+                    &config,
+                    rt.store(),
+                    from_id_addr,
+                    subscriber_id_addr,
+                    rt.curr_epoch(),
+                    params.hash,
+                    params.id,
+                )?;
+                (false, size)
+            } else {
+                st.delete_blob(
+                    &config,
+                    rt.store(),
+                    from_id_addr,
+                    subscriber_id_addr,
+                    rt.curr_epoch(),
+                    params.hash,
+                    params.id,
+                )?
+            };
             capacity_released = initial_capacity_used - st.capacity_used;
             Ok(res)
         })?;
@@ -626,6 +1326,125 @@ impl BlobsActor {
         Ok(())
     }
 
+    /// Restores a blob subscription that was moved to the trash by `DeleteBlob`.
+    ///
+    /// Credit is committed for the restored subscription as when adding a blob.
+    /// The `from` address must be the message origin or caller.
+    fn undelete_blob(
+        rt: &impl Runtime,
+        params: UndeleteBlobParams,
+    ) -> Result<Subscription, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let (from_id_addr, from_delegated_addr) = to_id_and_delegated_address(rt, params.from)?;
+        require_addr_is_origin_or_caller(rt, from_id_addr)?;
+        let (subscriber_id_addr, subscriber_delegated_addr) = if let Some(sponsor) = params.sponsor
+        {
+            to_id_and_delegated_address(rt, sponsor)?
+        } else {
+            (from_id_addr, from_delegated_addr)
+        };
+
+        let config = get_config(rt)?;
+
+        let mut capacity_used = 0;
+        let mut size = 0;
+        let sub = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
+            st.ensure_approval_caller_allowed(
+                rt.store(),
+                subscriber_id_addr,
+                from_id_addr,
+                rt.message().caller(),
+            )?;
+            let initial_capacity_used = st.capacity_used;
+            let res = st.undelete_blob(
+                &config,
+                rt.store(),
+                from_id_addr,
+                subscriber_id_addr,
+                rt.curr_epoch(),
+                params.hash,
+                params.id,
+            )?;
+            capacity_used = st.capacity_used - initial_capacity_used;
+            size = st
+                .get_blob(rt.store(), params.hash)?
+                .map_or(0, |blob| blob.size);
+            Ok(res)
+        })?;
+
+        emit_evm_event(
+            rt,
+            sol_blobs::BlobAdded {
+                subscriber: subscriber_delegated_addr,
+                hash: &params.hash,
+                size,
+                expiry: sub.expiry,
+                bytes_used: capacity_used,
+            },
+        )?;
+
+        Ok(sub)
+    }
+
+    /// Deletes all of a subscriber's subscriptions whose ID starts with the given prefix.
+    ///
+    /// Blobs are scanned in batches; callers continue with the returned `next_key`
+    /// until it is `None`.
+    fn delete_blobs(
+        rt: &impl Runtime,
+        params: DeleteBlobsParams,
+    ) -> Result<DeleteBlobsReturn, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let (from_id_addr, from_delegated_addr) = to_id_and_delegated_address(rt, params.from)?;
+        require_addr_is_origin_or_caller(rt, from_id_addr)?;
+        let (subscriber_id_addr, subscriber_delegated_addr) = if let Some(sponsor) = params.sponsor
+        {
+            to_id_and_delegated_address(rt, sponsor)?
+        } else {
+            (from_id_addr, from_delegated_addr)
+        };
+
+        let config = get_config(rt)?;
+
+        let outcome = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
+            st.delete_blobs_by_prefix(
+                &config,
+                rt.store(),
+                from_id_addr,
+                subscriber_id_addr,
+                rt.curr_epoch(),
+                &params.id_prefix,
+                params.start_key,
+                params.limit,
+            )
+        })?;
+
+        for deleted in &outcome.deleted {
+            if deleted.delete_from_disc {
+                delete_from_disc(deleted.hash)?;
+            }
+            emit_evm_event(
+                rt,
+                sol_blobs::BlobDeleted {
+                    subscriber: subscriber_delegated_addr,
+                    hash: &deleted.hash,
+                    size: deleted.size,
+                    bytes_released: deleted.bytes_released,
+                },
+            )?;
+        }
+
+        Ok(DeleteBlobsReturn {
+            deleted: outcome.deleted.len() as u64,
+            credit_refunded: outcome.credit_refunded,
+            next_key: outcome.next_key,
+        })
+    }
+
     /// Deletes a blob subscription and adds another in a sinlge call.
     ///
     /// This method is more efficient than two separate calls to `delete_blob` and `add_blob`,
@@ -662,11 +1481,19 @@ impl BlobsActor {
 
         // To ensure atomicity, we combine the two independent calls into a single transaction.
         let (delete, delete_size, sub) = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
+            st.ensure_approval_caller_allowed(
+                rt.store(),
+                subscriber_id_addr,
+                from_id_addr,
+                rt.message().caller(),
+            )?;
             let add_params = params.add;
 
             let initial_capacity_used = st.capacity_used;
             let (delete, delete_size) = if overwrite {
                 st.delete_blob(
+                    &config,
                     rt.store(),
                     from_id_addr,
                     subscriber_id_addr,
@@ -693,6 +1520,8 @@ impl BlobsActor {
                 add_params.ttl,
                 add_params.source,
                 TokenAmount::zero(),
+                add_params.auto_renew,
+                add_params.replication.unwrap_or(1),
             )?;
             capacity_used = st.capacity_used - initial_capacity_used;
 
@@ -725,7 +1554,196 @@ impl BlobsActor {
             },
         )?;
 
-        Ok(sub)
+        Ok(sub)
+    }
+
+    /// Extends the expiry of an existing blob subscription.
+    ///
+    /// Unlike `add_blob`, this does not reset the blob status or re-queue it for resolution.
+    /// The `sponsor` will be the subscriber (the account responsible for payment), if it exists
+    /// and there is an approval from `sponsor` to the message `origin` or `caller`.
+    ///
+    /// Only delegated addresses can own or use credit.
+    fn extend_subscription(
+        rt: &impl Runtime,
+        params: ExtendSubscriptionParams,
+    ) -> Result<Subscription, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let (from_id_addr, from_delegated_addr) = to_id_and_delegated_address(rt, params.from)?;
+        require_addr_is_origin_or_caller(rt, from_id_addr)?;
+        let (subscriber_id_addr, subscriber_delegated_addr) = if let Some(sponsor) = params.sponsor
+        {
+            to_id_and_delegated_address(rt, sponsor)?
+        } else {
+            (from_id_addr, from_delegated_addr)
+        };
+
+        let tokens_received = rt.message().value_received();
+
+        let config = get_config(rt)?;
+
+        let mut size = 0;
+        let mut credit_amount = Credit::zero();
+        let (sub, tokens_unspent) = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
+            st.ensure_approval_caller_allowed(
+                rt.store(),
+                subscriber_id_addr,
+                from_id_addr,
+                rt.message().caller(),
+            )?;
+            let pre_buy = st.credit_sold.clone();
+            let res = st.extend_subscription(
+                &config,
+                rt.store(),
+                from_id_addr,
+                subscriber_id_addr,
+                rt.curr_epoch(),
+                params.hash,
+                params.id,
+                params.ttl,
+                tokens_received,
+            )?;
+            size = st
+                .get_blob(rt.store(), params.hash)?
+                .map_or(0, |blob| blob.size);
+            credit_amount = &st.credit_sold - &pre_buy;
+            Ok(res)
+        })?;
+
+        // Send back unspent tokens
+        if !tokens_unspent.is_zero() {
+            extract_send_result(rt.send_simple(&from_id_addr, METHOD_SEND, None, tokens_unspent))?;
+        }
+
+        // Credit may have been bought inline with the tokens received
+        if credit_amount.is_positive() {
+            emit_evm_event(
+                rt,
+                CreditPurchased::new(subscriber_delegated_addr, credit_amount),
+            )?;
+        }
+
+        emit_evm_event(
+            rt,
+            sol_blobs::BlobAdded {
+                subscriber: subscriber_delegated_addr,
+                hash: &params.hash,
+                size,
+                expiry: sub.expiry,
+                bytes_used: 0,
+            },
+        )?;
+
+        Ok(sub)
+    }
+
+    /// Transfers a blob subscription to another account.
+    ///
+    /// The `to` account must have a credit approval for `from` to accept the transfer.
+    /// Credit for the remaining subscription TTL is committed on the `to` account, which owns
+    /// the new subscription without a delegate.
+    /// Only delegated addresses can own or use credit.
+    /// The `from` address must be the message origin or caller.
+    fn transfer_subscription(
+        rt: &impl Runtime,
+        params: TransferSubscriptionParams,
+    ) -> Result<Subscription, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let (from_id_addr, from_delegated_addr) = to_id_and_delegated_address(rt, params.from)?;
+        require_addr_is_origin_or_caller(rt, from_id_addr)?;
+        let (to_id_addr, to_delegated_addr) = to_id_and_delegated_address(rt, params.to)?;
+
+        let config = get_config(rt)?;
+
+        let mut capacity_used = 0;
+        let mut size = 0;
+        let sub = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
+            let initial_capacity_used = st.capacity_used;
+            let sub = st.transfer_subscription(
+                &config,
+                rt.store(),
+                from_id_addr,
+                to_id_addr,
+                rt.curr_epoch(),
+                params.hash,
+                params.id,
+            )?;
+            capacity_used = st.capacity_used - initial_capacity_used;
+            size = st
+                .get_blob(rt.store(), params.hash)?
+                .map_or(0, |blob| blob.size);
+            Ok(sub)
+        })?;
+
+        emit_evm_event(
+            rt,
+            sol_blobs::BlobAdded {
+                subscriber: to_delegated_addr,
+                hash: &params.hash,
+                size,
+                expiry: sub.expiry,
+                bytes_used: capacity_used,
+            },
+        )?;
+        emit_evm_event(
+            rt,
+            sol_blobs::BlobDeleted {
+                subscriber: from_delegated_addr,
+                hash: &params.hash,
+                size,
+                bytes_released: 0,
+            },
+        )?;
+
+        Ok(sub)
+    }
+
+    /// Inserts, updates, or deletes metadata entries on a blob subscription.
+    ///
+    /// The `sponsor` will be the subscriber (the account responsible for payment), if it exists
+    /// and there is an approval from `sponsor` to the message `origin` or `caller`.
+    /// Returns the updated metadata.
+    fn set_blob_metadata(
+        rt: &impl Runtime,
+        params: SetBlobMetadataParams,
+    ) -> Result<HashMap<String, String>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let from = to_id_address(rt, params.from, false)?;
+        require_addr_is_origin_or_caller(rt, from)?;
+        let subscriber = if let Some(sponsor) = params.sponsor {
+            to_id_address(rt, sponsor, false)?
+        } else {
+            from
+        };
+
+        rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_paused()?;
+            st.set_blob_metadata(
+                rt.store(),
+                from,
+                subscriber,
+                rt.curr_epoch(),
+                params.hash,
+                params.id,
+                params.metadata,
+            )
+        })
+    }
+
+    /// Returns the metadata of a blob subscription if it exists.
+    fn get_blob_metadata(
+        rt: &impl Runtime,
+        params: GetBlobMetadataParams,
+    ) -> Result<Option<HashMap<String, String>>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        let subscriber = to_id_address(rt, params.subscriber, false)?;
+        rt.state::<State>()?
+            .get_blob_metadata(rt.store(), subscriber, params.hash, params.id)
     }
 
     /// Trims the subscription expiries for an account based on its current maximum allowed blob TTL.
@@ -754,9 +1772,7 @@ impl BlobsActor {
             )
         })?;
 
-        for hash in deleted_blobs {
-            delete_from_disc(hash)?;
-        }
+        delete_many_from_disc(deleted_blobs)?;
 
         Ok((processed, next_key))
     }
@@ -880,10 +1896,31 @@ impl BlobsActor {
     }
 }
 
-/// Makes a syscall that will delete a blob from the underlying Iroh-based data store.
+/// Makes a syscall that will pin a blob in the underlying Iroh-based data store,
+/// so the store's own GC doesn't remove content that is still paid for.
+fn pin_on_disc(hash: Hash) -> Result<(), ActorError> {
+    #[cfg(feature = "fil-actor")]
+    {
+        recall_actor_sdk::hash_pin(hash.0).map_err(|en| {
+            ActorError::unspecified(format!("failed to pin blob on disc: {:?}", en))
+        })?;
+        log::debug!("pinned blob {} on disc", hash);
+        Ok(())
+    }
+    #[cfg(not(feature = "fil-actor"))]
+    {
+        log::debug!("mock pin on disc (hash={})", hash);
+        Ok(())
+    }
+}
+
+/// Makes syscalls that will unpin and delete a blob from the underlying Iroh-based data store.
 fn delete_from_disc(hash: Hash) -> Result<(), ActorError> {
     #[cfg(feature = "fil-actor")]
     {
+        recall_actor_sdk::hash_unpin(hash.0).map_err(|en| {
+            ActorError::unspecified(format!("failed to unpin blob on disc: {:?}", en))
+        })?;
         recall_actor_sdk::hash_rm(hash.0).map_err(|en| {
             ActorError::unspecified(format!("failed to delete blob from disc: {:?}", en))
         })?;
@@ -897,6 +1934,35 @@ fn delete_from_disc(hash: Hash) -> Result<(), ActorError> {
     }
 }
 
+/// Makes syscalls that will unpin many blobs and delete them from the underlying Iroh-based
+/// data store with a single deletion syscall per batch.
+fn delete_many_from_disc(hashes: impl IntoIterator<Item = Hash>) -> Result<(), ActorError> {
+    let hashes: Vec<[u8; 32]> = hashes.into_iter().map(|hash| hash.0).collect();
+    if hashes.is_empty() {
+        return Ok(());
+    }
+    #[cfg(feature = "fil-actor")]
+    {
+        for hash in &hashes {
+            recall_actor_sdk::hash_unpin(*hash).map_err(|en| {
+                ActorError::unspecified(format!("failed to unpin blob on disc: {:?}", en))
+            })?;
+        }
+        for chunk in hashes.chunks(DELETE_FROM_DISC_BATCH_SIZE) {
+            recall_actor_sdk::hash_rm_many(chunk).map_err(|en| {
+                ActorError::unspecified(format!("failed to delete blobs from disc: {:?}", en))
+            })?;
+        }
+        log::debug!("deleted {} blobs from disc", hashes.len());
+        Ok(())
+    }
+    #[cfg(not(feature = "fil-actor"))]
+    {
+        log::debug!("mock deletion of {} blobs from disc", hashes.len());
+        Ok(())
+    }
+}
+
 impl ActorCode for BlobsActor {
     type Methods = Method;
 
@@ -909,15 +1975,35 @@ impl ActorCode for BlobsActor {
 
         // User methods
         BuyCredit => buy_credit,
+        WithdrawCredit => withdraw_credit,
+        CloseAccount => close_account,
         ApproveCredit => approve_credit,
         RevokeCredit => revoke_credit,
+        TransferCredit => transfer_credit,
         SetAccountSponsor => set_account_sponsor,
+        CreateSponsorGroup => create_sponsor_group,
+        DeleteSponsorGroup => delete_sponsor_group,
+        AddSponsorGroupMember => add_sponsor_group_member,
+        RemoveSponsorGroupMember => remove_sponsor_group_member,
+        GetSponsorGroup => get_sponsor_group,
         GetAccount => get_account,
+        GetAccountStatement => get_account_statement,
         GetCreditApproval => get_credit_approval,
+        GetCreditApprovals => get_credit_approvals,
+        GetOrphanedSubscriptions => get_orphaned_subscriptions,
         AddBlob => add_blob,
         GetBlob => get_blob,
+        GetBlobProof => get_blob_proof,
+        ListBlobs => list_blobs,
+        GetBlobSubscribers => get_blob_subscribers,
         DeleteBlob => delete_blob,
+        DeleteBlobs => delete_blobs,
+        UndeleteBlob => undelete_blob,
         OverwriteBlob => overwrite_blob,
+        ExtendSubscription => extend_subscription,
+        TransferSubscription => transfer_subscription,
+        SetBlobMetadata => set_blob_metadata,
+        GetBlobMetadata => get_blob_metadata,
 
         // System methods
         GetGasAllowance => get_gas_allowance,
@@ -931,10 +2017,15 @@ impl ActorCode for BlobsActor {
 
         // Admin methods
         SetAccountStatus => set_account_status,
+        SetAccountQuota => set_account_quota,
+        Pause => pause,
+        Resume => resume,
         TrimBlobExpiries => trim_blob_expiries,
 
         // Metrics methods
         GetStats => get_stats,
+        GetUsageReport => get_usage_report,
+        VerifyInvariants => verify_invariants,
         // EVM interop
         InvokeContract => invoke_contract,
         _ => fallback,
@@ -953,7 +2044,6 @@ mod tests {
         expect_empty, MockRuntime, ETHACCOUNT_ACTOR_CODE_ID, EVM_ACTOR_CODE_ID,
         SYSTEM_ACTOR_CODE_ID,
     };
-    use fvm_shared::address::Address;
     use fvm_shared::{bigint::BigInt, clock::ChainEpoch, sys::SendFlags};
     use recall_actor_sdk::to_actor_event;
 
@@ -974,6 +2064,10 @@ mod tests {
     }
 
     fn expect_get_config(rt: &MockRuntime) {
+        expect_get_config_with(rt, &RecallConfig::default());
+    }
+
+    fn expect_get_config_with(rt: &MockRuntime, config: &RecallConfig) {
         rt.expect_send(
             RECALL_CONFIG_ACTOR_ADDR,
             fendermint_actor_recall_config_shared::Method::GetConfig as MethodNum,
@@ -981,7 +2075,7 @@ mod tests {
             TokenAmount::zero(),
             None,
             SendFlags::READ_ONLY,
-            IpldBlock::serialize_cbor(&RecallConfig::default()).unwrap(),
+            IpldBlock::serialize_cbor(config).unwrap(),
             ExitCode::OK,
             None,
         );
@@ -1120,6 +2214,225 @@ mod tests {
         rt.verify();
     }
 
+    #[test]
+    fn test_buy_credit_paused() {
+        let rt = construct_and_verify();
+
+        let id_addr = Address::new_id(110);
+        let eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let f4_eth_addr = Address::new_delegated(10, &eth_addr.0).unwrap();
+
+        rt.set_delegated_address(id_addr.id().unwrap(), f4_eth_addr);
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr);
+        rt.set_origin(id_addr);
+
+        let mut state = rt.get_state::<State>();
+        state.paused = true;
+        rt.replace_state(&state);
+
+        rt.set_received(TokenAmount::from_whole(1));
+        rt.expect_validate_caller_any();
+        let fund_params = BuyCreditParams(f4_eth_addr);
+        expect_get_config(&rt);
+        let result = rt.call::<BlobsActor>(
+            Method::BuyCredit as u64,
+            IpldBlock::serialize_cbor(&fund_params).unwrap(),
+        );
+        assert_eq!(result.unwrap_err().exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+    }
+
+    #[test]
+    fn test_withdraw_credit_paused() {
+        let rt = construct_and_verify();
+
+        let id_addr = Address::new_id(110);
+        let eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let f4_eth_addr = Address::new_delegated(10, &eth_addr.0).unwrap();
+        rt.set_delegated_address(id_addr.id().unwrap(), f4_eth_addr);
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr);
+        rt.set_origin(id_addr);
+
+        let mut state = rt.get_state::<State>();
+        state.paused = true;
+        rt.replace_state(&state);
+
+        rt.expect_validate_caller_any();
+        expect_get_config(&rt);
+        let withdraw_params = WithdrawCreditParams {
+            from: id_addr,
+            amount: None,
+        };
+        let result = rt.call::<BlobsActor>(
+            Method::WithdrawCredit as u64,
+            IpldBlock::serialize_cbor(&withdraw_params).unwrap(),
+        );
+        assert_eq!(result.unwrap_err().exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+    }
+
+    #[test]
+    fn test_transfer_credit_paused() {
+        let rt = construct_and_verify();
+
+        let from_id_addr = Address::new_id(110);
+        let from_eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let from_f4_eth_addr = Address::new_delegated(10, &from_eth_addr.0).unwrap();
+        rt.set_delegated_address(from_id_addr.id().unwrap(), from_f4_eth_addr);
+
+        let to_id_addr = Address::new_id(111);
+        let to_eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000001"
+        ));
+        let to_f4_eth_addr = Address::new_delegated(10, &to_eth_addr.0).unwrap();
+        rt.set_delegated_address(to_id_addr.id().unwrap(), to_f4_eth_addr);
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, from_id_addr);
+        rt.set_origin(from_id_addr);
+
+        let mut state = rt.get_state::<State>();
+        state.paused = true;
+        rt.replace_state(&state);
+
+        rt.expect_validate_caller_any();
+        expect_get_config(&rt);
+        let transfer_params = TransferCreditParams {
+            from: from_id_addr,
+            to: to_id_addr,
+            amount: None,
+            memo: None,
+        };
+        let result = rt.call::<BlobsActor>(
+            Method::TransferCredit as u64,
+            IpldBlock::serialize_cbor(&transfer_params).unwrap(),
+        );
+        assert_eq!(result.unwrap_err().exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+    }
+
+    #[test]
+    fn test_approve_credit_paused() {
+        let rt = construct_and_verify();
+
+        let from_id_addr = Address::new_id(110);
+        let from_eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let from_f4_eth_addr = Address::new_delegated(10, &from_eth_addr.0).unwrap();
+        rt.set_delegated_address(from_id_addr.id().unwrap(), from_f4_eth_addr);
+
+        let to_id_addr = Address::new_id(111);
+        let to_eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000001"
+        ));
+        let to_f4_eth_addr = Address::new_delegated(10, &to_eth_addr.0).unwrap();
+        rt.set_delegated_address(to_id_addr.id().unwrap(), to_f4_eth_addr);
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, from_id_addr);
+        rt.set_origin(from_id_addr);
+
+        let mut state = rt.get_state::<State>();
+        state.paused = true;
+        rt.replace_state(&state);
+
+        rt.expect_validate_caller_any();
+        expect_get_config(&rt);
+        let approve_params = ApproveCreditParams {
+            from: from_id_addr,
+            to: to_id_addr,
+            caller_allowlist: None,
+            credit_limit: None,
+            gas_fee_limit: None,
+            ttl: None,
+        };
+        let result = rt.call::<BlobsActor>(
+            Method::ApproveCredit as u64,
+            IpldBlock::serialize_cbor(&approve_params).unwrap(),
+        );
+        assert_eq!(result.unwrap_err().exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+    }
+
+    #[test]
+    fn test_set_account_sponsor_paused() {
+        let rt = construct_and_verify();
+
+        let from_id_addr = Address::new_id(110);
+        let from_eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let from_f4_eth_addr = Address::new_delegated(10, &from_eth_addr.0).unwrap();
+        rt.set_delegated_address(from_id_addr.id().unwrap(), from_f4_eth_addr);
+
+        let sponsor_id_addr = Address::new_id(111);
+        let sponsor_eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000001"
+        ));
+        let sponsor_f4_eth_addr = Address::new_delegated(10, &sponsor_eth_addr.0).unwrap();
+        rt.set_delegated_address(sponsor_id_addr.id().unwrap(), sponsor_f4_eth_addr);
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, from_id_addr);
+        rt.set_origin(from_id_addr);
+
+        let mut state = rt.get_state::<State>();
+        state.paused = true;
+        rt.replace_state(&state);
+
+        rt.expect_validate_caller_any();
+        expect_get_config(&rt);
+        let sponsor_params = SetSponsorParams {
+            from: from_id_addr,
+            sponsor: Some(sponsor_id_addr),
+        };
+        let result = rt.call::<BlobsActor>(
+            Method::SetAccountSponsor as u64,
+            IpldBlock::serialize_cbor(&sponsor_params).unwrap(),
+        );
+        assert_eq!(result.unwrap_err().exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+    }
+
+    #[test]
+    fn test_set_blob_metadata_paused() {
+        let rt = construct_and_verify();
+
+        let id_addr = Address::new_id(110);
+        let eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let f4_eth_addr = Address::new_delegated(10, &eth_addr.0).unwrap();
+        rt.set_delegated_address(id_addr.id().unwrap(), f4_eth_addr);
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr);
+        rt.set_origin(id_addr);
+
+        let mut state = rt.get_state::<State>();
+        state.paused = true;
+        rt.replace_state(&state);
+
+        rt.expect_validate_caller_any();
+        let metadata_params = SetBlobMetadataParams {
+            sponsor: None,
+            hash: new_hash(1024).0,
+            id: SubscriptionId::default(),
+            metadata: HashMap::from([("key".into(), Some("value".into()))]),
+            from: id_addr,
+        };
+        let result = rt.call::<BlobsActor>(
+            Method::SetBlobMetadata as u64,
+            IpldBlock::serialize_cbor(&metadata_params).unwrap(),
+        );
+        assert_eq!(result.unwrap_err().exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+    }
+
     #[test]
     fn test_approve_credit() {
         let rt = construct_and_verify();
@@ -1349,6 +2662,7 @@ mod tests {
             from: owner_id_addr,
             to: to_id_addr,
             for_caller: None,
+            terminate_subscriptions: false,
         };
         expect_emitted_revoke_event(&rt, owner_f4_eth_addr, to_f4_eth_addr);
         let result = rt.call::<BlobsActor>(
@@ -1366,6 +2680,7 @@ mod tests {
             from: owner_id_addr,
             to: to_id_addr,
             for_caller: None,
+            terminate_subscriptions: false,
         };
         let result = rt.call::<BlobsActor>(
             Method::RevokeCredit as u64,
@@ -1384,6 +2699,7 @@ mod tests {
             from: to_id_addr, // mismatch
             to: to_id_addr,
             for_caller: None,
+            terminate_subscriptions: false,
         };
         let result = rt.call::<BlobsActor>(
             Method::RevokeCredit as u64,
@@ -1426,6 +2742,8 @@ mod tests {
             size: hash.1,
             ttl: Some(3600),
             from: id_addr,
+            auto_renew: false,
+            replication: None,
         };
         expect_get_config(&rt);
         let result = rt.call::<BlobsActor>(
@@ -1491,6 +2809,107 @@ mod tests {
         assert_eq!(blob.status, BlobStatus::Added);
     }
 
+    #[test]
+    fn test_add_blob_config_cache() {
+        let rt = construct_and_verify();
+
+        let id_addr = Address::new_id(110);
+        let eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let f4_eth_addr = Address::new_delegated(10, &eth_addr.0).unwrap();
+
+        rt.set_delegated_address(id_addr.id().unwrap(), f4_eth_addr);
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr);
+        rt.set_origin(id_addr);
+        rt.set_epoch(ChainEpoch::from(0));
+
+        // Fund an account
+        let received = TokenAmount::from_whole(1);
+        rt.set_received(received.clone());
+        rt.expect_validate_caller_any();
+        let fund_params = BuyCreditParams(f4_eth_addr);
+        expect_get_config(&rt);
+        expect_emitted_purchase_event(
+            &rt,
+            &fund_params,
+            Credit::from_atto(received.atto() * BigInt::from(1000000000000000000u64)),
+        );
+        let result = rt.call::<BlobsActor>(
+            Method::BuyCredit as u64,
+            IpldBlock::serialize_cbor(&fund_params).unwrap(),
+        );
+        assert!(result.is_ok());
+        rt.verify();
+        rt.set_received(TokenAmount::zero());
+
+        let add_params = |size| {
+            let hash = new_hash(size);
+            AddBlobParams {
+                sponsor: None,
+                source: new_pk(),
+                hash: hash.0,
+                metadata_hash: new_hash(1024).0,
+                id: SubscriptionId::default(),
+                size: hash.1,
+                ttl: Some(3600),
+                from: id_addr,
+                auto_renew: false,
+                replication: None,
+            }
+        };
+
+        // The first blob of the epoch fetches the config
+        rt.set_epoch(ChainEpoch::from(5));
+        let first = add_params(1024);
+        rt.expect_validate_caller_any();
+        expect_get_config(&rt);
+        expect_emitted_add_event(&rt, 5, &first, f4_eth_addr, first.size);
+        let result = rt.call::<BlobsActor>(
+            Method::AddBlob as u64,
+            IpldBlock::serialize_cbor(&first).unwrap(),
+        );
+        assert!(result.is_ok());
+        rt.verify();
+
+        // Only a reference to the config is kept in the state
+        let cached = rt.get_state::<State>().config_cache.unwrap();
+        assert_eq!(cached.epoch, 5);
+        let stored: Option<RecallConfig> = rt.store().get_cbor(&cached.config).unwrap();
+        assert_eq!(stored, Some(RecallConfig::default()));
+
+        // The rest of the epoch reuses it
+        let second = add_params(2048);
+        rt.expect_validate_caller_any();
+        expect_emitted_add_event(&rt, 5, &second, f4_eth_addr, second.size);
+        let result = rt.call::<BlobsActor>(
+            Method::AddBlob as u64,
+            IpldBlock::serialize_cbor(&second).unwrap(),
+        );
+        assert!(result.is_ok());
+        rt.verify();
+
+        // A config changed in the meantime is seen in the next epoch
+        let config = RecallConfig {
+            blob_min_ttl: 7200,
+            ..Default::default()
+        };
+        rt.set_epoch(ChainEpoch::from(6));
+        rt.expect_validate_caller_any();
+        expect_get_config_with(&rt, &config);
+        let result = rt.call::<BlobsActor>(
+            Method::AddBlob as u64,
+            IpldBlock::serialize_cbor(&add_params(4096)).unwrap(),
+        );
+        assert_eq!(
+            result,
+            Err(ActorError::illegal_argument(
+                "minimum blob TTL is 7200".into()
+            ))
+        );
+        rt.verify();
+    }
+
     #[test]
     fn test_add_blob_inline_buy() {
         let rt = construct_and_verify();
@@ -1518,6 +2937,8 @@ mod tests {
             size: hash.1,
             ttl: Some(3600),
             from: id_addr,
+            auto_renew: false,
+            replication: None,
         };
         let tokens_sent = TokenAmount::from_whole(1);
         rt.set_received(tokens_sent.clone());
@@ -1525,6 +2946,11 @@ mod tests {
         let tokens_required_atto = add_params.size * add_params.ttl.unwrap() as u64;
         let expected_tokens_unspent = tokens_sent.atto() - tokens_required_atto;
         expect_get_config(&rt);
+        expect_emitted_purchase_event(
+            &rt,
+            &BuyCreditParams(f4_eth_addr),
+            Credit::from_whole(tokens_required_atto),
+        );
         expect_emitted_add_event(&rt, 0, &add_params, f4_eth_addr, add_params.size);
         rt.expect_send_simple(
             id_addr,
@@ -1554,8 +2980,10 @@ mod tests {
             size: hash.1,
             ttl: Some(3600),
             from: id_addr,
+            auto_renew: false,
+            replication: None,
         };
-        expect_get_config(&rt);
+        // The config is cached for the rest of the epoch
         let response = rt.call::<BlobsActor>(
             Method::AddBlob as u64,
             IpldBlock::serialize_cbor(&add_params).unwrap(),
@@ -1578,8 +3006,14 @@ mod tests {
             size: hash.1,
             ttl: Some(3600),
             from: id_addr,
+            auto_renew: false,
+            replication: None,
         };
-        expect_get_config(&rt);
+        expect_emitted_purchase_event(
+            &rt,
+            &BuyCreditParams(f4_eth_addr),
+            Credit::from_whole(tokens_required_atto),
+        );
         expect_emitted_add_event(&rt, 0, &add_params, f4_eth_addr, add_params.size);
         let result = rt.call::<BlobsActor>(
             Method::AddBlob as u64,
@@ -1682,6 +3116,8 @@ mod tests {
             size: hash.1,
             ttl: Some(3600),
             from: spender_id_addr,
+            auto_renew: false,
+            replication: None,
         };
         expect_get_config(&rt);
         expect_emitted_add_event(&rt, 0, &add_params, sponsor_f4_eth_addr, add_params.size);
@@ -1707,8 +3143,9 @@ mod tests {
             size: hash.1,
             ttl: Some(3600),
             from: spender_id_addr,
+            auto_renew: false,
+            replication: None,
         };
-        expect_get_config(&rt);
         let response = rt.call::<BlobsActor>(
             Method::AddBlob as u64,
             IpldBlock::serialize_cbor(&add_params).unwrap(),
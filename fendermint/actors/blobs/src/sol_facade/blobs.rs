@@ -147,6 +147,8 @@ impl AbiCallRuntime for sol::addBlobCall {
             size,
             ttl,
             from,
+            auto_renew: false,
+            replication: None,
         })
     }
     fn returns(&self, returns: Self::Returns) -> Self::Output {
@@ -168,6 +170,7 @@ impl AbiCallRuntime for sol::deleteBlobCall {
             hash,
             id: subscription_id,
             from,
+            trash: false,
         })
     }
     fn returns(&self, _: Self::Returns) -> Self::Output {
@@ -266,6 +269,8 @@ impl AbiCallRuntime for sol::overwriteBlobCall {
                 size,
                 ttl,
                 from,
+                auto_renew: false,
+                replication: None,
             },
         })
     }
@@ -7,7 +7,7 @@ use fendermint_actor_blobs_shared::params::{
     ApproveCreditParams, BuyCreditParams, GetAccountParams, GetCreditApprovalParams,
     RevokeCreditParams, SetAccountStatusParams, SetSponsorParams,
 };
-use fendermint_actor_blobs_shared::state::{Credit, CreditApproval, TtlStatus};
+use fendermint_actor_blobs_shared::state::{AccountInfo, Credit, CreditApproval, TtlStatus};
 use fil_actors_runtime::runtime::Runtime;
 use fil_actors_runtime::{actor_error, ActorError};
 use fvm_shared::address::Address;
@@ -22,7 +22,6 @@ use std::collections::{HashMap, HashSet};
 pub use recall_sol_facade::credit::Calls;
 
 use crate::sol_facade::{AbiCall, AbiCallRuntime, AbiEncodeError};
-use crate::state::AccountInfo;
 
 pub struct CreditPurchased {
     from: Address,
@@ -249,6 +248,7 @@ impl AbiCallRuntime for sol::revokeCredit_0Call {
             from,
             to,
             for_caller: Some(caller),
+            terminate_subscriptions: false,
         }
     }
 
@@ -270,6 +270,7 @@ impl AbiCallRuntime for sol::revokeCredit_1Call {
             from,
             to,
             for_caller: None,
+            terminate_subscriptions: false,
         }
     }
 
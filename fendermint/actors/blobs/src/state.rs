@@ -7,12 +7,22 @@ use std::error::Error;
 use std::fmt::Display;
 use std::str::from_utf8;
 
-use fendermint_actor_blobs_shared::params::GetStatsReturn;
+use fendermint_actor_blobs_shared::params::{
+    AccountDrift, AccountUsage, BlobSubscriberGroup, BlobSummary, GetAddedBlobsReturn,
+    GetPendingBlobsReturn, GetStatsReturn, GetUsageReportReturn, ListBlobsParams,
+    OrphanedSubscription, SponsorGroupInfo, SubscriptionSummary, VerifyInvariantsReturn,
+};
 use fendermint_actor_blobs_shared::state::{
-    Account, Blob, BlobRequest, BlobStatus, BlobSubscribers, Credit, CreditApproval, GasAllowance,
-    Hash, PublicKey, Subscription, SubscriptionGroup, SubscriptionId, TokenCreditRate, TtlStatus,
+    Account, Blob, BlobRequest, BlobStatus, BlobSubscribers, Credit, CreditApproval,
+    CreditApprovals, GasAllowance, Hash, PendingBlobRequest, PublicKey, SponsorGroup,
+    StatementEntry, StatementEntryKind, Subscription, SubscriptionGroup, SubscriptionId,
+    TokenCreditRate, TtlStatus,
+};
+use fendermint_actor_blobs_shared::{
+    MAX_BLOB_METADATA_ENTRIES, MAX_BLOB_METADATA_KEY_SIZE, MAX_BLOB_METADATA_VALUE_SIZE,
+    MAX_BLOB_REPLICATION, MAX_SPONSOR_GROUP_MEMBERS, MAX_SPONSOR_GROUP_NAME_SIZE,
 };
-use fendermint_actor_recall_config_shared::RecallConfig;
+use fendermint_actor_recall_config_shared::{CachedConfig, RecallConfig};
 use fil_actors_runtime::ActorError;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
@@ -21,21 +31,24 @@ use fvm_shared::address::Address;
 use fvm_shared::bigint::BigInt;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
 use log::{debug, warn};
 use num_traits::{ToPrimitive, Zero};
 use recall_ipld::hamt::{BytesKey, MapKey};
 
-type BlobSourcesResult = anyhow::Result<Vec<BlobRequest>, ActorError>;
+type CreditApprovalsPage = (Vec<(Address, CreditApproval)>, Option<Address>);
+type StatementPage = (Vec<(u64, StatementEntry)>, Option<u64>);
 
 mod accounts;
 mod blobs;
 mod expiries;
+mod trash;
 
 use accounts::AccountsState;
 use blobs::{BlobsProgressCollection, BlobsState};
 use expiries::{ExpiriesState, ExpiryUpdate};
 use fil_actors_runtime::runtime::Runtime;
-use recall_actor_sdk::to_delegated_address;
+use trash::{TrashState, TrashedBlob, TrashedSubscription};
 
 /// The state represents all accounts and stored blobs.
 #[derive(Debug, Serialize_tuple, Deserialize_tuple)]
@@ -58,14 +71,25 @@ pub struct State {
     pub accounts: AccountsState,
     /// HAMT containing all blobs keyed by blob hash.
     pub blobs: BlobsState,
+    /// HAMT containing trashed blob subscriptions that can still be restored.
+    pub trash: TrashState,
     /// The next account to debit in the current debit cycle.
     /// If this is None, we have finished the debit cycle.    
     pub next_debit_addr: Option<Address>,
+    /// Whether user methods that mutate credit or blobs are paused by the admin.
+    pub paused: bool,
+    /// Reference to the config fetched at the latest epoch a blob was added.
+    pub config_cache: Option<CachedConfig>,
 }
 
-/// Key used to namespace subscriptions in the expiry index.
+/// Composite key of a subscription in the expiry index.
+///
+/// Subscriptions expiring at the same epoch are keyed by subscriber, blob hash, and
+/// subscription ID, so any number of subscriptions can share an expiry epoch.
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
 pub struct ExpiryKey {
+    /// Key subscriber.
+    pub subscriber: Address,
     /// Key hash.
     pub hash: Hash,
     /// Key subscription ID.
@@ -74,7 +98,11 @@ pub struct ExpiryKey {
 
 impl Display for ExpiryKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "ExpiryKey(hash: {}, id: {})", self.hash, self.id)
+        write!(
+            f,
+            "ExpiryKey(subscriber: {}, hash: {}, id: {})",
+            self.subscriber, self.hash, self.id
+        )
     }
 }
 
@@ -94,8 +122,9 @@ impl MapKey for ExpiryKey {
 
 impl ExpiryKey {
     /// Create a new expiry key.
-    pub fn new(hash: Hash, id: &SubscriptionId) -> Self {
+    pub fn new(subscriber: Address, hash: Hash, id: &SubscriptionId) -> Self {
         Self {
+            subscriber,
             hash,
             id: id.clone(),
         }
@@ -110,14 +139,102 @@ struct CreditDelegation<'a> {
     /// Note that the Address that has issued this approval (the subscriber/sponsor), and whose
     /// credits are being allowed to be used, are not stored internal to this struct.
     pub approval: &'a mut CreditApproval,
+    /// Credit remaining in the budget of the sponsor group that "origin" belongs to, if any.
+    pub group_budget_unused: Option<Credit>,
 }
 
 impl<'a> CreditDelegation<'a> {
     pub fn new(origin: Address, approval: &'a mut CreditApproval) -> Self {
-        Self { origin, approval }
+        Self {
+            origin,
+            approval,
+            group_budget_unused: None,
+        }
     }
 }
 
+/// Outcome of processing expired subscriptions during a debit cycle.
+#[derive(Debug, Default)]
+pub struct DebitOutcome {
+    /// Blobs that no longer have any subscribers and should be deleted from disc.
+    pub delete_from_disc: HashSet<Hash>,
+    /// Auto-renew subscriptions that were extended by their original TTL.
+    pub renewed: Vec<RenewedSubscription>,
+    /// Subscriptions that were deleted because they expired.
+    pub expired: Vec<ExpiredSubscription>,
+    /// Auto-renew subscriptions that could not be renewed and entered a grace period.
+    pub delinquent: Vec<DelinquentSubscription>,
+}
+
+/// An auto-renew subscription that entered a grace period during a debit cycle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DelinquentSubscription {
+    /// The subscriber that ran out of credit.
+    pub subscriber: Address,
+    /// Blob hash.
+    pub hash: Hash,
+    /// Subscription ID.
+    pub id: SubscriptionId,
+    /// Epoch at which the subscription will be deleted unless it's renewed.
+    pub delinquent_until: ChainEpoch,
+}
+
+/// An auto-renew subscription that was extended during a debit cycle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenewedSubscription {
+    /// The subscriber paying for the renewal.
+    pub subscriber: Address,
+    /// Blob hash.
+    pub hash: Hash,
+    /// Subscription ID.
+    pub id: SubscriptionId,
+    /// Blob size.
+    pub size: u64,
+    /// New subscription expiry.
+    pub expiry: ChainEpoch,
+}
+
+/// A subscription that was deleted on expiry during a debit cycle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpiredSubscription {
+    /// The subscriber of the expired subscription.
+    pub subscriber: Address,
+    /// Blob hash.
+    pub hash: Hash,
+    /// Subscription ID.
+    pub id: SubscriptionId,
+    /// Blob size.
+    pub size: u64,
+    /// Subnet capacity released by the deletion.
+    pub bytes_released: u64,
+}
+
+/// A subscription that was deleted as part of a bulk deletion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeletedSubscription {
+    /// Blob hash.
+    pub hash: Hash,
+    /// Subscription ID.
+    pub id: SubscriptionId,
+    /// Blob size.
+    pub size: u64,
+    /// Subnet capacity released by the deletion.
+    pub bytes_released: u64,
+    /// Whether the blob no longer has any subscribers and should be deleted from disc.
+    pub delete_from_disc: bool,
+}
+
+/// Outcome of deleting a subscriber's subscriptions by ID prefix.
+#[derive(Debug, Default)]
+pub struct BulkDeleteOutcome {
+    /// The deleted subscriptions.
+    pub deleted: Vec<DeletedSubscription>,
+    /// Committed credit returned to the subscriber's free credit.
+    pub credit_refunded: Credit,
+    /// The blob hash to resume scanning from, if there are more blobs.
+    pub next_key: Option<Hash>,
+}
+
 impl State {
     pub fn new<BS: Blockstore>(store: &BS) -> anyhow::Result<Self, ActorError> {
         Ok(Self {
@@ -130,10 +247,23 @@ impl State {
             pending: BlobsProgressCollection::new(store, "pending blobs queue")?,
             accounts: AccountsState::new(store)?,
             blobs: BlobsState::new(store)?,
+            trash: TrashState::new(store)?,
             next_debit_addr: None,
+            paused: false,
+            config_cache: None,
         })
     }
 
+    /// Returns an error if mutating user methods are paused.
+    pub fn ensure_not_paused(&self) -> anyhow::Result<(), ActorError> {
+        if self.paused {
+            return Err(ActorError::forbidden(
+                "blobs actor is paused; please try again later".into(),
+            ));
+        }
+        Ok(())
+    }
+
     pub fn get_stats(&self, config: &RecallConfig, balance: TokenAmount) -> GetStatsReturn {
         GetStatsReturn {
             balance,
@@ -152,6 +282,178 @@ impl State {
         }
     }
 
+    /// Returns the accounts with the most capacity used and blob counts by status.
+    ///
+    /// Only blobs whose earliest subscription was added at or after `since` are counted.
+    /// This walks every account and blob, so it is only meant for diagnostics.
+    pub fn get_usage_report<BS: Blockstore>(
+        &self,
+        config: &RecallConfig,
+        store: &BS,
+        current_epoch: ChainEpoch,
+        limit: u32,
+        since: ChainEpoch,
+    ) -> anyhow::Result<GetUsageReportReturn, ActorError> {
+        let mut accounts = Vec::new();
+        self.accounts.hamt(store)?.for_each(|address, account| {
+            accounts.push((address, account.clone()));
+            Ok(())
+        })?;
+        accounts.sort_by(|(_, a), (_, b)| b.capacity_used.cmp(&a.capacity_used));
+        accounts.truncate(limit as usize);
+
+        // Debits are recorded in the account statement
+        let window_start = current_epoch - config.blob_credit_debit_interval;
+        let mut top_accounts = Vec::with_capacity(accounts.len());
+        for (address, account) in accounts {
+            let mut credit_debited = Credit::zero();
+            account
+                .statement
+                .amt(store)?
+                .for_each_while_ranged(None, None, |_, entry| {
+                    if entry.kind == StatementEntryKind::Debit && entry.epoch > window_start {
+                        credit_debited += &entry.amount;
+                    }
+                    Ok(true)
+                })?;
+            top_accounts.push(AccountUsage {
+                address,
+                capacity_used: account.capacity_used,
+                credit_committed: account.credit_committed,
+                credit_debited,
+            });
+        }
+
+        let mut report = GetUsageReportReturn {
+            top_accounts,
+            num_added: 0,
+            num_pending: 0,
+            num_resolved: 0,
+            num_failed: 0,
+        };
+        self.blobs.hamt(store)?.for_each(|_, blob| {
+            let mut added = None;
+            blob.subscribers.hamt(store)?.for_each(|_, group| {
+                group.hamt(store)?.for_each(|_, sub| {
+                    added = Some(added.map_or(sub.added, |a: ChainEpoch| a.min(sub.added)));
+                    Ok(())
+                })
+            })?;
+            if added.is_some_and(|added| added >= since) {
+                match blob.status {
+                    BlobStatus::Added => report.num_added += 1,
+                    BlobStatus::Pending => report.num_pending += 1,
+                    BlobStatus::Resolved => report.num_resolved += 1,
+                    BlobStatus::Failed => report.num_failed += 1,
+                }
+            }
+            Ok(())
+        })?;
+        Ok(report)
+    }
+
+    /// Recomputes capacity and credit totals from the blob and subscription tables and
+    /// compares them with the recorded totals.
+    ///
+    /// This walks every blob and account, so it is only meant for diagnostics.
+    pub fn verify_invariants<BS: Blockstore>(
+        &self,
+        store: &BS,
+    ) -> anyhow::Result<VerifyInvariantsReturn, ActorError> {
+        // Collect the size and max active expiry of every subscription group.
+        // Groups where every subscription has failed no longer hold capacity.
+        let mut capacity_used_expected = 0;
+        let mut groups: HashMap<Address, Vec<(u64, ChainEpoch)>> = HashMap::new();
+        self.blobs.hamt(store)?.for_each(|_, blob| {
+            let mut blob_active = false;
+            blob.subscribers
+                .hamt(store)?
+                .for_each(|subscriber, group| {
+                    let mut group_expiry = None;
+                    group.hamt(store)?.for_each(|_, sub| {
+                        if !sub.failed {
+                            group_expiry = group_expiry.max(Some(sub.expiry));
+                        }
+                        Ok(())
+                    })?;
+                    if let Some(expiry) = group_expiry {
+                        blob_active = true;
+                        groups
+                            .entry(subscriber)
+                            .or_default()
+                            .push((blob.size, expiry));
+                    }
+                    Ok(())
+                })?;
+            if blob_active {
+                capacity_used_expected += blob.size;
+            }
+            Ok(())
+        })?;
+
+        // Committed credit covers each group from the last debit epoch until its expiry
+        let mut credit_committed_expected = Credit::zero();
+        let mut credit_held = Credit::zero();
+        let mut accounts_drifted = Vec::new();
+        self.accounts.hamt(store)?.for_each(|address, account| {
+            let mut capacity_used = 0;
+            let mut credit_committed = Credit::zero();
+            for (size, expiry) in groups.remove(&address).unwrap_or_default() {
+                capacity_used += size;
+                credit_committed += Credit::from_whole(
+                    self.get_storage_cost(expiry - account.last_debit_epoch, &size),
+                );
+            }
+            if capacity_used != account.capacity_used
+                || credit_committed != account.credit_committed
+            {
+                accounts_drifted.push(AccountDrift {
+                    address,
+                    capacity_used: account.capacity_used,
+                    capacity_used_expected: capacity_used,
+                    credit_committed: account.credit_committed.clone(),
+                    credit_committed_expected: credit_committed.clone(),
+                });
+            }
+            credit_committed_expected += &credit_committed;
+            credit_held += &account.credit_free;
+            credit_held += &account.credit_committed;
+            Ok(())
+        })?;
+        // Subscriptions for missing accounts can't be reconciled against anything
+        if let Some(address) = groups.keys().next() {
+            return Err(ActorError::illegal_state(format!(
+                "subscriber {} has subscriptions but no account",
+                address
+            )));
+        }
+
+        Ok(VerifyInvariantsReturn {
+            capacity_used: self.capacity_used,
+            capacity_used_expected,
+            credit_committed: self.credit_committed.clone(),
+            credit_committed_expected,
+            credit_sold: self.credit_sold.clone(),
+            credit_sold_expected: credit_held + &self.credit_debited,
+            num_accounts: self.accounts.len(),
+            num_blobs: self.blobs.len(),
+            accounts_drifted,
+        })
+    }
+
+    /// Debug-only check that fails if any accounting invariant has drifted.
+    #[cfg(debug_assertions)]
+    pub fn reconcile<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<(), ActorError> {
+        let report = self.verify_invariants(store)?;
+        if !report.is_consistent() {
+            return Err(ActorError::illegal_state(format!(
+                "accounting invariants violated: {:?}",
+                report
+            )));
+        }
+        Ok(())
+    }
+
     pub fn buy_credit<BS: Blockstore>(
         &mut self,
         config: &RecallConfig,
@@ -181,6 +483,15 @@ impl State {
         })?;
         account.credit_free += &credits;
         account.gas_allowance += amount;
+        record_statement(
+            store,
+            config,
+            &mut account,
+            current_epoch,
+            StatementEntryKind::Buy,
+            &credits,
+            None,
+        )?;
         // Save account
         self.accounts
             .save_tracked(accounts.set_and_flush_tracked(&to, account.clone())?);
@@ -189,6 +500,107 @@ impl State {
         Ok(account)
     }
 
+    /// Sells free credit back to token at the current token credit rate.
+    ///
+    /// If `amount` is not specified, all of the account's free credit is withdrawn.
+    /// Only whole atto tokens are returned; any remainder stays in the account as free credit.
+    /// The returned tokens are also removed from the account's gas allowance, which is where
+    /// token received for credit is escrowed.
+    /// Returns the updated account and the amount of token to send back.
+    pub fn withdraw_credit<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        from: Address,
+        amount: Option<Credit>,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<(Account, TokenAmount), ActorError> {
+        let mut accounts = self.accounts.hamt(store)?;
+        let mut account = accounts.get_or_err(&from)?;
+        let amount = amount.unwrap_or_else(|| account.credit_free.clone());
+        if !amount.is_positive() {
+            return Err(ActorError::illegal_argument(
+                "credit amount must be positive".into(),
+            ));
+        }
+        ensure_enough_credits(&from, &account.credit_free, &amount)?;
+
+        let tokens = &amount / &config.token_credit_rate;
+        if !tokens.is_positive() {
+            return Err(ActorError::illegal_argument(format!(
+                "credit amount {} is too small to withdraw",
+                amount
+            )));
+        }
+        if account.gas_allowance < tokens {
+            return Err(ActorError::insufficient_funds(format!(
+                "account {} has insufficient gas allowance to withdraw (available: {}; required: {})",
+                from, account.gas_allowance, tokens
+            )));
+        }
+        // Only burn the credit that corresponds to whole atto tokens
+        let credits = tokens.clone() * &config.token_credit_rate;
+        account.credit_free -= &credits;
+        account.gas_allowance -= &tokens;
+        self.credit_sold -= &credits;
+        record_statement(
+            store,
+            config,
+            &mut account,
+            current_epoch,
+            StatementEntryKind::Withdraw,
+            &credits,
+            None,
+        )?;
+        // Save account
+        self.accounts
+            .save_tracked(accounts.set_and_flush_tracked(&from, account.clone())?);
+
+        debug!("bought back {} credits from {}", credits, from);
+        Ok((account, tokens))
+    }
+
+    /// Closes an account that has no active subscriptions or credit approvals.
+    /// Free credit backed by the account's gas allowance is bought back, and any remaining
+    /// credit is forfeited.
+    /// Returns the amount of token to send back.
+    pub fn close_account<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        address: Address,
+    ) -> anyhow::Result<TokenAmount, ActorError> {
+        let mut accounts = self.accounts.hamt(store)?;
+        let account = accounts.get_or_err(&address)?;
+        if account.capacity_used > 0 || account.credit_committed.is_positive() {
+            return Err(ActorError::forbidden(format!(
+                "cannot close account {} with active subscriptions (capacity used: {}; credit committed: {})",
+                address, account.capacity_used, account.credit_committed
+            )));
+        }
+        if !account.approvals_to.is_empty() || !account.approvals_from.is_empty() {
+            return Err(ActorError::forbidden(format!(
+                "cannot close account {} with outstanding credit approvals; revoke them first",
+                address
+            )));
+        }
+
+        let tokens = (&account.credit_free / &config.token_credit_rate).min(account.gas_allowance);
+        self.credit_sold -= &account.credit_free;
+        self.accounts
+            .save_tracked(accounts.delete_and_flush_tracked(&address)?.0);
+        // Restart the debit cycle if it was going to resume from this account
+        if self.next_debit_addr == Some(address) {
+            self.next_debit_addr = None;
+        }
+
+        debug!(
+            "closed account {} (forfeited {} credits; refunded {} tokens)",
+            address, account.credit_free, tokens
+        );
+        Ok(tokens)
+    }
+
     pub fn update_gas_allowance<BS: Blockstore>(
         &mut self,
         store: &BS,
@@ -285,6 +697,7 @@ impl State {
         credit_limit: Option<Credit>,
         gas_fee_limit: Option<TokenAmount>,
         ttl: Option<ChainEpoch>,
+        caller_allowlist: Option<HashSet<Address>>,
     ) -> anyhow::Result<CreditApproval, ActorError> {
         let credit_limit = credit_limit.map(Credit::from);
         let gas_fee_limit = gas_fee_limit.map(TokenAmount::from);
@@ -312,6 +725,7 @@ impl State {
             expiry,
             credit_used: Credit::zero(),
             gas_fee_used: TokenAmount::zero(),
+            caller_allowlist: caller_allowlist.clone(),
         };
         let mut from_approval = from_account
             .approvals_to
@@ -349,9 +763,11 @@ impl State {
         from_approval.credit_limit = credit_limit.clone();
         from_approval.gas_fee_limit = gas_fee_limit.clone();
         from_approval.expiry = expiry;
+        from_approval.caller_allowlist = caller_allowlist.clone();
         to_approval.credit_limit = credit_limit;
         to_approval.gas_fee_limit = gas_fee_limit;
         to_approval.expiry = expiry;
+        to_approval.caller_allowlist = caller_allowlist;
 
         from_account.approvals_to.save_tracked(
             from_account
@@ -401,6 +817,10 @@ impl State {
             )));
         }
         from_account.approvals_to.save_tracked(tracked_result);
+        // A revoked member no longer belongs to any of the sponsor's groups
+        for group in from_account.sponsor_groups.values_mut() {
+            group.members.remove(&to);
+        }
 
         let mut to_account = accounts.get_or_err(&to)?;
         let (tracked_result, approval) = to_account
@@ -424,103 +844,505 @@ impl State {
         Ok(())
     }
 
-    pub fn get_account<BS: Blockstore>(
-        &self,
-        store: &BS,
-        from: Address,
-    ) -> anyhow::Result<Option<Account>, ActorError> {
-        let accounts = self.accounts.hamt(store)?;
-        accounts.get(&from)
-    }
-
-    /// Returns a [`CreditApproval`] from the given address to the given address
-    /// or [`None`] if no approval exists.
-    pub fn get_credit_approval<BS: Blockstore>(
-        &self,
+    /// Removes a caller from the allowlist of a credit approval.
+    /// The approval is revoked entirely if no allowlisted callers remain.
+    pub fn revoke_credit_caller<BS: Blockstore>(
+        &mut self,
         store: &BS,
         from: Address,
         to: Address,
-    ) -> anyhow::Result<Option<CreditApproval>, ActorError> {
-        let accounts = self.accounts.hamt(store)?;
-        let account = accounts
-            .get(&from)?
-            .ok_or(ActorError::not_found(format!("account {} not found", from)))?;
-        let approval = account.approvals_to.hamt(store)?.get(&to)?;
-        Ok(approval)
-    }
-
-    /// Returns the gas allowance for the given address, including an amount from a default sponsor.
-    /// An error returned from this method would be fatal, as it's called from the FVM executor.
-    pub fn get_gas_allowance<BS: Blockstore>(
-        &self,
-        store: &BS,
-        from: Address,
-        current_epoch: ChainEpoch,
-    ) -> anyhow::Result<GasAllowance, ActorError> {
-        // Get the account or return default allowance
-        let accounts = self.accounts.hamt(store)?;
-        let account = match accounts.get(&from)? {
-            None => return Ok(GasAllowance::default()),
-            Some(account) => account,
-        };
-        let mut allowance = GasAllowance {
-            amount: account.gas_allowance.clone(),
-            ..Default::default()
-        };
-        if let Some(credit_sponsor) = account.credit_sponsor {
-            let sponsor = match accounts.get(&credit_sponsor)? {
-                None => return Ok(allowance),
-                Some(account) => account,
+        caller: Address,
+    ) -> anyhow::Result<(), ActorError> {
+        let mut accounts = self.accounts.hamt(store)?;
+        let mut from_account = accounts.get_or_err(&from)?;
+        let mut to_account = accounts.get_or_err(&to)?;
+        let mut approvals_to = from_account.approvals_to.hamt(store)?;
+        let mut approvals_from = to_account.approvals_from.hamt(store)?;
+        let (mut from_approval, mut to_approval) =
+            match (approvals_to.get(&to)?, approvals_from.get(&from)?) {
+                (Some(from_approval), Some(to_approval)) => (from_approval, to_approval),
+                _ => {
+                    return Err(ActorError::not_found(format!(
+                        "approval from {} to {} not found",
+                        from, to
+                    )))
+                }
             };
-            let sponsored = sponsor
-                .approvals_to
-                .hamt(store)?
-                .get(&from)?
-                .and_then(|approval| {
-                    let expiry_valid = approval
-                        .expiry
-                        .map_or(true, |expiry| expiry > current_epoch);
-                    if !expiry_valid {
-                        return None;
-                    }
-                    let gas_allowance = sponsor.gas_allowance.clone();
-                    let used = approval.gas_fee_used.clone();
-                    let amount = approval
-                        .gas_fee_limit
-                        .clone()
-                        .map_or(gas_allowance.clone(), |limit| {
-                            (limit - used).min(gas_allowance)
-                        });
-                    Some(amount)
-                })
-                .unwrap_or(TokenAmount::zero());
-            allowance.sponsor = Some(credit_sponsor);
-            allowance.sponsored_amount = sponsored;
-        } else {
-            return Ok(allowance);
+        let allowlist = from_approval.caller_allowlist.as_mut().ok_or_else(|| {
+            ActorError::illegal_argument(format!(
+                "approval from {} to {} does not have a caller allowlist",
+                from, to
+            ))
+        })?;
+        if !allowlist.remove(&caller) {
+            return Err(ActorError::not_found(format!(
+                "caller {} not found in approval from {} to {}",
+                caller, from, to
+            )));
         }
-        Ok(allowance)
+        if allowlist.is_empty() {
+            return self.revoke_credit(store, from, to);
+        }
+        to_approval.caller_allowlist = from_approval.caller_allowlist.clone();
+
+        from_account
+            .approvals_to
+            .save_tracked(approvals_to.set_and_flush_tracked(&to, from_approval)?);
+        to_account
+            .approvals_from
+            .save_tracked(approvals_from.set_and_flush_tracked(&from, to_approval)?);
+        accounts.set(&from, from_account)?;
+        accounts.set(&to, to_account)?;
+        self.accounts.save_tracked(accounts.flush_tracked()?);
+
+        debug!(
+            "revoked caller {} from approval from {} to {}",
+            caller, from, to
+        );
+        Ok(())
     }
 
-    pub fn set_account_sponsor<BS: Blockstore>(
+    /// Creates a named group of accounts whose blob additions are funded by `sponsor`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_sponsor_group<BS: Blockstore>(
         &mut self,
         config: &RecallConfig,
         store: &BS,
-        from: Address,
-        sponsor: Option<Address>,
+        sponsor: Address,
         current_epoch: ChainEpoch,
-    ) -> anyhow::Result<(), ActorError> {
-        // Get or create a new account
+        name: String,
+        budget: Option<Credit>,
+        member_credit_limit: Option<Credit>,
+    ) -> anyhow::Result<SponsorGroup, ActorError> {
+        if name.is_empty() || name.len() as u32 > MAX_SPONSOR_GROUP_NAME_SIZE {
+            return Err(ActorError::illegal_argument(format!(
+                "sponsor group name must be non-empty and less than or equal to {}",
+                MAX_SPONSOR_GROUP_NAME_SIZE
+            )));
+        }
+        if [&budget, &member_credit_limit]
+            .into_iter()
+            .flatten()
+            .any(|limit| limit.is_negative())
+        {
+            return Err(ActorError::illegal_argument(
+                "sponsor group limits must not be negative".into(),
+            ));
+        }
         let mut accounts = self.accounts.hamt(store)?;
-        let mut account = accounts.get_or_create(&from, || {
+        let mut account = accounts.get_or_create(&sponsor, || {
             Account::new(store, current_epoch, config.blob_default_ttl)
         })?;
-        account.credit_sponsor = sponsor;
-        // Save account
-        self.accounts
-            .save_tracked(accounts.set_and_flush_tracked(&from, account)?);
+        if account.sponsor_groups.contains_key(&name) {
+            return Err(ActorError::illegal_argument(format!(
+                "sponsor group {} already exists for {}",
+                name, sponsor
+            )));
+        }
+        let group = SponsorGroup {
+            budget,
+            member_credit_limit,
+            members: HashSet::new(),
+        };
+        account.sponsor_groups.insert(name.clone(), group.clone());
+        accounts.set(&sponsor, account)?;
+        self.accounts.save_tracked(accounts.flush_tracked()?);
 
-        debug!("set credit sponsor for {} to {:?}", from, sponsor);
+        debug!("created sponsor group {} for {}", name, sponsor);
+        Ok(group)
+    }
+
+    /// Deletes a sponsor group, revoking the credit approvals of all its members.
+    pub fn delete_sponsor_group<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        sponsor: Address,
+        name: &str,
+    ) -> anyhow::Result<SponsorGroup, ActorError> {
+        let mut accounts = self.accounts.hamt(store)?;
+        let mut account = accounts.get_or_err(&sponsor)?;
+        let group = account.sponsor_groups.remove(name).ok_or_else(|| {
+            ActorError::not_found(format!("sponsor group {} not found for {}", name, sponsor))
+        })?;
+        accounts.set(&sponsor, account)?;
+        self.accounts.save_tracked(accounts.flush_tracked()?);
+        for member in &group.members {
+            self.revoke_credit(store, sponsor, *member)?;
+        }
+
+        debug!("deleted sponsor group {} for {}", name, sponsor);
+        Ok(group)
+    }
+
+    /// Adds a member to a sponsor group.
+    ///
+    /// The member receives a credit approval from the sponsor that is limited to the group's
+    /// per-member credit limit.
+    pub fn add_sponsor_group_member<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        sponsor: Address,
+        current_epoch: ChainEpoch,
+        name: &str,
+        member: Address,
+    ) -> anyhow::Result<CreditApproval, ActorError> {
+        if member == sponsor {
+            return Err(ActorError::illegal_argument(
+                "sponsor cannot be a member of its own group".into(),
+            ));
+        }
+        let mut accounts = self.accounts.hamt(store)?;
+        let mut account = accounts.get_or_err(&sponsor)?;
+        if account
+            .sponsor_groups
+            .values()
+            .any(|g| g.members.contains(&member))
+        {
+            return Err(ActorError::illegal_argument(format!(
+                "{} is already a member of a sponsor group for {}",
+                member, sponsor
+            )));
+        }
+        if account.approvals_to.hamt(store)?.contains_key(&member)? {
+            return Err(ActorError::illegal_argument(format!(
+                "approval from {} to {} already exists",
+                sponsor, member
+            )));
+        }
+        let group = account.sponsor_groups.get_mut(name).ok_or_else(|| {
+            ActorError::not_found(format!("sponsor group {} not found for {}", name, sponsor))
+        })?;
+        if group.members.len() as u32 >= MAX_SPONSOR_GROUP_MEMBERS {
+            return Err(ActorError::forbidden(format!(
+                "sponsor group {} has reached the maximum of {} members",
+                name, MAX_SPONSOR_GROUP_MEMBERS
+            )));
+        }
+        group.members.insert(member);
+        let credit_limit = group.member_credit_limit.clone();
+        accounts.set(&sponsor, account)?;
+        self.accounts.save_tracked(accounts.flush_tracked()?);
+
+        // Members draw credit for blobs only; gas fees are not sponsored
+        let approval = self.approve_credit(
+            config,
+            store,
+            sponsor,
+            member,
+            current_epoch,
+            credit_limit,
+            Some(TokenAmount::zero()),
+            None,
+            None,
+        )?;
+
+        debug!("added {} to sponsor group {} for {}", member, name, sponsor);
+        Ok(approval)
+    }
+
+    /// Removes a member from a sponsor group, revoking its credit approval.
+    pub fn remove_sponsor_group_member<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        sponsor: Address,
+        name: &str,
+        member: Address,
+    ) -> anyhow::Result<(), ActorError> {
+        let account = self.accounts.hamt(store)?.get_or_err(&sponsor)?;
+        let group = account.sponsor_groups.get(name).ok_or_else(|| {
+            ActorError::not_found(format!("sponsor group {} not found for {}", name, sponsor))
+        })?;
+        if !group.members.contains(&member) {
+            return Err(ActorError::not_found(format!(
+                "{} is not a member of sponsor group {} for {}",
+                member, name, sponsor
+            )));
+        }
+        // Revoking the approval also removes the member from the group
+        self.revoke_credit(store, sponsor, member)?;
+
+        debug!(
+            "removed {} from sponsor group {} for {}",
+            member, name, sponsor
+        );
+        Ok(())
+    }
+
+    /// Returns a sponsor group and the credit used by its members.
+    pub fn get_sponsor_group<BS: Blockstore>(
+        &self,
+        store: &BS,
+        sponsor: Address,
+        name: &str,
+    ) -> anyhow::Result<Option<SponsorGroupInfo>, ActorError> {
+        let account = match self.accounts.hamt(store)?.get(&sponsor)? {
+            Some(account) => account,
+            None => return Ok(None),
+        };
+        let group = match account.sponsor_groups.get(name) {
+            Some(group) => group,
+            None => return Ok(None),
+        };
+        let approvals = account.approvals_to.hamt(store)?;
+        let mut credit_used = Credit::zero();
+        let mut members = Vec::with_capacity(group.members.len());
+        for member in &group.members {
+            let used = approvals
+                .get(member)?
+                .map(|approval| approval.credit_used)
+                .unwrap_or_default();
+            credit_used += &used;
+            members.push((*member, used));
+        }
+        Ok(Some(SponsorGroupInfo {
+            budget: group.budget.clone(),
+            member_credit_limit: group.member_credit_limit.clone(),
+            credit_used,
+            members,
+        }))
+    }
+
+    /// Ensures a delegate origin is allowed to use the subscriber's credit approval via the
+    /// given caller.
+    /// This is a no-op if the origin is the subscriber or if there's no approval, in which case
+    /// the credit checks on the delegated path report the missing approval.
+    pub fn ensure_approval_caller_allowed<BS: Blockstore>(
+        &self,
+        store: &BS,
+        subscriber: Address,
+        origin: Address,
+        caller: Address,
+    ) -> anyhow::Result<(), ActorError> {
+        if subscriber == origin {
+            return Ok(());
+        }
+        let approval = match self.accounts.hamt(store)?.get(&subscriber)? {
+            Some(account) => account.approvals_to.hamt(store)?.get(&origin)?,
+            None => None,
+        };
+        if let Some(approval) = approval {
+            if !approval.is_caller_allowed(&caller) {
+                return Err(ActorError::forbidden(format!(
+                    "approval from {} to {} does not allow caller {}",
+                    subscriber, origin, caller
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Transfers free credit from one account to another.
+    ///
+    /// Committed credit is never transferred.
+    /// If `amount` is not specified, all of the sender's free credit is transferred.
+    /// Returns the sender's updated account and the amount of credit transferred.
+    #[allow(clippy::too_many_arguments)]
+    pub fn transfer_credit<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        from: Address,
+        to: Address,
+        amount: Option<Credit>,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<(Account, Credit), ActorError> {
+        if from == to {
+            return Err(ActorError::illegal_argument(
+                "'from' and 'to' addresses must be different".into(),
+            ));
+        }
+        let mut accounts = self.accounts.hamt(store)?;
+        let mut from_account = accounts.get_or_err(&from)?;
+        let amount = amount.unwrap_or_else(|| from_account.credit_free.clone());
+        if !amount.is_positive() {
+            return Err(ActorError::illegal_argument(
+                "credit amount must be positive".into(),
+            ));
+        }
+        ensure_enough_credits(&from, &from_account.credit_free, &amount)?;
+        from_account.credit_free -= &amount;
+        record_statement(
+            store,
+            config,
+            &mut from_account,
+            current_epoch,
+            StatementEntryKind::TransferOut,
+            &amount,
+            Some(to),
+        )?;
+        accounts.set(&from, from_account.clone())?;
+        // Get or create the recipient account
+        let mut to_account = accounts.get_or_create(&to, || {
+            Account::new(store, current_epoch, config.blob_default_ttl)
+        })?;
+        to_account.credit_free += &amount;
+        record_statement(
+            store,
+            config,
+            &mut to_account,
+            current_epoch,
+            StatementEntryKind::TransferIn,
+            &amount,
+            Some(from),
+        )?;
+        self.accounts
+            .save_tracked(accounts.set_and_flush_tracked(&to, to_account)?);
+
+        Ok((from_account, amount))
+    }
+
+    pub fn get_account<BS: Blockstore>(
+        &self,
+        store: &BS,
+        from: Address,
+    ) -> anyhow::Result<Option<Account>, ActorError> {
+        let accounts = self.accounts.hamt(store)?;
+        accounts.get(&from)
+    }
+
+    /// Returns a [`CreditApproval`] from the given address to the given address
+    /// or [`None`] if no approval exists.
+    pub fn get_credit_approval<BS: Blockstore>(
+        &self,
+        store: &BS,
+        from: Address,
+        to: Address,
+    ) -> anyhow::Result<Option<CreditApproval>, ActorError> {
+        let accounts = self.accounts.hamt(store)?;
+        let account = accounts
+            .get(&from)?
+            .ok_or(ActorError::not_found(format!("account {} not found", from)))?;
+        let approval = account.approvals_to.hamt(store)?.get(&to)?;
+        Ok(approval)
+    }
+
+    /// Returns a page of the credit approvals granted and received by an account,
+    /// along with the key to continue from in each direction, if there are more approvals.
+    pub fn get_credit_approvals<BS: Blockstore>(
+        &self,
+        store: &BS,
+        address: Address,
+        granted_start_key: Option<Address>,
+        received_start_key: Option<Address>,
+        limit: Option<u32>,
+    ) -> anyhow::Result<Option<(CreditApprovalsPage, CreditApprovalsPage)>, ActorError> {
+        let accounts = self.accounts.hamt(store)?;
+        let account = if let Some(account) = accounts.get(&address)? {
+            account
+        } else {
+            return Ok(None);
+        };
+        let granted =
+            list_credit_approvals(store, &account.approvals_to, granted_start_key, limit)?;
+        let received =
+            list_credit_approvals(store, &account.approvals_from, received_start_key, limit)?;
+        Ok(Some((granted, received)))
+    }
+
+    /// Returns a page of the credit statement for an account, starting at `start_seq` or
+    /// the oldest retained entry, along with the sequence number to continue from.
+    pub fn get_account_statement<BS: Blockstore>(
+        &self,
+        store: &BS,
+        address: Address,
+        start_seq: Option<u64>,
+        limit: Option<u32>,
+    ) -> anyhow::Result<Option<StatementPage>, ActorError> {
+        let accounts = self.accounts.hamt(store)?;
+        let account = if let Some(account) = accounts.get(&address)? {
+            account
+        } else {
+            return Ok(None);
+        };
+        let statement = account.statement.amt(store)?;
+        let start = start_seq
+            .unwrap_or(account.statement.first_seq)
+            .max(account.statement.first_seq);
+        let mut entries = Vec::new();
+        let (_, next_seq) = statement.for_each_while_ranged(
+            Some(start),
+            limit.map(|l| l as u64),
+            |seq, entry| {
+                entries.push((seq, entry.clone()));
+                Ok(true)
+            },
+        )?;
+        Ok(Some((entries, next_seq)))
+    }
+
+    /// Returns the gas allowance for the given address, including an amount from a default sponsor.
+    /// An error returned from this method would be fatal, as it's called from the FVM executor.
+    pub fn get_gas_allowance<BS: Blockstore>(
+        &self,
+        store: &BS,
+        from: Address,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<GasAllowance, ActorError> {
+        // Get the account or return default allowance
+        let accounts = self.accounts.hamt(store)?;
+        let account = match accounts.get(&from)? {
+            None => return Ok(GasAllowance::default()),
+            Some(account) => account,
+        };
+        let mut allowance = GasAllowance {
+            amount: account.gas_allowance.clone(),
+            ..Default::default()
+        };
+        if let Some(credit_sponsor) = account.credit_sponsor {
+            let sponsor = match accounts.get(&credit_sponsor)? {
+                None => return Ok(allowance),
+                Some(account) => account,
+            };
+            let sponsored = sponsor
+                .approvals_to
+                .hamt(store)?
+                .get(&from)?
+                .and_then(|approval| {
+                    let expiry_valid = approval
+                        .expiry
+                        .map_or(true, |expiry| expiry > current_epoch);
+                    if !expiry_valid {
+                        return None;
+                    }
+                    let gas_allowance = sponsor.gas_allowance.clone();
+                    let used = approval.gas_fee_used.clone();
+                    let amount = approval
+                        .gas_fee_limit
+                        .clone()
+                        .map_or(gas_allowance.clone(), |limit| {
+                            (limit - used).min(gas_allowance)
+                        });
+                    Some(amount)
+                })
+                .unwrap_or(TokenAmount::zero());
+            allowance.sponsor = Some(credit_sponsor);
+            allowance.sponsored_amount = sponsored;
+        } else {
+            return Ok(allowance);
+        }
+        Ok(allowance)
+    }
+
+    pub fn set_account_sponsor<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        from: Address,
+        sponsor: Option<Address>,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<(), ActorError> {
+        // Get or create a new account
+        let mut accounts = self.accounts.hamt(store)?;
+        let mut account = accounts.get_or_create(&from, || {
+            Account::new(store, current_epoch, config.blob_default_ttl)
+        })?;
+        account.credit_sponsor = sponsor;
+        // Save account
+        self.accounts
+            .save_tracked(accounts.set_and_flush_tracked(&from, account)?);
+
+        debug!("set credit sponsor for {} to {:?}", from, sponsor);
         Ok(())
     }
 
@@ -555,39 +1377,107 @@ impl State {
         Ok(())
     }
 
-    #[allow(clippy::type_complexity)]
+    pub fn set_account_quota<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        subscriber: Address,
+        max_capacity: Option<u64>,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<(), ActorError> {
+        let mut accounts = self.accounts.hamt(store)?;
+        let mut account = accounts.get_or_create(&subscriber, || {
+            Account::new(store, current_epoch, config.blob_default_ttl)
+        })?;
+        account.max_capacity = max_capacity;
+        self.accounts
+            .save_tracked(accounts.set_and_flush_tracked(&subscriber, account)?);
+        Ok(())
+    }
+
+    /// Processes expired subscriptions and debits accounts for existing usage.
+    ///
+    /// Expired subscriptions with `auto_renew` set are extended by their original TTL if the
+    /// subscriber has enough free credit. Otherwise, they are deleted.
     pub fn debit_accounts<BS: Blockstore>(
         &mut self,
+        config: &RecallConfig,
         store: &BS,
         current_epoch: ChainEpoch,
-        blob_delete_batch_size: u64,
-        account_debit_batch_size: u64,
-    ) -> anyhow::Result<HashSet<Hash>, ActorError> {
-        // Delete expired subscriptions
-        let mut delete_from_disc = HashSet::new();
-        let mut num_deleted = 0;
+    ) -> anyhow::Result<DebitOutcome, ActorError> {
+        // Renew or delete expired subscriptions
+        let mut outcome = DebitOutcome::default();
         let mut expiries = self.expiries.clone();
 
         expiries.foreach_up_to_epoch(
             store,
             current_epoch,
-            Some(blob_delete_batch_size),
+            Some(config.blob_delete_batch_size),
             |_, subscriber, key| {
-                match self.delete_blob(
-                    store,
-                    subscriber,
-                    subscriber,
-                    current_epoch,
-                    key.hash,
-                    key.id.clone(),
-                ) {
-                    Ok((from_disc, _)) => {
-                        num_deleted += 1;
+                match self.renew_subscription(config, store, subscriber, current_epoch, &key) {
+                    Ok(Some((sub, size))) => {
+                        outcome.renewed.push(RenewedSubscription {
+                            subscriber,
+                            hash: key.hash,
+                            id: key.id,
+                            size,
+                            expiry: sub.expiry,
+                        });
+                        return Ok(());
+                    }
+                    Ok(None) => {
+                        // Give auto-renew subscriptions that ran out of credit a grace period
+                        match self.mark_delinquent(config, store, subscriber, current_epoch, &key) {
+                            Ok(Some(delinquent_until)) => {
+                                outcome.delinquent.push(DelinquentSubscription {
+                                    subscriber,
+                                    hash: key.hash,
+                                    id: key.id,
+                                    delinquent_until,
+                                });
+                                return Ok(());
+                            }
+                            Ok(None) => {}
+                            Err(e) => {
+                                warn!(
+                                    "failed to mark blob {} for {} as delinquent (id: {}): {}",
+                                    key.hash, subscriber, key.id, e
+                                )
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        warn!(
+                            "failed to renew blob {} for {} (id: {}): {}",
+                            key.hash, subscriber, key.id, e
+                        )
+                    }
+                }
+                let initial_capacity_used = self.capacity_used;
+                match self.delete_blob(
+                    config,
+                    store,
+                    subscriber,
+                    subscriber,
+                    current_epoch,
+                    key.hash,
+                    key.id.clone(),
+                ) {
+                    Ok((from_disc, size)) => {
                         if from_disc {
-                            delete_from_disc.insert(key.hash);
+                            outcome.delete_from_disc.insert(key.hash);
                         }
+                        outcome.expired.push(ExpiredSubscription {
+                            subscriber,
+                            hash: key.hash,
+                            id: key.id,
+                            size,
+                            bytes_released: initial_capacity_used - self.capacity_used,
+                        });
                     }
                     Err(e) => {
+                        // The subscription stays in the index and the sweep moves past it,
+                        // so it's retried on the next pass over the index
                         warn!(
                             "failed to delete blob {} for {} (id: {}): {}",
                             key.hash, subscriber, key.id, e
@@ -597,10 +1487,42 @@ impl State {
                 Ok(())
             },
         )?;
-        debug!("deleted {} expired subscriptions", num_deleted);
+        // The index itself was updated while processing; only the cursor is carried over
+        self.expiries.next_idx = expiries.next_idx;
+        self.expiries.next_key = expiries.next_key;
+        // Purge trashed subscriptions whose trash period has passed
+        let mut trash_expiries = self.trash.expiries.clone();
+        trash_expiries.foreach_up_to_epoch(
+            store,
+            current_epoch,
+            Some(config.blob_delete_batch_size),
+            |trashed_until, subscriber, key| {
+                match self.purge_trashed_subscription(store, trashed_until, subscriber, &key) {
+                    Ok(true) => {
+                        outcome.delete_from_disc.insert(key.hash);
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        warn!(
+                            "failed to purge trashed blob {} for {} (id: {}): {}",
+                            key.hash, subscriber, key.id, e
+                        )
+                    }
+                }
+                Ok(())
+            },
+        )?;
+        self.trash.expiries.next_idx = trash_expiries.next_idx;
+        self.trash.expiries.next_key = trash_expiries.next_key;
+        debug!("renewed {} expired subscriptions", outcome.renewed.len());
+        debug!(
+            "marked {} expired subscriptions as delinquent",
+            outcome.delinquent.len()
+        );
+        debug!("deleted {} expired subscriptions", outcome.expired.len());
         debug!(
             "{} blobs marked for deletion from disc",
-            delete_from_disc.len()
+            outcome.delete_from_disc.len()
         );
         // Debit for existing usage
         let reader = self.accounts.hamt(store)?;
@@ -611,7 +1533,7 @@ impl State {
             .map(|address| BytesKey::from(address.to_bytes()));
         let (count, next_account) = reader.for_each_ranged(
             start_key.as_ref(),
-            Some(account_debit_batch_size as usize),
+            Some(config.account_debit_batch_size as usize),
             |address, account| {
                 let mut account = account.clone();
                 let debit_blocks = current_epoch - account.last_debit_epoch;
@@ -622,6 +1544,15 @@ impl State {
                 account.credit_committed -= &debit_credits;
                 account.last_debit_epoch = current_epoch;
                 debug!("debited {} credits from {}", debit_credits, address);
+                record_statement(
+                    store,
+                    config,
+                    &mut account,
+                    current_epoch,
+                    StatementEntryKind::Debit,
+                    &debit_credits,
+                    None,
+                )?;
                 writer.set(&address, account)?;
                 Ok(true)
             },
@@ -632,7 +1563,164 @@ impl State {
         );
         self.next_debit_addr = next_account;
         self.accounts.root = writer.flush()?;
-        Ok(delete_from_disc)
+        Ok(outcome)
+    }
+
+    /// Renews an expired auto-renew subscription by its original TTL.
+    ///
+    /// Returns `None` if the subscription is not set to auto-renew, or if the subscriber
+    /// does not have enough free credit to cover the renewal, in which case it should expire.
+    fn renew_subscription<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        subscriber: Address,
+        current_epoch: ChainEpoch,
+        key: &ExpiryKey,
+    ) -> anyhow::Result<Option<(Subscription, u64)>, ActorError> {
+        let blob = if let Some(blob) = self.blobs.hamt(store)?.get(&key.hash)? {
+            blob
+        } else {
+            return Ok(None);
+        };
+        let sub = if let Some(group) = blob.subscribers.hamt(store)?.get(&subscriber)? {
+            group.hamt(store)?.get(&key.id)?
+        } else {
+            None
+        };
+        let sub = match sub {
+            Some(sub) if sub.auto_renew && !sub.failed => sub,
+            _ => return Ok(None),
+        };
+        // The renewal is charged to the same delegate, if any, that added the subscription
+        let origin = sub.delegate.unwrap_or(subscriber);
+        // Only renew if the subscriber can cover the full TTL, including the replication
+        // premium, with free credit.
+        // Running out of credit leads to the grace period; any other failure is returned.
+        let account = self.accounts.hamt(store)?.get_or_err(&subscriber)?;
+        let ttl = self.validate_ttl(config, Some(sub.ttl), &account)?;
+        let credit_required = Credit::from_whole(self.get_storage_cost(ttl, &blob.size));
+        let credit_required =
+            &credit_required + &replication_premium(&credit_required, sub.replication);
+        let mut approval =
+            if origin != subscriber {
+                Some(account.approvals_to.hamt(store)?.get(&origin)?.ok_or(
+                    ActorError::forbidden(format!(
+                        "approval from {} to {} not found",
+                        subscriber, origin
+                    )),
+                )?)
+            } else {
+                None
+            };
+        let group_budget_unused = if origin != subscriber {
+            sponsor_group_budget_unused(store, &account, &origin)?
+        } else {
+            None
+        };
+        let delegation = approval.as_mut().map(|approval| CreditDelegation {
+            group_budget_unused,
+            ..CreditDelegation::new(origin, approval)
+        });
+        match ensure_credit(
+            &subscriber,
+            current_epoch,
+            &account.credit_free,
+            &credit_required,
+            &delegation,
+        ) {
+            Ok(()) => {}
+            Err(e) if e.exit_code() == ExitCode::USR_INSUFFICIENT_FUNDS => {
+                debug!(
+                    "insufficient credit to renew blob {} for {} (id: {}): {}",
+                    key.hash, subscriber, key.id, e
+                );
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        }
+        let (sub, _) = self.add_blob(
+            config,
+            store,
+            origin,
+            subscriber,
+            current_epoch,
+            key.hash,
+            blob.metadata_hash,
+            key.id.clone(),
+            blob.size,
+            Some(ttl),
+            sub.source,
+            TokenAmount::zero(),
+            true,
+            sub.replication,
+        )?;
+        debug!(
+            "renewed subscription to blob {} for {} (id: {}) until {}",
+            key.hash, subscriber, key.id, sub.expiry
+        );
+        Ok(Some((sub, blob.size)))
+    }
+
+    /// Marks an expired auto-renew subscription that could not be renewed as delinquent.
+    ///
+    /// The subscription is kept, and the blob stays readable, until the grace period from
+    /// the config has passed, giving the subscriber a chance to top up credit.
+    /// Returns the epoch at which the subscription will be deleted, or `None` if it's not
+    /// eligible for a grace period, in which case it should expire now.
+    fn mark_delinquent<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        subscriber: Address,
+        current_epoch: ChainEpoch,
+        key: &ExpiryKey,
+    ) -> anyhow::Result<Option<ChainEpoch>, ActorError> {
+        if config.blob_delinquency_grace_period <= 0 {
+            return Ok(None);
+        }
+        let mut blobs = self.blobs.hamt(store)?;
+        let mut blob = if let Some(blob) = blobs.get(&key.hash)? {
+            blob
+        } else {
+            return Ok(None);
+        };
+        let mut subscribers = blob.subscribers.hamt(store)?;
+        let mut group = if let Some(group) = subscribers.get(&subscriber)? {
+            group
+        } else {
+            return Ok(None);
+        };
+        let mut group_hamt = group.hamt(store)?;
+        let mut sub = match group_hamt.get(&key.id)? {
+            Some(sub) if sub.auto_renew && !sub.failed && !sub.is_delinquent() => sub,
+            _ => return Ok(None),
+        };
+        let delinquent_until =
+            i64::saturating_add(current_epoch, config.blob_delinquency_grace_period);
+        // Move the subscription to the end of the grace period in the expiry index
+        self.expiries.update_index(
+            store,
+            subscriber,
+            key.hash,
+            &key.id,
+            vec![
+                ExpiryUpdate::Add(delinquent_until),
+                ExpiryUpdate::Remove(sub.expiry),
+            ],
+        )?;
+        sub.delinquent_until = Some(delinquent_until);
+        group.save_tracked(group_hamt.set_and_flush_tracked(&key.id, sub)?);
+        blob.subscribers
+            .save_tracked(subscribers.set_and_flush_tracked(&subscriber, group)?);
+        self.blobs
+            .save_tracked(blobs.set_and_flush_tracked(&key.hash, blob)?);
+
+        debug!(
+            "subscription to blob {} for {} (id: {}) is delinquent until {}",
+            key.hash, subscriber, key.id, delinquent_until
+        );
+        Ok(Some(delinquent_until))
     }
 
     /// Add a blob.
@@ -657,6 +1745,152 @@ impl State {
         ttl: Option<ChainEpoch>,
         source: PublicKey,
         tokens_received: TokenAmount,
+        auto_renew: bool,
+        replication: u32,
+    ) -> anyhow::Result<(Subscription, TokenAmount), ActorError> {
+        if size > config.blob_max_size {
+            return Err(ActorError::illegal_argument(format!(
+                "blob size {} exceeds maximum allowed {}",
+                size, config.blob_max_size
+            )));
+        }
+        self.upsert_subscription(
+            config,
+            store,
+            origin,
+            subscriber,
+            current_epoch,
+            hash,
+            metadata_hash,
+            id,
+            size,
+            ttl,
+            source,
+            tokens_received,
+            auto_renew,
+            replication,
+            true,
+        )
+    }
+
+    /// Check that queueing the blob for resolution keeps the subscriber within the configured
+    /// limit on blobs waiting to be resolved.
+    /// Blobs in the added and pending queues count toward the limit, except the given blob.
+    fn ensure_pending_limit<BS: Blockstore>(
+        &self,
+        config: &RecallConfig,
+        store: &BS,
+        subscriber: &Address,
+        hash: &Hash,
+    ) -> anyhow::Result<(), ActorError> {
+        if config.blob_max_pending_per_account == 0 {
+            return Ok(());
+        }
+        let count = self.added.count_subscriber(store, subscriber, hash)?
+            + self.pending.count_subscriber(store, subscriber, hash)?;
+        if count >= config.blob_max_pending_per_account {
+            return Err(ActorError::forbidden(format!(
+                "account {} has reached its limit of {} blobs waiting to be resolved; please try again later",
+                subscriber, config.blob_max_pending_per_account
+            )));
+        }
+        Ok(())
+    }
+
+    /// Extends the expiry of an existing subscription.
+    ///
+    /// Unlike `add_blob`, this only adjusts expiry, committed credit, and the expiry index.
+    /// The blob status and the added and pending queues are left untouched, so a resolved blob
+    /// is not re-queued for resolution.
+    /// The new expiry must be later than the current expiry.
+    #[allow(clippy::too_many_arguments)]
+    pub fn extend_subscription<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        origin: Address,
+        subscriber: Address,
+        current_epoch: ChainEpoch,
+        hash: Hash,
+        id: SubscriptionId,
+        ttl: Option<ChainEpoch>,
+        tokens_received: TokenAmount,
+    ) -> anyhow::Result<(Subscription, TokenAmount), ActorError> {
+        let blob = self
+            .blobs
+            .hamt(store)?
+            .get(&hash)?
+            .ok_or(ActorError::not_found(format!("blob {} not found", hash)))?;
+        let sub = blob
+            .subscribers
+            .hamt(store)?
+            .get(&subscriber)?
+            .ok_or(ActorError::forbidden(format!(
+                "subscriber {} is not subscribed to blob {}",
+                subscriber, hash
+            )))?
+            .hamt(store)?
+            .get(&id)?
+            .ok_or(ActorError::not_found(format!(
+                "subscription id {} not found",
+                id.clone()
+            )))?;
+        if sub.failed || matches!(blob.status, BlobStatus::Failed) {
+            return Err(ActorError::forbidden(format!(
+                "cannot extend failed subscription to blob {} for {} (id: {})",
+                hash, subscriber, id
+            )));
+        }
+        let account = self.accounts.hamt(store)?.get_or_err(&subscriber)?;
+        let ttl = self.validate_ttl(config, ttl, &account)?;
+        let expiry = i64::saturating_add(current_epoch, ttl);
+        if expiry <= sub.expiry {
+            return Err(ActorError::illegal_argument(format!(
+                "new expiry {} must be later than current expiry {}",
+                expiry, sub.expiry
+            )));
+        }
+        self.upsert_subscription(
+            config,
+            store,
+            origin,
+            subscriber,
+            current_epoch,
+            hash,
+            blob.metadata_hash,
+            id,
+            blob.size,
+            Some(ttl),
+            sub.source,
+            tokens_received,
+            sub.auto_renew,
+            sub.replication,
+            false,
+        )
+    }
+
+    /// Adds or updates a subscription.
+    ///
+    /// If `reset_status` is true, a blob that is not yet resolved is reset to the added status
+    /// and re-queued with the given source.
+    #[allow(clippy::too_many_arguments)]
+    fn upsert_subscription<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        origin: Address,
+        subscriber: Address,
+        current_epoch: ChainEpoch,
+        hash: Hash,
+        metadata_hash: Hash,
+        id: SubscriptionId,
+        size: u64,
+        ttl: Option<ChainEpoch>,
+        source: PublicKey,
+        tokens_received: TokenAmount,
+        auto_renew: bool,
+        replication: u32,
+        reset_status: bool,
     ) -> anyhow::Result<(Subscription, TokenAmount), ActorError> {
         // Get or create a new account
         let mut accounts = self.accounts.hamt(store)?;
@@ -665,6 +1899,15 @@ impl State {
         })?;
         // Validate the TTL
         let ttl = self.validate_ttl(config, ttl, &account)?;
+        if replication == 0 || replication > MAX_BLOB_REPLICATION {
+            return Err(ActorError::illegal_argument(format!(
+                "replication must be between 1 and {}",
+                MAX_BLOB_REPLICATION
+            )));
+        }
+        // Used to record credit bought inline with tokens received in the account statement
+        let initial_credit_sold = self.credit_sold.clone();
+        let mut credit_returned = Credit::zero();
 
         let mut origin_approval =
             if origin != subscriber {
@@ -679,10 +1922,18 @@ impl State {
             } else {
                 None
             };
+        let group_budget_unused = if origin != subscriber {
+            sponsor_group_budget_unused(store, &account, &origin)?
+        } else {
+            None
+        };
 
         let delegation = origin_approval
             .as_mut()
-            .map(|(origin, approval)| CreditDelegation::new(*origin, approval));
+            .map(|(origin, approval)| CreditDelegation {
+                group_budget_unused,
+                ..CreditDelegation::new(*origin, approval)
+            });
 
         // Capacity updates and required credit depend on whether the subscriber is already
         // subscribing to this blob
@@ -695,6 +1946,9 @@ impl State {
         // Get or create a new blob
         let mut blobs = self.blobs.hamt(store)?;
         let (sub, blob) = if let Some(mut blob) = blobs.get(&hash)? {
+            if reset_status && !matches!(blob.status, BlobStatus::Resolved) {
+                self.ensure_pending_limit(config, store, &subscriber, &hash)?;
+            }
             let mut subscribers = blob.subscribers.hamt(store)?;
             let sub = if let Some(mut group) = subscribers.get(&subscriber)? {
                 let mut group_hamt = group.hamt(store)?;
@@ -717,6 +1971,7 @@ impl State {
                         self.credit_committed += &return_credits;
                         account.credit_committed += &return_credits;
                         debug!("returned {} credits to {}", return_credits, subscriber);
+                        credit_returned = return_credits;
                     }
                 }
                 // Ensure subscriber has enough credits, considering the subscription group may
@@ -731,7 +1986,7 @@ impl State {
                 tokens_unspent = ensure_credit_or_buy(
                     &mut account.credit_free,
                     &mut self.credit_sold,
-                    &credit_required,
+                    &(&credit_required + &replication_premium(&credit_required, replication)),
                     &config.token_credit_rate,
                     &tokens_received,
                     &subscriber,
@@ -740,20 +1995,27 @@ impl State {
                 )?;
                 let sub = if let Some(mut sub) = group_hamt.get(&id)? {
                     // Update expiry index
-                    if expiry != sub.expiry {
+                    if expiry != sub.expiry_index_epoch() {
                         self.expiries.update_index(
                             store,
                             subscriber,
                             hash,
                             &id,
-                            vec![ExpiryUpdate::Add(expiry), ExpiryUpdate::Remove(sub.expiry)],
+                            vec![
+                                ExpiryUpdate::Add(expiry),
+                                ExpiryUpdate::Remove(sub.expiry_index_epoch()),
+                            ],
                         )?;
                     }
                     sub.expiry = expiry;
+                    sub.delinquent_until = None;
                     // Overwrite source allows subscriber to retry resolving
                     sub.source = source;
                     sub.delegate = delegation.as_ref().map(|d| d.origin);
                     sub.failed = false;
+                    sub.auto_renew = auto_renew;
+                    sub.ttl = ttl;
+                    sub.replication = replication;
                     debug!(
                         "updated subscription to blob {} for {} (key: {})",
                         hash, subscriber, id
@@ -761,6 +2023,7 @@ impl State {
                     group.save_tracked(group_hamt.set_and_flush_tracked(&id, sub.clone())?);
                     sub
                 } else {
+                    ensure_add_rate_limit(config, &subscriber, &mut account, current_epoch, size)?;
                     // Add new subscription
                     let sub = Subscription {
                         added: current_epoch,
@@ -768,6 +2031,11 @@ impl State {
                         source,
                         delegate: delegation.as_ref().map(|d| d.origin),
                         failed: false,
+                        auto_renew,
+                        ttl,
+                        metadata: HashMap::new(),
+                        delinquent_until: None,
+                        replication,
                     };
                     group.save_tracked(group_hamt.set_and_flush_tracked(&id, sub.clone())?);
                     debug!(
@@ -791,6 +2059,8 @@ impl State {
                 sub
             } else {
                 new_account_capacity = size;
+                ensure_account_quota(&subscriber, &account, size)?;
+                ensure_add_rate_limit(config, &subscriber, &mut account, current_epoch, size)?;
                 // One or more accounts have already committed credit.
                 // However, we still need to reserve the full required credit from the new
                 // subscriber, as the existing account(s) may decide to change the expiry or cancel.
@@ -798,7 +2068,7 @@ impl State {
                 tokens_unspent = ensure_credit_or_buy(
                     &mut account.credit_free,
                     &mut self.credit_sold,
-                    &credit_required,
+                    &(&credit_required + &replication_premium(&credit_required, replication)),
                     &config.token_credit_rate,
                     &tokens_received,
                     &subscriber,
@@ -812,6 +2082,11 @@ impl State {
                     source,
                     delegate: delegation.as_ref().map(|d| d.origin),
                     failed: false,
+                    auto_renew,
+                    ttl,
+                    metadata: HashMap::new(),
+                    delinquent_until: None,
+                    replication,
                 };
 
                 let mut subscribers = blob.subscribers.hamt(store)?;
@@ -837,7 +2112,7 @@ impl State {
                 )?;
                 sub
             };
-            if !matches!(blob.status, BlobStatus::Resolved) {
+            if reset_status && !matches!(blob.status, BlobStatus::Resolved) {
                 // It's pending or failed, reset to added status
                 blob.status = BlobStatus::Added;
                 // Add to or update the source in the added queue
@@ -847,6 +2122,9 @@ impl State {
             (sub, blob)
         } else {
             new_account_capacity = size;
+            ensure_account_quota(&subscriber, &account, size)?;
+            ensure_add_rate_limit(config, &subscriber, &mut account, current_epoch, size)?;
+            self.ensure_pending_limit(config, store, &subscriber, &hash)?;
             // New blob increases network capacity as well.
             // Ensure there is enough capacity available.
             let available_capacity = self.capacity_available(config.blob_capacity);
@@ -861,7 +2139,7 @@ impl State {
             tokens_unspent = ensure_credit_or_buy(
                 &mut account.credit_free,
                 &mut self.credit_sold,
-                &credit_required,
+                &(&credit_required + &replication_premium(&credit_required, replication)),
                 &config.token_credit_rate,
                 &tokens_received,
                 &subscriber,
@@ -875,6 +2153,11 @@ impl State {
                 source,
                 delegate: delegation.as_ref().map(|d| d.origin),
                 failed: false,
+                auto_renew,
+                ttl,
+                metadata: HashMap::new(),
+                delinquent_until: None,
+                replication,
             };
 
             let blob_subscribers = BlobSubscribers::new(store)?;
@@ -931,6 +2214,49 @@ impl State {
         self.credit_committed += &credit_required;
         account.credit_committed += &credit_required;
         account.credit_free -= &credit_required;
+        // Additional replicas are charged upfront instead of being committed
+        let premium = replication_premium(&credit_required, replication);
+        self.credit_debited += &premium;
+        account.credit_free -= &premium;
+        // Record credit events
+        let credit_bought = &self.credit_sold - &initial_credit_sold;
+        let statement_events = [
+            (StatementEntryKind::Buy, credit_bought, None),
+            (StatementEntryKind::Refund, credit_returned, None),
+            (StatementEntryKind::Debit, debit, None),
+            (StatementEntryKind::Debit, premium.clone(), None),
+        ];
+        for (kind, amount, counterparty) in statement_events {
+            record_statement(
+                store,
+                config,
+                &mut account,
+                current_epoch,
+                kind,
+                &amount,
+                counterparty,
+            )?;
+        }
+        let (kind, amount, counterparty) = if credit_required.is_negative() {
+            (StatementEntryKind::Release, -credit_required.clone(), None)
+        } else if let Some(delegation) = &delegation {
+            (
+                StatementEntryKind::ApprovalUsed,
+                credit_required.clone(),
+                Some(delegation.origin),
+            )
+        } else {
+            (StatementEntryKind::Commit, credit_required.clone(), None)
+        };
+        record_statement(
+            store,
+            config,
+            &mut account,
+            current_epoch,
+            kind,
+            &amount,
+            counterparty,
+        )?;
         // Update credit approval
         if let Some(delegation) = delegation {
             let origin = delegation.origin;
@@ -944,8 +2270,9 @@ impl State {
                     subscriber, origin
                 )))?;
 
-            delegation.approval.credit_used += &credit_required;
-            origin_approval.credit_used += &credit_required;
+            let credit_used = &credit_required + &premium;
+            delegation.approval.credit_used += &credit_used;
+            origin_approval.credit_used += &credit_used;
 
             // Save delegation origin account
             origin_account.approvals_from.save_tracked(
@@ -995,44 +2322,290 @@ impl State {
         blobs.get(&hash)
     }
 
-    pub fn get_blob_status<BS: Blockstore>(
+    /// Returns a page of blob summaries in key order, starting from `start_key`,
+    /// along with the key to continue from, if there are more blobs.
+    ///
+    /// The page size defaults to [`ListBlobsParams::DEFAULT_LIMIT`] and is capped at
+    /// [`ListBlobsParams::MAX_LIMIT`], so a single call never walks the whole blob set.
+    pub fn list_blobs<BS: Blockstore>(
         &self,
         store: &BS,
-        subscriber: Address,
-        hash: Hash,
-        id: SubscriptionId,
-    ) -> anyhow::Result<Option<BlobStatus>, ActorError> {
-        let blob = if let Some(blob) = self
-            .blobs
-            .hamt(store)
-            .ok()
-            .and_then(|blobs| blobs.get(&hash).ok())
-            .flatten()
-        {
-            blob
-        } else {
-            return Ok(None);
-        };
-        let subscribers = blob.subscribers.hamt(store)?;
-        if subscribers.contains_key(&subscriber)? {
-            match blob.status {
-                BlobStatus::Added => Ok(Some(BlobStatus::Added)),
-                BlobStatus::Pending => Ok(Some(BlobStatus::Pending)),
-                BlobStatus::Resolved => Ok(Some(BlobStatus::Resolved)),
-                BlobStatus::Failed => {
-                    // The blob state's status may have been finalized as failed by another
-                    // subscription.
-                    // We need to see if this specific subscription failed.
-                    let group = subscribers.get(&subscriber)?.unwrap(); // safe here
-                    let group_hamt = group.hamt(store)?;
-                    if let Some(sub) = group_hamt.get(&id)? {
-                        if sub.failed {
-                            Ok(Some(BlobStatus::Failed))
-                        } else {
-                            Ok(Some(BlobStatus::Pending))
-                        }
-                    } else {
-                        Ok(None)
+        start_key: Option<Hash>,
+        limit: Option<u32>,
+    ) -> anyhow::Result<(Vec<BlobSummary>, Option<Hash>), ActorError> {
+        let limit = limit
+            .unwrap_or(ListBlobsParams::DEFAULT_LIMIT)
+            .min(ListBlobsParams::MAX_LIMIT);
+        let blobs = self.blobs.hamt(store)?;
+        let start_key = start_key.map(|h| BytesKey::from(h.0.as_slice()));
+        let mut summaries = Vec::new();
+        let (_, next_key) = blobs.for_each_ranged(
+            start_key.as_ref(),
+            Some(limit as usize),
+            |hash, blob| -> Result<bool, ActorError> {
+                summaries.push(BlobSummary {
+                    hash,
+                    size: blob.size,
+                    status: blob.status.clone(),
+                    num_subscribers: blob.subscribers.len(),
+                });
+                Ok(true)
+            },
+        )?;
+        Ok((summaries, next_key))
+    }
+
+    /// Returns the subscriptions created by `delegate` whose subscriber no longer approves it,
+    /// scanning a page of blobs starting from `start_key`, along with the key to continue from,
+    /// if there are more blobs.
+    pub fn get_orphaned_subscriptions<BS: Blockstore>(
+        &self,
+        store: &BS,
+        delegate: Address,
+        start_key: Option<Hash>,
+        limit: Option<u32>,
+    ) -> anyhow::Result<(Vec<OrphanedSubscription>, Option<Hash>), ActorError> {
+        let accounts = self.accounts.hamt(store)?;
+        let blobs = self.blobs.hamt(store)?;
+        let start_key = start_key.map(|h| BytesKey::from(h.0.as_slice()));
+        // Whether each subscriber still approves the delegate
+        let mut approved: HashMap<Address, bool> = HashMap::new();
+        let mut orphaned = Vec::new();
+        let (_, next_key) = blobs.for_each_ranged(
+            start_key.as_ref(),
+            limit.map(|l| l as usize),
+            |hash, blob| -> Result<bool, ActorError> {
+                blob.subscribers
+                    .hamt(store)?
+                    .for_each(|subscriber, group| {
+                        group.hamt(store)?.for_each(|id, sub| {
+                            if sub.delegate != Some(delegate) {
+                                return Ok(());
+                            }
+                            let is_approved = match approved.get(&subscriber) {
+                                Some(is_approved) => *is_approved,
+                                None => {
+                                    let is_approved = match accounts.get(&subscriber)? {
+                                        Some(account) => account
+                                            .approvals_to
+                                            .hamt(store)?
+                                            .contains_key(&delegate)?,
+                                        None => false,
+                                    };
+                                    approved.insert(subscriber, is_approved);
+                                    is_approved
+                                }
+                            };
+                            if !is_approved {
+                                orphaned.push(OrphanedSubscription {
+                                    subscriber,
+                                    hash,
+                                    id,
+                                });
+                            }
+                            Ok(())
+                        })
+                    })?;
+                Ok(true)
+            },
+        )?;
+        Ok((orphaned, next_key))
+    }
+
+    /// Returns a page of the subscriber groups of a blob, or `None` if the blob doesn't exist.
+    /// Each group lists all of the subscriber's subscriptions to the blob.
+    #[allow(clippy::type_complexity)]
+    pub fn get_blob_subscribers<BS: Blockstore>(
+        &self,
+        store: &BS,
+        hash: Hash,
+        start_key: Option<Address>,
+        limit: Option<u32>,
+    ) -> anyhow::Result<Option<(Vec<BlobSubscriberGroup>, Option<Address>)>, ActorError> {
+        let blob = if let Some(blob) = self.blobs.hamt(store)?.get(&hash)? {
+            blob
+        } else {
+            return Ok(None);
+        };
+        let start_key = start_key.map(|a| BytesKey::from(a.to_bytes()));
+        let mut groups = Vec::new();
+        let (_, next_key) = blob.subscribers.hamt(store)?.for_each_ranged(
+            start_key.as_ref(),
+            limit.map(|l| l as usize),
+            |subscriber, group| -> Result<bool, ActorError> {
+                let mut subscriptions = Vec::new();
+                group.hamt(store)?.for_each(|id, sub| {
+                    subscriptions.push(SubscriptionSummary {
+                        id,
+                        expiry: sub.expiry,
+                        source: sub.source,
+                        delegate: sub.delegate,
+                    });
+                    Ok(())
+                })?;
+                groups.push(BlobSubscriberGroup {
+                    subscriber,
+                    subscriptions,
+                });
+                Ok(true)
+            },
+        )?;
+        Ok(Some((groups, next_key)))
+    }
+
+    /// Returns the metadata of a blob subscription, or `None` if the subscription doesn't exist.
+    pub fn get_blob_metadata<BS: Blockstore>(
+        &self,
+        store: &BS,
+        subscriber: Address,
+        hash: Hash,
+        id: SubscriptionId,
+    ) -> anyhow::Result<Option<HashMap<String, String>>, ActorError> {
+        let blob = if let Some(blob) = self.blobs.hamt(store)?.get(&hash)? {
+            blob
+        } else {
+            return Ok(None);
+        };
+        let group = if let Some(group) = blob.subscribers.hamt(store)?.get(&subscriber)? {
+            group
+        } else {
+            return Ok(None);
+        };
+        let sub = group.hamt(store)?.get(&id)?;
+        Ok(sub.map(|sub| sub.metadata))
+    }
+
+    /// Inserts, updates, or deletes metadata entries on a blob subscription.
+    ///
+    /// Entries with a `None` value are deleted.
+    /// The origin must be the subscriber or the delegate that created the subscription.
+    /// Returns the updated metadata.
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_blob_metadata<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        origin: Address,
+        subscriber: Address,
+        current_epoch: ChainEpoch,
+        hash: Hash,
+        id: SubscriptionId,
+        metadata: HashMap<String, Option<String>>,
+    ) -> anyhow::Result<HashMap<String, String>, ActorError> {
+        validate_blob_metadata(&metadata)?;
+        let mut blobs = self.blobs.hamt(store)?;
+        let mut blob = blobs
+            .get(&hash)?
+            .ok_or(ActorError::not_found(format!("blob {} not found", hash)))?;
+        let mut subscribers = blob.subscribers.hamt(store)?;
+        let mut group = subscribers
+            .get(&subscriber)?
+            .ok_or(ActorError::forbidden(format!(
+                "subscriber {} is not subscribed to blob {}",
+                subscriber, hash
+            )))?;
+        let mut group_hamt = group.hamt(store)?;
+        let mut sub = group_hamt.get(&id)?.ok_or(ActorError::not_found(format!(
+            "subscription id {} not found",
+            id.clone()
+        )))?;
+        if origin != subscriber {
+            // The origin must be the delegate with a valid approval from subscriber
+            if sub.delegate != Some(origin) {
+                return Err(ActorError::forbidden(format!(
+                    "origin {} is not delegate origin or subscriber {} for blob {}",
+                    origin, subscriber, hash
+                )));
+            }
+            let account = self.accounts.hamt(store)?.get_or_err(&subscriber)?;
+            let approval =
+                account
+                    .approvals_to
+                    .hamt(store)?
+                    .get(&origin)?
+                    .ok_or(ActorError::forbidden(format!(
+                        "approval from {} to {} not found",
+                        subscriber, origin
+                    )))?;
+            if let Some(expiry) = approval.expiry {
+                if expiry <= current_epoch {
+                    return Err(ActorError::forbidden(format!(
+                        "approval from {} to {} expired",
+                        subscriber, origin
+                    )));
+                }
+            }
+        }
+
+        for (key, value) in metadata {
+            match value {
+                Some(value) => {
+                    sub.metadata.insert(key, value);
+                }
+                None => {
+                    sub.metadata.remove(&key);
+                }
+            }
+        }
+        if sub.metadata.len() as u32 > MAX_BLOB_METADATA_ENTRIES {
+            return Err(ActorError::illegal_argument(format!(
+                "the maximum metadata entries allowed is {}",
+                MAX_BLOB_METADATA_ENTRIES
+            )));
+        }
+
+        // Save subscription
+        group.save_tracked(group_hamt.set_and_flush_tracked(&id, sub.clone())?);
+        blob.subscribers
+            .save_tracked(subscribers.set_and_flush_tracked(&subscriber, group)?);
+        // Save blob
+        self.blobs
+            .save_tracked(blobs.set_and_flush_tracked(&hash, blob)?);
+
+        debug!(
+            "updated metadata of blob {} for {} (id: {})",
+            hash, subscriber, id
+        );
+        Ok(sub.metadata)
+    }
+
+    pub fn get_blob_status<BS: Blockstore>(
+        &self,
+        store: &BS,
+        subscriber: Address,
+        hash: Hash,
+        id: SubscriptionId,
+    ) -> anyhow::Result<Option<BlobStatus>, ActorError> {
+        let blob = if let Some(blob) = self
+            .blobs
+            .hamt(store)
+            .ok()
+            .and_then(|blobs| blobs.get(&hash).ok())
+            .flatten()
+        {
+            blob
+        } else {
+            return Ok(None);
+        };
+        let subscribers = blob.subscribers.hamt(store)?;
+        if subscribers.contains_key(&subscriber)? {
+            match blob.status {
+                BlobStatus::Added => Ok(Some(BlobStatus::Added)),
+                BlobStatus::Pending => Ok(Some(BlobStatus::Pending)),
+                BlobStatus::Resolved => Ok(Some(BlobStatus::Resolved)),
+                BlobStatus::Failed => {
+                    // The blob state's status may have been finalized as failed by another
+                    // subscription.
+                    // We need to see if this specific subscription failed.
+                    let group = subscribers.get(&subscriber)?.unwrap(); // safe here
+                    let group_hamt = group.hamt(store)?;
+                    if let Some(sub) = group_hamt.get(&id)? {
+                        if sub.failed {
+                            Ok(Some(BlobStatus::Failed))
+                        } else {
+                            Ok(Some(BlobStatus::Pending))
+                        }
+                    } else {
+                        Ok(None)
                     }
                 }
             }
@@ -1041,11 +2614,17 @@ impl State {
         }
     }
 
-    #[allow(clippy::type_complexity)]
-    pub fn get_added_blobs<BS: Blockstore>(&self, store: &BS, size: u32) -> BlobSourcesResult {
+    /// Returns a page of added blobs that have not yet been picked up by validators,
+    /// starting from `start_key`.
+    pub fn get_added_blobs<BS: Blockstore>(
+        &self,
+        store: &BS,
+        size: u32,
+        start_key: Option<Hash>,
+    ) -> anyhow::Result<GetAddedBlobsReturn, ActorError> {
         let blobs = self.blobs.hamt(store)?;
-        self.added
-            .take_page(store, size)?
+        let (page, next_key) = self.added.take_page(store, size, start_key)?;
+        let blobs = page
             .into_iter()
             .map(|(hash, sources)| {
                 let blob = blobs
@@ -1053,21 +2632,40 @@ impl State {
                     .ok_or_else(|| ActorError::not_found(format!("blob {} not found", hash)))?;
                 Ok((hash, blob.size, sources))
             })
-            .collect()
+            .collect::<anyhow::Result<Vec<BlobRequest>, ActorError>>()?;
+        Ok(GetAddedBlobsReturn { blobs, next_key })
     }
 
-    pub fn get_pending_blobs<BS: Blockstore>(&self, store: &BS, size: u32) -> BlobSourcesResult {
+    /// Returns a page of pending blobs along with the replication target for each blob,
+    /// which is the highest replication requested by any of its pending sources,
+    /// starting from `start_key`.
+    pub fn get_pending_blobs<BS: Blockstore>(
+        &self,
+        store: &BS,
+        size: u32,
+        start_key: Option<Hash>,
+    ) -> anyhow::Result<GetPendingBlobsReturn, ActorError> {
         let blobs = self.blobs.hamt(store)?;
-        self.pending
-            .take_page(store, size)?
+        let (page, next_key) = self.pending.take_page(store, size, start_key)?;
+        let blobs = page
             .into_iter()
             .map(|(hash, sources)| {
                 let blob = blobs
                     .get(&hash)?
                     .ok_or_else(|| ActorError::not_found(format!("blob {} not found", hash)))?;
-                Ok((hash, blob.size, sources))
+                let subscribers = blob.subscribers.hamt(store)?;
+                let mut replication = 1;
+                for (subscriber, id, _) in sources.iter() {
+                    if let Some(group) = subscribers.get(subscriber)? {
+                        if let Some(sub) = group.hamt(store)?.get(id)? {
+                            replication = replication.max(sub.replication);
+                        }
+                    }
+                }
+                Ok((hash, blob.size, replication, sources))
             })
-            .collect()
+            .collect::<anyhow::Result<Vec<PendingBlobRequest>, ActorError>>()?;
+        Ok(GetPendingBlobsReturn { blobs, next_key })
     }
 
     pub fn set_blob_pending<BS: Blockstore>(
@@ -1115,7 +2713,8 @@ impl State {
         hash: Hash,
         id: SubscriptionId,
         status: BlobStatus,
-    ) -> anyhow::Result<(), ActorError> {
+        resolved_size: Option<u64>,
+    ) -> anyhow::Result<BlobStatus, ActorError> {
         // Validate incoming status
         if matches!(status, BlobStatus::Added | BlobStatus::Pending) {
             return Err(ActorError::illegal_state(format!(
@@ -1134,7 +2733,7 @@ impl State {
             blob
         } else {
             // The blob may have been deleted before it was finalized
-            return Ok(());
+            return Ok(status);
         };
         if matches!(blob.status, BlobStatus::Added) {
             return Err(ActorError::illegal_state(format!(
@@ -1144,8 +2743,21 @@ impl State {
         } else if matches!(blob.status, BlobStatus::Resolved) {
             // Blob is already finalized as resolved.
             // We can ignore later finalizations, even if they are failed.
-            return Ok(());
+            return Ok(BlobStatus::Resolved);
         }
+        // The declared size is what subscribers were charged for. The blob size is shared by
+        // all subscribers, so a mismatch can't be charged to this subscriber alone; instead,
+        // the subscription is failed, which releases its committed credit.
+        let status = match resolved_size {
+            Some(size) if matches!(status, BlobStatus::Resolved) && size != blob.size => {
+                warn!(
+                    "resolved size {} of blob {} does not match declared size {}",
+                    size, hash, blob.size
+                );
+                BlobStatus::Failed
+            }
+            _ => status,
+        };
         let mut subscribers = blob.subscribers.hamt(store)?;
         let mut group = subscribers
             .get(&subscriber)?
@@ -1178,7 +2790,7 @@ impl State {
             .map(|(origin, approval)| CreditDelegation::new(*origin, approval));
 
         // Update blob status
-        blob.status = status;
+        blob.status = status.clone();
         debug!("finalized blob {} to status {}", hash, blob.status);
         if matches!(blob.status, BlobStatus::Failed) {
             // When failing, the existing group expiry will always contain a value.
@@ -1234,8 +2846,6 @@ impl State {
                 account.credit_free += &reclaim_credits;
                 // Update credit approval
                 if let Some(delegation) = delegation {
-                    delegation.approval.credit_used -= &reclaim_credits;
-
                     let origin = delegation.origin;
                     let mut origin_account = accounts.get_or_err(&origin)?;
                     let mut origin_approval = origin_account
@@ -1284,12 +2894,151 @@ impl State {
         self.blobs
             .save_tracked(blobs.set_and_flush_tracked(&hash, blob)?);
 
-        Ok(())
+        Ok(status)
+    }
+
+    /// Deletes all of a subscriber's subscriptions whose ID starts with `id_prefix`.
+    ///
+    /// At most `limit` blobs are scanned per call, starting from `start_key`, so large
+    /// deletions must be continued with the returned `next_key`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn delete_blobs_by_prefix<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        origin: Address,
+        subscriber: Address,
+        current_epoch: ChainEpoch,
+        id_prefix: &str,
+        start_key: Option<Hash>,
+        limit: Option<u32>,
+    ) -> anyhow::Result<BulkDeleteOutcome, ActorError> {
+        let limit = limit.map_or(config.blob_delete_batch_size, |l| {
+            (l as u64).min(config.blob_delete_batch_size)
+        });
+        let committed_before = self
+            .accounts
+            .hamt(store)?
+            .get_or_err(&subscriber)?
+            .credit_committed;
+
+        // Collect matching subscriptions first, since deleting mutates the blobs map
+        let blobs = self.blobs.hamt(store)?;
+        let start_key = start_key.map(|h| BytesKey::from(h.0.as_slice()));
+        let mut matches = Vec::new();
+        let (_, next_key) = blobs.for_each_ranged(
+            start_key.as_ref(),
+            Some(limit as usize),
+            |hash, blob| -> Result<bool, ActorError> {
+                if let Some(group) = blob.subscribers.hamt(store)?.get(&subscriber)? {
+                    group.hamt(store)?.for_each(|id, _| {
+                        if id.as_str().starts_with(id_prefix) {
+                            matches.push((hash, id));
+                        }
+                        Ok(())
+                    })?;
+                }
+                Ok(true)
+            },
+        )?;
+
+        let mut deleted = Vec::with_capacity(matches.len());
+        for (hash, id) in matches {
+            let initial_capacity_used = self.capacity_used;
+            let (delete_from_disc, size) = self.delete_blob(
+                config,
+                store,
+                origin,
+                subscriber,
+                current_epoch,
+                hash,
+                id.clone(),
+            )?;
+            deleted.push(DeletedSubscription {
+                hash,
+                id,
+                size,
+                bytes_released: initial_capacity_used - self.capacity_used,
+                delete_from_disc,
+            });
+        }
+
+        let committed_after = self
+            .accounts
+            .hamt(store)?
+            .get_or_err(&subscriber)?
+            .credit_committed;
+        debug!(
+            "deleted {} subscriptions with prefix '{}' for {}",
+            deleted.len(),
+            id_prefix,
+            subscriber
+        );
+        Ok(BulkDeleteOutcome {
+            deleted,
+            credit_refunded: committed_before - committed_after,
+            next_key,
+        })
+    }
+
+    /// Deletes all of a subscriber's subscriptions that were created by `delegate`.
+    ///
+    /// Used to terminate the subscriptions made under a revoked credit approval.
+    pub fn delete_delegated_subscriptions<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        subscriber: Address,
+        delegate: Address,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<Vec<DeletedSubscription>, ActorError> {
+        // Collect matching subscriptions first, since deleting mutates the blobs map
+        let mut matches = Vec::new();
+        self.blobs.hamt(store)?.for_each(|hash, blob| {
+            if let Some(group) = blob.subscribers.hamt(store)?.get(&subscriber)? {
+                group.hamt(store)?.for_each(|id, sub| {
+                    if sub.delegate == Some(delegate) {
+                        matches.push((hash, id));
+                    }
+                    Ok(())
+                })?;
+            }
+            Ok(())
+        })?;
+
+        let mut deleted = Vec::with_capacity(matches.len());
+        for (hash, id) in matches {
+            let initial_capacity_used = self.capacity_used;
+            let (delete_from_disc, size) = self.delete_blob(
+                config,
+                store,
+                subscriber,
+                subscriber,
+                current_epoch,
+                hash,
+                id.clone(),
+            )?;
+            deleted.push(DeletedSubscription {
+                hash,
+                id,
+                size,
+                bytes_released: initial_capacity_used - self.capacity_used,
+                delete_from_disc,
+            });
+        }
+        debug!(
+            "deleted {} subscriptions created by {} for {}",
+            deleted.len(),
+            delegate,
+            subscriber
+        );
+        Ok(deleted)
     }
 
     #[allow(clippy::too_many_arguments)]
     pub fn delete_blob<BS: Blockstore>(
         &mut self,
+        config: &RecallConfig,
         store: &BS,
         origin: Address,
         subscriber: Address,
@@ -1407,6 +3156,15 @@ impl State {
                 account.credit_committed -= &debit;
                 account.last_debit_epoch = debit_epoch;
                 debug!("debited {} credits from {}", debit, subscriber);
+                record_statement(
+                    store,
+                    config,
+                    &mut account,
+                    current_epoch,
+                    StatementEntryKind::Debit,
+                    &debit,
+                    None,
+                )?;
             } else if account.last_debit_epoch != debit_epoch {
                 // The account was debited after this blob's expiry
                 let return_credits = Credit::from_whole(
@@ -1417,6 +3175,15 @@ impl State {
                 self.credit_committed += &return_credits;
                 account.credit_committed += &return_credits;
                 debug!("returned {} credits to {}", return_credits, subscriber);
+                record_statement(
+                    store,
+                    config,
+                    &mut account,
+                    current_epoch,
+                    StatementEntryKind::Refund,
+                    &return_credits,
+                    None,
+                )?;
             }
         }
         // Account for reclaimed size and move committed credit to free credit
@@ -1446,10 +3213,17 @@ impl State {
                     self.credit_committed -= &reclaim_credits;
                     account.credit_committed -= &reclaim_credits;
                     account.credit_free += &reclaim_credits;
-                    // Update credit approval
-                    if let Some(delegation) = delegation {
-                        delegation.approval.credit_used -= &reclaim_credits;
-
+                    record_statement(
+                        store,
+                        config,
+                        &mut account,
+                        current_epoch,
+                        StatementEntryKind::Release,
+                        &reclaim_credits,
+                        None,
+                    )?;
+                    // Update credit approval
+                    if let Some(delegation) = delegation {
                         let origin = delegation.origin;
                         let mut origin_account = accounts.get_or_err(&origin)?;
                         let mut origin_approval = origin_account
@@ -1488,7 +3262,7 @@ impl State {
             subscriber,
             hash,
             &id,
-            vec![ExpiryUpdate::Remove(sub.expiry)],
+            vec![ExpiryUpdate::Remove(sub.expiry_index_epoch())],
         )?;
         // Remove the source from the added queue
         self.added
@@ -1526,7 +3300,343 @@ impl State {
         // Save accounts
         accounts.set(&subscriber, account)?;
         self.accounts.save_tracked(accounts.flush_tracked()?);
-        Ok((delete_blob, size))
+        // Data of trashed subscriptions is kept on disc until the trash period has passed
+        let delete_from_disc = delete_blob && !self.trash.hamt(store)?.contains_key(&hash)?;
+        Ok((delete_from_disc, size))
+    }
+
+    /// Moves a blob subscription to the trash.
+    ///
+    /// The subscription is deleted as with `delete_blob`, so it no longer uses capacity or
+    /// accrues charges. However, the blob data is kept on disc until the configured trash
+    /// period has passed, and the subscription can be restored with `undelete_blob` until then.
+    /// Returns the blob size, or zero if the blob does not exist.
+    #[allow(clippy::too_many_arguments)]
+    pub fn trash_blob<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        origin: Address,
+        subscriber: Address,
+        current_epoch: ChainEpoch,
+        hash: Hash,
+        id: SubscriptionId,
+    ) -> anyhow::Result<u64, ActorError> {
+        if config.blob_trash_period <= 0 {
+            return Err(ActorError::forbidden("blob trash is disabled".into()));
+        }
+        let blob = if let Some(blob) = self.blobs.hamt(store)?.get(&hash)? {
+            blob
+        } else {
+            return Ok(0);
+        };
+        let sub = match blob.subscribers.hamt(store)?.get(&subscriber)? {
+            Some(group) => group.hamt(store)?.get(&id)?,
+            None => None,
+        };
+        let (_, size) = self.delete_blob(
+            config,
+            store,
+            origin,
+            subscriber,
+            current_epoch,
+            hash,
+            id.clone(),
+        )?;
+        // The deletion fails if the subscription does not exist
+        let sub = sub.ok_or(ActorError::illegal_state(format!(
+            "subscription id {} not found",
+            id
+        )))?;
+
+        let trashed_until = i64::saturating_add(current_epoch, config.blob_trash_period);
+        let mut trash = self.trash.hamt(store)?;
+        let mut trashed = trash.get(&hash)?.unwrap_or(TrashedBlob {
+            size,
+            metadata_hash: blob.metadata_hash,
+            subscriptions: Vec::new(),
+        });
+        let mut updates = vec![ExpiryUpdate::Add(trashed_until)];
+        if let Some(previous) = trashed.take(&subscriber, &id) {
+            updates.push(ExpiryUpdate::Remove(previous.trashed_until));
+        }
+        trashed.subscriptions.push(TrashedSubscription {
+            subscriber,
+            id: id.clone(),
+            expiry: sub.expiry,
+            source: sub.source,
+            auto_renew: sub.auto_renew,
+            replication: sub.replication,
+            trashed_until,
+        });
+        self.trash
+            .save_tracked(trash.set_and_flush_tracked(&hash, trashed)?);
+        self.trash
+            .expiries
+            .update_index(store, subscriber, hash, &id, updates)?;
+
+        debug!(
+            "trashed subscription to blob {} for {} (id: {}) until {}",
+            hash, subscriber, id, trashed_until
+        );
+        Ok(size)
+    }
+
+    /// Restores a trashed blob subscription.
+    ///
+    /// The subscription is added back with its original expiry, or the minimum TTL if
+    /// less than that remains, and credit is committed for it as with `add_blob`.
+    /// If the blob was deleted while its subscription was in the trash, it's re-added
+    /// and resolved again from its original source.
+    #[allow(clippy::too_many_arguments)]
+    pub fn undelete_blob<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        origin: Address,
+        subscriber: Address,
+        current_epoch: ChainEpoch,
+        hash: Hash,
+        id: SubscriptionId,
+    ) -> anyhow::Result<Subscription, ActorError> {
+        let mut trash = self.trash.hamt(store)?;
+        let mut trashed = trash.get(&hash)?.ok_or(ActorError::not_found(format!(
+            "blob {} not found in trash",
+            hash
+        )))?;
+        let trashed_sub = trashed
+            .take(&subscriber, &id)
+            .ok_or(ActorError::not_found(format!(
+                "subscription id {} for {} not found in trash for blob {}",
+                id, subscriber, hash
+            )))?;
+        if trashed_sub.trashed_until <= current_epoch {
+            return Err(ActorError::forbidden(format!(
+                "trash period for subscription id {} to blob {} has passed",
+                id, hash
+            )));
+        }
+        if let Some(blob) = self.blobs.hamt(store)?.get(&hash)? {
+            if let Some(group) = blob.subscribers.hamt(store)?.get(&subscriber)? {
+                if group.hamt(store)?.contains_key(&id)? {
+                    return Err(ActorError::forbidden(format!(
+                        "subscription id {} to blob {} already exists",
+                        id, hash
+                    )));
+                }
+            }
+        }
+        // Remove the subscription from the trash
+        let size = trashed.size;
+        let metadata_hash = trashed.metadata_hash;
+        if trashed.subscriptions.is_empty() {
+            let (res, _) = trash.delete_and_flush_tracked(&hash)?;
+            self.trash.save_tracked(res);
+        } else {
+            self.trash
+                .save_tracked(trash.set_and_flush_tracked(&hash, trashed)?);
+        }
+        self.trash.expiries.update_index(
+            store,
+            subscriber,
+            hash,
+            &id,
+            vec![ExpiryUpdate::Remove(trashed_sub.trashed_until)],
+        )?;
+
+        let ttl = i64::max(trashed_sub.expiry - current_epoch, config.blob_min_ttl);
+        let (sub, _) = self.add_blob(
+            config,
+            store,
+            origin,
+            subscriber,
+            current_epoch,
+            hash,
+            metadata_hash,
+            id.clone(),
+            size,
+            Some(ttl),
+            trashed_sub.source,
+            TokenAmount::zero(),
+            trashed_sub.auto_renew,
+            trashed_sub.replication,
+        )?;
+        debug!(
+            "restored subscription to blob {} for {} (id: {}) until {}",
+            hash, subscriber, id, sub.expiry
+        );
+        Ok(sub)
+    }
+
+    /// Removes a trashed subscription whose trash period has passed.
+    ///
+    /// Returns whether the blob no longer has any trashed or active subscriptions,
+    /// in which case it should be deleted from disc.
+    fn purge_trashed_subscription<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        trashed_until: ChainEpoch,
+        subscriber: Address,
+        key: &ExpiryKey,
+    ) -> anyhow::Result<bool, ActorError> {
+        self.trash.expiries.update_index(
+            store,
+            subscriber,
+            key.hash,
+            &key.id,
+            vec![ExpiryUpdate::Remove(trashed_until)],
+        )?;
+        let mut trash = self.trash.hamt(store)?;
+        let mut trashed = if let Some(trashed) = trash.get(&key.hash)? {
+            trashed
+        } else {
+            return Ok(false);
+        };
+        trashed.take(&subscriber, &key.id);
+        if !trashed.subscriptions.is_empty() {
+            self.trash
+                .save_tracked(trash.set_and_flush_tracked(&key.hash, trashed)?);
+            return Ok(false);
+        }
+        let (res, _) = trash.delete_and_flush_tracked(&key.hash)?;
+        self.trash.save_tracked(res);
+        debug!(
+            "purged trashed subscription to blob {} for {} (id: {})",
+            key.hash, subscriber, key.id
+        );
+        // The blob may have been added again while it was in the trash
+        Ok(!self.blobs.hamt(store)?.contains_key(&key.hash)?)
+    }
+
+    /// Moves a resolved blob subscription from one account to another.
+    ///
+    /// The recipient must have a credit approval for `from`, which signals that it accepts
+    /// subscriptions from `from`. The recipient then owns the new subscription outright:
+    /// credit for the remaining TTL is committed on its own account, and the subscription has
+    /// no delegate, so revoking the approval later doesn't affect it.
+    /// The new subscription keeps the original expiry, source, ID, auto-renew setting,
+    /// and metadata.
+    /// The recipient's subscription is added before the sender's is deleted, so the blob is
+    /// never left without a subscriber.
+    /// Returns the recipient's new subscription.
+    #[allow(clippy::too_many_arguments)]
+    pub fn transfer_subscription<BS: Blockstore>(
+        &mut self,
+        config: &RecallConfig,
+        store: &BS,
+        from: Address,
+        to: Address,
+        current_epoch: ChainEpoch,
+        hash: Hash,
+        id: SubscriptionId,
+    ) -> anyhow::Result<Subscription, ActorError> {
+        if from == to {
+            return Err(ActorError::illegal_argument(
+                "'from' and 'to' addresses must be different".into(),
+            ));
+        }
+        let blob = self
+            .blobs
+            .hamt(store)?
+            .get(&hash)?
+            .ok_or(ActorError::not_found(format!("blob {} not found", hash)))?;
+        if !matches!(blob.status, BlobStatus::Resolved) {
+            return Err(ActorError::forbidden(format!(
+                "blob {} is not resolved; only resolved blobs can be transferred",
+                hash
+            )));
+        }
+        let subscribers = blob.subscribers.hamt(store)?;
+        let sub = subscribers
+            .get(&from)?
+            .ok_or(ActorError::forbidden(format!(
+                "subscriber {} is not subscribed to blob {}",
+                from, hash
+            )))?
+            .hamt(store)?
+            .get(&id)?
+            .ok_or(ActorError::not_found(format!(
+                "subscription id {} not found",
+                id.clone()
+            )))?;
+        if sub.failed {
+            return Err(ActorError::forbidden(format!(
+                "subscription to blob {} for {} (id: {}) failed",
+                hash, from, id
+            )));
+        }
+        if let Some(group) = subscribers.get(&to)? {
+            if group.hamt(store)?.contains_key(&id)? {
+                return Err(ActorError::illegal_argument(format!(
+                    "{} already has a subscription to blob {} (id: {})",
+                    to, hash, id
+                )));
+            }
+        }
+        let ttl = sub.expiry - current_epoch;
+        if ttl < config.blob_min_ttl {
+            return Err(ActorError::illegal_argument(format!(
+                "subscription expires too soon to transfer (minimum remaining TTL is {})",
+                config.blob_min_ttl
+            )));
+        }
+
+        let approved = self
+            .get_credit_approval(store, to, from)
+            .ok()
+            .flatten()
+            .is_some_and(|approval| {
+                approval
+                    .expiry
+                    .map_or(true, |expiry| expiry > current_epoch)
+            });
+        if !approved {
+            return Err(ActorError::forbidden(format!(
+                "{} has not approved transfers from {}",
+                to, from
+            )));
+        }
+
+        // Subscribe the recipient first with the same expiry
+        let (new_sub, _) = self.add_blob(
+            config,
+            store,
+            to,
+            to,
+            current_epoch,
+            hash,
+            blob.metadata_hash,
+            id.clone(),
+            blob.size,
+            Some(ttl),
+            sub.source,
+            TokenAmount::zero(),
+            sub.auto_renew,
+            sub.replication,
+        )?;
+        let new_sub = if sub.metadata.is_empty() {
+            new_sub
+        } else {
+            let metadata = sub
+                .metadata
+                .into_iter()
+                .map(|(key, value)| (key, Some(value)))
+                .collect();
+            let metadata =
+                self.set_blob_metadata(store, to, to, current_epoch, hash, id.clone(), metadata)?;
+            Subscription {
+                metadata,
+                ..new_sub
+            }
+        };
+        // Then release the sender's subscription
+        self.delete_blob(config, store, from, from, current_epoch, hash, id.clone())?;
+
+        debug!(
+            "transferred subscription to blob {} from {} to {} (id: {})",
+            hash, from, to, id
+        );
+        Ok(new_sub)
     }
 
     /// Return available capacity as a difference between `blob_capacity_total` and `capacity_used`.
@@ -1584,6 +3694,7 @@ impl State {
                             if new_ttl == 0 {
                                 // Delete subscription
                                 let (from_disc, _) = self.delete_blob(
+                                    config,
                                     store,
                                     subscriber,
                                     subscriber,
@@ -1608,6 +3719,8 @@ impl State {
                                     Some(new_ttl),
                                     sub.source,
                                     TokenAmount::zero(),
+                                    sub.auto_renew,
+                                    sub.replication,
                                 )?;
                             }
                             processed += 1;
@@ -1644,6 +3757,11 @@ impl State {
                 "minimum blob TTL is {}",
                 config.blob_min_ttl
             )));
+        } else if ttl > config.blob_max_ttl {
+            return Err(ActorError::forbidden(format!(
+                "attempt to add a blob with TTL ({}) that exceeds the maximum allowed TTL ({})",
+                ttl, config.blob_max_ttl,
+            )));
         } else if ttl > account.max_ttl {
             return Err(ActorError::forbidden(format!(
                 "attempt to add a blob with TTL ({}) that exceeds account's max allowed TTL ({})",
@@ -1654,6 +3772,28 @@ impl State {
     }
 }
 
+/// Returns a page of credit approvals starting from `start_key`, along with the key to
+/// continue from, if there are more approvals.
+fn list_credit_approvals<BS: Blockstore>(
+    store: &BS,
+    approvals: &CreditApprovals,
+    start_key: Option<Address>,
+    limit: Option<u32>,
+) -> anyhow::Result<CreditApprovalsPage, ActorError> {
+    let approvals = approvals.hamt(store)?;
+    let start_key = start_key.map(|address| BytesKey::from(address.to_bytes()));
+    let mut page = Vec::new();
+    let (_, next_key) = approvals.for_each_ranged(
+        start_key.as_ref(),
+        limit.map(|l| l as usize),
+        |address, approval| -> Result<bool, ActorError> {
+            page.push((address, approval.clone()));
+            Ok(true)
+        },
+    )?;
+    Ok((page, next_key))
+}
+
 /// Check if `subscriber` has enough credits, including delegated credits.
 fn ensure_credit(
     subscriber: &Address,
@@ -1682,6 +3822,123 @@ fn ensure_enough_credits(
     }
 }
 
+/// Check if `subscriber` can use `size` more bytes without exceeding its storage quota.
+fn ensure_account_quota(
+    subscriber: &Address,
+    account: &Account,
+    size: u64,
+) -> anyhow::Result<(), ActorError> {
+    if let Some(max_capacity) = account.max_capacity {
+        let required = account.capacity_used.saturating_add(size);
+        if required > max_capacity {
+            return Err(ActorError::forbidden(format!(
+                "account {} would exceed its storage quota (quota: {}; required: {})",
+                subscriber, max_capacity, required
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the credit charged on top of the base storage cost for additional replicas.
+/// Each replica beyond the first is priced at the base credit rate.
+fn replication_premium(credit_required: &Credit, replication: u32) -> Credit {
+    if replication <= 1 || !credit_required.is_positive() {
+        return Credit::zero();
+    }
+    Credit::from_atto(credit_required.atto() * (replication - 1))
+}
+
+/// Check that a new subscription keeps the account within the configured limits on blob
+/// additions per credit debit interval, and count it toward the current window.
+fn ensure_add_rate_limit(
+    config: &RecallConfig,
+    subscriber: &Address,
+    account: &mut Account,
+    current_epoch: ChainEpoch,
+    size: u64,
+) -> anyhow::Result<(), ActorError> {
+    if config.blob_add_rate_limit_count == 0 && config.blob_add_rate_limit_bytes == 0 {
+        return Ok(());
+    }
+    // Start a new window once the debit interval has passed
+    if current_epoch
+        >= account
+            .add_window_start
+            .saturating_add(config.blob_credit_debit_interval)
+    {
+        account.add_window_start = current_epoch;
+        account.add_window_count = 0;
+        account.add_window_bytes = 0;
+    }
+    let count = account.add_window_count.saturating_add(1);
+    if config.blob_add_rate_limit_count > 0 && count > config.blob_add_rate_limit_count {
+        return Err(ActorError::forbidden(format!(
+            "account {} exceeded its limit of {} new blobs per {} epochs; please try again later",
+            subscriber, config.blob_add_rate_limit_count, config.blob_credit_debit_interval
+        )));
+    }
+    let bytes = account.add_window_bytes.saturating_add(size);
+    if config.blob_add_rate_limit_bytes > 0 && bytes > config.blob_add_rate_limit_bytes {
+        return Err(ActorError::forbidden(format!(
+            "account {} exceeded its limit of {} new blob bytes per {} epochs (required: {}); please try again later",
+            subscriber, config.blob_add_rate_limit_bytes, config.blob_credit_debit_interval, bytes
+        )));
+    }
+    account.add_window_count = count;
+    account.add_window_bytes = bytes;
+    Ok(())
+}
+
+/// Check that metadata keys and values are within the allowed sizes.
+/// Values are optional, since a missing value deletes the entry.
+fn validate_blob_metadata(
+    metadata: &HashMap<String, Option<String>>,
+) -> anyhow::Result<(), ActorError> {
+    for (key, value) in metadata {
+        if key.is_empty() || key.len() as u32 > MAX_BLOB_METADATA_KEY_SIZE {
+            return Err(ActorError::illegal_argument(format!(
+                "metadata key must be non-empty and less than or equal to {}",
+                MAX_BLOB_METADATA_KEY_SIZE
+            )));
+        }
+        if let Some(value) = value {
+            if value.is_empty() || value.len() as u32 > MAX_BLOB_METADATA_VALUE_SIZE {
+                return Err(ActorError::illegal_argument(format!(
+                    "metadata value must be non-empty and less than or equal to {}",
+                    MAX_BLOB_METADATA_VALUE_SIZE
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Records a credit event in the account's statement.
+/// Zero amounts are not recorded.
+fn record_statement<BS: Blockstore>(
+    store: &BS,
+    config: &RecallConfig,
+    account: &mut Account,
+    epoch: ChainEpoch,
+    kind: StatementEntryKind,
+    amount: &Credit,
+    counterparty: Option<Address>,
+) -> anyhow::Result<(), ActorError> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    let entry = StatementEntry {
+        epoch,
+        kind,
+        amount: amount.clone(),
+        counterparty,
+    };
+    account
+        .statement
+        .push(store, entry, config.account_statement_length)
+}
+
 #[allow(clippy::too_many_arguments)]
 fn ensure_credit_or_buy(
     account_credit_free: &mut Credit,
@@ -1761,6 +4018,15 @@ fn ensure_delegated_credit(
             }
         }
 
+        if let Some(unused) = &delegation.group_budget_unused {
+            if unused < credit_required {
+                return Err(ActorError::insufficient_funds(format!(
+                    "sponsor group of {} has insufficient credit for {} (available: {}; required: {})",
+                    subscriber, delegation.origin, unused, credit_required
+                )));
+            }
+        }
+
         if let Some(expiry) = delegation.approval.expiry {
             if expiry <= current_epoch {
                 return Err(ActorError::forbidden(format!(
@@ -1773,6 +4039,35 @@ fn ensure_delegated_credit(
     Ok(())
 }
 
+/// Returns the credit remaining in the budget of the sponsor group, if any, that `origin`
+/// belongs to.
+fn sponsor_group_budget_unused<BS: Blockstore>(
+    store: &BS,
+    account: &Account,
+    origin: &Address,
+) -> anyhow::Result<Option<Credit>, ActorError> {
+    let group = match account
+        .sponsor_groups
+        .values()
+        .find(|group| group.members.contains(origin))
+    {
+        Some(group) => group,
+        None => return Ok(None),
+    };
+    let budget = match &group.budget {
+        Some(budget) => budget,
+        None => return Ok(None),
+    };
+    let approvals = account.approvals_to.hamt(store)?;
+    let mut credit_used = Credit::zero();
+    for member in &group.members {
+        if let Some(approval) = approvals.get(member)? {
+            credit_used += &approval.credit_used;
+        }
+    }
+    Ok(Some(budget - &credit_used))
+}
+
 fn ensure_gas_limit(
     subscriber: &Address,
     current_epoch: ChainEpoch,
@@ -1801,66 +4096,6 @@ fn ensure_gas_limit(
     Ok(())
 }
 
-/// The return type used for Account.
-#[derive(Debug, Serialize_tuple, Deserialize_tuple)]
-pub struct AccountInfo {
-    /// Total size of all blobs managed by the account.
-    pub capacity_used: u64,
-    /// Current free credit in byte-blocks that can be used for new commitments.
-    pub credit_free: Credit,
-    /// Current committed credit in byte-blocks that will be used for debits.
-    pub credit_committed: Credit,
-    /// Optional default sponsor account address.
-    pub credit_sponsor: Option<Address>,
-    /// The chain epoch of the last debit.
-    pub last_debit_epoch: ChainEpoch,
-    /// Credit approvals to other accounts from this account, keyed by receiver.
-    pub approvals_to: HashMap<Address, CreditApproval>,
-    /// Credit approvals to this account from other accounts, keyed by sender.
-    pub approvals_from: HashMap<Address, CreditApproval>,
-    /// The maximum allowed TTL for actor's blobs.
-    pub max_ttl: ChainEpoch,
-    /// The total token value an account has used to buy credits.
-    pub gas_allowance: TokenAmount,
-}
-
-impl AccountInfo {
-    pub fn from(rt: &impl Runtime, account: Account) -> Result<Self, ActorError> {
-        let store = rt.store();
-        let mut approvals_to = HashMap::new();
-        account
-            .approvals_to
-            .hamt(store)?
-            .for_each(|address, approval| {
-                let external_account_address = to_delegated_address(rt, address)?;
-                approvals_to.insert(external_account_address, approval.clone());
-                Ok(())
-            })?;
-
-        let mut approvals_from = HashMap::new();
-        account
-            .approvals_from
-            .hamt(store)?
-            .for_each(|address, approval| {
-                let external_account_address = to_delegated_address(rt, address)?;
-                approvals_from.insert(external_account_address, approval.clone());
-                Ok(())
-            })?;
-
-        Ok(AccountInfo {
-            capacity_used: account.capacity_used,
-            credit_free: account.credit_free,
-            credit_committed: account.credit_committed,
-            credit_sponsor: account.credit_sponsor,
-            last_debit_epoch: account.last_debit_epoch,
-            approvals_to,
-            approvals_from,
-            max_ttl: account.max_ttl,
-            gas_allowance: account.gas_allowance,
-        })
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1991,20 +4226,396 @@ mod tests {
     }
 
     #[test]
-    fn test_approve_credit_success() {
+    fn test_withdraw_credit_success() {
         setup_logs();
+        let config = RecallConfig::default();
         let store = MemoryBlockstore::default();
         let mut state = State::new(&store).unwrap();
         let from = new_address();
-        let to = new_address();
-        let current_epoch = 1;
+        let amount = TokenAmount::from_whole(2);
+        state
+            .buy_credit(&config, &store, from, amount.clone(), 1)
+            .unwrap();
 
+        // Withdraw half of the credit
+        let credits = TokenAmount::from_whole(1) * &config.token_credit_rate;
+        let (account, tokens) = state
+            .withdraw_credit(&config, &store, from, Some(credits.clone()), 2)
+            .unwrap();
+        assert_eq!(tokens, TokenAmount::from_whole(1));
+        assert_eq!(account.credit_free, credits);
+        assert_eq!(account.gas_allowance, TokenAmount::from_whole(1));
+        assert_eq!(state.credit_sold, credits);
+
+        // Withdraw the rest
+        let (account, tokens) = state
+            .withdraw_credit(&config, &store, from, None, 2)
+            .unwrap();
+        assert_eq!(tokens, TokenAmount::from_whole(1));
+        assert_eq!(account.credit_free, Credit::zero());
+        assert_eq!(account.gas_allowance, TokenAmount::zero());
+        assert_eq!(state.credit_sold, Credit::zero());
+        let account_back = state.get_account(&store, from).unwrap().unwrap();
+        assert_eq!(account, account_back);
+    }
+
+    #[test]
+    fn test_withdraw_credit_insufficient() {
+        setup_logs();
         let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let from = new_address();
+        let to = new_address();
+        state
+            .buy_credit(&config, &store, from, TokenAmount::from_whole(1), 1)
+            .unwrap();
 
-        // No limit or expiry
-        let res = state.approve_credit(&config, &store, from, to, current_epoch, None, None, None);
-        assert!(res.is_ok());
-        let approval = res.unwrap();
+        // Cannot withdraw more than free credit
+        let credits = TokenAmount::from_whole(2) * &config.token_credit_rate;
+        let res = state.withdraw_credit(&config, &store, from, Some(credits), 2);
+        assert!(res.is_err());
+
+        // Cannot withdraw less than one atto token's worth of credit
+        let res = state.withdraw_credit(&config, &store, from, Some(Credit::from_atto(1)), 2);
+        assert!(res.is_err());
+
+        // Transferred credit is not backed by the recipient's tokens
+        state
+            .transfer_credit(&config, &store, from, to, None, 1)
+            .unwrap();
+        let res = state.withdraw_credit(&config, &store, to, None, 2);
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap().exit_code(),
+            fvm_shared::error::ExitCode::USR_INSUFFICIENT_FUNDS
+        );
+    }
+
+    #[test]
+    fn test_close_account() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let from = new_address();
+        let to = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(
+                &config,
+                &store,
+                from,
+                TokenAmount::from_whole(2),
+                current_epoch,
+            )
+            .unwrap();
+
+        // Cannot close an account with an active subscription
+        let (hash, size) = new_hash(1024);
+        let id = SubscriptionId::default();
+        state
+            .add_blob(
+                &config,
+                &store,
+                from,
+                from,
+                current_epoch,
+                hash,
+                new_metadata_hash(),
+                id.clone(),
+                size,
+                None,
+                new_pk(),
+                TokenAmount::zero(),
+                false,
+                1,
+            )
+            .unwrap();
+        let res = state.close_account(&config, &store, from);
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap().exit_code(),
+            fvm_shared::error::ExitCode::USR_FORBIDDEN
+        );
+        state
+            .delete_blob(&config, &store, from, from, current_epoch, hash, id)
+            .unwrap();
+
+        // Cannot close an account with outstanding approvals
+        state
+            .approve_credit(
+                &config,
+                &store,
+                from,
+                to,
+                current_epoch,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        let res = state.close_account(&config, &store, from);
+        assert!(res.is_err());
+        state.revoke_credit(&store, from, to).unwrap();
+
+        // Free credit is refunded as tokens and the account is removed
+        let account = state.get_account(&store, from).unwrap().unwrap();
+        assert_eq!(account.capacity_used, 0);
+        assert!(account.credit_committed.is_zero());
+        let tokens = state.close_account(&config, &store, from).unwrap();
+        assert_eq!(tokens, &account.credit_free / &config.token_credit_rate);
+        assert!(tokens.is_positive());
+        assert!(state.get_account(&store, from).unwrap().is_none());
+        assert_eq!(state.credit_sold, Credit::zero());
+
+        // Closing a missing account fails
+        assert!(state.close_account(&config, &store, from).is_err());
+    }
+
+    #[test]
+    fn test_transfer_credit_success() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let from = new_address();
+        let to = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(&config, &store, from, TokenAmount::from_whole(1), 1)
+            .unwrap();
+        let credit_sold = state.credit_sold.clone();
+
+        // Transfer a specific amount
+        let amount = Credit::from_whole(100);
+        let (account, transferred) = state
+            .transfer_credit(
+                &config,
+                &store,
+                from,
+                to,
+                Some(amount.clone()),
+                current_epoch,
+            )
+            .unwrap();
+        assert_eq!(transferred, amount);
+        assert_eq!(account.credit_free, &credit_sold - &amount);
+        let to_account = state.get_account(&store, to).unwrap().unwrap();
+        assert_eq!(to_account.credit_free, amount);
+        assert_eq!(to_account.gas_allowance, TokenAmount::zero());
+
+        // Transfer the remaining free credit
+        let (account, transferred) = state
+            .transfer_credit(&config, &store, from, to, None, current_epoch)
+            .unwrap();
+        assert_eq!(transferred, &credit_sold - &amount);
+        assert_eq!(account.credit_free, Credit::zero());
+        let to_account = state.get_account(&store, to).unwrap().unwrap();
+        assert_eq!(to_account.credit_free, credit_sold);
+
+        // Credit is moved between accounts, not sold
+        assert_eq!(state.credit_sold, credit_sold);
+        assert_eq!(state.accounts.len(), 2);
+    }
+
+    #[test]
+    fn test_transfer_credit_does_not_move_committed_credit() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let from = new_address();
+        let to = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(
+                &config,
+                &store,
+                from,
+                TokenAmount::from_atto(config.blob_min_ttl as u64 * 1024),
+                current_epoch,
+            )
+            .unwrap();
+        let (hash, size) = new_hash(1024);
+        state
+            .add_blob(
+                &config,
+                &store,
+                from,
+                from,
+                current_epoch,
+                hash,
+                new_metadata_hash(),
+                SubscriptionId::default(),
+                size,
+                Some(config.blob_min_ttl),
+                new_pk(),
+                TokenAmount::zero(),
+                false,
+                1,
+            )
+            .unwrap();
+        let account = state.get_account(&store, from).unwrap().unwrap();
+        assert_eq!(account.credit_free, Credit::zero());
+        assert!(account.credit_committed.is_positive());
+
+        // Nothing is free, so there's nothing to transfer
+        let res = state.transfer_credit(&config, &store, from, to, None, current_epoch);
+        assert!(res.is_err());
+        let res = state.transfer_credit(
+            &config,
+            &store,
+            from,
+            to,
+            Some(Credit::from_whole(1)),
+            current_epoch,
+        );
+        assert_eq!(
+            res.err().unwrap().exit_code(),
+            fvm_shared::error::ExitCode::USR_INSUFFICIENT_FUNDS
+        );
+        assert!(state.get_account(&store, to).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_account_statement_records_credit_events() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let from = new_address();
+        let to = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        let amount = TokenAmount::from_whole(1);
+        state
+            .buy_credit(&config, &store, from, amount.clone(), current_epoch)
+            .unwrap();
+        let (hash, size) = new_hash(1024);
+        state
+            .add_blob(
+                &config,
+                &store,
+                from,
+                from,
+                current_epoch,
+                hash,
+                new_metadata_hash(),
+                SubscriptionId::default(),
+                size,
+                Some(config.blob_min_ttl),
+                new_pk(),
+                TokenAmount::zero(),
+                false,
+                1,
+            )
+            .unwrap();
+        let transferred = Credit::from_whole(100);
+        state
+            .transfer_credit(
+                &config,
+                &store,
+                from,
+                to,
+                Some(transferred.clone()),
+                current_epoch + 1,
+            )
+            .unwrap();
+
+        // Nothing is recorded for accounts that don't exist
+        let res = state
+            .get_account_statement(&store, new_address(), None, None)
+            .unwrap();
+        assert!(res.is_none());
+
+        // First page
+        let (entries, next_seq) = state
+            .get_account_statement(&store, from, None, Some(2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].0, 0);
+        assert_eq!(entries[0].1.kind, StatementEntryKind::Buy);
+        assert_eq!(entries[0].1.amount, amount * &config.token_credit_rate);
+        assert_eq!(entries[1].1.kind, StatementEntryKind::Commit);
+        assert_eq!(
+            entries[1].1.amount,
+            Credit::from_whole(config.blob_min_ttl as u64 * size)
+        );
+        assert_eq!(next_seq, Some(2));
+
+        // Second page
+        let (entries, next_seq) = state
+            .get_account_statement(&store, from, next_seq, Some(2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, 2);
+        assert_eq!(entries[0].1.epoch, current_epoch + 1);
+        assert_eq!(entries[0].1.kind, StatementEntryKind::TransferOut);
+        assert_eq!(entries[0].1.amount, transferred);
+        assert_eq!(entries[0].1.counterparty, Some(to));
+        assert_eq!(next_seq, None);
+
+        // The recipient records the other side of the transfer
+        let (entries, _) = state
+            .get_account_statement(&store, to, None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].1.kind, StatementEntryKind::TransferIn);
+        assert_eq!(entries[0].1.counterparty, Some(from));
+    }
+
+    #[test]
+    fn test_transfer_credit_invalid_params() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let from = new_address();
+        let to = new_address();
+        state
+            .buy_credit(&config, &store, from, TokenAmount::from_whole(1), 1)
+            .unwrap();
+
+        // Transfers to self are not allowed
+        let res = state.transfer_credit(&config, &store, from, from, None, 1);
+        assert!(res.is_err());
+        // Amounts must be positive
+        let res = state.transfer_credit(&config, &store, from, to, Some(Credit::from_whole(-1)), 1);
+        assert!(res.is_err());
+        // Unknown senders have no credit
+        let res = state.transfer_credit(&config, &store, to, from, None, 1);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_approve_credit_success() {
+        setup_logs();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let from = new_address();
+        let to = new_address();
+        let current_epoch = 1;
+
+        let config = RecallConfig::default();
+
+        // No limit or expiry
+        let res = state.approve_credit(
+            &config,
+            &store,
+            from,
+            to,
+            current_epoch,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert!(res.is_ok());
+        let approval = res.unwrap();
         assert_eq!(approval.credit_limit, None);
         assert_eq!(approval.gas_fee_limit, None);
         assert_eq!(approval.expiry, None);
@@ -2021,6 +4632,7 @@ mod tests {
             Some(Credit::from_whole(limit)),
             None,
             None,
+            None,
         );
         assert!(res.is_ok());
         let approval = res.unwrap();
@@ -2040,6 +4652,7 @@ mod tests {
             None,
             Some(TokenAmount::from_atto(limit)),
             None,
+            None,
         );
         assert!(res.is_ok());
         let approval = res.unwrap();
@@ -2059,6 +4672,7 @@ mod tests {
             Some(Credit::from_whole(limit)),
             None,
             Some(ttl),
+            None,
         );
         assert!(res.is_ok());
         let approval = res.unwrap();
@@ -2088,6 +4702,7 @@ mod tests {
             None,
             None,
             Some(ttl),
+            None,
         );
         assert!(res.is_err());
         assert_eq!(
@@ -2116,6 +4731,7 @@ mod tests {
             None,
             None,
             Some(ChainEpoch::MAX),
+            None,
         );
         assert!(res.is_ok());
         let approval = res.unwrap();
@@ -2136,7 +4752,17 @@ mod tests {
         state
             .buy_credit(&config, &store, from, amount.clone(), current_epoch)
             .unwrap();
-        let res = state.approve_credit(&config, &store, from, to, current_epoch, None, None, None);
+        let res = state.approve_credit(
+            &config,
+            &store,
+            from,
+            to,
+            current_epoch,
+            None,
+            None,
+            None,
+            None,
+        );
         assert!(res.is_ok());
 
         // Add a blob
@@ -2154,6 +4780,8 @@ mod tests {
             None,
             new_pk(),
             TokenAmount::zero(),
+            false,
+            1,
         );
         assert!(res.is_ok());
 
@@ -2179,6 +4807,7 @@ mod tests {
             Some(Credit::from_whole(limit)),
             None,
             None,
+            None,
         );
         assert!(res.is_err());
         assert_eq!(
@@ -2191,19 +4820,112 @@ mod tests {
     }
 
     #[test]
-    fn test_revoke_credit_success() {
+    fn test_get_credit_approvals_paginated() {
         setup_logs();
+        let config = RecallConfig::default();
         let store = MemoryBlockstore::default();
         let mut state = State::new(&store).unwrap();
-        let from = new_address();
-        let to = new_address();
+        let account = new_address();
         let current_epoch = 1;
 
-        let config = RecallConfig::default();
-        let res = state.approve_credit(&config, &store, from, to, current_epoch, None, None, None);
-        assert!(res.is_ok());
+        // Grant approvals to three accounts and receive one from another
+        let mut receivers = HashSet::new();
+        for _ in 0..3 {
+            let to = new_address();
+            state
+                .approve_credit(
+                    &config,
+                    &store,
+                    account,
+                    to,
+                    current_epoch,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap();
+            receivers.insert(to);
+        }
+        let sender = new_address();
+        state
+            .approve_credit(
+                &config,
+                &store,
+                sender,
+                account,
+                current_epoch,
+                Some(Credit::from_whole(100)),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
 
-        // Check the account approvals
+        // Page through granted approvals
+        let mut granted = Vec::new();
+        let mut start_key = None;
+        loop {
+            let ((page, next_key), (received, _)) = state
+                .get_credit_approvals(&store, account, start_key, None, Some(2))
+                .unwrap()
+                .unwrap();
+            assert!(page.len() <= 2);
+            assert_eq!(received.len(), 1);
+            granted.extend(page);
+            if next_key.is_none() {
+                break;
+            }
+            start_key = next_key;
+        }
+        assert_eq!(granted.len(), 3);
+        for (to, approval) in granted {
+            assert!(receivers.contains(&to));
+            check_approvals_match(&state, &store, account, to, approval);
+        }
+
+        // Check the received approval
+        let (_, (received, next_key)) = state
+            .get_credit_approvals(&store, account, None, None, None)
+            .unwrap()
+            .unwrap();
+        assert!(next_key.is_none());
+        assert_eq!(received.len(), 1);
+        let (from, approval) = received[0].clone();
+        assert_eq!(from, sender);
+        assert_eq!(approval.credit_limit, Some(Credit::from_whole(100)));
+
+        // Unknown accounts have no approvals
+        let res = state
+            .get_credit_approvals(&store, new_address(), None, None, None)
+            .unwrap();
+        assert!(res.is_none());
+    }
+
+    #[test]
+    fn test_revoke_credit_success() {
+        setup_logs();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let from = new_address();
+        let to = new_address();
+        let current_epoch = 1;
+
+        let config = RecallConfig::default();
+        let res = state.approve_credit(
+            &config,
+            &store,
+            from,
+            to,
+            current_epoch,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert!(res.is_ok());
+
+        // Check the account approvals
         let from_account = state.get_account(&store, from).unwrap().unwrap();
         assert_eq!(from_account.approvals_to.len(), 1);
         let to_account = state.get_account(&store, to).unwrap().unwrap();
@@ -2218,6 +4940,84 @@ mod tests {
         assert_eq!(to_account.approvals_from.len(), 0);
     }
 
+    #[test]
+    fn test_approval_caller_allowlist() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let from = new_address();
+        let to = new_address();
+        let bucket = new_address();
+        let other_bucket = new_address();
+        let current_epoch = 1;
+
+        state
+            .approve_credit(
+                &config,
+                &store,
+                from,
+                to,
+                current_epoch,
+                None,
+                None,
+                None,
+                Some(HashSet::from([bucket, other_bucket])),
+            )
+            .unwrap();
+
+        // Only allowlisted callers may use the approval
+        state
+            .ensure_approval_caller_allowed(&store, from, to, bucket)
+            .unwrap();
+        let res = state.ensure_approval_caller_allowed(&store, from, to, to);
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap().exit_code(),
+            fvm_shared::error::ExitCode::USR_FORBIDDEN
+        );
+        // The subscriber itself is never restricted
+        state
+            .ensure_approval_caller_allowed(&store, from, from, to)
+            .unwrap();
+
+        // Revoke one caller
+        state
+            .revoke_credit_caller(&store, from, to, bucket)
+            .unwrap();
+        assert!(state
+            .ensure_approval_caller_allowed(&store, from, to, bucket)
+            .is_err());
+        let from_account = state.get_account(&store, from).unwrap().unwrap();
+        let to_account = state.get_account(&store, to).unwrap().unwrap();
+        let from_approval = from_account
+            .approvals_to
+            .hamt(&store)
+            .unwrap()
+            .get(&to)
+            .unwrap();
+        let to_approval = to_account
+            .approvals_from
+            .hamt(&store)
+            .unwrap()
+            .get(&from)
+            .unwrap();
+        assert_eq!(from_approval, to_approval);
+        assert_eq!(
+            from_approval.unwrap().caller_allowlist,
+            Some(HashSet::from([other_bucket]))
+        );
+
+        // Revoking the last caller revokes the approval
+        state
+            .revoke_credit_caller(&store, from, to, other_bucket)
+            .unwrap();
+        let from_account = state.get_account(&store, from).unwrap().unwrap();
+        assert_eq!(from_account.approvals_to.len(), 0);
+        let to_account = state.get_account(&store, to).unwrap().unwrap();
+        assert_eq!(to_account.approvals_from.len(), 0);
+    }
+
     #[test]
     fn test_revoke_credit_account_not_found() {
         setup_logs();
@@ -2287,6 +5087,7 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
             )
             .unwrap();
         debit_accounts_delete_from_disc(
@@ -2334,6 +5135,8 @@ mod tests {
             Some(ttl1),
             source,
             TokenAmount::zero(),
+            false,
+            1,
         );
         assert!(res.is_ok());
 
@@ -2367,6 +5170,7 @@ mod tests {
             hash,
             id1.clone(),
             BlobStatus::Resolved,
+            None,
         );
         assert!(res.is_ok());
         let stats = state.get_stats(config, TokenAmount::zero());
@@ -2405,6 +5209,8 @@ mod tests {
             Some(ttl2),
             source,
             TokenAmount::zero(),
+            false,
+            1,
         );
         assert!(res.is_ok());
 
@@ -2435,13 +5241,9 @@ mod tests {
         // Debit all accounts at an epoch between the two expiries (3601-3621)
         let debit_epoch = ChainEpoch::from(config.blob_min_ttl + 11);
         let deletes_from_disc = state
-            .debit_accounts(
-                &store,
-                debit_epoch,
-                config.blob_delete_batch_size,
-                config.account_debit_batch_size,
-            )
-            .unwrap();
+            .debit_accounts(&config, &store, debit_epoch)
+            .unwrap()
+            .delete_from_disc;
         assert!(deletes_from_disc.is_empty());
 
         // Check the account balance
@@ -2463,13 +5265,9 @@ mod tests {
         // Debit all accounts at an epoch greater than group expiry (3621)
         let debit_epoch = ChainEpoch::from(config.blob_min_ttl + 31);
         let deletes_from_disc = state
-            .debit_accounts(
-                &store,
-                debit_epoch,
-                config.blob_delete_batch_size,
-                config.account_debit_batch_size,
-            )
-            .unwrap();
+            .debit_accounts(&config, &store, debit_epoch)
+            .unwrap()
+            .delete_from_disc;
         assert!(!deletes_from_disc.is_empty()); // blob is marked for deletion
 
         // Check the account balance
@@ -2554,6 +5352,7 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
             )
             .unwrap();
         add_blob_refund(
@@ -2600,6 +5399,8 @@ mod tests {
             Some(config.blob_min_ttl),
             source,
             TokenAmount::zero(),
+            false,
+            1,
         );
         assert!(res.is_ok());
 
@@ -2650,6 +5451,8 @@ mod tests {
             Some(config.blob_min_ttl),
             source,
             TokenAmount::zero(),
+            false,
+            1,
         );
         assert!(res.is_ok());
 
@@ -2705,6 +5508,8 @@ mod tests {
             Some(config.blob_min_ttl),
             source,
             TokenAmount::zero(),
+            false,
+            1,
         );
         assert!(res.is_ok());
 
@@ -2803,6 +5608,7 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
             )
             .unwrap();
         add_blob_same_hash_same_account(
@@ -2859,6 +5665,8 @@ mod tests {
             Some(config.blob_min_ttl),
             source,
             TokenAmount::zero(),
+            false,
+            1,
         );
         assert!(res.is_ok());
         let (sub, _) = res.unwrap();
@@ -2933,6 +5741,7 @@ mod tests {
             hash,
             id1.clone(),
             BlobStatus::Resolved,
+            None,
         );
         assert!(res.is_ok());
         assert_eq!(
@@ -2966,6 +5775,8 @@ mod tests {
             Some(config.blob_min_ttl),
             source,
             TokenAmount::zero(),
+            false,
+            1,
         );
         assert!(res.is_ok());
         let (sub, _) = res.unwrap();
@@ -3028,6 +5839,8 @@ mod tests {
             Some(config.blob_min_ttl),
             source,
             TokenAmount::zero(),
+            false,
+            1,
         );
         assert!(res.is_ok());
         let (sub, _) = res.unwrap();
@@ -3084,13 +5897,9 @@ mod tests {
         // Debit all accounts
         let debit_epoch = ChainEpoch::from(41);
         let deletes_from_disc = state
-            .debit_accounts(
-                &store,
-                debit_epoch,
-                config.blob_delete_batch_size,
-                config.account_debit_batch_size,
-            )
-            .unwrap();
+            .debit_accounts(&config, &store, debit_epoch)
+            .unwrap()
+            .delete_from_disc;
         assert!(deletes_from_disc.is_empty());
 
         // Check the account balance
@@ -3110,7 +5919,15 @@ mod tests {
 
         // Delete the default subscription ID
         let delete_epoch = ChainEpoch::from(51);
-        let res = state.delete_blob(&store, origin, subscriber, delete_epoch, hash, id1.clone());
+        let res = state.delete_blob(
+            &config,
+            &store,
+            origin,
+            subscriber,
+            delete_epoch,
+            hash,
+            id1.clone(),
+        );
 
         assert!(res.is_ok());
         let (delete_from_disk, deleted_size) = res.unwrap();
@@ -3191,6 +6008,8 @@ mod tests {
             None,
             new_pk(),
             TokenAmount::zero(),
+            false,
+            1,
         );
         assert!(res.is_ok());
 
@@ -3204,6 +6023,7 @@ mod tests {
             hash,
             SubscriptionId::default(),
             BlobStatus::Pending,
+            None,
         );
         assert!(res.is_err());
         assert_eq!(
@@ -3248,6 +6068,8 @@ mod tests {
             Some(ChainEpoch::MAX),
             new_pk(),
             TokenAmount::zero(),
+            false,
+            1,
         );
         assert!(res.is_ok());
         let (sub, _) = res.unwrap();
@@ -3255,21 +6077,37 @@ mod tests {
     }
 
     #[test]
-    fn test_finalize_blob_resolved() {
+    fn test_add_blob_exceeding_config_max_ttl() {
         setup_logs();
-        let config = RecallConfig::default();
+        let config = RecallConfig {
+            blob_max_ttl: 60 * 60 * 24 * 7,
+            ..Default::default()
+        };
         let store = MemoryBlockstore::default();
         let mut state = State::new(&store).unwrap();
         let subscriber = new_address();
         let current_epoch = ChainEpoch::from(1);
-        let amount = TokenAmount::from_whole(10);
         state
-            .buy_credit(&config, &store, subscriber, amount.clone(), current_epoch)
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_whole(1000000),
+                current_epoch,
+            )
+            .unwrap();
+        // Extended accounts are still bound by the config cap
+        state
+            .set_account_status(
+                &config,
+                &store,
+                subscriber,
+                TtlStatus::Extended,
+                current_epoch,
+            )
             .unwrap();
 
-        // Add a blob
         let (hash, size) = new_hash(1024);
-        let source = new_pk();
         let res = state.add_blob(
             &config,
             &store,
@@ -3280,1683 +6118,4038 @@ mod tests {
             new_metadata_hash(),
             SubscriptionId::default(),
             size,
-            None,
-            source,
+            Some(config.blob_max_ttl + 1),
+            new_pk(),
             TokenAmount::zero(),
+            false,
+            1,
         );
-        assert!(res.is_ok());
-
-        // Set to status pending
-        let res = state.set_blob_pending(
-            &store,
-            subscriber,
-            hash,
-            size,
-            SubscriptionId::default(),
-            source,
-        );
-        assert!(res.is_ok());
-
-        // Finalize as resolved
-        let finalize_epoch = ChainEpoch::from(11);
-        let res = state.finalize_blob(
-            &config,
-            &store,
-            subscriber,
-            finalize_epoch,
-            hash,
-            SubscriptionId::default(),
-            BlobStatus::Resolved,
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().exit_code(),
+            fvm_shared::error::ExitCode::USR_FORBIDDEN
         );
-        assert!(res.is_ok());
 
-        // Check status
-        let status = state
-            .get_blob_status(&store, subscriber, hash, SubscriptionId::default())
-            .unwrap()
+        let (sub, _) = state
+            .add_blob(
+                &config,
+                &store,
+                subscriber,
+                subscriber,
+                current_epoch,
+                hash,
+                new_metadata_hash(),
+                SubscriptionId::default(),
+                size,
+                Some(config.blob_max_ttl),
+                new_pk(),
+                TokenAmount::zero(),
+                false,
+                1,
+            )
             .unwrap();
-        assert!(matches!(status, BlobStatus::Resolved));
-
-        // Check indexes
-        assert_eq!(state.expiries.len(&store).unwrap(), 1);
-        assert_eq!(state.added.len(), 0);
-        assert_eq!(state.pending.len(), 0);
+        assert_eq!(sub.expiry, current_epoch + config.blob_max_ttl);
     }
 
     #[test]
-    fn test_finalize_blob_failed() {
+    fn test_list_blobs_paginated() {
         setup_logs();
         let config = RecallConfig::default();
         let store = MemoryBlockstore::default();
         let mut state = State::new(&store).unwrap();
         let subscriber = new_address();
         let current_epoch = ChainEpoch::from(1);
-        let amount = TokenAmount::from_whole(10);
         state
-            .buy_credit(&config, &store, subscriber, amount.clone(), current_epoch)
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_whole(10),
+                current_epoch,
+            )
             .unwrap();
-        let credit_amount = amount * &config.token_credit_rate;
 
-        // Add a blob
-        let add_epoch = current_epoch;
-        let (hash, size) = new_hash(1024);
-        let source = new_pk();
-        let res = state.add_blob(
-            &config,
-            &store,
-            subscriber,
-            subscriber,
-            add_epoch,
-            hash,
-            new_metadata_hash(),
-            SubscriptionId::default(),
-            size,
-            None,
-            source,
-            TokenAmount::zero(),
-        );
-        assert!(res.is_ok());
+        let mut hashes = HashSet::new();
+        for _ in 0..5 {
+            let (hash, size) = new_hash(1024);
+            state
+                .add_blob(
+                    &config,
+                    &store,
+                    subscriber,
+                    subscriber,
+                    current_epoch,
+                    hash,
+                    new_metadata_hash(),
+                    SubscriptionId::default(),
+                    size,
+                    None,
+                    new_pk(),
+                    TokenAmount::zero(),
+                    false,
+                    1,
+                )
+                .unwrap();
+            hashes.insert(hash);
+        }
 
-        // Set to status pending
-        let res = state.set_blob_pending(
-            &store,
-            subscriber,
-            hash,
-            size,
-            SubscriptionId::default(),
-            source,
-        );
-        assert!(res.is_ok());
+        // Walk the blob set two at a time
+        let mut listed = Vec::new();
+        let mut start_key = None;
+        loop {
+            let (blobs, next_key) = state.list_blobs(&store, start_key, Some(2)).unwrap();
+            assert!(blobs.len() <= 2);
+            listed.extend(blobs);
+            if next_key.is_none() {
+                break;
+            }
+            start_key = next_key;
+        }
+        assert_eq!(listed.len(), 5);
+        for summary in &listed {
+            assert!(hashes.contains(&summary.hash));
+            assert_eq!(summary.size, 1024);
+            assert_eq!(summary.status, BlobStatus::Added);
+            assert_eq!(summary.num_subscribers, 1);
+        }
 
-        // Finalize as failed
-        let finalize_epoch = ChainEpoch::from(11);
-        let res = state.finalize_blob(
-            &config,
-            &store,
-            subscriber,
-            finalize_epoch,
-            hash,
-            SubscriptionId::default(),
-            BlobStatus::Failed,
-        );
-        assert!(res.is_ok());
+        // Listing with the default page size returns the same blobs in the same order
+        let (all, next_key) = state.list_blobs(&store, None, None).unwrap();
+        assert!(next_key.is_none());
+        assert_eq!(all, listed);
+    }
 
-        // Check status
-        let status = state
-            .get_blob_status(&store, subscriber, hash, SubscriptionId::default())
-            .unwrap()
+    #[test]
+    fn test_list_blobs_page_size() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_whole(10_000),
+                current_epoch,
+            )
             .unwrap();
-        assert!(matches!(status, BlobStatus::Failed));
-
-        // Check the account balance
-        let account = state.get_account(&store, subscriber).unwrap().unwrap();
-        assert_eq!(account.last_debit_epoch, add_epoch);
-        assert_eq!(account.credit_committed, Credit::from_whole(0)); // credit was released
-        assert_eq!(account.credit_free, credit_amount);
-        assert_eq!(account.capacity_used, 0); // capacity was released
-
-        // Check state
-        assert_eq!(state.credit_committed, Credit::from_whole(0)); // credit was released
-        assert_eq!(state.credit_debited, Credit::from_whole(0));
-        assert_eq!(state.capacity_used, 0); // capacity was released
+        for _ in 0..=ListBlobsParams::MAX_LIMIT {
+            let (hash, size) = new_hash(1024);
+            state
+                .add_blob(
+                    &config,
+                    &store,
+                    subscriber,
+                    subscriber,
+                    current_epoch,
+                    hash,
+                    new_metadata_hash(),
+                    SubscriptionId::default(),
+                    size,
+                    None,
+                    new_pk(),
+                    TokenAmount::zero(),
+                    false,
+                    1,
+                )
+                .unwrap();
+        }
 
-        // Check indexes
-        assert_eq!(state.expiries.len(&store).unwrap(), 1); // remains until the blob is explicitly deleted
-        assert_eq!(state.added.len(), 0);
-        assert_eq!(state.pending.len(), 0);
+        // No limit means the default page size
+        let (blobs, next_key) = state.list_blobs(&store, None, None).unwrap();
+        assert_eq!(blobs.len(), ListBlobsParams::DEFAULT_LIMIT as usize);
+        assert!(next_key.is_some());
+
+        // Larger limits are capped
+        let (blobs, next_key) = state.list_blobs(&store, None, Some(u32::MAX)).unwrap();
+        assert_eq!(blobs.len(), ListBlobsParams::MAX_LIMIT as usize);
+        let (rest, next_key) = state.list_blobs(&store, next_key, Some(u32::MAX)).unwrap();
+        assert_eq!(rest.len(), 1);
+        assert!(next_key.is_none());
+        assert!(!blobs.contains(&rest[0]));
     }
 
     #[test]
-    fn test_finalize_blob_failed_refund() {
+    fn test_extend_subscription_keeps_status() {
         setup_logs();
         let config = RecallConfig::default();
         let store = MemoryBlockstore::default();
         let mut state = State::new(&store).unwrap();
         let subscriber = new_address();
         let current_epoch = ChainEpoch::from(1);
-        let amount = TokenAmount::from_whole(10);
         state
-            .buy_credit(&config, &store, subscriber, amount.clone(), current_epoch)
-            .unwrap();
-        let mut credit_amount = amount.clone() * &config.token_credit_rate;
-
-        assert!(state
-            .set_account_status(
+            .buy_credit(
                 &config,
                 &store,
                 subscriber,
-                TtlStatus::Extended,
-                current_epoch
+                TokenAmount::from_whole(10),
+                current_epoch,
             )
-            .is_ok());
-
-        // Add a blob
-        let add_epoch = current_epoch;
+            .unwrap();
         let (hash, size) = new_hash(1024);
+        let id = SubscriptionId::default();
         let source = new_pk();
-        let res = state.add_blob(
-            &config,
-            &store,
-            subscriber,
-            subscriber,
-            add_epoch,
-            hash,
-            new_metadata_hash(),
-            SubscriptionId::default(),
-            size,
-            Some(config.blob_min_ttl),
-            source,
-            TokenAmount::zero(),
-        );
-        assert!(res.is_ok());
-
-        // Check the account balance
-        let account = state.get_account(&store, subscriber).unwrap().unwrap();
-        assert_eq!(account.last_debit_epoch, add_epoch);
-        assert_eq!(
-            account.credit_committed,
-            Credit::from_whole(config.blob_min_ttl as u64 * size),
-        );
-        credit_amount -= &account.credit_committed;
-        assert_eq!(account.credit_free, credit_amount);
-        assert_eq!(account.capacity_used, size);
-
-        // Check state
-        assert_eq!(state.credit_committed, account.credit_committed);
-        assert_eq!(state.credit_debited, Credit::from_whole(0));
-        assert_eq!(state.capacity_used, account.capacity_used); // capacity was released
-
-        // Debit accounts to trigger a refund when we fail below
-        let debit_epoch = ChainEpoch::from(11);
-        let deletes_from_disc = state
-            .debit_accounts(
+        let ttl = ChainEpoch::from(config.blob_min_ttl);
+        state
+            .add_blob(
+                &config,
                 &store,
-                debit_epoch,
-                config.blob_delete_batch_size,
-                config.account_debit_batch_size,
+                subscriber,
+                subscriber,
+                current_epoch,
+                hash,
+                new_metadata_hash(),
+                id.clone(),
+                size,
+                Some(ttl),
+                source,
+                TokenAmount::zero(),
+                false,
+                1,
             )
             .unwrap();
-        assert!(deletes_from_disc.is_empty());
+        state
+            .set_blob_pending(&store, subscriber, hash, size, id.clone(), source)
+            .unwrap();
 
-        // Check the account balance
+        // Extend by another TTL
+        let (sub, tokens_unspent) = state
+            .extend_subscription(
+                &config,
+                &store,
+                subscriber,
+                subscriber,
+                current_epoch,
+                hash,
+                id.clone(),
+                Some(ttl * 2),
+                TokenAmount::zero(),
+            )
+            .unwrap();
+        assert_eq!(sub.expiry, current_epoch + ttl * 2);
+        assert!(tokens_unspent.is_zero());
         let account = state.get_account(&store, subscriber).unwrap().unwrap();
-        assert_eq!(account.last_debit_epoch, debit_epoch);
         assert_eq!(
             account.credit_committed,
-            Credit::from_whole((config.blob_min_ttl - (debit_epoch - add_epoch)) as u64 * size),
-        );
-        assert_eq!(account.credit_free, credit_amount); // not changed
-        assert_eq!(account.capacity_used, size);
-
-        // Check state
-        assert_eq!(state.credit_committed, account.credit_committed);
-        assert_eq!(
-            state.credit_debited,
-            Credit::from_whole((debit_epoch - add_epoch) as u64 * size)
-        );
-        assert_eq!(state.capacity_used, account.capacity_used);
-
-        // Set to status pending
-        let res = state.set_blob_pending(
-            &store,
-            subscriber,
-            hash,
-            size,
-            SubscriptionId::default(),
-            source,
-        );
-        assert!(res.is_ok());
-
-        // Finalize as failed
-        let finalize_epoch = ChainEpoch::from(21);
-        let res = state.finalize_blob(
-            &config,
-            &store,
-            subscriber,
-            finalize_epoch,
-            hash,
-            SubscriptionId::default(),
-            BlobStatus::Failed,
+            Credit::from_whole(ttl as u64 * 2 * size)
         );
-        assert!(res.is_ok());
 
-        // Check status
+        // The blob is still pending and was not re-queued
         let status = state
-            .get_blob_status(&store, subscriber, hash, SubscriptionId::default())
-            .unwrap()
+            .get_blob_status(&store, subscriber, hash, id.clone())
             .unwrap();
-        assert!(matches!(status, BlobStatus::Failed));
-
-        // Check the account balance
-        let account = state.get_account(&store, subscriber).unwrap().unwrap();
-        assert_eq!(account.last_debit_epoch, debit_epoch);
-        assert_eq!(account.credit_committed, Credit::from_whole(0)); // credit was released
-        assert_eq!(
-            account.credit_free,
-            amount.clone() * &config.token_credit_rate
-        ); // credit was refunded
-        assert_eq!(account.capacity_used, 0); // capacity was released
-
-        // Check state
-        assert_eq!(state.credit_committed, Credit::from_whole(0)); // credit was released
-        assert_eq!(state.credit_debited, Credit::from_whole(0)); // credit was refunded and released
-        assert_eq!(state.capacity_used, 0); // capacity was released
-
-        // Check indexes
-        assert_eq!(state.expiries.len(&store).unwrap(), 1); // remains until the blob is explicitly deleted
+        assert_eq!(status, Some(BlobStatus::Pending));
+        assert_eq!(state.pending.len(), 1);
         assert_eq!(state.added.len(), 0);
-        assert_eq!(state.pending.len(), 0);
-    }
 
-    #[test]
-    fn test_delete_blob_refund() {
-        setup_logs();
-        let config = RecallConfig::default();
-        let store = MemoryBlockstore::default();
-        let mut state = State::new(&store).unwrap();
-        let origin = new_address();
-        let current_epoch = ChainEpoch::from(1);
-        let token_amount = TokenAmount::from_whole(10);
-        state
-            .buy_credit(&config, &store, origin, token_amount.clone(), current_epoch)
-            .unwrap();
-        delete_blob_refund(
+        // The expiry can only be extended
+        let res = state.extend_subscription(
             &config,
             &store,
-            state,
-            origin,
-            origin,
+            subscriber,
+            subscriber,
             current_epoch,
-            token_amount,
-            false,
+            hash,
+            id,
+            Some(ttl),
+            TokenAmount::zero(),
+        );
+        assert_eq!(
+            res.err().unwrap().exit_code(),
+            fvm_shared::error::ExitCode::USR_ILLEGAL_ARGUMENT
         );
     }
 
     #[test]
-    fn test_delete_blob_refund_with_approval() {
+    fn test_transfer_subscription() {
         setup_logs();
         let config = RecallConfig::default();
         let store = MemoryBlockstore::default();
         let mut state = State::new(&store).unwrap();
-        let origin = new_address();
-        let subscriber = new_address();
+        let from = new_address();
+        let to = new_address();
         let current_epoch = ChainEpoch::from(1);
-        let token_amount = TokenAmount::from_whole(10);
-        state
-            .buy_credit(
+        for address in [from, to] {
+            state
+                .buy_credit(
+                    &config,
+                    &store,
+                    address,
+                    TokenAmount::from_whole(10),
+                    current_epoch,
+                )
+                .unwrap();
+        }
+        let (hash, size) = new_hash(1024);
+        let id = SubscriptionId::default();
+        let source = new_pk();
+        let ttl = ChainEpoch::from(config.blob_min_ttl * 2);
+        let sub = state
+            .add_blob(
                 &config,
                 &store,
-                subscriber,
-                token_amount.clone(),
+                from,
+                from,
                 current_epoch,
+                hash,
+                new_metadata_hash(),
+                id.clone(),
+                size,
+                Some(ttl),
+                source,
+                TokenAmount::zero(),
+                false,
+                1,
             )
+            .unwrap()
+            .0;
+
+        // Only resolved blobs can be transferred
+        let res =
+            state.transfer_subscription(&config, &store, from, to, current_epoch, hash, id.clone());
+        assert!(res.is_err());
+        state
+            .set_blob_pending(&store, from, hash, size, id.clone(), source)
             .unwrap();
         state
-            .approve_credit(
+            .finalize_blob(
                 &config,
                 &store,
-                subscriber,
-                origin,
+                from,
                 current_epoch,
-                None,
-                None,
+                hash,
+                id.clone(),
+                BlobStatus::Resolved,
                 None,
             )
             .unwrap();
-        delete_blob_refund(
-            &config,
-            &store,
-            state,
-            origin,
-            subscriber,
-            current_epoch,
-            token_amount,
-            true,
-        );
-    }
-
-    #[allow(clippy::too_many_arguments)]
-    fn delete_blob_refund<BS: Blockstore>(
-        config: &RecallConfig,
-        store: &BS,
-        mut state: State,
-        origin: Address,
-        subscriber: Address,
-        current_epoch: ChainEpoch,
-        token_amount: TokenAmount,
-        using_approval: bool,
-    ) {
-        let mut credit_amount = token_amount * &config.token_credit_rate;
-
-        // Add a blob
-        let add1_epoch = current_epoch;
-        let (hash1, size1) = new_hash(1024);
-        let source1 = new_pk();
-        let res = state.add_blob(
-            config,
-            &store,
-            origin,
-            subscriber,
-            add1_epoch,
-            hash1,
-            new_metadata_hash(),
-            SubscriptionId::default(),
-            size1,
-            Some(config.blob_min_ttl),
-            source1,
-            TokenAmount::zero(),
-        );
-        assert!(res.is_ok());
-
-        // Finalize as resolved
-        let res = state.set_blob_pending(
-            &store,
-            subscriber,
-            hash1,
-            size1,
-            SubscriptionId::default(),
-            source1,
-        );
-        assert!(res.is_ok());
-        let finalize_epoch = ChainEpoch::from(current_epoch + 1);
-        let res = state.finalize_blob(
-            config,
-            &store,
-            subscriber,
-            finalize_epoch,
-            hash1,
-            SubscriptionId::default(),
-            BlobStatus::Resolved,
-        );
-        assert!(res.is_ok());
-
-        // Check stats
-        let stats = state.get_stats(config, TokenAmount::zero());
-        assert_eq!(stats.num_blobs, 1);
-        assert_eq!(stats.num_resolving, 0);
-        assert_eq!(stats.bytes_resolving, 0);
-        assert_eq!(stats.num_added, 0);
-        assert_eq!(stats.bytes_added, 0);
-
-        // Check the account balance
-        let account = state.get_account(&store, subscriber).unwrap().unwrap();
-        assert_eq!(account.last_debit_epoch, add1_epoch);
-        assert_eq!(
-            account.credit_committed,
-            Credit::from_whole(config.blob_min_ttl as u64 * size1),
-        );
-        credit_amount -= &account.credit_committed;
-        assert_eq!(account.credit_free, credit_amount);
-        assert_eq!(account.capacity_used, size1);
 
-        // Add another blob past the first blob expiry
-        // This will trigger a debit on the account
-        let add2_epoch = ChainEpoch::from(config.blob_min_ttl + 10);
-        let (hash2, size2) = new_hash(2048);
-        let res = state.add_blob(
-            config,
+        // The recipient must approve the sender
+        let transfer_epoch = current_epoch + 10;
+        let res = state.transfer_subscription(
+            &config,
             &store,
-            origin,
-            subscriber,
-            add2_epoch,
-            hash2,
-            new_metadata_hash(),
-            SubscriptionId::default(),
-            size2,
-            Some(config.blob_min_ttl),
-            new_pk(),
-            TokenAmount::zero(),
+            from,
+            to,
+            transfer_epoch,
+            hash,
+            id.clone(),
         );
-        assert!(res.is_ok());
-
-        // Check stats
-        let stats = state.get_stats(config, TokenAmount::zero());
-        assert_eq!(stats.num_blobs, 2);
-        assert_eq!(stats.num_resolving, 0);
-        assert_eq!(stats.bytes_resolving, 0);
-        assert_eq!(stats.num_added, 1);
-        assert_eq!(stats.bytes_added, size2);
-
-        // Check the account balance
-        let account = state.get_account(&store, subscriber).unwrap().unwrap();
-        assert_eq!(account.last_debit_epoch, add2_epoch);
-        let blob1_expiry = ChainEpoch::from(config.blob_min_ttl + add1_epoch);
-        let overcharge = BigInt::from((add2_epoch - blob1_expiry) as u64 * size1);
         assert_eq!(
-            account.credit_committed, // this includes an overcharge that needs to be refunded
-            Credit::from_whole(config.blob_min_ttl as u64 * size2 - overcharge),
+            res.err().unwrap().exit_code(),
+            fvm_shared::error::ExitCode::USR_FORBIDDEN
         );
-        credit_amount -= Credit::from_whole(config.blob_min_ttl as u64 * size2);
-        assert_eq!(account.credit_free, credit_amount);
-        assert_eq!(account.capacity_used, size1 + size2);
-
-        // Delete the first blob
-        let delete_epoch = ChainEpoch::from(config.blob_min_ttl + 20);
-        let (delete_from_disc, deleted_size) = state
-            .delete_blob(
+        state
+            .approve_credit(
+                &config,
                 &store,
-                origin,
-                subscriber,
-                delete_epoch,
-                hash1,
-                SubscriptionId::default(),
+                to,
+                from,
+                current_epoch,
+                None,
+                None,
+                None,
+                None,
             )
             .unwrap();
-        assert!(delete_from_disc);
-        assert_eq!(size1, deleted_size);
-
-        // Check stats
-        let stats = state.get_stats(config, TokenAmount::zero());
-        assert_eq!(stats.num_blobs, 1);
-        assert_eq!(stats.num_resolving, 0);
-        assert_eq!(stats.bytes_resolving, 0);
-        assert_eq!(stats.num_added, 1);
-        assert_eq!(stats.bytes_added, size2);
+        let new_sub = state
+            .transfer_subscription(&config, &store, from, to, transfer_epoch, hash, id.clone())
+            .unwrap();
+        assert_eq!(new_sub.expiry, sub.expiry);
+        assert_eq!(new_sub.source, source);
 
-        // Check the account balance
-        let account = state.get_account(&store, subscriber).unwrap().unwrap();
-        assert_eq!(account.last_debit_epoch, add2_epoch); // not changed, blob is expired
-        assert_eq!(
-            account.credit_committed, // should not include overcharge due to refund
-            Credit::from_whole(config.blob_min_ttl as u64 * size2),
-        );
-        assert_eq!(account.credit_free, credit_amount); // not changed
-        assert_eq!(account.capacity_used, size2);
+        // The sender no longer pays for the blob
+        let from_account = state.get_account(&store, from).unwrap().unwrap();
+        assert_eq!(from_account.capacity_used, 0);
+        assert_eq!(from_account.credit_committed, Credit::zero());
+        assert!(state
+            .get_blob_metadata(&store, from, hash, id.clone())
+            .unwrap()
+            .is_none());
 
-        // Check state
-        assert_eq!(state.credit_committed, account.credit_committed); // credit was released
+        // The recipient commits credit for the remaining TTL
+        let to_account = state.get_account(&store, to).unwrap().unwrap();
+        assert_eq!(to_account.capacity_used, size);
         assert_eq!(
-            state.credit_debited,
-            Credit::from_whole(config.blob_min_ttl as u64 * size1)
+            to_account.credit_committed,
+            Credit::from_whole((sub.expiry - transfer_epoch) as u64 * size)
         );
-        assert_eq!(state.capacity_used, size2); // capacity was released
-
-        // Check indexes
-        assert_eq!(state.expiries.len(store).unwrap(), 1);
-        assert_eq!(state.added.len(), 1);
-        assert_eq!(state.pending.len(), 0);
+        let status = state.get_blob_status(&store, to, hash, id).unwrap();
+        assert_eq!(status, Some(BlobStatus::Resolved));
+        assert_eq!(state.capacity_used, size);
+    }
 
-        // Check approval
-        if using_approval {
-            check_approval_used(&state, store, origin, subscriber);
+    #[test]
+    fn test_transfer_subscription_survives_revocation() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let from = new_address();
+        let to = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        for address in [from, to] {
+            state
+                .buy_credit(
+                    &config,
+                    &store,
+                    address,
+                    TokenAmount::from_whole(10),
+                    current_epoch,
+                )
+                .unwrap();
         }
+        state
+            .approve_credit(
+                &config,
+                &store,
+                to,
+                from,
+                current_epoch,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        let (hash, size, id) =
+            add_auto_renew_blob_with(&config, &store, &mut state, from, from, current_epoch, 1);
+
+        // The recipient owns the transferred subscription outright
+        let new_sub = state
+            .transfer_subscription(&config, &store, from, to, current_epoch, hash, id.clone())
+            .unwrap();
+        assert_eq!(new_sub.delegate, None);
+        let approval = state
+            .get_credit_approval(&store, to, from)
+            .unwrap()
+            .unwrap();
+        assert_eq!(approval.credit_used, Credit::zero());
+
+        // Revoking the sender's approval doesn't stop the recipient's subscription from renewing
+        state.revoke_credit(&store, to, from).unwrap();
+        let debit_epoch = current_epoch + config.blob_min_ttl;
+        let outcome = state.debit_accounts(&config, &store, debit_epoch).unwrap();
+        assert_eq!(outcome.renewed.len(), 1);
+        assert!(outcome.expired.is_empty());
+        let status = state.get_blob_status(&store, to, hash, id).unwrap();
+        assert_eq!(status, Some(BlobStatus::Resolved));
+        let to_account = state.get_account(&store, to).unwrap().unwrap();
+        assert_eq!(to_account.capacity_used, size);
     }
 
     #[test]
-    fn test_if_blobs_ttl_exceeds_accounts_ttl_should_error() {
+    fn test_get_blob_subscribers() {
         setup_logs();
-
         let config = RecallConfig::default();
-        const YEAR: ChainEpoch = 365 * 24 * 60 * 60;
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let current_epoch = ChainEpoch::from(1);
+        let (hash, size) = new_hash(1024);
+        let source = new_pk();
+        let metadata_hash = new_metadata_hash();
 
-        // Test cases structure
-        struct TestCase {
-            name: &'static str,
-            account_ttl_status: TtlStatus,
-            blob_ttl: Option<ChainEpoch>,
-            should_succeed: bool,
-            expected_account_ttl: ChainEpoch,
-            expected_blob_ttl: ChainEpoch,
+        // Add three subscribers, one with two subscriptions
+        let subscribers = [new_address(), new_address(), new_address()];
+        for subscriber in subscribers {
+            state
+                .buy_credit(
+                    &config,
+                    &store,
+                    subscriber,
+                    TokenAmount::from_whole(10),
+                    current_epoch,
+                )
+                .unwrap();
+            state
+                .add_blob(
+                    &config,
+                    &store,
+                    subscriber,
+                    subscriber,
+                    current_epoch,
+                    hash,
+                    metadata_hash,
+                    SubscriptionId::default(),
+                    size,
+                    Some(config.blob_min_ttl),
+                    source,
+                    TokenAmount::zero(),
+                    false,
+                    1,
+                )
+                .unwrap();
         }
+        let id = SubscriptionId::new("other").unwrap();
+        state
+            .add_blob(
+                &config,
+                &store,
+                subscribers[0],
+                subscribers[0],
+                current_epoch,
+                hash,
+                metadata_hash,
+                id.clone(),
+                size,
+                Some(config.blob_min_ttl * 2),
+                source,
+                TokenAmount::zero(),
+                false,
+                1,
+            )
+            .unwrap();
 
-        // Define test cases
-        let test_cases = vec![
-            TestCase {
-                name: "Reduced status rejects even minimum TTL",
-                account_ttl_status: TtlStatus::Reduced,
-                blob_ttl: Some(config.blob_min_ttl),
-                should_succeed: false,
-                expected_account_ttl: 0,
-                expected_blob_ttl: 0,
-            },
-            TestCase {
-                name: "Reduced status rejects no TTL",
-                account_ttl_status: TtlStatus::Reduced,
-                blob_ttl: Some(config.blob_min_ttl),
-                should_succeed: false,
-                expected_account_ttl: 0,
-                expected_blob_ttl: 0,
-            },
-            TestCase {
-                name: "Default status allows default TTL",
-                account_ttl_status: TtlStatus::Default,
-                blob_ttl: Some(config.blob_default_ttl),
-                should_succeed: true,
-                expected_account_ttl: config.blob_default_ttl,
-                expected_blob_ttl: config.blob_default_ttl,
-            },
-            TestCase {
-                name: "Default status sets no TTL to default without auto renew",
-                account_ttl_status: TtlStatus::Default,
-                blob_ttl: None,
-                should_succeed: true,
-                expected_account_ttl: config.blob_default_ttl,
-                expected_blob_ttl: config.blob_default_ttl,
-            },
-            TestCase {
-                name: "Default status preserves given TTL if it's less than default",
-                account_ttl_status: TtlStatus::Default,
-                blob_ttl: Some(config.blob_default_ttl - 1),
-                should_succeed: true,
-                expected_account_ttl: config.blob_default_ttl,
-                expected_blob_ttl: config.blob_default_ttl - 1,
-            },
-            TestCase {
-                name: "Default status rejects TTLs higher than default",
-                account_ttl_status: TtlStatus::Default,
-                blob_ttl: Some(config.blob_default_ttl + 1),
-                should_succeed: false,
-                expected_account_ttl: config.blob_default_ttl,
-                expected_blob_ttl: 0,
-            },
-            TestCase {
-                name: "Extended status allows any TTL",
-                account_ttl_status: TtlStatus::Extended,
-                blob_ttl: Some(YEAR),
-                should_succeed: true,
-                expected_account_ttl: ChainEpoch::MAX,
-                expected_blob_ttl: YEAR,
-            },
-        ];
-
-        // Run all test cases
-        for tc in test_cases {
-            let config = RecallConfig::default();
-            let store = MemoryBlockstore::default();
-            let mut state = State::new(&store).unwrap();
-            let subscriber = new_address();
-            let current_epoch = ChainEpoch::from(1);
-            let amount = TokenAmount::from_whole(10);
+        // Page through the subscribers
+        let (first, next_key) = state
+            .get_blob_subscribers(&store, hash, None, Some(2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.len(), 2);
+        assert!(next_key.is_some());
+        let (rest, next_key) = state
+            .get_blob_subscribers(&store, hash, next_key, Some(2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(rest.len(), 1);
+        assert!(next_key.is_none());
 
-            state
-                .buy_credit(&config, &store, subscriber, amount.clone(), current_epoch)
-                .unwrap();
-            state
-                .set_account_status(
-                    &config,
-                    &store,
-                    subscriber,
-                    tc.account_ttl_status,
-                    current_epoch,
-                )
+        let groups: Vec<_> = first.into_iter().chain(rest).collect();
+        let listed: HashSet<_> = groups.iter().map(|g| g.subscriber).collect();
+        assert_eq!(listed, HashSet::from(subscribers));
+
+        let group = groups
+            .iter()
+            .find(|g| g.subscriber == subscribers[0])
+            .unwrap();
+        assert_eq!(group.subscriptions.len(), 2);
+        let sub = group.subscriptions.iter().find(|s| s.id == id).unwrap();
+        assert_eq!(sub.expiry, current_epoch + config.blob_min_ttl * 2);
+        assert_eq!(sub.source, source);
+        assert_eq!(sub.delegate, None);
+
+        // Unknown blobs return none
+        let (unknown, _) = new_hash(1024);
+        assert!(state
+            .get_blob_subscribers(&store, unknown, None, None)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_sponsor_groups() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let sponsor = new_address();
+        let member_a = new_address();
+        let member_b = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(
+                &config,
+                &store,
+                sponsor,
+                TokenAmount::from_whole(10),
+                current_epoch,
+            )
+            .unwrap();
+
+        // Each member may use the cost of one blob; the group may use one and a half
+        let cost = config.blob_min_ttl as u64 * 1024;
+        let name = "engineering".to_string();
+        state
+            .create_sponsor_group(
+                &config,
+                &store,
+                sponsor,
+                current_epoch,
+                name.clone(),
+                Some(Credit::from_whole(cost * 3 / 2)),
+                Some(Credit::from_whole(cost)),
+            )
+            .unwrap();
+        let res = state.create_sponsor_group(
+            &config,
+            &store,
+            sponsor,
+            current_epoch,
+            name.clone(),
+            None,
+            None,
+        );
+        assert!(res.is_err());
+        for member in [member_a, member_b] {
+            let approval = state
+                .add_sponsor_group_member(&config, &store, sponsor, current_epoch, &name, member)
                 .unwrap();
+            assert_eq!(approval.credit_limit, Some(Credit::from_whole(cost)));
+        }
+        let res = state.add_sponsor_group_member(
+            &config,
+            &store,
+            sponsor,
+            current_epoch,
+            &name,
+            member_a,
+        );
+        assert!(res.is_err());
 
-            let (hash, size) = new_hash(1024);
-            let res = state.add_blob(
+        let add_blob = |state: &mut State, origin: Address, size: u64| {
+            let (hash, _) = new_hash(size as usize);
+            state.add_blob(
                 &config,
                 &store,
-                subscriber,
-                subscriber,
+                origin,
+                sponsor,
                 current_epoch,
                 hash,
                 new_metadata_hash(),
                 SubscriptionId::default(),
                 size,
-                tc.blob_ttl,
+                Some(config.blob_min_ttl),
                 new_pk(),
                 TokenAmount::zero(),
-            );
+                false,
+                1,
+            )
+        };
+        assert!(add_blob(&mut state, member_a, 1024).is_ok());
+        // The per-member limit is reached
+        assert!(add_blob(&mut state, member_a, 256).is_err());
+        // The group budget can't cover another full blob
+        assert!(add_blob(&mut state, member_b, 1024).is_err());
+        assert!(add_blob(&mut state, member_b, 256).is_ok());
+
+        let group = state
+            .get_sponsor_group(&store, sponsor, &name)
+            .unwrap()
+            .unwrap();
+        assert_eq!(group.credit_used, Credit::from_whole(cost + cost / 4));
+        assert_eq!(group.members.len(), 2);
 
-            let account_ttl = state
-                .get_account_max_ttl(&config, &store, subscriber)
-                .unwrap();
-            assert_eq!(
-                account_ttl, tc.expected_account_ttl,
-                "Test case '{}' has unexpected account TTL (expected {}, got {})",
-                tc.name, tc.expected_account_ttl, account_ttl
-            );
+        // Removing a member revokes its approval
+        state
+            .remove_sponsor_group_member(&store, sponsor, &name, member_b)
+            .unwrap();
+        assert!(state
+            .get_credit_approval(&store, sponsor, member_b)
+            .unwrap()
+            .is_none());
+        let group = state
+            .get_sponsor_group(&store, sponsor, &name)
+            .unwrap()
+            .unwrap();
+        assert_eq!(group.members, vec![(member_a, Credit::from_whole(cost))]);
 
-            if tc.should_succeed {
-                assert!(
-                    res.is_ok(),
-                    "Test case '{}' should succeed but failed: {:?}",
-                    tc.name,
-                    res.err()
-                );
+        // Deleting the group revokes the remaining approvals
+        state.delete_sponsor_group(&store, sponsor, &name).unwrap();
+        assert!(state
+            .get_credit_approval(&store, sponsor, member_a)
+            .unwrap()
+            .is_none());
+        assert!(state
+            .get_sponsor_group(&store, sponsor, &name)
+            .unwrap()
+            .is_none());
+    }
 
-                let res = state.get_blob(&store, hash);
-                assert!(res.is_ok(), "Failed to get blob: {:?}", res.err());
-                let blob = res.unwrap().unwrap();
-                let subscribers = blob.subscribers.hamt(&store).unwrap();
-                subscribers
-                    .for_each(|_, group| {
-                        let group_hamt = group.hamt(&store).unwrap();
-                        for val in group_hamt.iter() {
-                            let (_, sub) = val.unwrap();
-                            assert_eq!(
-                                sub.expiry,
-                                current_epoch + tc.expected_blob_ttl,
-                                "Test case '{}' has unexpected blob expiry",
-                                tc.name
-                            );
-                        }
-                        Ok(())
-                    })
+    #[test]
+    fn test_get_usage_report() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let small = new_address();
+        let large = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        for (subscriber, sizes, epoch) in [(small, vec![1024], 1), (large, vec![2048, 4096], 5)] {
+            state
+                .buy_credit(
+                    &config,
+                    &store,
+                    subscriber,
+                    TokenAmount::from_whole(10),
+                    current_epoch,
+                )
+                .unwrap();
+            for size in sizes {
+                let (hash, size) = new_hash(size);
+                state
+                    .add_blob(
+                        &config,
+                        &store,
+                        subscriber,
+                        subscriber,
+                        epoch,
+                        hash,
+                        new_metadata_hash(),
+                        SubscriptionId::default(),
+                        size,
+                        None,
+                        new_pk(),
+                        TokenAmount::zero(),
+                        false,
+                        1,
+                    )
                     .unwrap();
-            } else {
-                assert!(
-                    res.is_err(),
-                    "Test case '{}' should fail but succeeded",
-                    tc.name
-                );
-                assert_eq!(
-                    res.err().unwrap().msg(),
-                    format!(
-                        "attempt to add a blob with TTL ({}) that exceeds account's max allowed TTL ({})",
-                        tc.blob_ttl.map_or_else(|| "none".to_string(), |ttl| ttl.to_string()), tc.account_ttl_status.get_max_ttl(config.blob_default_ttl),
-                    ),
-                    "Test case '{}' failed with unexpected error message",
-                    tc.name
-                );
             }
         }
+        let debit_epoch = current_epoch + 10;
+        state.debit_accounts(&config, &store, debit_epoch).unwrap();
+
+        let report = state
+            .get_usage_report(&config, &store, debit_epoch, 1, 0)
+            .unwrap();
+        assert_eq!(report.top_accounts.len(), 1);
+        let top = &report.top_accounts[0];
+        assert_eq!(top.address, large);
+        assert_eq!(top.capacity_used, 2048 + 4096);
+        assert!(top.credit_debited.is_positive());
+        assert_eq!(report.num_added, 3);
+        assert_eq!(
+            report.num_pending + report.num_resolved + report.num_failed,
+            0
+        );
+
+        // Only blobs added at or after the given epoch are counted
+        let report = state
+            .get_usage_report(&config, &store, debit_epoch, 2, 5)
+            .unwrap();
+        assert_eq!(report.top_accounts.len(), 2);
+        assert_eq!(report.top_accounts[1].address, small);
+        assert_eq!(report.num_added, 2);
     }
 
     #[test]
-    fn test_set_ttl_status() {
+    fn test_verify_invariants() {
         setup_logs();
-
         let config = RecallConfig::default();
-
-        struct TestCase {
-            name: &'static str,
-            initial_ttl_status: Option<TtlStatus>, // None means don't set initial status
-            new_ttl_status: TtlStatus,
-            expected_ttl: ChainEpoch,
-        }
-
-        let test_cases = vec![
-            TestCase {
-                name: "Setting Reduced on new account",
-                initial_ttl_status: None,
-                new_ttl_status: TtlStatus::Reduced,
-                expected_ttl: 0,
-            },
-            TestCase {
-                name: "Setting Default on new account",
-                initial_ttl_status: None,
-                new_ttl_status: TtlStatus::Default,
-                expected_ttl: config.blob_default_ttl,
-            },
-            TestCase {
-                name: "Changing from Default to Reduced",
-                initial_ttl_status: Some(TtlStatus::Default),
-                new_ttl_status: TtlStatus::Reduced,
-                expected_ttl: 0,
-            },
-            TestCase {
-                name: "Changing from Extended to Reduced",
-                initial_ttl_status: Some(TtlStatus::Extended),
-                new_ttl_status: TtlStatus::Reduced,
-                expected_ttl: 0,
-            },
-            TestCase {
-                name: "Changing from Reduced to Extended",
-                initial_ttl_status: Some(TtlStatus::Reduced),
-                new_ttl_status: TtlStatus::Extended,
-                expected_ttl: ChainEpoch::MAX,
-            },
-        ];
-
-        for tc in test_cases {
-            let store = MemoryBlockstore::default();
-            let mut state = State::new(&store).unwrap();
-            let account = new_address();
-            let current_epoch = ChainEpoch::from(1);
-
-            // Initialize the account if needed
-            if tc.initial_ttl_status.is_some() {
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let current_epoch = ChainEpoch::from(1);
+        let subscribers = [new_address(), new_address()];
+        let (shared_hash, shared_size) = new_hash(1024);
+        for subscriber in subscribers {
+            state
+                .buy_credit(
+                    &config,
+                    &store,
+                    subscriber,
+                    TokenAmount::from_whole(10),
+                    current_epoch,
+                )
+                .unwrap();
+            // Both subscribe to the same blob, plus one of their own
+            let (hash, size) = new_hash(2048);
+            for (hash, size) in [(shared_hash, shared_size), (hash, size)] {
                 state
-                    .set_account_status(
+                    .add_blob(
                         &config,
                         &store,
-                        account,
-                        tc.initial_ttl_status.unwrap(),
+                        subscriber,
+                        subscriber,
                         current_epoch,
+                        hash,
+                        new_metadata_hash(),
+                        SubscriptionId::default(),
+                        size,
+                        Some(config.blob_min_ttl),
+                        new_pk(),
+                        TokenAmount::zero(),
+                        false,
+                        1,
                     )
                     .unwrap();
             }
+        }
 
-            // Change TTL status
-            let res = state.set_account_status(
-                &config,
-                &store,
-                account,
-                tc.new_ttl_status,
-                current_epoch,
-            );
-            assert!(
-                res.is_ok(),
-                "Test case '{}' failed to set TTL status",
-                tc.name
-            );
+        let report = state.verify_invariants(&store).unwrap();
+        assert!(report.is_consistent(), "{:?}", report);
+        assert_eq!(report.num_accounts, 2);
+        assert_eq!(report.num_blobs, 3);
+        assert_eq!(report.capacity_used_expected, shared_size + 2 * 2048);
+        state.reconcile(&store).unwrap();
 
-            // Verify max TTL
-            let max_ttl = state.get_account_max_ttl(&config, &store, account).unwrap();
-            assert_eq!(
-                max_ttl, tc.expected_ttl,
-                "Test case '{}' failed: expected max TTL {}, got {}",
-                tc.name, tc.expected_ttl, max_ttl
-            );
-        }
+        // Debits keep the totals consistent
+        state
+            .debit_accounts(&config, &store, current_epoch + 10)
+            .unwrap();
+        let report = state.verify_invariants(&store).unwrap();
+        assert!(report.is_consistent(), "{:?}", report);
+
+        // Tampered account usage is reported as drift
+        let mut accounts = state.accounts.hamt(&store).unwrap();
+        let mut account = accounts.get_or_err(&subscribers[0]).unwrap();
+        account.capacity_used += 1;
+        state.accounts.save_tracked(
+            accounts
+                .set_and_flush_tracked(&subscribers[0], account)
+                .unwrap(),
+        );
+        state.capacity_used += 1;
+        let report = state.verify_invariants(&store).unwrap();
+        assert!(!report.is_consistent());
+        assert_eq!(report.capacity_used, report.capacity_used_expected + 1);
+        assert_eq!(report.accounts_drifted.len(), 1);
+        assert_eq!(report.accounts_drifted[0].address, subscribers[0]);
+        assert!(state.reconcile(&store).is_err());
     }
 
     #[test]
-    fn test_adjust_blob_ttls_for_account() {
+    fn test_delete_blobs_by_prefix() {
         setup_logs();
         let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_whole(10),
+                current_epoch,
+            )
+            .unwrap();
 
-        const HOUR: ChainEpoch = 3600;
-        const TWO_HOURS: ChainEpoch = HOUR * 2;
-        const DAY: ChainEpoch = HOUR * 24;
-        const YEAR: ChainEpoch = DAY * 365;
+        // Add blobs under two different ID prefixes
+        let mut hashes = Vec::new();
+        let mut total_size = 0;
+        for id in ["photos/a", "photos/b", "docs/c"] {
+            let (hash, size) = new_hash(1024);
+            state
+                .add_blob(
+                    &config,
+                    &store,
+                    subscriber,
+                    subscriber,
+                    current_epoch,
+                    hash,
+                    new_metadata_hash(),
+                    SubscriptionId::new(id).unwrap(),
+                    size,
+                    Some(config.blob_min_ttl),
+                    new_pk(),
+                    TokenAmount::zero(),
+                    false,
+                    1,
+                )
+                .unwrap();
+            hashes.push(hash);
+            total_size += size;
+        }
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        let committed_before = account.credit_committed;
 
-        let blobs_ttls: Vec<Option<ChainEpoch>> =
-            vec![None, Some(HOUR), Some(TWO_HOURS), Some(DAY), Some(YEAR)];
+        let outcome = state
+            .delete_blobs_by_prefix(
+                &config,
+                &store,
+                subscriber,
+                subscriber,
+                current_epoch,
+                "photos/",
+                None,
+                None,
+            )
+            .unwrap();
+        assert_eq!(outcome.deleted.len(), 2);
+        assert!(outcome.next_key.is_none());
+        assert!(outcome.deleted.iter().all(|d| d.delete_from_disc));
+        assert!(outcome
+            .deleted
+            .iter()
+            .all(|d| d.id.as_str().starts_with("photos/")));
+        let released: u64 = outcome.deleted.iter().map(|d| d.bytes_released).sum();
+        assert_eq!(state.capacity_used, total_size - released);
+
+        // Committed credit for the deleted subscriptions was refunded
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert!(outcome.credit_refunded.is_positive());
+        assert_eq!(
+            account.credit_committed,
+            committed_before - &outcome.credit_refunded
+        );
 
-        struct TestCase {
-            name: &'static str,
-            account_ttl: TtlStatus,
-            expected_ttls: Vec<ChainEpoch>,
-            limit: Option<u32>, // None means process all at once
-        }
+        // The remaining blob is untouched
+        assert!(state.get_blob(&store, hashes[2]).unwrap().is_some());
+        assert!(state.get_blob(&store, hashes[0]).unwrap().is_none());
+        assert!(state.get_blob(&store, hashes[1]).unwrap().is_none());
 
-        let test_cases = vec![
-            TestCase {
-                name: "Set to zero with Reduced status",
-                account_ttl: TtlStatus::Reduced,
-                expected_ttls: vec![0, 0, 0, 0, 0],
-                limit: None,
-            },
-            TestCase {
-                name: "Set to default with Default status",
-                account_ttl: TtlStatus::Default,
-                expected_ttls: vec![DAY, HOUR, TWO_HOURS, DAY, DAY],
-                limit: None,
-            },
-            TestCase {
-                name: "Set to extended with Extended status",
-                account_ttl: TtlStatus::Extended,
-                expected_ttls: vec![DAY, HOUR, TWO_HOURS, DAY, YEAR],
-                limit: None,
-            },
-        ];
+        // A prefix that matches nothing is a no-op
+        let outcome = state
+            .delete_blobs_by_prefix(
+                &config,
+                &store,
+                subscriber,
+                subscriber,
+                current_epoch,
+                "videos/",
+                None,
+                None,
+            )
+            .unwrap();
+        assert!(outcome.deleted.is_empty());
+        assert!(outcome.credit_refunded.is_zero());
+    }
 
-        for tc in test_cases {
-            let store = MemoryBlockstore::default();
-            let mut state = State::new(&store).unwrap();
-            let addr = new_address();
-            let current_epoch = ChainEpoch::from(1);
+    #[test]
+    fn test_trash_and_undelete_blob() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_whole(10),
+                current_epoch,
+            )
+            .unwrap();
 
-            // Setup account with credits and TTL status
-            let token = TokenAmount::from_whole(1000);
+        let resolve = |state: &mut State, epoch: ChainEpoch, hash, size, id: &SubscriptionId| {
+            let source = state
+                .get_blob(&store, hash)
+                .unwrap()
+                .unwrap()
+                .subscribers
+                .hamt(&store)
+                .unwrap()
+                .get(&subscriber)
+                .unwrap()
+                .unwrap()
+                .hamt(&store)
+                .unwrap()
+                .get(id)
+                .unwrap()
+                .unwrap()
+                .source;
             state
-                .buy_credit(&config, &store, addr, token, current_epoch)
+                .set_blob_pending(&store, subscriber, hash, size, id.clone(), source)
                 .unwrap();
-
-            // Set extended TTL status to allow adding all blobs
             state
-                .set_account_status(&config, &store, addr, TtlStatus::Extended, current_epoch)
+                .finalize_blob(
+                    &config,
+                    &store,
+                    subscriber,
+                    epoch,
+                    hash,
+                    id.clone(),
+                    BlobStatus::Resolved,
+                    None,
+                )
                 .unwrap();
+        };
 
-            // Add blobs
-            let mut blob_hashes = Vec::new();
-            let mut total_cost = Credit::zero();
-            let mut expected_credits = Credit::zero();
-            for (i, ttl) in blobs_ttls.iter().enumerate() {
-                let size = (i + 1) * 1024;
-                let (hash, _) = new_hash(size);
-                let size = size as u64;
-                let id = SubscriptionId::try_from(format!("blob-{}", i)).unwrap();
-                let source = new_pk();
-                blob_hashes.push(hash);
+        let (hash, size) = new_hash(1024);
+        let id = SubscriptionId::new("foo").unwrap();
+        let sub = state
+            .add_blob(
+                &config,
+                &store,
+                subscriber,
+                subscriber,
+                current_epoch,
+                hash,
+                new_metadata_hash(),
+                id.clone(),
+                size,
+                Some(config.blob_min_ttl * 2),
+                new_pk(),
+                TokenAmount::zero(),
+                false,
+                1,
+            )
+            .unwrap()
+            .0;
+        resolve(&mut state, current_epoch, hash, size, &id);
 
-                state
-                    .add_blob(
-                        &config,
-                        &store,
-                        addr,
-                        addr,
-                        current_epoch,
-                        hash,
-                        new_metadata_hash(),
-                        id.clone(),
-                        size,
-                        *ttl,
-                        source,
-                        TokenAmount::zero(),
-                    )
-                    .unwrap();
-                state
-                    .set_blob_pending(&store, addr, hash, size, id.clone(), source)
-                    .unwrap();
-                state
-                    .finalize_blob(
-                        &config,
-                        &store,
-                        addr,
-                        current_epoch,
-                        hash,
-                        id,
-                        BlobStatus::Resolved,
-                    )
-                    .unwrap();
+        // Trashing releases capacity and committed credit, but keeps the blob in the trash
+        let trash_epoch = ChainEpoch::from(11);
+        let res = state.trash_blob(
+            &config,
+            &store,
+            subscriber,
+            subscriber,
+            trash_epoch,
+            hash,
+            id.clone(),
+        );
+        assert_eq!(res.unwrap(), size);
+        assert_eq!(state.capacity_used, 0);
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(account.capacity_used, 0);
+        assert!(account.credit_committed.is_zero());
+        assert!(state.get_blob(&store, hash).unwrap().is_none());
+        assert!(state
+            .trash
+            .hamt(&store)
+            .unwrap()
+            .contains_key(&hash)
+            .unwrap());
 
-                total_cost += Credit::from_whole(
-                    state.get_storage_cost(ttl.unwrap_or(config.blob_default_ttl), &size),
-                );
-                expected_credits +=
-                    Credit::from_whole(state.get_storage_cost(tc.expected_ttls[i], &size));
-            }
+        // Restoring keeps the original expiry and commits credit again
+        let restore_epoch = ChainEpoch::from(21);
+        let restored = state
+            .undelete_blob(
+                &config,
+                &store,
+                subscriber,
+                subscriber,
+                restore_epoch,
+                hash,
+                id.clone(),
+            )
+            .unwrap();
+        assert_eq!(restored.expiry, sub.expiry);
+        assert_eq!(state.capacity_used, size);
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(account.capacity_used, size);
+        assert_eq!(
+            account.credit_committed,
+            Credit::from_whole(state.get_storage_cost(sub.expiry - restore_epoch, &size))
+        );
+        assert!(!state
+            .trash
+            .hamt(&store)
+            .unwrap()
+            .contains_key(&hash)
+            .unwrap());
 
-            let account = state.get_account(&store, addr).unwrap().unwrap();
-            assert_eq!(
-                account.credit_committed, total_cost,
-                "Test case '{}' failed: committed credits don't match",
-                tc.name
-            );
+        // A restored subscription cannot be restored again
+        let res = state.undelete_blob(
+            &config,
+            &store,
+            subscriber,
+            subscriber,
+            restore_epoch,
+            hash,
+            id.clone(),
+        );
+        assert!(res.is_err());
 
-            state
-                .set_account_status(&config, &store, addr, tc.account_ttl, current_epoch)
-                .unwrap();
+        // Once the trash period has passed, the blob is purged and deleted from disc
+        resolve(&mut state, restore_epoch, hash, size, &id);
+        let trash_epoch = ChainEpoch::from(31);
+        state
+            .trash_blob(
+                &config,
+                &store,
+                subscriber,
+                subscriber,
+                trash_epoch,
+                hash,
+                id.clone(),
+            )
+            .unwrap();
+        let outcome = state
+            .debit_accounts(&config, &store, trash_epoch + 1)
+            .unwrap();
+        assert!(outcome.delete_from_disc.is_empty());
+        let purge_epoch = trash_epoch + config.blob_trash_period;
+        let outcome = state.debit_accounts(&config, &store, purge_epoch).unwrap();
+        assert!(outcome.delete_from_disc.contains(&hash));
+        assert!(!state
+            .trash
+            .hamt(&store)
+            .unwrap()
+            .contains_key(&hash)
+            .unwrap());
+        let res = state.undelete_blob(
+            &config,
+            &store,
+            subscriber,
+            subscriber,
+            purge_epoch,
+            hash,
+            id,
+        );
+        assert!(res.is_err());
+    }
 
-            let res =
-                state.trim_blob_expiries(&config, &store, addr, current_epoch, None, tc.limit);
-            assert!(
-                res.is_ok(),
-                "Test case '{}' failed to adjust TTLs: {}",
-                tc.name,
-                res.err().unwrap()
-            );
+    #[test]
+    fn test_set_blob_metadata() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_whole(10),
+                current_epoch,
+            )
+            .unwrap();
+        let (hash, size) = new_hash(1024);
+        let id = SubscriptionId::default();
+        state
+            .add_blob(
+                &config,
+                &store,
+                subscriber,
+                subscriber,
+                current_epoch,
+                hash,
+                new_metadata_hash(),
+                id.clone(),
+                size,
+                None,
+                new_pk(),
+                TokenAmount::zero(),
+                false,
+                1,
+            )
+            .unwrap();
+        let metadata = state
+            .get_blob_metadata(&store, subscriber, hash, id.clone())
+            .unwrap();
+        assert_eq!(metadata, Some(HashMap::new()));
+
+        // Insert entries
+        let updates = HashMap::from([
+            ("content-type".to_string(), Some("text/plain".to_string())),
+            ("filename".to_string(), Some("hello.txt".to_string())),
+        ]);
+        let metadata = state
+            .set_blob_metadata(
+                &store,
+                subscriber,
+                subscriber,
+                current_epoch,
+                hash,
+                id.clone(),
+                updates,
+            )
+            .unwrap();
+        assert_eq!(metadata.len(), 2);
 
-            // Verify TTLs were adjusted correctly
-            for (i, hash) in blob_hashes.iter().enumerate() {
-                // If the TTL is zero, the blob should be deleted
-                if tc.expected_ttls[i] == 0 {
-                    assert!(
-                        state.get_blob(&store, *hash).unwrap().is_none(),
-                        "Test case '{}' failed: blob {} not deleted",
-                        tc.name,
-                        i
-                    );
-                } else {
-                    let blob = state.get_blob(&store, *hash).unwrap().unwrap();
-                    let subscribers = blob.subscribers.hamt(&store).unwrap();
-                    let group = subscribers.get(&addr).unwrap().unwrap();
-                    let group_hamt = group.hamt(&store).unwrap();
-                    let sub = group_hamt
-                        .get(&SubscriptionId::new(&format!("blob-{}", i)).unwrap())
-                        .unwrap()
-                        .unwrap();
+        // Update one entry and delete the other
+        let updates = HashMap::from([
+            ("content-type".to_string(), Some("text/html".to_string())),
+            ("filename".to_string(), None),
+        ]);
+        state
+            .set_blob_metadata(
+                &store,
+                subscriber,
+                subscriber,
+                current_epoch,
+                hash,
+                id.clone(),
+                updates,
+            )
+            .unwrap();
+        let metadata = state
+            .get_blob_metadata(&store, subscriber, hash, id.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            metadata,
+            HashMap::from([("content-type".to_string(), "text/html".to_string())])
+        );
 
-                    assert_eq!(
-                        sub.expiry - sub.added,
-                        tc.expected_ttls[i],
-                        "Test case '{}' failed: blob {} TTL not adjusted correctly. Expected {}, got {}",
-                        tc.name,
-                        i,
-                        tc.expected_ttls[i],
-                        sub.expiry - sub.added,
-                    );
-                }
-            }
+        // Values must be within the size limit
+        let updates = HashMap::from([(
+            "content-type".to_string(),
+            Some("a".repeat(MAX_BLOB_METADATA_VALUE_SIZE as usize + 1)),
+        )]);
+        let res = state.set_blob_metadata(
+            &store,
+            subscriber,
+            subscriber,
+            current_epoch,
+            hash,
+            id.clone(),
+            updates,
+        );
+        assert!(res.is_err());
 
-            let account = state.get_account(&store, addr).unwrap().unwrap();
-            assert_eq!(
-                account.credit_committed, expected_credits,
-                "Test case '{}' failed: account's committed credits after blob adjustment don't match",
-                tc.name
-            );
+        // The number of entries is capped
+        let updates = (0..MAX_BLOB_METADATA_ENTRIES)
+            .map(|i| (format!("key-{}", i), Some("value".to_string())))
+            .collect();
+        let res = state.set_blob_metadata(
+            &store,
+            subscriber,
+            subscriber,
+            current_epoch,
+            hash,
+            id.clone(),
+            updates,
+        );
+        assert!(res.is_err());
 
-            assert_eq!(
-                state.credit_committed, expected_credits,
-                "Test case '{}' failed: state's committed credits after blob adjustment don't match",
-                tc.name
-            );
-        }
+        // Only the subscriber or delegate can update metadata
+        let updates = HashMap::from([("filename".to_string(), Some("other.txt".to_string()))]);
+        let res = state.set_blob_metadata(
+            &store,
+            new_address(),
+            subscriber,
+            current_epoch,
+            hash,
+            id.clone(),
+            updates,
+        );
+        assert_eq!(
+            res.err().unwrap().exit_code(),
+            fvm_shared::error::ExitCode::USR_FORBIDDEN
+        );
+        let metadata = state
+            .get_blob_metadata(&store, subscriber, hash, id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.len(), 1);
     }
 
     #[test]
-    fn test_adjust_blob_ttls_pagination() {
+    fn test_add_blob_exceeds_account_quota() {
         setup_logs();
         let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_whole(10),
+                current_epoch,
+            )
+            .unwrap();
+        state
+            .set_account_quota(&config, &store, subscriber, Some(1536), current_epoch)
+            .unwrap();
 
-        // Test cases for pagination
-        struct PaginationTest {
-            name: &'static str,
-            limit: Option<u32>,
-            start: Option<usize>,
-            expected_next_key: Option<usize>,
-            expected_processed: usize,
-        }
+        let add_blob = |state: &mut State, hash: Hash, size: u64, id: SubscriptionId| {
+            state.add_blob(
+                &config,
+                &store,
+                subscriber,
+                subscriber,
+                current_epoch,
+                hash,
+                new_metadata_hash(),
+                id,
+                size,
+                None,
+                new_pk(),
+                TokenAmount::zero(),
+                false,
+                1,
+            )
+        };
 
-        let test_cases = vec![
-            PaginationTest {
-                name: "Process all at once",
-                limit: None,
-                start: None,
-                expected_next_key: None,
-                expected_processed: 5,
-            },
-            PaginationTest {
-                name: "Process two at a time from beginning",
-                limit: Some(2),
-                start: None,
-                expected_next_key: Some(2),
-                expected_processed: 2,
-            },
-            PaginationTest {
-                name: "Process one at a time with offset",
-                limit: Some(1),
-                start: Some(1),
-                expected_next_key: Some(2),
-                expected_processed: 1,
-            },
-            PaginationTest {
-                name: "Out of bounds limit",
-                limit: Some(10),
-                start: Some(1),
-                expected_next_key: None,
-                expected_processed: 4,
-            },
-            PaginationTest {
-                name: "With offset ending at last item",
-                limit: Some(2),
-                start: Some(3),
-                expected_next_key: None,
-                expected_processed: 2,
-            },
-        ];
-
-        for tc in test_cases {
-            let store = MemoryBlockstore::default();
-            let mut state = State::new(&store).unwrap();
-            let addr = new_address();
-            let current_epoch = ChainEpoch::from(1);
-
-            // Setup account with credits and Extended TTL status to allow adding all blobs
-            state
-                .buy_credit(
-                    &config,
-                    &store,
-                    addr,
-                    TokenAmount::from_whole(1000),
-                    current_epoch,
-                )
-                .unwrap();
-            state
-                .set_account_status(&config, &store, addr, TtlStatus::Extended, current_epoch)
-                .unwrap();
+        // First blob fits within the quota
+        let (hash1, size1) = new_hash(1024);
+        add_blob(&mut state, hash1, size1, SubscriptionId::default()).unwrap();
 
-            // Add 5 blobs with different sizes to ensure different hashes
-            for i in 0..5 {
-                let (hash, size) = new_hash((i + 1) * 1024);
-                let id = SubscriptionId::try_from(format!("blob-{}", i)).unwrap();
-                let source = new_pk();
-                state
-                    .add_blob(
-                        &config,
-                        &store,
-                        addr,
-                        addr,
-                        current_epoch,
-                        hash,
-                        new_metadata_hash(),
-                        id.clone(),
-                        size,
-                        Some(7200), // 2 hours
-                        source,
-                        TokenAmount::zero(),
-                    )
-                    .unwrap();
-                state
-                    .set_blob_pending(&store, addr, hash, size, id.clone(), source)
-                    .unwrap();
-                state
-                    .finalize_blob(
-                        &config,
-                        &store,
-                        addr,
-                        current_epoch,
-                        hash,
-                        id,
-                        BlobStatus::Resolved,
-                    )
-                    .unwrap();
-            }
+        // Second blob would exceed the quota, even though there's plenty of credit
+        let (hash2, size2) = new_hash(1024);
+        let res = add_blob(&mut state, hash2, size2, SubscriptionId::default());
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap().msg(),
+            format!(
+                "account {} would exceed its storage quota (quota: 1536; required: 2048)",
+                subscriber
+            )
+        );
 
-            // range over all blobs and store their hashes
-            let mut blob_hashes = Vec::with_capacity(5);
-            for _ in 0..5 {
-                let res = state.blobs.hamt(&store).unwrap().for_each(
-                    |hash, _| -> Result<(), ActorError> {
-                        blob_hashes.push(hash);
-                        Ok(())
-                    },
-                );
-                assert!(
-                    res.is_ok(),
-                    "Failed to iterate over blobs: {}",
-                    res.err().unwrap()
-                );
-            }
+        // Adding another subscription to an already stored blob doesn't use more capacity
+        let id = SubscriptionId::new("foo").unwrap();
+        add_blob(&mut state, hash1, size1, id).unwrap();
 
-            // Change to Reduced status and process blobs with pagination
-            state
-                .set_account_status(&config, &store, addr, TtlStatus::Reduced, current_epoch)
-                .unwrap();
+        // Removing the quota allows the blob to be added
+        state
+            .set_account_quota(&config, &store, subscriber, None, current_epoch)
+            .unwrap();
+        add_blob(&mut state, hash2, size2, SubscriptionId::default()).unwrap();
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(account.capacity_used, size1 + size2);
+        assert_eq!(account.max_capacity, None);
+    }
 
-            let res = state.trim_blob_expiries(
+    #[test]
+    fn test_add_blob_rate_limit() {
+        setup_logs();
+        let config = RecallConfig {
+            blob_add_rate_limit_count: 2,
+            blob_add_rate_limit_bytes: 3072,
+            ..Default::default()
+        };
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(
                 &config,
                 &store,
-                addr,
+                subscriber,
+                TokenAmount::from_whole(10),
                 current_epoch,
-                tc.start.map(|ind| blob_hashes[ind]),
-                tc.limit,
-            );
-            assert!(
-                res.is_ok(),
-                "Test case '{}' failed to adjust TTLs: {}",
-                tc.name,
-                res.err().unwrap()
-            );
+            )
+            .unwrap();
 
-            let (processed, next, deleted_blobs) = res.unwrap();
+        let add_blob = |state: &mut State, epoch: ChainEpoch, hash: Hash, size: u64, id: &str| {
+            state.add_blob(
+                &config,
+                &store,
+                subscriber,
+                subscriber,
+                epoch,
+                hash,
+                new_metadata_hash(),
+                SubscriptionId::new(id).unwrap(),
+                size,
+                None,
+                new_pk(),
+                TokenAmount::zero(),
+                false,
+                1,
+            )
+        };
 
-            assert_eq!(
-                processed as usize, tc.expected_processed,
-                "Test case '{}' had unexpected number of items processed",
-                tc.name
-            );
+        // Two new subscriptions fit within the count limit
+        let (hash1, size1) = new_hash(1024);
+        add_blob(&mut state, current_epoch, hash1, size1, "a").unwrap();
+        let (hash2, size2) = new_hash(1024);
+        add_blob(&mut state, current_epoch, hash2, size2, "b").unwrap();
 
-            assert_eq!(
-                deleted_blobs.len(),
-                tc.expected_processed,
-                "Test case '{}' had unexpected number of deleted blobs",
-                tc.name
-            );
+        // A third new subscription is rejected, even to an existing blob
+        let res = add_blob(&mut state, current_epoch, hash1, size1, "c");
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap().msg(),
+            format!(
+                "account {} exceeded its limit of 2 new blobs per {} epochs; please try again later",
+                subscriber, config.blob_credit_debit_interval
+            )
+        );
 
-            if let Some(expected_next_key) = tc.expected_next_key {
-                assert!(next.is_some(), "Test case '{}' expected next key", tc.name);
-                assert_eq!(
-                    next.unwrap(),
-                    blob_hashes[expected_next_key],
-                    "Test case '{}' had unexpected next key",
-                    tc.name
-                );
-            } else {
-                assert!(next.is_none(), "Test case '{}' had no next key", tc.name);
-            }
-        }
+        // Updating an existing subscription does not count toward the limit
+        add_blob(&mut state, current_epoch, hash1, size1, "a").unwrap();
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(account.add_window_count, 2);
+        assert_eq!(account.add_window_bytes, size1 + size2);
+
+        // The window restarts after the debit interval, but the byte limit still applies
+        let next_epoch = current_epoch + config.blob_credit_debit_interval;
+        let (hash3, size3) = new_hash(4096);
+        let res = add_blob(&mut state, next_epoch, hash3, size3, "d");
+        assert!(res.is_err());
+        let (hash4, size4) = new_hash(2048);
+        add_blob(&mut state, next_epoch, hash4, size4, "e").unwrap();
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(account.add_window_start, next_epoch);
+        assert_eq!(account.add_window_count, 1);
+        assert_eq!(account.add_window_bytes, size4);
     }
 
     #[test]
-    fn test_adjust_blob_ttls_for_multiple_accounts() {
+    fn test_add_blob_size_and_pending_limits() {
         setup_logs();
-
-        let config = RecallConfig::default();
+        let config = RecallConfig {
+            blob_max_size: 2048,
+            blob_max_pending_per_account: 2,
+            ..Default::default()
+        };
         let store = MemoryBlockstore::default();
         let mut state = State::new(&store).unwrap();
-        let account1 = new_address();
-        let account2 = new_address();
+        let subscriber = new_address();
         let current_epoch = ChainEpoch::from(1);
-
-        // Setup accounts with credits and Extended TTL status to allow adding all blobs
         state
             .buy_credit(
                 &config,
                 &store,
-                account1,
-                TokenAmount::from_whole(1000),
+                subscriber,
+                TokenAmount::from_whole(10),
                 current_epoch,
             )
             .unwrap();
-        state
-            .buy_credit(
+
+        let add_blob = |state: &mut State, hash: Hash, size: u64, source: PublicKey| {
+            state.add_blob(
                 &config,
                 &store,
-                account2,
-                TokenAmount::from_whole(1000),
+                subscriber,
+                subscriber,
                 current_epoch,
+                hash,
+                new_metadata_hash(),
+                SubscriptionId::default(),
+                size,
+                None,
+                source,
+                TokenAmount::zero(),
+                false,
+                1,
+            )
+        };
+
+        // Blobs larger than the maximum size are rejected
+        let (hash, size) = new_hash(4096);
+        let res = add_blob(&mut state, hash, size, new_pk());
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap().msg(),
+            "blob size 4096 exceeds maximum allowed 2048"
+        );
+
+        // Two blobs can wait to be resolved, whether added or pending
+        let (hash1, size1) = new_hash(1024);
+        let source1 = new_pk();
+        add_blob(&mut state, hash1, size1, source1).unwrap();
+        state
+            .set_blob_pending(
+                &store,
+                subscriber,
+                hash1,
+                size1,
+                SubscriptionId::default(),
+                source1,
             )
             .unwrap();
+        let (hash2, size2) = new_hash(1024);
+        add_blob(&mut state, hash2, size2, new_pk()).unwrap();
+
+        // A third is rejected, but re-adding a blob that is already waiting is allowed
+        let (hash3, size3) = new_hash(1024);
+        let res = add_blob(&mut state, hash3, size3, new_pk());
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap().msg(),
+            format!(
+                "account {} has reached its limit of 2 blobs waiting to be resolved; please try again later",
+                subscriber
+            )
+        );
+        add_blob(&mut state, hash2, size2, new_pk()).unwrap();
+
+        // Resolving a blob frees up a slot
         state
-            .set_account_status(
+            .finalize_blob(
                 &config,
                 &store,
-                account1,
-                TtlStatus::Extended,
+                subscriber,
                 current_epoch,
+                hash1,
+                SubscriptionId::default(),
+                BlobStatus::Resolved,
+                None,
             )
             .unwrap();
+        add_blob(&mut state, hash3, size3, new_pk()).unwrap();
+    }
+
+    #[test]
+    fn test_add_blob_replication() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        let token_amount = TokenAmount::from_whole(10);
         state
-            .set_account_status(
+            .buy_credit(
                 &config,
                 &store,
-                account2,
-                TtlStatus::Extended,
+                subscriber,
+                token_amount.clone(),
                 current_epoch,
             )
             .unwrap();
+        let credit_bought = token_amount * &config.token_credit_rate;
 
-        // Add blobs for both accounts
-        let mut blob_hashes_account1 = Vec::new();
-        let mut blob_hashes_account2 = Vec::new();
-        for i in 0..3 {
-            let (hash, size) = new_hash((i + 1) * 1024);
-            let id = SubscriptionId::try_from(format!("blob-1-{}", i)).unwrap();
-            let source = new_pk();
-            blob_hashes_account1.push(hash);
-            state
-                .add_blob(
-                    &config,
-                    &store,
-                    account1,
-                    account1,
-                    current_epoch,
-                    hash,
-                    new_metadata_hash(),
-                    id.clone(),
-                    size,
-                    Some(7200), // 2 hours
-                    source,
-                    TokenAmount::zero(),
-                )
-                .unwrap();
-            state
-                .set_blob_pending(&store, account1, hash, size, id.clone(), source)
-                .unwrap();
-            state
-                .finalize_blob(
-                    &config,
-                    &store,
-                    account1,
-                    current_epoch,
-                    hash,
-                    id,
-                    BlobStatus::Resolved,
-                )
-                .unwrap();
-        }
-        for i in 0..3 {
-            let (hash, size) = new_hash((i + 1) * 1024);
-            let id = SubscriptionId::try_from(format!("blob-2-{}", i)).unwrap();
-            let source = new_pk();
-            blob_hashes_account2.push(hash);
+        let add_blob = |state: &mut State, hash: Hash, size: u64, replication: u32| {
+            state.add_blob(
+                &config,
+                &store,
+                subscriber,
+                subscriber,
+                current_epoch,
+                hash,
+                new_metadata_hash(),
+                SubscriptionId::default(),
+                size,
+                Some(config.blob_min_ttl),
+                new_pk(),
+                TokenAmount::zero(),
+                false,
+                replication,
+            )
+        };
+
+        // Replication must be within bounds
+        let (hash, size) = new_hash(1024);
+        assert!(add_blob(&mut state, hash, size, 0).is_err());
+        assert!(add_blob(&mut state, hash, size, MAX_BLOB_REPLICATION + 1).is_err());
+
+        // Base storage is committed, and additional replicas are charged upfront
+        let (sub, _) = add_blob(&mut state, hash, size, 3).unwrap();
+        assert_eq!(sub.replication, 3);
+        let cost = |replicas: i64| {
+            Credit::from_whole(state.get_storage_cost(config.blob_min_ttl * replicas, &size))
+        };
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(account.credit_committed, cost(1));
+        assert_eq!(state.credit_debited, cost(2));
+        assert_eq!(account.credit_free, &credit_bought - &cost(3));
+
+        // The replication target is surfaced with pending blobs
+        state
+            .set_blob_pending(
+                &store,
+                subscriber,
+                hash,
+                size,
+                SubscriptionId::default(),
+                sub.source,
+            )
+            .unwrap();
+        let pending = state.get_pending_blobs(&store, 10, None).unwrap().blobs;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].0, hash);
+        assert_eq!(pending[0].2, 3);
+    }
+
+    #[test]
+    fn test_get_added_blobs_paginated() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        let token_amount = TokenAmount::from_whole(10);
+        state
+            .buy_credit(&config, &store, subscriber, token_amount, current_epoch)
+            .unwrap();
+
+        let mut total_size = 0;
+        for _ in 0..3 {
+            let (hash, size) = new_hash(1024);
+            total_size += size;
             state
                 .add_blob(
                     &config,
                     &store,
-                    account2,
-                    account2,
+                    subscriber,
+                    subscriber,
                     current_epoch,
                     hash,
                     new_metadata_hash(),
-                    id.clone(),
+                    SubscriptionId::default(),
                     size,
-                    Some(7200), // 2 hours
-                    source,
+                    Some(config.blob_min_ttl),
+                    new_pk(),
                     TokenAmount::zero(),
-                )
-                .unwrap();
-            state
-                .set_blob_pending(&store, account2, hash, size, id.clone(), source)
-                .unwrap();
-            state
-                .finalize_blob(
-                    &config,
-                    &store,
-                    account2,
-                    current_epoch,
-                    hash,
-                    id,
-                    BlobStatus::Resolved,
+                    false,
+                    1,
                 )
                 .unwrap();
         }
+        let stats = state.get_stats(&config, TokenAmount::zero());
+        assert_eq!(stats.num_added, 3);
+        assert_eq!(stats.bytes_added, total_size);
+        assert_eq!(stats.num_resolving, 0);
 
-        // Change TTL status for account1 and adjust blobs
-        state
-            .set_account_status(&config, &store, account1, TtlStatus::Reduced, current_epoch)
-            .unwrap();
-        let res = state.trim_blob_expiries(&config, &store, account1, current_epoch, None, None);
-        assert!(
-            res.is_ok(),
-            "Failed to adjust TTLs for account1: {}",
-            res.err().unwrap()
-        );
-
-        // Verify account1's blobs were adjusted
-        for hash in &blob_hashes_account1 {
-            assert!(
-                state.get_blob(&store, *hash).unwrap().is_none(),
-                "Blob {} for account1 was not deleted",
-                hash,
-            );
-        }
+        // The first page ends with a key to continue from
+        let first = state.get_added_blobs(&store, 2, None).unwrap();
+        assert_eq!(first.blobs.len(), 2);
+        assert!(first.next_key.is_some());
 
-        // Verify account2's blobs were not adjusted
-        for hash in &blob_hashes_account2 {
-            assert!(
-                state.get_blob(&store, *hash).unwrap().is_some(),
-                "Blob {} for account2 was incorrectly deleted",
-                hash,
-            );
-        }
+        // The second page picks up where the first left off
+        let second = state.get_added_blobs(&store, 2, first.next_key).unwrap();
+        assert_eq!(second.blobs.len(), 1);
+        assert!(second.next_key.is_none());
+        assert!(first
+            .blobs
+            .iter()
+            .all(|(hash, _, _)| second.blobs[0].0 != *hash));
     }
 
     #[test]
-    fn test_simulate_one_day() {
+    fn test_finalize_blob_resolved() {
         setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        let amount = TokenAmount::from_whole(10);
+        state
+            .buy_credit(&config, &store, subscriber, amount.clone(), current_epoch)
+            .unwrap();
 
-        let config = RecallConfig {
-            blob_credit_debit_interval: ChainEpoch::from(60),
-            blob_min_ttl: ChainEpoch::from(10),
-            ..Default::default()
-        };
-
-        #[derive(Clone, Debug, Hash, PartialEq, Eq)]
-        struct TestBlob {
-            hash: Hash,
-            metadata_hash: Hash,
-            size: u64,
-            added: Option<ChainEpoch>,
-            resolve: Option<ChainEpoch>,
-        }
+        // Add a blob
+        let (hash, size) = new_hash(1024);
+        let source = new_pk();
+        let res = state.add_blob(
+            &config,
+            &store,
+            subscriber,
+            subscriber,
+            current_epoch,
+            hash,
+            new_metadata_hash(),
+            SubscriptionId::default(),
+            size,
+            None,
+            source,
+            TokenAmount::zero(),
+            false,
+            1,
+        );
+        assert!(res.is_ok());
 
-        fn generate_test_blobs(count: i64, min_size: usize, max_size: usize) -> Vec<TestBlob> {
-            let mut blobs = Vec::new();
-            let mut rng = rand::thread_rng();
+        // Set to status pending
+        let res = state.set_blob_pending(
+            &store,
+            subscriber,
+            hash,
+            size,
+            SubscriptionId::default(),
+            source,
+        );
+        assert!(res.is_ok());
 
-            for _ in 0..count {
-                let size = rng.gen_range(min_size..=max_size);
-                let (hash, size) = new_hash(size);
-                blobs.push(TestBlob {
-                    hash,
-                    metadata_hash: new_metadata_hash(),
-                    size,
-                    added: None,
-                    resolve: None,
-                });
-            }
-            blobs
-        }
+        // Finalize as resolved
+        let finalize_epoch = ChainEpoch::from(11);
+        let res = state.finalize_blob(
+            &config,
+            &store,
+            subscriber,
+            finalize_epoch,
+            hash,
+            SubscriptionId::default(),
+            BlobStatus::Resolved,
+            None,
+        );
+        assert!(res.is_ok());
 
-        fn generate_test_users<BS: Blockstore>(
-            config: &RecallConfig,
-            store: &BS,
-            state: &mut State,
-            credit_tokens: TokenAmount,
-            count: i64,
-        ) -> Vec<Address> {
-            let mut users = Vec::new();
-            for _ in 0..count {
-                let user = new_address();
-                state
-                    .buy_credit(config, &store, user, credit_tokens.clone(), 0)
-                    .unwrap();
-                users.push(user);
-            }
-            users
-        }
+        // Check status
+        let status = state
+            .get_blob_status(&store, subscriber, hash, SubscriptionId::default())
+            .unwrap()
+            .unwrap();
+        assert!(matches!(status, BlobStatus::Resolved));
 
-        // Test params
-        let epochs: i64 = 360; // num. epochs to run test for
-        let user_pool_size: i64 = 10; // some may not be used, some will be used more than once
-        let blob_pool_size: i64 = epochs; // some may not be used, some will be used more than once
-        let min_ttl = config.blob_min_ttl;
-        let max_ttl = epochs;
-        let min_size = 8;
-        let max_size = 1024;
-        let add_intervals = [1, 2, 4, 8, 10, 12, 15, 20]; // used to add at random intervals
-        let max_resolve_epochs = 30; // max num. epochs in future to resolve
-        let debit_interval: i64 = config.blob_credit_debit_interval; // interval at which to debit all accounts
-        let percent_fail_resolve = 0.1; // controls % of subscriptions that fail resolve
+        // Check indexes
+        assert_eq!(state.expiries.len(&store).unwrap(), 1);
+        assert_eq!(state.added.len(), 0);
+        assert_eq!(state.pending.len(), 0);
+    }
 
-        // Set up store and state
+    #[test]
+    fn test_finalize_blob_resolved_size_mismatch() {
+        setup_logs();
+        let config = RecallConfig::default();
         let store = MemoryBlockstore::default();
         let mut state = State::new(&store).unwrap();
-        let mut rng = rand::thread_rng();
-
-        // Get some users
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        let amount = TokenAmount::from_whole(10);
+        state
+            .buy_credit(&config, &store, subscriber, amount.clone(), current_epoch)
+            .unwrap();
+
+        // Add a blob with an understated size
+        let (hash, size) = new_hash(1024);
+        let source = new_pk();
+        state
+            .add_blob(
+                &config,
+                &store,
+                subscriber,
+                subscriber,
+                current_epoch,
+                hash,
+                new_metadata_hash(),
+                SubscriptionId::default(),
+                size,
+                None,
+                source,
+                TokenAmount::zero(),
+                false,
+                1,
+            )
+            .unwrap();
+        state
+            .set_blob_pending(
+                &store,
+                subscriber,
+                hash,
+                size,
+                SubscriptionId::default(),
+                source,
+            )
+            .unwrap();
+
+        // The resolver reports a different size, so the blob is failed
+        let status = state
+            .finalize_blob(
+                &config,
+                &store,
+                subscriber,
+                current_epoch,
+                hash,
+                SubscriptionId::default(),
+                BlobStatus::Resolved,
+                Some(size * 2),
+            )
+            .unwrap();
+        assert!(matches!(status, BlobStatus::Failed));
+        let status = state
+            .get_blob_status(&store, subscriber, hash, SubscriptionId::default())
+            .unwrap()
+            .unwrap();
+        assert!(matches!(status, BlobStatus::Failed));
+
+        // The commitment is released
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(account.capacity_used, 0);
+        assert_eq!(account.credit_committed, Credit::zero());
+        assert_eq!(state.capacity_used, 0);
+        assert_eq!(state.pending.len(), 0);
+    }
+
+    #[test]
+    fn test_finalize_blob_failed() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        let amount = TokenAmount::from_whole(10);
+        state
+            .buy_credit(&config, &store, subscriber, amount.clone(), current_epoch)
+            .unwrap();
+        let credit_amount = amount * &config.token_credit_rate;
+
+        // Add a blob
+        let add_epoch = current_epoch;
+        let (hash, size) = new_hash(1024);
+        let source = new_pk();
+        let res = state.add_blob(
+            &config,
+            &store,
+            subscriber,
+            subscriber,
+            add_epoch,
+            hash,
+            new_metadata_hash(),
+            SubscriptionId::default(),
+            size,
+            None,
+            source,
+            TokenAmount::zero(),
+            false,
+            1,
+        );
+        assert!(res.is_ok());
+
+        // Set to status pending
+        let res = state.set_blob_pending(
+            &store,
+            subscriber,
+            hash,
+            size,
+            SubscriptionId::default(),
+            source,
+        );
+        assert!(res.is_ok());
+
+        // Finalize as failed
+        let finalize_epoch = ChainEpoch::from(11);
+        let res = state.finalize_blob(
+            &config,
+            &store,
+            subscriber,
+            finalize_epoch,
+            hash,
+            SubscriptionId::default(),
+            BlobStatus::Failed,
+            None,
+        );
+        assert!(res.is_ok());
+
+        // Check status
+        let status = state
+            .get_blob_status(&store, subscriber, hash, SubscriptionId::default())
+            .unwrap()
+            .unwrap();
+        assert!(matches!(status, BlobStatus::Failed));
+
+        // Check the account balance
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(account.last_debit_epoch, add_epoch);
+        assert_eq!(account.credit_committed, Credit::from_whole(0)); // credit was released
+        assert_eq!(account.credit_free, credit_amount);
+        assert_eq!(account.capacity_used, 0); // capacity was released
+
+        // Check state
+        assert_eq!(state.credit_committed, Credit::from_whole(0)); // credit was released
+        assert_eq!(state.credit_debited, Credit::from_whole(0));
+        assert_eq!(state.capacity_used, 0); // capacity was released
+
+        // Check indexes
+        assert_eq!(state.expiries.len(&store).unwrap(), 1); // remains until the blob is explicitly deleted
+        assert_eq!(state.added.len(), 0);
+        assert_eq!(state.pending.len(), 0);
+    }
+
+    #[test]
+    fn test_finalize_blob_failed_refund() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        let amount = TokenAmount::from_whole(10);
+        state
+            .buy_credit(&config, &store, subscriber, amount.clone(), current_epoch)
+            .unwrap();
+        let mut credit_amount = amount.clone() * &config.token_credit_rate;
+
+        assert!(state
+            .set_account_status(
+                &config,
+                &store,
+                subscriber,
+                TtlStatus::Extended,
+                current_epoch
+            )
+            .is_ok());
+
+        // Add a blob
+        let add_epoch = current_epoch;
+        let (hash, size) = new_hash(1024);
+        let source = new_pk();
+        let res = state.add_blob(
+            &config,
+            &store,
+            subscriber,
+            subscriber,
+            add_epoch,
+            hash,
+            new_metadata_hash(),
+            SubscriptionId::default(),
+            size,
+            Some(config.blob_min_ttl),
+            source,
+            TokenAmount::zero(),
+            false,
+            1,
+        );
+        assert!(res.is_ok());
+
+        // Check the account balance
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(account.last_debit_epoch, add_epoch);
+        assert_eq!(
+            account.credit_committed,
+            Credit::from_whole(config.blob_min_ttl as u64 * size),
+        );
+        credit_amount -= &account.credit_committed;
+        assert_eq!(account.credit_free, credit_amount);
+        assert_eq!(account.capacity_used, size);
+
+        // Check state
+        assert_eq!(state.credit_committed, account.credit_committed);
+        assert_eq!(state.credit_debited, Credit::from_whole(0));
+        assert_eq!(state.capacity_used, account.capacity_used); // capacity was released
+
+        // Debit accounts to trigger a refund when we fail below
+        let debit_epoch = ChainEpoch::from(11);
+        let deletes_from_disc = state
+            .debit_accounts(&config, &store, debit_epoch)
+            .unwrap()
+            .delete_from_disc;
+        assert!(deletes_from_disc.is_empty());
+
+        // Check the account balance
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(account.last_debit_epoch, debit_epoch);
+        assert_eq!(
+            account.credit_committed,
+            Credit::from_whole((config.blob_min_ttl - (debit_epoch - add_epoch)) as u64 * size),
+        );
+        assert_eq!(account.credit_free, credit_amount); // not changed
+        assert_eq!(account.capacity_used, size);
+
+        // Check state
+        assert_eq!(state.credit_committed, account.credit_committed);
+        assert_eq!(
+            state.credit_debited,
+            Credit::from_whole((debit_epoch - add_epoch) as u64 * size)
+        );
+        assert_eq!(state.capacity_used, account.capacity_used);
+
+        // Set to status pending
+        let res = state.set_blob_pending(
+            &store,
+            subscriber,
+            hash,
+            size,
+            SubscriptionId::default(),
+            source,
+        );
+        assert!(res.is_ok());
+
+        // Finalize as failed
+        let finalize_epoch = ChainEpoch::from(21);
+        let res = state.finalize_blob(
+            &config,
+            &store,
+            subscriber,
+            finalize_epoch,
+            hash,
+            SubscriptionId::default(),
+            BlobStatus::Failed,
+            None,
+        );
+        assert!(res.is_ok());
+
+        // Check status
+        let status = state
+            .get_blob_status(&store, subscriber, hash, SubscriptionId::default())
+            .unwrap()
+            .unwrap();
+        assert!(matches!(status, BlobStatus::Failed));
+
+        // Check the account balance
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(account.last_debit_epoch, debit_epoch);
+        assert_eq!(account.credit_committed, Credit::from_whole(0)); // credit was released
+        assert_eq!(
+            account.credit_free,
+            amount.clone() * &config.token_credit_rate
+        ); // credit was refunded
+        assert_eq!(account.capacity_used, 0); // capacity was released
+
+        // Check state
+        assert_eq!(state.credit_committed, Credit::from_whole(0)); // credit was released
+        assert_eq!(state.credit_debited, Credit::from_whole(0)); // credit was refunded and released
+        assert_eq!(state.capacity_used, 0); // capacity was released
+
+        // Check indexes
+        assert_eq!(state.expiries.len(&store).unwrap(), 1); // remains until the blob is explicitly deleted
+        assert_eq!(state.added.len(), 0);
+        assert_eq!(state.pending.len(), 0);
+    }
+
+    #[test]
+    fn test_delete_blob_refund() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let origin = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        let token_amount = TokenAmount::from_whole(10);
+        state
+            .buy_credit(&config, &store, origin, token_amount.clone(), current_epoch)
+            .unwrap();
+        delete_blob_refund(
+            &config,
+            &store,
+            state,
+            origin,
+            origin,
+            current_epoch,
+            token_amount,
+            false,
+        );
+    }
+
+    #[test]
+    fn test_delete_blob_refund_with_approval() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let origin = new_address();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        let token_amount = TokenAmount::from_whole(10);
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                token_amount.clone(),
+                current_epoch,
+            )
+            .unwrap();
+        state
+            .approve_credit(
+                &config,
+                &store,
+                subscriber,
+                origin,
+                current_epoch,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        delete_blob_refund(
+            &config,
+            &store,
+            state,
+            origin,
+            subscriber,
+            current_epoch,
+            token_amount,
+            true,
+        );
+    }
+
+    #[test]
+    fn test_revoked_approval_orphaned_subscriptions() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let origin = new_address();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_whole(10),
+                current_epoch,
+            )
+            .unwrap();
+        state
+            .approve_credit(
+                &config,
+                &store,
+                subscriber,
+                origin,
+                current_epoch,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+        // Add one blob through the delegate and one directly
+        let add_blob = |state: &mut State, origin: Address| {
+            let (hash, size) = new_hash(1024);
+            state
+                .add_blob(
+                    &config,
+                    &store,
+                    origin,
+                    subscriber,
+                    current_epoch,
+                    hash,
+                    new_metadata_hash(),
+                    SubscriptionId::default(),
+                    size,
+                    Some(config.blob_min_ttl),
+                    new_pk(),
+                    TokenAmount::zero(),
+                    false,
+                    1,
+                )
+                .unwrap();
+            hash
+        };
+        let delegated_hash = add_blob(&mut state, origin);
+        add_blob(&mut state, subscriber);
+
+        // Nothing is orphaned while the approval exists
+        let (orphaned, next_key) = state
+            .get_orphaned_subscriptions(&store, origin, None, None)
+            .unwrap();
+        assert!(orphaned.is_empty());
+        assert!(next_key.is_none());
+
+        // Revoking the approval orphans the delegate's subscription
+        state.revoke_credit(&store, subscriber, origin).unwrap();
+        let (orphaned, _) = state
+            .get_orphaned_subscriptions(&store, origin, None, None)
+            .unwrap();
+        assert_eq!(
+            orphaned,
+            vec![OrphanedSubscription {
+                subscriber,
+                hash: delegated_hash,
+                id: SubscriptionId::default(),
+            }]
+        );
+
+        // Terminating deletes only the delegate's subscription
+        let deleted = state
+            .delete_delegated_subscriptions(&config, &store, subscriber, origin, current_epoch)
+            .unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].hash, delegated_hash);
+        assert!(deleted[0].delete_from_disc);
+        assert_eq!(state.blobs.len(), 1);
+        let (orphaned, _) = state
+            .get_orphaned_subscriptions(&store, origin, None, None)
+            .unwrap();
+        assert!(orphaned.is_empty());
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn delete_blob_refund<BS: Blockstore>(
+        config: &RecallConfig,
+        store: &BS,
+        mut state: State,
+        origin: Address,
+        subscriber: Address,
+        current_epoch: ChainEpoch,
+        token_amount: TokenAmount,
+        using_approval: bool,
+    ) {
+        let mut credit_amount = token_amount * &config.token_credit_rate;
+
+        // Add a blob
+        let add1_epoch = current_epoch;
+        let (hash1, size1) = new_hash(1024);
+        let source1 = new_pk();
+        let res = state.add_blob(
+            config,
+            &store,
+            origin,
+            subscriber,
+            add1_epoch,
+            hash1,
+            new_metadata_hash(),
+            SubscriptionId::default(),
+            size1,
+            Some(config.blob_min_ttl),
+            source1,
+            TokenAmount::zero(),
+            false,
+            1,
+        );
+        assert!(res.is_ok());
+
+        // Finalize as resolved
+        let res = state.set_blob_pending(
+            &store,
+            subscriber,
+            hash1,
+            size1,
+            SubscriptionId::default(),
+            source1,
+        );
+        assert!(res.is_ok());
+        let finalize_epoch = ChainEpoch::from(current_epoch + 1);
+        let res = state.finalize_blob(
+            config,
+            &store,
+            subscriber,
+            finalize_epoch,
+            hash1,
+            SubscriptionId::default(),
+            BlobStatus::Resolved,
+            None,
+        );
+        assert!(res.is_ok());
+
+        // Check stats
+        let stats = state.get_stats(config, TokenAmount::zero());
+        assert_eq!(stats.num_blobs, 1);
+        assert_eq!(stats.num_resolving, 0);
+        assert_eq!(stats.bytes_resolving, 0);
+        assert_eq!(stats.num_added, 0);
+        assert_eq!(stats.bytes_added, 0);
+
+        // Check the account balance
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(account.last_debit_epoch, add1_epoch);
+        assert_eq!(
+            account.credit_committed,
+            Credit::from_whole(config.blob_min_ttl as u64 * size1),
+        );
+        credit_amount -= &account.credit_committed;
+        assert_eq!(account.credit_free, credit_amount);
+        assert_eq!(account.capacity_used, size1);
+
+        // Add another blob past the first blob expiry
+        // This will trigger a debit on the account
+        let add2_epoch = ChainEpoch::from(config.blob_min_ttl + 10);
+        let (hash2, size2) = new_hash(2048);
+        let res = state.add_blob(
+            config,
+            &store,
+            origin,
+            subscriber,
+            add2_epoch,
+            hash2,
+            new_metadata_hash(),
+            SubscriptionId::default(),
+            size2,
+            Some(config.blob_min_ttl),
+            new_pk(),
+            TokenAmount::zero(),
+            false,
+            1,
+        );
+        assert!(res.is_ok());
+
+        // Check stats
+        let stats = state.get_stats(config, TokenAmount::zero());
+        assert_eq!(stats.num_blobs, 2);
+        assert_eq!(stats.num_resolving, 0);
+        assert_eq!(stats.bytes_resolving, 0);
+        assert_eq!(stats.num_added, 1);
+        assert_eq!(stats.bytes_added, size2);
+
+        // Check the account balance
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(account.last_debit_epoch, add2_epoch);
+        let blob1_expiry = ChainEpoch::from(config.blob_min_ttl + add1_epoch);
+        let overcharge = BigInt::from((add2_epoch - blob1_expiry) as u64 * size1);
+        assert_eq!(
+            account.credit_committed, // this includes an overcharge that needs to be refunded
+            Credit::from_whole(config.blob_min_ttl as u64 * size2 - overcharge),
+        );
+        credit_amount -= Credit::from_whole(config.blob_min_ttl as u64 * size2);
+        assert_eq!(account.credit_free, credit_amount);
+        assert_eq!(account.capacity_used, size1 + size2);
+
+        // Delete the first blob
+        let delete_epoch = ChainEpoch::from(config.blob_min_ttl + 20);
+        let (delete_from_disc, deleted_size) = state
+            .delete_blob(
+                config,
+                &store,
+                origin,
+                subscriber,
+                delete_epoch,
+                hash1,
+                SubscriptionId::default(),
+            )
+            .unwrap();
+        assert!(delete_from_disc);
+        assert_eq!(size1, deleted_size);
+
+        // Check stats
+        let stats = state.get_stats(config, TokenAmount::zero());
+        assert_eq!(stats.num_blobs, 1);
+        assert_eq!(stats.num_resolving, 0);
+        assert_eq!(stats.bytes_resolving, 0);
+        assert_eq!(stats.num_added, 1);
+        assert_eq!(stats.bytes_added, size2);
+
+        // Check the account balance
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(account.last_debit_epoch, add2_epoch); // not changed, blob is expired
+        assert_eq!(
+            account.credit_committed, // should not include overcharge due to refund
+            Credit::from_whole(config.blob_min_ttl as u64 * size2),
+        );
+        assert_eq!(account.credit_free, credit_amount); // not changed
+        assert_eq!(account.capacity_used, size2);
+
+        // Check state
+        assert_eq!(state.credit_committed, account.credit_committed); // credit was released
+        assert_eq!(
+            state.credit_debited,
+            Credit::from_whole(config.blob_min_ttl as u64 * size1)
+        );
+        assert_eq!(state.capacity_used, size2); // capacity was released
+
+        // Check indexes
+        assert_eq!(state.expiries.len(store).unwrap(), 1);
+        assert_eq!(state.added.len(), 1);
+        assert_eq!(state.pending.len(), 0);
+
+        // Check approval
+        if using_approval {
+            check_approval_used(&state, store, origin, subscriber);
+        }
+    }
+
+    #[test]
+    fn test_if_blobs_ttl_exceeds_accounts_ttl_should_error() {
+        setup_logs();
+
+        let config = RecallConfig::default();
+        const YEAR: ChainEpoch = 365 * 24 * 60 * 60;
+
+        // Test cases structure
+        struct TestCase {
+            name: &'static str,
+            account_ttl_status: TtlStatus,
+            blob_ttl: Option<ChainEpoch>,
+            should_succeed: bool,
+            expected_account_ttl: ChainEpoch,
+            expected_blob_ttl: ChainEpoch,
+        }
+
+        // Define test cases
+        let test_cases = vec![
+            TestCase {
+                name: "Reduced status rejects even minimum TTL",
+                account_ttl_status: TtlStatus::Reduced,
+                blob_ttl: Some(config.blob_min_ttl),
+                should_succeed: false,
+                expected_account_ttl: 0,
+                expected_blob_ttl: 0,
+            },
+            TestCase {
+                name: "Reduced status rejects no TTL",
+                account_ttl_status: TtlStatus::Reduced,
+                blob_ttl: Some(config.blob_min_ttl),
+                should_succeed: false,
+                expected_account_ttl: 0,
+                expected_blob_ttl: 0,
+            },
+            TestCase {
+                name: "Default status allows default TTL",
+                account_ttl_status: TtlStatus::Default,
+                blob_ttl: Some(config.blob_default_ttl),
+                should_succeed: true,
+                expected_account_ttl: config.blob_default_ttl,
+                expected_blob_ttl: config.blob_default_ttl,
+            },
+            TestCase {
+                name: "Default status sets no TTL to default without auto renew",
+                account_ttl_status: TtlStatus::Default,
+                blob_ttl: None,
+                should_succeed: true,
+                expected_account_ttl: config.blob_default_ttl,
+                expected_blob_ttl: config.blob_default_ttl,
+            },
+            TestCase {
+                name: "Default status preserves given TTL if it's less than default",
+                account_ttl_status: TtlStatus::Default,
+                blob_ttl: Some(config.blob_default_ttl - 1),
+                should_succeed: true,
+                expected_account_ttl: config.blob_default_ttl,
+                expected_blob_ttl: config.blob_default_ttl - 1,
+            },
+            TestCase {
+                name: "Default status rejects TTLs higher than default",
+                account_ttl_status: TtlStatus::Default,
+                blob_ttl: Some(config.blob_default_ttl + 1),
+                should_succeed: false,
+                expected_account_ttl: config.blob_default_ttl,
+                expected_blob_ttl: 0,
+            },
+            TestCase {
+                name: "Extended status allows any TTL",
+                account_ttl_status: TtlStatus::Extended,
+                blob_ttl: Some(YEAR),
+                should_succeed: true,
+                expected_account_ttl: ChainEpoch::MAX,
+                expected_blob_ttl: YEAR,
+            },
+        ];
+
+        // Run all test cases
+        for tc in test_cases {
+            let config = RecallConfig::default();
+            let store = MemoryBlockstore::default();
+            let mut state = State::new(&store).unwrap();
+            let subscriber = new_address();
+            let current_epoch = ChainEpoch::from(1);
+            let amount = TokenAmount::from_whole(10);
+
+            state
+                .buy_credit(&config, &store, subscriber, amount.clone(), current_epoch)
+                .unwrap();
+            state
+                .set_account_status(
+                    &config,
+                    &store,
+                    subscriber,
+                    tc.account_ttl_status,
+                    current_epoch,
+                )
+                .unwrap();
+
+            let (hash, size) = new_hash(1024);
+            let res = state.add_blob(
+                &config,
+                &store,
+                subscriber,
+                subscriber,
+                current_epoch,
+                hash,
+                new_metadata_hash(),
+                SubscriptionId::default(),
+                size,
+                tc.blob_ttl,
+                new_pk(),
+                TokenAmount::zero(),
+                false,
+                1,
+            );
+
+            let account_ttl = state
+                .get_account_max_ttl(&config, &store, subscriber)
+                .unwrap();
+            assert_eq!(
+                account_ttl, tc.expected_account_ttl,
+                "Test case '{}' has unexpected account TTL (expected {}, got {})",
+                tc.name, tc.expected_account_ttl, account_ttl
+            );
+
+            if tc.should_succeed {
+                assert!(
+                    res.is_ok(),
+                    "Test case '{}' should succeed but failed: {:?}",
+                    tc.name,
+                    res.err()
+                );
+
+                let res = state.get_blob(&store, hash);
+                assert!(res.is_ok(), "Failed to get blob: {:?}", res.err());
+                let blob = res.unwrap().unwrap();
+                let subscribers = blob.subscribers.hamt(&store).unwrap();
+                subscribers
+                    .for_each(|_, group| {
+                        let group_hamt = group.hamt(&store).unwrap();
+                        for val in group_hamt.iter() {
+                            let (_, sub) = val.unwrap();
+                            assert_eq!(
+                                sub.expiry,
+                                current_epoch + tc.expected_blob_ttl,
+                                "Test case '{}' has unexpected blob expiry",
+                                tc.name
+                            );
+                        }
+                        Ok(())
+                    })
+                    .unwrap();
+            } else {
+                assert!(
+                    res.is_err(),
+                    "Test case '{}' should fail but succeeded",
+                    tc.name
+                );
+                assert_eq!(
+                    res.err().unwrap().msg(),
+                    format!(
+                        "attempt to add a blob with TTL ({}) that exceeds account's max allowed TTL ({})",
+                        tc.blob_ttl.map_or_else(|| "none".to_string(), |ttl| ttl.to_string()), tc.account_ttl_status.get_max_ttl(config.blob_default_ttl),
+                    ),
+                    "Test case '{}' failed with unexpected error message",
+                    tc.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_ttl_status() {
+        setup_logs();
+
+        let config = RecallConfig::default();
+
+        struct TestCase {
+            name: &'static str,
+            initial_ttl_status: Option<TtlStatus>, // None means don't set initial status
+            new_ttl_status: TtlStatus,
+            expected_ttl: ChainEpoch,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Setting Reduced on new account",
+                initial_ttl_status: None,
+                new_ttl_status: TtlStatus::Reduced,
+                expected_ttl: 0,
+            },
+            TestCase {
+                name: "Setting Default on new account",
+                initial_ttl_status: None,
+                new_ttl_status: TtlStatus::Default,
+                expected_ttl: config.blob_default_ttl,
+            },
+            TestCase {
+                name: "Changing from Default to Reduced",
+                initial_ttl_status: Some(TtlStatus::Default),
+                new_ttl_status: TtlStatus::Reduced,
+                expected_ttl: 0,
+            },
+            TestCase {
+                name: "Changing from Extended to Reduced",
+                initial_ttl_status: Some(TtlStatus::Extended),
+                new_ttl_status: TtlStatus::Reduced,
+                expected_ttl: 0,
+            },
+            TestCase {
+                name: "Changing from Reduced to Extended",
+                initial_ttl_status: Some(TtlStatus::Reduced),
+                new_ttl_status: TtlStatus::Extended,
+                expected_ttl: ChainEpoch::MAX,
+            },
+        ];
+
+        for tc in test_cases {
+            let store = MemoryBlockstore::default();
+            let mut state = State::new(&store).unwrap();
+            let account = new_address();
+            let current_epoch = ChainEpoch::from(1);
+
+            // Initialize the account if needed
+            if tc.initial_ttl_status.is_some() {
+                state
+                    .set_account_status(
+                        &config,
+                        &store,
+                        account,
+                        tc.initial_ttl_status.unwrap(),
+                        current_epoch,
+                    )
+                    .unwrap();
+            }
+
+            // Change TTL status
+            let res = state.set_account_status(
+                &config,
+                &store,
+                account,
+                tc.new_ttl_status,
+                current_epoch,
+            );
+            assert!(
+                res.is_ok(),
+                "Test case '{}' failed to set TTL status",
+                tc.name
+            );
+
+            // Verify max TTL
+            let max_ttl = state.get_account_max_ttl(&config, &store, account).unwrap();
+            assert_eq!(
+                max_ttl, tc.expected_ttl,
+                "Test case '{}' failed: expected max TTL {}, got {}",
+                tc.name, tc.expected_ttl, max_ttl
+            );
+        }
+    }
+
+    #[test]
+    fn test_adjust_blob_ttls_for_account() {
+        setup_logs();
+        let config = RecallConfig::default();
+
+        const HOUR: ChainEpoch = 3600;
+        const TWO_HOURS: ChainEpoch = HOUR * 2;
+        const DAY: ChainEpoch = HOUR * 24;
+        const YEAR: ChainEpoch = DAY * 365;
+
+        let blobs_ttls: Vec<Option<ChainEpoch>> =
+            vec![None, Some(HOUR), Some(TWO_HOURS), Some(DAY), Some(YEAR)];
+
+        struct TestCase {
+            name: &'static str,
+            account_ttl: TtlStatus,
+            expected_ttls: Vec<ChainEpoch>,
+            limit: Option<u32>, // None means process all at once
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Set to zero with Reduced status",
+                account_ttl: TtlStatus::Reduced,
+                expected_ttls: vec![0, 0, 0, 0, 0],
+                limit: None,
+            },
+            TestCase {
+                name: "Set to default with Default status",
+                account_ttl: TtlStatus::Default,
+                expected_ttls: vec![DAY, HOUR, TWO_HOURS, DAY, DAY],
+                limit: None,
+            },
+            TestCase {
+                name: "Set to extended with Extended status",
+                account_ttl: TtlStatus::Extended,
+                expected_ttls: vec![DAY, HOUR, TWO_HOURS, DAY, YEAR],
+                limit: None,
+            },
+        ];
+
+        for tc in test_cases {
+            let store = MemoryBlockstore::default();
+            let mut state = State::new(&store).unwrap();
+            let addr = new_address();
+            let current_epoch = ChainEpoch::from(1);
+
+            // Setup account with credits and TTL status
+            let token = TokenAmount::from_whole(1000);
+            state
+                .buy_credit(&config, &store, addr, token, current_epoch)
+                .unwrap();
+
+            // Set extended TTL status to allow adding all blobs
+            state
+                .set_account_status(&config, &store, addr, TtlStatus::Extended, current_epoch)
+                .unwrap();
+
+            // Add blobs
+            let mut blob_hashes = Vec::new();
+            let mut total_cost = Credit::zero();
+            let mut expected_credits = Credit::zero();
+            for (i, ttl) in blobs_ttls.iter().enumerate() {
+                let size = (i + 1) * 1024;
+                let (hash, _) = new_hash(size);
+                let size = size as u64;
+                let id = SubscriptionId::try_from(format!("blob-{}", i)).unwrap();
+                let source = new_pk();
+                blob_hashes.push(hash);
+
+                state
+                    .add_blob(
+                        &config,
+                        &store,
+                        addr,
+                        addr,
+                        current_epoch,
+                        hash,
+                        new_metadata_hash(),
+                        id.clone(),
+                        size,
+                        *ttl,
+                        source,
+                        TokenAmount::zero(),
+                        false,
+                        1,
+                    )
+                    .unwrap();
+                state
+                    .set_blob_pending(&store, addr, hash, size, id.clone(), source)
+                    .unwrap();
+                state
+                    .finalize_blob(
+                        &config,
+                        &store,
+                        addr,
+                        current_epoch,
+                        hash,
+                        id,
+                        BlobStatus::Resolved,
+                        None,
+                    )
+                    .unwrap();
+
+                total_cost += Credit::from_whole(
+                    state.get_storage_cost(ttl.unwrap_or(config.blob_default_ttl), &size),
+                );
+                expected_credits +=
+                    Credit::from_whole(state.get_storage_cost(tc.expected_ttls[i], &size));
+            }
+
+            let account = state.get_account(&store, addr).unwrap().unwrap();
+            assert_eq!(
+                account.credit_committed, total_cost,
+                "Test case '{}' failed: committed credits don't match",
+                tc.name
+            );
+
+            state
+                .set_account_status(&config, &store, addr, tc.account_ttl, current_epoch)
+                .unwrap();
+
+            let res =
+                state.trim_blob_expiries(&config, &store, addr, current_epoch, None, tc.limit);
+            assert!(
+                res.is_ok(),
+                "Test case '{}' failed to adjust TTLs: {}",
+                tc.name,
+                res.err().unwrap()
+            );
+
+            // Verify TTLs were adjusted correctly
+            for (i, hash) in blob_hashes.iter().enumerate() {
+                // If the TTL is zero, the blob should be deleted
+                if tc.expected_ttls[i] == 0 {
+                    assert!(
+                        state.get_blob(&store, *hash).unwrap().is_none(),
+                        "Test case '{}' failed: blob {} not deleted",
+                        tc.name,
+                        i
+                    );
+                } else {
+                    let blob = state.get_blob(&store, *hash).unwrap().unwrap();
+                    let subscribers = blob.subscribers.hamt(&store).unwrap();
+                    let group = subscribers.get(&addr).unwrap().unwrap();
+                    let group_hamt = group.hamt(&store).unwrap();
+                    let sub = group_hamt
+                        .get(&SubscriptionId::new(&format!("blob-{}", i)).unwrap())
+                        .unwrap()
+                        .unwrap();
+
+                    assert_eq!(
+                        sub.expiry - sub.added,
+                        tc.expected_ttls[i],
+                        "Test case '{}' failed: blob {} TTL not adjusted correctly. Expected {}, got {}",
+                        tc.name,
+                        i,
+                        tc.expected_ttls[i],
+                        sub.expiry - sub.added,
+                    );
+                }
+            }
+
+            let account = state.get_account(&store, addr).unwrap().unwrap();
+            assert_eq!(
+                account.credit_committed, expected_credits,
+                "Test case '{}' failed: account's committed credits after blob adjustment don't match",
+                tc.name
+            );
+
+            assert_eq!(
+                state.credit_committed, expected_credits,
+                "Test case '{}' failed: state's committed credits after blob adjustment don't match",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_adjust_blob_ttls_pagination() {
+        setup_logs();
+        let config = RecallConfig::default();
+
+        // Test cases for pagination
+        struct PaginationTest {
+            name: &'static str,
+            limit: Option<u32>,
+            start: Option<usize>,
+            expected_next_key: Option<usize>,
+            expected_processed: usize,
+        }
+
+        let test_cases = vec![
+            PaginationTest {
+                name: "Process all at once",
+                limit: None,
+                start: None,
+                expected_next_key: None,
+                expected_processed: 5,
+            },
+            PaginationTest {
+                name: "Process two at a time from beginning",
+                limit: Some(2),
+                start: None,
+                expected_next_key: Some(2),
+                expected_processed: 2,
+            },
+            PaginationTest {
+                name: "Process one at a time with offset",
+                limit: Some(1),
+                start: Some(1),
+                expected_next_key: Some(2),
+                expected_processed: 1,
+            },
+            PaginationTest {
+                name: "Out of bounds limit",
+                limit: Some(10),
+                start: Some(1),
+                expected_next_key: None,
+                expected_processed: 4,
+            },
+            PaginationTest {
+                name: "With offset ending at last item",
+                limit: Some(2),
+                start: Some(3),
+                expected_next_key: None,
+                expected_processed: 2,
+            },
+        ];
+
+        for tc in test_cases {
+            let store = MemoryBlockstore::default();
+            let mut state = State::new(&store).unwrap();
+            let addr = new_address();
+            let current_epoch = ChainEpoch::from(1);
+
+            // Setup account with credits and Extended TTL status to allow adding all blobs
+            state
+                .buy_credit(
+                    &config,
+                    &store,
+                    addr,
+                    TokenAmount::from_whole(1000),
+                    current_epoch,
+                )
+                .unwrap();
+            state
+                .set_account_status(&config, &store, addr, TtlStatus::Extended, current_epoch)
+                .unwrap();
+
+            // Add 5 blobs with different sizes to ensure different hashes
+            for i in 0..5 {
+                let (hash, size) = new_hash((i + 1) * 1024);
+                let id = SubscriptionId::try_from(format!("blob-{}", i)).unwrap();
+                let source = new_pk();
+                state
+                    .add_blob(
+                        &config,
+                        &store,
+                        addr,
+                        addr,
+                        current_epoch,
+                        hash,
+                        new_metadata_hash(),
+                        id.clone(),
+                        size,
+                        Some(7200), // 2 hours
+                        source,
+                        TokenAmount::zero(),
+                        false,
+                        1,
+                    )
+                    .unwrap();
+                state
+                    .set_blob_pending(&store, addr, hash, size, id.clone(), source)
+                    .unwrap();
+                state
+                    .finalize_blob(
+                        &config,
+                        &store,
+                        addr,
+                        current_epoch,
+                        hash,
+                        id,
+                        BlobStatus::Resolved,
+                        None,
+                    )
+                    .unwrap();
+            }
+
+            // range over all blobs and store their hashes
+            let mut blob_hashes = Vec::with_capacity(5);
+            for _ in 0..5 {
+                let res = state.blobs.hamt(&store).unwrap().for_each(
+                    |hash, _| -> Result<(), ActorError> {
+                        blob_hashes.push(hash);
+                        Ok(())
+                    },
+                );
+                assert!(
+                    res.is_ok(),
+                    "Failed to iterate over blobs: {}",
+                    res.err().unwrap()
+                );
+            }
+
+            // Change to Reduced status and process blobs with pagination
+            state
+                .set_account_status(&config, &store, addr, TtlStatus::Reduced, current_epoch)
+                .unwrap();
+
+            let res = state.trim_blob_expiries(
+                &config,
+                &store,
+                addr,
+                current_epoch,
+                tc.start.map(|ind| blob_hashes[ind]),
+                tc.limit,
+            );
+            assert!(
+                res.is_ok(),
+                "Test case '{}' failed to adjust TTLs: {}",
+                tc.name,
+                res.err().unwrap()
+            );
+
+            let (processed, next, deleted_blobs) = res.unwrap();
+
+            assert_eq!(
+                processed as usize, tc.expected_processed,
+                "Test case '{}' had unexpected number of items processed",
+                tc.name
+            );
+
+            assert_eq!(
+                deleted_blobs.len(),
+                tc.expected_processed,
+                "Test case '{}' had unexpected number of deleted blobs",
+                tc.name
+            );
+
+            if let Some(expected_next_key) = tc.expected_next_key {
+                assert!(next.is_some(), "Test case '{}' expected next key", tc.name);
+                assert_eq!(
+                    next.unwrap(),
+                    blob_hashes[expected_next_key],
+                    "Test case '{}' had unexpected next key",
+                    tc.name
+                );
+            } else {
+                assert!(next.is_none(), "Test case '{}' had no next key", tc.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_adjust_blob_ttls_for_multiple_accounts() {
+        setup_logs();
+
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let account1 = new_address();
+        let account2 = new_address();
+        let current_epoch = ChainEpoch::from(1);
+
+        // Setup accounts with credits and Extended TTL status to allow adding all blobs
+        state
+            .buy_credit(
+                &config,
+                &store,
+                account1,
+                TokenAmount::from_whole(1000),
+                current_epoch,
+            )
+            .unwrap();
+        state
+            .buy_credit(
+                &config,
+                &store,
+                account2,
+                TokenAmount::from_whole(1000),
+                current_epoch,
+            )
+            .unwrap();
+        state
+            .set_account_status(
+                &config,
+                &store,
+                account1,
+                TtlStatus::Extended,
+                current_epoch,
+            )
+            .unwrap();
+        state
+            .set_account_status(
+                &config,
+                &store,
+                account2,
+                TtlStatus::Extended,
+                current_epoch,
+            )
+            .unwrap();
+
+        // Add blobs for both accounts
+        let mut blob_hashes_account1 = Vec::new();
+        let mut blob_hashes_account2 = Vec::new();
+        for i in 0..3 {
+            let (hash, size) = new_hash((i + 1) * 1024);
+            let id = SubscriptionId::try_from(format!("blob-1-{}", i)).unwrap();
+            let source = new_pk();
+            blob_hashes_account1.push(hash);
+            state
+                .add_blob(
+                    &config,
+                    &store,
+                    account1,
+                    account1,
+                    current_epoch,
+                    hash,
+                    new_metadata_hash(),
+                    id.clone(),
+                    size,
+                    Some(7200), // 2 hours
+                    source,
+                    TokenAmount::zero(),
+                    false,
+                    1,
+                )
+                .unwrap();
+            state
+                .set_blob_pending(&store, account1, hash, size, id.clone(), source)
+                .unwrap();
+            state
+                .finalize_blob(
+                    &config,
+                    &store,
+                    account1,
+                    current_epoch,
+                    hash,
+                    id,
+                    BlobStatus::Resolved,
+                    None,
+                )
+                .unwrap();
+        }
+        for i in 0..3 {
+            let (hash, size) = new_hash((i + 1) * 1024);
+            let id = SubscriptionId::try_from(format!("blob-2-{}", i)).unwrap();
+            let source = new_pk();
+            blob_hashes_account2.push(hash);
+            state
+                .add_blob(
+                    &config,
+                    &store,
+                    account2,
+                    account2,
+                    current_epoch,
+                    hash,
+                    new_metadata_hash(),
+                    id.clone(),
+                    size,
+                    Some(7200), // 2 hours
+                    source,
+                    TokenAmount::zero(),
+                    false,
+                    1,
+                )
+                .unwrap();
+            state
+                .set_blob_pending(&store, account2, hash, size, id.clone(), source)
+                .unwrap();
+            state
+                .finalize_blob(
+                    &config,
+                    &store,
+                    account2,
+                    current_epoch,
+                    hash,
+                    id,
+                    BlobStatus::Resolved,
+                    None,
+                )
+                .unwrap();
+        }
+
+        // Change TTL status for account1 and adjust blobs
+        state
+            .set_account_status(&config, &store, account1, TtlStatus::Reduced, current_epoch)
+            .unwrap();
+        let res = state.trim_blob_expiries(&config, &store, account1, current_epoch, None, None);
+        assert!(
+            res.is_ok(),
+            "Failed to adjust TTLs for account1: {}",
+            res.err().unwrap()
+        );
+
+        // Verify account1's blobs were adjusted
+        for hash in &blob_hashes_account1 {
+            assert!(
+                state.get_blob(&store, *hash).unwrap().is_none(),
+                "Blob {} for account1 was not deleted",
+                hash,
+            );
+        }
+
+        // Verify account2's blobs were not adjusted
+        for hash in &blob_hashes_account2 {
+            assert!(
+                state.get_blob(&store, *hash).unwrap().is_some(),
+                "Blob {} for account2 was incorrectly deleted",
+                hash,
+            );
+        }
+    }
+
+    #[test]
+    fn test_simulate_one_day() {
+        setup_logs();
+
+        let config = RecallConfig {
+            blob_credit_debit_interval: ChainEpoch::from(60),
+            blob_min_ttl: ChainEpoch::from(10),
+            ..Default::default()
+        };
+
+        #[derive(Clone, Debug, Hash, PartialEq, Eq)]
+        struct TestBlob {
+            hash: Hash,
+            metadata_hash: Hash,
+            size: u64,
+            added: Option<ChainEpoch>,
+            resolve: Option<ChainEpoch>,
+        }
+
+        fn generate_test_blobs(count: i64, min_size: usize, max_size: usize) -> Vec<TestBlob> {
+            let mut blobs = Vec::new();
+            let mut rng = rand::thread_rng();
+
+            for _ in 0..count {
+                let size = rng.gen_range(min_size..=max_size);
+                let (hash, size) = new_hash(size);
+                blobs.push(TestBlob {
+                    hash,
+                    metadata_hash: new_metadata_hash(),
+                    size,
+                    added: None,
+                    resolve: None,
+                });
+            }
+            blobs
+        }
+
+        fn generate_test_users<BS: Blockstore>(
+            config: &RecallConfig,
+            store: &BS,
+            state: &mut State,
+            credit_tokens: TokenAmount,
+            count: i64,
+        ) -> Vec<Address> {
+            let mut users = Vec::new();
+            for _ in 0..count {
+                let user = new_address();
+                state
+                    .buy_credit(config, &store, user, credit_tokens.clone(), 0)
+                    .unwrap();
+                users.push(user);
+            }
+            users
+        }
+
+        // Test params
+        let epochs: i64 = 360; // num. epochs to run test for
+        let user_pool_size: i64 = 10; // some may not be used, some will be used more than once
+        let blob_pool_size: i64 = epochs; // some may not be used, some will be used more than once
+        let min_ttl = config.blob_min_ttl;
+        let max_ttl = epochs;
+        let min_size = 8;
+        let max_size = 1024;
+        let add_intervals = [1, 2, 4, 8, 10, 12, 15, 20]; // used to add at random intervals
+        let max_resolve_epochs = 30; // max num. epochs in future to resolve
+        let debit_interval: i64 = config.blob_credit_debit_interval; // interval at which to debit all accounts
+        let percent_fail_resolve = 0.1; // controls % of subscriptions that fail resolve
+
+        // Set up store and state
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let mut rng = rand::thread_rng();
+
+        // Get some users
         let credit_tokens = TokenAmount::from_whole(100); // buy a lot
         let user_credit: Credit = credit_tokens.clone() * &config.token_credit_rate;
         let users = generate_test_users(&config, &store, &mut state, credit_tokens, user_pool_size);
 
-        // Get some blobs.
-        let mut blobs = generate_test_blobs(blob_pool_size, min_size, max_size);
+        // Get some blobs.
+        let mut blobs = generate_test_blobs(blob_pool_size, min_size, max_size);
+
+        // Map of resolve epochs to set of blob indexes
+        #[allow(clippy::type_complexity)]
+        let mut resolves: BTreeMap<
+            ChainEpoch,
+            HashMap<Address, HashMap<usize, (SubscriptionId, PublicKey, Credit)>>,
+        > = BTreeMap::new();
+
+        // Walk epochs.
+        // We go for twice the paramaterized epochs to ensure all subscriptions can expire.
+        let mut num_added = 0;
+        let mut num_readded = 0;
+        let mut num_resolved = 0;
+        let mut num_failed = 0;
+        let mut credit_used: HashMap<Address, Credit> = HashMap::new();
+        for epoch in 1..=epochs * 2 {
+            if epoch <= epochs {
+                let add_interval = add_intervals.choose(&mut rng).unwrap().to_owned();
+                if epoch % add_interval == 0 {
+                    // Add a random blob with a random user
+                    let blob_index = rng.gen_range(0..blobs.len());
+                    let blob = unsafe { blobs.get_unchecked_mut(blob_index) };
+                    if blob.added.is_none() {
+                        let user_index = rng.gen_range(0..users.len());
+                        let user = users[user_index];
+                        let sub_id = new_subscription_id(7);
+                        let ttl = rng.gen_range(min_ttl..=max_ttl);
+                        let source = new_pk();
+                        let res = state.add_blob(
+                            &config,
+                            &store,
+                            user,
+                            user,
+                            epoch,
+                            blob.hash,
+                            blob.metadata_hash,
+                            sub_id.clone(),
+                            blob.size,
+                            Some(ttl),
+                            source,
+                            TokenAmount::zero(),
+                            false,
+                            1,
+                        );
+                        assert!(res.is_ok());
+                        if blob.added.is_none() {
+                            num_added += 1;
+                            warn!(
+                                "added new blob {} at epoch {} with ttl {}",
+                                blob.hash, epoch, ttl
+                            );
+                        } else {
+                            warn!(
+                                "added new sub to blob {} at epoch {} with ttl {}",
+                                blob.hash, epoch, ttl
+                            );
+                            num_readded += 1;
+                        }
+                        blob.added = Some(epoch);
+
+                        // Determine how much credit should get committed for this blob
+                        let credit = Credit::from_whole(state.get_storage_cost(ttl, &blob.size));
+                        // Track credit amount for user, assuming the whole committed amount gets debited
+                        credit_used
+                            .entry(user)
+                            .and_modify(|c| c.add_assign(&credit))
+                            .or_insert(credit.clone());
+
+                        // Schedule a resolve to happen in the future
+                        let resolve = rng.gen_range(1..=max_resolve_epochs) + epoch;
+                        resolves
+                            .entry(resolve)
+                            .and_modify(|entry| {
+                                entry
+                                    .entry(user)
+                                    .and_modify(|subs| {
+                                        subs.insert(
+                                            blob_index,
+                                            (sub_id.clone(), source, credit.clone()),
+                                        );
+                                    })
+                                    .or_insert(HashMap::from([(
+                                        blob_index,
+                                        (sub_id.clone(), source, credit.clone()),
+                                    )]));
+                            })
+                            .or_insert(HashMap::from([(
+                                user,
+                                HashMap::from([(blob_index, (sub_id, source, credit))]),
+                            )]));
+                    }
+                }
+            }
+
+            // Resolve blob(s)
+            if let Some(users) = resolves.get(&epoch) {
+                for (user, index) in users {
+                    for (i, (sub_id, source, credit)) in index {
+                        let blob = unsafe { blobs.get_unchecked(*i) };
+                        let fail = rng.gen_bool(percent_fail_resolve);
+                        let status = if fail {
+                            num_failed += 1;
+                            credit_used
+                                .entry(*user)
+                                .and_modify(|c| c.sub_assign(credit));
+                            BlobStatus::Failed
+                        } else {
+                            num_resolved += 1;
+                            BlobStatus::Resolved
+                        };
+                        // Simulate the chain putting this blob into pending state, which is
+                        // required before finalization.
+                        state
+                            .set_blob_pending(
+                                &store,
+                                *user,
+                                blob.hash,
+                                blob.size,
+                                sub_id.clone(),
+                                *source,
+                            )
+                            .unwrap();
+                        state
+                            .finalize_blob(
+                                &config,
+                                &store,
+                                *user,
+                                epoch,
+                                blob.hash,
+                                sub_id.clone(),
+                                status,
+                                None,
+                            )
+                            .unwrap();
+                    }
+                }
+            }
+
+            // Every debit interval epochs we debit all acounts
+            if epoch % debit_interval == 0 {
+                let deletes_from_disc = state
+                    .debit_accounts(&config, &store, epoch)
+                    .unwrap()
+                    .delete_from_disc;
+                warn!(
+                    "deleting {} blobs at epoch {}",
+                    deletes_from_disc.len(),
+                    epoch
+                );
+            }
+        }
 
-        // Map of resolve epochs to set of blob indexes
-        #[allow(clippy::type_complexity)]
-        let mut resolves: BTreeMap<
-            ChainEpoch,
-            HashMap<Address, HashMap<usize, (SubscriptionId, PublicKey, Credit)>>,
-        > = BTreeMap::new();
+        let mut total_credit_used = Credit::zero();
+        for (_, credit) in credit_used.clone() {
+            total_credit_used.add_assign(&credit);
+        }
+
+        debug!("credit used: {}", total_credit_used);
+        debug!("num. blobs added: {}", num_added);
+        debug!("num. blobs re-added: {}", num_readded);
+        debug!("num. blobs resolved: {}", num_resolved);
+        debug!("num. blobs failed: {}", num_failed);
+
+        // Check the account balances
+        for (i, user) in users.iter().enumerate() {
+            let account = state.get_account(&store, *user).unwrap().unwrap();
+            debug!("account {}: {:#?}", i, account);
+            assert_eq!(account.capacity_used, 0);
+            assert_eq!(account.credit_committed, Credit::zero());
+            let credit_used = credit_used.get(user).unwrap();
+            assert_eq!(account.credit_free, &user_credit - credit_used);
+        }
+
+        // Check state.
+        // Everything should be empty except for credit_debited.
+        let stats = state.get_stats(&config, TokenAmount::zero());
+        debug!("stats: {:#?}", stats);
+        assert_eq!(stats.capacity_used, 0);
+        assert_eq!(stats.credit_committed, Credit::zero());
+        assert_eq!(stats.credit_debited, total_credit_used);
+        assert_eq!(stats.num_blobs, 0);
+        assert_eq!(stats.num_added, 0);
+        assert_eq!(stats.bytes_added, 0);
+        assert_eq!(stats.num_resolving, 0);
+        assert_eq!(stats.bytes_resolving, 0);
+    }
+
+    #[test]
+    fn test_paginated_debit_accounts() {
+        let config = RecallConfig {
+            account_debit_batch_size: 5, // Process 5 accounts at a time (10 accounts total)
+            ..Default::default()
+        };
+
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let current_epoch = ChainEpoch::from(1);
+
+        // Create more than one batch worth of accounts (>5)
+        for i in 0..10 {
+            let address = Address::new_id(1000 + i);
+            let token_amount = TokenAmount::from_whole(10);
+
+            // Buy credits for each account
+            state
+                .buy_credit(
+                    &config,
+                    &store,
+                    address,
+                    token_amount.clone(),
+                    current_epoch,
+                )
+                .unwrap();
+
+            // Add some storage usage
+            let mut accounts = state.accounts.hamt(&store).unwrap();
+            let mut account = accounts.get(&address).unwrap().unwrap();
+            account.capacity_used = 1000;
+            accounts.set(&address, account).unwrap();
+        }
+
+        // First batch (should process 5 accounts)
+        assert!(state.next_debit_addr.is_none());
+        let deletes1 = state
+            .debit_accounts(&config, &store, current_epoch + 1)
+            .unwrap()
+            .delete_from_disc;
+        assert!(deletes1.is_empty()); // No expired blobs
+        assert!(state.next_debit_addr.is_some());
+
+        // Second batch (should process remaining 5 accounts and clear state)
+        let deletes2 = state
+            .debit_accounts(&config, &store, current_epoch + 1)
+            .unwrap()
+            .delete_from_disc;
+        assert!(deletes2.is_empty());
+        assert!(state.next_debit_addr.is_none()); // State should be cleared after all accounts processed
+
+        // Verify all accounts were processed
+        let reader = state.accounts.hamt(&store).unwrap();
+        reader
+            .for_each(|_, account| {
+                assert_eq!(account.last_debit_epoch, current_epoch + 1);
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_multiple_debit_cycles() {
+        let config = RecallConfig {
+            account_debit_batch_size: 5, // Process 5 accounts at a time (10 accounts total)
+            ..Default::default()
+        };
+
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let current_epoch = ChainEpoch::from(1);
+
+        // Create accounts
+        for i in 0..10 {
+            let address = Address::new_id(1000 + i);
+            let token_amount = TokenAmount::from_whole(10);
+            state
+                .buy_credit(
+                    &config,
+                    &store,
+                    address,
+                    token_amount.clone(),
+                    current_epoch,
+                )
+                .unwrap();
+
+            let mut accounts = state.accounts.hamt(&store).unwrap();
+            let mut account = accounts.get(&address).unwrap().unwrap();
+            account.capacity_used = 1000;
+            accounts.set(&address, account).unwrap();
+        }
+
+        // First cycle
+        let deletes1 = state
+            .debit_accounts(&config, &store, current_epoch + 1)
+            .unwrap()
+            .delete_from_disc;
+        assert!(deletes1.is_empty());
+        assert!(state.next_debit_addr.is_some());
+
+        let deletes2 = state
+            .debit_accounts(&config, &store, current_epoch + 1)
+            .unwrap()
+            .delete_from_disc;
+        assert!(deletes2.is_empty());
+        assert!(state.next_debit_addr.is_none()); // First cycle complete
+
+        // Second cycle
+        let deletes3 = state
+            .debit_accounts(&config, &store, current_epoch + 2)
+            .unwrap()
+            .delete_from_disc;
+        assert!(deletes3.is_empty());
+        assert!(state.next_debit_addr.is_some());
+
+        let deletes4 = state
+            .debit_accounts(&config, &store, current_epoch + 2)
+            .unwrap()
+            .delete_from_disc;
+        assert!(deletes4.is_empty());
+        assert!(state.next_debit_addr.is_none()); // Second cycle complete
+    }
+
+    fn add_auto_renew_blob(
+        config: &RecallConfig,
+        store: &MemoryBlockstore,
+        state: &mut State,
+        subscriber: Address,
+        current_epoch: ChainEpoch,
+    ) -> (Hash, u64, SubscriptionId) {
+        add_auto_renew_blob_with(
+            config,
+            store,
+            state,
+            subscriber,
+            subscriber,
+            current_epoch,
+            1,
+        )
+    }
+
+    fn add_auto_renew_blob_with(
+        config: &RecallConfig,
+        store: &MemoryBlockstore,
+        state: &mut State,
+        origin: Address,
+        subscriber: Address,
+        current_epoch: ChainEpoch,
+        replication: u32,
+    ) -> (Hash, u64, SubscriptionId) {
+        let (hash, size) = new_hash(1024);
+        let id = SubscriptionId::default();
+        let source = new_pk();
+        state
+            .add_blob(
+                config,
+                store,
+                origin,
+                subscriber,
+                current_epoch,
+                hash,
+                new_metadata_hash(),
+                id.clone(),
+                size,
+                Some(config.blob_min_ttl),
+                source,
+                TokenAmount::zero(),
+                true,
+                replication,
+            )
+            .unwrap();
+        state
+            .set_blob_pending(store, subscriber, hash, size, id.clone(), source)
+            .unwrap();
+        state
+            .finalize_blob(
+                config,
+                store,
+                subscriber,
+                current_epoch,
+                hash,
+                id.clone(),
+                BlobStatus::Resolved,
+                None,
+            )
+            .unwrap();
+        (hash, size, id)
+    }
+
+    #[test]
+    fn test_debit_accounts_renews_auto_renew_subscription() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_whole(10),
+                current_epoch,
+            )
+            .unwrap();
+        let (hash, size, id) =
+            add_auto_renew_blob(&config, &store, &mut state, subscriber, current_epoch);
+        let credit_free = state
+            .get_account(&store, subscriber)
+            .unwrap()
+            .unwrap()
+            .credit_free;
+
+        // Debit at expiry; the subscription should be extended by its original TTL
+        let debit_epoch = current_epoch + config.blob_min_ttl;
+        let outcome = state.debit_accounts(&config, &store, debit_epoch).unwrap();
+        assert!(outcome.delete_from_disc.is_empty());
+        assert!(outcome.expired.is_empty());
+        assert_eq!(
+            outcome.renewed,
+            vec![RenewedSubscription {
+                subscriber,
+                hash,
+                id: id.clone(),
+                size,
+                expiry: debit_epoch + config.blob_min_ttl,
+            }]
+        );
+
+        let blob = state.get_blob(&store, hash).unwrap().unwrap();
+        let group = blob
+            .subscribers
+            .hamt(&store)
+            .unwrap()
+            .get(&subscriber)
+            .unwrap()
+            .unwrap();
+        let sub = group.hamt(&store).unwrap().get(&id).unwrap().unwrap();
+        assert_eq!(sub.expiry, debit_epoch + config.blob_min_ttl);
+        assert!(sub.auto_renew);
 
-        // Walk epochs.
-        // We go for twice the paramaterized epochs to ensure all subscriptions can expire.
-        let mut num_added = 0;
-        let mut num_readded = 0;
-        let mut num_resolved = 0;
-        let mut num_failed = 0;
-        let mut credit_used: HashMap<Address, Credit> = HashMap::new();
-        for epoch in 1..=epochs * 2 {
-            if epoch <= epochs {
-                let add_interval = add_intervals.choose(&mut rng).unwrap().to_owned();
-                if epoch % add_interval == 0 {
-                    // Add a random blob with a random user
-                    let blob_index = rng.gen_range(0..blobs.len());
-                    let blob = unsafe { blobs.get_unchecked_mut(blob_index) };
-                    if blob.added.is_none() {
-                        let user_index = rng.gen_range(0..users.len());
-                        let user = users[user_index];
-                        let sub_id = new_subscription_id(7);
-                        let ttl = rng.gen_range(min_ttl..=max_ttl);
-                        let source = new_pk();
-                        let res = state.add_blob(
-                            &config,
-                            &store,
-                            user,
-                            user,
-                            epoch,
-                            blob.hash,
-                            blob.metadata_hash,
-                            sub_id.clone(),
-                            blob.size,
-                            Some(ttl),
-                            source,
-                            TokenAmount::zero(),
-                        );
-                        assert!(res.is_ok());
-                        if blob.added.is_none() {
-                            num_added += 1;
-                            warn!(
-                                "added new blob {} at epoch {} with ttl {}",
-                                blob.hash, epoch, ttl
-                            );
-                        } else {
-                            warn!(
-                                "added new sub to blob {} at epoch {} with ttl {}",
-                                blob.hash, epoch, ttl
-                            );
-                            num_readded += 1;
-                        }
-                        blob.added = Some(epoch);
+        // The renewal is paid for with free credit
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(
+            account.credit_free,
+            credit_free - Credit::from_whole(config.blob_min_ttl as u64 * size)
+        );
+        assert_eq!(account.capacity_used, size);
+        assert_eq!(state.capacity_used, size);
+    }
 
-                        // Determine how much credit should get committed for this blob
-                        let credit = Credit::from_whole(state.get_storage_cost(ttl, &blob.size));
-                        // Track credit amount for user, assuming the whole committed amount gets debited
-                        credit_used
-                            .entry(user)
-                            .and_modify(|c| c.add_assign(&credit))
-                            .or_insert(credit.clone());
+    #[test]
+    fn test_debit_accounts_expires_auto_renew_subscription_without_credit() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        // Buy just enough credit for the initial TTL (1 atto token buys 1 credit by default)
+        let initial_cost = config.blob_min_ttl as u64 * 1024;
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_atto(initial_cost),
+                current_epoch,
+            )
+            .unwrap();
+        let (hash, size, id) =
+            add_auto_renew_blob(&config, &store, &mut state, subscriber, current_epoch);
+
+        // Debit at expiry; there isn't enough credit to renew, so the subscription becomes
+        // delinquent and the blob is kept for the grace period
+        let debit_epoch = current_epoch + config.blob_min_ttl;
+        let outcome = state.debit_accounts(&config, &store, debit_epoch).unwrap();
+        assert!(outcome.renewed.is_empty());
+        assert!(outcome.expired.is_empty());
+        let delinquent_until = debit_epoch + config.blob_delinquency_grace_period;
+        assert_eq!(
+            outcome.delinquent,
+            vec![DelinquentSubscription {
+                subscriber,
+                hash,
+                id: id.clone(),
+                delinquent_until,
+            }]
+        );
+        let blob = state.get_blob(&store, hash).unwrap().unwrap();
+        let sub = blob
+            .subscribers
+            .hamt(&store)
+            .unwrap()
+            .get(&subscriber)
+            .unwrap()
+            .unwrap()
+            .hamt(&store)
+            .unwrap()
+            .get(&id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(sub.delinquent_until, Some(delinquent_until));
+        assert_eq!(state.capacity_used, size);
 
-                        // Schedule a resolve to happen in the future
-                        let resolve = rng.gen_range(1..=max_resolve_epochs) + epoch;
-                        resolves
-                            .entry(resolve)
-                            .and_modify(|entry| {
-                                entry
-                                    .entry(user)
-                                    .and_modify(|subs| {
-                                        subs.insert(
-                                            blob_index,
-                                            (sub_id.clone(), source, credit.clone()),
-                                        );
-                                    })
-                                    .or_insert(HashMap::from([(
-                                        blob_index,
-                                        (sub_id.clone(), source, credit.clone()),
-                                    )]));
-                            })
-                            .or_insert(HashMap::from([(
-                                user,
-                                HashMap::from([(blob_index, (sub_id, source, credit))]),
-                            )]));
-                    }
-                }
-            }
+        // Debit at the end of the grace period; the subscription expires
+        let outcome = state
+            .debit_accounts(&config, &store, delinquent_until)
+            .unwrap();
+        assert!(outcome.renewed.is_empty());
+        assert!(outcome.delinquent.is_empty());
+        assert_eq!(
+            outcome.expired,
+            vec![ExpiredSubscription {
+                subscriber,
+                hash,
+                id,
+                size,
+                bytes_released: size,
+            }]
+        );
+        assert!(outcome.delete_from_disc.contains(&hash));
+        assert!(state.get_blob(&store, hash).unwrap().is_none());
+        assert_eq!(state.capacity_used, 0);
+    }
 
-            // Resolve blob(s)
-            if let Some(users) = resolves.get(&epoch) {
-                for (user, index) in users {
-                    for (i, (sub_id, source, credit)) in index {
-                        let blob = unsafe { blobs.get_unchecked(*i) };
-                        let fail = rng.gen_bool(percent_fail_resolve);
-                        let status = if fail {
-                            num_failed += 1;
-                            credit_used
-                                .entry(*user)
-                                .and_modify(|c| c.sub_assign(credit));
-                            BlobStatus::Failed
-                        } else {
-                            num_resolved += 1;
-                            BlobStatus::Resolved
-                        };
-                        // Simulate the chain putting this blob into pending state, which is
-                        // required before finalization.
-                        state
-                            .set_blob_pending(
-                                &store,
-                                *user,
-                                blob.hash,
-                                blob.size,
-                                sub_id.clone(),
-                                *source,
-                            )
-                            .unwrap();
-                        state
-                            .finalize_blob(
-                                &config,
-                                &store,
-                                *user,
-                                epoch,
-                                blob.hash,
-                                sub_id.clone(),
-                                status,
-                            )
-                            .unwrap();
-                    }
-                }
-            }
+    #[test]
+    fn test_debit_accounts_renews_delinquent_subscription_after_top_up() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        let initial_cost = config.blob_min_ttl as u64 * 1024;
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_atto(initial_cost),
+                current_epoch,
+            )
+            .unwrap();
+        let (hash, size, id) =
+            add_auto_renew_blob(&config, &store, &mut state, subscriber, current_epoch);
 
-            // Every debit interval epochs we debit all acounts
-            if epoch % debit_interval == 0 {
-                let deletes_from_disc = state
-                    .debit_accounts(
-                        &store,
-                        epoch,
-                        config.blob_delete_batch_size,
-                        config.account_debit_batch_size,
-                    )
-                    .unwrap();
-                warn!(
-                    "deleting {} blobs at epoch {}",
-                    deletes_from_disc.len(),
-                    epoch
-                );
-            }
-        }
+        // The subscription becomes delinquent at expiry
+        let debit_epoch = current_epoch + config.blob_min_ttl;
+        let outcome = state.debit_accounts(&config, &store, debit_epoch).unwrap();
+        assert_eq!(outcome.delinquent.len(), 1);
+        let delinquent_until = outcome.delinquent[0].delinquent_until;
 
-        let mut total_credit_used = Credit::zero();
-        for (_, credit) in credit_used.clone() {
-            total_credit_used.add_assign(&credit);
-        }
+        // Top up during the grace period
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_whole(1),
+                debit_epoch + 1,
+            )
+            .unwrap();
 
-        debug!("credit used: {}", total_credit_used);
-        debug!("num. blobs added: {}", num_added);
-        debug!("num. blobs re-added: {}", num_readded);
-        debug!("num. blobs resolved: {}", num_resolved);
-        debug!("num. blobs failed: {}", num_failed);
+        // The subscription is renewed instead of deleted at the end of the grace period
+        let outcome = state
+            .debit_accounts(&config, &store, delinquent_until)
+            .unwrap();
+        assert!(outcome.expired.is_empty());
+        assert_eq!(
+            outcome.renewed,
+            vec![RenewedSubscription {
+                subscriber,
+                hash,
+                id: id.clone(),
+                size,
+                expiry: delinquent_until + config.blob_min_ttl,
+            }]
+        );
+        let blob = state.get_blob(&store, hash).unwrap().unwrap();
+        let sub = blob
+            .subscribers
+            .hamt(&store)
+            .unwrap()
+            .get(&subscriber)
+            .unwrap()
+            .unwrap()
+            .hamt(&store)
+            .unwrap()
+            .get(&id)
+            .unwrap()
+            .unwrap();
+        assert!(!sub.is_delinquent());
+        assert_eq!(blob.status, BlobStatus::Resolved);
+    }
 
-        // Check the account balances
-        for (i, user) in users.iter().enumerate() {
-            let account = state.get_account(&store, *user).unwrap().unwrap();
-            debug!("account {}: {:#?}", i, account);
-            assert_eq!(account.capacity_used, 0);
-            assert_eq!(account.credit_committed, Credit::zero());
-            let credit_used = credit_used.get(user).unwrap();
-            assert_eq!(account.credit_free, &user_credit - credit_used);
-        }
+    #[test]
+    fn test_debit_accounts_renews_replicated_subscription_with_premium() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_whole(10),
+                current_epoch,
+            )
+            .unwrap();
+        let (hash, size, id) = add_auto_renew_blob_with(
+            &config,
+            &store,
+            &mut state,
+            subscriber,
+            subscriber,
+            current_epoch,
+            2,
+        );
+        let credit_free = state
+            .get_account(&store, subscriber)
+            .unwrap()
+            .unwrap()
+            .credit_free;
 
-        // Check state.
-        // Everything should be empty except for credit_debited.
-        let stats = state.get_stats(&config, TokenAmount::zero());
-        debug!("stats: {:#?}", stats);
-        assert_eq!(stats.capacity_used, 0);
-        assert_eq!(stats.credit_committed, Credit::zero());
-        assert_eq!(stats.credit_debited, total_credit_used);
-        assert_eq!(stats.num_blobs, 0);
-        assert_eq!(stats.num_added, 0);
-        assert_eq!(stats.bytes_added, 0);
-        assert_eq!(stats.num_resolving, 0);
-        assert_eq!(stats.bytes_resolving, 0);
+        let debit_epoch = current_epoch + config.blob_min_ttl;
+        let outcome = state.debit_accounts(&config, &store, debit_epoch).unwrap();
+        assert_eq!(
+            outcome.renewed,
+            vec![RenewedSubscription {
+                subscriber,
+                hash,
+                id,
+                size,
+                expiry: debit_epoch + config.blob_min_ttl,
+            }]
+        );
+
+        // The renewal is charged for both replicas, like the initial add
+        let account = state.get_account(&store, subscriber).unwrap().unwrap();
+        assert_eq!(
+            account.credit_free,
+            credit_free - Credit::from_whole(2 * config.blob_min_ttl as u64 * size)
+        );
     }
 
     #[test]
-    fn test_paginated_debit_accounts() {
-        let config = RecallConfig {
-            account_debit_batch_size: 5, // Process 5 accounts at a time (10 accounts total)
-            ..Default::default()
-        };
-
+    fn test_debit_accounts_marks_replicated_subscription_delinquent_without_premium() {
+        setup_logs();
+        let config = RecallConfig::default();
         let store = MemoryBlockstore::default();
         let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
         let current_epoch = ChainEpoch::from(1);
+        // Buy enough credit for the initial add with two replicas, and a renewal of one
+        let base_cost = config.blob_min_ttl as u64 * 1024;
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_atto(3 * base_cost),
+                current_epoch,
+            )
+            .unwrap();
+        let (hash, _, id) = add_auto_renew_blob_with(
+            &config,
+            &store,
+            &mut state,
+            subscriber,
+            subscriber,
+            current_epoch,
+            2,
+        );
 
-        // Create more than one batch worth of accounts (>5)
-        for i in 0..10 {
-            let address = Address::new_id(1000 + i);
-            let token_amount = TokenAmount::from_whole(10);
+        // The base cost is covered but the premium is not, so the subscription is delinquent
+        let debit_epoch = current_epoch + config.blob_min_ttl;
+        let outcome = state.debit_accounts(&config, &store, debit_epoch).unwrap();
+        assert!(outcome.renewed.is_empty());
+        assert!(outcome.expired.is_empty());
+        assert_eq!(
+            outcome.delinquent,
+            vec![DelinquentSubscription {
+                subscriber,
+                hash,
+                id,
+                delinquent_until: debit_epoch + config.blob_delinquency_grace_period,
+            }]
+        );
+    }
 
-            // Buy credits for each account
-            state
-                .buy_credit(
-                    &config,
-                    &store,
-                    address,
-                    token_amount.clone(),
-                    current_epoch,
-                )
-                .unwrap();
+    #[test]
+    fn test_debit_accounts_renews_delegated_subscription() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let delegate = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_whole(10),
+                current_epoch,
+            )
+            .unwrap();
+        state
+            .approve_credit(
+                &config,
+                &store,
+                subscriber,
+                delegate,
+                current_epoch,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        let (hash, size, id) = add_auto_renew_blob_with(
+            &config,
+            &store,
+            &mut state,
+            delegate,
+            subscriber,
+            current_epoch,
+            1,
+        );
+        let credit_used = state
+            .get_credit_approval(&store, subscriber, delegate)
+            .unwrap()
+            .unwrap()
+            .credit_used;
 
-            // Add some storage usage
-            let mut accounts = state.accounts.hamt(&store).unwrap();
-            let mut account = accounts.get(&address).unwrap().unwrap();
-            account.capacity_used = 1000;
-            accounts.set(&address, account).unwrap();
-        }
+        let debit_epoch = current_epoch + config.blob_min_ttl;
+        let outcome = state.debit_accounts(&config, &store, debit_epoch).unwrap();
+        assert_eq!(outcome.renewed.len(), 1);
 
-        // First batch (should process 5 accounts)
-        assert!(state.next_debit_addr.is_none());
-        let deletes1 = state
-            .debit_accounts(
+        // The renewed subscription keeps its delegate, whose approval is charged again
+        let blob = state.get_blob(&store, hash).unwrap().unwrap();
+        let sub = blob
+            .subscribers
+            .hamt(&store)
+            .unwrap()
+            .get(&subscriber)
+            .unwrap()
+            .unwrap()
+            .hamt(&store)
+            .unwrap()
+            .get(&id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(sub.delegate, Some(delegate));
+        let approval = state
+            .get_credit_approval(&store, subscriber, delegate)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            approval.credit_used,
+            credit_used + Credit::from_whole(config.blob_min_ttl as u64 * size)
+        );
+    }
+
+    #[test]
+    fn test_debit_accounts_expires_delegated_subscription_after_revocation() {
+        setup_logs();
+        let config = RecallConfig::default();
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
+        let delegate = new_address();
+        let current_epoch = ChainEpoch::from(1);
+        state
+            .buy_credit(
+                &config,
                 &store,
-                current_epoch + 1,
-                config.blob_delete_batch_size,
-                config.account_debit_batch_size,
+                subscriber,
+                TokenAmount::from_whole(10),
+                current_epoch,
             )
             .unwrap();
-        assert!(deletes1.is_empty()); // No expired blobs
-        assert!(state.next_debit_addr.is_some());
-
-        // Second batch (should process remaining 5 accounts and clear state)
-        let deletes2 = state
-            .debit_accounts(
+        state
+            .approve_credit(
+                &config,
                 &store,
-                current_epoch + 1,
-                config.blob_delete_batch_size,
-                config.account_debit_batch_size,
+                subscriber,
+                delegate,
+                current_epoch,
+                None,
+                None,
+                None,
+                None,
             )
             .unwrap();
-        assert!(deletes2.is_empty());
-        assert!(state.next_debit_addr.is_none()); // State should be cleared after all accounts processed
+        let (hash, size, id) = add_auto_renew_blob_with(
+            &config,
+            &store,
+            &mut state,
+            delegate,
+            subscriber,
+            current_epoch,
+            1,
+        );
+        state.revoke_credit(&store, subscriber, delegate).unwrap();
 
-        // Verify all accounts were processed
-        let reader = state.accounts.hamt(&store).unwrap();
-        reader
-            .for_each(|_, account| {
-                assert_eq!(account.last_debit_epoch, current_epoch + 1);
-                Ok(())
-            })
-            .unwrap();
+        // A missing approval is not a lack of credit, so there's no grace period
+        let debit_epoch = current_epoch + config.blob_min_ttl;
+        let outcome = state.debit_accounts(&config, &store, debit_epoch).unwrap();
+        assert!(outcome.renewed.is_empty());
+        assert!(outcome.delinquent.is_empty());
+        assert_eq!(
+            outcome.expired,
+            vec![ExpiredSubscription {
+                subscriber,
+                hash,
+                id,
+                size,
+                bytes_released: size,
+            }]
+        );
+        assert!(state.get_blob(&store, hash).unwrap().is_none());
     }
 
     #[test]
-    fn test_multiple_debit_cycles() {
+    fn test_debit_accounts_moves_past_failed_deletions() {
+        setup_logs();
         let config = RecallConfig {
-            account_debit_batch_size: 5, // Process 5 accounts at a time (10 accounts total)
+            blob_delete_batch_size: 1,
             ..Default::default()
         };
-
         let store = MemoryBlockstore::default();
         let mut state = State::new(&store).unwrap();
+        let subscriber = new_address();
         let current_epoch = ChainEpoch::from(1);
-
-        // Create accounts
-        for i in 0..10 {
-            let address = Address::new_id(1000 + i);
-            let token_amount = TokenAmount::from_whole(10);
+        state
+            .buy_credit(
+                &config,
+                &store,
+                subscriber,
+                TokenAmount::from_whole(10),
+                current_epoch,
+            )
+            .unwrap();
+        let expiry = current_epoch + config.blob_min_ttl;
+        let mut hashes = vec![];
+        for _ in 0..2 {
+            let (hash, size) = new_hash(1024);
             state
-                .buy_credit(
+                .add_blob(
                     &config,
                     &store,
-                    address,
-                    token_amount.clone(),
+                    subscriber,
+                    subscriber,
                     current_epoch,
+                    hash,
+                    new_metadata_hash(),
+                    SubscriptionId::default(),
+                    size,
+                    Some(config.blob_min_ttl),
+                    new_pk(),
+                    TokenAmount::zero(),
+                    false,
+                    1,
+                )
+                .unwrap();
+            hashes.push(hash);
+        }
+        // Index subscriptions that can't be deleted because their subscribers have no account
+        for _ in 0..3 {
+            let (hash, _) = new_hash(1024);
+            state
+                .expiries
+                .update_index(
+                    &store,
+                    new_address(),
+                    hash,
+                    &SubscriptionId::default(),
+                    vec![ExpiryUpdate::Add(expiry)],
                 )
                 .unwrap();
-
-            let mut accounts = state.accounts.hamt(&store).unwrap();
-            let mut account = accounts.get(&address).unwrap().unwrap();
-            account.capacity_used = 1000;
-            accounts.set(&address, account).unwrap();
         }
 
-        // First cycle
-        let deletes1 = state
-            .debit_accounts(
-                &store,
-                current_epoch + 1,
-                config.blob_delete_batch_size,
-                config.account_debit_batch_size,
-            )
-            .unwrap();
-        assert!(deletes1.is_empty());
-        assert!(state.next_debit_addr.is_some());
-
-        let deletes2 = state
-            .debit_accounts(
-                &store,
-                current_epoch + 1,
-                config.blob_delete_batch_size,
-                config.account_debit_batch_size,
-            )
-            .unwrap();
-        assert!(deletes2.is_empty());
-        assert!(state.next_debit_addr.is_none()); // First cycle complete
-
-        // Second cycle
-        let deletes3 = state
-            .debit_accounts(
-                &store,
-                current_epoch + 2,
-                config.blob_delete_batch_size,
-                config.account_debit_batch_size,
-            )
-            .unwrap();
-        assert!(deletes3.is_empty());
-        assert!(state.next_debit_addr.is_some());
+        // Each sweep visits one subscription; failed deletions don't stall the ones after them
+        let mut expired = vec![];
+        for _ in 0..5 {
+            let outcome = state.debit_accounts(&config, &store, expiry).unwrap();
+            expired.extend(outcome.expired.into_iter().map(|e| e.hash));
+        }
+        assert_eq!(expired.len(), 2);
+        for hash in &hashes {
+            assert!(expired.contains(hash));
+            assert!(state.get_blob(&store, *hash).unwrap().is_none());
+        }
+        assert!(state.expiries.next_idx.is_none());
+        assert!(state.expiries.next_key.is_none());
 
-        let deletes4 = state
-            .debit_accounts(
-                &store,
-                current_epoch + 2,
-                config.blob_delete_batch_size,
-                config.account_debit_batch_size,
-            )
+        // The failed subscriptions stay indexed and are retried on the next pass
+        assert_eq!(state.expiries.len(&store).unwrap(), 1);
+        let mut remaining = 0;
+        state
+            .expiries
+            .clone()
+            .foreach_up_to_epoch(&store, expiry, None, |_, _, _| {
+                remaining += 1;
+                Ok(())
+            })
             .unwrap();
-        assert!(deletes4.is_empty());
-        assert!(state.next_debit_addr.is_none()); // Second cycle complete
+        assert_eq!(remaining, 3);
     }
 }
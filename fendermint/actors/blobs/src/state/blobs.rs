@@ -120,20 +120,43 @@ impl BlobsProgressCollection {
         Ok(())
     }
 
-    /// Returns a page of entries from the collection.
+    /// Returns a page of entries from the collection, starting from `start_key`,
+    /// along with the key to continue from, if there are more entries.
     pub fn take_page<BS: Blockstore>(
         &self,
         store: BS,
         size: u32,
-    ) -> Result<Vec<(Hash, BlobSourceSet)>, ActorError> {
+        start_key: Option<Hash>,
+    ) -> Result<(Vec<(Hash, BlobSourceSet)>, Option<Hash>), ActorError> {
         let map = self.hamt(store)?;
-        let mut page = Vec::with_capacity(size as usize);
-        map.for_each_ranged(None, Some(size as usize), |hash, set| {
-            page.push((hash, set.clone()));
-            Ok(true)
+        let mut entries = map.iter_from(start_key.as_ref())?;
+        let page = entries
+            .by_ref()
+            .take(size as usize)
+            .map(|res| res.map(|(hash, set)| (hash, set.clone())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let next_key = entries.next().transpose()?.map(|(hash, _)| hash);
+        Ok((page, next_key))
+    }
+
+    /// Returns the number of entries in the collection with a source from the given subscriber,
+    /// not counting the entry for `exclude`.
+    /// This walks the whole collection.
+    pub fn count_subscriber<BS: Blockstore>(
+        &self,
+        store: BS,
+        subscriber: &Address,
+        exclude: &Hash,
+    ) -> Result<u64, ActorError> {
+        let map = self.hamt(store)?;
+        let mut count = 0;
+        map.for_each(|hash, set| {
+            if &hash != exclude && set.iter().any(|(addr, _, _)| addr == subscriber) {
+                count += 1;
+            }
+            Ok(())
         })?;
-        page.shrink_to_fit();
-        Ok(page)
+        Ok(count)
     }
 
     /// Removes a source from an entry in the collection.
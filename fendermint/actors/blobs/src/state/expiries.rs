@@ -9,17 +9,16 @@ use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
 use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
-use recall_ipld::amt::vec::TrackedFlushResult;
-use recall_ipld::{amt, hamt};
+use recall_ipld::ordered_set_map::{self, OrderedSetMap};
 
 use crate::state::ExpiryKey;
 
-type PerChainEpochRoot = hamt::Root<Address, hamt::Root<ExpiryKey, ()>>;
-
 #[derive(Debug, Clone, Serialize_tuple, Deserialize_tuple)]
 pub struct ExpiriesState {
-    pub root: amt::Root<PerChainEpochRoot>,
+    pub root: ordered_set_map::Root<ExpiryKey>,
     pub next_idx: Option<u64>,
+    /// The subscription to resume from within the `next_idx` epoch, if it was partially visited.
+    pub next_key: Option<ExpiryKey>,
 }
 
 impl ExpiriesState {
@@ -27,41 +26,32 @@ impl ExpiriesState {
         "expiries".to_string()
     }
 
-    fn store_name_per_chain_epoch(chain_epoch: ChainEpoch) -> String {
-        format!("{}.{}", ExpiriesState::store_name(), chain_epoch)
-    }
-
-    fn store_name_per_address(chain_epoch: ChainEpoch, address: &Address) -> String {
-        format!(
-            "{}.{}",
-            ExpiriesState::store_name_per_chain_epoch(chain_epoch),
-            address
-        )
-    }
-
     pub fn new<BS: Blockstore>(store: &BS) -> Result<Self, ActorError> {
-        let root = amt::Root::<PerChainEpochRoot>::new(store)?;
+        let root = OrderedSetMap::<_, ChainEpoch, ExpiryKey>::flush_empty(store)?;
         Ok(Self {
             root,
             next_idx: None,
+            next_key: None,
         })
     }
 
-    pub fn amt<BS: Blockstore>(
+    fn set_map<BS: Blockstore + Clone>(
         &self,
         store: BS,
-    ) -> Result<amt::vec::Amt<BS, PerChainEpochRoot>, ActorError> {
-        self.root.amt(store)
-    }
-
-    pub fn save_tracked(&mut self, tracked_flush_result: TrackedFlushResult<PerChainEpochRoot>) {
-        self.root = tracked_flush_result.root;
+    ) -> Result<OrderedSetMap<BS, ChainEpoch, ExpiryKey>, ActorError> {
+        OrderedSetMap::load(store, &self.root, &Self::store_name())
     }
 
     pub fn len<BS: Blockstore>(&self, store: BS) -> Result<u64, ActorError> {
-        Ok(self.root.amt(store)?.count())
+        Ok(self.set_map(&store)?.len())
     }
 
+    /// Calls `f` for each subscription expiring at or before `epoch`, in epoch order.
+    ///
+    /// If `batch_size` is given, at most that many subscriptions are visited, and the epoch
+    /// and subscription to resume from are stored in `next_idx` and `next_key`.
+    /// Visited subscriptions that callers fail to remove or move are not visited again until
+    /// a later pass starts over from the earliest epoch.
     pub fn foreach_up_to_epoch<BS: Blockstore, F>(
         &mut self,
         store: BS,
@@ -72,27 +62,25 @@ impl ExpiriesState {
     where
         F: FnMut(ChainEpoch, Address, ExpiryKey) -> Result<(), ActorError>,
     {
-        let expiries = self.amt(&store)?;
-        let (count, next_idx) = expiries.for_each_while_ranged(
-            self.next_idx,
-            batch_size,
-            |index, per_chain_epoch_root| {
-                if index > epoch as u64 {
-                    return Ok(false);
-                }
-                let per_chain_epoch_hamt = per_chain_epoch_root.hamt(&store, 0)?;
-                per_chain_epoch_hamt.for_each(|address, per_address_root| {
-                    let per_address_hamt = per_address_root.hamt(&store, 0)?;
-                    per_address_hamt.for_each(|expiry_key, _| f(index as i64, address, expiry_key))
-                })?;
-                Ok(true)
-            },
-        )?;
-        self.next_idx = batch_size.and(next_idx);
+        let expiries = self.set_map(&store)?;
+        let start = self
+            .next_idx
+            .map(|idx| (idx as ChainEpoch, self.next_key.clone()));
+        let (count, next) =
+            expiries.for_each_up_to(start, epoch, batch_size, |expiry, expiry_key| {
+                f(expiry, expiry_key.subscriber, expiry_key)
+            })?;
+        let (next_idx, next_key) = match batch_size.and(next) {
+            Some((expiry, key)) => (Some(expiry as u64), key),
+            None => (None, None),
+        };
+        self.next_idx = next_idx;
+        self.next_key = next_key;
         log::info!(
-            "finished deleting {} blobs, next_idx: {:?}, current_epoch: {}",
+            "finished processing {} expired subscriptions, next_idx: {:?}, next_key: {:?}, current_epoch: {}",
             count,
             self.next_idx,
+            self.next_key,
             epoch
         );
         Ok(())
@@ -106,73 +94,17 @@ impl ExpiriesState {
         id: &SubscriptionId,
         updates: Vec<ExpiryUpdate>,
     ) -> Result<(), ActorError> {
-        let mut expiries = self.amt(&store)?;
+        let mut expiries = self.set_map(&store)?;
+        let expiry_key = ExpiryKey::new(subscriber, hash, id);
         for update in updates {
             match update {
-                ExpiryUpdate::Add(chain_epoch) => {
-                    // You cannot do get_or_create here: it expects value, we give it Result<Option<Value>>
-                    let per_chain_epoch_root =
-                        if let Some(per_chain_epoch_root) = expiries.get(chain_epoch as u64)? {
-                            per_chain_epoch_root
-                        } else {
-                            hamt::Root::<Address, hamt::Root<ExpiryKey, ()>>::new(
-                                &store,
-                                &ExpiriesState::store_name_per_chain_epoch(chain_epoch),
-                            )?
-                        };
-                    // The size does not matter
-                    let mut per_chain_epoch_hamt = per_chain_epoch_root.hamt(&store, 1)?;
-                    // You cannot do get_or_create here: it expects value, we give it Result<Option<Value>>
-                    let per_address_root =
-                        if let Some(per_address_root) = per_chain_epoch_hamt.get(&subscriber)? {
-                            per_address_root
-                        } else {
-                            hamt::Root::<ExpiryKey, ()>::new(
-                                &store,
-                                &ExpiriesState::store_name_per_address(chain_epoch, &subscriber),
-                            )?
-                        };
-                    let mut per_address_hamt = per_address_root.hamt(&store, 1)?; // The size does not matter here
-                    let expiry_key = ExpiryKey::new(hash, id);
-                    let per_address_root = per_address_hamt.set_and_flush(&expiry_key, ())?;
-                    let per_chain_epoch_root =
-                        per_chain_epoch_hamt.set_and_flush(&subscriber, per_address_root)?;
-                    self.save_tracked(
-                        expiries.set_and_flush_tracked(chain_epoch as u64, per_chain_epoch_root)?,
-                    );
-                }
+                ExpiryUpdate::Add(chain_epoch) => expiries.add(chain_epoch, &expiry_key)?,
                 ExpiryUpdate::Remove(chain_epoch) => {
-                    if let Some(mut per_chain_epoch_root) = expiries.get(chain_epoch as u64)? {
-                        let mut per_chain_epoch_hamt = per_chain_epoch_root.hamt(&store, 1)?; // The size does not matter here
-                        if let Some(mut per_address_root) = per_chain_epoch_hamt.get(&subscriber)? {
-                            let mut per_address_hamt = per_address_root.hamt(&store, 1)?; // The size does not matter here
-                            let expiry_key = ExpiryKey::new(hash, id);
-                            (per_address_root, _) =
-                                per_address_hamt.delete_and_flush(&expiry_key)?;
-                            if per_address_hamt.is_empty() {
-                                (per_chain_epoch_root, _) =
-                                    per_chain_epoch_hamt.delete_and_flush(&subscriber)?;
-                            } else {
-                                per_chain_epoch_root = per_chain_epoch_hamt
-                                    .set_and_flush(&subscriber, per_address_root)?;
-                            }
-                        }
-                        if per_chain_epoch_hamt.is_empty() {
-                            self.save_tracked(
-                                expiries.delete_and_flush_tracked(chain_epoch as u64)?,
-                            );
-                        } else {
-                            self.save_tracked(
-                                expiries.set_and_flush_tracked(
-                                    chain_epoch as u64,
-                                    per_chain_epoch_root,
-                                )?,
-                            );
-                        }
-                    }
+                    expiries.remove(chain_epoch, &expiry_key)?;
                 }
             }
         }
+        self.root = expiries.flush()?;
         Ok(())
     }
 }
@@ -244,6 +176,72 @@ mod tests {
         assert_eq!(range.len(), 9);
     }
 
+    #[test]
+    fn test_expiries_many_subscriptions_per_epoch() {
+        let store = MemoryBlockstore::default();
+        let mut state = ExpiriesState::new(&store).unwrap();
+        let addr = new_address();
+
+        // Several blobs, and several subscriptions to the same blob, expire at epoch 10
+        let (shared_hash, _) = new_hash(1024);
+        let mut keys = vec![];
+        for id in ["a", "b", "c"] {
+            keys.push((shared_hash, SubscriptionId::new(id).unwrap(), 10));
+        }
+        for _ in 0..2 {
+            let (hash, _) = new_hash(1024);
+            keys.push((hash, SubscriptionId::default(), 10));
+        }
+        let (hash, _) = new_hash(1024);
+        keys.push((hash, SubscriptionId::default(), 20));
+        for (hash, id, epoch) in &keys {
+            state
+                .update_index(&store, addr, *hash, id, vec![ExpiryUpdate::Add(*epoch)])
+                .unwrap();
+        }
+        assert_eq!(state.len(&store).unwrap(), 2);
+
+        // Process in batches of 2, removing visited subscriptions like the debit sweep does
+        let mut processed = vec![];
+        loop {
+            let mut cursor = state.clone();
+            let mut batch = vec![];
+            cursor
+                .foreach_up_to_epoch(&store, 20, Some(2), |epoch, subscriber, key| {
+                    assert_eq!(subscriber, addr);
+                    batch.push((epoch, key));
+                    Ok(())
+                })
+                .unwrap();
+            assert!(batch.len() <= 2);
+            for (epoch, key) in &batch {
+                state
+                    .update_index(
+                        &store,
+                        key.subscriber,
+                        key.hash,
+                        &key.id,
+                        vec![ExpiryUpdate::Remove(*epoch)],
+                    )
+                    .unwrap();
+            }
+            processed.extend(batch);
+            state.next_idx = cursor.next_idx;
+            state.next_key = cursor.next_key;
+            if state.next_idx.is_none() {
+                break;
+            }
+        }
+
+        assert_eq!(processed.len(), keys.len());
+        for (hash, id, epoch) in &keys {
+            assert!(processed
+                .iter()
+                .any(|(e, key)| e == epoch && key.hash == *hash && key.id == *id));
+        }
+        assert_eq!(state.len(&store).unwrap(), 0);
+    }
+
     #[test]
     fn test_expiries_pagination() {
         let store = MemoryBlockstore::default();
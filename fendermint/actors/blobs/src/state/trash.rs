@@ -0,0 +1,91 @@
+// Copyright 2025 Recall Contributors
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fendermint_actor_blobs_shared::state::{Hash, PublicKey, SubscriptionId};
+use fil_actors_runtime::ActorError;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use recall_ipld::hamt;
+use recall_ipld::hamt::map::TrackedFlushResult;
+
+use crate::state::expiries::ExpiriesState;
+
+/// A blob subscription that was moved to the trash and can still be restored.
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct TrashedSubscription {
+    /// The subscriber of the trashed subscription.
+    pub subscriber: Address,
+    /// Subscription ID.
+    pub id: SubscriptionId,
+    /// Expiry block of the subscription at the time it was trashed.
+    pub expiry: ChainEpoch,
+    /// Source Iroh node ID used for ingestion.
+    pub source: PublicKey,
+    /// Whether the subscription should be renewed by its TTL when it expires.
+    pub auto_renew: bool,
+    /// Number of independent Iroh nodes that should hold the blob.
+    pub replication: u32,
+    /// Epoch at which the trash window ends and the subscription can no longer be restored.
+    pub trashed_until: ChainEpoch,
+}
+
+/// A blob with one or more trashed subscriptions.
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct TrashedBlob {
+    /// Blob size.
+    pub size: u64,
+    /// Blob metadata that contains information for blob recovery.
+    pub metadata_hash: Hash,
+    /// The trashed subscriptions of the blob.
+    pub subscriptions: Vec<TrashedSubscription>,
+}
+
+impl TrashedBlob {
+    /// Removes and returns the trashed subscription for the given subscriber and ID, if any.
+    pub fn take(
+        &mut self,
+        subscriber: &Address,
+        id: &SubscriptionId,
+    ) -> Option<TrashedSubscription> {
+        let index = self
+            .subscriptions
+            .iter()
+            .position(|sub| &sub.subscriber == subscriber && &sub.id == id)?;
+        Some(self.subscriptions.remove(index))
+    }
+}
+
+/// Trashed blob subscriptions and an index of when their trash windows end.
+#[derive(Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct TrashState {
+    pub root: hamt::Root<Hash, TrashedBlob>,
+    size: u64,
+    /// Index of trashed subscriptions by the epoch their trash window ends.
+    pub expiries: ExpiriesState,
+}
+
+impl TrashState {
+    pub fn new<BS: Blockstore>(store: &BS) -> Result<Self, ActorError> {
+        let root = hamt::Root::<Hash, TrashedBlob>::new(store, "trash")?;
+        Ok(Self {
+            root,
+            size: 0,
+            expiries: ExpiriesState::new(store)?,
+        })
+    }
+
+    pub fn hamt<BS: Blockstore>(
+        &self,
+        store: BS,
+    ) -> Result<hamt::map::Hamt<BS, Hash, TrashedBlob>, ActorError> {
+        self.root.hamt(store, self.size)
+    }
+
+    pub fn save_tracked(&mut self, tracked_flush_result: TrackedFlushResult<Hash, TrashedBlob>) {
+        self.root = tracked_flush_result.root;
+        self.size = tracked_flush_result.size;
+    }
+}
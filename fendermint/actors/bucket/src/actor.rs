@@ -6,9 +6,9 @@ use std::collections::HashMap;
 
 use fendermint_actor_blobs_shared::{
     add_blob, delete_blob, get_blob, has_credit_approval, overwrite_blob,
-    state::{BlobInfo, BlobStatus, SubscriptionId},
+    state::{BlobInfo, BlobStatus, Hash, SubscriptionId},
 };
-use fendermint_actor_machine::MachineActor;
+use fendermint_actor_machine::{is_writer, MachineActor, MachineState};
 use fil_actors_runtime::{
     actor_dispatch, actor_error,
     runtime::{ActorCode, Runtime},
@@ -42,6 +42,7 @@ impl Actor {
     /// We will pass the bucket owner as the `subscriber`,
     /// and the Blobs actor will enforce that the `from` address is either
     /// the `subscriber` or has a valid credit delegation from the `subscriber`.
+    /// Writers act through the bucket, see [`blobs_sender`].
     /// The `from` address must be the origin or the caller.
     fn add_object(rt: &impl Runtime, params: AddParams) -> Result<Object, ActorError> {
         rt.validate_immediate_caller_accept_any()?;
@@ -50,19 +51,25 @@ impl Actor {
         require_addr_is_origin_or_caller(rt, from)?;
 
         let state = rt.state::<State>()?;
+        state.ensure_not_frozen()?;
+        let sender = blobs_sender(rt, &state, from)?;
         let sub_id = get_blob_id(&state, &params.key)?;
         let key = BytesKey(params.key.clone());
 
         validate_metadata(&params.metadata)?;
 
+        let mut replaced = None;
         let sub = if let Some(object) = state.get(rt.store(), &key)? {
             // If we have existing blob and it's not expired
             let expired = object.expiry <= rt.curr_epoch();
             if params.overwrite || expired {
+                if object.hash != params.hash {
+                    replaced = Some(object.hash);
+                }
                 // Overwrite if the flag is passed
                 overwrite_blob(
                     rt,
-                    from,
+                    sender,
                     object.hash,
                     sub_id,
                     params.hash,
@@ -82,7 +89,7 @@ impl Actor {
             // No object found, just a new blob
             add_blob(
                 rt,
-                params.from,
+                sender,
                 sub_id,
                 params.hash,
                 Some(state.owner),
@@ -110,6 +117,12 @@ impl Actor {
             ObjectAdded::new(&params.key, &params.hash, &params.metadata),
         )?;
 
+        // Tagging happens outside the chain and can't be rolled back, so it goes last.
+        tag_on_disc(params.hash, &params.key)?;
+        if let Some(hash) = replaced {
+            untag_on_disc(hash, &params.key)?;
+        }
+
         Ok(Object {
             hash: params.hash,
             recovery_hash: params.recovery_hash,
@@ -124,7 +137,9 @@ impl Actor {
     /// Access control will be enforced by the Blobs actor.
     /// We will pass the bucket owner as the `subscriber`,
     /// and the Blobs actor will enforce that the `from` address is either
-    /// the `subscriber` or has a valid credit delegation from the `subscriber`.
+    /// the `subscriber` or the delegate that added the object.
+    /// Writers act through the bucket, see [`blobs_sender`], so they can delete objects added
+    /// by any writer, but not objects the owner or other delegates added.
     /// The `from` address must be the origin or the caller.
     fn delete_object(rt: &impl Runtime, params: DeleteParams) -> Result<(), ActorError> {
         rt.validate_immediate_caller_accept_any()?;
@@ -133,6 +148,8 @@ impl Actor {
         require_addr_is_origin_or_caller(rt, from)?;
 
         let state = rt.state::<State>()?;
+        state.ensure_not_frozen()?;
+        let sender = blobs_sender(rt, &state, from)?;
         let sub_id = get_blob_id(&state, &params.key)?;
         let key = BytesKey(params.key);
         let object = state
@@ -140,12 +157,14 @@ impl Actor {
             .ok_or(ActorError::illegal_state("object not found".into()))?;
 
         // Delete blob for object
-        delete_blob(rt, from, sub_id, object.hash, Some(state.owner))?;
+        delete_blob(rt, sender, sub_id, object.hash, Some(state.owner))?;
 
         rt.transaction(|st: &mut State, rt| st.delete(rt.store(), &key))?;
 
         emit_evm_event(rt, ObjectDeleted::new(&key, &object.hash))?;
 
+        untag_on_disc(object.hash, &key.0)?;
+
         Ok(())
     }
 
@@ -205,7 +224,7 @@ impl Actor {
 
     /// Updates object metadata.
     ///
-    /// Only the bucket owner or an account with a credit delegation
+    /// Only the bucket owner, one of its writers, or an account with a credit delegation
     /// from the bucket owner can update object metadata.
     /// The `from` address must be the origin or the caller.
     fn update_object_metadata(
@@ -219,12 +238,13 @@ impl Actor {
 
         let key = BytesKey(params.key.clone());
         let state = rt.state::<State>()?;
+        state.ensure_not_frozen()?;
         let mut object = state
             .get(rt.store(), &key)?
             .ok_or(ActorError::illegal_state("object not found".into()))?;
 
         let bucket_owner = state.owner;
-        if !has_credit_approval(rt, bucket_owner, from)? {
+        if !is_writer(rt.store(), &state, from)? && !has_credit_approval(rt, bucket_owner, from)? {
             return Err(actor_error!(
                 forbidden;
                 format!("Unauthorized: missing delegation from bucket owner {} to {}", bucket_owner, from)));
@@ -274,6 +294,53 @@ impl Actor {
     }
 }
 
+/// Returns the address that adds and deletes blobs with the Blobs actor on behalf of `from`.
+///
+/// Writers other than the owner act through the bucket itself, so they spend the owner's credit
+/// under a single credit approval from the owner to the bucket, instead of each needing an
+/// approval of their own.
+/// Everyone else acts as themselves.
+fn blobs_sender(rt: &impl Runtime, state: &State, from: Address) -> Result<Address, ActorError> {
+    if from != state.owner && is_writer(rt.store(), state, from)? {
+        Ok(rt.message().receiver())
+    } else {
+        Ok(from)
+    }
+}
+
+/// Makes a syscall that will tag a blob in the underlying Iroh-based data store under this
+/// bucket's namespace, so operators can attribute local disk usage to the bucket.
+///
+/// The tag is specific to the object key, because several keys can refer to the same blob.
+fn tag_on_disc(hash: Hash, key: &[u8]) -> Result<(), ActorError> {
+    #[cfg(feature = "fil-actor")]
+    {
+        recall_actor_sdk::hash_tag(hash.0, *blake3::hash(key).as_bytes())
+            .map_err(|en| ActorError::unspecified(format!("failed to tag blob on disc: {:?}", en)))
+    }
+    #[cfg(not(feature = "fil-actor"))]
+    {
+        let _ = (hash, key);
+        Ok(())
+    }
+}
+
+/// Makes a syscall that will remove the tag set for an object key from a blob in the underlying
+/// Iroh-based data store.
+fn untag_on_disc(hash: Hash, key: &[u8]) -> Result<(), ActorError> {
+    #[cfg(feature = "fil-actor")]
+    {
+        recall_actor_sdk::hash_untag(hash.0, *blake3::hash(key).as_bytes()).map_err(|en| {
+            ActorError::unspecified(format!("failed to untag blob on disc: {:?}", en))
+        })
+    }
+    #[cfg(not(feature = "fil-actor"))]
+    {
+        let _ = (hash, key);
+        Ok(())
+    }
+}
+
 /// Returns a blob subscription ID specific to this machine and object key.
 fn get_blob_id(state: &State, key: &[u8]) -> anyhow::Result<SubscriptionId, ActorError> {
     let mut data = state.address.get()?.payload_bytes();
@@ -376,6 +443,13 @@ impl ActorCode for Actor {
         Init => init,
         GetAddress => get_address,
         GetMetadata => get_metadata,
+        AddWriter => add_writer,
+        RemoveWriter => remove_writer,
+        ListWriters => list_writers,
+        TransferOwnership => transfer_ownership,
+        AcceptOwnership => accept_ownership,
+        Freeze => freeze,
+        Unfreeze => unfreeze,
         AddObject => add_object,
         DeleteObject => delete_object,
         GetObject => get_object,
@@ -398,12 +472,16 @@ mod tests {
         Method as BlobMethod, BLOBS_ACTOR_ADDR,
     };
     use fendermint_actor_blobs_testing::{new_hash, new_pk, setup_logs};
+    use fendermint_actor_machine::registry::{
+        MACHINE_REGISTRY_ACTOR_ADDR, REGISTER_MACHINE_METHOD,
+    };
     use fendermint_actor_machine::sol_facade::{MachineCreated, MachineInitialized};
-    use fendermint_actor_machine::{ConstructorParams, InitParams, Kind};
+    use fendermint_actor_machine::{AddWriterParams, ConstructorParams, InitParams, Kind};
     use fil_actors_evm_shared::address::EthAddress;
     use fil_actors_runtime::runtime::Runtime;
     use fil_actors_runtime::test_utils::{
-        expect_empty, MockRuntime, ADM_ACTOR_CODE_ID, ETHACCOUNT_ACTOR_CODE_ID, INIT_ACTOR_CODE_ID,
+        expect_empty, make_identity_cid, MockRuntime, ADM_ACTOR_CODE_ID, ETHACCOUNT_ACTOR_CODE_ID,
+        INIT_ACTOR_CODE_ID,
     };
     use fil_actors_runtime::{ADM_ACTOR_ADDR, INIT_ACTOR_ADDR};
     use fvm_ipld_encoding::ipld_block::IpldBlock;
@@ -435,6 +513,21 @@ mod tests {
 
         rt.set_caller(*INIT_ACTOR_CODE_ID, INIT_ACTOR_ADDR);
         rt.expect_validate_caller_addr(vec![INIT_ACTOR_ADDR]);
+        rt.set_address_actor_type(
+            MACHINE_REGISTRY_ACTOR_ADDR,
+            make_identity_cid(b"machine_registry"),
+        );
+        rt.expect_send(
+            MACHINE_REGISTRY_ACTOR_ADDR,
+            REGISTER_MACHINE_METHOD,
+            None,
+            TokenAmount::from_whole(0),
+            None,
+            SendFlags::empty(),
+            None,
+            ExitCode::OK,
+            None,
+        );
         let metadata = HashMap::new();
         let event = to_actor_event(MachineCreated::new(
             Kind::Bucket,
@@ -522,6 +615,8 @@ mod tests {
                 size: add_params.size,
                 ttl: add_params.ttl,
                 from: origin,
+                auto_renew: false,
+                replication: None,
             })
             .unwrap(),
             TokenAmount::from_whole(0),
@@ -578,6 +673,8 @@ mod tests {
                 size: add_params.size,
                 ttl: add_params.ttl,
                 from: origin,
+                auto_renew: false,
+                replication: None,
             })
             .unwrap(),
             TokenAmount::from_whole(0),
@@ -628,6 +725,8 @@ mod tests {
                     size: add_params2.size,
                     ttl: add_params2.ttl,
                     from: origin,
+                    auto_renew: false,
+                    replication: None,
                 },
             })
             .unwrap(),
@@ -685,6 +784,8 @@ mod tests {
                 size: add_params.size,
                 ttl: add_params.ttl,
                 from: origin,
+                auto_renew: false,
+                replication: None,
             })
             .unwrap(),
             TokenAmount::from_whole(0),
@@ -694,6 +795,11 @@ mod tests {
                 source: add_params.source,
                 delegate: None,
                 failed: false,
+                auto_renew: false,
+                ttl: ChainEpoch::from(3600),
+                metadata: HashMap::new(),
+                delinquent_until: None,
+                replication: 1,
             })
             .unwrap(),
             ExitCode::OK,
@@ -772,6 +878,8 @@ mod tests {
                 metadata_hash: add_params.recovery_hash,
                 ttl: add_params.ttl,
                 from: origin,
+                auto_renew: false,
+                replication: None,
             })
             .unwrap(),
             TokenAmount::from_whole(0),
@@ -805,6 +913,7 @@ mod tests {
                 hash: add_params.hash,
                 id: sub_id,
                 from: origin,
+                trash: false,
             })
             .unwrap(),
             TokenAmount::from_whole(0),
@@ -820,6 +929,139 @@ mod tests {
         rt.verify();
     }
 
+    #[test]
+    pub fn test_writer_add_and_delete_object() {
+        let (rt, origin) = get_runtime();
+        let bucket = rt.receiver;
+        let writer = Address::new_id(112);
+
+        rt.expect_validate_caller_addr(vec![origin]);
+        rt.call::<Actor>(
+            Method::AddWriter as u64,
+            IpldBlock::serialize_cbor(&AddWriterParams(writer)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        // A writer adds objects through the bucket, spending the owner's credit
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, writer);
+        rt.set_origin(writer);
+        let key = vec![0, 1, 2];
+        let hash = new_hash(256);
+        let add_params: AddParams = AddParams {
+            source: new_pk(),
+            key: key.clone(),
+            hash: hash.0,
+            size: hash.1,
+            recovery_hash: new_hash(256).0,
+            ttl: None,
+            metadata: HashMap::new(),
+            from: writer,
+            overwrite: false,
+        };
+        rt.expect_validate_caller_any();
+        let state = rt.state::<State>().unwrap();
+        let sub_id = get_blob_id(&state, &key).unwrap();
+        rt.expect_send_simple(
+            BLOBS_ACTOR_ADDR,
+            BlobMethod::AddBlob as MethodNum,
+            IpldBlock::serialize_cbor(&AddBlobParams {
+                sponsor: Some(origin),
+                source: add_params.source,
+                hash: add_params.hash,
+                id: sub_id.clone(),
+                size: add_params.size,
+                metadata_hash: add_params.recovery_hash,
+                ttl: add_params.ttl,
+                from: bucket,
+                auto_renew: false,
+                replication: None,
+            })
+            .unwrap(),
+            TokenAmount::from_whole(0),
+            IpldBlock::serialize_cbor(&Subscription::default()).unwrap(),
+            ExitCode::OK,
+        );
+        expect_emitted_add_event(&rt, &add_params);
+        rt.call::<Actor>(
+            Method::AddObject as u64,
+            IpldBlock::serialize_cbor(&add_params).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        // Deletes go through the bucket too
+        let delete_params = DeleteParams { key, from: writer };
+        rt.expect_validate_caller_any();
+        rt.expect_send_simple(
+            BLOBS_ACTOR_ADDR,
+            BlobMethod::DeleteBlob as MethodNum,
+            IpldBlock::serialize_cbor(&DeleteBlobParams {
+                sponsor: Some(origin),
+                hash: add_params.hash,
+                id: sub_id,
+                from: bucket,
+                trash: false,
+            })
+            .unwrap(),
+            TokenAmount::from_whole(0),
+            None,
+            ExitCode::OK,
+        );
+        expect_emitted_delete_event(&rt, &delete_params, add_params.hash);
+        rt.call::<Actor>(
+            Method::DeleteObject as u64,
+            IpldBlock::serialize_cbor(&delete_params).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        // Accounts that aren't writers act as themselves
+        let alien = Address::new_id(113);
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, alien);
+        rt.set_origin(alien);
+        let hash = new_hash(256);
+        let add_params = AddParams {
+            source: new_pk(),
+            key: vec![3, 4, 5],
+            hash: hash.0,
+            size: hash.1,
+            recovery_hash: new_hash(256).0,
+            ttl: None,
+            metadata: HashMap::new(),
+            from: alien,
+            overwrite: false,
+        };
+        rt.expect_validate_caller_any();
+        rt.expect_send_simple(
+            BLOBS_ACTOR_ADDR,
+            BlobMethod::AddBlob as MethodNum,
+            IpldBlock::serialize_cbor(&AddBlobParams {
+                sponsor: Some(origin),
+                source: add_params.source,
+                hash: add_params.hash,
+                id: get_blob_id(&state, &add_params.key).unwrap(),
+                size: add_params.size,
+                metadata_hash: add_params.recovery_hash,
+                ttl: add_params.ttl,
+                from: alien,
+                auto_renew: false,
+                replication: None,
+            })
+            .unwrap(),
+            TokenAmount::from_whole(0),
+            IpldBlock::serialize_cbor(&Subscription::default()).unwrap(),
+            ExitCode::OK,
+        );
+        expect_emitted_add_event(&rt, &add_params);
+        rt.call::<Actor>(
+            Method::AddObject as u64,
+            IpldBlock::serialize_cbor(&add_params).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+    }
+
     #[test]
     pub fn test_get_object_none() {
         let (rt, _) = get_runtime();
@@ -873,6 +1115,8 @@ mod tests {
                 metadata_hash: add_params.recovery_hash,
                 ttl: add_params.ttl,
                 from: origin,
+                auto_renew: false,
+                replication: None,
             })
             .unwrap(),
             TokenAmount::from_whole(0),
@@ -882,6 +1126,11 @@ mod tests {
                 source: add_params.source,
                 delegate: None,
                 failed: false,
+                auto_renew: false,
+                ttl: ChainEpoch::from(3600),
+                metadata: HashMap::new(),
+                delinquent_until: None,
+                replication: 1,
             })
             .unwrap(),
             ExitCode::OK,
@@ -974,6 +1223,8 @@ mod tests {
                 size: add_params.size,
                 ttl: add_params.ttl,
                 from: origin,
+                auto_renew: false,
+                replication: None,
             })
             .unwrap(),
             TokenAmount::from_whole(0),
@@ -983,6 +1234,11 @@ mod tests {
                 source: add_params.source,
                 delegate: None,
                 failed: false,
+                auto_renew: false,
+                ttl: ChainEpoch::from(3600),
+                metadata: HashMap::new(),
+                delinquent_until: None,
+                replication: 1,
             })
             .unwrap(),
             ExitCode::OK,
@@ -1092,6 +1348,7 @@ mod tests {
                 expiry: None,
                 credit_used: TokenAmount::from_whole(0),
                 gas_fee_used: TokenAmount::from_whole(0),
+                caller_allowlist: None,
             }))
             .unwrap(),
             ExitCode::OK,
@@ -6,7 +6,9 @@ use std::collections::HashMap;
 
 use fendermint_actor_blobs_shared::state::{Hash, PublicKey};
 use fendermint_actor_machine::{
-    GET_ADDRESS_METHOD, GET_METADATA_METHOD, INIT_METHOD, METHOD_CONSTRUCTOR,
+    ACCEPT_OWNERSHIP_METHOD, ADD_WRITER_METHOD, FREEZE_METHOD, GET_ADDRESS_METHOD,
+    GET_METADATA_METHOD, INIT_METHOD, LIST_WRITERS_METHOD, METHOD_CONSTRUCTOR,
+    REMOVE_WRITER_METHOD, TRANSFER_OWNERSHIP_METHOD, UNFREEZE_METHOD,
 };
 use fvm_ipld_encoding::{strict_bytes, tuple::*};
 use fvm_shared::address::Address;
@@ -28,6 +30,13 @@ pub enum Method {
     Init = INIT_METHOD,
     GetAddress = GET_ADDRESS_METHOD,
     GetMetadata = GET_METADATA_METHOD,
+    AddWriter = ADD_WRITER_METHOD,
+    RemoveWriter = REMOVE_WRITER_METHOD,
+    ListWriters = LIST_WRITERS_METHOD,
+    TransferOwnership = TRANSFER_OWNERSHIP_METHOD,
+    AcceptOwnership = ACCEPT_OWNERSHIP_METHOD,
+    Freeze = FREEZE_METHOD,
+    Unfreeze = UNFREEZE_METHOD,
     AddObject = frc42_dispatch::method_hash!("AddObject"),
     DeleteObject = frc42_dispatch::method_hash!("DeleteObject"),
     GetObject = frc42_dispatch::method_hash!("GetObject"),
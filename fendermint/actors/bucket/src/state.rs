@@ -8,7 +8,7 @@ use std::string::FromUtf8Error;
 
 use cid::Cid;
 use fendermint_actor_blobs_shared::state::Hash;
-use fendermint_actor_machine::{Kind, MachineAddress, MachineState};
+use fendermint_actor_machine::{Kind, MachineAddress, MachineState, WritersState};
 use fil_actors_runtime::ActorError;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
@@ -32,8 +32,14 @@ pub struct State {
     pub address: MachineAddress,
     /// The machine robust owner address.
     pub owner: Address,
+    /// The proposed owner, until they accept the transfer.
+    pub pending_owner: Option<Address>,
     /// The objects Hamt.
     pub objects: ObjectsState,
+    /// Addresses that can write in addition to the owner.
+    pub writers: WritersState,
+    /// Whether the machine is read-only.
+    pub frozen: bool,
     /// User-defined metadata (e.g., bucket name, etc.).
     pub metadata: HashMap<String, String>,
 }
@@ -46,7 +52,10 @@ impl MachineState for State {
         Ok(Self {
             address: Default::default(),
             objects: ObjectsState::new(store)?,
+            writers: WritersState::new(store)?,
+            frozen: false,
             owner,
+            pending_owner: None,
             metadata,
         })
     }
@@ -70,6 +79,45 @@ impl MachineState for State {
     fn metadata(&self) -> HashMap<String, String> {
         self.metadata.clone()
     }
+
+    fn writers(&self) -> &WritersState {
+        &self.writers
+    }
+
+    fn writers_mut(&mut self) -> &mut WritersState {
+        &mut self.writers
+    }
+
+    fn set_owner(&mut self, owner: Address) {
+        self.owner = owner;
+    }
+
+    fn pending_owner(&self) -> Option<Address> {
+        self.pending_owner
+    }
+
+    fn set_pending_owner(&mut self, owner: Option<Address>) {
+        self.pending_owner = owner;
+    }
+
+    fn frozen(&self) -> bool {
+        self.frozen
+    }
+
+    fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen;
+    }
+
+    /// Objects are backed by blobs subscribed to by the owner, so only an empty bucket
+    /// can change owners.
+    fn ensure_transferable(&self) -> Result<(), ActorError> {
+        if self.objects.len() > 0 {
+            return Err(ActorError::forbidden(
+                "bucket must be empty to transfer ownership".into(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// The stored representation of an object in the bucket.
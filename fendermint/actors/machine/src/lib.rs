@@ -16,11 +16,16 @@ use fvm_shared::{address::Address, MethodNum};
 use recall_actor_sdk::{
     emit_evm_event, to_delegated_address, to_id_address, to_id_and_delegated_address,
 };
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+use crate::registry::{register_machine, update_machine_owner};
 use crate::sol_facade::{MachineCreated, MachineInitialized};
 
+pub mod registry;
 pub mod sol_facade;
+mod writers;
+
+pub use writers::WritersState;
 
 /// Params for creating a machine.
 #[derive(Debug, Serialize_tuple, Deserialize_tuple)]
@@ -38,14 +43,42 @@ pub struct InitParams {
     pub address: Address,
 }
 
+/// Params for adding a machine writer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AddWriterParams(pub Address);
+
+/// Params for removing a machine writer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RemoveWriterParams(pub Address);
+
+/// Params for proposing a new machine owner.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TransferOwnershipParams(pub Address);
+
 /// Machine initialization method number.
 pub const INIT_METHOD: MethodNum = 2;
 /// Get machine address method number.
 pub const GET_ADDRESS_METHOD: MethodNum = frc42_dispatch::method_hash!("GetAddress");
 /// Get machine metadata method number.
 pub const GET_METADATA_METHOD: MethodNum = frc42_dispatch::method_hash!("GetMetadata");
+/// Add writer method number.
+pub const ADD_WRITER_METHOD: MethodNum = frc42_dispatch::method_hash!("AddWriter");
+/// Remove writer method number.
+pub const REMOVE_WRITER_METHOD: MethodNum = frc42_dispatch::method_hash!("RemoveWriter");
+/// List writers method number.
+pub const LIST_WRITERS_METHOD: MethodNum = frc42_dispatch::method_hash!("ListWriters");
+/// Transfer ownership method number.
+pub const TRANSFER_OWNERSHIP_METHOD: MethodNum = frc42_dispatch::method_hash!("TransferOwnership");
+/// Accept ownership method number.
+pub const ACCEPT_OWNERSHIP_METHOD: MethodNum = frc42_dispatch::method_hash!("AcceptOwnership");
+/// Freeze method number.
+pub const FREEZE_METHOD: MethodNum = frc42_dispatch::method_hash!("Freeze");
+/// Unfreeze method number.
+pub const UNFREEZE_METHOD: MethodNum = frc42_dispatch::method_hash!("Unfreeze");
 
-// TODO: Add method for changing owner from ADM actor.
 pub trait MachineActor {
     type State: MachineState + Serialize + DeserializeOwned;
 
@@ -58,6 +91,8 @@ pub trait MachineActor {
         let state = Self::State::new(rt.store(), id_addr, params.metadata)?;
         rt.create(&state)?;
 
+        register_machine(rt)?;
+
         emit_evm_event(
             rt,
             MachineCreated::new(state.kind(), delegated_addr, &state.metadata()),
@@ -98,6 +133,115 @@ pub trait MachineActor {
         })
     }
 
+    /// Allow an address to write to the machine. Only the owner can add writers.
+    fn add_writer(rt: &impl Runtime, params: AddWriterParams) -> Result<(), ActorError> {
+        let owner = rt.state::<Self::State>()?.owner();
+        rt.validate_immediate_caller_is(std::iter::once(&owner))?;
+
+        let writer = to_id_address(rt, params.0, false)?;
+        if writer == owner {
+            return Err(actor_error!(illegal_argument; "owner is always a writer"));
+        }
+        rt.transaction(|st: &mut Self::State, rt| {
+            st.ensure_not_frozen()?;
+            if !st.writers_mut().add(rt.store(), writer)? {
+                return Err(actor_error!(illegal_argument; "{} is already a writer", writer));
+            }
+            Ok(())
+        })
+    }
+
+    /// Revoke an address's write access. Only the owner can remove writers.
+    fn remove_writer(rt: &impl Runtime, params: RemoveWriterParams) -> Result<(), ActorError> {
+        let owner = rt.state::<Self::State>()?.owner();
+        rt.validate_immediate_caller_is(std::iter::once(&owner))?;
+
+        let writer = to_id_address(rt, params.0, false)?;
+        rt.transaction(|st: &mut Self::State, rt| {
+            st.ensure_not_frozen()?;
+            if !st.writers_mut().remove(rt.store(), writer)? {
+                return Err(actor_error!(not_found; "{} is not a writer", writer));
+            }
+            Ok(())
+        })
+    }
+
+    /// List the addresses that can write to the machine in addition to the owner.
+    fn list_writers(rt: &impl Runtime) -> Result<Vec<Address>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        let st = rt.state::<Self::State>()?;
+        st.writers().list(rt.store())
+    }
+
+    /// Propose a new owner, who must accept before ownership changes hands.
+    /// Only the owner can propose, and a new proposal replaces a pending one.
+    fn transfer_ownership(
+        rt: &impl Runtime,
+        params: TransferOwnershipParams,
+    ) -> Result<(), ActorError> {
+        let owner = rt.state::<Self::State>()?.owner();
+        rt.validate_immediate_caller_is(std::iter::once(&owner))?;
+
+        let new_owner = to_id_address(rt, params.0, false)?;
+        if new_owner == owner {
+            return Err(actor_error!(illegal_argument; "{} is already the owner", new_owner));
+        }
+        rt.transaction(|st: &mut Self::State, _| {
+            st.ensure_not_frozen()?;
+            st.ensure_transferable()?;
+            st.set_pending_owner(Some(new_owner));
+            Ok(())
+        })
+    }
+
+    /// Accept a pending ownership transfer. Only the proposed owner can accept.
+    /// The new owner is dropped from the writers, since the owner can always write.
+    fn accept_ownership(rt: &impl Runtime) -> Result<(), ActorError> {
+        let pending = rt
+            .state::<Self::State>()?
+            .pending_owner()
+            .ok_or_else(|| actor_error!(illegal_state; "no pending ownership transfer"))?;
+        rt.validate_immediate_caller_is(std::iter::once(&pending))?;
+
+        rt.transaction(|st: &mut Self::State, rt| {
+            st.ensure_not_frozen()?;
+            st.ensure_transferable()?;
+            st.writers_mut().remove(rt.store(), pending)?;
+            st.set_owner(pending);
+            st.set_pending_owner(None);
+            Ok(())
+        })?;
+
+        update_machine_owner(rt)
+    }
+
+    /// Make the machine read-only. Only the owner can freeze.
+    /// Every mutating method fails while the machine is frozen, but reads keep working.
+    fn freeze(rt: &impl Runtime) -> Result<(), ActorError> {
+        let owner = rt.state::<Self::State>()?.owner();
+        rt.validate_immediate_caller_is(std::iter::once(&owner))?;
+
+        rt.transaction(|st: &mut Self::State, _| {
+            st.ensure_not_frozen()?;
+            st.set_frozen(true);
+            Ok(())
+        })
+    }
+
+    /// Make a frozen machine writable again. Only the owner can unfreeze.
+    fn unfreeze(rt: &impl Runtime) -> Result<(), ActorError> {
+        let owner = rt.state::<Self::State>()?.owner();
+        rt.validate_immediate_caller_is(std::iter::once(&owner))?;
+
+        rt.transaction(|st: &mut Self::State, _| {
+            if !st.frozen() {
+                return Err(actor_error!(illegal_state; "machine is not frozen"));
+            }
+            st.set_frozen(false);
+            Ok(())
+        })
+    }
+
     fn fallback(
         rt: &impl Runtime,
         method: MethodNum,
@@ -137,6 +281,33 @@ pub trait MachineState {
     fn kind(&self) -> Kind;
     fn owner(&self) -> Address;
     fn metadata(&self) -> HashMap<String, String>;
+    fn writers(&self) -> &WritersState;
+    fn writers_mut(&mut self) -> &mut WritersState;
+    fn set_owner(&mut self, owner: Address);
+    fn pending_owner(&self) -> Option<Address>;
+    fn set_pending_owner(&mut self, owner: Option<Address>);
+    /// Returns an error if the machine can't change owners in its current state.
+    fn ensure_transferable(&self) -> Result<(), ActorError> {
+        Ok(())
+    }
+    fn frozen(&self) -> bool;
+    fn set_frozen(&mut self, frozen: bool);
+    /// Returns an error if the machine is frozen and can't be mutated.
+    fn ensure_not_frozen(&self) -> Result<(), ActorError> {
+        if self.frozen() {
+            return Err(actor_error!(forbidden; "machine is frozen"));
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether the ID address is the machine owner or one of its writers.
+pub fn is_writer<BS: Blockstore, S: MachineState>(
+    store: &BS,
+    state: &S,
+    address: Address,
+) -> Result<bool, ActorError> {
+    Ok(address == state.owner() || state.writers().contains(store, address)?)
 }
 
 /// Machine address wrapper.
@@ -0,0 +1,85 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fil_actor_adm::Kind;
+use fil_actors_runtime::runtime::Runtime;
+use fil_actors_runtime::{extract_send_result, ActorError};
+use fvm_ipld_encoding::tuple::*;
+use fvm_shared::{address::Address, econ::TokenAmount, ActorID, MethodNum};
+
+pub const MACHINE_REGISTRY_ACTOR_ID: ActorID = 71;
+pub const MACHINE_REGISTRY_ACTOR_ADDR: Address = Address::new_id(MACHINE_REGISTRY_ACTOR_ID);
+
+/// The metadata key holding a machine's human-readable label.
+pub const LABEL_METADATA_KEY: &str = "alias";
+
+/// Register machine method number.
+pub const REGISTER_MACHINE_METHOD: MethodNum = frc42_dispatch::method_hash!("RegisterMachine");
+/// Update machine owner method number.
+pub const UPDATE_MACHINE_OWNER_METHOD: MethodNum =
+    frc42_dispatch::method_hash!("UpdateMachineOwner");
+/// List machines by owner method number.
+pub const LIST_MACHINES_BY_OWNER_METHOD: MethodNum =
+    frc42_dispatch::method_hash!("ListMachinesByOwner");
+
+/// Params for listing the machines of an owner.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct ListMachinesByOwnerParams {
+    /// Owner address.
+    pub owner: Address,
+    /// Number of machines to skip, in registration order.
+    pub offset: u64,
+    /// Maximum number of machines to return.
+    pub limit: u64,
+}
+
+/// A registered machine.
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct MachineEntry {
+    /// Machine ID address.
+    pub address: Address,
+    /// Machine kind.
+    pub kind: Kind,
+    /// Machine owner ID address.
+    pub owner: Address,
+    /// Human-readable label.
+    pub label: Option<String>,
+}
+
+/// A page of machines of an owner.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct ListMachinesByOwnerReturn {
+    /// The machines in registration order.
+    pub machines: Vec<MachineEntry>,
+    /// The offset of the next page, if there are more machines.
+    pub next_offset: Option<u64>,
+}
+
+/// Registers the calling machine with the machine registry.
+///
+/// The registry reads the kind, owner, and label off the machine itself, so the machine state
+/// must exist before registering.
+/// Registration is skipped on chains that don't have a machine registry.
+pub fn register_machine(rt: &impl Runtime) -> Result<(), ActorError> {
+    notify_registry(rt, REGISTER_MACHINE_METHOD)
+}
+
+/// Tells the machine registry that the owner of the calling machine changed.
+///
+/// Skipped on chains that don't have a machine registry.
+pub fn update_machine_owner(rt: &impl Runtime) -> Result<(), ActorError> {
+    notify_registry(rt, UPDATE_MACHINE_OWNER_METHOD)
+}
+
+fn notify_registry(rt: &impl Runtime, method: MethodNum) -> Result<(), ActorError> {
+    if rt.get_actor_code_cid(&MACHINE_REGISTRY_ACTOR_ID).is_none() {
+        return Ok(());
+    }
+    extract_send_result(rt.send_simple(
+        &MACHINE_REGISTRY_ACTOR_ADDR,
+        method,
+        None,
+        TokenAmount::default(),
+    ))?;
+    Ok(())
+}
@@ -0,0 +1,76 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fil_actors_runtime::ActorError;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_shared::address::Address;
+use recall_ipld::hamt;
+
+/// Addresses that are allowed to write to a machine in addition to its owner.
+#[derive(Debug, Clone, Serialize_tuple, Deserialize_tuple)]
+pub struct WritersState {
+    pub root: hamt::Root<Address, ()>,
+    size: u64,
+}
+
+impl WritersState {
+    pub fn new<BS: Blockstore>(store: &BS) -> Result<Self, ActorError> {
+        let root = hamt::Root::<Address, ()>::new(store, "writers")?;
+        Ok(Self { root, size: 0 })
+    }
+
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Adds a writer. Returns false if the address was already a writer.
+    pub fn add<BS: Blockstore>(&mut self, store: &BS, writer: Address) -> Result<bool, ActorError> {
+        let mut hamt = self.root.hamt(store, self.size)?;
+        if !hamt.set_if_absent(&writer, ())? {
+            return Ok(false);
+        }
+        let res = hamt.flush_tracked()?;
+        self.root = res.root;
+        self.size = res.size;
+        Ok(true)
+    }
+
+    /// Removes a writer. Returns false if the address was not a writer.
+    pub fn remove<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        writer: Address,
+    ) -> Result<bool, ActorError> {
+        let mut hamt = self.root.hamt(store, self.size)?;
+        if hamt.delete(&writer)?.is_none() {
+            return Ok(false);
+        }
+        let res = hamt.flush_tracked()?;
+        self.root = res.root;
+        self.size = res.size;
+        Ok(true)
+    }
+
+    pub fn contains<BS: Blockstore>(
+        &self,
+        store: &BS,
+        writer: Address,
+    ) -> Result<bool, ActorError> {
+        self.root.hamt(store, self.size)?.contains_key(&writer)
+    }
+
+    /// Returns all writers in key order.
+    pub fn list<BS: Blockstore>(&self, store: &BS) -> Result<Vec<Address>, ActorError> {
+        let mut writers = Vec::with_capacity(self.size as usize);
+        self.root.hamt(store, self.size)?.for_each(|writer, _| {
+            writers.push(writer);
+            Ok(())
+        })?;
+        Ok(writers)
+    }
+}
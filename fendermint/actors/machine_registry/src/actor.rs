@@ -0,0 +1,371 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fendermint_actor_machine::registry::LABEL_METADATA_KEY;
+use fendermint_actor_machine::{Metadata, GET_METADATA_METHOD};
+use fil_actors_runtime::{
+    actor_dispatch, actor_error, deserialize_block, extract_send_result,
+    runtime::{ActorCode, Runtime},
+    ActorError, SYSTEM_ACTOR_ADDR,
+};
+use fvm_shared::{address::Address, econ::TokenAmount, sys::SendFlags};
+use num_traits::Zero;
+
+use crate::shared::{
+    ConstructorParams, ListMachinesByOwnerParams, ListMachinesByOwnerReturn, MachineEntry, Method,
+    State, MACHINE_REGISTRY_ACTOR_NAME, MAX_LIST_LIMIT,
+};
+
+#[cfg(feature = "fil-actor")]
+fil_actors_runtime::wasm_trampoline!(MachineRegistryActor);
+
+pub struct MachineRegistryActor;
+
+impl MachineRegistryActor {
+    fn constructor(rt: &impl Runtime, params: ConstructorParams) -> Result<(), ActorError> {
+        rt.validate_immediate_caller_is(std::iter::once(&SYSTEM_ACTOR_ADDR))?;
+        let state = State::new(rt.store(), params.machine_codes)?;
+        rt.create(&state)
+    }
+
+    /// Registers the calling machine.
+    ///
+    /// Machines register themselves when they are constructed, so the entry is keyed by the
+    /// caller's ID address.
+    /// Only actors running machine code can register, and the kind, owner, and label are
+    /// read off the machine rather than taken from the caller.
+    fn register_machine(rt: &impl Runtime) -> Result<(), ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let entry = calling_machine(rt)?;
+        rt.transaction(|st: &mut State, rt| st.register_machine(rt.store(), entry))
+    }
+
+    /// Records the current owner of the calling machine.
+    ///
+    /// The owner is read off the machine, which calls in after an ownership transfer.
+    /// Machines created before the registry existed are registered on their first transfer.
+    fn update_machine_owner(rt: &impl Runtime) -> Result<(), ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let entry = calling_machine(rt)?;
+        rt.transaction(|st: &mut State, rt| {
+            if st.get_machine(rt.store(), entry.address)?.is_some() {
+                st.update_machine_owner(rt.store(), entry.address, entry.owner)
+            } else {
+                st.register_machine(rt.store(), entry)
+            }
+        })
+    }
+
+    /// Returns a page of the machines of an owner in registration order.
+    fn list_machines_by_owner(
+        rt: &impl Runtime,
+        params: ListMachinesByOwnerParams,
+    ) -> Result<ListMachinesByOwnerReturn, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        if params.limit > MAX_LIST_LIMIT {
+            return Err(actor_error!(
+                illegal_argument,
+                "limit {} exceeds maximum {}",
+                params.limit,
+                MAX_LIST_LIMIT
+            ));
+        }
+        let Some(owner) = rt.resolve_address(&params.owner).map(Address::new_id) else {
+            return Ok(ListMachinesByOwnerReturn {
+                machines: vec![],
+                next_offset: None,
+            });
+        };
+        rt.state::<State>()?
+            .list_machines_by_owner(rt.store(), owner, params.offset, params.limit)
+    }
+}
+
+/// Builds the registry entry of the calling machine.
+///
+/// The caller must run one of the machine codes the registry was constructed with.
+/// The owner and label come from the machine's own metadata.
+fn calling_machine(rt: &impl Runtime) -> Result<MachineEntry, ActorError> {
+    let address = rt.message().caller();
+    let st = rt.state::<State>()?;
+    let kind = address
+        .id()
+        .ok()
+        .and_then(|id| rt.get_actor_code_cid(&id))
+        .and_then(|code| st.machine_kind(&code))
+        .ok_or_else(|| actor_error!(forbidden; "caller {} is not a machine", address))?;
+
+    let metadata: Metadata = deserialize_block(extract_send_result(rt.send(
+        &address,
+        GET_METADATA_METHOD,
+        None,
+        TokenAmount::zero(),
+        None,
+        SendFlags::READ_ONLY,
+    ))?)?;
+    let owner = rt
+        .resolve_address(&metadata.owner)
+        .map(Address::new_id)
+        .ok_or_else(
+            || actor_error!(illegal_state; "failed to resolve machine owner {}", metadata.owner),
+        )?;
+    Ok(MachineEntry {
+        address,
+        kind,
+        owner,
+        label: metadata.metadata.get(LABEL_METADATA_KEY).cloned(),
+    })
+}
+
+impl ActorCode for MachineRegistryActor {
+    type Methods = Method;
+
+    fn name() -> &'static str {
+        MACHINE_REGISTRY_ACTOR_NAME
+    }
+
+    actor_dispatch! {
+        Constructor => constructor,
+        RegisterMachine => register_machine,
+        UpdateMachineOwner => update_machine_owner,
+        ListMachinesByOwner => list_machines_by_owner,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::MACHINE_REGISTRY_ACTOR_ADDR;
+
+    use std::collections::HashMap;
+
+    use cid::Cid;
+    use fendermint_actor_machine::Kind;
+    use fil_actors_runtime::test_utils::{
+        expect_empty, make_identity_cid, MockRuntime, ACCOUNT_ACTOR_CODE_ID, SYSTEM_ACTOR_CODE_ID,
+    };
+    use fvm_ipld_encoding::ipld_block::IpldBlock;
+    use fvm_shared::error::ExitCode;
+
+    fn bucket_code() -> Cid {
+        make_identity_cid(b"bucket")
+    }
+
+    fn timehub_code() -> Cid {
+        make_identity_cid(b"timehub")
+    }
+
+    fn construct_and_verify() -> MockRuntime {
+        let rt = MockRuntime {
+            receiver: MACHINE_REGISTRY_ACTOR_ADDR,
+            ..Default::default()
+        };
+        rt.set_caller(*SYSTEM_ACTOR_CODE_ID, SYSTEM_ACTOR_ADDR);
+        rt.expect_validate_caller_addr(vec![SYSTEM_ACTOR_ADDR]);
+        let params = ConstructorParams {
+            machine_codes: vec![
+                (Kind::Bucket, bucket_code()),
+                (Kind::Timehub, timehub_code()),
+            ],
+        };
+        let result = rt
+            .call::<MachineRegistryActor>(
+                Method::Constructor as u64,
+                IpldBlock::serialize_cbor(&params).unwrap(),
+            )
+            .unwrap();
+        expect_empty(result);
+        rt.verify();
+        rt.reset();
+        rt
+    }
+
+    /// Calls the registry from a machine whose metadata names the given owner.
+    fn call_as_machine(
+        rt: &MockRuntime,
+        method: Method,
+        machine: Address,
+        code: Cid,
+        owner: Address,
+    ) -> Result<(), ActorError> {
+        rt.set_caller(code, machine);
+        rt.expect_validate_caller_any();
+        let metadata = Metadata {
+            kind: Kind::Timehub,
+            owner,
+            metadata: HashMap::from([(LABEL_METADATA_KEY.into(), "my-timehub".into())]),
+        };
+        rt.expect_send(
+            machine,
+            GET_METADATA_METHOD,
+            None,
+            TokenAmount::zero(),
+            None,
+            SendFlags::READ_ONLY,
+            IpldBlock::serialize_cbor(&metadata).unwrap(),
+            ExitCode::OK,
+            None,
+        );
+        let result = rt.call::<MachineRegistryActor>(method as u64, None);
+        rt.verify();
+        result.map(|_| ())
+    }
+
+    fn register(rt: &MockRuntime, machine: Address, owner: Address) -> Result<(), ActorError> {
+        call_as_machine(rt, Method::RegisterMachine, machine, timehub_code(), owner)
+    }
+
+    fn list(
+        rt: &MockRuntime,
+        owner: Address,
+        offset: u64,
+        limit: u64,
+    ) -> ListMachinesByOwnerReturn {
+        rt.expect_validate_caller_any();
+        let params = ListMachinesByOwnerParams {
+            owner,
+            offset,
+            limit,
+        };
+        let result = rt
+            .call::<MachineRegistryActor>(
+                Method::ListMachinesByOwner as u64,
+                IpldBlock::serialize_cbor(&params).unwrap(),
+            )
+            .unwrap()
+            .unwrap()
+            .deserialize::<ListMachinesByOwnerReturn>()
+            .unwrap();
+        rt.verify();
+        result
+    }
+
+    #[test]
+    fn test_register_and_list() {
+        let rt = construct_and_verify();
+        let owner = Address::new_id(100);
+
+        for id in 1000..1003 {
+            register(&rt, Address::new_id(id), owner).unwrap();
+        }
+
+        // A machine can only register once
+        let err = register(&rt, Address::new_id(1000), owner).unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_ILLEGAL_ARGUMENT);
+
+        let page = list(&rt, owner, 0, 2);
+        assert_eq!(page.machines.len(), 2);
+        assert_eq!(page.machines[0].address, Address::new_id(1000));
+        assert_eq!(page.machines[0].kind, Kind::Timehub);
+        assert_eq!(page.machines[0].owner, owner);
+        assert_eq!(page.machines[0].label, Some("my-timehub".into()));
+        assert_eq!(page.next_offset, Some(2));
+
+        let page = list(&rt, owner, 2, 2);
+        assert_eq!(page.machines.len(), 1);
+        assert_eq!(page.machines[0].address, Address::new_id(1002));
+        assert_eq!(page.next_offset, None);
+
+        // Unknown owners have no machines
+        let page = list(&rt, Address::new_id(101), 0, 10);
+        assert!(page.machines.is_empty());
+
+        // The page size is capped
+        rt.expect_validate_caller_any();
+        let params = ListMachinesByOwnerParams {
+            owner,
+            offset: 0,
+            limit: MAX_LIST_LIMIT + 1,
+        };
+        let err = rt
+            .call::<MachineRegistryActor>(
+                Method::ListMachinesByOwner as u64,
+                IpldBlock::serialize_cbor(&params).unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_ILLEGAL_ARGUMENT);
+        rt.verify();
+    }
+
+    #[test]
+    fn test_register_kind_from_code() {
+        let rt = construct_and_verify();
+        let owner = Address::new_id(100);
+        let bucket = Address::new_id(1000);
+
+        // The kind comes from the caller's code, not from the machine's metadata
+        call_as_machine(&rt, Method::RegisterMachine, bucket, bucket_code(), owner).unwrap();
+        let page = list(&rt, owner, 0, 10);
+        assert_eq!(page.machines.len(), 1);
+        assert_eq!(page.machines[0].address, bucket);
+        assert_eq!(page.machines[0].kind, Kind::Bucket);
+    }
+
+    #[test]
+    fn test_register_rejects_non_machines() {
+        let rt = construct_and_verify();
+        let owner = Address::new_id(100);
+
+        // Accounts and other non-machine actors cannot register, whatever they claim to be
+        rt.set_caller(*ACCOUNT_ACTOR_CODE_ID, owner);
+        rt.expect_validate_caller_any();
+        let err = rt
+            .call::<MachineRegistryActor>(Method::RegisterMachine as u64, None)
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+
+        assert!(list(&rt, owner, 0, 10).machines.is_empty());
+    }
+
+    #[test]
+    fn test_update_machine_owner() {
+        let rt = construct_and_verify();
+        let owner = Address::new_id(100);
+        let new_owner = Address::new_id(101);
+        let machine = Address::new_id(1000);
+
+        register(&rt, machine, owner).unwrap();
+
+        // The new owner is read off the machine
+        call_as_machine(
+            &rt,
+            Method::UpdateMachineOwner,
+            machine,
+            timehub_code(),
+            new_owner,
+        )
+        .unwrap();
+
+        assert!(list(&rt, owner, 0, 10).machines.is_empty());
+        let page = list(&rt, new_owner, 0, 10);
+        assert_eq!(page.machines.len(), 1);
+        assert_eq!(page.machines[0].owner, new_owner);
+
+        // Non-machine callers cannot update an owner
+        rt.set_caller(*ACCOUNT_ACTOR_CODE_ID, owner);
+        rt.expect_validate_caller_any();
+        let err = rt
+            .call::<MachineRegistryActor>(Method::UpdateMachineOwner as u64, None)
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+
+        // Machines created before the registry are registered on their first transfer
+        let unregistered = Address::new_id(1001);
+        call_as_machine(
+            &rt,
+            Method::UpdateMachineOwner,
+            unregistered,
+            timehub_code(),
+            new_owner,
+        )
+        .unwrap();
+        let page = list(&rt, new_owner, 0, 10);
+        assert_eq!(page.machines.len(), 2);
+        assert_eq!(page.machines[1].address, unregistered);
+    }
+}
@@ -0,0 +1,8 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+mod actor;
+mod shared;
+mod state;
+
+pub use shared::*;
@@ -0,0 +1,38 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use cid::Cid;
+pub use fendermint_actor_machine::registry::{
+    ListMachinesByOwnerParams, ListMachinesByOwnerReturn, MachineEntry,
+    MACHINE_REGISTRY_ACTOR_ADDR, MACHINE_REGISTRY_ACTOR_ID,
+};
+use fendermint_actor_machine::registry::{
+    LIST_MACHINES_BY_OWNER_METHOD, REGISTER_MACHINE_METHOD, UPDATE_MACHINE_OWNER_METHOD,
+};
+use fendermint_actor_machine::Kind;
+use fvm_ipld_encoding::tuple::*;
+use fvm_shared::METHOD_CONSTRUCTOR;
+use num_derive::FromPrimitive;
+
+pub use crate::state::State;
+
+pub const MACHINE_REGISTRY_ACTOR_NAME: &str = "machine_registry";
+
+/// Params for constructing the machine registry.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct ConstructorParams {
+    /// Code CIDs of the actors that can register as machines, by machine kind.
+    pub machine_codes: Vec<(Kind, Cid)>,
+}
+
+/// The maximum number of machines returned in a single page.
+pub const MAX_LIST_LIMIT: u64 = 1000;
+
+#[derive(FromPrimitive)]
+#[repr(u64)]
+pub enum Method {
+    Constructor = METHOD_CONSTRUCTOR,
+    RegisterMachine = REGISTER_MACHINE_METHOD,
+    UpdateMachineOwner = UPDATE_MACHINE_OWNER_METHOD,
+    ListMachinesByOwner = LIST_MACHINES_BY_OWNER_METHOD,
+}
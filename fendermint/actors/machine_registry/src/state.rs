@@ -0,0 +1,307 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use cid::Cid;
+use fendermint_actor_machine::Kind;
+use fil_actors_runtime::ActorError;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_shared::address::Address;
+use recall_ipld::hamt;
+
+use crate::shared::{ListMachinesByOwnerReturn, MachineEntry};
+
+/// The state represents all registered machines.
+#[derive(Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct State {
+    /// Code CIDs of the actors that can register as machines, by machine kind.
+    pub machine_codes: Vec<(Kind, Cid)>,
+    /// Registered machines by machine address.
+    pub machines: MachinesState,
+    /// Machine addresses by owner, in registration order.
+    pub owners: OwnersState,
+}
+
+impl State {
+    pub fn new<BS: Blockstore>(
+        store: &BS,
+        machine_codes: Vec<(Kind, Cid)>,
+    ) -> Result<Self, ActorError> {
+        Ok(Self {
+            machine_codes,
+            machines: MachinesState::new(store)?,
+            owners: OwnersState::new(store)?,
+        })
+    }
+
+    /// Returns the machine kind of an actor code, if actors with the code are machines.
+    pub fn machine_kind(&self, code: &Cid) -> Option<Kind> {
+        self.machine_codes
+            .iter()
+            .find(|(_, c)| c == code)
+            .map(|(kind, _)| kind.clone())
+    }
+
+    /// Registers a machine and indexes it under its owner.
+    pub fn register_machine<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        entry: MachineEntry,
+    ) -> Result<(), ActorError> {
+        let mut machines = self.machines.hamt(store)?;
+        if machines.contains_key(&entry.address)? {
+            return Err(ActorError::illegal_argument(format!(
+                "machine {} is already registered",
+                entry.address
+            )));
+        }
+        self.owners.push(store, entry.owner, entry.address)?;
+        machines.set(&entry.address, entry)?;
+        self.machines.save_tracked(machines.flush_tracked()?);
+        Ok(())
+    }
+
+    /// Moves a registered machine to a new owner.
+    pub fn update_machine_owner<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        address: Address,
+        owner: Address,
+    ) -> Result<(), ActorError> {
+        let mut machines = self.machines.hamt(store)?;
+        let mut entry = machines.get(&address)?.ok_or_else(|| {
+            ActorError::not_found(format!("machine {} is not registered", address))
+        })?;
+        if entry.owner == owner {
+            return Ok(());
+        }
+        self.owners.remove(store, entry.owner, address)?;
+        self.owners.push(store, owner, address)?;
+        entry.owner = owner;
+        machines.set(&address, entry)?;
+        self.machines.save_tracked(machines.flush_tracked()?);
+        Ok(())
+    }
+
+    pub fn get_machine<BS: Blockstore>(
+        &self,
+        store: &BS,
+        address: Address,
+    ) -> Result<Option<MachineEntry>, ActorError> {
+        self.machines.hamt(store)?.get(&address)
+    }
+
+    /// Returns a page of an owner's machines in registration order.
+    pub fn list_machines_by_owner<BS: Blockstore>(
+        &self,
+        store: &BS,
+        owner: Address,
+        offset: u64,
+        limit: u64,
+    ) -> Result<ListMachinesByOwnerReturn, ActorError> {
+        let addresses = self.owners.get(store, owner)?;
+        let machines_hamt = self.machines.hamt(store)?;
+        let mut machines = Vec::new();
+        for address in addresses.iter().skip(offset as usize).take(limit as usize) {
+            machines.push(machines_hamt.get_or_err(address)?);
+        }
+        let end = offset.saturating_add(machines.len() as u64);
+        let next_offset = if limit > 0 && end < addresses.len() as u64 {
+            Some(end)
+        } else {
+            None
+        };
+        Ok(ListMachinesByOwnerReturn {
+            machines,
+            next_offset,
+        })
+    }
+}
+
+#[derive(Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct MachinesState {
+    pub root: hamt::Root<Address, MachineEntry>,
+    size: u64,
+}
+
+impl MachinesState {
+    pub fn new<BS: Blockstore>(store: &BS) -> Result<Self, ActorError> {
+        let root = hamt::Root::<Address, MachineEntry>::new(store, "machines")?;
+        Ok(Self { root, size: 0 })
+    }
+
+    pub fn hamt<BS: Blockstore>(
+        &self,
+        store: BS,
+    ) -> Result<hamt::map::Hamt<BS, Address, MachineEntry>, ActorError> {
+        self.root.hamt(store, self.size)
+    }
+
+    pub fn save_tracked(
+        &mut self,
+        tracked_flush_result: hamt::map::TrackedFlushResult<Address, MachineEntry>,
+    ) {
+        self.root = tracked_flush_result.root;
+        self.size = tracked_flush_result.size;
+    }
+
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+}
+
+#[derive(Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct OwnersState {
+    pub root: hamt::Root<Address, Vec<Address>>,
+    size: u64,
+}
+
+impl OwnersState {
+    pub fn new<BS: Blockstore>(store: &BS) -> Result<Self, ActorError> {
+        let root = hamt::Root::<Address, Vec<Address>>::new(store, "owners")?;
+        Ok(Self { root, size: 0 })
+    }
+
+    /// Returns the machine addresses of an owner in registration order.
+    pub fn get<BS: Blockstore>(
+        &self,
+        store: &BS,
+        owner: Address,
+    ) -> Result<Vec<Address>, ActorError> {
+        Ok(self
+            .root
+            .hamt(store, self.size)?
+            .get(&owner)?
+            .unwrap_or_default())
+    }
+
+    fn push<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        owner: Address,
+        machine: Address,
+    ) -> Result<(), ActorError> {
+        let mut hamt = self.root.hamt(store, self.size)?;
+        let mut machines = hamt.get(&owner)?.unwrap_or_default();
+        machines.push(machine);
+        let res = hamt.set_and_flush_tracked(&owner, machines)?;
+        self.root = res.root;
+        self.size = res.size;
+        Ok(())
+    }
+
+    fn remove<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        owner: Address,
+        machine: Address,
+    ) -> Result<(), ActorError> {
+        let mut hamt = self.root.hamt(store, self.size)?;
+        let mut machines = hamt.get(&owner)?.unwrap_or_default();
+        machines.retain(|m| m != &machine);
+        let res = if machines.is_empty() {
+            hamt.delete_and_flush_tracked(&owner)?.0
+        } else {
+            hamt.set_and_flush_tracked(&owner, machines)?
+        };
+        self.root = res.root;
+        self.size = res.size;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    fn entry(id: u64, owner: Address) -> MachineEntry {
+        MachineEntry {
+            address: Address::new_id(id),
+            kind: Kind::Timehub,
+            owner,
+            label: None,
+        }
+    }
+
+    #[test]
+    fn test_register_and_list() {
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store, vec![]).unwrap();
+        let alice = Address::new_id(100);
+        let bob = Address::new_id(101);
+
+        for id in 1000..1005 {
+            state.register_machine(&store, entry(id, alice)).unwrap();
+        }
+        state.register_machine(&store, entry(2000, bob)).unwrap();
+        assert_eq!(state.machines.len(), 6);
+
+        // Registering twice fails
+        assert!(state.register_machine(&store, entry(1000, bob)).is_err());
+
+        let page = state.list_machines_by_owner(&store, alice, 0, 2).unwrap();
+        assert_eq!(page.machines, vec![entry(1000, alice), entry(1001, alice)]);
+        assert_eq!(page.next_offset, Some(2));
+
+        let page = state.list_machines_by_owner(&store, alice, 4, 2).unwrap();
+        assert_eq!(page.machines, vec![entry(1004, alice)]);
+        assert_eq!(page.next_offset, None);
+
+        let page = state.list_machines_by_owner(&store, alice, 10, 2).unwrap();
+        assert!(page.machines.is_empty());
+        assert_eq!(page.next_offset, None);
+
+        let page = state
+            .list_machines_by_owner(&store, Address::new_id(102), 0, 10)
+            .unwrap();
+        assert!(page.machines.is_empty());
+    }
+
+    #[test]
+    fn test_update_machine_owner() {
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store, vec![]).unwrap();
+        let alice = Address::new_id(100);
+        let bob = Address::new_id(101);
+
+        state.register_machine(&store, entry(1000, alice)).unwrap();
+        state.register_machine(&store, entry(1001, alice)).unwrap();
+        state.register_machine(&store, entry(1002, bob)).unwrap();
+
+        state
+            .update_machine_owner(&store, Address::new_id(1000), bob)
+            .unwrap();
+        assert_eq!(
+            state
+                .get_machine(&store, Address::new_id(1000))
+                .unwrap()
+                .unwrap()
+                .owner,
+            bob
+        );
+        assert_eq!(
+            state.owners.get(&store, alice).unwrap(),
+            vec![Address::new_id(1001)]
+        );
+        assert_eq!(
+            state.owners.get(&store, bob).unwrap(),
+            vec![Address::new_id(1002), Address::new_id(1000)]
+        );
+
+        // Moving the last machine removes the owner entry
+        state
+            .update_machine_owner(&store, Address::new_id(1001), bob)
+            .unwrap();
+        assert!(state.owners.get(&store, alice).unwrap().is_empty());
+
+        // Unknown machines cannot be updated
+        assert!(state
+            .update_machine_owner(&store, Address::new_id(3000), alice)
+            .is_err());
+    }
+}
@@ -2,10 +2,14 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use cid::multihash::Code;
+use cid::Cid;
 use fendermint_actor_blobs_shared::state::TokenCreditRate;
 use fil_actors_runtime::runtime::Runtime;
 use fil_actors_runtime::{deserialize_block, extract_send_result, ActorError};
+use fvm_ipld_encoding::ipld_block::IpldBlock;
 use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::CborStore;
 use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
@@ -19,7 +23,7 @@ pub const RECALL_CONFIG_ACTOR_ID: ActorID = 70;
 pub const RECALL_CONFIG_ACTOR_ADDR: Address = Address::new_id(RECALL_CONFIG_ACTOR_ID);
 
 /// The updatable config.
-#[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone)]
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone, PartialEq)]
 pub struct RecallConfig {
     /// The total storage capacity of the subnet.
     pub blob_capacity: u64,
@@ -35,6 +39,30 @@ pub struct RecallConfig {
     pub blob_delete_batch_size: u64,
     /// Maximum number of accounts to process in a single batch during debit.
     pub account_debit_batch_size: u64,
+    /// Maximum number of credit events retained in each account statement.
+    /// Zero disables account statements.
+    pub account_statement_length: u64,
+    /// Epoch duration an expired auto-renew subscription is kept after its renewal fails
+    /// due to insufficient credit. Zero deletes such subscriptions immediately.
+    pub blob_delinquency_grace_period: ChainEpoch,
+    /// The maximum epoch duration a blob can be stored, regardless of account TTL status.
+    pub blob_max_ttl: ChainEpoch,
+    /// Number of epochs a trashed blob subscription can be restored before its data is removed.
+    /// Zero disables trashing.
+    pub blob_trash_period: ChainEpoch,
+    /// Maximum number of new blob subscriptions an account can add per credit debit interval.
+    /// Zero means no limit.
+    pub blob_add_rate_limit_count: u64,
+    /// Maximum number of blob bytes an account can add in new subscriptions per credit debit
+    /// interval. Zero means no limit.
+    pub blob_add_rate_limit_bytes: u64,
+    /// The maximum size of a single blob in bytes.
+    pub blob_max_size: u64,
+    /// The maximum epoch duration a read request can stay open before it expires.
+    pub read_request_max_ttl: ChainEpoch,
+    /// Maximum number of blobs an account can have waiting to be resolved at once.
+    /// Zero means no limit.
+    pub blob_max_pending_per_account: u64,
 }
 
 impl Default for RecallConfig {
@@ -50,16 +78,210 @@ impl Default for RecallConfig {
             blob_default_ttl: ChainEpoch::from(60 * 60 * 24),      // ~1 day
             blob_delete_batch_size: 100,
             account_debit_batch_size: 1000,
+            account_statement_length: 100,
+            blob_delinquency_grace_period: ChainEpoch::from(60 * 60 * 24), // ~1 day
+            blob_max_ttl: ChainEpoch::MAX,
+            blob_trash_period: ChainEpoch::from(60 * 60 * 24), // ~1 day
+            blob_add_rate_limit_count: 0,
+            blob_add_rate_limit_bytes: 0,
+            blob_max_size: 10 * 1024 * 1024 * 1024, // 10 GiB
+            read_request_max_ttl: ChainEpoch::from(60 * 60 * 24), // ~1 day
+            blob_max_pending_per_account: 0,
         }
     }
 }
 
+/// A role that can administer the config.
+/// Owners implicitly hold every other role.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Role {
+    /// Can manage roles and perform any admin action.
+    Owner,
+    /// Can update the config.
+    ParamSetter,
+    /// Can pause and resume actors.
+    Pauser,
+}
+
+/// Inclusive bounds that config values must fall within.
+///
+/// These guard against nonsensical configs, on top of the basic checks that always apply,
+/// e.g., that capacity and intervals are positive.
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone, PartialEq)]
+pub struct ConfigBounds {
+    /// Minimum total storage capacity of the subnet.
+    pub min_blob_capacity: u64,
+    /// Maximum total storage capacity of the subnet.
+    pub max_blob_capacity: u64,
+    /// Minimum epoch interval at which to debit all credit accounts.
+    pub min_blob_credit_debit_interval: ChainEpoch,
+    /// Maximum epoch interval at which to debit all credit accounts.
+    pub max_blob_credit_debit_interval: ChainEpoch,
+    /// Lowest allowed minimum blob TTL.
+    pub min_blob_min_ttl: ChainEpoch,
+    /// Highest allowed maximum blob TTL.
+    pub max_blob_max_ttl: ChainEpoch,
+    /// Maximum number of blobs to delete in a single batch during debit.
+    pub max_blob_delete_batch_size: u64,
+    /// Maximum number of accounts to process in a single batch during debit.
+    pub max_account_debit_batch_size: u64,
+    /// Maximum number of credit events retained in each account statement.
+    pub max_account_statement_length: u64,
+    /// Maximum grace period for delinquent auto-renew subscriptions.
+    pub max_blob_delinquency_grace_period: ChainEpoch,
+    /// Maximum trash period for deleted blob subscriptions.
+    pub max_blob_trash_period: ChainEpoch,
+}
+
+impl Default for ConfigBounds {
+    fn default() -> Self {
+        Self {
+            min_blob_capacity: 1,
+            max_blob_capacity: u64::MAX,
+            min_blob_credit_debit_interval: 1,
+            max_blob_credit_debit_interval: ChainEpoch::from(60 * 60 * 24), // ~1 day
+            min_blob_min_ttl: 1,
+            max_blob_max_ttl: ChainEpoch::MAX,
+            max_blob_delete_batch_size: 10_000,
+            max_account_debit_batch_size: 100_000,
+            max_account_statement_length: 10_000,
+            max_blob_delinquency_grace_period: ChainEpoch::from(60 * 60 * 24 * 30), // ~30 days
+            max_blob_trash_period: ChainEpoch::from(60 * 60 * 24 * 30),             // ~30 days
+        }
+    }
+}
+
+pub type SetConfigBoundsParams = ConfigBounds;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct SetAdminParams(pub Address);
 
+/// Params for adding or removing an address from a role.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct RoleParams {
+    /// The role to update.
+    pub role: Role,
+    /// The address to add or remove.
+    pub address: Address,
+}
+
+/// Params for getting the addresses that hold a role.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GetRoleMembersParams(pub Role);
+
 pub type SetConfigParams = RecallConfig;
 
+/// Params for setting the total storage capacity of the subnet.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SetBlobCapacityParams(pub u64);
+
+/// Params for setting the token to credit rate.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SetCreditRateParams(pub TokenCreditRate);
+
+/// Params for setting the epoch interval at which to debit all credit accounts.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SetDebitIntervalParams(pub ChainEpoch);
+
+/// Params for setting the blob TTLs.
+/// These are set together since they must stay in order.
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct SetBlobTtlsParams {
+    /// The minimum epoch duration a blob can be stored.
+    pub min_ttl: ChainEpoch,
+    /// The default epoch duration a blob is stored.
+    pub default_ttl: ChainEpoch,
+    /// The maximum epoch duration a blob can be stored.
+    pub max_ttl: ChainEpoch,
+}
+
+/// Params for setting the maximum size of a single blob in bytes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SetBlobMaxSizeParams(pub u64);
+
+/// A config that takes effect at a future epoch.
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone)]
+pub struct ScheduledConfig {
+    /// The config to apply.
+    pub config: RecallConfig,
+    /// The epoch at which the config takes effect.
+    pub activation_epoch: ChainEpoch,
+}
+
+pub type ScheduleConfigParams = ScheduledConfig;
+
+/// A config along with when and by whom it was set.
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone, PartialEq)]
+pub struct ConfigRecord {
+    /// The epoch at which the config took effect.
+    pub epoch: ChainEpoch,
+    /// The admin that set the config.
+    /// Not present for the config the actor was constructed with.
+    pub admin: Option<Address>,
+    /// The config.
+    pub config: RecallConfig,
+}
+
+/// Voters whose approval is required to change the config.
+///
+/// While governance is enabled, admins can no longer change the config directly.
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone, PartialEq)]
+pub struct Governance {
+    /// The addresses that can propose and approve config changes.
+    pub voters: Vec<Address>,
+    /// Number of voter approvals required for a proposal to take effect.
+    pub quorum: u32,
+    /// Number of epochs a proposal stays open for approval.
+    pub proposal_ttl: ChainEpoch,
+}
+
+pub type EnableGovernanceParams = Governance;
+
+/// A change that voters can propose while governance is enabled.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ProposalAction {
+    /// Replace the config.
+    SetConfig(RecallConfig),
+    /// Replace the governance settings, or disable governance with `None`.
+    SetGovernance(Option<Governance>),
+}
+
+/// A proposed change awaiting voter approvals.
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone, PartialEq)]
+pub struct ConfigProposal {
+    /// The proposal ID.
+    pub id: u64,
+    /// The voter that made the proposal.
+    pub proposer: Address,
+    /// The proposed change.
+    pub action: ProposalAction,
+    /// The voters that approved the proposal, including the proposer.
+    pub approvals: Vec<Address>,
+    /// The epoch after which the proposal can no longer be approved.
+    pub expiry: ChainEpoch,
+}
+
+/// Params for proposing a change.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProposeConfigChangeParams(pub ProposalAction);
+
+/// Params for approving a proposal.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ApproveConfigProposalParams(pub u64);
+
+/// Params for getting the config in force at an epoch.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GetConfigAtParams(pub ChainEpoch);
+
 #[derive(FromPrimitive)]
 #[repr(u64)]
 pub enum Method {
@@ -68,6 +290,29 @@ pub enum Method {
     GetAdmin = frc42_dispatch::method_hash!("GetAdmin"),
     SetConfig = frc42_dispatch::method_hash!("SetConfig"),
     GetConfig = frc42_dispatch::method_hash!("GetConfig"),
+    AddRole = frc42_dispatch::method_hash!("AddRole"),
+    RemoveRole = frc42_dispatch::method_hash!("RemoveRole"),
+    GetRoleMembers = frc42_dispatch::method_hash!("GetRoleMembers"),
+    ProposeAdmin = frc42_dispatch::method_hash!("ProposeAdmin"),
+    AcceptAdmin = frc42_dispatch::method_hash!("AcceptAdmin"),
+    CancelProposedAdmin = frc42_dispatch::method_hash!("CancelProposedAdmin"),
+    GetProposedAdmin = frc42_dispatch::method_hash!("GetProposedAdmin"),
+    ScheduleConfig = frc42_dispatch::method_hash!("ScheduleConfig"),
+    ApplyScheduled = frc42_dispatch::method_hash!("ApplyScheduled"),
+    GetPendingConfig = frc42_dispatch::method_hash!("GetPendingConfig"),
+    GetConfigAt = frc42_dispatch::method_hash!("GetConfigAt"),
+    SetConfigBounds = frc42_dispatch::method_hash!("SetConfigBounds"),
+    GetConfigBounds = frc42_dispatch::method_hash!("GetConfigBounds"),
+    SetBlobCapacity = frc42_dispatch::method_hash!("SetBlobCapacity"),
+    SetCreditRate = frc42_dispatch::method_hash!("SetCreditRate"),
+    SetDebitInterval = frc42_dispatch::method_hash!("SetDebitInterval"),
+    SetBlobTtls = frc42_dispatch::method_hash!("SetBlobTtls"),
+    SetBlobMaxSize = frc42_dispatch::method_hash!("SetBlobMaxSize"),
+    EnableGovernance = frc42_dispatch::method_hash!("EnableGovernance"),
+    GetGovernance = frc42_dispatch::method_hash!("GetGovernance"),
+    ProposeConfigChange = frc42_dispatch::method_hash!("ProposeConfigChange"),
+    ApproveConfigProposal = frc42_dispatch::method_hash!("ApproveConfigProposal"),
+    GetConfigProposals = frc42_dispatch::method_hash!("GetConfigProposals"),
 }
 
 pub fn get_admin(rt: &impl Runtime) -> Result<Option<Address>, ActorError> {
@@ -81,18 +326,37 @@ pub fn get_admin(rt: &impl Runtime) -> Result<Option<Address>, ActorError> {
     ))?)
 }
 
-/// Requires caller is the Recall Admin.
-pub fn require_caller_is_admin(rt: &impl Runtime) -> Result<(), ActorError> {
-    let admin = get_admin(rt)?;
-    if admin.is_none() {
-        Err(ActorError::illegal_state(
-            "admin address not set".to_string(),
-        ))
+/// Returns the addresses that hold a role, including owners, who hold every role.
+pub fn get_role_members(rt: &impl Runtime, role: Role) -> Result<Vec<Address>, ActorError> {
+    deserialize_block(extract_send_result(rt.send(
+        &RECALL_CONFIG_ACTOR_ADDR,
+        Method::GetRoleMembers as MethodNum,
+        IpldBlock::serialize_cbor(&GetRoleMembersParams(role))?,
+        TokenAmount::zero(),
+        None,
+        SendFlags::READ_ONLY,
+    ))?)
+}
+
+/// Requires caller holds the given role.
+pub fn require_caller_has_role(rt: &impl Runtime, role: Role) -> Result<(), ActorError> {
+    let members = get_role_members(rt, role)?;
+    if members.is_empty() {
+        Err(ActorError::illegal_state(format!(
+            "no addresses hold role {:?}",
+            role
+        )))
     } else {
-        Ok(rt.validate_immediate_caller_is(std::iter::once(&admin.unwrap()))?)
+        Ok(rt.validate_immediate_caller_is(members.iter())?)
     }
 }
 
+/// Requires caller is a Recall Admin, i.e., holds the owner role.
+pub fn require_caller_is_admin(rt: &impl Runtime) -> Result<(), ActorError> {
+    require_caller_has_role(rt, Role::Owner)
+}
+
+/// Fetches the config in force from the config actor.
 pub fn get_config(rt: &impl Runtime) -> Result<RecallConfig, ActorError> {
     deserialize_block(extract_send_result(rt.send(
         &RECALL_CONFIG_ACTOR_ADDR,
@@ -103,3 +367,42 @@ pub fn get_config(rt: &impl Runtime) -> Result<RecallConfig, ActorError> {
         SendFlags::READ_ONLY,
     ))?)
 }
+
+/// A reference to a config fetched from the config actor, tagged with the epoch it was fetched at.
+///
+/// Actors can keep this in their state to avoid calling the config actor more than once
+/// per epoch. A config change made during an epoch is picked up at the next epoch.
+/// The config itself is a separate block, shared for as long as the config doesn't change.
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone, PartialEq)]
+pub struct CachedConfig {
+    /// The epoch the config was fetched at.
+    pub epoch: ChainEpoch,
+    /// CID of the config.
+    pub config: Cid,
+}
+
+/// Returns the cached config if it was fetched at the current epoch.
+/// Otherwise, fetches the config from the config actor and refreshes the cache.
+/// The caller is responsible for persisting the cache.
+pub fn get_config_cached(
+    rt: &impl Runtime,
+    cache: &mut Option<CachedConfig>,
+) -> Result<RecallConfig, ActorError> {
+    let epoch = rt.curr_epoch();
+    if let Some(cached) = cache.as_ref().filter(|cached| cached.epoch == epoch) {
+        let config = rt
+            .store()
+            .get_cbor::<RecallConfig>(&cached.config)
+            .map_err(|e| ActorError::illegal_state(format!("failed to load cached config: {e}")))?;
+        if let Some(config) = config {
+            return Ok(config);
+        }
+    }
+    let config = get_config(rt)?;
+    let cid = rt
+        .store()
+        .put_cbor(&config, Code::Blake2b256)
+        .map_err(|e| ActorError::illegal_state(format!("failed to cache config: {e}")))?;
+    *cache = Some(CachedConfig { epoch, config: cid });
+    Ok(config)
+}
@@ -0,0 +1,50 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fil_actors_runtime::runtime::Runtime;
+use fil_actors_runtime::{ActorError, EventBuilder};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::event::ActorEvent;
+
+/// Indicates a config took effect at `epoch`, so listeners can re-read the config.
+/// The admin is not present for configs that were not set by an admin.
+pub fn config_set(
+    rt: &impl Runtime,
+    admin: Option<Address>,
+    epoch: ChainEpoch,
+) -> Result<(), ActorError> {
+    rt.emit_event(&config_set_event(admin, epoch)?)
+}
+
+/// Indicates that config ownership was handed to a new admin.
+/// The old admin is not present if there was no admin.
+pub fn admin_changed(
+    rt: &impl Runtime,
+    old: Option<Address>,
+    new: Address,
+) -> Result<(), ActorError> {
+    rt.emit_event(&admin_changed_event(old, new)?)
+}
+
+pub(crate) fn config_set_event(
+    admin: Option<Address>,
+    epoch: ChainEpoch,
+) -> Result<ActorEvent, ActorError> {
+    EventBuilder::new()
+        .typ("config-set")
+        .field_indexed("admin", &admin)
+        .field("epoch", &epoch)
+        .build()
+}
+
+pub(crate) fn admin_changed_event(
+    old: Option<Address>,
+    new: Address,
+) -> Result<ActorEvent, ActorError> {
+    EventBuilder::new()
+        .typ("admin-changed")
+        .field_indexed("old", &old)
+        .field_indexed("new", &new)
+        .build()
+}
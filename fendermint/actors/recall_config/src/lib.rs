@@ -4,21 +4,31 @@
 
 use fendermint_actor_blobs_shared::state::TokenCreditRate;
 use fendermint_actor_recall_config_shared::{
-    Method, RecallConfig, SetAdminParams, SetConfigParams,
+    ApproveConfigProposalParams, ConfigBounds, ConfigProposal, ConfigRecord,
+    EnableGovernanceParams, GetConfigAtParams, GetRoleMembersParams, Governance, Method,
+    ProposalAction, ProposeConfigChangeParams, RecallConfig, Role, RoleParams,
+    ScheduleConfigParams, ScheduledConfig, SetAdminParams, SetBlobCapacityParams,
+    SetBlobMaxSizeParams, SetBlobTtlsParams, SetConfigBoundsParams, SetConfigParams,
+    SetCreditRateParams, SetDebitIntervalParams,
 };
 use fil_actors_runtime::{
     actor_dispatch, actor_error,
     runtime::{ActorCode, Runtime},
     ActorError, SYSTEM_ACTOR_ADDR,
 };
+use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
 use fvm_shared::bigint::BigUint;
 use fvm_shared::{address::Address, clock::ChainEpoch};
 use num_traits::Zero;
-use recall_actor_sdk::{emit_evm_event, to_delegated_address, to_id_and_delegated_address};
+use recall_actor_sdk::{
+    emit_evm_event, to_delegated_address, to_id_address, to_id_and_delegated_address,
+};
+use recall_ipld::amt;
 
 use crate::sol_facade::{ConfigAdminSet, ConfigSet};
 
+mod emit;
 mod sol_facade;
 
 #[cfg(feature = "fil-actor")]
@@ -26,12 +36,203 @@ fil_actors_runtime::wasm_trampoline!(Actor);
 
 pub const ACTOR_NAME: &str = "recall_config";
 
+/// Maximum number of configs kept in the config history.
+pub const MAX_CONFIG_HISTORY: u64 = 1000;
+
 #[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone)]
 pub struct State {
-    /// The admin address that is allowed to update the config.
-    pub admin: Option<Address>,
+    /// The addresses that are allowed to administer the config, by role.
+    pub roles: Roles,
+    /// The address proposed to take over ownership, pending its acceptance.
+    pub proposed_admin: Option<Address>,
     /// The Recall network configuration.
     pub config: RecallConfig,
+    /// A config that replaces `config` at its activation epoch.
+    pub scheduled_config: Option<ScheduledConfig>,
+    /// The admin that scheduled `scheduled_config`.
+    pub scheduled_by: Option<Address>,
+    /// Past and current configs in the order they took effect.
+    pub history: amt::Root<ConfigRecord>,
+    /// Index of the oldest config kept in `history`.
+    pub history_start: u64,
+    /// Index of the next config added to `history`.
+    pub history_next: u64,
+    /// Bounds that new configs must fall within.
+    pub bounds: ConfigBounds,
+    /// Voters that must approve config changes, if governance is enabled.
+    pub governance: Option<Governance>,
+    /// Open governance proposals.
+    pub proposals: Vec<ConfigProposal>,
+    /// ID of the next governance proposal.
+    pub next_proposal_id: u64,
+}
+
+impl State {
+    pub fn new<BS: Blockstore>(
+        store: &BS,
+        config: RecallConfig,
+        current_epoch: ChainEpoch,
+    ) -> Result<Self, ActorError> {
+        let mut st = Self {
+            roles: Roles::default(),
+            proposed_admin: None,
+            config: config.clone(),
+            scheduled_config: None,
+            scheduled_by: None,
+            history: amt::Root::new(store)?,
+            history_start: 0,
+            history_next: 0,
+            bounds: ConfigBounds::default(),
+            governance: None,
+            proposals: Vec::new(),
+            next_proposal_id: 0,
+        };
+        st.record_config(store, current_epoch, None, config)?;
+        Ok(st)
+    }
+
+    /// Returns the config in force at the given epoch.
+    pub fn effective_config(self, epoch: ChainEpoch) -> RecallConfig {
+        match self.scheduled_config {
+            Some(scheduled) if scheduled.activation_epoch <= epoch => scheduled.config,
+            _ => self.config,
+        }
+    }
+
+    /// Sets the config and records it in the history.
+    pub fn set_config<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        current_epoch: ChainEpoch,
+        admin: Address,
+        config: RecallConfig,
+    ) -> Result<(), ActorError> {
+        self.config = config.clone();
+        self.record_config(store, current_epoch, Some(admin), config)
+    }
+
+    /// Applies the scheduled config if its activation epoch has passed.
+    /// Returns the record of the applied config, if any.
+    pub fn apply_due_config<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        current_epoch: ChainEpoch,
+    ) -> Result<Option<ConfigRecord>, ActorError> {
+        match self.scheduled_config.take() {
+            Some(scheduled) if scheduled.activation_epoch <= current_epoch => {
+                let admin = self.scheduled_by.take();
+                self.config = scheduled.config.clone();
+                self.record_config(
+                    store,
+                    scheduled.activation_epoch,
+                    admin,
+                    scheduled.config.clone(),
+                )?;
+                Ok(Some(ConfigRecord {
+                    epoch: scheduled.activation_epoch,
+                    admin,
+                    config: scheduled.config,
+                }))
+            }
+            scheduled => {
+                self.scheduled_config = scheduled;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns the config in force at the given epoch, or `None` if the epoch is older than
+    /// the history that is kept.
+    pub fn config_at<BS: Blockstore>(
+        &self,
+        store: &BS,
+        epoch: ChainEpoch,
+    ) -> Result<Option<ConfigRecord>, ActorError> {
+        // A due scheduled config is newer than anything in the history
+        if let Some(scheduled) = &self.scheduled_config {
+            if scheduled.activation_epoch <= epoch {
+                return Ok(Some(ConfigRecord {
+                    epoch: scheduled.activation_epoch,
+                    admin: self.scheduled_by,
+                    config: scheduled.config.clone(),
+                }));
+            }
+        }
+        // Records are ordered by epoch, so search for the last one at or before the epoch
+        let history = self.history.amt(store)?;
+        let (mut low, mut high) = (self.history_start, self.history_next);
+        let mut found = None;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let record = history.get_or_err(mid)?;
+            if record.epoch <= epoch {
+                found = Some(record);
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(found)
+    }
+
+    fn record_config<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        epoch: ChainEpoch,
+        admin: Option<Address>,
+        config: RecallConfig,
+    ) -> Result<(), ActorError> {
+        let mut history = self.history.amt(store)?;
+        history.set(
+            self.history_next,
+            ConfigRecord {
+                epoch,
+                admin,
+                config,
+            },
+        )?;
+        self.history_next += 1;
+        if self.history_next - self.history_start > MAX_CONFIG_HISTORY {
+            history.delete(self.history_start)?;
+            self.history_start += 1;
+        }
+        self.history = history.flush()?;
+        Ok(())
+    }
+}
+
+/// The ID addresses that hold each [`Role`].
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone, Default)]
+pub struct Roles {
+    pub owners: Vec<Address>,
+    pub param_setters: Vec<Address>,
+    pub pausers: Vec<Address>,
+}
+
+impl Roles {
+    fn members_mut(&mut self, role: Role) -> &mut Vec<Address> {
+        match role {
+            Role::Owner => &mut self.owners,
+            Role::ParamSetter => &mut self.param_setters,
+            Role::Pauser => &mut self.pausers,
+        }
+    }
+
+    /// Returns the addresses that hold a role, including owners, who hold every role.
+    pub fn holders(&self, role: Role) -> Vec<Address> {
+        let mut holders = self.owners.clone();
+        let members = match role {
+            Role::Owner => return holders,
+            Role::ParamSetter => &self.param_setters,
+            Role::Pauser => &self.pausers,
+        };
+        for member in members {
+            if !holders.contains(member) {
+                holders.push(*member);
+            }
+        }
+        holders
+    }
 }
 
 #[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone)]
@@ -43,6 +244,15 @@ pub struct ConstructorParams {
     initial_blob_default_ttl: ChainEpoch,
     initial_blob_delete_batch_size: u64,
     initial_account_debit_batch_size: u64,
+    initial_account_statement_length: u64,
+    initial_blob_delinquency_grace_period: ChainEpoch,
+    initial_blob_max_ttl: ChainEpoch,
+    initial_blob_trash_period: ChainEpoch,
+    initial_blob_add_rate_limit_count: u64,
+    initial_blob_add_rate_limit_bytes: u64,
+    initial_blob_max_size: u64,
+    initial_read_request_max_ttl: ChainEpoch,
+    initial_blob_max_pending_per_account: u64,
 }
 
 pub struct Actor {}
@@ -51,99 +261,216 @@ impl Actor {
     /// Creates the actor
     pub fn constructor(rt: &impl Runtime, params: ConstructorParams) -> Result<(), ActorError> {
         rt.validate_immediate_caller_is(std::iter::once(&SYSTEM_ACTOR_ADDR))?;
-        let st = State {
-            admin: None,
-            config: RecallConfig {
-                blob_capacity: params.initial_blob_capacity,
-                token_credit_rate: params.initial_token_credit_rate,
-                blob_credit_debit_interval: params.initial_blob_credit_debit_interval,
-                blob_min_ttl: params.initial_blob_min_ttl,
-                blob_default_ttl: params.initial_blob_default_ttl,
-                blob_delete_batch_size: params.initial_blob_delete_batch_size,
-                account_debit_batch_size: params.initial_account_debit_batch_size,
-            },
+        let config = RecallConfig {
+            blob_capacity: params.initial_blob_capacity,
+            token_credit_rate: params.initial_token_credit_rate,
+            blob_credit_debit_interval: params.initial_blob_credit_debit_interval,
+            blob_min_ttl: params.initial_blob_min_ttl,
+            blob_default_ttl: params.initial_blob_default_ttl,
+            blob_delete_batch_size: params.initial_blob_delete_batch_size,
+            account_debit_batch_size: params.initial_account_debit_batch_size,
+            account_statement_length: params.initial_account_statement_length,
+            blob_delinquency_grace_period: params.initial_blob_delinquency_grace_period,
+            blob_max_ttl: params.initial_blob_max_ttl,
+            blob_trash_period: params.initial_blob_trash_period,
+            blob_add_rate_limit_count: params.initial_blob_add_rate_limit_count,
+            blob_add_rate_limit_bytes: params.initial_blob_add_rate_limit_bytes,
+            blob_max_size: params.initial_blob_max_size,
+            read_request_max_ttl: params.initial_read_request_max_ttl,
+            blob_max_pending_per_account: params.initial_blob_max_pending_per_account,
         };
+        let st = State::new(rt.store(), config, rt.curr_epoch())?;
         rt.create(&st)
     }
 
+    /// Sets the first owner.
+    /// Once an owner exists, ownership can only be handed over with `propose_admin`
+    /// and `accept_admin`.
     fn set_admin(rt: &impl Runtime, params: SetAdminParams) -> Result<(), ActorError> {
-        Self::ensure_update_allowed(rt)?;
+        if Self::ensure_update_allowed(rt, Role::Owner)? {
+            return Err(actor_error!(
+                illegal_state,
+                "admin already set; use ProposeAdmin to transfer it"
+            ));
+        }
 
         let (admin_id_addr, admin_delegated_addr) = to_id_and_delegated_address(rt, params.0)?;
 
         rt.transaction(|st: &mut State, _rt| {
-            st.admin = Some(admin_id_addr);
+            st.roles.owners = vec![admin_id_addr];
             Ok(())
         })?;
 
         emit_evm_event(rt, ConfigAdminSet::new(admin_delegated_addr))?;
+        emit::admin_changed(rt, None, admin_delegated_addr)?;
 
         Ok(())
     }
 
+    /// Returns the first owner, if any.
     fn get_admin(rt: &impl Runtime) -> Result<Option<Address>, ActorError> {
         rt.validate_immediate_caller_accept_any()?;
-        match rt.state::<State>().map(|s| s.admin)? {
+        match rt.state::<State>()?.roles.owners.first() {
             Some(admin) => {
-                let admin = to_delegated_address(rt, admin)?;
+                let admin = to_delegated_address(rt, *admin)?;
                 Ok(Some(admin))
             }
             None => Ok(None),
         }
     }
 
-    fn set_config(rt: &impl Runtime, params: SetConfigParams) -> Result<(), ActorError> {
-        let admin_exists = Self::ensure_update_allowed(rt)?;
-
-        if params.token_credit_rate.rate() <= &BigUint::zero() {
-            return Err(actor_error!(
-                illegal_argument,
-                "token credit rate must be positive"
-            ));
-        }
-        if params.blob_capacity == 0 {
-            return Err(actor_error!(
-                illegal_argument,
-                "blob capacity must be positive"
-            ));
-        }
-        if params.blob_credit_debit_interval <= 0 {
-            return Err(actor_error!(
-                illegal_argument,
-                "credit debit interval must be positive"
-            ));
-        }
-        if params.blob_min_ttl <= 0 {
-            return Err(actor_error!(
-                illegal_argument,
-                "minimum TTL must be positive"
+    /// Proposes an address to take over ownership.
+    /// The change only takes effect once the proposed address calls `accept_admin`.
+    fn propose_admin(rt: &impl Runtime, params: SetAdminParams) -> Result<(), ActorError> {
+        if !Self::ensure_update_allowed(rt, Role::Owner)? {
+            return Err(ActorError::illegal_state(
+                "admin address not set".to_string(),
             ));
         }
-        if params.blob_default_ttl <= 0 {
-            return Err(actor_error!(
-                illegal_argument,
-                "default TTL must be positive"
+
+        let (admin_id_addr, _) = to_id_and_delegated_address(rt, params.0)?;
+
+        rt.transaction(|st: &mut State, _rt| {
+            st.proposed_admin = Some(admin_id_addr);
+            Ok(())
+        })
+    }
+
+    /// Accepts a proposed ownership transfer.
+    /// The caller must be the proposed address, which replaces all current owners.
+    fn accept_admin(rt: &impl Runtime) -> Result<(), ActorError> {
+        let proposed = rt
+            .state::<State>()?
+            .proposed_admin
+            .ok_or_else(|| ActorError::illegal_state("no admin proposed".to_string()))?;
+        rt.validate_immediate_caller_is(std::iter::once(&proposed))?;
+
+        let old = rt.transaction(|st: &mut State, _rt| {
+            let old = st.roles.owners.first().copied();
+            st.roles.owners = vec![proposed];
+            st.proposed_admin = None;
+            Ok(old)
+        })?;
+
+        let proposed = to_delegated_address(rt, proposed)?;
+        emit_evm_event(rt, ConfigAdminSet::new(proposed))?;
+        emit::admin_changed(
+            rt,
+            old.map(|old| to_delegated_address(rt, old)).transpose()?,
+            proposed,
+        )?;
+
+        Ok(())
+    }
+
+    /// Cancels a pending ownership transfer.
+    fn cancel_proposed_admin(rt: &impl Runtime) -> Result<(), ActorError> {
+        if !Self::ensure_update_allowed(rt, Role::Owner)? {
+            return Err(ActorError::illegal_state(
+                "admin address not set".to_string(),
             ));
         }
-        if params.blob_default_ttl < params.blob_min_ttl {
-            return Err(actor_error!(
-                illegal_argument,
-                "default TTL must be greater than or equal to minimum TTL"
+
+        rt.transaction(|st: &mut State, _rt| {
+            if st.proposed_admin.take().is_none() {
+                return Err(ActorError::illegal_state("no admin proposed".to_string()));
+            }
+            Ok(())
+        })
+    }
+
+    /// Returns the address proposed to take over ownership, if any.
+    fn get_proposed_admin(rt: &impl Runtime) -> Result<Option<Address>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        rt.state::<State>()?
+            .proposed_admin
+            .map(|admin| to_delegated_address(rt, admin))
+            .transpose()
+    }
+
+    /// Adds an address to a role. Only owners can manage roles.
+    fn add_role(rt: &impl Runtime, params: RoleParams) -> Result<(), ActorError> {
+        if !Self::ensure_update_allowed(rt, Role::Owner)? {
+            return Err(ActorError::illegal_state(
+                "admin address not set".to_string(),
             ));
         }
-        if params.blob_delete_batch_size == 0 {
-            return Err(actor_error!(
-                illegal_argument,
-                "blob delete batch size must be positive"
-            ));
+
+        let (id_addr, delegated_addr) = to_id_and_delegated_address(rt, params.address)?;
+
+        rt.transaction(|st: &mut State, _rt| {
+            let members = st.roles.members_mut(params.role);
+            if members.contains(&id_addr) {
+                return Err(actor_error!(
+                    illegal_argument,
+                    "address {} already holds role {:?}",
+                    params.address,
+                    params.role
+                ));
+            }
+            members.push(id_addr);
+            Ok(())
+        })?;
+
+        if params.role == Role::Owner {
+            emit_evm_event(rt, ConfigAdminSet::new(delegated_addr))?;
         }
-        if params.account_debit_batch_size == 0 {
-            return Err(actor_error!(
-                illegal_argument,
-                "account debit batch size must be positive"
+
+        Ok(())
+    }
+
+    /// Removes an address from a role. Only owners can manage roles.
+    /// The last owner cannot be removed.
+    fn remove_role(rt: &impl Runtime, params: RoleParams) -> Result<(), ActorError> {
+        if !Self::ensure_update_allowed(rt, Role::Owner)? {
+            return Err(ActorError::illegal_state(
+                "admin address not set".to_string(),
             ));
         }
 
+        let id_addr = to_id_address(rt, params.address, false)?;
+
+        rt.transaction(|st: &mut State, _rt| {
+            let members = st.roles.members_mut(params.role);
+            let index = members.iter().position(|m| m == &id_addr).ok_or_else(|| {
+                actor_error!(
+                    not_found,
+                    "address {} does not hold role {:?}",
+                    params.address,
+                    params.role
+                )
+            })?;
+            if params.role == Role::Owner && members.len() == 1 {
+                return Err(actor_error!(
+                    illegal_argument,
+                    "cannot remove the last owner"
+                ));
+            }
+            members.remove(index);
+            Ok(())
+        })
+    }
+
+    /// Returns the addresses that hold a role, including owners, who hold every role.
+    fn get_role_members(
+        rt: &impl Runtime,
+        params: GetRoleMembersParams,
+    ) -> Result<Vec<Address>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        rt.state::<State>()?
+            .roles
+            .holders(params.0)
+            .into_iter()
+            .map(|addr| to_delegated_address(rt, addr))
+            .collect()
+    }
+
+    fn set_config(rt: &impl Runtime, params: SetConfigParams) -> Result<(), ActorError> {
+        let admin_exists = Self::ensure_update_allowed(rt, Role::ParamSetter)?;
+        Self::ensure_not_governed(rt)?;
+
+        Self::validate_config(&params, &rt.state::<State>()?.bounds)?;
+
         let (admin_id_addr, admin_delegated_addr) = if !admin_exists {
             // The first caller becomes admin
             let addrs = to_id_and_delegated_address(rt, rt.message().caller())?;
@@ -152,162 +479,792 @@ impl Actor {
             (None, None)
         };
 
-        rt.transaction(|st: &mut State, _rt| {
+        let caller = rt.message().caller();
+        let applied = rt.transaction(|st: &mut State, rt| {
             if let Some(admin) = admin_id_addr {
-                st.admin = Some(admin);
+                st.roles.owners = vec![admin];
             }
-            st.config = params.clone();
-            Ok(())
+            // A due scheduled config took effect before this one
+            let applied = st.apply_due_config(rt.store(), rt.curr_epoch())?;
+            st.set_config(rt.store(), rt.curr_epoch(), caller, params.clone())?;
+            Ok(applied)
         })?;
 
         if let Some(admin) = admin_delegated_addr {
             emit_evm_event(rt, ConfigAdminSet::new(admin))?;
+            emit::admin_changed(rt, None, admin)?;
+        }
+        if let Some(record) = applied {
+            Self::emit_config_set(rt, record)?;
         }
-        emit_evm_event(
+        Self::emit_config_set(
             rt,
-            ConfigSet {
-                blob_capacity: params.blob_capacity,
-                token_credit_rate: params.token_credit_rate,
-                blob_credit_debit_interval: params.blob_credit_debit_interval,
-                blob_min_ttl: params.blob_min_ttl,
-                blob_default_ttl: params.blob_default_ttl,
-                blob_delete_batch_size: params.blob_delete_batch_size,
-                account_debit_batch_size: params.account_debit_batch_size,
+            ConfigRecord {
+                epoch: rt.curr_epoch(),
+                admin: Some(caller),
+                config: params,
             },
         )?;
 
         Ok(())
     }
 
-    fn get_config(rt: &impl Runtime) -> Result<RecallConfig, ActorError> {
-        rt.validate_immediate_caller_accept_any()?;
-        rt.state::<State>().map(|s| s.config)
+    /// Sets the total storage capacity of the subnet, leaving the rest of the config as is.
+    fn set_blob_capacity(
+        rt: &impl Runtime,
+        params: SetBlobCapacityParams,
+    ) -> Result<(), ActorError> {
+        Self::update_config(rt, |config| config.blob_capacity = params.0)
     }
 
-    /// Ensures that immediate caller is allowed to update the config.
-    /// Returns whether the admin exists.
-    fn ensure_update_allowed(rt: &impl Runtime) -> Result<bool, ActorError> {
-        let st = rt.state::<State>()?;
-        let admin_exists = if let Some(admin) = st.admin {
-            if let Some(admin_id) = rt.resolve_address(&admin) {
-                rt.validate_immediate_caller_is(std::iter::once(&Address::new_id(admin_id)))?
-            } else {
-                // This should not happen.
-                return Err(ActorError::forbidden(String::from(
-                    "failed to resolve config admin id",
-                )));
-            }
-            true
-        } else {
-            // The first caller becomes the admin
-            rt.validate_immediate_caller_accept_any()?;
-            false
-        };
-        Ok(admin_exists)
+    /// Sets the token to credit rate, leaving the rest of the config as is.
+    fn set_credit_rate(rt: &impl Runtime, params: SetCreditRateParams) -> Result<(), ActorError> {
+        Self::update_config(rt, |config| config.token_credit_rate = params.0)
     }
-}
 
-impl ActorCode for Actor {
-    type Methods = Method;
+    /// Sets the credit debit interval, leaving the rest of the config as is.
+    fn set_debit_interval(
+        rt: &impl Runtime,
+        params: SetDebitIntervalParams,
+    ) -> Result<(), ActorError> {
+        Self::update_config(rt, |config| config.blob_credit_debit_interval = params.0)
+    }
 
-    fn name() -> &'static str {
-        ACTOR_NAME
+    /// Sets the minimum, default, and maximum blob TTLs, leaving the rest of the config as is.
+    fn set_blob_ttls(rt: &impl Runtime, params: SetBlobTtlsParams) -> Result<(), ActorError> {
+        Self::update_config(rt, |config| {
+            config.blob_min_ttl = params.min_ttl;
+            config.blob_default_ttl = params.default_ttl;
+            config.blob_max_ttl = params.max_ttl;
+        })
     }
 
-    actor_dispatch! {
-        Constructor => constructor,
-        SetAdmin => set_admin,
-        GetAdmin => get_admin,
-        SetConfig => set_config,
-        GetConfig => get_config,
+    /// Sets the maximum size of a single blob, leaving the rest of the config as is.
+    fn set_blob_max_size(
+        rt: &impl Runtime,
+        params: SetBlobMaxSizeParams,
+    ) -> Result<(), ActorError> {
+        Self::update_config(rt, |config| config.blob_max_size = params.0)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Applies a change to the config in force and validates the result.
+    ///
+    /// Unlike `set_config`, the change is made against the current config within the same
+    /// transaction, so admins adjusting different parameters don't overwrite each other.
+    fn update_config(
+        rt: &impl Runtime,
+        update: impl FnOnce(&mut RecallConfig),
+    ) -> Result<(), ActorError> {
+        if !Self::ensure_update_allowed(rt, Role::ParamSetter)? {
+            return Err(ActorError::illegal_state(
+                "admin address not set".to_string(),
+            ));
+        }
+        Self::ensure_not_governed(rt)?;
+
+        let caller = rt.message().caller();
+        let (applied, config) = rt.transaction(|st: &mut State, rt| {
+            let applied = st.apply_due_config(rt.store(), rt.curr_epoch())?;
+            let mut config = st.config.clone();
+            update(&mut config);
+            Self::validate_config(&config, &st.bounds)?;
+            st.set_config(rt.store(), rt.curr_epoch(), caller, config.clone())?;
+            Ok((applied, config))
+        })?;
 
-    use fendermint_actor_recall_config_shared::{RecallConfig, RECALL_CONFIG_ACTOR_ID};
-    use fil_actors_evm_shared::address::EthAddress;
-    use fil_actors_runtime::test_utils::{
-        expect_empty, MockRuntime, ETHACCOUNT_ACTOR_CODE_ID, SYSTEM_ACTOR_CODE_ID,
-    };
-    use fvm_ipld_encoding::ipld_block::IpldBlock;
-    use fvm_shared::error::ExitCode;
-    use recall_actor_sdk::to_actor_event;
+        if let Some(record) = applied {
+            Self::emit_config_set(rt, record)?;
+        }
+        Self::emit_config_set(
+            rt,
+            ConfigRecord {
+                epoch: rt.curr_epoch(),
+                admin: Some(caller),
+                config,
+            },
+        )
+    }
 
-    pub fn construct_and_verify(
-        blob_capacity: u64,
-        token_credit_rate: TokenCreditRate,
-        blob_credit_debit_interval: i32,
-        initial_blob_min_ttl: ChainEpoch,
-        initial_blob_default_ttl: ChainEpoch,
-    ) -> MockRuntime {
-        let rt = MockRuntime {
-            receiver: Address::new_id(RECALL_CONFIG_ACTOR_ID),
-            ..Default::default()
-        };
+    /// Enables governance, after which config changes must be proposed and approved by a
+    /// quorum of voters. Only owners can enable governance. Once enabled, governance can only
+    /// be changed or disabled by a proposal.
+    fn enable_governance(
+        rt: &impl Runtime,
+        params: EnableGovernanceParams,
+    ) -> Result<(), ActorError> {
+        if !Self::ensure_update_allowed(rt, Role::Owner)? {
+            return Err(ActorError::illegal_state(
+                "admin address not set".to_string(),
+            ));
+        }
+        Self::ensure_not_governed(rt)?;
 
-        rt.set_caller(*SYSTEM_ACTOR_CODE_ID, SYSTEM_ACTOR_ADDR);
-        rt.expect_validate_caller_addr(vec![SYSTEM_ACTOR_ADDR]);
+        let governance = Self::validate_governance(rt, params)?;
 
-        let result = rt
-            .call::<Actor>(
-                Method::Constructor as u64,
-                IpldBlock::serialize_cbor(&ConstructorParams {
-                    initial_blob_capacity: blob_capacity,
-                    initial_token_credit_rate: token_credit_rate,
-                    initial_blob_credit_debit_interval: ChainEpoch::from(
-                        blob_credit_debit_interval,
-                    ),
-                    initial_blob_min_ttl,
-                    initial_blob_default_ttl,
-                    initial_blob_delete_batch_size: 100,
-                    initial_account_debit_batch_size: 100,
-                })
-                .unwrap(),
-            )
-            .unwrap();
-        expect_empty(result);
-        rt.verify();
-        rt.reset();
+        rt.transaction(|st: &mut State, _rt| {
+            st.governance = Some(governance);
+            Ok(())
+        })
+    }
 
-        rt
+    /// Returns the governance settings, if governance is enabled.
+    fn get_governance(rt: &impl Runtime) -> Result<Option<Governance>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        rt.state::<State>().map(|s| s.governance)
     }
 
-    #[test]
-    fn test_get_initial_admin() {
-        let rt = construct_and_verify(1024, TokenCreditRate::from(5usize), 3600, 3600, 3600);
+    /// Proposes a change. The proposer's approval is counted.
+    /// Returns the proposal ID.
+    fn propose_config_change(
+        rt: &impl Runtime,
+        params: ProposeConfigChangeParams,
+    ) -> Result<u64, ActorError> {
+        let governance = Self::ensure_caller_is_voter(rt)?;
+
+        let action = match params.0 {
+            ProposalAction::SetConfig(config) => {
+                Self::validate_config(&config, &rt.state::<State>()?.bounds)?;
+                ProposalAction::SetConfig(config)
+            }
+            ProposalAction::SetGovernance(new) => ProposalAction::SetGovernance(
+                new.map(|new| Self::validate_governance(rt, new))
+                    .transpose()?,
+            ),
+        };
 
-        rt.expect_validate_caller_any();
-        let admin = rt
-            .call::<Actor>(Method::GetAdmin as u64, None)
-            .unwrap()
-            .unwrap()
-            .deserialize::<Option<Address>>()
-            .unwrap();
-        rt.verify();
+        let proposer = rt.message().caller();
+        let proposal = rt.transaction(|st: &mut State, rt| {
+            st.proposals.retain(|p| p.expiry >= rt.curr_epoch());
+            let proposal = ConfigProposal {
+                id: st.next_proposal_id,
+                proposer,
+                action,
+                approvals: vec![proposer],
+                expiry: rt.curr_epoch().saturating_add(governance.proposal_ttl),
+            };
+            st.next_proposal_id += 1;
+            st.proposals.push(proposal.clone());
+            Ok(proposal)
+        })?;
 
-        assert!(admin.is_none());
+        let id = proposal.id;
+        if governance.quorum == 1 {
+            Self::execute_proposal(rt, proposal)?;
+        }
+        Ok(id)
     }
 
-    #[test]
-    fn test_set_admin() {
-        let rt = construct_and_verify(1024, TokenCreditRate::from(5usize), 3600, 3600, 3600);
+    /// Approves a proposal. The proposal takes effect once it reaches quorum.
+    /// Returns whether the proposal took effect.
+    fn approve_config_proposal(
+        rt: &impl Runtime,
+        params: ApproveConfigProposalParams,
+    ) -> Result<bool, ActorError> {
+        let governance = Self::ensure_caller_is_voter(rt)?;
+
+        let voter = rt.message().caller();
+        let proposal = rt.transaction(|st: &mut State, rt| {
+            let proposal = st
+                .proposals
+                .iter_mut()
+                .find(|p| p.id == params.0)
+                .ok_or_else(|| actor_error!(not_found, "proposal {} not found", params.0))?;
+            if proposal.expiry < rt.curr_epoch() {
+                return Err(actor_error!(
+                    forbidden,
+                    "proposal {} expired at epoch {}",
+                    params.0,
+                    proposal.expiry
+                ));
+            }
+            if proposal.approvals.contains(&voter) {
+                return Err(actor_error!(
+                    illegal_argument,
+                    "proposal {} already approved by {}",
+                    params.0,
+                    voter
+                ));
+            }
+            proposal.approvals.push(voter);
+            Ok(proposal.clone())
+        })?;
 
-        let id_addr = Address::new_id(110);
-        let eth_addr = EthAddress(hex_literal::hex!(
-            "CAFEB0BA00000000000000000000000000000000"
-        ));
-        let f4_eth_addr = Address::new_delegated(10, &eth_addr.0).unwrap();
-        rt.set_delegated_address(id_addr.id().unwrap(), f4_eth_addr);
+        if proposal.approvals.len() < governance.quorum as usize {
+            return Ok(false);
+        }
+        Self::execute_proposal(rt, proposal)?;
+        Ok(true)
+    }
 
-        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr);
-        rt.expect_validate_caller_any();
-        let event = to_actor_event(ConfigAdminSet::new(f4_eth_addr)).unwrap();
-        rt.expect_emitted_event(event);
-        let result = rt.call::<Actor>(
-            Method::SetAdmin as u64,
+    /// Returns the open proposals that have not expired.
+    fn get_config_proposals(rt: &impl Runtime) -> Result<Vec<ConfigProposal>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        let mut proposals = rt.state::<State>()?.proposals;
+        proposals.retain(|p| p.expiry >= rt.curr_epoch());
+        Ok(proposals)
+    }
+
+    /// Applies a proposal that reached quorum and removes it.
+    fn execute_proposal(rt: &impl Runtime, proposal: ConfigProposal) -> Result<(), ActorError> {
+        let (applied, record) = rt.transaction(|st: &mut State, rt| {
+            st.proposals.retain(|p| p.id != proposal.id);
+            match proposal.action {
+                ProposalAction::SetConfig(config) => {
+                    // The bounds may have changed since the proposal was made
+                    Self::validate_config(&config, &st.bounds)?;
+                    let applied = st.apply_due_config(rt.store(), rt.curr_epoch())?;
+                    st.set_config(
+                        rt.store(),
+                        rt.curr_epoch(),
+                        proposal.proposer,
+                        config.clone(),
+                    )?;
+                    Ok((
+                        applied,
+                        Some(ConfigRecord {
+                            epoch: rt.curr_epoch(),
+                            admin: Some(proposal.proposer),
+                            config,
+                        }),
+                    ))
+                }
+                ProposalAction::SetGovernance(governance) => {
+                    // Approvals were given under the old voters
+                    st.proposals.clear();
+                    st.governance = governance;
+                    Ok((None, None))
+                }
+            }
+        })?;
+
+        if let Some(record) = applied {
+            Self::emit_config_set(rt, record)?;
+        }
+        if let Some(record) = record {
+            Self::emit_config_set(rt, record)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the config in force at the current epoch, including a scheduled config whose
+    /// activation epoch has passed, even if it hasn't been applied yet.
+    fn get_config(rt: &impl Runtime) -> Result<RecallConfig, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        rt.state::<State>()
+            .map(|s| s.effective_config(rt.curr_epoch()))
+    }
+
+    /// Schedules a config to replace the current one at a future epoch.
+    ///
+    /// This gives users time to see changes, e.g., to pricing, before they take effect.
+    /// Any previously scheduled config is replaced.
+    fn schedule_config(rt: &impl Runtime, params: ScheduleConfigParams) -> Result<(), ActorError> {
+        if !Self::ensure_update_allowed(rt, Role::ParamSetter)? {
+            return Err(ActorError::illegal_state(
+                "admin address not set".to_string(),
+            ));
+        }
+        Self::ensure_not_governed(rt)?;
+
+        if params.activation_epoch <= rt.curr_epoch() {
+            return Err(actor_error!(
+                illegal_argument,
+                "activation epoch must be in the future"
+            ));
+        }
+        Self::validate_config(&params.config, &rt.state::<State>()?.bounds)?;
+
+        let caller = rt.message().caller();
+        let applied = rt.transaction(|st: &mut State, rt| {
+            // Don't drop a due scheduled config by replacing it
+            let applied = st.apply_due_config(rt.store(), rt.curr_epoch())?;
+            st.scheduled_config = Some(params);
+            st.scheduled_by = Some(caller);
+            Ok(applied)
+        })?;
+
+        if let Some(record) = applied {
+            Self::emit_config_set(rt, record)?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies the scheduled config if its activation epoch has passed.
+    ///
+    /// Reads already see a due config, so this only persists it. It can be called by anyone,
+    /// e.g., from a cron hook. Returns whether a config was applied.
+    fn apply_scheduled(rt: &impl Runtime) -> Result<bool, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+
+        let applied =
+            rt.transaction(|st: &mut State, rt| st.apply_due_config(rt.store(), rt.curr_epoch()))?;
+
+        match applied {
+            Some(record) => {
+                Self::emit_config_set(rt, record)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Emits events for a config that took effect.
+    fn emit_config_set(rt: &impl Runtime, record: ConfigRecord) -> Result<(), ActorError> {
+        emit_evm_event(rt, ConfigSet::new(&record.config))?;
+        let admin = record
+            .admin
+            .map(|admin| to_delegated_address(rt, admin))
+            .transpose()?;
+        emit::config_set(rt, admin, record.epoch)
+    }
+
+    /// Returns the scheduled config that has not yet been applied, if any.
+    fn get_pending_config(rt: &impl Runtime) -> Result<Option<ScheduledConfig>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        rt.state::<State>().map(|s| s.scheduled_config)
+    }
+
+    /// Returns the config that was in force at an epoch, along with when it took effect and
+    /// the admin that set it.
+    ///
+    /// Returns `None` if the epoch is older than the kept history.
+    fn get_config_at(
+        rt: &impl Runtime,
+        params: GetConfigAtParams,
+    ) -> Result<Option<ConfigRecord>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        let record = rt.state::<State>()?.config_at(rt.store(), params.0)?;
+        record
+            .map(|mut record| {
+                record.admin = record
+                    .admin
+                    .map(|admin| to_delegated_address(rt, admin))
+                    .transpose()?;
+                Ok(record)
+            })
+            .transpose()
+    }
+
+    /// Sets the bounds that new configs must fall within.
+    /// Only owners can change the bounds. The current config is not affected.
+    fn set_config_bounds(
+        rt: &impl Runtime,
+        params: SetConfigBoundsParams,
+    ) -> Result<(), ActorError> {
+        if !Self::ensure_update_allowed(rt, Role::Owner)? {
+            return Err(ActorError::illegal_state(
+                "admin address not set".to_string(),
+            ));
+        }
+        Self::ensure_not_governed(rt)?;
+
+        let ranges = [
+            (
+                "blob capacity",
+                params.min_blob_capacity as i128,
+                params.max_blob_capacity as i128,
+            ),
+            (
+                "credit debit interval",
+                params.min_blob_credit_debit_interval as i128,
+                params.max_blob_credit_debit_interval as i128,
+            ),
+            (
+                "blob TTL",
+                params.min_blob_min_ttl as i128,
+                params.max_blob_max_ttl as i128,
+            ),
+        ];
+        for (name, min, max) in ranges {
+            if min > max {
+                return Err(actor_error!(
+                    illegal_argument,
+                    "minimum {} bound {} exceeds maximum {}",
+                    name,
+                    min,
+                    max
+                ));
+            }
+        }
+
+        rt.transaction(|st: &mut State, _rt| {
+            st.bounds = params;
+            Ok(())
+        })
+    }
+
+    fn get_config_bounds(rt: &impl Runtime) -> Result<ConfigBounds, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        rt.state::<State>().map(|s| s.bounds)
+    }
+
+    fn validate_config(params: &RecallConfig, bounds: &ConfigBounds) -> Result<(), ActorError> {
+        if params.token_credit_rate.rate() <= &BigUint::zero() {
+            return Err(actor_error!(
+                illegal_argument,
+                "token credit rate must be positive"
+            ));
+        }
+        if params.blob_capacity == 0 {
+            return Err(actor_error!(
+                illegal_argument,
+                "blob capacity must be positive"
+            ));
+        }
+        if params.blob_credit_debit_interval <= 0 {
+            return Err(actor_error!(
+                illegal_argument,
+                "credit debit interval must be positive"
+            ));
+        }
+        if params.blob_min_ttl <= 0 {
+            return Err(actor_error!(
+                illegal_argument,
+                "minimum TTL must be positive"
+            ));
+        }
+        if params.blob_default_ttl <= 0 {
+            return Err(actor_error!(
+                illegal_argument,
+                "default TTL must be positive"
+            ));
+        }
+        if params.blob_default_ttl < params.blob_min_ttl {
+            return Err(actor_error!(
+                illegal_argument,
+                "default TTL must be greater than or equal to minimum TTL"
+            ));
+        }
+        if params.blob_max_ttl < params.blob_default_ttl {
+            return Err(actor_error!(
+                illegal_argument,
+                "max TTL must be greater than or equal to default TTL"
+            ));
+        }
+        if params.blob_delete_batch_size == 0 {
+            return Err(actor_error!(
+                illegal_argument,
+                "blob delete batch size must be positive"
+            ));
+        }
+        if params.account_debit_batch_size == 0 {
+            return Err(actor_error!(
+                illegal_argument,
+                "account debit batch size must be positive"
+            ));
+        }
+        if params.blob_trash_period < 0 {
+            return Err(actor_error!(
+                illegal_argument,
+                "blob trash period must not be negative"
+            ));
+        }
+        if params.blob_max_size == 0 {
+            return Err(actor_error!(
+                illegal_argument,
+                "blob max size must be positive"
+            ));
+        }
+        if params.read_request_max_ttl <= 0 {
+            return Err(actor_error!(
+                illegal_argument,
+                "read request max TTL must be positive"
+            ));
+        }
+
+        if params.blob_capacity < bounds.min_blob_capacity
+            || params.blob_capacity > bounds.max_blob_capacity
+        {
+            return Err(actor_error!(
+                illegal_argument,
+                "blob capacity {} must be between {} and {}",
+                params.blob_capacity,
+                bounds.min_blob_capacity,
+                bounds.max_blob_capacity
+            ));
+        }
+        if params.blob_credit_debit_interval < bounds.min_blob_credit_debit_interval
+            || params.blob_credit_debit_interval > bounds.max_blob_credit_debit_interval
+        {
+            return Err(actor_error!(
+                illegal_argument,
+                "credit debit interval {} must be between {} and {}",
+                params.blob_credit_debit_interval,
+                bounds.min_blob_credit_debit_interval,
+                bounds.max_blob_credit_debit_interval
+            ));
+        }
+        if params.blob_min_ttl < bounds.min_blob_min_ttl {
+            return Err(actor_error!(
+                illegal_argument,
+                "minimum TTL {} must be at least {}",
+                params.blob_min_ttl,
+                bounds.min_blob_min_ttl
+            ));
+        }
+        if params.blob_max_ttl > bounds.max_blob_max_ttl {
+            return Err(actor_error!(
+                illegal_argument,
+                "max TTL {} must be at most {}",
+                params.blob_max_ttl,
+                bounds.max_blob_max_ttl
+            ));
+        }
+        let maximums = [
+            (
+                "blob delete batch size",
+                params.blob_delete_batch_size as i128,
+                bounds.max_blob_delete_batch_size as i128,
+            ),
+            (
+                "account debit batch size",
+                params.account_debit_batch_size as i128,
+                bounds.max_account_debit_batch_size as i128,
+            ),
+            (
+                "account statement length",
+                params.account_statement_length as i128,
+                bounds.max_account_statement_length as i128,
+            ),
+            (
+                "blob delinquency grace period",
+                params.blob_delinquency_grace_period as i128,
+                bounds.max_blob_delinquency_grace_period as i128,
+            ),
+            (
+                "blob trash period",
+                params.blob_trash_period as i128,
+                bounds.max_blob_trash_period as i128,
+            ),
+        ];
+        for (name, value, max) in maximums {
+            if value > max {
+                return Err(actor_error!(
+                    illegal_argument,
+                    "{} {} must be at most {}",
+                    name,
+                    value,
+                    max
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Ensures that governance is disabled, so admins can change the config directly.
+    fn ensure_not_governed(rt: &impl Runtime) -> Result<(), ActorError> {
+        if rt.state::<State>()?.governance.is_some() {
+            return Err(actor_error!(
+                forbidden,
+                "governance is enabled; config changes must be proposed and approved by voters"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Ensures that governance is enabled and the immediate caller is a voter.
+    fn ensure_caller_is_voter(rt: &impl Runtime) -> Result<Governance, ActorError> {
+        let governance = rt
+            .state::<State>()?
+            .governance
+            .ok_or_else(|| ActorError::illegal_state("governance is not enabled".to_string()))?;
+        rt.validate_immediate_caller_is(governance.voters.iter())?;
+        Ok(governance)
+    }
+
+    /// Resolves voters to ID addresses and checks the governance settings are sane.
+    fn validate_governance(
+        rt: &impl Runtime,
+        governance: Governance,
+    ) -> Result<Governance, ActorError> {
+        let mut voters = Vec::with_capacity(governance.voters.len());
+        for voter in governance.voters {
+            let (voter, _) = to_id_and_delegated_address(rt, voter)?;
+            if !voters.contains(&voter) {
+                voters.push(voter);
+            }
+        }
+        if governance.quorum == 0 || governance.quorum as usize > voters.len() {
+            return Err(actor_error!(
+                illegal_argument,
+                "quorum must be between 1 and the number of voters ({})",
+                voters.len()
+            ));
+        }
+        if governance.proposal_ttl <= 0 {
+            return Err(actor_error!(
+                illegal_argument,
+                "proposal TTL must be positive"
+            ));
+        }
+        Ok(Governance {
+            voters,
+            ..governance
+        })
+    }
+
+    /// Ensures that immediate caller holds the given role.
+    /// Returns whether an owner exists. If not, any caller is allowed.
+    fn ensure_update_allowed(rt: &impl Runtime, role: Role) -> Result<bool, ActorError> {
+        let st = rt.state::<State>()?;
+        if st.roles.owners.is_empty() {
+            // The first caller becomes the admin
+            rt.validate_immediate_caller_accept_any()?;
+            return Ok(false);
+        }
+        let holders = st
+            .roles
+            .holders(role)
+            .iter()
+            .map(|addr| {
+                rt.resolve_address(addr)
+                    .map(Address::new_id)
+                    .ok_or_else(|| {
+                        // This should not happen.
+                        ActorError::forbidden(String::from("failed to resolve config admin id"))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        rt.validate_immediate_caller_is(holders.iter())?;
+        Ok(true)
+    }
+}
+
+impl ActorCode for Actor {
+    type Methods = Method;
+
+    fn name() -> &'static str {
+        ACTOR_NAME
+    }
+
+    actor_dispatch! {
+        Constructor => constructor,
+        SetAdmin => set_admin,
+        GetAdmin => get_admin,
+        SetConfig => set_config,
+        GetConfig => get_config,
+        AddRole => add_role,
+        RemoveRole => remove_role,
+        GetRoleMembers => get_role_members,
+        ProposeAdmin => propose_admin,
+        AcceptAdmin => accept_admin,
+        CancelProposedAdmin => cancel_proposed_admin,
+        GetProposedAdmin => get_proposed_admin,
+        ScheduleConfig => schedule_config,
+        ApplyScheduled => apply_scheduled,
+        GetPendingConfig => get_pending_config,
+        GetConfigAt => get_config_at,
+        SetConfigBounds => set_config_bounds,
+        GetConfigBounds => get_config_bounds,
+        SetBlobCapacity => set_blob_capacity,
+        SetCreditRate => set_credit_rate,
+        SetDebitInterval => set_debit_interval,
+        SetBlobTtls => set_blob_ttls,
+        SetBlobMaxSize => set_blob_max_size,
+        EnableGovernance => enable_governance,
+        GetGovernance => get_governance,
+        ProposeConfigChange => propose_config_change,
+        ApproveConfigProposal => approve_config_proposal,
+        GetConfigProposals => get_config_proposals,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use fendermint_actor_recall_config_shared::{RecallConfig, RECALL_CONFIG_ACTOR_ID};
+    use fil_actors_evm_shared::address::EthAddress;
+    use fil_actors_runtime::test_utils::{
+        expect_empty, MockRuntime, ETHACCOUNT_ACTOR_CODE_ID, SYSTEM_ACTOR_CODE_ID,
+    };
+    use fvm_ipld_encoding::ipld_block::IpldBlock;
+    use fvm_shared::error::ExitCode;
+    use recall_actor_sdk::to_actor_event;
+
+    pub fn construct_and_verify(
+        blob_capacity: u64,
+        token_credit_rate: TokenCreditRate,
+        blob_credit_debit_interval: i32,
+        initial_blob_min_ttl: ChainEpoch,
+        initial_blob_default_ttl: ChainEpoch,
+    ) -> MockRuntime {
+        let rt = MockRuntime {
+            receiver: Address::new_id(RECALL_CONFIG_ACTOR_ID),
+            ..Default::default()
+        };
+
+        rt.set_caller(*SYSTEM_ACTOR_CODE_ID, SYSTEM_ACTOR_ADDR);
+        rt.expect_validate_caller_addr(vec![SYSTEM_ACTOR_ADDR]);
+
+        let result = rt
+            .call::<Actor>(
+                Method::Constructor as u64,
+                IpldBlock::serialize_cbor(&ConstructorParams {
+                    initial_blob_capacity: blob_capacity,
+                    initial_token_credit_rate: token_credit_rate,
+                    initial_blob_credit_debit_interval: ChainEpoch::from(
+                        blob_credit_debit_interval,
+                    ),
+                    initial_blob_min_ttl,
+                    initial_blob_default_ttl,
+                    initial_blob_delete_batch_size: 100,
+                    initial_account_debit_batch_size: 100,
+                    initial_account_statement_length: 100,
+                    initial_blob_delinquency_grace_period: ChainEpoch::from(3600),
+                    initial_blob_max_ttl: ChainEpoch::from(60 * 60 * 24 * 365),
+                    initial_blob_trash_period: ChainEpoch::from(3600),
+                    initial_blob_add_rate_limit_count: 100,
+                    initial_blob_add_rate_limit_bytes: 1024,
+                    initial_blob_max_size: 1024 * 1024,
+                    initial_read_request_max_ttl: ChainEpoch::from(3600),
+                    initial_blob_max_pending_per_account: 10,
+                })
+                .unwrap(),
+            )
+            .unwrap();
+        expect_empty(result);
+        rt.verify();
+        rt.reset();
+
+        rt
+    }
+
+    #[test]
+    fn test_get_initial_admin() {
+        let rt = construct_and_verify(1024, TokenCreditRate::from(5usize), 3600, 3600, 3600);
+
+        rt.expect_validate_caller_any();
+        let admin = rt
+            .call::<Actor>(Method::GetAdmin as u64, None)
+            .unwrap()
+            .unwrap()
+            .deserialize::<Option<Address>>()
+            .unwrap();
+        rt.verify();
+
+        assert!(admin.is_none());
+    }
+
+    #[test]
+    fn test_set_admin() {
+        let rt = construct_and_verify(1024, TokenCreditRate::from(5usize), 3600, 3600, 3600);
+
+        let id_addr = Address::new_id(110);
+        let eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let f4_eth_addr = Address::new_delegated(10, &eth_addr.0).unwrap();
+        rt.set_delegated_address(id_addr.id().unwrap(), f4_eth_addr);
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr);
+        rt.expect_validate_caller_any();
+        let event = to_actor_event(ConfigAdminSet::new(f4_eth_addr)).unwrap();
+        rt.expect_emitted_event(event);
+        rt.expect_emitted_event(emit::admin_changed_event(None, f4_eth_addr).unwrap());
+        let result = rt.call::<Actor>(
+            Method::SetAdmin as u64,
             IpldBlock::serialize_cbor(&SetAdminParams(f4_eth_addr)).unwrap(),
         );
         assert!(result.is_ok());
@@ -332,16 +1289,52 @@ mod tests {
         let new_f4_eth_addr = Address::new_delegated(10, &new_eth_addr.0).unwrap();
         rt.set_delegated_address(new_id_addr.id().unwrap(), new_f4_eth_addr);
 
+        // Setting the admin directly is no longer allowed
         rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr); // current admin
         rt.expect_validate_caller_addr(vec![id_addr]);
-        let event = to_actor_event(ConfigAdminSet::new(new_f4_eth_addr)).unwrap();
-        rt.expect_emitted_event(event);
         let result = rt.call::<Actor>(
             Method::SetAdmin as u64,
             IpldBlock::serialize_cbor(&SetAdminParams(new_f4_eth_addr)).unwrap(),
         );
+        rt.verify();
+        assert_eq!(result.unwrap_err().exit_code(), ExitCode::USR_ILLEGAL_STATE);
+
+        // Propose the new admin
+        rt.expect_validate_caller_addr(vec![id_addr]);
+        let result = rt.call::<Actor>(
+            Method::ProposeAdmin as u64,
+            IpldBlock::serialize_cbor(&SetAdminParams(new_f4_eth_addr)).unwrap(),
+        );
+        assert!(result.is_ok());
+        rt.verify();
+
+        // Only the proposed admin can accept
+        rt.expect_validate_caller_addr(vec![new_id_addr]);
+        let result = rt.call::<Actor>(Method::AcceptAdmin as u64, None);
+        rt.verify();
+        assert_eq!(result.unwrap_err().exit_code(), ExitCode::USR_FORBIDDEN);
+
+        rt.expect_validate_caller_any();
+        let proposed = rt
+            .call::<Actor>(Method::GetProposedAdmin as u64, None)
+            .unwrap()
+            .unwrap()
+            .deserialize::<Option<Address>>()
+            .unwrap();
+        rt.verify();
+        assert_eq!(proposed, Some(new_f4_eth_addr));
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, new_id_addr);
+        rt.expect_validate_caller_addr(vec![new_id_addr]);
+        let event = to_actor_event(ConfigAdminSet::new(new_f4_eth_addr)).unwrap();
+        rt.expect_emitted_event(event);
+        rt.expect_emitted_event(
+            emit::admin_changed_event(Some(f4_eth_addr), new_f4_eth_addr).unwrap(),
+        );
+        let result = rt.call::<Actor>(Method::AcceptAdmin as u64, None);
         assert!(result.is_ok());
         rt.verify();
+        assert!(rt.get_state::<State>().proposed_admin.is_none());
 
         rt.expect_validate_caller_any();
         let admin = rt
@@ -370,6 +1363,7 @@ mod tests {
         rt.expect_validate_caller_any();
         let event = to_actor_event(ConfigAdminSet::new(f4_eth_addr)).unwrap();
         rt.expect_emitted_event(event);
+        rt.expect_emitted_event(emit::admin_changed_event(None, f4_eth_addr).unwrap());
         let result = rt.call::<Actor>(
             Method::SetAdmin as u64,
             IpldBlock::serialize_cbor(&SetAdminParams(f4_eth_addr)).unwrap(),
@@ -398,6 +1392,154 @@ mod tests {
         assert_eq!(result.unwrap_err().exit_code(), ExitCode::USR_FORBIDDEN);
     }
 
+    #[test]
+    fn test_cancel_proposed_admin() {
+        let rt = construct_and_verify(1024, TokenCreditRate::from(5usize), 3600, 3600, 3600);
+
+        let id_addr = Address::new_id(110);
+        let eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let f4_eth_addr = Address::new_delegated(10, &eth_addr.0).unwrap();
+        rt.set_delegated_address(id_addr.id().unwrap(), f4_eth_addr);
+        let new_id_addr = Address::new_id(111);
+        let new_eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000001"
+        ));
+        let new_f4_eth_addr = Address::new_delegated(10, &new_eth_addr.0).unwrap();
+        rt.set_delegated_address(new_id_addr.id().unwrap(), new_f4_eth_addr);
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr);
+        rt.expect_validate_caller_any();
+        rt.expect_emitted_event(to_actor_event(ConfigAdminSet::new(f4_eth_addr)).unwrap());
+        rt.expect_emitted_event(emit::admin_changed_event(None, f4_eth_addr).unwrap());
+        rt.call::<Actor>(
+            Method::SetAdmin as u64,
+            IpldBlock::serialize_cbor(&SetAdminParams(f4_eth_addr)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        rt.expect_validate_caller_addr(vec![id_addr]);
+        rt.call::<Actor>(
+            Method::ProposeAdmin as u64,
+            IpldBlock::serialize_cbor(&SetAdminParams(new_f4_eth_addr)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        rt.expect_validate_caller_addr(vec![id_addr]);
+        rt.call::<Actor>(Method::CancelProposedAdmin as u64, None)
+            .unwrap();
+        rt.verify();
+
+        // The proposal can no longer be accepted
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, new_id_addr);
+        let result = rt.call::<Actor>(Method::AcceptAdmin as u64, None);
+        rt.verify();
+        assert_eq!(result.unwrap_err().exit_code(), ExitCode::USR_ILLEGAL_STATE);
+        assert_eq!(rt.get_state::<State>().roles.owners, vec![id_addr]);
+    }
+
+    #[test]
+    fn test_roles() {
+        let rt = construct_and_verify(1024, TokenCreditRate::from(5usize), 3600, 3600, 3600);
+
+        let new_account = |id: u64| {
+            let id_addr = Address::new_id(id);
+            let mut eth_addr = [0u8; 20];
+            eth_addr[19] = id as u8;
+            let f4_eth_addr = Address::new_delegated(10, &eth_addr).unwrap();
+            rt.set_delegated_address(id, f4_eth_addr);
+            (id_addr, f4_eth_addr)
+        };
+        let (owner_id_addr, owner_f4_addr) = new_account(110);
+        let (setter_id_addr, setter_f4_addr) = new_account(111);
+
+        // Set the owner
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, owner_id_addr);
+        rt.expect_validate_caller_any();
+        rt.expect_emitted_event(to_actor_event(ConfigAdminSet::new(owner_f4_addr)).unwrap());
+        rt.expect_emitted_event(emit::admin_changed_event(None, owner_f4_addr).unwrap());
+        rt.call::<Actor>(
+            Method::SetAdmin as u64,
+            IpldBlock::serialize_cbor(&SetAdminParams(owner_f4_addr)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        // The owner adds a param setter
+        let params = RoleParams {
+            role: Role::ParamSetter,
+            address: setter_f4_addr,
+        };
+        rt.expect_validate_caller_addr(vec![owner_id_addr]);
+        rt.call::<Actor>(
+            Method::AddRole as u64,
+            IpldBlock::serialize_cbor(&params).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        // Owners hold every role
+        rt.expect_validate_caller_any();
+        let members = rt
+            .call::<Actor>(
+                Method::GetRoleMembers as u64,
+                IpldBlock::serialize_cbor(&GetRoleMembersParams(Role::ParamSetter)).unwrap(),
+            )
+            .unwrap()
+            .unwrap()
+            .deserialize::<Vec<Address>>()
+            .unwrap();
+        rt.verify();
+        assert_eq!(members, vec![owner_f4_addr, setter_f4_addr]);
+
+        // The param setter cannot manage roles
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, setter_id_addr);
+        rt.expect_validate_caller_addr(vec![owner_id_addr]);
+        let result = rt.call::<Actor>(
+            Method::AddRole as u64,
+            IpldBlock::serialize_cbor(&RoleParams {
+                role: Role::Owner,
+                address: setter_f4_addr,
+            })
+            .unwrap(),
+        );
+        rt.verify();
+        assert_eq!(result.unwrap_err().exit_code(), ExitCode::USR_FORBIDDEN);
+
+        // The last owner cannot be removed
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, owner_id_addr);
+        rt.expect_validate_caller_addr(vec![owner_id_addr]);
+        let result = rt.call::<Actor>(
+            Method::RemoveRole as u64,
+            IpldBlock::serialize_cbor(&RoleParams {
+                role: Role::Owner,
+                address: owner_f4_addr,
+            })
+            .unwrap(),
+        );
+        rt.verify();
+        assert_eq!(
+            result.unwrap_err().exit_code(),
+            ExitCode::USR_ILLEGAL_ARGUMENT
+        );
+
+        // The owner removes the param setter
+        rt.expect_validate_caller_addr(vec![owner_id_addr]);
+        rt.call::<Actor>(
+            Method::RemoveRole as u64,
+            IpldBlock::serialize_cbor(&params).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+        assert_eq!(
+            rt.get_state::<State>().roles.holders(Role::ParamSetter),
+            vec![owner_id_addr]
+        );
+    }
+
     #[test]
     fn test_set_config() {
         let rt = construct_and_verify(1024, TokenCreditRate::from(5usize), 3600, 3600, 3600);
@@ -414,6 +1556,7 @@ mod tests {
 
         let admin_event = to_actor_event(ConfigAdminSet::new(f4_eth_addr)).unwrap();
         rt.expect_emitted_event(admin_event);
+        rt.expect_emitted_event(emit::admin_changed_event(None, f4_eth_addr).unwrap());
 
         let config = RecallConfig {
             blob_capacity: 2048,
@@ -423,6 +1566,15 @@ mod tests {
             blob_default_ttl: ChainEpoch::from(24 * 60 * 60),
             blob_delete_batch_size: 100,
             account_debit_batch_size: 100,
+            account_statement_length: 100,
+            blob_delinquency_grace_period: ChainEpoch::from(3600),
+            blob_max_ttl: ChainEpoch::from(60 * 60 * 24 * 365),
+            blob_trash_period: ChainEpoch::from(3600),
+            blob_add_rate_limit_count: 100,
+            blob_add_rate_limit_bytes: 1024,
+            blob_max_size: 1024 * 1024,
+            read_request_max_ttl: ChainEpoch::from(3600),
+            blob_max_pending_per_account: 10,
         };
         let config_event = to_actor_event(ConfigSet {
             blob_capacity: config.blob_capacity,
@@ -435,6 +1587,7 @@ mod tests {
         })
         .unwrap();
         rt.expect_emitted_event(config_event);
+        rt.expect_emitted_event(emit::config_set_event(Some(f4_eth_addr), 0).unwrap());
 
         let result = rt.call::<Actor>(
             Method::SetConfig as u64,
@@ -491,6 +1644,15 @@ mod tests {
             blob_default_ttl: ChainEpoch::from(24 * 60 * 60),
             blob_delete_batch_size: 100,
             account_debit_batch_size: 100,
+            account_statement_length: 100,
+            blob_delinquency_grace_period: ChainEpoch::from(3600),
+            blob_max_ttl: ChainEpoch::from(60 * 60 * 24 * 365),
+            blob_trash_period: ChainEpoch::from(3600),
+            blob_add_rate_limit_count: 100,
+            blob_add_rate_limit_bytes: 1024,
+            blob_max_size: 1024 * 1024,
+            read_request_max_ttl: ChainEpoch::from(3600),
+            blob_max_pending_per_account: 10,
         };
 
         let test_cases = vec![
@@ -504,61 +1666,105 @@ mod tests {
             },
             // Blob capacity validation
             TestCase {
-                name: "blob capacity cannot be zero",
+                name: "blob capacity cannot be zero",
+                config: RecallConfig {
+                    blob_capacity: 0,
+                    ..valid_config.clone()
+                },
+            },
+            // Credit debit interval validation
+            TestCase {
+                name: "blob credit debit interval cannot be zero",
+                config: RecallConfig {
+                    blob_credit_debit_interval: 0,
+                    ..valid_config.clone()
+                },
+            },
+            TestCase {
+                name: "blob credit debit interval cannot be negative",
+                config: RecallConfig {
+                    blob_credit_debit_interval: -1,
+                    ..valid_config.clone()
+                },
+            },
+            // TTL validations
+            TestCase {
+                name: "blob min ttl cannot be negative",
+                config: RecallConfig {
+                    blob_min_ttl: -1,
+                    ..valid_config.clone()
+                },
+            },
+            TestCase {
+                name: "blob min ttl cannot be zero",
+                config: RecallConfig {
+                    blob_min_ttl: 0,
+                    ..valid_config.clone()
+                },
+            },
+            TestCase {
+                name: "blob default ttl must be greater than or equal to min ttl",
+                config: RecallConfig {
+                    blob_min_ttl: 4 * 60 * 60,
+                    blob_default_ttl: 2 * 60 * 60,
+                    ..valid_config.clone()
+                },
+            },
+            TestCase {
+                name: "blob default ttl cannot be zero",
                 config: RecallConfig {
-                    blob_capacity: 0,
+                    blob_default_ttl: 0,
                     ..valid_config.clone()
                 },
             },
-            // Credit debit interval validation
             TestCase {
-                name: "blob credit debit interval cannot be zero",
+                name: "blob default ttl cannot be negative",
                 config: RecallConfig {
-                    blob_credit_debit_interval: 0,
+                    blob_default_ttl: -1,
                     ..valid_config.clone()
                 },
             },
             TestCase {
-                name: "blob credit debit interval cannot be negative",
+                name: "blob max ttl must be greater than or equal to default ttl",
                 config: RecallConfig {
-                    blob_credit_debit_interval: -1,
+                    blob_max_ttl: 12 * 60 * 60,
                     ..valid_config.clone()
                 },
             },
-            // TTL validations
+            // Blob size and read request validations
             TestCase {
-                name: "blob min ttl cannot be negative",
+                name: "blob max size cannot be zero",
                 config: RecallConfig {
-                    blob_min_ttl: -1,
+                    blob_max_size: 0,
                     ..valid_config.clone()
                 },
             },
             TestCase {
-                name: "blob min ttl cannot be zero",
+                name: "read request max ttl cannot be zero",
                 config: RecallConfig {
-                    blob_min_ttl: 0,
+                    read_request_max_ttl: 0,
                     ..valid_config.clone()
                 },
             },
+            // Bounds validations
             TestCase {
-                name: "blob default ttl must be greater than or equal to min ttl",
+                name: "blob credit debit interval cannot exceed bound",
                 config: RecallConfig {
-                    blob_min_ttl: 4 * 60 * 60,
-                    blob_default_ttl: 2 * 60 * 60,
+                    blob_credit_debit_interval: 60 * 60 * 24 * 2,
                     ..valid_config.clone()
                 },
             },
             TestCase {
-                name: "blob default ttl cannot be zero",
+                name: "blob delete batch size cannot exceed bound",
                 config: RecallConfig {
-                    blob_default_ttl: 0,
+                    blob_delete_batch_size: 10_001,
                     ..valid_config.clone()
                 },
             },
             TestCase {
-                name: "blob default ttl cannot be negative",
+                name: "blob trash period cannot exceed bound",
                 config: RecallConfig {
-                    blob_default_ttl: -1,
+                    blob_trash_period: 60 * 60 * 24 * 31,
                     ..valid_config.clone()
                 },
             },
@@ -591,6 +1797,520 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_schedule_config() {
+        let rt = construct_and_verify(1024, TokenCreditRate::from(5usize), 3600, 3600, 3600);
+
+        let id_addr = Address::new_id(110);
+        let eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let f4_eth_addr = Address::new_delegated(10, &eth_addr.0).unwrap();
+        rt.set_delegated_address(id_addr.id().unwrap(), f4_eth_addr);
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr);
+        rt.expect_validate_caller_any();
+        rt.expect_emitted_event(to_actor_event(ConfigAdminSet::new(f4_eth_addr)).unwrap());
+        rt.expect_emitted_event(emit::admin_changed_event(None, f4_eth_addr).unwrap());
+        rt.call::<Actor>(
+            Method::SetAdmin as u64,
+            IpldBlock::serialize_cbor(&SetAdminParams(f4_eth_addr)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        let get_config = || {
+            rt.expect_validate_caller_any();
+            let config = rt
+                .call::<Actor>(Method::GetConfig as u64, None)
+                .unwrap()
+                .unwrap()
+                .deserialize::<RecallConfig>()
+                .unwrap();
+            rt.verify();
+            config
+        };
+
+        // The activation epoch must be in the future
+        rt.set_epoch(100);
+        let config = RecallConfig {
+            blob_capacity: 2048,
+            ..get_config()
+        };
+        rt.expect_validate_caller_addr(vec![id_addr]);
+        let result = rt.call::<Actor>(
+            Method::ScheduleConfig as u64,
+            IpldBlock::serialize_cbor(&ScheduledConfig {
+                config: config.clone(),
+                activation_epoch: 100,
+            })
+            .unwrap(),
+        );
+        rt.verify();
+        assert_eq!(
+            result.unwrap_err().exit_code(),
+            ExitCode::USR_ILLEGAL_ARGUMENT
+        );
+
+        rt.expect_validate_caller_addr(vec![id_addr]);
+        rt.call::<Actor>(
+            Method::ScheduleConfig as u64,
+            IpldBlock::serialize_cbor(&ScheduledConfig {
+                config: config.clone(),
+                activation_epoch: 200,
+            })
+            .unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        // The scheduled config is visible but not yet in force
+        rt.expect_validate_caller_any();
+        let pending = rt
+            .call::<Actor>(Method::GetPendingConfig as u64, None)
+            .unwrap()
+            .unwrap()
+            .deserialize::<Option<ScheduledConfig>>()
+            .unwrap();
+        rt.verify();
+        assert_eq!(pending.unwrap().activation_epoch, 200);
+        assert_eq!(get_config().blob_capacity, 1024);
+
+        // Applying early is a no-op
+        rt.expect_validate_caller_any();
+        let applied = rt
+            .call::<Actor>(Method::ApplyScheduled as u64, None)
+            .unwrap()
+            .unwrap()
+            .deserialize::<bool>()
+            .unwrap();
+        rt.verify();
+        assert!(!applied);
+
+        // Reads see the config once it's due, before it's applied
+        rt.set_epoch(200);
+        assert_eq!(get_config().blob_capacity, 2048);
+        assert_eq!(rt.get_state::<State>().config.blob_capacity, 1024);
+
+        rt.expect_validate_caller_any();
+        rt.expect_emitted_event(to_actor_event(ConfigSet::new(&config)).unwrap());
+        rt.expect_emitted_event(emit::config_set_event(Some(f4_eth_addr), 200).unwrap());
+        let applied = rt
+            .call::<Actor>(Method::ApplyScheduled as u64, None)
+            .unwrap()
+            .unwrap()
+            .deserialize::<bool>()
+            .unwrap();
+        rt.verify();
+        assert!(applied);
+        let state = rt.get_state::<State>();
+        assert_eq!(state.config.blob_capacity, 2048);
+        assert!(state.scheduled_config.is_none());
+    }
+
+    #[test]
+    fn test_set_config_params() {
+        let rt = construct_and_verify(1024, TokenCreditRate::from(5usize), 3600, 3600, 3600);
+
+        let id_addr = Address::new_id(110);
+        let eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let f4_eth_addr = Address::new_delegated(10, &eth_addr.0).unwrap();
+        rt.set_delegated_address(id_addr.id().unwrap(), f4_eth_addr);
+
+        // Params can't be set individually before an admin exists
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr);
+        rt.expect_validate_caller_any();
+        let result = rt.call::<Actor>(
+            Method::SetBlobCapacity as u64,
+            IpldBlock::serialize_cbor(&SetBlobCapacityParams(2048)).unwrap(),
+        );
+        rt.verify();
+        assert_eq!(result.unwrap_err().exit_code(), ExitCode::USR_ILLEGAL_STATE);
+
+        rt.expect_validate_caller_any();
+        rt.expect_emitted_event(to_actor_event(ConfigAdminSet::new(f4_eth_addr)).unwrap());
+        rt.expect_emitted_event(emit::admin_changed_event(None, f4_eth_addr).unwrap());
+        rt.call::<Actor>(
+            Method::SetAdmin as u64,
+            IpldBlock::serialize_cbor(&SetAdminParams(f4_eth_addr)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        let initial = rt.get_state::<State>().config;
+
+        // Each setter only changes its own params
+        rt.set_epoch(10);
+        let expected = RecallConfig {
+            blob_capacity: 2048,
+            ..initial.clone()
+        };
+        rt.expect_validate_caller_addr(vec![id_addr]);
+        rt.expect_emitted_event(to_actor_event(ConfigSet::new(&expected)).unwrap());
+        rt.expect_emitted_event(emit::config_set_event(Some(f4_eth_addr), 10).unwrap());
+        rt.call::<Actor>(
+            Method::SetBlobCapacity as u64,
+            IpldBlock::serialize_cbor(&SetBlobCapacityParams(2048)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        rt.set_epoch(20);
+        let expected = RecallConfig {
+            blob_credit_debit_interval: 1800,
+            ..expected
+        };
+        rt.expect_validate_caller_addr(vec![id_addr]);
+        rt.expect_emitted_event(to_actor_event(ConfigSet::new(&expected)).unwrap());
+        rt.expect_emitted_event(emit::config_set_event(Some(f4_eth_addr), 20).unwrap());
+        rt.call::<Actor>(
+            Method::SetDebitInterval as u64,
+            IpldBlock::serialize_cbor(&SetDebitIntervalParams(1800)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+        assert_eq!(rt.get_state::<State>().config, expected);
+
+        // The updated config is validated as a whole
+        rt.expect_validate_caller_addr(vec![id_addr]);
+        let result = rt.call::<Actor>(
+            Method::SetBlobTtls as u64,
+            IpldBlock::serialize_cbor(&SetBlobTtlsParams {
+                min_ttl: 7200,
+                default_ttl: 3600,
+                max_ttl: 86400,
+            })
+            .unwrap(),
+        );
+        rt.verify();
+        assert_eq!(
+            result.unwrap_err().exit_code(),
+            ExitCode::USR_ILLEGAL_ARGUMENT
+        );
+        assert_eq!(rt.get_state::<State>().config, expected);
+
+        // Only admins can set params
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, Address::new_id(111));
+        rt.expect_validate_caller_addr(vec![id_addr]);
+        let result = rt.call::<Actor>(
+            Method::SetBlobMaxSize as u64,
+            IpldBlock::serialize_cbor(&SetBlobMaxSizeParams(1024)).unwrap(),
+        );
+        rt.verify();
+        assert_eq!(result.unwrap_err().exit_code(), ExitCode::USR_FORBIDDEN);
+    }
+
+    #[test]
+    fn test_governance() {
+        let rt = construct_and_verify(1024, TokenCreditRate::from(5usize), 3600, 3600, 3600);
+
+        let new_eth_addr = |id: u64| {
+            let id_addr = Address::new_id(id);
+            let mut eth_addr = [0u8; 20];
+            eth_addr[..8].copy_from_slice(&id.to_be_bytes());
+            let f4_eth_addr = Address::new_delegated(10, &eth_addr).unwrap();
+            rt.set_delegated_address(id, f4_eth_addr);
+            (id_addr, f4_eth_addr)
+        };
+        let (admin, f4_admin) = new_eth_addr(110);
+        let voters: Vec<_> = (111..114).map(new_eth_addr).collect();
+        let voter_ids: Vec<_> = voters.iter().map(|(id, _)| *id).collect();
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, admin);
+        rt.expect_validate_caller_any();
+        rt.expect_emitted_event(to_actor_event(ConfigAdminSet::new(f4_admin)).unwrap());
+        rt.expect_emitted_event(emit::admin_changed_event(None, f4_admin).unwrap());
+        rt.call::<Actor>(
+            Method::SetAdmin as u64,
+            IpldBlock::serialize_cbor(&SetAdminParams(f4_admin)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        // Quorum must be reachable
+        let governance = Governance {
+            voters: voters.iter().map(|(_, f4)| *f4).collect(),
+            quorum: 4,
+            proposal_ttl: 100,
+        };
+        rt.expect_validate_caller_addr(vec![admin]);
+        let result = rt.call::<Actor>(
+            Method::EnableGovernance as u64,
+            IpldBlock::serialize_cbor(&governance).unwrap(),
+        );
+        rt.verify();
+        assert_eq!(
+            result.unwrap_err().exit_code(),
+            ExitCode::USR_ILLEGAL_ARGUMENT
+        );
+
+        rt.expect_validate_caller_addr(vec![admin]);
+        rt.call::<Actor>(
+            Method::EnableGovernance as u64,
+            IpldBlock::serialize_cbor(&Governance {
+                quorum: 2,
+                ..governance
+            })
+            .unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+        assert_eq!(
+            rt.get_state::<State>().governance.unwrap().voters,
+            voter_ids
+        );
+
+        // The admin can no longer change the config directly
+        rt.expect_validate_caller_addr(vec![admin]);
+        let result = rt.call::<Actor>(
+            Method::SetBlobCapacity as u64,
+            IpldBlock::serialize_cbor(&SetBlobCapacityParams(2048)).unwrap(),
+        );
+        rt.verify();
+        assert_eq!(result.unwrap_err().exit_code(), ExitCode::USR_FORBIDDEN);
+
+        // A voter proposes a config, and it takes effect once a second voter approves
+        let config = RecallConfig {
+            blob_capacity: 2048,
+            ..rt.get_state::<State>().config
+        };
+        let propose = |caller: Address, action: ProposalAction| {
+            rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, caller);
+            rt.expect_validate_caller_addr(voter_ids.clone());
+            let id = rt
+                .call::<Actor>(
+                    Method::ProposeConfigChange as u64,
+                    IpldBlock::serialize_cbor(&ProposeConfigChangeParams(action)).unwrap(),
+                )
+                .unwrap()
+                .unwrap()
+                .deserialize::<u64>()
+                .unwrap();
+            rt.verify();
+            id
+        };
+        let approve = |caller: Address, id: u64| {
+            rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, caller);
+            rt.expect_validate_caller_addr(voter_ids.clone());
+            let result = rt
+                .call::<Actor>(
+                    Method::ApproveConfigProposal as u64,
+                    IpldBlock::serialize_cbor(&ApproveConfigProposalParams(id)).unwrap(),
+                )
+                .map(|ret| ret.unwrap().deserialize::<bool>().unwrap());
+            rt.verify();
+            result
+        };
+
+        let id = propose(voters[0].0, ProposalAction::SetConfig(config.clone()));
+        assert_eq!(id, 0);
+        assert_eq!(
+            approve(voters[0].0, id).unwrap_err().exit_code(),
+            ExitCode::USR_ILLEGAL_ARGUMENT
+        );
+        assert_eq!(rt.get_state::<State>().config.blob_capacity, 1024);
+
+        rt.set_epoch(10);
+        rt.expect_emitted_event(to_actor_event(ConfigSet::new(&config)).unwrap());
+        rt.expect_emitted_event(emit::config_set_event(Some(voters[0].1), 10).unwrap());
+        assert!(approve(voters[1].0, id).unwrap());
+        let state = rt.get_state::<State>();
+        assert_eq!(state.config, config);
+        assert!(state.proposals.is_empty());
+
+        // Proposals can't be approved after they expire
+        let id = propose(voters[0].0, ProposalAction::SetGovernance(None));
+        rt.set_epoch(111);
+        assert_eq!(
+            approve(voters[1].0, id).unwrap_err().exit_code(),
+            ExitCode::USR_FORBIDDEN
+        );
+        rt.expect_validate_caller_any();
+        let proposals = rt
+            .call::<Actor>(Method::GetConfigProposals as u64, None)
+            .unwrap()
+            .unwrap()
+            .deserialize::<Vec<ConfigProposal>>()
+            .unwrap();
+        rt.verify();
+        assert!(proposals.is_empty());
+
+        // Governance can be disabled by a proposal
+        let id = propose(voters[2].0, ProposalAction::SetGovernance(None));
+        assert!(approve(voters[1].0, id).unwrap());
+        assert!(rt.get_state::<State>().governance.is_none());
+    }
+
+    #[test]
+    fn test_get_config_at() {
+        let rt = construct_and_verify(1024, TokenCreditRate::from(5usize), 3600, 3600, 3600);
+
+        let id_addr = Address::new_id(110);
+        let eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let f4_eth_addr = Address::new_delegated(10, &eth_addr.0).unwrap();
+        rt.set_delegated_address(id_addr.id().unwrap(), f4_eth_addr);
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr);
+
+        let initial = rt.get_state::<State>().config;
+        let config_with_capacity = |blob_capacity| RecallConfig {
+            blob_capacity,
+            ..initial.clone()
+        };
+
+        // Set the config twice, then schedule a third
+        rt.set_epoch(10);
+        rt.expect_validate_caller_any();
+        rt.expect_emitted_event(to_actor_event(ConfigAdminSet::new(f4_eth_addr)).unwrap());
+        rt.expect_emitted_event(emit::admin_changed_event(None, f4_eth_addr).unwrap());
+        rt.expect_emitted_event(
+            to_actor_event(ConfigSet::new(&config_with_capacity(2048))).unwrap(),
+        );
+        rt.expect_emitted_event(emit::config_set_event(Some(f4_eth_addr), 10).unwrap());
+        rt.call::<Actor>(
+            Method::SetConfig as u64,
+            IpldBlock::serialize_cbor(&config_with_capacity(2048)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        rt.set_epoch(20);
+        rt.expect_validate_caller_addr(vec![id_addr]);
+        rt.expect_emitted_event(
+            to_actor_event(ConfigSet::new(&config_with_capacity(4096))).unwrap(),
+        );
+        rt.expect_emitted_event(emit::config_set_event(Some(f4_eth_addr), 20).unwrap());
+        rt.call::<Actor>(
+            Method::SetConfig as u64,
+            IpldBlock::serialize_cbor(&config_with_capacity(4096)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        rt.expect_validate_caller_addr(vec![id_addr]);
+        rt.call::<Actor>(
+            Method::ScheduleConfig as u64,
+            IpldBlock::serialize_cbor(&ScheduledConfig {
+                config: config_with_capacity(8192),
+                activation_epoch: 30,
+            })
+            .unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        let get_config_at = |epoch| {
+            rt.expect_validate_caller_any();
+            let record = rt
+                .call::<Actor>(
+                    Method::GetConfigAt as u64,
+                    IpldBlock::serialize_cbor(&GetConfigAtParams(epoch)).unwrap(),
+                )
+                .unwrap()
+                .unwrap()
+                .deserialize::<Option<ConfigRecord>>()
+                .unwrap()
+                .unwrap();
+            rt.verify();
+            record
+        };
+
+        let record = get_config_at(5);
+        assert_eq!(record.epoch, 0);
+        assert_eq!(record.admin, None);
+        assert_eq!(record.config, initial);
+        let record = get_config_at(10);
+        assert_eq!(record.epoch, 10);
+        assert_eq!(record.admin, Some(f4_eth_addr));
+        assert_eq!(record.config.blob_capacity, 2048);
+        assert_eq!(get_config_at(29).config.blob_capacity, 4096);
+        // The scheduled config is in force once due, before it's applied
+        let record = get_config_at(35);
+        assert_eq!(record.epoch, 30);
+        assert_eq!(record.admin, Some(f4_eth_addr));
+        assert_eq!(record.config.blob_capacity, 8192);
+    }
+
+    #[test]
+    fn test_set_config_bounds() {
+        let rt = construct_and_verify(1024, TokenCreditRate::from(5usize), 3600, 3600, 3600);
+
+        let id_addr = Address::new_id(110);
+        let eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let f4_eth_addr = Address::new_delegated(10, &eth_addr.0).unwrap();
+        rt.set_delegated_address(id_addr.id().unwrap(), f4_eth_addr);
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, id_addr);
+        rt.expect_validate_caller_any();
+        rt.expect_emitted_event(to_actor_event(ConfigAdminSet::new(f4_eth_addr)).unwrap());
+        rt.expect_emitted_event(emit::admin_changed_event(None, f4_eth_addr).unwrap());
+        rt.call::<Actor>(
+            Method::SetAdmin as u64,
+            IpldBlock::serialize_cbor(&SetAdminParams(f4_eth_addr)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        // Bounds with min above max are rejected
+        rt.expect_validate_caller_addr(vec![id_addr]);
+        let result = rt.call::<Actor>(
+            Method::SetConfigBounds as u64,
+            IpldBlock::serialize_cbor(&ConfigBounds {
+                min_blob_capacity: 2048,
+                max_blob_capacity: 1024,
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        rt.verify();
+        assert_eq!(
+            result.unwrap_err().exit_code(),
+            ExitCode::USR_ILLEGAL_ARGUMENT
+        );
+
+        let bounds = ConfigBounds {
+            max_blob_capacity: 1024,
+            ..Default::default()
+        };
+        rt.expect_validate_caller_addr(vec![id_addr]);
+        rt.call::<Actor>(
+            Method::SetConfigBounds as u64,
+            IpldBlock::serialize_cbor(&bounds).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+
+        rt.expect_validate_caller_any();
+        let result = rt
+            .call::<Actor>(Method::GetConfigBounds as u64, None)
+            .unwrap()
+            .unwrap()
+            .deserialize::<ConfigBounds>()
+            .unwrap();
+        rt.verify();
+        assert_eq!(result, bounds);
+
+        // Configs outside the new bounds are rejected
+        let config = RecallConfig {
+            blob_capacity: 2048,
+            ..rt.get_state::<State>().config
+        };
+        rt.expect_validate_caller_addr(vec![id_addr]);
+        let result = rt.call::<Actor>(
+            Method::SetConfig as u64,
+            IpldBlock::serialize_cbor(&config).unwrap(),
+        );
+        rt.verify();
+        let err = result.unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_ILLEGAL_ARGUMENT);
+        assert_eq!(err.msg(), "blob capacity 2048 must be between 1 and 1024");
+    }
+
     #[test]
     fn test_get_config() {
         let rt = construct_and_verify(1024, TokenCreditRate::from(5usize), 3600, 3600, 3600);
@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use fendermint_actor_blobs_shared::state::TokenCreditRate;
+use fendermint_actor_recall_config_shared::RecallConfig;
 use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
 use recall_actor_sdk::TryIntoEVMEvent;
@@ -37,6 +38,19 @@ pub struct ConfigSet {
     pub blob_delete_batch_size: u64,
     pub account_debit_batch_size: u64,
 }
+impl ConfigSet {
+    pub fn new(config: &RecallConfig) -> Self {
+        Self {
+            blob_capacity: config.blob_capacity,
+            token_credit_rate: config.token_credit_rate.clone(),
+            blob_credit_debit_interval: config.blob_credit_debit_interval,
+            blob_min_ttl: config.blob_min_ttl,
+            blob_default_ttl: config.blob_default_ttl,
+            blob_delete_batch_size: config.blob_delete_batch_size,
+            account_debit_batch_size: config.account_debit_batch_size,
+        }
+    }
+}
 impl TryIntoEVMEvent for ConfigSet {
     type Target = sol::Events;
     fn try_into_evm_event(self) -> Result<Self::Target, anyhow::Error> {
@@ -8,6 +8,7 @@ use fendermint_actor_bucket::BUCKET_ACTOR_NAME;
 use fendermint_actor_chainmetadata::CHAINMETADATA_ACTOR_NAME;
 use fendermint_actor_eam::IPC_EAM_ACTOR_NAME;
 use fendermint_actor_gas_market_eip1559::ACTOR_NAME as GAS_MARKET_EIP1559_ACTOR_NAME;
+use fendermint_actor_machine_registry::MACHINE_REGISTRY_ACTOR_NAME;
 use fendermint_actor_recall_config::ACTOR_NAME as RECALL_CONFIG_ACTOR_NAME;
 use fendermint_actor_timehub::TIMEHUB_ACTOR_NAME;
 use fvm_ipld_blockstore::Blockstore;
@@ -21,6 +22,7 @@ pub const REQUIRED_ACTORS: &[&str] = &[
     BUCKET_ACTOR_NAME,
     CHAINMETADATA_ACTOR_NAME,
     GAS_MARKET_EIP1559_ACTOR_NAME,
+    MACHINE_REGISTRY_ACTOR_NAME,
     RECALL_CONFIG_ACTOR_NAME,
     IPC_EAM_ACTOR_NAME,
     TIMEHUB_ACTOR_NAME,
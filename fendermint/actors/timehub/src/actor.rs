@@ -4,17 +4,22 @@
 
 use cid::Cid;
 use fendermint_actor_blobs_shared::has_credit_approval;
-use fendermint_actor_machine::MachineActor;
+use fendermint_actor_machine::{is_writer, MachineActor, MachineState};
 use fil_actors_runtime::{
     actor_dispatch, actor_error,
     runtime::{ActorCode, Runtime},
     ActorError,
 };
+use fvm_shared::{address::Address, clock::ChainEpoch};
 use recall_actor_sdk::{emit_evm_event, require_addr_is_origin_or_caller, to_id_address};
 use tracing::debug;
 
+use crate::emit;
 use crate::sol_facade::EventPushed;
-use crate::{Leaf, Method, PushParams, PushReturn, State, TIMEHUB_ACTOR_NAME};
+use crate::{
+    leaf_cid, ConstructorParams, GetLeavesParams, InclusionProof, Leaf, Method, PushManyParams,
+    PushManyReturn, PushParams, PushReturn, State, TIMEHUB_ACTOR_NAME,
+};
 
 #[cfg(feature = "fil-actor")]
 fil_actors_runtime::wasm_trampoline!(TimehubActor);
@@ -25,24 +30,54 @@ pub struct TimehubActor;
 // This avoids using CID so that the store does not try to validate or resolve it.
 type RawLeaf = (u64, Vec<u8>);
 
+/// Check access control.
+/// Either the caller needs to be the Timehub owner or one of its writers, or the owner needs
+/// to have given a credit approval to the caller.
+fn ensure_push_allowed(rt: &impl Runtime, from: Address) -> Result<(), ActorError> {
+    let state = rt.state::<State>()?;
+    let owner = state.owner;
+    let from = to_id_address(rt, from, false)?;
+    require_addr_is_origin_or_caller(rt, from)?;
+    if is_writer(rt.store(), &state, from)? {
+        return Ok(());
+    }
+
+    let actor_address = state.address.get()?;
+    if !has_credit_approval(rt, owner, from)? {
+        return Err(actor_error!(
+            forbidden;
+            format!("Unauthorized: missing credit approval from Timehub owner {} to {} for Timehub {}", owner, from, actor_address)));
+    }
+    Ok(())
+}
+
+/// Decode a raw leaf as timestamp and raw bytes. Then decode as a CID.
+fn to_leaf((timestamp, bytes): RawLeaf) -> Result<Leaf, ActorError> {
+    Ok(Leaf {
+        timestamp,
+        witnessed: Cid::try_from(bytes)
+            .map_err(|_err| actor_error!(illegal_argument; "internal bytes are not a valid CID"))?,
+    })
+}
+
 impl TimehubActor {
+    /// Creates the timehub as a machine, then applies the optional leaf limits.
+    fn constructor(rt: &impl Runtime, params: ConstructorParams) -> Result<(), ActorError> {
+        <Self as MachineActor>::constructor(
+            rt,
+            fendermint_actor_machine::ConstructorParams {
+                owner: params.owner,
+                metadata: params.metadata,
+            },
+        )?;
+        rt.transaction(|st: &mut State, _| {
+            st.set_limits(params.max_leaf_size, params.max_leaf_count)
+        })
+    }
+
     fn push(rt: &impl Runtime, params: PushParams) -> Result<PushReturn, ActorError> {
         rt.validate_immediate_caller_accept_any()?;
-
-        // Check access control.
-        // Either the caller needs to be the Timehub owner, or the owner needs to have given a
-        // credit approval to the caller.
-        let state = rt.state::<State>()?;
-        let owner = state.owner;
-        let from = to_id_address(rt, params.from, false)?;
-        require_addr_is_origin_or_caller(rt, from)?;
-
-        let actor_address = state.address.get()?;
-        if !has_credit_approval(rt, owner, from)? {
-            return Err(actor_error!(
-                forbidden;
-                format!("Unauthorized: missing credit approval from Timehub owner {} to {} for Timehub {}", owner, from, actor_address)));
-        }
+        ensure_push_allowed(rt, params.from)?;
 
         // Decode the raw bytes as a Cid and report any errors.
         // However, we pass opaque bytes to the store as it tries to validate and resolve any CID
@@ -53,10 +88,57 @@ impl TimehubActor {
         })?;
         let timestamp = rt.tipset_timestamp();
         let data: RawLeaf = (timestamp, params.cid_bytes);
+        let leaf = leaf_cid(&data)?;
 
-        let ret = rt.transaction(|st: &mut State, rt| st.push(rt.store(), data))?;
+        let ret = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_frozen()?;
+            let ret = st.push(rt.store(), data)?;
+            st.record_root(rt.store(), rt.curr_epoch(), ret.root)?;
+            Ok(ret)
+        })?;
 
         emit_evm_event(rt, EventPushed::new(ret.index, timestamp, cid))?;
+        emit::leaf_appended(rt, ret.index, leaf, ret.root)?;
+
+        Ok(ret)
+    }
+
+    /// Pushes multiple CIDs in one transaction, flushing the timehub once.
+    fn push_many(rt: &impl Runtime, params: PushManyParams) -> Result<PushManyReturn, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        if params.cids.is_empty() {
+            return Err(actor_error!(illegal_argument; "at least one CID is required"));
+        }
+        ensure_push_allowed(rt, params.from)?;
+
+        let cids = params
+            .cids
+            .iter()
+            .map(|bytes| {
+                Cid::try_from(bytes.as_slice())
+                    .map_err(|_err| actor_error!(illegal_argument; "data must be valid CID bytes"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let timestamp = rt.tipset_timestamp();
+        let data: Vec<RawLeaf> = params
+            .cids
+            .into_iter()
+            .map(|bytes| (timestamp, bytes.into_vec()))
+            .collect();
+        let leaves = data.iter().map(leaf_cid).collect::<Result<Vec<_>, _>>()?;
+
+        let ret = rt.transaction(|st: &mut State, rt| {
+            st.ensure_not_frozen()?;
+            let ret = st.push_many(rt.store(), data)?;
+            st.record_root(rt.store(), rt.curr_epoch(), ret.root)?;
+            Ok(ret)
+        })?;
+
+        for (i, (cid, leaf)) in cids.into_iter().zip(leaves).enumerate() {
+            let index = ret.start_index + i as u64;
+            emit_evm_event(rt, EventPushed::new(index, timestamp, cid))?;
+            emit::leaf_appended(rt, index, leaf, ret.root)?;
+        }
 
         Ok(ret)
     }
@@ -65,17 +147,36 @@ impl TimehubActor {
         debug!(index, "get_leaf_at");
         rt.validate_immediate_caller_accept_any()?;
         let st: State = rt.state()?;
-        // Decode leaf as timestamp and raw bytes. Then decode as a CID
         let leaf: Option<RawLeaf> = st.get_leaf_at(rt.store(), index)?;
-        leaf.map(|(timestamp, bytes)| -> Result<Leaf, ActorError> {
-            Ok(Leaf {
-                timestamp,
-                witnessed: Cid::try_from(bytes).map_err(
-                    |_err| actor_error!(illegal_argument; "internal bytes are not a valid CID"),
-                )?,
-            })
+        leaf.map(to_leaf).transpose()
+    }
+
+    /// Returns up to `limit` leaves in index order, starting at `start`.
+    ///
+    /// At most [`MAX_LEAVES_PER_CALL`](crate::MAX_LEAVES_PER_CALL) leaves are returned.
+    fn get_leaves(rt: &impl Runtime, params: GetLeavesParams) -> Result<Vec<Leaf>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        let st: State = rt.state()?;
+        let leaves: Vec<RawLeaf> = st.get_leaves(rt.store(), params.start, params.limit)?;
+        leaves.into_iter().map(to_leaf).collect()
+    }
+
+    /// Returns a proof that the leaf at the given index is included in the current root.
+    fn get_proof(rt: &impl Runtime, index: u64) -> Result<Option<InclusionProof>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        let st: State = rt.state()?;
+        st.get_proof(rt.store(), index)
+    }
+
+    /// Prunes all leaves below `up_to_index`. Only the Timehub owner can prune.
+    /// Returns the new pruned watermark.
+    fn prune(rt: &impl Runtime, up_to_index: u64) -> Result<u64, ActorError> {
+        let owner = rt.state::<State>()?.owner;
+        rt.validate_immediate_caller_is(std::iter::once(&owner))?;
+        rt.transaction(|st: &mut State, _| {
+            st.ensure_not_frozen()?;
+            st.prune(rt.store(), up_to_index)
         })
-        .transpose()
     }
 
     fn get_root(rt: &impl Runtime) -> Result<Cid, ActorError> {
@@ -84,6 +185,13 @@ impl TimehubActor {
         st.get_root(rt.store())
     }
 
+    /// Returns the root as of the end of the given epoch, if anything was pushed by then.
+    fn get_root_at(rt: &impl Runtime, epoch: ChainEpoch) -> Result<Option<Cid>, ActorError> {
+        rt.validate_immediate_caller_accept_any()?;
+        let st: State = rt.state()?;
+        st.get_root_at(rt.store(), epoch)
+    }
+
     fn get_peaks(rt: &impl Runtime) -> Result<Vec<Cid>, ActorError> {
         rt.validate_immediate_caller_accept_any()?;
         let st: State = rt.state()?;
@@ -113,11 +221,23 @@ impl ActorCode for TimehubActor {
         Init => init,
         GetAddress => get_address,
         GetMetadata => get_metadata,
+        AddWriter => add_writer,
+        RemoveWriter => remove_writer,
+        ListWriters => list_writers,
+        TransferOwnership => transfer_ownership,
+        AcceptOwnership => accept_ownership,
+        Freeze => freeze,
+        Unfreeze => unfreeze,
         Push => push,
         Get => get_leaf_at,
         Root => get_root,
         Peaks => get_peaks,
         Count => get_count,
+        GetProof => get_proof,
+        GetLeaves => get_leaves,
+        PushMany => push_many,
+        Prune => prune,
+        GetRootAt => get_root_at,
         _ => fallback,
     }
 }
@@ -134,21 +254,26 @@ mod tests {
         params::GetCreditApprovalParams, state::CreditApproval, Method as BlobMethod,
         BLOBS_ACTOR_ADDR,
     };
+    use fendermint_actor_machine::registry::{
+        MACHINE_REGISTRY_ACTOR_ADDR, REGISTER_MACHINE_METHOD, UPDATE_MACHINE_OWNER_METHOD,
+    };
     use fendermint_actor_machine::sol_facade::{MachineCreated, MachineInitialized};
-    use fendermint_actor_machine::{ConstructorParams, InitParams, Kind};
+    use fendermint_actor_machine::{
+        AddWriterParams, ConstructorParams, InitParams, Kind, RemoveWriterParams,
+        TransferOwnershipParams,
+    };
     use fil_actors_evm_shared::address::EthAddress;
     use fil_actors_runtime::{
         runtime::MessageInfo,
         test_utils::{
-            expect_empty, MockRuntime, ADM_ACTOR_CODE_ID, ETHACCOUNT_ACTOR_CODE_ID,
-            INIT_ACTOR_CODE_ID,
+            expect_empty, make_identity_cid, MockRuntime, ADM_ACTOR_CODE_ID,
+            ETHACCOUNT_ACTOR_CODE_ID, INIT_ACTOR_CODE_ID,
         },
         ADM_ACTOR_ADDR, INIT_ACTOR_ADDR,
     };
     use fvm_ipld_encoding::ipld_block::IpldBlock;
     use fvm_shared::{
-        address::Address, clock::ChainEpoch, econ::TokenAmount, error::ExitCode, sys::SendFlags,
-        MethodNum,
+        address::Address, econ::TokenAmount, error::ExitCode, sys::SendFlags, MethodNum,
     };
     use recall_actor_sdk::to_actor_event;
 
@@ -166,6 +291,21 @@ mod tests {
 
         rt.set_caller(*INIT_ACTOR_CODE_ID, INIT_ACTOR_ADDR);
         rt.expect_validate_caller_addr(vec![INIT_ACTOR_ADDR]);
+        rt.set_address_actor_type(
+            MACHINE_REGISTRY_ACTOR_ADDR,
+            make_identity_cid(b"machine_registry"),
+        );
+        rt.expect_send(
+            MACHINE_REGISTRY_ACTOR_ADDR,
+            REGISTER_MACHINE_METHOD,
+            None,
+            TokenAmount::from_whole(0),
+            None,
+            SendFlags::empty(),
+            None,
+            ExitCode::OK,
+            None,
+        );
         let metadata = HashMap::new();
         let event = to_actor_event(MachineCreated::new(
             Kind::Timehub,
@@ -238,6 +378,21 @@ mod tests {
         .unwrap()
     }
 
+    /// Expects the events for pushing the CIDs, with the root the pushed leaves will lead to.
+    fn expect_push_events(rt: &MockRuntime, cids: &[Cid], timestamp: u64) {
+        let leaves: Vec<RawLeaf> = cids.iter().map(|cid| (timestamp, cid.to_bytes())).collect();
+        let mut state = rt.get_state::<State>();
+        let start_index = state.leaf_count();
+        let root = state.push_many(rt.store(), leaves.clone()).unwrap().root;
+        for (i, (cid, leaf)) in cids.iter().zip(&leaves).enumerate() {
+            let index = start_index + i as u64;
+            let event = to_actor_event(EventPushed::new(index, timestamp, *cid)).unwrap();
+            rt.expect_emitted_event(event);
+            let event = emit::leaf_appended_event(index, leaf_cid(leaf).unwrap(), root).unwrap();
+            rt.expect_emitted_event(event);
+        }
+    }
+
     fn push_cid(rt: &mut MockRuntime, cid: Cid, timestamp: u64, expected_index: u64) -> PushReturn {
         rt.expect_validate_caller_any();
         rt.tipset_timestamp = timestamp;
@@ -245,8 +400,8 @@ mod tests {
             cid_bytes: cid.to_bytes(),
             from: rt.caller(),
         };
-        let event = to_actor_event(EventPushed::new(expected_index, timestamp, cid)).unwrap();
-        rt.expect_emitted_event(event);
+        assert_eq!(rt.get_state::<State>().leaf_count(), expected_index);
+        expect_push_events(rt, &[cid], timestamp);
         rt.call::<TimehubActor>(
             Method::Push as u64,
             IpldBlock::serialize_cbor(&push_params).unwrap(),
@@ -331,7 +486,567 @@ mod tests {
         let count = get_count(&rt);
         assert_eq!(count, 2);
 
+        // Read both values back in one call
+        rt.expect_validate_caller_any();
+        let leaves = rt
+            .call::<TimehubActor>(
+                Method::GetLeaves as u64,
+                IpldBlock::serialize_cbor(&GetLeavesParams {
+                    start: 0,
+                    limit: 10,
+                })
+                .unwrap(),
+            )
+            .unwrap()
+            .unwrap()
+            .deserialize::<Vec<Leaf>>()
+            .unwrap();
+        assert_eq!(leaves.len(), 2);
+        assert_eq!(leaves[0].witnessed, cid0);
+        assert_eq!(leaves[1].witnessed, cid1);
+        assert_eq!(leaves[1].timestamp, t1);
+
+        rt.verify();
+    }
+
+    #[test]
+    pub fn test_push_many() {
+        let owner = Address::new_id(110);
+        let actor_address = Address::new_id(111);
+
+        let rt = construct_runtime(actor_address, owner);
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, owner);
+        rt.set_origin(owner);
+
+        let cid0 = Cid::from_str("bafk2bzacecmnyfiwb52tkbwmm2dsd7ysi3nvuxl3lmspy7pl26wxj4zj7w4wi")
+            .unwrap();
+        let cid1 =
+            Cid::from_str("baeabeidtz333ke5c4ultzeg6jkyzgdmvduytt2so3ahozm4zqstiuwq33e").unwrap();
+
+        // An empty batch is rejected
+        rt.expect_validate_caller_any();
+        let err = rt
+            .call::<TimehubActor>(
+                Method::PushMany as u64,
+                IpldBlock::serialize_cbor(&PushManyParams {
+                    cids: vec![],
+                    from: owner,
+                })
+                .unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_ILLEGAL_ARGUMENT);
+        rt.verify();
+
+        let timestamp = 1738787063;
+        rt.tipset_timestamp = timestamp;
+        rt.expect_validate_caller_any();
+        expect_push_events(&rt, &[cid0, cid1], timestamp);
+        let result = rt
+            .call::<TimehubActor>(
+                Method::PushMany as u64,
+                IpldBlock::serialize_cbor(&PushManyParams {
+                    cids: vec![cid0.to_bytes().into(), cid1.to_bytes().into()],
+                    from: owner,
+                })
+                .unwrap(),
+            )
+            .unwrap()
+            .unwrap()
+            .deserialize::<PushManyReturn>()
+            .unwrap();
+        rt.verify();
+
+        assert_eq!(result.start_index, 0);
+        assert_eq!(result.root, get_root(&rt));
+        assert_eq!(get_count(&rt), 2);
+        assert_eq!(get_leaf(&rt, 1).witnessed, cid1);
+    }
+
+    #[test]
+    pub fn test_writers() {
+        let owner = Address::new_id(110);
+        let actor_address = Address::new_id(111);
+        let writer = Address::new_id(112);
+
+        let mut rt = construct_runtime(actor_address, owner);
+
+        let list_writers = |rt: &MockRuntime| {
+            rt.expect_validate_caller_any();
+            rt.call::<TimehubActor>(Method::ListWriters as u64, None)
+                .unwrap()
+                .unwrap()
+                .deserialize::<Vec<Address>>()
+                .unwrap()
+        };
+
+        // Only the owner can add writers
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, writer);
+        rt.expect_validate_caller_addr(vec![owner]);
+        let err = rt
+            .call::<TimehubActor>(
+                Method::AddWriter as u64,
+                IpldBlock::serialize_cbor(&AddWriterParams(writer)).unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, owner);
+        rt.expect_validate_caller_addr(vec![owner]);
+        rt.call::<TimehubActor>(
+            Method::AddWriter as u64,
+            IpldBlock::serialize_cbor(&AddWriterParams(writer)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+        assert_eq!(list_writers(&rt), vec![writer]);
+
+        // Adding the same writer twice fails
+        rt.expect_validate_caller_addr(vec![owner]);
+        let err = rt
+            .call::<TimehubActor>(
+                Method::AddWriter as u64,
+                IpldBlock::serialize_cbor(&AddWriterParams(writer)).unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_ILLEGAL_ARGUMENT);
+        rt.verify();
+
+        // A writer can push without a credit approval from the owner
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, writer);
+        rt.set_origin(writer);
+        let cid = Cid::from_str("bafk2bzacecmnyfiwb52tkbwmm2dsd7ysi3nvuxl3lmspy7pl26wxj4zj7w4wi")
+            .unwrap();
+        push_cid(&mut rt, cid, 1738787063, 0);
+        rt.verify();
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, owner);
+        rt.expect_validate_caller_addr(vec![owner]);
+        rt.call::<TimehubActor>(
+            Method::RemoveWriter as u64,
+            IpldBlock::serialize_cbor(&RemoveWriterParams(writer)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+        assert!(list_writers(&rt).is_empty());
+
+        // Removing a missing writer fails
+        rt.expect_validate_caller_addr(vec![owner]);
+        let err = rt
+            .call::<TimehubActor>(
+                Method::RemoveWriter as u64,
+                IpldBlock::serialize_cbor(&RemoveWriterParams(writer)).unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_NOT_FOUND);
+        rt.verify();
+    }
+
+    #[test]
+    pub fn test_transfer_ownership() {
+        let owner = Address::new_id(110);
+        let actor_address = Address::new_id(111);
+        let new_owner = Address::new_id(112);
+
+        let rt = construct_runtime(actor_address, owner);
+
+        // Only the owner can propose a transfer
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, new_owner);
+        rt.expect_validate_caller_addr(vec![owner]);
+        let err = rt
+            .call::<TimehubActor>(
+                Method::TransferOwnership as u64,
+                IpldBlock::serialize_cbor(&TransferOwnershipParams(new_owner)).unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, owner);
+        rt.expect_validate_caller_addr(vec![owner]);
+        rt.call::<TimehubActor>(
+            Method::TransferOwnership as u64,
+            IpldBlock::serialize_cbor(&TransferOwnershipParams(new_owner)).unwrap(),
+        )
+        .unwrap();
+        rt.verify();
+        // Ownership doesn't change until the transfer is accepted
+        let state = rt.get_state::<State>();
+        assert_eq!(state.owner, owner);
+        assert_eq!(state.pending_owner, Some(new_owner));
+
+        // Only the proposed owner can accept
+        rt.expect_validate_caller_addr(vec![new_owner]);
+        let err = rt
+            .call::<TimehubActor>(Method::AcceptOwnership as u64, None)
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, new_owner);
+        rt.expect_validate_caller_addr(vec![new_owner]);
+        rt.expect_send(
+            MACHINE_REGISTRY_ACTOR_ADDR,
+            UPDATE_MACHINE_OWNER_METHOD,
+            None,
+            TokenAmount::from_whole(0),
+            None,
+            SendFlags::empty(),
+            None,
+            ExitCode::OK,
+            None,
+        );
+        rt.call::<TimehubActor>(Method::AcceptOwnership as u64, None)
+            .unwrap();
+        rt.verify();
+        let state = rt.get_state::<State>();
+        assert_eq!(state.owner, new_owner);
+        assert_eq!(state.pending_owner, None);
+
+        // The previous owner lost access
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, owner);
+        rt.expect_validate_caller_addr(vec![new_owner]);
+        let err = rt
+            .call::<TimehubActor>(
+                Method::AddWriter as u64,
+                IpldBlock::serialize_cbor(&AddWriterParams(owner)).unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+
+        // There is nothing left to accept
+        let err = rt
+            .call::<TimehubActor>(Method::AcceptOwnership as u64, None)
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_ILLEGAL_STATE);
+        rt.verify();
+    }
+
+    #[test]
+    pub fn test_freeze() {
+        let owner = Address::new_id(110);
+        let actor_address = Address::new_id(111);
+        let other = Address::new_id(112);
+
+        let mut rt = construct_runtime(actor_address, owner);
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, owner);
+        rt.set_origin(owner);
+
+        let t0 = 1738787063;
+        let cid0 = Cid::from_str("bafk2bzacecmnyfiwb52tkbwmm2dsd7ysi3nvuxl3lmspy7pl26wxj4zj7w4wi")
+            .unwrap();
+        push_cid(&mut rt, cid0, t0, 0);
+        rt.verify();
+
+        // Only the owner can freeze
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, other);
+        rt.expect_validate_caller_addr(vec![owner]);
+        let err = rt
+            .call::<TimehubActor>(Method::Freeze as u64, None)
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, owner);
+        rt.expect_validate_caller_addr(vec![owner]);
+        rt.call::<TimehubActor>(Method::Freeze as u64, None)
+            .unwrap();
+        rt.verify();
+        assert!(rt.get_state::<State>().frozen);
+
+        // Freezing twice fails
+        rt.expect_validate_caller_addr(vec![owner]);
+        let err = rt
+            .call::<TimehubActor>(Method::Freeze as u64, None)
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+
+        // Mutations fail
+        rt.expect_validate_caller_any();
+        let err = rt
+            .call::<TimehubActor>(
+                Method::Push as u64,
+                IpldBlock::serialize_cbor(&PushParams {
+                    cid_bytes: cid0.to_bytes(),
+                    from: owner,
+                })
+                .unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+
+        rt.expect_validate_caller_addr(vec![owner]);
+        let err = rt
+            .call::<TimehubActor>(
+                Method::AddWriter as u64,
+                IpldBlock::serialize_cbor(&AddWriterParams(other)).unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+
+        // Reads keep working
+        assert_eq!(get_count(&rt), 1);
+        assert_eq!(get_leaf(&rt, 0).witnessed, cid0);
+
+        rt.expect_validate_caller_addr(vec![owner]);
+        rt.call::<TimehubActor>(Method::Unfreeze as u64, None)
+            .unwrap();
+        rt.verify();
+        assert!(!rt.get_state::<State>().frozen);
+
+        // Unfreezing twice fails
+        rt.expect_validate_caller_addr(vec![owner]);
+        let err = rt
+            .call::<TimehubActor>(Method::Unfreeze as u64, None)
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_ILLEGAL_STATE);
+        rt.verify();
+
+        // Pushes work again
+        let result = push_cid(&mut rt, cid0, t0 + 1, 1);
+        assert_eq!(result.index, 1);
+        rt.verify();
+    }
+
+    #[test]
+    pub fn test_get_root_at() {
+        let owner = Address::new_id(110);
+        let actor_address = Address::new_id(111);
+
+        let mut rt = construct_runtime(actor_address, owner);
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, owner);
+        rt.set_origin(owner);
+
+        let get_root_at = |rt: &MockRuntime, epoch: ChainEpoch| {
+            rt.expect_validate_caller_any();
+            rt.call::<TimehubActor>(
+                Method::GetRootAt as u64,
+                IpldBlock::serialize_cbor(&epoch).unwrap(),
+            )
+            .unwrap()
+            .unwrap()
+            .deserialize::<Option<Cid>>()
+            .unwrap()
+        };
+
+        let cid = Cid::from_str("bafk2bzacecmnyfiwb52tkbwmm2dsd7ysi3nvuxl3lmspy7pl26wxj4zj7w4wi")
+            .unwrap();
+        rt.set_epoch(10);
+        let root0 = push_cid(&mut rt, cid, 1738787063, 0).root;
+        rt.set_epoch(20);
+        let root1 = push_cid(&mut rt, cid, 1738787064, 1).root;
+
+        assert_eq!(get_root_at(&rt, 9), None);
+        assert_eq!(get_root_at(&rt, 10), Some(root0));
+        assert_eq!(get_root_at(&rt, 19), Some(root0));
+        assert_eq!(get_root_at(&rt, 20), Some(root1));
+        rt.verify();
+    }
+
+    #[test]
+    pub fn test_prune() {
+        let owner = Address::new_id(110);
+        let actor_address = Address::new_id(111);
+        let origin = Address::new_id(112);
+
+        let mut rt = construct_runtime(actor_address, owner);
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, owner);
+        rt.set_origin(owner);
+
+        let cid = Cid::from_str("bafk2bzacecmnyfiwb52tkbwmm2dsd7ysi3nvuxl3lmspy7pl26wxj4zj7w4wi")
+            .unwrap();
+        for index in 0..3 {
+            push_cid(&mut rt, cid, 1738787063, index);
+        }
+        let root = get_root(&rt);
+
+        // Only the owner can prune
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, origin);
+        rt.expect_validate_caller_addr(vec![owner]);
+        let err = rt
+            .call::<TimehubActor>(
+                Method::Prune as u64,
+                IpldBlock::serialize_cbor(&2u64).unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_FORBIDDEN);
+        rt.verify();
+
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, owner);
+        rt.expect_validate_caller_addr(vec![owner]);
+        let watermark = rt
+            .call::<TimehubActor>(
+                Method::Prune as u64,
+                IpldBlock::serialize_cbor(&2u64).unwrap(),
+            )
+            .unwrap()
+            .unwrap()
+            .deserialize::<u64>()
+            .unwrap();
+        assert_eq!(watermark, 2);
+        rt.verify();
+
+        // The root is unchanged, and pruned leaves are no longer served
+        assert_eq!(get_root(&rt), root);
+        rt.expect_validate_caller_any();
+        let leaf = rt
+            .call::<TimehubActor>(
+                Method::Get as u64,
+                IpldBlock::serialize_cbor(&1u64).unwrap(),
+            )
+            .unwrap()
+            .unwrap()
+            .deserialize::<Option<Leaf>>()
+            .unwrap();
+        assert!(leaf.is_none());
+        assert_eq!(get_leaf(&rt, 2).witnessed, cid);
+
+        // The watermark can't move backwards
+        rt.expect_validate_caller_addr(vec![owner]);
+        let err = rt
+            .call::<TimehubActor>(
+                Method::Prune as u64,
+                IpldBlock::serialize_cbor(&1u64).unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_ILLEGAL_ARGUMENT);
+        rt.verify();
+    }
+
+    #[test]
+    pub fn test_constructor_limits() {
+        let owner = Address::new_id(110);
+        let actor_address = Address::new_id(111);
+        let owner_eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let owner_delegated_addr = Address::new_delegated(10, &owner_eth_addr.0).unwrap();
+
+        let rt = MockRuntime {
+            receiver: actor_address,
+            ..Default::default()
+        };
+        rt.set_delegated_address(owner.id().unwrap(), owner_delegated_addr);
+        rt.set_caller(*INIT_ACTOR_CODE_ID, INIT_ACTOR_ADDR);
+        rt.expect_validate_caller_addr(vec![INIT_ACTOR_ADDR]);
+        rt.set_address_actor_type(
+            MACHINE_REGISTRY_ACTOR_ADDR,
+            make_identity_cid(b"machine_registry"),
+        );
+        rt.expect_send(
+            MACHINE_REGISTRY_ACTOR_ADDR,
+            REGISTER_MACHINE_METHOD,
+            None,
+            TokenAmount::from_whole(0),
+            None,
+            SendFlags::empty(),
+            None,
+            ExitCode::OK,
+            None,
+        );
+        let metadata = HashMap::new();
+        let event = to_actor_event(MachineCreated::new(
+            Kind::Timehub,
+            owner_delegated_addr,
+            &metadata,
+        ))
+        .unwrap();
+        rt.expect_emitted_event(event);
+        let result = rt
+            .call::<TimehubActor>(
+                Method::Constructor as u64,
+                IpldBlock::serialize_cbor(&crate::ConstructorParams {
+                    owner,
+                    metadata,
+                    max_leaf_size: Some(64),
+                    max_leaf_count: Some(1),
+                })
+                .unwrap(),
+            )
+            .unwrap();
+        expect_empty(result);
+        rt.verify();
+
+        let state = rt.get_state::<State>();
+        assert_eq!(state.max_leaf_size, 64);
+        assert_eq!(state.max_leaf_count, Some(1));
+    }
+
+    #[test]
+    pub fn test_constructor_without_registry() {
+        let owner = Address::new_id(110);
+        let actor_address = Address::new_id(111);
+        let owner_eth_addr = EthAddress(hex_literal::hex!(
+            "CAFEB0BA00000000000000000000000000000000"
+        ));
+        let owner_delegated_addr = Address::new_delegated(10, &owner_eth_addr.0).unwrap();
+
+        // Chains without a machine registry still create machines, they just aren't registered
+        let rt = MockRuntime {
+            receiver: actor_address,
+            ..Default::default()
+        };
+        rt.set_delegated_address(owner.id().unwrap(), owner_delegated_addr);
+        rt.set_caller(*INIT_ACTOR_CODE_ID, INIT_ACTOR_ADDR);
+        rt.expect_validate_caller_addr(vec![INIT_ACTOR_ADDR]);
+        let metadata = HashMap::new();
+        let event = to_actor_event(MachineCreated::new(
+            Kind::Timehub,
+            owner_delegated_addr,
+            &metadata,
+        ))
+        .unwrap();
+        rt.expect_emitted_event(event);
+        let result = rt
+            .call::<TimehubActor>(
+                Method::Constructor as u64,
+                IpldBlock::serialize_cbor(&ConstructorParams { owner, metadata }).unwrap(),
+            )
+            .unwrap();
+        expect_empty(result);
+        rt.verify();
+
+        let state = rt.get_state::<State>();
+        assert_eq!(state.owner, owner);
+    }
+
+    #[test]
+    pub fn test_push_over_leaf_count_cap() {
+        let owner = Address::new_id(110);
+        let actor_address = Address::new_id(111);
+
+        let mut rt = construct_runtime(actor_address, owner);
+        rt.replace_state(&{
+            let mut state = rt.get_state::<State>();
+            state.set_limits(None, Some(1)).unwrap();
+            state
+        });
+        rt.set_caller(*ETHACCOUNT_ACTOR_CODE_ID, owner);
+        rt.set_origin(owner);
+
+        let cid = Cid::from_str("bafk2bzacecmnyfiwb52tkbwmm2dsd7ysi3nvuxl3lmspy7pl26wxj4zj7w4wi")
+            .unwrap();
+        push_cid(&mut rt, cid, 1738787063, 0);
+
+        rt.expect_validate_caller_any();
+        let err = rt
+            .call::<TimehubActor>(
+                Method::Push as u64,
+                IpldBlock::serialize_cbor(&PushParams {
+                    cid_bytes: cid.to_bytes(),
+                    from: owner,
+                })
+                .unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_ILLEGAL_ARGUMENT);
         rt.verify();
+        assert_eq!(get_count(&rt), 1);
     }
 
     #[test]
@@ -403,6 +1118,7 @@ mod tests {
             expiry: None,
             credit_used: Default::default(),
             gas_fee_used: Default::default(),
+            caller_allowlist: None,
         };
         rt.expect_send(
             BLOBS_ACTOR_ADDR,
@@ -458,6 +1174,7 @@ mod tests {
             expiry: Some(epoch1),
             credit_used: Default::default(),
             gas_fee_used: Default::default(),
+            caller_allowlist: None,
         };
         rt.expect_send(
             BLOBS_ACTOR_ADDR,
@@ -514,6 +1231,7 @@ mod tests {
             expiry: Some(epoch0),
             credit_used: Default::default(),
             gas_fee_used: Default::default(),
+            caller_allowlist: None,
         };
         rt.expect_send(
             BLOBS_ACTOR_ADDR,
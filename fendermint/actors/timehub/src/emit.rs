@@ -0,0 +1,31 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use cid::Cid;
+use fil_actors_runtime::runtime::Runtime;
+use fil_actors_runtime::{ActorError, EventBuilder};
+use fvm_shared::event::ActorEvent;
+
+/// Indicates a leaf was appended at `index`.
+/// The root is the timehub root after the call that appended the leaf.
+pub fn leaf_appended(
+    rt: &impl Runtime,
+    index: u64,
+    leaf: Cid,
+    root: Cid,
+) -> Result<(), ActorError> {
+    rt.emit_event(&leaf_appended_event(index, leaf, root)?)
+}
+
+pub(crate) fn leaf_appended_event(
+    index: u64,
+    leaf: Cid,
+    root: Cid,
+) -> Result<ActorEvent, ActorError> {
+    EventBuilder::new()
+        .typ("leaf-appended")
+        .field_indexed("index", &index)
+        .field("leaf", &leaf)
+        .field("root", &root)
+        .build()
+}
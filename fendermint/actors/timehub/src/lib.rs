@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 mod actor;
+mod emit;
 mod shared;
 mod sol_facade;
 
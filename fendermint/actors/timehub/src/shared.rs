@@ -7,19 +7,26 @@ use std::collections::HashMap;
 use cid::multihash::{Code, MultihashDigest};
 use cid::Cid;
 use fendermint_actor_machine::{
-    Kind, MachineAddress, MachineState, GET_ADDRESS_METHOD, GET_METADATA_METHOD, INIT_METHOD,
-    METHOD_CONSTRUCTOR,
+    Kind, MachineAddress, MachineState, WritersState, ACCEPT_OWNERSHIP_METHOD, ADD_WRITER_METHOD,
+    FREEZE_METHOD, GET_ADDRESS_METHOD, GET_METADATA_METHOD, INIT_METHOD, LIST_WRITERS_METHOD,
+    METHOD_CONSTRUCTOR, REMOVE_WRITER_METHOD, TRANSFER_OWNERSHIP_METHOD, UNFREEZE_METHOD,
 };
 use fil_actors_runtime::ActorError;
 use fvm_ipld_amt::Amt;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::{strict_bytes, to_vec, tuple::*, CborStore, DAG_CBOR};
 use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
 use num_derive::FromPrimitive;
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_bytes::ByteBuf;
 
 pub const TIMEHUB_ACTOR_NAME: &str = "timehub";
 const BIT_WIDTH: u32 = 3;
+/// Default maximum size in bytes of an encoded leaf.
+pub const DEFAULT_MAX_LEAF_SIZE: u64 = 1024;
+/// Maximum number of leaves returned by a single `GetLeaves` call.
+pub const MAX_LEAVES_PER_CALL: u64 = 1000;
 
 fn state_error(e: fvm_ipld_amt::Error) -> ActorError {
     ActorError::illegal_state(e.to_string())
@@ -36,11 +43,39 @@ pub enum Method {
     Init = INIT_METHOD,
     GetAddress = GET_ADDRESS_METHOD,
     GetMetadata = GET_METADATA_METHOD,
+    AddWriter = ADD_WRITER_METHOD,
+    RemoveWriter = REMOVE_WRITER_METHOD,
+    ListWriters = LIST_WRITERS_METHOD,
+    TransferOwnership = TRANSFER_OWNERSHIP_METHOD,
+    AcceptOwnership = ACCEPT_OWNERSHIP_METHOD,
+    Freeze = FREEZE_METHOD,
+    Unfreeze = UNFREEZE_METHOD,
     Push = frc42_dispatch::method_hash!("Push"),
     Get = frc42_dispatch::method_hash!("Get"),
     Root = frc42_dispatch::method_hash!("Root"),
     Peaks = frc42_dispatch::method_hash!("Peaks"),
     Count = frc42_dispatch::method_hash!("Count"),
+    GetProof = frc42_dispatch::method_hash!("GetProof"),
+    GetLeaves = frc42_dispatch::method_hash!("GetLeaves"),
+    PushMany = frc42_dispatch::method_hash!("PushMany"),
+    Prune = frc42_dispatch::method_hash!("Prune"),
+    GetRootAt = frc42_dispatch::method_hash!("GetRootAt"),
+}
+
+/// Params for creating a timehub.
+/// The limits are optional so that generic machine constructor params are also accepted.
+#[derive(Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct ConstructorParams {
+    /// The machine owner ID address.
+    pub owner: Address,
+    /// User-defined metadata.
+    pub metadata: HashMap<String, String>,
+    /// Maximum size in bytes of an encoded leaf. Defaults to [`DEFAULT_MAX_LEAF_SIZE`].
+    #[serde(default)]
+    pub max_leaf_size: Option<u64>,
+    /// Maximum number of leaves the timehub can hold. Unlimited if not set.
+    #[serde(default)]
+    pub max_leaf_count: Option<u64>,
 }
 
 #[derive(Serialize_tuple, Deserialize_tuple)]
@@ -60,6 +95,30 @@ pub struct PushReturn {
     pub index: u64,
 }
 
+#[derive(Serialize_tuple, Deserialize_tuple)]
+pub struct GetLeavesParams {
+    /// Index of the first leaf to return.
+    pub start: u64,
+    /// Maximum number of leaves to return, capped at [`MAX_LEAVES_PER_CALL`].
+    pub limit: u64,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+pub struct PushManyParams {
+    /// Bytes of the CIDs to add, in order.
+    pub cids: Vec<ByteBuf>,
+    /// Account address that initiated the call.
+    pub from: Address,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+pub struct PushManyReturn {
+    /// The new root of the timehub MMR after the objects were pushed into it.
+    pub root: Cid,
+    /// The index of the first object that was just pushed into the timehub.
+    pub start_index: u64,
+}
+
 #[derive(Debug, Serialize_tuple, Deserialize_tuple)]
 pub struct Leaf {
     /// Timestamp of the witness in seconds since the UNIX epoch
@@ -68,6 +127,61 @@ pub struct Leaf {
     pub witnessed: Cid,
 }
 
+/// A proof that a leaf is included in the timehub MMR.
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct InclusionProof {
+    /// Index of the leaf.
+    pub index: u64,
+    /// Number of leaves in the MMR when the proof was made.
+    pub leaf_count: u64,
+    /// CID of the leaf block.
+    pub leaf: Cid,
+    /// Sibling hashes on the path from the leaf up to its peak, starting next to the leaf.
+    pub siblings: Vec<Cid>,
+    /// All peaks of the MMR, in the order they are bagged into the root.
+    pub peaks: Vec<Cid>,
+}
+
+/// Compute the CID of a leaf block for the given object.
+/// Clients can use this to derive the leaf of an [`InclusionProof`] from a pushed object.
+pub fn leaf_cid<S: Serialize>(obj: &S) -> anyhow::Result<Cid, ActorError> {
+    let data = to_vec(obj)?;
+    let mh = Code::Blake2b256.digest(&data);
+    Ok(Cid::new_v1(DAG_CBOR, mh))
+}
+
+/// Verify that a proof is consistent and leads to the given root.
+///
+/// This only uses hashing, so it can be run by light clients that don't have access to
+/// the blockstore.
+pub fn verify_proof(root: &Cid, proof: &InclusionProof) -> anyhow::Result<bool, ActorError> {
+    let (path, eigen_index) = match path_for_eigen_root(proof.index, proof.leaf_count)
+        .map_err(|e| ActorError::illegal_argument(e.to_string()))?
+    {
+        None => return Ok(false),
+        Some(res) => res,
+    };
+    let height = u64::BITS - path.leading_zeros() - 1;
+    if proof.siblings.len() != height as usize
+        || proof.peaks.len() != proof.leaf_count.count_ones() as usize
+    {
+        return Ok(false);
+    }
+    // Walk up from the leaf, where each bit of the path tells whether the node is a right child
+    let mut node = proof.leaf;
+    for (i, sibling) in proof.siblings.iter().enumerate() {
+        node = if (path >> i) & 1 == 1 {
+            hash_pair(Some(sibling), Some(&node))?
+        } else {
+            hash_pair(Some(&node), Some(sibling))?
+        };
+    }
+    if proof.peaks[eigen_index as usize] != node {
+        return Ok(false);
+    }
+    Ok(&bag_peak_cids(&proof.peaks)? == root)
+}
+
 /// Compute the hash of a pair of CIDs.
 /// The hash is the CID of a new block containing the concatenation of the two CIDs.
 /// We do not include the index of the element(s) because incoming data should already be "nonced".
@@ -107,19 +221,136 @@ fn hash_and_put_pair<BS: Blockstore>(
     }
 }
 
-/// Return the new peaks of the timehub after adding `new_leaf`.
+/// Reference to a node of the MMR, which may have some or all of its leaves pruned.
+///
+/// Pruned subtrees are only referenced by the bytes of their hash, not by a CID link,
+/// so their blocks are no longer reachable from the actor state.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum NodeRef {
+    /// A node stored as a block under its hash, with none of its leaves pruned.
+    Full(Cid),
+    /// A node with all of its leaves pruned.
+    Pruned(ByteBuf),
+    /// A node with some of its leaves pruned, along with a link to its [`PartialNode`].
+    Partial(ByteBuf, Cid),
+}
+
+/// The children of a node with some of its leaves pruned.
+///
+/// The block stored under the hash of such a node would link to the pruned children,
+/// so the children are stored separately.
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct PartialNode {
+    left: NodeRef,
+    right: NodeRef,
+}
+
+impl NodeRef {
+    fn pruned(hash: Cid) -> Self {
+        Self::Pruned(ByteBuf::from(hash.to_bytes()))
+    }
+
+    /// The hash of the node, which is the CID of the block it would be stored under.
+    fn hash(&self) -> anyhow::Result<Cid, ActorError> {
+        match self {
+            Self::Full(cid) => Ok(*cid),
+            Self::Pruned(bytes) | Self::Partial(bytes, _) => Cid::try_from(bytes.as_slice())
+                .map_err(|e| ActorError::illegal_state(e.to_string())),
+        }
+    }
+
+    /// Returns the children of an eigentree node, or `None` if the node has been pruned.
+    fn children<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<Option<(NodeRef, NodeRef)>> {
+        match self {
+            Self::Full(cid) => {
+                let [left, right] = store.get_cbor::<[Cid; 2]>(cid)?.ok_or_else(|| {
+                    anyhow::anyhow!("failed to get eigentree node for cid {}", cid)
+                })?;
+                Ok(Some((Self::Full(left), Self::Full(right))))
+            }
+            Self::Pruned(_) => Ok(None),
+            Self::Partial(_, cid) => {
+                let node = store
+                    .get_cbor::<PartialNode>(cid)?
+                    .ok_or_else(|| anyhow::anyhow!("failed to get partial node for cid {}", cid))?;
+                Ok(Some((node.left, node.right)))
+            }
+        }
+    }
+}
+
+/// Combine two sibling nodes into their parent.
+fn merge<BS: Blockstore>(
+    store: &BS,
+    left: Option<NodeRef>,
+    right: Option<NodeRef>,
+) -> anyhow::Result<NodeRef, ActorError> {
+    let (left, right) = match (left, right) {
+        (Some(NodeRef::Full(left)), Some(NodeRef::Full(right))) => {
+            return Ok(NodeRef::Full(hash_and_put_pair(
+                store,
+                Some(&left),
+                Some(&right),
+            )?))
+        }
+        (Some(left), Some(right)) => (left, right),
+        _ => {
+            return Err(ActorError::illegal_argument(
+                "hash_pair requires two CIDs".into(),
+            ))
+        }
+    };
+    let hash = hash_pair(Some(&left.hash()?), Some(&right.hash()?))?;
+    if let (NodeRef::Pruned(_), NodeRef::Pruned(_)) = (&left, &right) {
+        return Ok(NodeRef::pruned(hash));
+    }
+    let node = store
+        .put_cbor(&PartialNode { left, right }, Code::Blake2b256)
+        .map_err(store_error)?;
+    Ok(NodeRef::Partial(ByteBuf::from(hash.to_bytes()), node))
+}
+
+/// Replace the references to the leaves below `pruned_count` in a subtree with their hashes.
+///
+/// The subtree holds `size` leaves, starting at index `start`.
+fn prune_node<BS: Blockstore>(
+    store: &BS,
+    node: NodeRef,
+    start: u64,
+    size: u64,
+    pruned_count: u64,
+) -> anyhow::Result<NodeRef, ActorError> {
+    if start >= pruned_count {
+        return Ok(node);
+    }
+    if start + size <= pruned_count {
+        return Ok(NodeRef::pruned(node.hash()?));
+    }
+    let (left, right) = node
+        .children(store)
+        .map_err(store_error)?
+        .ok_or_else(|| ActorError::illegal_state("partially pruned node has no children".into()))?;
+    let half = size / 2;
+    let left = prune_node(store, left, start, half, pruned_count)?;
+    let right = prune_node(store, right, start + half, half, pruned_count)?;
+    merge(store, Some(left), Some(right))
+}
+
+/// Add `obj` as a new leaf to the peaks of the timehub without flushing them.
 fn push<BS: Blockstore, S: DeserializeOwned + Serialize>(
     store: &BS,
     leaf_count: u64,
-    peaks: &mut Amt<Cid, &BS>,
+    peaks: &mut Amt<NodeRef, &BS>,
     obj: S,
-) -> anyhow::Result<Cid, ActorError> {
+) -> anyhow::Result<(), ActorError> {
     // Create new leaf
     let leaf = store
         .put_cbor(&obj, Code::Blake2b256)
         .map_err(store_error)?;
     // Push the new leaf onto the peaks
-    peaks.set(peaks.count(), leaf).map_err(state_error)?;
+    peaks
+        .set(peaks.count(), NodeRef::Full(leaf))
+        .map_err(state_error)?;
     // Count trailing ones in binary representation of the previous leaf_count
     // This works because adding a leaf fills the next available spot,
     // and the binary representation of this index will have trailing ones
@@ -131,37 +362,45 @@ fn push<BS: Blockstore, S: DeserializeOwned + Serialize>(
         let left = peaks.delete(peaks.count() - 1).map_err(state_error)?;
         // Push the new peak onto the peak array
         peaks
-            .set(
-                peaks.count(),
-                hash_and_put_pair(store, left.as_ref(), right.as_ref())?,
-            )
+            .set(peaks.count(), merge(store, left, right)?)
             .map_err(state_error)?;
         new_peaks -= 1;
     }
-    peaks.flush().map_err(state_error)
+    Ok(())
 }
 
 /// Collect the peaks and combine to compute the root commitment.
-fn bag_peaks<BS: Blockstore>(peaks: &Amt<Cid, &BS>) -> anyhow::Result<Cid, ActorError> {
-    let peaks_count = peaks.count();
+fn bag_peaks<BS: Blockstore>(peaks: &Amt<NodeRef, &BS>) -> anyhow::Result<Cid, ActorError> {
+    bag_peak_cids(&peak_hashes(peaks)?)
+}
+
+/// Collect the hashes of the peaks, in order.
+fn peak_hashes<BS: Blockstore>(peaks: &Amt<NodeRef, &BS>) -> anyhow::Result<Vec<Cid>, ActorError> {
+    let mut nodes = Vec::with_capacity(peaks.count() as usize);
+    peaks
+        .for_each(|_, node| {
+            nodes.push(node.clone());
+            Ok(())
+        })
+        .map_err(state_error)?;
+    nodes.iter().map(NodeRef::hash).collect()
+}
+
+/// Combine the peaks to compute the root commitment.
+fn bag_peak_cids(peaks: &[Cid]) -> anyhow::Result<Cid, ActorError> {
+    let peaks_count = peaks.len();
     // Handle special cases where we have no peaks or only one peak
     if peaks_count == 0 {
         return Ok(Cid::default());
     }
     // If there is only one leaf element, we simply "promote" that to the root peak
     if peaks_count == 1 {
-        return Ok(peaks.get(0).map_err(state_error)?.unwrap().to_owned());
+        return Ok(peaks[0]);
     }
     // Walk backward through the peaks, combining them pairwise
-    let mut root = hash_pair(
-        peaks.get(peaks_count - 2).map_err(state_error)?,
-        peaks.get(peaks_count - 1).map_err(state_error)?,
-    )?;
+    let mut root = hash_pair(Some(&peaks[peaks_count - 2]), Some(&peaks[peaks_count - 1]))?;
     for i in 2..peaks_count {
-        root = hash_pair(
-            peaks.get(peaks_count - 1 - i).map_err(state_error)?,
-            Some(&root),
-        )?;
+        root = hash_pair(Some(&peaks[peaks_count - 1 - i]), Some(&root))?;
     }
     Ok(root)
 }
@@ -197,55 +436,70 @@ fn path_for_eigen_root(leaf_index: u64, leaf_count: u64) -> anyhow::Result<Optio
     Ok(Some((local_path, eigen_index as u64)))
 }
 
-/// Returns None when the index doesn't point to a leaf.
+/// Returns None when the index doesn't point to a leaf, or the leaf has been pruned.
 /// If the index is valid, it will return a value or error.
 fn get_at<BS: Blockstore, S: DeserializeOwned + Serialize>(
     store: &BS,
     leaf_index: u64,
     leaf_count: u64,
-    peaks: &Amt<Cid, &BS>,
+    peaks: &Amt<NodeRef, &BS>,
 ) -> anyhow::Result<Option<S>> {
+    let cid = match proof_at(store, leaf_index, leaf_count, peaks)? {
+        None => return Ok(None),
+        Some((cid, _)) => cid,
+    };
+    let leaf = store
+        .get_cbor::<S>(&cid)?
+        .ok_or_else(|| anyhow::anyhow!("failed to get leaf for cid {}", cid))?;
+    Ok(Some(leaf))
+}
+
+/// Returns None when the index doesn't point to a leaf, or the leaf has been pruned.
+/// Otherwise, returns the leaf CID and the sibling hashes on the path up to its peak,
+/// starting next to the leaf.
+fn proof_at<BS: Blockstore>(
+    store: &BS,
+    leaf_index: u64,
+    leaf_count: u64,
+    peaks: &Amt<NodeRef, &BS>,
+) -> anyhow::Result<Option<(Cid, Vec<Cid>)>> {
     let (path, eigen_index) = match path_for_eigen_root(leaf_index, leaf_count)? {
         None => return Ok(None),
         Some(res) => res,
     };
-    let cid = match peaks.get(eigen_index)? {
-        Some(cid) => cid,
+    let mut node = match peaks.get(eigen_index)? {
+        Some(node) => node.clone(),
         None => return Ok(None),
     };
-    // Special case where eigentree has a height of one
-    if path == 1 {
-        return Ok(Some(store.get_cbor::<S>(cid)?.ok_or_else(|| {
-            anyhow::anyhow!("failed to get leaf for cid {}", cid)
-        })?));
+    let significant_bits = 64 - path.leading_zeros();
+    let mut siblings = Vec::with_capacity(significant_bits as usize - 1);
+    // Walk down from the peak, following each bit of the path below the leading one
+    for i in 1..significant_bits {
+        let (left, right) = match node.children(store)? {
+            None => return Ok(None),
+            Some(children) => children,
+        };
+        let bit = (path >> (significant_bits - i - 1)) & 1;
+        let (next, sibling) = if bit == 1 {
+            (right, left)
+        } else {
+            (left, right)
+        };
+        siblings.push(sibling.hash()?);
+        node = next;
     }
-
-    let mut pair = match store.get_cbor::<[Cid; 2]>(cid)? {
-        Some(value) => value,
-        None => anyhow::bail!("failed to get eigentree root node for cid {}", cid),
-    };
-
-    let leading_zeros = path.leading_zeros();
-    let significant_bits = 64 - leading_zeros;
-
-    // Iterate over each bit from the most significant bit to the least
-    for i in 1..(significant_bits - 1) {
-        let bit = ((path >> (significant_bits - i - 1)) & 1) as usize;
-        let cid = &pair[bit];
-        pair = store.get_cbor(cid)?.ok_or_else(|| {
-            anyhow::anyhow!("failed to get eigentree intermediate node for cid {}", cid)
-        })?;
+    siblings.reverse();
+    match node {
+        NodeRef::Full(leaf) => Ok(Some((leaf, siblings))),
+        _ => Ok(None),
     }
-
-    let bit = (path & 1) as usize;
-    let cid = &pair[bit];
-    let leaf = store
-        .get_cbor::<S>(cid)?
-        .ok_or_else(|| anyhow::anyhow!("failed to get leaf for cid {}", cid))?;
-
-    Ok(Some(leaf))
 }
 
+// Raw root record persisted in the store, as the epoch, the bytes of the root CID,
+// and the number of leaves the root commits to.
+// The root is not stored as a block, so it can't be persisted as a CID link.
+type RawRootRecord = (ChainEpoch, Vec<u8>, u64);
+
 /// The state represents an MMR with peaks stored in an AMT
 #[derive(Serialize_tuple, Deserialize_tuple)]
 pub struct State {
@@ -253,10 +507,27 @@ pub struct State {
     pub address: MachineAddress,
     /// The machine rubust owner address.
     pub owner: Address,
+    /// The proposed owner, until they accept the transfer.
+    pub pending_owner: Option<Address>,
     /// Root of the AMT that is storing the peaks of the MMR
     pub peaks: Cid,
     /// Number of leaf nodes in the timehub MMR.
     pub leaf_count: u64,
+    /// Number of leading leaves that have been pruned.
+    /// Pruned leaves can no longer be read or proven, but still count toward the root.
+    pub pruned_count: u64,
+    /// Maximum size in bytes of an encoded leaf.
+    pub max_leaf_size: u64,
+    /// Maximum number of leaves the timehub can hold, if any.
+    pub max_leaf_count: Option<u64>,
+    /// Root of the AMT storing the root at the end of each epoch with pushes, in epoch order.
+    pub root_history: Cid,
+    /// Index of the oldest root record which hasn't been pruned.
+    pub root_history_start: u64,
+    /// Addresses that can push in addition to the owner.
+    pub writers: WritersState,
+    /// Whether the machine is read-only.
+    pub frozen: bool,
     /// User-defined metadata.
     pub metadata: HashMap<String, String>,
 }
@@ -276,11 +547,22 @@ impl MachineState for State {
                 )));
             }
         };
+        let root_history = Amt::<(), _>::new_with_bit_width(store, BIT_WIDTH)
+            .flush()
+            .map_err(state_error)?;
         Ok(Self {
             address: Default::default(),
             owner,
+            pending_owner: None,
             peaks,
             leaf_count: 0,
+            pruned_count: 0,
+            max_leaf_size: DEFAULT_MAX_LEAF_SIZE,
+            max_leaf_count: None,
+            root_history,
+            root_history_start: 0,
+            writers: WritersState::new(store)?,
+            frozen: false,
             metadata,
         })
     }
@@ -304,6 +586,34 @@ impl MachineState for State {
     fn metadata(&self) -> HashMap<String, String> {
         self.metadata.clone()
     }
+
+    fn writers(&self) -> &WritersState {
+        &self.writers
+    }
+
+    fn writers_mut(&mut self) -> &mut WritersState {
+        &mut self.writers
+    }
+
+    fn set_owner(&mut self, owner: Address) {
+        self.owner = owner;
+    }
+
+    fn pending_owner(&self) -> Option<Address> {
+        self.pending_owner
+    }
+
+    fn set_pending_owner(&mut self, owner: Option<Address>) {
+        self.pending_owner = owner;
+    }
+
+    fn frozen(&self) -> bool {
+        self.frozen
+    }
+
+    fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen;
+    }
 }
 
 impl State {
@@ -315,13 +625,62 @@ impl State {
         self.leaf_count
     }
 
+    pub fn pruned_count(&self) -> u64 {
+        self.pruned_count
+    }
+
+    /// Sets the leaf size and leaf count limits.
+    pub fn set_limits(
+        &mut self,
+        max_leaf_size: Option<u64>,
+        max_leaf_count: Option<u64>,
+    ) -> anyhow::Result<(), ActorError> {
+        if max_leaf_size == Some(0) {
+            return Err(ActorError::illegal_argument(
+                "max leaf size must be positive".into(),
+            ));
+        }
+        if max_leaf_count == Some(0) {
+            return Err(ActorError::illegal_argument(
+                "max leaf count must be positive".into(),
+            ));
+        }
+        self.max_leaf_size = max_leaf_size.unwrap_or(DEFAULT_MAX_LEAF_SIZE);
+        self.max_leaf_count = max_leaf_count;
+        Ok(())
+    }
+
+    /// Returns an error if the objects can't be pushed without exceeding the limits.
+    fn ensure_within_limits<S: Serialize>(&self, objs: &[S]) -> anyhow::Result<(), ActorError> {
+        if let Some(max) = self.max_leaf_count {
+            if self.leaf_count.saturating_add(objs.len() as u64) > max {
+                return Err(ActorError::illegal_argument(format!(
+                    "timehub is limited to {} leaves",
+                    max
+                )));
+            }
+        }
+        for obj in objs {
+            let size = to_vec(obj)?.len() as u64;
+            if size > self.max_leaf_size {
+                return Err(ActorError::illegal_argument(format!(
+                    "leaf size {} exceeds maximum allowed {}",
+                    size, self.max_leaf_size
+                )));
+            }
+        }
+        Ok(())
+    }
+
     pub fn push<BS: Blockstore, S: DeserializeOwned + Serialize>(
         &mut self,
         store: &BS,
         obj: S,
     ) -> anyhow::Result<PushReturn, ActorError> {
-        let mut amt = Amt::<Cid, &BS>::load(&self.peaks, store).map_err(state_error)?;
-        self.peaks = push(store, self.leaf_count, &mut amt, obj)?;
+        self.ensure_within_limits(std::slice::from_ref(&obj))?;
+        let mut amt = Amt::<NodeRef, &BS>::load(&self.peaks, store).map_err(state_error)?;
+        push(store, self.leaf_count, &mut amt, obj)?;
+        self.peaks = amt.flush().map_err(state_error)?;
         self.leaf_count += 1;
 
         let root = bag_peaks(&amt)?;
@@ -331,20 +690,33 @@ impl State {
         })
     }
 
+    /// Pushes the objects in order, flushing the peaks once at the end.
+    pub fn push_many<BS: Blockstore, S: DeserializeOwned + Serialize>(
+        &mut self,
+        store: &BS,
+        objs: Vec<S>,
+    ) -> anyhow::Result<PushManyReturn, ActorError> {
+        self.ensure_within_limits(&objs)?;
+        let mut amt = Amt::<NodeRef, &BS>::load(&self.peaks, store).map_err(state_error)?;
+        let start_index = self.leaf_count;
+        for obj in objs {
+            push(store, self.leaf_count, &mut amt, obj)?;
+            self.leaf_count += 1;
+        }
+        self.peaks = amt.flush().map_err(state_error)?;
+
+        let root = bag_peaks(&amt)?;
+        Ok(PushManyReturn { root, start_index })
+    }
+
     pub fn get_root<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<Cid, ActorError> {
-        let amt = Amt::<Cid, &BS>::load(&self.peaks, store).map_err(state_error)?;
+        let amt = Amt::<NodeRef, &BS>::load(&self.peaks, store).map_err(state_error)?;
         bag_peaks(&amt)
     }
 
     pub fn get_peaks<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<Vec<Cid>, ActorError> {
-        let amt = Amt::<Cid, &BS>::load(&self.peaks, store).map_err(state_error)?;
-        let mut peaks = Vec::new();
-        amt.for_each(|_, cid| {
-            peaks.push(cid.to_owned());
-            Ok(())
-        })
-        .map_err(state_error)?;
-        Ok(peaks)
+        let amt = Amt::<NodeRef, &BS>::load(&self.peaks, store).map_err(state_error)?;
+        peak_hashes(&amt)
     }
 
     pub fn get_leaf_at<BS: Blockstore, S: DeserializeOwned + Serialize>(
@@ -352,15 +724,233 @@ impl State {
         store: &BS,
         index: u64,
     ) -> anyhow::Result<Option<S>, ActorError> {
-        let amt = Amt::<Cid, &BS>::load(&self.peaks, store).map_err(state_error)?;
+        if index < self.pruned_count {
+            return Ok(None);
+        }
+        let amt = Amt::<NodeRef, &BS>::load(&self.peaks, store).map_err(state_error)?;
         get_at::<BS, S>(store, index, self.leaf_count, &amt)
             .map_err(|e| ActorError::serialization(e.to_string()))
     }
+
+    /// Returns up to `limit` leaves in index order, starting at `start`.
+    ///
+    /// The limit is capped at [`MAX_LEAVES_PER_CALL`], so a single call can't run out of gas.
+    pub fn get_leaves<BS: Blockstore, S: DeserializeOwned + Serialize>(
+        &self,
+        store: &BS,
+        start: u64,
+        limit: u64,
+    ) -> anyhow::Result<Vec<S>, ActorError> {
+        if start < self.pruned_count {
+            return Err(ActorError::illegal_argument(format!(
+                "leaves below index {} have been pruned",
+                self.pruned_count
+            )));
+        }
+        let amt = Amt::<NodeRef, &BS>::load(&self.peaks, store).map_err(state_error)?;
+        let limit = limit.min(MAX_LEAVES_PER_CALL);
+        let end = start.saturating_add(limit).min(self.leaf_count);
+        let mut leaves = Vec::with_capacity(end.saturating_sub(start) as usize);
+        for index in start..end {
+            let leaf = get_at::<BS, S>(store, index, self.leaf_count, &amt)
+                .map_err(|e| ActorError::serialization(e.to_string()))?
+                .ok_or_else(|| ActorError::illegal_state(format!("leaf {} not found", index)))?;
+            leaves.push(leaf);
+        }
+        Ok(leaves)
+    }
+
+    /// Returns a proof that the leaf at the given index is included in the current root.
+    pub fn get_proof<BS: Blockstore>(
+        &self,
+        store: &BS,
+        index: u64,
+    ) -> anyhow::Result<Option<InclusionProof>, ActorError> {
+        if index < self.pruned_count {
+            return Ok(None);
+        }
+        let amt = Amt::<NodeRef, &BS>::load(&self.peaks, store).map_err(state_error)?;
+        let (leaf, siblings) = match proof_at(store, index, self.leaf_count, &amt)
+            .map_err(|e| ActorError::serialization(e.to_string()))?
+        {
+            None => return Ok(None),
+            Some(res) => res,
+        };
+        Ok(Some(InclusionProof {
+            index,
+            leaf_count: self.leaf_count,
+            leaf,
+            siblings,
+            peaks: self.get_peaks(store)?,
+        }))
+    }
+
+    /// Records the root as of the given epoch, committing to the current leaves.
+    /// A later record for the same epoch replaces the earlier one.
+    pub fn record_root<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        epoch: ChainEpoch,
+        root: Cid,
+    ) -> anyhow::Result<(), ActorError> {
+        let mut amt =
+            Amt::<RawRootRecord, &BS>::load(&self.root_history, store).map_err(state_error)?;
+        let end = self.root_history_start + amt.count();
+        let index = if amt.count() == 0 {
+            end
+        } else {
+            let last = end - 1;
+            let (last_epoch, _, _) = amt
+                .get(last)
+                .map_err(state_error)?
+                .ok_or_else(|| ActorError::illegal_state("root record missing".into()))?;
+            if *last_epoch > epoch {
+                return Err(ActorError::illegal_state(format!(
+                    "root recorded at epoch {} is newer than epoch {}",
+                    last_epoch, epoch
+                )));
+            }
+            if *last_epoch == epoch {
+                last
+            } else {
+                end
+            }
+        };
+        amt.set(index, (epoch, root.to_bytes(), self.leaf_count))
+            .map_err(state_error)?;
+        self.root_history = amt.flush().map_err(state_error)?;
+        Ok(())
+    }
+
+    /// Returns the root as of the end of the given epoch, or `None` if nothing was pushed
+    /// at or before it, or the roots of that time have been pruned.
+    pub fn get_root_at<BS: Blockstore>(
+        &self,
+        store: &BS,
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<Option<Cid>, ActorError> {
+        let amt =
+            Amt::<RawRootRecord, &BS>::load(&self.root_history, store).map_err(state_error)?;
+        let record_epoch = |index: u64| -> anyhow::Result<ChainEpoch, ActorError> {
+            amt.get(index)
+                .map_err(state_error)?
+                .map(|(epoch, _, _)| *epoch)
+                .ok_or_else(|| ActorError::illegal_state("root record missing".into()))
+        };
+        // Binary search for the number of records at or before the epoch
+        let (mut lo, mut hi) = (
+            self.root_history_start,
+            self.root_history_start + amt.count(),
+        );
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if record_epoch(mid)? <= epoch {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        if lo == self.root_history_start {
+            return Ok(None);
+        }
+        let (_, bytes, _) = amt
+            .get(lo - 1)
+            .map_err(state_error)?
+            .ok_or_else(|| ActorError::illegal_state("root record missing".into()))?;
+        let root = Cid::try_from(bytes.as_slice())
+            .map_err(|e| ActorError::illegal_state(e.to_string()))?;
+        Ok(Some(root))
+    }
+
+    /// Prunes all leaves below `up_to_index`, raising the pruned watermark.
+    ///
+    /// The root is not affected, so proofs for retained leaves keep verifying. The pruned
+    /// subtrees are only referenced by their hashes from then on, so their blocks are no longer
+    /// reachable from the actor state. Recorded roots which only commit to pruned leaves are
+    /// dropped as well, except for the latest one.
+    pub fn prune<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        up_to_index: u64,
+    ) -> anyhow::Result<u64, ActorError> {
+        if up_to_index <= self.pruned_count {
+            return Err(ActorError::illegal_argument(format!(
+                "prune index {} must be greater than the current watermark {}",
+                up_to_index, self.pruned_count
+            )));
+        }
+        if up_to_index > self.leaf_count {
+            return Err(ActorError::illegal_argument(format!(
+                "prune index {} exceeds leaf count {}",
+                up_to_index, self.leaf_count
+            )));
+        }
+
+        // The eigentrees are ordered from the oldest and largest to the newest and smallest.
+        let mut amt = Amt::<NodeRef, &BS>::load(&self.peaks, store).map_err(state_error)?;
+        let mut start = 0;
+        let mut eigen_index = 0;
+        for height in (0..u64::BITS).rev() {
+            let size = 1u64 << height;
+            if self.leaf_count & size == 0 {
+                continue;
+            }
+            if start >= up_to_index {
+                break;
+            }
+            // Eigentrees which were already fully pruned don't need to be visited.
+            if start + size > self.pruned_count {
+                let node = amt
+                    .get(eigen_index)
+                    .map_err(state_error)?
+                    .cloned()
+                    .ok_or_else(|| ActorError::illegal_state("peak missing".into()))?;
+                let node = prune_node(store, node, start, size, up_to_index)?;
+                amt.set(eigen_index, node).map_err(state_error)?;
+            }
+            start += size;
+            eigen_index += 1;
+        }
+        self.peaks = amt.flush().map_err(state_error)?;
+
+        self.prune_root_history(store, up_to_index)?;
+        self.pruned_count = up_to_index;
+        Ok(self.pruned_count)
+    }
+
+    /// Drops the recorded roots which only commit to leaves below `pruned_count`,
+    /// keeping the latest record.
+    fn prune_root_history<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        pruned_count: u64,
+    ) -> anyhow::Result<(), ActorError> {
+        let mut amt =
+            Amt::<RawRootRecord, &BS>::load(&self.root_history, store).map_err(state_error)?;
+        let end = self.root_history_start + amt.count();
+        while self.root_history_start + 1 < end {
+            let leaf_count = amt
+                .get(self.root_history_start)
+                .map_err(state_error)?
+                .map(|(_, _, leaf_count)| *leaf_count)
+                .ok_or_else(|| ActorError::illegal_state("root record missing".into()))?;
+            if leaf_count > pruned_count {
+                break;
+            }
+            amt.delete(self.root_history_start).map_err(state_error)?;
+            self.root_history_start += 1;
+        }
+        self.root_history = amt.flush().map_err(state_error)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fvm_ipld_encoding::from_slice;
+    use fvm_shared::error::ExitCode;
+    use std::collections::HashSet;
     use std::str::FromStr;
 
     #[test]
@@ -527,4 +1117,327 @@ mod tests {
         }
         assert_eq!(state.peak_count(), 5);
     }
+
+    #[test]
+    fn test_push_many() {
+        let store = fvm_ipld_blockstore::MemoryBlockstore::default();
+        let mut single = State::new(&store, Address::new_id(100), HashMap::new()).unwrap();
+        let mut batched = State::new(&store, Address::new_id(100), HashMap::new()).unwrap();
+        for i in 0..3u64 {
+            single.push(&store, vec![i]).unwrap();
+        }
+        batched.push(&store, vec![0u64]).unwrap();
+
+        // A batch ends up in the same state as pushing one by one
+        for i in 3..11u64 {
+            single.push(&store, vec![i]).unwrap();
+        }
+        let res = batched
+            .push_many(&store, vec![vec![1u64], vec![2]])
+            .unwrap();
+        assert_eq!(res.start_index, 1);
+        let res = batched
+            .push_many(&store, (3..11u64).map(|i| vec![i]).collect())
+            .unwrap();
+        assert_eq!(res.start_index, 3);
+        assert_eq!(res.root, single.get_root(&store).unwrap());
+        assert_eq!(batched.peaks, single.peaks);
+        assert_eq!(batched.leaf_count(), 11);
+    }
+
+    #[test]
+    fn test_get_leaves() {
+        let store = fvm_ipld_blockstore::MemoryBlockstore::default();
+        let mut state = State::new(&store, Address::new_id(100), HashMap::new()).unwrap();
+        for i in 0..11u64 {
+            state.push(&store, vec![i]).unwrap();
+        }
+
+        let leaves = state.get_leaves::<_, Vec<u64>>(&store, 3, 4).unwrap();
+        assert_eq!(leaves, vec![vec![3], vec![4], vec![5], vec![6]]);
+
+        // The range is cut off at the last leaf
+        let leaves = state.get_leaves::<_, Vec<u64>>(&store, 9, 10).unwrap();
+        assert_eq!(leaves, vec![vec![9], vec![10]]);
+        assert!(state
+            .get_leaves::<_, Vec<u64>>(&store, 11, 10)
+            .unwrap()
+            .is_empty());
+        assert!(state
+            .get_leaves::<_, Vec<u64>>(&store, 0, 0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_get_leaves_clamped() {
+        let store = fvm_ipld_blockstore::MemoryBlockstore::default();
+        let mut state = State::new(&store, Address::new_id(100), HashMap::new()).unwrap();
+        let objs = (0..MAX_LEAVES_PER_CALL + 5)
+            .map(|i| vec![i])
+            .collect::<Vec<_>>();
+        state.push_many(&store, objs).unwrap();
+
+        let leaves = state
+            .get_leaves::<_, Vec<u64>>(&store, 0, u64::MAX)
+            .unwrap();
+        assert_eq!(leaves.len() as u64, MAX_LEAVES_PER_CALL);
+        assert_eq!(leaves.last(), Some(&vec![MAX_LEAVES_PER_CALL - 1]));
+
+        // The rest can be paged through
+        let leaves = state
+            .get_leaves::<_, Vec<u64>>(&store, MAX_LEAVES_PER_CALL, u64::MAX)
+            .unwrap();
+        assert_eq!(leaves.len(), 5);
+    }
+
+    #[test]
+    fn test_get_proof() {
+        let store = fvm_ipld_blockstore::MemoryBlockstore::default();
+        let mut state = State::new(&store, Address::new_id(100), HashMap::new()).unwrap();
+        assert!(state.get_proof(&store, 0).unwrap().is_none());
+
+        for i in 0..11u64 {
+            state.push(&store, vec![i]).unwrap();
+            let root = state.get_root(&store).unwrap();
+
+            // Every leaf pushed so far can be proven against the current root
+            for j in 0..=i {
+                let proof = state.get_proof(&store, j).unwrap().unwrap();
+                assert_eq!(proof.leaf, leaf_cid(&vec![j]).unwrap());
+                assert!(verify_proof(&root, &proof).unwrap());
+            }
+            assert!(state.get_proof(&store, i + 1).unwrap().is_none());
+        }
+
+        // Tampered proofs are rejected
+        let root = state.get_root(&store).unwrap();
+        let proof = state.get_proof(&store, 5).unwrap().unwrap();
+        let wrong_leaf = InclusionProof {
+            leaf: leaf_cid(&vec![6u64]).unwrap(),
+            ..proof.clone()
+        };
+        assert!(!verify_proof(&root, &wrong_leaf).unwrap());
+        let wrong_index = InclusionProof {
+            index: 4,
+            ..proof.clone()
+        };
+        assert!(!verify_proof(&root, &wrong_index).unwrap());
+        let mut missing_sibling = proof.clone();
+        missing_sibling.siblings.pop();
+        assert!(!verify_proof(&root, &missing_sibling).unwrap());
+        assert!(!verify_proof(&leaf_cid(&vec![0u64]).unwrap(), &proof).unwrap());
+    }
+
+    #[test]
+    fn test_prune() {
+        let store = fvm_ipld_blockstore::MemoryBlockstore::default();
+        let mut state = State::new(&store, Address::new_id(100), HashMap::new()).unwrap();
+        for i in 0..11u64 {
+            state.push(&store, vec![i]).unwrap();
+        }
+        let root = state.get_root(&store).unwrap();
+
+        assert_eq!(state.prune(&store, 4).unwrap(), 4);
+        assert_eq!(state.pruned_count(), 4);
+        assert_eq!(state.get_root(&store).unwrap(), root);
+
+        // Pruned leaves are no longer served
+        assert!(state
+            .get_leaf_at::<_, Vec<u64>>(&store, 3)
+            .unwrap()
+            .is_none());
+        assert!(state.get_proof(&store, 3).unwrap().is_none());
+        assert!(state.get_leaves::<_, Vec<u64>>(&store, 2, 4).is_err());
+
+        // Retained leaves can still be read and proven
+        let leaves = state.get_leaves::<_, Vec<u64>>(&store, 4, 2).unwrap();
+        assert_eq!(leaves, vec![vec![4], vec![5]]);
+        let proof = state.get_proof(&store, 4).unwrap().unwrap();
+        assert!(verify_proof(&root, &proof).unwrap());
+
+        // The watermark only moves forward and stays within the leaves
+        assert!(state.prune(&store, 4).is_err());
+        assert!(state.prune(&store, 12).is_err());
+        assert_eq!(state.prune(&store, 11).unwrap(), 11);
+
+        // New leaves can be pushed and read after pruning
+        let res = state.push(&store, vec![11u64]).unwrap();
+        assert_eq!(res.index, 11);
+        assert_eq!(
+            state.get_leaf_at::<_, Vec<u64>>(&store, 11).unwrap(),
+            Some(vec![11])
+        );
+    }
+
+    /// Collects the blocks reachable from the state, which are the ones the FVM keeps
+    /// when it flushes the state of the actor.
+    fn reachable<BS: Blockstore>(store: &BS, state: &State) -> HashSet<Cid> {
+        fn links(ipld: libipld::Ipld, queue: &mut Vec<Cid>) {
+            match ipld {
+                libipld::Ipld::List(items) => items.into_iter().for_each(|i| links(i, queue)),
+                libipld::Ipld::Map(map) => map.into_values().for_each(|v| links(v, queue)),
+                libipld::Ipld::Link(cid) => queue.push(cid),
+                _ => {}
+            }
+        }
+        let mut seen = HashSet::new();
+        let mut queue = Vec::new();
+        links(from_slice(&to_vec(state).unwrap()).unwrap(), &mut queue);
+        while let Some(cid) = queue.pop() {
+            if seen.insert(cid) {
+                let bytes = store.get(&cid).unwrap().expect("reachable block is stored");
+                links(from_slice(&bytes).unwrap(), &mut queue);
+            }
+        }
+        seen
+    }
+
+    #[test]
+    fn test_prune_unlinks_leaves() {
+        let store = fvm_ipld_blockstore::MemoryBlockstore::default();
+        let mut state = State::new(&store, Address::new_id(100), HashMap::new()).unwrap();
+        let mut unpruned = State::new(&store, Address::new_id(100), HashMap::new()).unwrap();
+        let leaves = (0..20u64)
+            .map(|i| leaf_cid(&vec![i]).unwrap())
+            .collect::<Vec<_>>();
+
+        state
+            .push_many(&store, (0..11u64).map(|i| vec![i]).collect())
+            .unwrap();
+        let root = state.get_root(&store).unwrap();
+        let proof = state.get_proof(&store, 9).unwrap().unwrap();
+
+        state.prune(&store, 5).unwrap();
+        assert_eq!(state.get_root(&store).unwrap(), root);
+        assert_eq!(state.get_proof(&store, 9).unwrap().unwrap(), proof);
+
+        // Pruned leaves can't be fetched from the state anymore
+        let blocks = reachable(&store, &state);
+        for (i, leaf) in leaves.iter().enumerate().take(11) {
+            assert_eq!(blocks.contains(leaf), i >= 5, "leaf {i}");
+        }
+
+        // Merging with the pruned peaks doesn't link the pruned leaves again
+        state
+            .push_many(&store, (11..20u64).map(|i| vec![i]).collect())
+            .unwrap();
+        unpruned
+            .push_many(&store, (0..20u64).map(|i| vec![i]).collect())
+            .unwrap();
+        let root = state.get_root(&store).unwrap();
+        assert_eq!(root, unpruned.get_root(&store).unwrap());
+        assert_eq!(
+            state.get_peaks(&store).unwrap(),
+            unpruned.get_peaks(&store).unwrap()
+        );
+
+        let blocks = reachable(&store, &state);
+        for (i, leaf) in leaves.iter().enumerate() {
+            assert_eq!(blocks.contains(leaf), i >= 5, "leaf {i}");
+        }
+        for i in 5..20u64 {
+            assert_eq!(
+                state.get_leaf_at::<_, Vec<u64>>(&store, i).unwrap(),
+                Some(vec![i])
+            );
+            let proof = state.get_proof(&store, i).unwrap().unwrap();
+            assert_eq!(proof, unpruned.get_proof(&store, i).unwrap().unwrap());
+            assert!(verify_proof(&root, &proof).unwrap());
+        }
+
+        // Pruning further, across several peaks, keeps the root
+        state.prune(&store, 17).unwrap();
+        assert_eq!(state.get_root(&store).unwrap(), root);
+        let blocks = reachable(&store, &state);
+        for (i, leaf) in leaves.iter().enumerate() {
+            assert_eq!(blocks.contains(leaf), i >= 17, "leaf {i}");
+        }
+        let proof = state.get_proof(&store, 18).unwrap().unwrap();
+        assert!(verify_proof(&root, &proof).unwrap());
+    }
+
+    #[test]
+    fn test_prune_root_history() {
+        let store = fvm_ipld_blockstore::MemoryBlockstore::default();
+        let mut state = State::new(&store, Address::new_id(100), HashMap::new()).unwrap();
+
+        let mut roots = Vec::new();
+        for i in 0..5u64 {
+            let root = state.push(&store, vec![i]).unwrap().root;
+            state
+                .record_root(&store, i as ChainEpoch * 10, root)
+                .unwrap();
+            roots.push(root);
+        }
+
+        // Roots which only commit to pruned leaves are dropped
+        state.prune(&store, 3).unwrap();
+        assert_eq!(state.root_history_start, 3);
+        assert!(state.get_root_at(&store, 25).unwrap().is_none());
+        assert_eq!(state.get_root_at(&store, 30).unwrap(), Some(roots[3]));
+        assert_eq!(state.get_root_at(&store, 45).unwrap(), Some(roots[4]));
+
+        // New roots are recorded after the retained ones
+        let root = state.push(&store, vec![5u64]).unwrap().root;
+        state.record_root(&store, 50, root).unwrap();
+        assert_eq!(state.get_root_at(&store, 45).unwrap(), Some(roots[4]));
+        assert_eq!(state.get_root_at(&store, 50).unwrap(), Some(root));
+
+        // The latest root is kept even if all the leaves are pruned
+        state.prune(&store, 6).unwrap();
+        assert!(state.get_root_at(&store, 45).unwrap().is_none());
+        assert_eq!(state.get_root_at(&store, 100).unwrap(), Some(root));
+    }
+
+    #[test]
+    fn test_limits() {
+        let store = fvm_ipld_blockstore::MemoryBlockstore::default();
+        let mut state = State::new(&store, Address::new_id(100), HashMap::new()).unwrap();
+        assert_eq!(state.max_leaf_size, DEFAULT_MAX_LEAF_SIZE);
+        assert!(state.set_limits(Some(0), None).is_err());
+        assert!(state.set_limits(None, Some(0)).is_err());
+        state.set_limits(Some(16), Some(3)).unwrap();
+
+        // Oversized leaves are rejected
+        let err = state.push(&store, vec![0u8; 32]).unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_ILLEGAL_ARGUMENT);
+        assert_eq!(state.leaf_count(), 0);
+
+        // A batch is rejected as a whole if it doesn't fit under the cap
+        state.push(&store, vec![0u8]).unwrap();
+        let err = state
+            .push_many(&store, vec![vec![1u8], vec![2], vec![3]])
+            .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_ILLEGAL_ARGUMENT);
+        assert_eq!(state.leaf_count(), 1);
+        state.push_many(&store, vec![vec![1u8], vec![2]]).unwrap();
+        let err = state.push(&store, vec![3u8]).unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_ILLEGAL_ARGUMENT);
+        assert_eq!(state.leaf_count(), 3);
+    }
+
+    #[test]
+    fn test_get_root_at() {
+        let store = fvm_ipld_blockstore::MemoryBlockstore::default();
+        let mut state = State::new(&store, Address::new_id(100), HashMap::new()).unwrap();
+        assert!(state.get_root_at(&store, 10).unwrap().is_none());
+
+        let mut roots = Vec::new();
+        for (i, epoch) in [5, 5, 8, 12, 20].into_iter().enumerate() {
+            let root = state.push(&store, vec![i as u64]).unwrap().root;
+            state.record_root(&store, epoch, root).unwrap();
+            roots.push(root);
+        }
+        // Records can't go back in time
+        assert!(state.record_root(&store, 19, roots[0]).is_err());
+
+        assert!(state.get_root_at(&store, 4).unwrap().is_none());
+        // The last root recorded in an epoch wins
+        assert_eq!(state.get_root_at(&store, 5).unwrap(), Some(roots[1]));
+        assert_eq!(state.get_root_at(&store, 7).unwrap(), Some(roots[1]));
+        assert_eq!(state.get_root_at(&store, 8).unwrap(), Some(roots[2]));
+        assert_eq!(state.get_root_at(&store, 19).unwrap(), Some(roots[3]));
+        assert_eq!(state.get_root_at(&store, 100).unwrap(), Some(roots[4]));
+    }
 }
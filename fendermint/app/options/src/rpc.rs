@@ -162,7 +162,7 @@ pub struct TransArgs {
     /// Sender account nonce.
     #[arg(long, short = 'n')]
     pub sequence: u64,
-    /// Maximum amount of gas that can be charged.
+    /// Maximum amount of gas that can be charged; 0 means estimate it by simulating the message.
     #[arg(long, default_value_t = 10_000_000_000)] // Default from ref-fvm testkit.
     pub gas_limit: u64,
     /// Price of gas.
@@ -7,4 +7,17 @@ use clap::Args;
 pub struct RunArgs {
     #[arg(long, short, default_value = "127.0.0.1:4919", env = "IROH_RPC_ADDR")]
     pub iroh_addr: String,
+    /// Seconds to wait when connecting to the Iroh node.
+    #[arg(long, default_value_t = 10, env = "IROH_CONNECT_TIMEOUT_SECS")]
+    pub iroh_connect_timeout_secs: u64,
+    /// Seconds to wait for a single Iroh request made by a syscall.
+    #[arg(long, default_value_t = 5, env = "IROH_REQUEST_TIMEOUT_SECS")]
+    pub iroh_request_timeout_secs: u64,
+    /// Number of connections to the Iroh node used by syscalls.
+    #[arg(long, default_value_t = 4, env = "IROH_POOL_SIZE")]
+    pub iroh_pool_size: usize,
+    /// Seconds between sweeps that reconcile the local Iroh store with the committed blobs.
+    /// Set to 0 to disable the sweeper.
+    #[arg(long, default_value_t = 3600, env = "IROH_SWEEP_INTERVAL_SECS")]
+    pub iroh_sweep_interval_secs: u64,
 }
@@ -347,6 +347,8 @@ impl Settings {
                     .with_list_parse_key("resolver.connection.external_addresses")
                     .with_list_parse_key("resolver.discovery.static_addresses")
                     .with_list_parse_key("resolver.membership.static_subnets")
+                    .with_list_parse_key("resolver.nat.relay_addresses")
+                    .with_list_parse_key("resolver.reputation.denylist")
                     .with_list_parse_key("eth.cors.allowed_origins")
                     .with_list_parse_key("eth.cors.allowed_methods")
                     .with_list_parse_key("eth.cors.allowed_headers")
@@ -447,6 +449,7 @@ mod tests {
                 ("FM_RESOLVER__CONNECTION__EXTERNAL_ADDRESSES", "/ip4/198.51.100.0/tcp/4242/p2p/QmYyQSo1c1Ym7orWxLYvCrM2EmxFTANf8wXmmE7DWjhx5N,/ip6/2604:1380:2000:7a00::1/udp/4001/quic/p2p/QmbLHAnMoJPWSCR5Zhtx6BHJX9KiKNN6tpvbUcqanj75Nb"),
                 ("FM_RESOLVER__DISCOVERY__STATIC_ADDRESSES", "/ip4/198.51.100.1/tcp/4242/p2p/QmYyQSo1c1Ym7orWxLYvCrM2EmxFTANf8wXmmE7DWjhx5N,/ip6/2604:1380:2000:7a00::2/udp/4001/quic/p2p/QmbLHAnMoJPWSCR5Zhtx6BHJX9KiKNN6tpvbUcqanj75Nb"),
                 ("FM_RESOLVER__MEMBERSHIP__STATIC_SUBNETS", "/r314/f410fijl3evsntewwhqxy6cx5ijdq5qp5cjlocbgzgey,/r314/f410fwplxlims2wnigaha2gofgktue7hiusmttwridkq"),
+                ("FM_RESOLVER__NAT__RELAY_ADDRESSES", "/ip4/198.51.100.2/tcp/4242/p2p/QmYyQSo1c1Ym7orWxLYvCrM2EmxFTANf8wXmmE7DWjhx5N,/ip4/198.51.100.3/tcp/4242/p2p/QmbLHAnMoJPWSCR5Zhtx6BHJX9KiKNN6tpvbUcqanj75Nb"),
                 ("FM_ETH__CORS__ALLOWED_ORIGINS", "https://example.com,https://www.example.org"),
                 ("FM_ETH__CORS__ALLOWED_METHODS", "GET,POST"),
                 ("FM_ETH__CORS__ALLOWED_HEADERS", "Accept,Content-Type"),
@@ -457,6 +460,7 @@ mod tests {
         assert_eq!(settings.resolver.connection.external_addresses.len(), 2);
         assert_eq!(settings.resolver.discovery.static_addresses.len(), 2);
         assert_eq!(settings.resolver.membership.static_subnets.len(), 2);
+        assert_eq!(settings.resolver.nat.relay_addresses.len(), 2);
         assert_eq!(
             format!("{:?}", settings.eth.cors.allowed_origins),
             "List([\"https://example.com\", \"https://www.example.org\"])"
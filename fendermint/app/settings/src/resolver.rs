@@ -22,6 +22,9 @@ pub struct ResolverSettings {
     pub membership: MembershipSettings,
     pub connection: ConnectionSettings,
     pub content: ContentSettings,
+    pub nat: NatSettings,
+    pub reputation: ReputationSettings,
+    pub queue: QueueSettings,
 }
 
 /// Settings describing the subnet hierarchy, not the physical network.
@@ -41,6 +44,7 @@ pub struct NetworkSettings {
 home_relative!(NetworkSettings { local_key });
 
 /// Configuration for [`discovery::Behaviour`].
+#[serde_as]
 #[derive(Clone, Debug, Deserialize)]
 pub struct DiscoverySettings {
     /// Custom nodes which never expire, e.g. bootstrap or reserved nodes.
@@ -51,6 +55,25 @@ pub struct DiscoverySettings {
     pub target_connections: usize,
     /// Option to disable Kademlia, for example in a fixed static network.
     pub enable_kademlia: bool,
+    /// Option to discover peers on the local network with mDNS.
+    pub enable_mdns: bool,
+    /// Option to remember discovered peers in the data directory across restarts.
+    pub persist_peers: bool,
+    /// Maximum number of peers to remember.
+    pub max_persisted_peers: usize,
+    /// Peers not seen for longer than this are forgotten.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub max_persisted_peer_age: Duration,
+    /// Option to publish provider records for our subnets into the DHT,
+    /// and to look up providers there when gossip knows too few of them.
+    pub enable_provider_records: bool,
+    /// Interval between republishing our provider records.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub provider_record_interval: Duration,
+    /// Number of known providers of a subnet below which we look for more in the DHT.
+    pub min_providers: usize,
+    /// Option to dial back the addresses peers advertise before adding them to the DHT.
+    pub verify_addresses: bool,
 }
 
 /// Configuration for [`membership::Behaviour`].
@@ -75,6 +98,31 @@ pub struct MembershipSettings {
     /// Maximum age of provider records before the peer is removed without an update.
     #[serde_as(as = "DurationSeconds<u64>")]
     pub max_provider_age: Duration,
+
+    /// Which peers are admitted to connect to us, based on their subnet membership.
+    pub connection_gating: ConnectionGating,
+
+    /// Time peers admitted by permissive gating have to prove their membership.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub gating_grace_period: Duration,
+
+    /// Only accept provider records for our own subnet signed by one of its validators.
+    ///
+    /// Provider records are signed with the network key, so this requires every validator
+    /// to use its validator key as its network key.
+    pub validate_provider_records: bool,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Admission of incoming connections by subnet membership.
+pub enum ConnectionGating {
+    /// Admit every peer.
+    Disabled,
+    /// Admit every peer, but disconnect those not proving their membership within the grace period.
+    Permissive,
+    /// Refuse peers which haven't proven their membership.
+    Strict,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -89,9 +137,58 @@ pub struct ConnectionSettings {
     pub expected_peer_count: u32,
     /// Maximum number of peers to send Bitswap requests to in a single attempt.
     pub max_peers_per_query: u32,
+    /// Number of peers to resolve a CID from in parallel, the fastest one winning.
+    pub parallel_queries: u32,
     /// Maximum number of events in the push-based broadcast channel before a slow
     /// consumer gets an error because it's falling behind.
     pub event_buffer_capacity: u32,
+    /// Option to add QUIC to the transports.
+    pub enable_quic: bool,
+    /// QUIC address to listen on in addition to `listen_addr`.
+    pub quic_listen_addr: Option<Multiaddr>,
+    /// Option to keep TCP next to QUIC, to be able to connect to peers which don't support QUIC.
+    pub tcp_fallback: bool,
+}
+
+/// Options to make nodes behind NAT reachable.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NatSettings {
+    /// Option to probe whether we are publicly reachable with AutoNAT.
+    pub enable_autonat: bool,
+    /// Option to act as a relay for other nodes which are not publicly reachable.
+    pub enable_relay_server: bool,
+    /// Relays to make a reservation with, so that others can reach us through them.
+    ///
+    /// The addresses must end with a `/p2p/<peer-id>` part.
+    pub relay_addresses: Vec<Multiaddr>,
+    /// Option to upgrade relayed connections to direct ones with DCUtR hole punching.
+    pub enable_dcutr: bool,
+}
+
+/// Peer scoring and banning.
+#[serde_as]
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReputationSettings {
+    /// Peers whose score drops to or below this (negative) value are temporarily banned.
+    pub ban_threshold: i32,
+    /// How long a peer stays banned after reaching the threshold, in seconds.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub ban_duration: Duration,
+    /// Peer IDs which are never allowed to connect.
+    pub denylist: Vec<String>,
+}
+
+/// Prioritization of resolutions, so bursts of blobs can't starve checkpointing.
+#[serde_as]
+#[derive(Debug, Clone, Deserialize)]
+pub struct QueueSettings {
+    /// Maximum number of resolutions waiting to be started.
+    pub capacity: usize,
+    /// Maximum number of resolutions running at the same time.
+    pub max_concurrent: usize,
+    /// Maximum time a resolution can wait in the queue before it fails, in seconds.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub max_wait: Duration,
 }
 
 /// Configuration for [`content::Behaviour`].
@@ -107,4 +204,21 @@ pub struct ContentSettings {
     /// 0 means no limit.
     #[serde_as(as = "DurationSeconds<u64>")]
     pub rate_limit_period: Duration,
+    /// Number of requests a single remote peer can make in a time period.
+    ///
+    /// 0 means no limit.
+    pub request_rate_limit_per_peer: u32,
+    /// Number of requests the providers of a subnet can make together in a time period.
+    ///
+    /// 0 means no limit.
+    pub request_rate_limit_per_subnet: u32,
+    /// Length of the time period at which the request limits fill.
+    ///
+    /// 0 means no limit.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub request_rate_limit_period: Duration,
+    /// Number of bytes we serve a single remote peer per day.
+    ///
+    /// 0 means no limit.
+    pub peer_daily_quota_bytes: u64,
 }
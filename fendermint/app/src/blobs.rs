@@ -0,0 +1,131 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Blob related queries
+
+use crate::app::{AppState, AppStoreKey};
+use crate::{App, BlockHeight};
+use anyhow::anyhow;
+use fendermint_actor_blobs_shared::params::{
+    GetBlobSubscribersParams, GetBlobSubscribersReturn, ListBlobsParams, ListBlobsReturn,
+};
+use fendermint_actor_blobs_shared::state::BlobStatus;
+use fendermint_actor_blobs_shared::Method::{GetBlobSubscribers, ListBlobs};
+use fendermint_storage::{Codec, Encode, KVReadable, KVStore, KVWritable};
+use fendermint_vm_actor_interface::{blobs, system};
+use fendermint_vm_interpreter::fvm::state::{FvmExecState, FvmStateParams};
+use fendermint_vm_interpreter::fvm::store::ReadOnlyBlockstore;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::message::Message;
+use iroh::blobs::Hash;
+use iroh::net::NodeId;
+use recall_syscalls::sweeper::{BlobCatalog, CommittedBlob, MAX_REFETCH_SOURCES};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Queries the LATEST COMMITTED blobs from the blobs actor.
+pub struct AppBlobCatalog<DB, SS, S, I>
+where
+    SS: Blockstore + Clone + 'static,
+    S: KVStore,
+{
+    /// The app to get state
+    app: App<DB, SS, S, I>,
+}
+
+impl<DB, SS, S, I> AppBlobCatalog<DB, SS, S, I>
+where
+    S: KVStore
+        + Codec<AppState>
+        + Encode<AppStoreKey>
+        + Encode<BlockHeight>
+        + Codec<FvmStateParams>,
+    DB: KVWritable<S> + KVReadable<S> + 'static + Clone,
+    SS: Blockstore + 'static + Clone,
+{
+    pub fn new(app: App<DB, SS, S, I>) -> Self {
+        Self { app }
+    }
+
+    /// Calls a read-only method of the blobs actor.
+    fn call<P, R>(&self, method: u64, params: P) -> anyhow::Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let mut state: FvmExecState<ReadOnlyBlockstore<Arc<SS>>> =
+            self.app
+                .read_only_view(None)?
+                .ok_or_else(|| anyhow!("no committed state"))?;
+        let msg = Message {
+            version: Default::default(),
+            from: system::SYSTEM_ACTOR_ADDR,
+            to: blobs::BLOBS_ACTOR_ADDR,
+            sequence: 0,
+            value: Default::default(),
+            method_num: method,
+            params: RawBytes::serialize(params)?,
+            gas_limit: fvm_shared::BLOCK_GAS_LIMIT,
+            gas_fee_cap: Default::default(),
+            gas_premium: Default::default(),
+        };
+        let (apply_ret, _) = state.execute_implicit(msg)?;
+        if let Some(err) = apply_ret.failure_info {
+            return Err(anyhow!("failed to call blobs actor: {}", err));
+        }
+        fvm_ipld_encoding::from_slice(&apply_ret.msg_receipt.return_data)
+            .map_err(|e| anyhow!("error parsing blobs actor return: {e}"))
+    }
+}
+
+impl<DB, SS, S, I> BlobCatalog for AppBlobCatalog<DB, SS, S, I>
+where
+    S: KVStore
+        + Codec<AppState>
+        + Encode<AppStoreKey>
+        + Encode<BlockHeight>
+        + Codec<FvmStateParams>,
+    DB: KVWritable<S> + KVReadable<S> + 'static + Clone,
+    SS: Blockstore + 'static + Clone,
+    App<DB, SS, S, I>: Send + Sync,
+{
+    fn list_blobs(
+        &self,
+        start_key: Option<Hash>,
+        limit: u32,
+    ) -> anyhow::Result<(Vec<CommittedBlob>, Option<Hash>)> {
+        let params = ListBlobsParams {
+            start_key: start_key
+                .map(|key| fendermint_actor_blobs_shared::state::Hash(*key.as_bytes())),
+            limit: Some(limit),
+        };
+        let ret: ListBlobsReturn = self.call(ListBlobs as u64, params)?;
+        let blobs = ret
+            .blobs
+            .into_iter()
+            .map(|blob| CommittedBlob {
+                hash: Hash::from_bytes(blob.hash.0),
+                resolved: matches!(blob.status, BlobStatus::Resolved),
+            })
+            .collect();
+        Ok((blobs, ret.next_key.map(|key| Hash::from_bytes(key.0))))
+    }
+
+    fn blob_sources(&self, hash: Hash) -> anyhow::Result<Vec<NodeId>> {
+        let params = GetBlobSubscribersParams {
+            hash: fendermint_actor_blobs_shared::state::Hash(*hash.as_bytes()),
+            start_key: None,
+            limit: Some(MAX_REFETCH_SOURCES),
+        };
+        let ret: Option<GetBlobSubscribersReturn> = self.call(GetBlobSubscribers as u64, params)?;
+        let mut sources = HashSet::new();
+        for group in ret.map(|ret| ret.subscribers).unwrap_or_default() {
+            for sub in group.subscriptions {
+                sources.insert(NodeId::from_bytes(&sub.source.0)?);
+            }
+        }
+        Ok(sources.into_iter().collect())
+    }
+}
@@ -2,10 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{anyhow, bail, Context};
-use async_stm::atomically_or_err;
+use async_stm::{atomically, atomically_or_err};
+use async_trait::async_trait;
 use fendermint_abci::ApplicationService;
+use fendermint_app::blobs::AppBlobCatalog;
 use fendermint_app::ipc::{AppParentFinalityQuery, AppVote};
 use fendermint_app::{App, AppConfig, AppStore, BitswapBlockstore};
 use fendermint_app_settings::AccountKind;
@@ -32,10 +35,12 @@ use fendermint_vm_topdown::{
     CachedFinalityProvider, IPCBlobFinality, IPCParentFinality, IPCReadRequestClosed, Toggle,
 };
 use fvm_shared::address::{current_network, Address, Network};
-use ipc_ipld_resolver::{Event as ResolverEvent, VoteRecord};
+use ipc_api::subnet_id::SubnetID;
+use ipc_ipld_resolver::{Event as ResolverEvent, SubnetValidators, ValidatorKey, VoteRecord};
 use ipc_observability::{emit, observe::register_metrics as register_default_metrics};
 use ipc_provider::config::subnet::{EVMSubnet, SubnetConfig};
 use ipc_provider::IpcProvider;
+use iroh_manager::IrohConfig;
 use tokio::sync::broadcast::error::RecvError;
 use tower::ServiceBuilder;
 use tracing::{debug, error, info, warn};
@@ -50,10 +55,20 @@ use fendermint_vm_iroh_resolver::observe::{
 
 cmd! {
   RunArgs(self, settings) {
-    // this env var must be set for the blobs_syscall to work. the CLI has a default and accepts
-    // an override via the env variable, but it doesn't require it's set, so we ensure it here
-    std::env::set_var("IROH_RPC_ADDR", self.iroh_addr.clone());
-    run(settings, self.iroh_addr.clone()).await
+    // The blob syscalls talk to Iroh through a process-wide client, which must be configured
+    // before any blocks are executed.
+    recall_syscalls::init_iroh(IrohConfig {
+        addr: self.iroh_addr.clone(),
+        connect_timeout: Duration::from_secs(self.iroh_connect_timeout_secs),
+        request_timeout: Duration::from_secs(self.iroh_request_timeout_secs),
+        pool_size: self.iroh_pool_size,
+        health_check_interval: iroh_manager::DEFAULT_HEALTH_CHECK_INTERVAL,
+    })?;
+    let iroh_sweep_interval = match self.iroh_sweep_interval_secs {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    };
+    run(settings, self.iroh_addr.clone(), iroh_sweep_interval).await
   }
 }
 
@@ -70,7 +85,11 @@ namespaces! {
 /// Run the Fendermint ABCI Application.
 ///
 /// This method acts as our composition root.
-async fn run(settings: Settings, iroh_addr: String) -> anyhow::Result<()> {
+async fn run(
+    settings: Settings,
+    iroh_addr: String,
+    iroh_sweep_interval: Option<Duration>,
+) -> anyhow::Result<()> {
     let tendermint_rpc_url = settings.tendermint_rpc_url()?;
     info!("Connecting to Tendermint at {tendermint_rpc_url}");
 
@@ -92,6 +111,10 @@ async fn run(settings: Settings, iroh_addr: String) -> anyhow::Result<()> {
             .context("failed to register interpreter metrics")?;
         register_consensus_metrics(&registry).context("failed to register consensus metrics")?;
         register_blobs_metrics(&registry).context("failed to register blobs metrics")?;
+        recall_syscalls::observe::register_metrics(&registry)
+            .context("failed to register recall syscalls metrics")?;
+        iroh_manager::observe::register_metrics(&registry)
+            .context("failed to register iroh manager metrics")?;
 
         Some(registry)
     } else {
@@ -170,6 +193,14 @@ async fn run(settings: Settings, iroh_addr: String) -> anyhow::Result<()> {
     let ns = Namespaces::default();
     let db = open_db(&settings, &ns).context("error opening DB")?;
 
+    let deletion_queue_path = settings.data_dir().join("iroh_deletes");
+    info!(
+        path = deletion_queue_path.to_string_lossy().into_owned(),
+        "opening Iroh deletion queue"
+    );
+    recall_syscalls::init_deletion_queue(deletion_queue_path)
+        .context("error opening Iroh deletion queue")?;
+
     // Blockstore for actors.
     let state_store =
         NamespaceBlockstore::new(db.clone(), ns.state_store).context("error creating state DB")?;
@@ -203,6 +234,13 @@ async fn run(settings: Settings, iroh_addr: String) -> anyhow::Result<()> {
 
         let own_subnet_id = settings.ipc.subnet_id.clone();
 
+        if settings.resolver.membership.validate_provider_records {
+            service.set_subnet_validators(Arc::new(OwnSubnetValidators {
+                subnet_id: own_subnet_id.clone(),
+                parent_finality_votes: parent_finality_votes.clone(),
+            }));
+        }
+
         client
             .add_provided_subnet(own_subnet_id.clone())
             .context("error adding own provided subnet.")?;
@@ -379,6 +417,14 @@ async fn run(settings: Settings, iroh_addr: String) -> anyhow::Result<()> {
         snapshots,
     )?;
 
+    if let Some(interval) = iroh_sweep_interval {
+        info!(interval = ?interval, "starting the Iroh store sweeper");
+        let catalog = AppBlobCatalog::new(app.clone());
+        tokio::spawn(recall_syscalls::sweeper::run_sweeper(catalog, interval));
+    } else {
+        info!("Iroh store sweeper disabled");
+    }
+
     if let Some((agent_proxy, config)) = ipc_tuple {
         let app_parent_finality_query = AppParentFinalityQuery::new(app.clone());
         tokio::spawn(async move {
@@ -487,6 +533,30 @@ async fn make_resolver_service(
     Ok(service)
 }
 
+/// Check the signers of provider records for our own subnet against its power table.
+///
+/// We don't know the validators of other subnets, so anyone can provide those.
+struct OwnSubnetValidators {
+    subnet_id: SubnetID,
+    parent_finality_votes: VoteTally,
+}
+
+#[async_trait]
+impl SubnetValidators for OwnSubnetValidators {
+    async fn is_validator(
+        &self,
+        subnet_id: &SubnetID,
+        public_key: &libp2p::identity::PublicKey,
+    ) -> anyhow::Result<Option<bool>> {
+        if *subnet_id != self.subnet_id {
+            return Ok(None);
+        }
+        let key = ValidatorKey::from(public_key.clone());
+        let has_power = atomically(|| self.parent_finality_votes.has_power(&key)).await;
+        Ok(Some(has_power))
+    }
+}
+
 fn make_ipc_provider_proxy(settings: &Settings) -> anyhow::Result<IPCProviderProxy> {
     let topdown_config = settings.ipc.topdown_config()?;
     let subnet = ipc_provider::config::Subnet {
@@ -513,8 +583,10 @@ fn to_resolver_config(
     settings: &Settings,
     iroh_addr: String,
 ) -> anyhow::Result<ipc_ipld_resolver::Config> {
+    use fendermint_app_settings::resolver::ConnectionGating;
     use ipc_ipld_resolver::{
-        Config, ConnectionConfig, ContentConfig, DiscoveryConfig, MembershipConfig, NetworkConfig,
+        Config, ConnectionConfig, ContentConfig, DiscoveryConfig, GatingMode, MembershipConfig,
+        NatConfig, NetworkConfig, PeerStoreConfig, QueueConfig, ReputationConfig,
     };
 
     let r = &settings.resolver;
@@ -526,6 +598,16 @@ fn to_resolver_config(
         libp2p::identity::secp256k1::Keypair::from(sk).into()
     };
 
+    let denylist = r
+        .reputation
+        .denylist
+        .iter()
+        .map(|id| {
+            id.parse::<libp2p::PeerId>()
+                .with_context(|| format!("invalid peer ID in the resolver denylist: {id}"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
     let network_name = format!(
         "ipld-resolver-{}-{}",
         settings.ipc.subnet_id.root_id(),
@@ -539,7 +621,11 @@ fn to_resolver_config(
             expected_peer_count: r.connection.expected_peer_count,
             max_incoming: r.connection.max_incoming,
             max_peers_per_query: r.connection.max_peers_per_query,
+            parallel_queries: r.connection.parallel_queries,
             event_buffer_capacity: r.connection.event_buffer_capacity,
+            enable_quic: r.connection.enable_quic,
+            quic_listen_addr: r.connection.quic_listen_addr.clone(),
+            tcp_fallback: r.connection.tcp_fallback,
         },
         network: NetworkConfig {
             local_key,
@@ -549,6 +635,17 @@ fn to_resolver_config(
             static_addresses: r.discovery.static_addresses.clone(),
             target_connections: r.discovery.target_connections,
             enable_kademlia: r.discovery.enable_kademlia,
+            enable_mdns: r.discovery.enable_mdns,
+            peer_store: r.discovery.persist_peers.then(|| PeerStoreConfig {
+                path: settings.data_dir().join("resolver_peers.json"),
+                max_peers: r.discovery.max_persisted_peers,
+                max_age: r.discovery.max_persisted_peer_age,
+                flush_interval: Duration::from_secs(60),
+            }),
+            enable_provider_records: r.discovery.enable_provider_records,
+            provider_record_interval: r.discovery.provider_record_interval,
+            min_providers: r.discovery.min_providers,
+            verify_addresses: r.discovery.verify_addresses,
         },
         membership: MembershipConfig {
             static_subnets: r.membership.static_subnets.clone(),
@@ -556,10 +653,36 @@ fn to_resolver_config(
             publish_interval: r.membership.publish_interval,
             min_time_between_publish: r.membership.min_time_between_publish,
             max_provider_age: r.membership.max_provider_age,
+            gating: match r.membership.connection_gating {
+                ConnectionGating::Disabled => GatingMode::Disabled,
+                ConnectionGating::Permissive => GatingMode::Permissive,
+                ConnectionGating::Strict => GatingMode::Strict,
+            },
+            gating_grace_period: r.membership.gating_grace_period,
         },
         content: ContentConfig {
             rate_limit_bytes: r.content.rate_limit_bytes,
             rate_limit_period: r.content.rate_limit_period,
+            request_rate_limit_per_peer: r.content.request_rate_limit_per_peer,
+            request_rate_limit_per_subnet: r.content.request_rate_limit_per_subnet,
+            request_rate_limit_period: r.content.request_rate_limit_period,
+            peer_daily_quota_bytes: r.content.peer_daily_quota_bytes,
+        },
+        nat: NatConfig {
+            enable_autonat: r.nat.enable_autonat,
+            enable_relay_server: r.nat.enable_relay_server,
+            relay_addresses: r.nat.relay_addresses.clone(),
+            enable_dcutr: r.nat.enable_dcutr,
+        },
+        reputation: ReputationConfig {
+            ban_threshold: r.reputation.ban_threshold,
+            ban_duration: r.reputation.ban_duration,
+            denylist,
+        },
+        queue: QueueConfig {
+            capacity: r.queue.capacity,
+            max_concurrent: r.queue.max_concurrent,
+            max_wait: r.queue.max_wait,
         },
         iroh_addr: Some(iroh_addr),
     };
@@ -587,6 +710,13 @@ async fn dispatch_resolver_events(
                 ResolverEvent::ReceivedVote(vote) => {
                     dispatch_vote(*vote, &parent_finality_votes, topdown_enabled).await;
                 }
+                ResolverEvent::ReceivedBlobAnnouncement(announcement) => {
+                    let f = IPCBlobFinality::new(
+                        announcement.content.hash,
+                        announcement.content.resolved,
+                    );
+                    dispatch_blob_vote(announcement.public_key, f, &parent_finality_votes).await;
+                }
             },
             Err(RecvError::Lagged(n)) => {
                 warn!("the resolver service skipped {n} gossip events")
@@ -641,39 +771,7 @@ async fn dispatch_vote(
             }
         }
         AppVote::BlobFinality(f) => {
-            debug!(hash = %f.hash, success = ?f.success, "received vote for blob finality");
-            match atomically_or_err(|| {
-                parent_finality_votes.add_blob_vote(
-                    vote.public_key.clone(),
-                    f.hash.as_bytes().to_vec(),
-                    f.success,
-                )
-            })
-            .await
-            {
-                Ok(_) => {
-                    debug!("vote handled for blob finality");
-                    if f.success {
-                        emit(BlobsFinalityVotingSuccess {
-                            blob_hash: Some(f.hash.to_string()),
-                        });
-                    } else {
-                        emit(BlobsFinalityVotingFailure {
-                            blob_hash: Some(f.hash.to_string()),
-                        });
-                    }
-                }
-                Err(e @ VoteError::Equivocation(_, _, _, _)) => {
-                    warn!(error = e.to_string(), "failed to handle blob finality vote");
-                }
-                Err(
-                    e @ (VoteError::Uninitialized // early vote, we're not ready yet
-                    | VoteError::UnpoweredValidator(_) // maybe arrived too early or too late, or spam
-                    | VoteError::UnexpectedBlock(_, _)), // won't happen here
-                ) => {
-                    debug!(error = e.to_string(), "failed to handle blob finality vote");
-                }
-            }
+            dispatch_blob_vote(vote.public_key, f, parent_finality_votes).await;
         }
         AppVote::ReadRequestClosed(r) => {
             debug!(hash = %r.hash, "received vote for read request completion");
@@ -712,3 +810,45 @@ async fn dispatch_vote(
         }
     }
 }
+
+/// Add a vote about a blob to the tally, whether it was cast on the voting topic
+/// or came from an announcement of the blob resolution outcome.
+async fn dispatch_blob_vote(
+    public_key: ValidatorKey,
+    f: IPCBlobFinality,
+    parent_finality_votes: &VoteTally,
+) {
+    debug!(hash = %f.hash, success = ?f.success, "received vote for blob finality");
+    match atomically_or_err(|| {
+        parent_finality_votes.add_blob_vote(
+            public_key.clone(),
+            f.hash.as_bytes().to_vec(),
+            f.success,
+        )
+    })
+    .await
+    {
+        Ok(_) => {
+            debug!("vote handled for blob finality");
+            if f.success {
+                emit(BlobsFinalityVotingSuccess {
+                    blob_hash: Some(f.hash.to_string()),
+                });
+            } else {
+                emit(BlobsFinalityVotingFailure {
+                    blob_hash: Some(f.hash.to_string()),
+                });
+            }
+        }
+        Err(e @ VoteError::Equivocation(_, _, _, _)) => {
+            warn!(error = e.to_string(), "failed to handle blob finality vote");
+        }
+        Err(
+            e @ (VoteError::Uninitialized // early vote, we're not ready yet
+            | VoteError::UnpoweredValidator(_) // maybe arrived too early or too late, or spam
+            | VoteError::UnexpectedBlock(_, _)), // won't happen here
+        ) => {
+            debug!(error = e.to_string(), "failed to handle blob finality vote");
+        }
+    }
+}
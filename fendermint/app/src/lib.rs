@@ -3,6 +3,7 @@ extern crate core;
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 mod app;
+pub mod blobs;
 pub mod ipc;
 pub mod metrics;
 pub mod observe;
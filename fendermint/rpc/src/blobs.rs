@@ -0,0 +1,158 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use async_trait::async_trait;
+
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::econ::TokenAmount;
+
+use fendermint_actor_blobs_shared::params::{
+    AddBlobParams, ApproveCreditParams, BuyCreditParams, DeleteBlobParams, GetBlobParams,
+    GetStatsReturn,
+};
+use fendermint_actor_blobs_shared::state::{AccountInfo, BlobInfo, CreditApproval, Subscription};
+use fendermint_actor_blobs_shared::{Method, BLOBS_ACTOR_ADDR};
+use fendermint_vm_message::chain::ChainMessage;
+
+use crate::message::GasParams;
+use crate::response::decode_cbor;
+use crate::tx::{BroadcastMode, TxClient, TxCommit};
+
+/// Typed access to the methods of the blobs actor, so that callers don't have to
+/// encode the parameters and decode the return values of raw method numbers.
+#[async_trait]
+pub trait BlobsClient<M: BroadcastMode = TxCommit>: TxClient<M> {
+    /// Buy credit for an account with the tokens sent along.
+    async fn buy_credit(
+        &mut self,
+        params: BuyCreditParams,
+        value: TokenAmount,
+        gas_params: GasParams,
+    ) -> anyhow::Result<M::Response<AccountInfo>> {
+        let msg = blobs_message(
+            self,
+            Method::BuyCredit,
+            RawBytes::serialize(params)?,
+            value,
+            gas_params,
+        )
+        .await?;
+        let msg = self.fill_gas_limit(msg).await?;
+        self.perform(msg, decode_cbor).await
+    }
+
+    /// Approve another account to use the credit of the sender.
+    async fn approve_credit(
+        &mut self,
+        params: ApproveCreditParams,
+        gas_params: GasParams,
+    ) -> anyhow::Result<M::Response<CreditApproval>> {
+        let msg = blobs_message(
+            self,
+            Method::ApproveCredit,
+            RawBytes::serialize(params)?,
+            TokenAmount::default(),
+            gas_params,
+        )
+        .await?;
+        let msg = self.fill_gas_limit(msg).await?;
+        self.perform(msg, decode_cbor).await
+    }
+
+    /// Add a blob, or subscribe to an existing one.
+    async fn add_blob(
+        &mut self,
+        params: AddBlobParams,
+        gas_params: GasParams,
+    ) -> anyhow::Result<M::Response<Subscription>> {
+        let msg = blobs_message(
+            self,
+            Method::AddBlob,
+            RawBytes::serialize(params)?,
+            TokenAmount::default(),
+            gas_params,
+        )
+        .await?;
+        let msg = self.fill_gas_limit(msg).await?;
+        self.perform(msg, decode_cbor).await
+    }
+
+    /// Get a blob, if it exists.
+    async fn get_blob(
+        &mut self,
+        params: GetBlobParams,
+        gas_params: GasParams,
+    ) -> anyhow::Result<M::Response<Option<BlobInfo>>> {
+        let msg = blobs_message(
+            self,
+            Method::GetBlob,
+            RawBytes::serialize(params)?,
+            TokenAmount::default(),
+            gas_params,
+        )
+        .await?;
+        let msg = self.fill_gas_limit(msg).await?;
+        self.perform(msg, decode_cbor).await
+    }
+
+    /// Delete the subscription of an account to a blob.
+    async fn delete_blob(
+        &mut self,
+        params: DeleteBlobParams,
+        gas_params: GasParams,
+    ) -> anyhow::Result<M::Response<()>> {
+        let msg = blobs_message(
+            self,
+            Method::DeleteBlob,
+            RawBytes::serialize(params)?,
+            TokenAmount::default(),
+            gas_params,
+        )
+        .await?;
+        let msg = self.fill_gas_limit(msg).await?;
+        self.perform(msg, |_| Ok(())).await
+    }
+
+    /// Get the storage and credit statistics of the subnet.
+    async fn get_stats(
+        &mut self,
+        gas_params: GasParams,
+    ) -> anyhow::Result<M::Response<GetStatsReturn>> {
+        let msg = blobs_message(
+            self,
+            Method::GetStats,
+            RawBytes::default(),
+            TokenAmount::default(),
+            gas_params,
+        )
+        .await?;
+        let msg = self.fill_gas_limit(msg).await?;
+        self.perform(msg, decode_cbor).await
+    }
+}
+
+/// Auto-implement this trait for anything that can send transactions.
+impl<C, M> BlobsClient<M> for C
+where
+    C: TxClient<M>,
+    M: BroadcastMode,
+{
+}
+
+/// Create a signed message calling a method of the blobs actor.
+async fn blobs_message<C, M>(
+    client: &mut C,
+    method: Method,
+    params: RawBytes,
+    value: TokenAmount,
+    gas_params: GasParams,
+) -> anyhow::Result<ChainMessage>
+where
+    C: TxClient<M> + ?Sized,
+    M: BroadcastMode,
+{
+    client
+        .message_factory_mut()
+        .transaction(BLOBS_ACTOR_ADDR, method as u64, params, value, gas_params)
+        .await
+}
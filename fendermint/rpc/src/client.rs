@@ -3,19 +3,22 @@
 
 use std::fmt::Display;
 use std::marker::PhantomData;
+use std::time::Duration;
 
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
 use async_trait::async_trait;
 use fendermint_vm_message::chain::ChainMessage;
 use tendermint::abci::response::DeliverTx;
 use tendermint::block::Height;
+use tendermint_rpc::endpoint::broadcast::{tx_async, tx_commit, tx_sync};
 use tendermint_rpc::{endpoint::abci_query::AbciQuery, Client, HttpClient, Scheme, Url};
 use tendermint_rpc::{WebSocketClient, WebSocketClientDriver, WebSocketClientUrl};
 
 use fendermint_vm_message::query::{FvmQuery, FvmQueryHeight};
 
-use crate::message::SignedMessageFactory;
+use crate::message::{pad_gas_limit, SignedMessageFactory, AUTO_GAS_LIMIT};
 use crate::query::QueryClient;
+use crate::retry::{broadcast_with_retry, is_tx_not_found, BroadcastFailure, RetryPolicy};
 use crate::tx::{
     AsyncResponse, BoundClient, CommitResponse, SyncResponse, TxAsync, TxClient, TxCommit, TxSync,
 };
@@ -85,6 +88,12 @@ where
     Ok((client, driver))
 }
 
+/// Interval between lookups when waiting for a transaction to be committed.
+const COMMIT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Padding applied to the gas used in simulations when the gas limit is estimated automatically.
+pub const DEFAULT_GAS_OVERESTIMATION_RATE: f64 = 1.25;
+
 /// Unauthenticated Fendermint client.
 #[derive(Clone)]
 pub struct FendermintClient<C = HttpClient> {
@@ -140,6 +149,8 @@ where
 pub struct BoundFendermintClient<C = HttpClient> {
     inner: C,
     message_factory: SignedMessageFactory,
+    gas_overestimation_rate: f64,
+    retry_policy: RetryPolicy,
 }
 
 impl<C> BoundFendermintClient<C> {
@@ -147,8 +158,55 @@ impl<C> BoundFendermintClient<C> {
         Self {
             inner,
             message_factory,
+            gas_overestimation_rate: DEFAULT_GAS_OVERESTIMATION_RATE,
+            retry_policy: RetryPolicy::default(),
         }
     }
+
+    /// Set how to retry broadcasts which failed for reasons other than the transaction itself.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set the factor by which the gas used in simulations is padded when estimating the gas limit.
+    pub fn with_gas_overestimation_rate(mut self, rate: f64) -> Self {
+        self.gas_overestimation_rate = rate;
+        self
+    }
+}
+
+impl<C> BoundFendermintClient<C>
+where
+    C: Client + Sync + Send,
+{
+    /// Simulate a message sent with an automatic gas limit on the pending state,
+    /// then sign it again with the padded estimate.
+    async fn estimate_gas_limit(&mut self, msg: ChainMessage) -> anyhow::Result<ChainMessage> {
+        let message = match msg {
+            ChainMessage::Signed(ref signed) if signed.message().gas_limit == AUTO_GAS_LIMIT => {
+                signed.message().clone()
+            }
+            msg => return Ok(msg),
+        };
+
+        let estimate = self
+            .estimate_gas(message.clone(), FvmQueryHeight::Pending)
+            .await
+            .context("failed to estimate gas")?;
+
+        if !estimate.value.exit_code.is_success() {
+            bail!(
+                "failed to estimate gas: {} - {}",
+                estimate.value.exit_code,
+                estimate.value.info
+            );
+        }
+
+        let mut message = message;
+        message.gas_limit = pad_gas_limit(estimate.value.gas_limit, self.gas_overestimation_rate);
+        self.message_factory.sign(message).await
+    }
 }
 
 impl<C> BoundClient for BoundFendermintClient<C> {
@@ -181,16 +239,31 @@ impl<C> TxClient<TxAsync> for BoundFendermintClient<C>
 where
     C: Client + Sync + Send,
 {
+    async fn fill_gas_limit(&mut self, msg: ChainMessage) -> anyhow::Result<ChainMessage> {
+        self.estimate_gas_limit(msg).await
+    }
+
     async fn perform<F, T>(&self, msg: ChainMessage, _f: F) -> anyhow::Result<AsyncResponse<T>>
     where
         F: FnOnce(&DeliverTx) -> anyhow::Result<T> + Sync + Send,
     {
         let data = SignedMessageFactory::serialize(&msg)?;
-        let response = self
-            .inner
-            .broadcast_tx_async(data)
-            .await
-            .context("broadcast_tx_async failed")?;
+        let response = match broadcast_with_retry(&self.retry_policy, data, |data| {
+            self.inner.broadcast_tx_async(data)
+        })
+        .await
+        {
+            Ok(response) => response,
+            // An earlier attempt made it into the mempool, which is all this mode waits for.
+            Err(e) if e.failure == BroadcastFailure::AlreadyBroadcast => tx_async::Response {
+                codespace: String::new(),
+                code: Default::default(),
+                data: Default::default(),
+                log: e.to_string(),
+                hash: e.hash,
+            },
+            Err(e) => return Err(e).context("broadcast_tx_async failed"),
+        };
         let response = AsyncResponse {
             response,
             return_data: PhantomData,
@@ -204,6 +277,10 @@ impl<C> TxClient<TxSync> for BoundFendermintClient<C>
 where
     C: Client + Sync + Send,
 {
+    async fn fill_gas_limit(&mut self, msg: ChainMessage) -> anyhow::Result<ChainMessage> {
+        self.estimate_gas_limit(msg).await
+    }
+
     async fn perform<F, T>(
         &self,
         msg: ChainMessage,
@@ -213,11 +290,22 @@ where
         F: FnOnce(&DeliverTx) -> anyhow::Result<T> + Sync + Send,
     {
         let data = SignedMessageFactory::serialize(&msg)?;
-        let response = self
-            .inner
-            .broadcast_tx_sync(data)
-            .await
-            .context("broadcast_tx_sync failed")?;
+        let response = match broadcast_with_retry(&self.retry_policy, data, |data| {
+            self.inner.broadcast_tx_sync(data)
+        })
+        .await
+        {
+            Ok(response) => response,
+            // An earlier attempt made it into the mempool, so it has already passed the check.
+            Err(e) if e.failure == BroadcastFailure::AlreadyBroadcast => tx_sync::Response {
+                codespace: String::new(),
+                code: Default::default(),
+                data: Default::default(),
+                log: e.to_string(),
+                hash: e.hash,
+            },
+            Err(e) => return Err(e).context("broadcast_tx_sync failed"),
+        };
         let response = SyncResponse {
             response,
             return_data: PhantomData,
@@ -231,6 +319,10 @@ impl<C> TxClient<TxCommit> for BoundFendermintClient<C>
 where
     C: Client + Sync + Send,
 {
+    async fn fill_gas_limit(&mut self, msg: ChainMessage) -> anyhow::Result<ChainMessage> {
+        self.estimate_gas_limit(msg).await
+    }
+
     async fn perform<F, T>(
         &self,
         msg: ChainMessage,
@@ -240,27 +332,113 @@ where
         F: FnOnce(&DeliverTx) -> anyhow::Result<T> + Sync + Send,
     {
         let data = SignedMessageFactory::serialize(&msg)?;
-        let response = self
-            .inner
-            .broadcast_tx_commit(data)
-            .await
-            .context("broadcast_tx_commit failed")?;
-        // We have a fully `DeliverTx` with default fields even if `CheckTx` indicates failure.
-        let return_data = if response.check_tx.code.is_err() || response.deliver_tx.code.is_err() {
-            None
-        } else {
-            let return_data =
-                f(&response.deliver_tx).context("error decoding data from deliver_tx in commit")?;
-            Some(return_data)
-        };
-        let response = CommitResponse {
-            response,
-            return_data,
+        let response = match broadcast_with_retry(&self.retry_policy, data, |data| {
+            self.inner.broadcast_tx_commit(data)
+        })
+        .await
+        {
+            Ok(response) => response,
+            // An earlier attempt made it into the mempool, or it's still waiting to be included.
+            Err(e)
+                if e.failure == BroadcastFailure::AlreadyBroadcast
+                    || e.failure == BroadcastFailure::CommitTimeout =>
+            {
+                let poll = poll_committed_tx(&self.inner, e.hash, COMMIT_POLL_INTERVAL);
+                tokio::time::timeout(self.retry_policy.commit_timeout, poll)
+                    .await
+                    .map_err(|_| anyhow!("transaction {} was not committed", e.hash))
+                    .and_then(|res| res)
+                    .context("broadcast_tx_commit failed")?
+            }
+            Err(e) => return Err(e).context("broadcast_tx_commit failed"),
         };
-        Ok(response)
+        to_commit_response(response, f)
     }
 }
 
+/// Wait for a transaction broadcast in async or sync mode to be committed, polling for it by hash,
+/// then decode its results the same way as with [`TxCommit`].
+///
+/// The hash is the one in the response of the broadcast.
+pub async fn wait_for_commit<C, F, T>(
+    client: &C,
+    hash: tendermint::Hash,
+    timeout: Duration,
+    f: F,
+) -> anyhow::Result<CommitResponse<T>>
+where
+    C: Client + Sync + Send,
+    F: FnOnce(&DeliverTx) -> anyhow::Result<T>,
+{
+    let response = tokio::time::timeout(
+        timeout,
+        poll_committed_tx(client, hash, COMMIT_POLL_INTERVAL),
+    )
+    .await
+    .map_err(|_| anyhow!("timed out waiting for transaction {hash} to be committed"))??;
+
+    to_commit_response(response, f)
+}
+
+/// Look up a transaction by hash until it's found in a block.
+///
+/// Only keeps polling while the transaction isn't found; any other error is returned.
+async fn poll_committed_tx<C>(
+    client: &C,
+    hash: tendermint::Hash,
+    interval: Duration,
+) -> anyhow::Result<tx_commit::Response>
+where
+    C: Client + Sync + Send,
+{
+    loop {
+        match client.tx(hash, false).await {
+            Ok(res) => {
+                // The transaction could only be included if it passed the check.
+                return Ok(tx_commit::Response {
+                    check_tx: Default::default(),
+                    deliver_tx: res.tx_result,
+                    hash: res.hash,
+                    height: res.height,
+                });
+            }
+            Err(e) if is_tx_not_found(&e) => {
+                tracing::debug!(
+                    error = e.to_string(),
+                    hash = hash.to_string(),
+                    "transaction not committed yet"
+                );
+                tokio::time::sleep(interval).await;
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to look up transaction {hash}"))
+            }
+        }
+    }
+}
+
+/// Decode the return data of a committed transaction, unless it failed.
+fn to_commit_response<F, T>(
+    response: tx_commit::Response,
+    f: F,
+) -> anyhow::Result<CommitResponse<T>>
+where
+    F: FnOnce(&DeliverTx) -> anyhow::Result<T>,
+{
+    // We have a fully `DeliverTx` with default fields even if `CheckTx` indicates failure.
+    let return_data = if response.check_tx.code.is_err() || response.deliver_tx.code.is_err() {
+        None
+    } else {
+        let return_data =
+            f(&response.deliver_tx).context("error decoding data from deliver_tx in commit")?;
+        Some(return_data)
+    };
+    Ok(CommitResponse {
+        response,
+        return_data,
+    })
+}
+
 async fn perform_query<C>(
     client: &C,
     query: FvmQuery,
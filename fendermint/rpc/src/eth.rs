@@ -0,0 +1,187 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! EIP-1559 transactions sent from accounts with a delegated `f410` address,
+//! so that tools built on [ethers] can use the same client as for FVM messages.
+
+use anyhow::{anyhow, bail, Context};
+use ethers_core::types as et;
+use ethers_core::types::transaction::eip2718::TypedTransaction;
+use ethers_core::utils::rlp;
+use fendermint_vm_message::chain::ChainMessage;
+use fendermint_vm_message::conv::from_eth::to_fvm_signed_message;
+use fendermint_vm_message::conv::from_fvm::{to_eth_address, to_eth_signature};
+use fvm_shared::address::Address;
+use fvm_shared::chainid::ChainID;
+
+use crate::signer::Signer;
+
+/// An EIP-1559 transaction along with its Ethereum signature.
+#[derive(Clone, Debug)]
+pub struct SignedEthTransaction {
+    pub tx: et::Eip1559TransactionRequest,
+    pub signature: et::Signature,
+}
+
+impl SignedEthTransaction {
+    /// Sign a transaction, filling in the sender and the chain ID if they are missing.
+    ///
+    /// The signer has to have a delegated Ethereum address.
+    pub async fn sign(
+        signer: &dyn Signer,
+        tx: et::Eip1559TransactionRequest,
+        chain_id: &ChainID,
+    ) -> anyhow::Result<Self> {
+        let tx = with_sender_and_chain_id(tx, &signer.address(), chain_id)?;
+        let sighash = TypedTransaction::Eip1559(tx.clone()).sighash();
+        let signature = signer.sign_digest(&sighash.0).await?;
+        let signature = to_eth_signature(&signature, true)?;
+        Ok(Self { tx, signature })
+    }
+
+    /// Decode a transaction signed by an Ethereum wallet, e.g. the payload of `eth_sendRawTransaction`.
+    ///
+    /// The sender is recovered from the signature.
+    pub fn decode(raw: &[u8]) -> anyhow::Result<Self> {
+        let rlp = rlp::Rlp::new(raw);
+        let (tx, signature) = TypedTransaction::decode_signed(&rlp)
+            .context("failed to decode RLP as signed TypedTransaction")?;
+
+        match tx {
+            TypedTransaction::Eip1559(tx) => Ok(Self { tx, signature }),
+            other => bail!("only EIP-1559 transactions are supported; got {other:?}"),
+        }
+    }
+
+    /// The hash Ethereum tools know the transaction by.
+    pub fn hash(&self) -> et::TxHash {
+        self.typed().hash(&self.signature)
+    }
+
+    /// RLP encoding of the signed transaction, which can be sent with `eth_sendRawTransaction`.
+    pub fn rlp(&self) -> et::Bytes {
+        self.typed().rlp_signed(&self.signature)
+    }
+
+    /// Convert the transaction to a message which can be broadcast to Fendermint.
+    ///
+    /// The signature is checked the way the chain will check it, so we find out early
+    /// if the transaction doesn't survive the conversion, e.g. because it's for another chain.
+    pub fn to_chain_message(&self, chain_id: &ChainID) -> anyhow::Result<ChainMessage> {
+        let signed = to_fvm_signed_message(&self.tx, &self.signature)?;
+        signed
+            .verify(chain_id)
+            .context("invalid signature on the converted message")?;
+        Ok(ChainMessage::Signed(signed))
+    }
+
+    fn typed(&self) -> TypedTransaction {
+        TypedTransaction::Eip1559(self.tx.clone())
+    }
+}
+
+/// Make sure the transaction is sent by the account and on the chain we expect.
+fn with_sender_and_chain_id(
+    mut tx: et::Eip1559TransactionRequest,
+    sender: &Address,
+    chain_id: &ChainID,
+) -> anyhow::Result<et::Eip1559TransactionRequest> {
+    let from = to_eth_address(sender, false)?
+        .ok_or_else(|| anyhow!("not an Ethereum address: {sender}"))?;
+
+    match tx.from {
+        Some(tx_from) if tx_from != from => {
+            bail!("transaction is from {tx_from:?}, but the signer is {from:?}")
+        }
+        _ => tx.from = Some(from),
+    }
+
+    let chain_id: u64 = (*chain_id).into();
+    match tx.chain_id {
+        Some(tx_chain_id) if tx_chain_id.as_u64() != chain_id => {
+            bail!("transaction is for chain {tx_chain_id}, but the client is on chain {chain_id}")
+        }
+        _ => tx.chain_id = Some(chain_id.into()),
+    }
+
+    Ok(tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers_core::types as et;
+    use fendermint_crypto::SecretKey;
+    use fendermint_vm_actor_interface::eam::EthAddress;
+    use fvm_shared::address::Address;
+    use fvm_shared::chainid::ChainID;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::SignedEthTransaction;
+    use crate::signer::{SecretKeySigner, Signer};
+
+    fn eth_signer(seed: u64) -> SecretKeySigner {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let sk = SecretKey::random(&mut rng);
+        let addr = Address::from(EthAddress::from(sk.public_key()));
+        SecretKeySigner::new(sk, addr)
+    }
+
+    fn transfer() -> et::Eip1559TransactionRequest {
+        et::Eip1559TransactionRequest::new()
+            .to(et::H160::repeat_byte(0x11))
+            .nonce(3)
+            .value(1000)
+            .gas(10_000_000)
+            .max_fee_per_gas(100)
+            .max_priority_fee_per_gas(10)
+    }
+
+    #[tokio::test]
+    async fn sign_encode_and_convert() {
+        let signer = eth_signer(1);
+        let chain_id = ChainID::from(1234);
+
+        let signed = SignedEthTransaction::sign(&signer, transfer(), &chain_id)
+            .await
+            .expect("failed to sign transaction");
+
+        assert_eq!(signed.tx.chain_id, Some(1234.into()));
+
+        let decoded = SignedEthTransaction::decode(&signed.rlp()).expect("failed to decode");
+        assert_eq!(decoded.hash(), signed.hash());
+        assert_eq!(decoded.tx.from, signed.tx.from, "sender is recovered");
+
+        let msg = signed
+            .to_chain_message(&chain_id)
+            .expect("should convert to a valid message");
+
+        let fendermint_vm_message::chain::ChainMessage::Signed(msg) = msg else {
+            panic!("expected signed message");
+        };
+        assert_eq!(msg.message.from, signer.address());
+        assert_eq!(msg.message.sequence, 3);
+
+        assert!(
+            signed.to_chain_message(&ChainID::from(1)).is_err(),
+            "signature should not be valid on another chain"
+        );
+    }
+
+    #[tokio::test]
+    async fn reject_mismatching_chain_id_and_non_eth_signer() {
+        let signer = eth_signer(2);
+        let tx = transfer().chain_id(1);
+        assert!(SignedEthTransaction::sign(&signer, tx, &ChainID::from(2))
+            .await
+            .is_err());
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let f1_signer = SecretKeySigner::new_secp256k1(SecretKey::random(&mut rng));
+        assert!(
+            SignedEthTransaction::sign(&f1_signer, transfer(), &ChainID::from(2))
+                .await
+                .is_err()
+        );
+    }
+}
@@ -6,14 +6,22 @@ use base64::{
     engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
 };
 
+pub mod blobs;
 pub mod client;
+pub mod eth;
 pub mod message;
+pub mod nonce;
 pub mod query;
 pub mod response;
+pub mod retry;
+pub mod signer;
+pub mod subscribe;
 pub mod tx;
 
+pub use blobs::BlobsClient;
 pub use client::FendermintClient;
-pub use query::QueryClient;
+pub use query::{PinnedQueryClient, QueryClient};
+pub use subscribe::SubscribeClient;
 pub use tx::TxClient;
 
 /// A [`base64::Engine`] using the [`alphabet::STANDARD`] base64 alphabet
@@ -2,19 +2,24 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::path::Path;
+use std::sync::Arc;
 
 use anyhow::Context;
 use base64::Engine;
 use bytes::Bytes;
+use ethers_core::types::Eip1559TransactionRequest;
 use fendermint_actor_bucket::{GetParams, Method::GetObject};
 use fendermint_crypto::SecretKey;
 use fendermint_vm_actor_interface::{eam, evm};
-use fendermint_vm_message::{chain::ChainMessage, signed::SignedMessage};
+use fendermint_vm_message::chain::ChainMessage;
 use fvm_ipld_encoding::{BytesSer, RawBytes};
 use fvm_shared::{
-    address::Address, chainid::ChainID, econ::TokenAmount, message::Message, MethodNum, METHOD_SEND,
+    address::Address, chainid::ChainID, econ::TokenAmount, message::Message, MethodNum,
+    BLOCK_GAS_LIMIT, METHOD_SEND,
 };
 
+use crate::eth::SignedEthTransaction;
+use crate::signer::{SecretKeySigner, Signer};
 use crate::B64_ENGINE;
 
 /// Factory methods for transaction payload construction.
@@ -35,6 +40,11 @@ impl MessageFactory {
         &self.addr
     }
 
+    /// The sequence of the next message.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
     /// Set the sequence to an arbitrary value.
     pub fn set_sequence(&mut self, sequence: u64) {
         self.sequence = sequence;
@@ -134,25 +144,32 @@ impl MessageFactory {
 /// For those one must use the Ethereum API, with a suitable client library such as [ethers].
 pub struct SignedMessageFactory {
     inner: MessageFactory,
-    sk: SecretKey,
+    signer: Arc<dyn Signer>,
     chain_id: ChainID,
 }
 
 impl SignedMessageFactory {
     /// Create a factor from a secret key and its corresponding address, which could be a delegated one.
     pub fn new(sk: SecretKey, addr: Address, sequence: u64, chain_id: ChainID) -> Self {
-        Self {
-            inner: MessageFactory::new(addr, sequence),
-            sk,
-            chain_id,
-        }
+        Self::with_signer(Arc::new(SecretKeySigner::new(sk, addr)), sequence, chain_id)
     }
 
     /// Treat the secret key as an f1 type account.
     pub fn new_secp256k1(sk: SecretKey, sequence: u64, chain_id: ChainID) -> Self {
-        let pk = sk.public_key();
-        let addr = Address::new_secp256k1(&pk.serialize()).expect("public key is 65 bytes");
-        Self::new(sk, addr, sequence, chain_id)
+        Self::with_signer(
+            Arc::new(SecretKeySigner::new_secp256k1(sk)),
+            sequence,
+            chain_id,
+        )
+    }
+
+    /// Create a factory which leaves the signing to a [`Signer`], e.g. a hardware wallet or a KMS.
+    pub fn with_signer(signer: Arc<dyn Signer>, sequence: u64, chain_id: ChainID) -> Self {
+        Self {
+            inner: MessageFactory::new(signer.address(), sequence),
+            signer,
+            chain_id,
+        }
     }
 
     /// Convenience method to read the secret key from a file, expected to be in Base64 format.
@@ -175,18 +192,31 @@ impl SignedMessageFactory {
         self.inner.address()
     }
 
+    /// The chain ID messages are signed for.
+    pub fn chain_id(&self) -> &ChainID {
+        &self.chain_id
+    }
+
+    /// Set the sequence of the next message, e.g. to one reserved by a [`NonceManager`].
+    ///
+    /// [`NonceManager`]: crate::nonce::NonceManager
+    pub fn set_sequence(&mut self, sequence: u64) {
+        self.inner.set_sequence(sequence)
+    }
+
     /// Transfer tokens to another account.
-    pub fn transfer(
+    pub async fn transfer(
         &mut self,
         to: Address,
         value: TokenAmount,
         gas_params: GasParams,
     ) -> anyhow::Result<ChainMessage> {
         self.transaction(to, METHOD_SEND, Default::default(), value, gas_params)
+            .await
     }
 
     /// Send a message to an actor.
-    pub fn transaction(
+    pub async fn transaction(
         &mut self,
         to: Address,
         method_num: MethodNum,
@@ -197,13 +227,35 @@ impl SignedMessageFactory {
         let message = self
             .inner
             .transaction(to, method_num, params, value, gas_params);
-        let signed = SignedMessage::new_secp256k1(message, &self.sk, &self.chain_id)?;
+        self.sign(message).await
+    }
+
+    /// Sign an EIP-1559 transaction, filling in the sender, the chain ID and, unless given, the nonce.
+    ///
+    /// Only works if the signer has a delegated Ethereum address.
+    pub async fn eth_transaction(
+        &mut self,
+        mut tx: Eip1559TransactionRequest,
+    ) -> anyhow::Result<SignedEthTransaction> {
+        let sequence = tx
+            .nonce
+            .map(|nonce| nonce.as_u64())
+            .unwrap_or_else(|| self.inner.sequence());
+        tx.nonce = Some(sequence.into());
+        let signed = SignedEthTransaction::sign(self.signer.as_ref(), tx, &self.chain_id).await?;
+        self.inner.set_sequence(sequence + 1);
+        Ok(signed)
+    }
+
+    /// Sign a message, e.g. after its gas limit has been changed.
+    pub async fn sign(&self, message: Message) -> anyhow::Result<ChainMessage> {
+        let signed = self.signer.sign_message(message, &self.chain_id).await?;
         let chain = ChainMessage::Signed(signed);
         Ok(chain)
     }
 
     /// Deploy a FEVM contract.
-    pub fn fevm_create(
+    pub async fn fevm_create(
         &mut self,
         contract: Bytes,
         constructor_args: Bytes,
@@ -212,18 +264,20 @@ impl SignedMessageFactory {
     ) -> anyhow::Result<ChainMessage> {
         let initcode = [contract.to_vec(), constructor_args.to_vec()].concat();
         let initcode = RawBytes::serialize(BytesSer(&initcode))?;
-        let message = self.transaction(
-            eam::EAM_ACTOR_ADDR,
-            eam::Method::CreateExternal as u64,
-            initcode,
-            value,
-            gas_params,
-        )?;
+        let message = self
+            .transaction(
+                eam::EAM_ACTOR_ADDR,
+                eam::Method::CreateExternal as u64,
+                initcode,
+                value,
+                gas_params,
+            )
+            .await?;
         Ok(message)
     }
 
     /// Invoke a method on a FEVM contract.
-    pub fn fevm_invoke(
+    pub async fn fevm_invoke(
         &mut self,
         contract: Address,
         calldata: Bytes,
@@ -231,17 +285,21 @@ impl SignedMessageFactory {
         gas_params: GasParams,
     ) -> anyhow::Result<ChainMessage> {
         let calldata = RawBytes::serialize(BytesSer(&calldata))?;
-        let message = self.transaction(
-            contract,
-            evm::Method::InvokeContract as u64,
-            calldata,
-            value,
-            gas_params,
-        )?;
+        let message = self
+            .transaction(
+                contract,
+                evm::Method::InvokeContract as u64,
+                calldata,
+                value,
+                gas_params,
+            )
+            .await?;
         Ok(message)
     }
 
     /// Create a message for a read-only operation.
+    ///
+    /// The message isn't signed, so calls don't have to wait for the signer.
     pub fn fevm_call(
         &mut self,
         contract: Address,
@@ -249,18 +307,7 @@ impl SignedMessageFactory {
         value: TokenAmount,
         gas_params: GasParams,
     ) -> anyhow::Result<Message> {
-        let msg = self.fevm_invoke(contract, calldata, value, gas_params)?;
-
-        let msg = if let ChainMessage::Signed(signed) = msg {
-            signed.into_message()
-        } else {
-            panic!("unexpected message type: {msg:?}");
-        };
-
-        // Roll back the sequence, we don't really want to invoke anything.
-        self.inner.set_sequence(msg.sequence);
-
-        Ok(msg)
+        self.inner.fevm_call(contract, calldata, value, gas_params)
     }
 }
 
@@ -276,3 +323,43 @@ pub struct GasParams {
     /// Gas premium.
     pub gas_premium: TokenAmount,
 }
+
+/// Gas limit signalling that it should be estimated by simulating the message.
+pub const AUTO_GAS_LIMIT: u64 = 0;
+
+impl GasParams {
+    /// Let the client estimate the gas limit by simulating the message, paying the default fees.
+    pub fn auto() -> Self {
+        Self {
+            gas_limit: AUTO_GAS_LIMIT,
+            gas_fee_cap: TokenAmount::default(),
+            gas_premium: TokenAmount::default(),
+        }
+    }
+
+    /// Check whether the gas limit should be estimated.
+    pub fn is_auto(&self) -> bool {
+        self.gas_limit == AUTO_GAS_LIMIT
+    }
+}
+
+/// Pad the gas used in a simulation to account for state changes until the message is executed,
+/// without going over what fits into a block.
+pub fn pad_gas_limit(gas_used: u64, overestimation_rate: f64) -> u64 {
+    let gas_limit = (gas_used as f64 * overestimation_rate) as u64;
+    gas_limit.min(BLOCK_GAS_LIMIT)
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::BLOCK_GAS_LIMIT;
+
+    use super::{pad_gas_limit, GasParams};
+
+    #[test]
+    fn auto_gas_params() {
+        assert!(GasParams::auto().is_auto());
+        assert_eq!(pad_gas_limit(1000, 1.25), 1250);
+        assert_eq!(pad_gas_limit(BLOCK_GAS_LIMIT, 2.0), BLOCK_GAS_LIMIT);
+    }
+}
@@ -0,0 +1,266 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use fvm_shared::address::Address;
+use fvm_shared::error::ExitCode;
+use tendermint::abci::Code;
+use tokio::sync::Mutex;
+
+use fendermint_vm_message::query::FvmQueryHeight;
+
+use crate::query::QueryClient;
+
+/// Hands out the sequence numbers (nonces) of a single signer, so that multiple transactions
+/// can be in flight at the same time, including several in the same block.
+///
+/// The first nonce is taken from the pending state of the chain, which includes the
+/// transactions already in the mempool; after that nonces are reserved locally until
+/// the chain reports a mismatch, at which point they are synced again.
+#[derive(Clone)]
+pub struct NonceManager {
+    addr: Address,
+    state: Arc<Mutex<NonceState>>,
+}
+
+#[derive(Default)]
+struct NonceState {
+    /// The next nonce to hand out, or `None` if it has to be fetched from the chain.
+    next: Option<u64>,
+    /// Nonces reserved by transactions which haven't been checked yet.
+    in_flight: BTreeSet<u64>,
+}
+
+impl NonceManager {
+    pub fn new(addr: Address) -> Self {
+        Self {
+            addr,
+            state: Default::default(),
+        }
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.addr
+    }
+
+    /// Reserve the next nonce, fetching the sequence from the chain if we don't know it yet.
+    pub async fn reserve(&self, client: &impl QueryClient) -> anyhow::Result<u64> {
+        let mut state = self.state.lock().await;
+        let nonce = match state.next {
+            Some(nonce) => nonce,
+            None => self.fetch_sequence(client).await?,
+        };
+        state.next = Some(nonce + 1);
+        state.in_flight.insert(nonce);
+        Ok(nonce)
+    }
+
+    /// Mark a nonce as used by a transaction which the chain accepted.
+    pub async fn confirm(&self, nonce: u64) {
+        self.state.lock().await.in_flight.remove(&nonce);
+    }
+
+    /// Give back a nonce which wasn't used, because the transaction never made it to the mempool.
+    ///
+    /// If it was the last one handed out it can be reused, otherwise the transactions after it
+    /// would be stuck behind the gap, so we sync with the chain before reserving the next one.
+    pub async fn release(&self, nonce: u64) {
+        let mut state = self.state.lock().await;
+        state.in_flight.remove(&nonce);
+        if state.next == Some(nonce + 1) {
+            state.next = Some(nonce);
+        } else {
+            state.next = None;
+        }
+    }
+
+    /// Forget the local nonces and fetch the sequence from the chain again.
+    pub async fn resync(&self, client: &impl QueryClient) -> anyhow::Result<u64> {
+        let mut state = self.state.lock().await;
+        let sequence = self.fetch_sequence(client).await?;
+        tracing::debug!(
+            addr = self.addr.to_string(),
+            sequence,
+            in_flight = state.in_flight.len(),
+            "resynced nonce with the chain"
+        );
+        state.next = Some(sequence);
+        state.in_flight.clear();
+        Ok(sequence)
+    }
+
+    /// Number of nonces reserved by transactions which haven't been confirmed or released yet.
+    pub async fn in_flight(&self) -> usize {
+        self.state.lock().await.in_flight.len()
+    }
+
+    /// Run `f` with a reserved nonce to broadcast a transaction, returning the result code
+    /// of the check along with the response.
+    ///
+    /// If the check fails with a sequence mismatch, the nonces are synced with the chain
+    /// and the transaction is sent again, up to `max_retries` times.
+    pub async fn send<Q, F, Fut, T>(
+        &self,
+        client: &Q,
+        max_retries: u8,
+        mut f: F,
+    ) -> anyhow::Result<T>
+    where
+        Q: QueryClient,
+        F: FnMut(u64) -> Fut,
+        Fut: Future<Output = anyhow::Result<(Code, T)>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let nonce = self.reserve(client).await?;
+            match f(nonce).await {
+                Err(e) => {
+                    self.release(nonce).await;
+                    return Err(e);
+                }
+                Ok((code, res)) if is_sequence_mismatch(code) => {
+                    if attempt == max_retries {
+                        self.release(nonce).await;
+                        return Ok(res);
+                    }
+                    tracing::warn!(
+                        addr = self.addr.to_string(),
+                        nonce,
+                        attempt,
+                        "sequence mismatch; resyncing nonce"
+                    );
+                    attempt += 1;
+                    self.resync(client).await?;
+                }
+                Ok((code, res)) => {
+                    if code.is_ok() {
+                        self.confirm(nonce).await;
+                    } else {
+                        self.release(nonce).await;
+                    }
+                    return Ok(res);
+                }
+            }
+        }
+    }
+
+    /// Fetch the next sequence from the pending state, which accounts for transactions in the mempool.
+    async fn fetch_sequence(&self, client: &impl QueryClient) -> anyhow::Result<u64> {
+        let res = client
+            .actor_state(&self.addr, FvmQueryHeight::Pending)
+            .await
+            .context("failed to get actor state")?;
+
+        match res.value {
+            Some((_, state)) => Ok(state.sequence),
+            None => Err(anyhow!("actor {} cannot be found", self.addr)),
+        }
+    }
+}
+
+/// Check if a transaction was rejected because its nonce didn't match the sender's sequence.
+///
+/// The same code is used when the sender can't cover the gas, in which case syncing
+/// the nonce doesn't hurt, and the retry fails the same way.
+pub fn is_sequence_mismatch(code: Code) -> bool {
+    ExitCode::new(code.value()) == ExitCode::SYS_SENDER_STATE_INVALID
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use async_trait::async_trait;
+    use cid::Cid;
+    use fendermint_vm_message::query::{ActorState, FvmQuery, FvmQueryHeight};
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::error::ExitCode;
+    use tendermint::abci::Code;
+    use tendermint_rpc::endpoint::abci_query::AbciQuery;
+
+    use crate::query::QueryClient;
+
+    use super::NonceManager;
+
+    /// Returns the actor state with a sequence that can be changed by the test.
+    #[derive(Default)]
+    struct MockQueryClient {
+        sequence: AtomicU64,
+    }
+
+    #[async_trait]
+    impl QueryClient for MockQueryClient {
+        async fn perform(&self, _: FvmQuery, _: FvmQueryHeight) -> anyhow::Result<AbciQuery> {
+            let state = ActorState {
+                code: Cid::default(),
+                state: Cid::default(),
+                sequence: self.sequence.load(Ordering::SeqCst),
+                balance: TokenAmount::default(),
+                delegated_address: None,
+            };
+            Ok(AbciQuery {
+                key: fvm_ipld_encoding::to_vec(&100u64)?,
+                value: fvm_ipld_encoding::to_vec(&state)?,
+                ..Default::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn reserve_and_release() {
+        let client = MockQueryClient::default();
+        client.sequence.store(5, Ordering::SeqCst);
+        let nonces = NonceManager::new(Address::new_id(100));
+
+        assert_eq!(nonces.reserve(&client).await.unwrap(), 5);
+        assert_eq!(nonces.reserve(&client).await.unwrap(), 6);
+        assert_eq!(nonces.in_flight().await, 2);
+
+        // The last nonce can be reused.
+        nonces.release(6).await;
+        assert_eq!(nonces.reserve(&client).await.unwrap(), 6);
+
+        // Releasing one in the middle leaves a gap, so it's resynced with the chain.
+        client.sequence.store(7, Ordering::SeqCst);
+        nonces.confirm(6).await;
+        nonces.release(5).await;
+        assert_eq!(nonces.reserve(&client).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn resync_on_sequence_mismatch() {
+        let client = MockQueryClient::default();
+        let nonces = NonceManager::new(Address::new_id(100));
+
+        // Someone else used nonce 0 before us.
+        assert_eq!(nonces.reserve(&client).await.unwrap(), 0);
+        nonces.confirm(0).await;
+        client.sequence.store(3, Ordering::SeqCst);
+
+        let mut attempts = Vec::new();
+        let nonce = nonces
+            .send(&client, 1, |nonce| {
+                attempts.push(nonce);
+                async move {
+                    let code = if nonce == 3 {
+                        Code::Ok
+                    } else {
+                        Code::from(ExitCode::SYS_SENDER_STATE_INVALID.value())
+                    };
+                    Ok((code, nonce))
+                }
+            })
+            .await
+            .expect("should send");
+
+        assert_eq!(nonce, 3);
+        assert_eq!(attempts, vec![1, 3]);
+        assert_eq!(nonces.in_flight().await, 0);
+        assert_eq!(nonces.reserve(&client).await.unwrap(), 4);
+    }
+}
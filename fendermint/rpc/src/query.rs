@@ -9,6 +9,7 @@ use fvm_ipld_encoding::serde::Serialize;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::message::Message;
 use prost::Message as ProstMessage;
+use serde::de::DeserializeOwned;
 use tendermint::abci::response::DeliverTx;
 use tendermint::block::Height;
 use tendermint::v0_37::abci::response;
@@ -23,8 +24,8 @@ use fendermint_vm_message::query::{
 };
 
 use crate::message::{GasParams, MessageFactory};
-use crate::response::decode_os_get;
 use crate::response::encode_data;
+use crate::response::{decode_cbor, decode_os_get};
 
 #[derive(Serialize, Debug, Clone)]
 /// The parsed value from a query, along with the height at which the query was performed.
@@ -45,6 +46,21 @@ pub trait QueryClient: Sync {
         extract_opt(res, |res| Ok(res.value))
     }
 
+    /// Query the contents of a CID from the IPLD store and decode it.
+    async fn ipld_decoded<T>(&self, cid: &Cid, height: FvmQueryHeight) -> anyhow::Result<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        match self.ipld(cid, height).await? {
+            None => Ok(None),
+            Some(bz) => fvm_ipld_encoding::from_slice(&bz)
+                .map(Some)
+                .with_context(|| {
+                    format!("failed to decode {} from IPLD", std::any::type_name::<T>())
+                }),
+        }
+    }
+
     /// Query the the state of an actor.
     async fn actor_state(
         &self,
@@ -60,6 +76,32 @@ pub trait QueryClient: Sync {
         Ok(QueryResponse { height, value })
     }
 
+    /// Query the state of an actor and decode it from the IPLD store.
+    ///
+    /// The state is looked up at the height the actor was found at, so the two are consistent.
+    async fn actor_state_decoded<T>(
+        &self,
+        address: &Address,
+        height: FvmQueryHeight,
+    ) -> anyhow::Result<QueryResponse<Option<(ActorID, T)>>>
+    where
+        T: DeserializeOwned,
+    {
+        let res = self.actor_state(address, height).await?;
+        let height = res.height;
+        let value = match res.value {
+            None => None,
+            Some((id, actor_state)) => {
+                let state = self
+                    .ipld_decoded::<T>(&actor_state.state, FvmQueryHeight::Height(height.value()))
+                    .await?
+                    .ok_or_else(|| anyhow!("state of actor {address} not found at {height}"))?;
+                Some((id, state))
+            }
+        };
+        Ok(QueryResponse { height, value })
+    }
+
     /// Run a message in a read-only fashion.
     async fn call(
         &self,
@@ -75,6 +117,31 @@ pub trait QueryClient: Sync {
         Ok(QueryResponse { height, value })
     }
 
+    /// Run a message in a read-only fashion and decode the CBOR value it returns.
+    async fn call_decoded<T>(
+        &self,
+        message: Message,
+        height: FvmQueryHeight,
+    ) -> anyhow::Result<QueryResponse<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let res = self.call(message, height).await?;
+        if res.value.code.is_err() {
+            return Err(anyhow!(
+                "call failed with exit code {}: {}",
+                res.value.code.value(),
+                res.value.info
+            ));
+        }
+        let value =
+            decode_cbor(&res.value).context("error decoding data from deliver_tx in call")?;
+        Ok(QueryResponse {
+            height: res.height,
+            value,
+        })
+    }
+
     /// Estimate the gas limit of a message.
     async fn estimate_gas(
         &self,
@@ -159,6 +226,45 @@ pub trait QueryClient: Sync {
     async fn perform(&self, query: FvmQuery, height: FvmQueryHeight) -> anyhow::Result<AbciQuery>;
 }
 
+/// Runs every query at the same height, regardless of the height passed to the individual
+/// methods, so that a series of queries sees a consistent state even as new blocks are committed.
+pub struct PinnedQueryClient<C> {
+    inner: C,
+    height: u64,
+}
+
+impl<C: QueryClient> PinnedQueryClient<C> {
+    pub fn new(inner: C, height: u64) -> Self {
+        Self { inner, height }
+    }
+
+    /// Pin the queries to the latest committed height.
+    pub async fn latest(inner: C) -> anyhow::Result<Self> {
+        let res = inner.state_params(FvmQueryHeight::Committed).await?;
+        Ok(Self::new(inner, res.height.value()))
+    }
+
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+#[async_trait]
+impl<C> QueryClient for PinnedQueryClient<C>
+where
+    C: QueryClient + Send,
+{
+    async fn perform(&self, query: FvmQuery, _height: FvmQueryHeight) -> anyhow::Result<AbciQuery> {
+        self.inner
+            .perform(query, FvmQueryHeight::Height(self.height))
+            .await
+    }
+}
+
 /// Extract some value from the query result, unless it's not found or other error.
 fn extract_opt<T, F>(res: AbciQuery, f: F) -> anyhow::Result<Option<T>>
 where
@@ -219,11 +325,58 @@ fn parse_deliver_tx(res: AbciQuery) -> anyhow::Result<DeliverTx> {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use cid::Cid;
+    use fendermint_vm_message::query::{FvmQuery, FvmQueryHeight};
     use tendermint_rpc::endpoint::abci_query::AbciQuery;
 
     use crate::response::decode_fevm_invoke;
 
-    use super::parse_deliver_tx;
+    use super::{parse_deliver_tx, PinnedQueryClient, QueryClient};
+
+    /// Returns the same IPLD value for every query, remembering the heights asked for.
+    struct MockQueryClient {
+        value: Vec<u8>,
+        heights: Mutex<Vec<FvmQueryHeight>>,
+    }
+
+    #[async_trait]
+    impl QueryClient for MockQueryClient {
+        async fn perform(&self, _: FvmQuery, height: FvmQueryHeight) -> anyhow::Result<AbciQuery> {
+            self.heights.lock().unwrap().push(height);
+            Ok(AbciQuery {
+                value: self.value.clone(),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn pinned_queries_use_same_height() {
+        let client = MockQueryClient {
+            value: fvm_ipld_encoding::to_vec(&(1u64, "foo".to_string())).unwrap(),
+            heights: Default::default(),
+        };
+        let client = PinnedQueryClient::new(client, 10);
+
+        let value = client
+            .ipld_decoded::<(u64, String)>(&Cid::default(), FvmQueryHeight::Committed)
+            .await
+            .expect("failed to query")
+            .expect("value should be found");
+
+        assert_eq!(value, (1, "foo".to_string()));
+
+        client
+            .ipld(&Cid::default(), FvmQueryHeight::Height(5))
+            .await
+            .expect("failed to query");
+
+        let heights = client.into_inner().heights.into_inner().unwrap();
+        assert_eq!(heights, vec![FvmQueryHeight::Height(10); 2]);
+    }
 
     #[test]
     fn parse_call_query_response() {
@@ -6,6 +6,7 @@ use bytes::Bytes;
 use fendermint_actor_bucket::Object;
 use fendermint_vm_actor_interface::eam;
 use fvm_ipld_encoding::{BytesDe, RawBytes};
+use serde::de::DeserializeOwned;
 use tendermint::abci::response::DeliverTx;
 
 /// Parse what Tendermint returns in the `data` field of [`DeliverTx`] into bytes.
@@ -33,6 +34,13 @@ pub fn decode_bytes(deliver_tx: &DeliverTx) -> anyhow::Result<RawBytes> {
     decode_data(&deliver_tx.data)
 }
 
+/// Parse what Tendermint returns in the `data` field of [`DeliverTx`] as the CBOR encoded return value of a built-in actor method.
+pub fn decode_cbor<T: DeserializeOwned>(deliver_tx: &DeliverTx) -> anyhow::Result<T> {
+    let data = decode_data(&deliver_tx.data)?;
+    fvm_ipld_encoding::from_slice::<T>(&data)
+        .map_err(|e| anyhow!("error parsing as {}: {e}", std::any::type_name::<T>()))
+}
+
 /// Parse what Tendermint returns in the `data` field of [`DeliverTx`] as [`CreateReturn`].
 pub fn decode_fevm_create(deliver_tx: &DeliverTx) -> anyhow::Result<eam::CreateReturn> {
     let data = decode_data(&deliver_tx.data)?;
@@ -0,0 +1,215 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+use tendermint::crypto::default::Sha256;
+use tendermint::crypto::sha256::Sha256 as _;
+use tendermint_rpc::error::ErrorDetail;
+
+/// Default time to wait for a transaction in the mempool to be committed.
+pub const DEFAULT_COMMIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How to retry broadcasting transactions which failed for reasons other than the transaction itself,
+/// for example because the node couldn't be reached or its mempool was full.
+///
+/// Retries send the exact same bytes, so CometBFT recognises the transaction by its hash
+/// if an earlier attempt made it into the mempool, rather than including it twice.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Maximum number of times to send a transaction again; 0 means no retries.
+    pub max_retries: u32,
+    /// Time to wait between attempts.
+    pub retry_delay: Duration,
+    /// Time to wait for a transaction which is already in the mempool to be committed.
+    pub commit_timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            // Roughly the block creation time.
+            retry_delay: Duration::from_secs(1),
+            commit_timeout: DEFAULT_COMMIT_TIMEOUT,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, retry_delay: Duration) -> Self {
+        Self {
+            max_retries,
+            retry_delay,
+            commit_timeout: DEFAULT_COMMIT_TIMEOUT,
+        }
+    }
+
+    /// Set how long to wait for a transaction which is already in the mempool to be committed.
+    pub fn with_commit_timeout(mut self, commit_timeout: Duration) -> Self {
+        self.commit_timeout = commit_timeout;
+        self
+    }
+}
+
+/// Why broadcasting a transaction failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BroadcastFailure {
+    /// The node couldn't be reached, or its mempool was full; sending again may help.
+    Transient,
+    /// The transaction is already in the mempool, most likely put there by an earlier attempt.
+    AlreadyBroadcast,
+    /// Waiting for the commit timed out; the transaction may still be included in a block.
+    CommitTimeout,
+    /// Anything else, which sending again won't fix.
+    Permanent,
+}
+
+impl BroadcastFailure {
+    pub fn classify(err: &tendermint_rpc::Error) -> Self {
+        match err.detail() {
+            ErrorDetail::Response(_) => Self::classify_response(&err.to_string()),
+            // Transport errors, which tell us nothing about the transaction.
+            _ => Self::Transient,
+        }
+    }
+
+    /// Classify an error returned by CometBFT based on its message, which is all we get.
+    fn classify_response(msg: &str) -> Self {
+        if msg.contains("tx already exists in cache") {
+            Self::AlreadyBroadcast
+        } else if msg.contains("mempool is full") {
+            Self::Transient
+        } else if msg.contains("timed out waiting for tx to be included in a block") {
+            Self::CommitTimeout
+        } else {
+            Self::Permanent
+        }
+    }
+}
+
+/// Whether looking up a transaction by hash failed only because it's not in a block yet.
+pub fn is_tx_not_found(err: &tendermint_rpc::Error) -> bool {
+    match err.detail() {
+        ErrorDetail::Response(_) => is_not_found_response(&err.to_string()),
+        _ => false,
+    }
+}
+
+fn is_not_found_response(msg: &str) -> bool {
+    msg.contains("not found")
+}
+
+/// Error returned when a transaction couldn't be broadcast, carrying its hash
+/// so that callers can look it up if it might still be included in a block.
+#[derive(Debug)]
+pub struct BroadcastError {
+    pub hash: tendermint::Hash,
+    pub failure: BroadcastFailure,
+    pub error: tendermint_rpc::Error,
+}
+
+impl Display for BroadcastError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.failure {
+            BroadcastFailure::AlreadyBroadcast => {
+                write!(f, "transaction {} is already in the mempool", self.hash)
+            }
+            BroadcastFailure::CommitTimeout => write!(
+                f,
+                "timed out waiting for transaction {} to be committed",
+                self.hash
+            ),
+            _ => write!(
+                f,
+                "failed to broadcast transaction {}: {}",
+                self.hash, self.error
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BroadcastError {}
+
+/// Hash the transaction payload the way CometBFT does.
+pub fn tx_hash(data: &[u8]) -> tendermint::Hash {
+    tendermint::Hash::Sha256(Sha256::digest(data))
+}
+
+/// Send a transaction, retrying transient failures according to the policy.
+pub async fn broadcast_with_retry<F, Fut, R>(
+    policy: &RetryPolicy,
+    data: Vec<u8>,
+    send: F,
+) -> Result<R, BroadcastError>
+where
+    F: Fn(Vec<u8>) -> Fut,
+    Fut: Future<Output = Result<R, tendermint_rpc::Error>>,
+{
+    let hash = tx_hash(&data);
+    let mut attempt = 0;
+    loop {
+        match send(data.clone()).await {
+            Ok(res) => return Ok(res),
+            Err(error) => {
+                let failure = BroadcastFailure::classify(&error);
+                if failure != BroadcastFailure::Transient || attempt == policy.max_retries {
+                    return Err(BroadcastError {
+                        hash,
+                        failure,
+                        error,
+                    });
+                }
+                tracing::warn!(
+                    error = error.to_string(),
+                    hash = hash.to_string(),
+                    attempt,
+                    "retry broadcast"
+                );
+                attempt += 1;
+                tokio::time::sleep(policy.retry_delay).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_not_found_response, BroadcastFailure};
+
+    #[test]
+    fn classify_response() {
+        for (msg, failure) in [
+            (
+                "Internal error: tx already exists in cache (code: -32603)",
+                BroadcastFailure::AlreadyBroadcast,
+            ),
+            (
+                "Internal error: mempool is full: number of txs 5000 (max: 5000)",
+                BroadcastFailure::Transient,
+            ),
+            (
+                "Internal error: timed out waiting for tx to be included in a block",
+                BroadcastFailure::CommitTimeout,
+            ),
+            (
+                "Internal error: tx too large. Max size is 1048576, but got 2000000",
+                BroadcastFailure::Permanent,
+            ),
+        ] {
+            assert_eq!(BroadcastFailure::classify_response(msg), failure, "{msg}");
+        }
+    }
+
+    #[test]
+    fn not_found_response() {
+        assert!(is_not_found_response(
+            "Internal error: tx (0A1B2C) not found (code: -32603)"
+        ));
+        assert!(!is_not_found_response(
+            "Internal error: transaction indexing is disabled (code: -32603)"
+        ));
+    }
+}
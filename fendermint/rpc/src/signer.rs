@@ -0,0 +1,321 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use fendermint_crypto::{PublicKey, SecretKey};
+use fendermint_vm_message::signed::{sign_secp256k1, SignedMessage};
+use fvm_shared::address::Address;
+use fvm_shared::chainid::ChainID;
+use fvm_shared::crypto::signature::ops::recover_secp_public_key;
+use fvm_shared::crypto::signature::{Signature, SignatureType, SECP_SIG_LEN};
+use fvm_shared::message::Message;
+use serde::{Deserialize, Serialize};
+
+/// Signs messages on behalf of an account, without the client having to hold its secret key.
+///
+/// Signing is asynchronous, so implementations talking to a device or a remote service
+/// don't block the runtime while waiting for the signature.
+#[async_trait]
+pub trait Signer: Send + Sync {
+    /// The address messages are sent from.
+    fn address(&self) -> Address;
+
+    /// The secp256k1 public key of the account.
+    fn public_key(&self) -> PublicKey;
+
+    /// Sign the digest of a message, returning a 65 byte recoverable secp256k1 signature.
+    async fn sign_digest(&self, digest: &[u8; 32]) -> anyhow::Result<Signature>;
+
+    /// Sign a message the way Fendermint expects it for the given chain.
+    async fn sign_message(
+        &self,
+        message: Message,
+        chain_id: &ChainID,
+    ) -> anyhow::Result<SignedMessage> {
+        let (digest, origin_kind) = SignedMessage::signing_digest(&message, chain_id)
+            .context("failed to calculate signing digest")?;
+        let signature = self.sign_digest(&digest).await?;
+        Ok(SignedMessage::new_unchecked(
+            origin_kind,
+            message,
+            signature,
+        ))
+    }
+}
+
+/// Signer holding the secret key in memory.
+pub struct SecretKeySigner {
+    sk: SecretKey,
+    addr: Address,
+}
+
+impl SecretKeySigner {
+    /// Create a signer from a secret key and its corresponding address, which could be a delegated one.
+    pub fn new(sk: SecretKey, addr: Address) -> Self {
+        Self { sk, addr }
+    }
+
+    /// Treat the secret key as an f1 type account.
+    pub fn new_secp256k1(sk: SecretKey) -> Self {
+        let pk = sk.public_key();
+        let addr = Address::new_secp256k1(&pk.serialize()).expect("public key is 65 bytes");
+        Self::new(sk, addr)
+    }
+}
+
+#[async_trait]
+impl Signer for SecretKeySigner {
+    fn address(&self) -> Address {
+        self.addr
+    }
+
+    fn public_key(&self) -> PublicKey {
+        self.sk.public_key()
+    }
+
+    async fn sign_digest(&self, digest: &[u8; 32]) -> anyhow::Result<Signature> {
+        Ok(sign_secp256k1(&self.sk, digest))
+    }
+}
+
+/// A device or service which holds a secp256k1 key and signs digests with it,
+/// such as a hardware wallet, a cloud KMS or a remote signing service.
+///
+/// [`RemoteSigner`] talks to a signing service over HTTP; Ledger devices are not supported yet.
+#[async_trait]
+pub trait DigestSigner: Send + Sync {
+    /// Sign a digest, returning the 64 byte compact signature followed by the recovery ID.
+    async fn sign(&self, digest: &[u8; 32]) -> anyhow::Result<[u8; SECP_SIG_LEN]>;
+}
+
+/// Request sent to a remote signing service.
+#[derive(Serialize)]
+struct RemoteSignRequest {
+    /// The account whose key should be used, in case the service holds more than one.
+    address: String,
+    /// Hex encoded digest to sign.
+    digest: String,
+}
+
+/// Response expected from a remote signing service.
+#[derive(Deserialize)]
+struct RemoteSignResponse {
+    /// Hex encoded 65 byte recoverable signature, with or without a `0x` prefix.
+    signature: String,
+}
+
+/// [`DigestSigner`] for a signing service which receives a [`RemoteSignRequest`]
+/// as a JSON `POST` and responds with a [`RemoteSignResponse`].
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    url: reqwest::Url,
+    addr: Address,
+    auth_token: Option<String>,
+}
+
+impl RemoteSigner {
+    pub fn new(url: reqwest::Url, addr: Address) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            addr,
+            auth_token: None,
+        }
+    }
+
+    /// Send a bearer token with every request.
+    pub fn with_auth_token(mut self, token: String) -> Self {
+        self.auth_token = Some(token);
+        self
+    }
+}
+
+#[async_trait]
+impl DigestSigner for RemoteSigner {
+    async fn sign(&self, digest: &[u8; 32]) -> anyhow::Result<[u8; SECP_SIG_LEN]> {
+        let request = RemoteSignRequest {
+            address: self.addr.to_string(),
+            digest: hex::encode(digest),
+        };
+
+        let mut builder = self.client.post(self.url.clone()).json(&request);
+        if let Some(ref token) = self.auth_token {
+            builder = builder.bearer_auth(token);
+        }
+
+        let response: RemoteSignResponse = builder
+            .send()
+            .await
+            .context("failed to reach remote signer")?
+            .error_for_status()
+            .context("remote signer returned an error")?
+            .json()
+            .await
+            .context("failed to parse remote signer response")?;
+
+        decode_signature(&response.signature)
+    }
+}
+
+/// Parse a hex encoded recoverable signature.
+fn decode_signature(signature: &str) -> anyhow::Result<[u8; SECP_SIG_LEN]> {
+    let bytes =
+        hex::decode(signature.trim_start_matches("0x")).context("signature is not hex encoded")?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| {
+        anyhow!(
+            "expected {SECP_SIG_LEN} byte signature; got {}",
+            bytes.len()
+        )
+    })
+}
+
+/// Signer delegating to an external [`DigestSigner`], so the secret key never leaves it.
+///
+/// The public key is given upfront, and every signature is checked against it,
+/// so that a misconfigured device doesn't result in transactions the chain rejects.
+pub struct ExternalSigner<D> {
+    signer: D,
+    public_key: PublicKey,
+    addr: Address,
+}
+
+impl<D: DigestSigner> ExternalSigner<D> {
+    pub fn new(signer: D, public_key: PublicKey, addr: Address) -> Self {
+        Self {
+            signer,
+            public_key,
+            addr,
+        }
+    }
+
+    /// Treat the public key as an f1 type account.
+    pub fn new_secp256k1(signer: D, public_key: PublicKey) -> Self {
+        let addr = Address::new_secp256k1(&public_key.serialize()).expect("public key is 65 bytes");
+        Self::new(signer, public_key, addr)
+    }
+}
+
+#[async_trait]
+impl<D: DigestSigner> Signer for ExternalSigner<D> {
+    fn address(&self) -> Address {
+        self.addr
+    }
+
+    fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    async fn sign_digest(&self, digest: &[u8; 32]) -> anyhow::Result<Signature> {
+        let bytes = self
+            .signer
+            .sign(digest)
+            .await
+            .context("external signer failed")?;
+
+        let signature = Signature {
+            sig_type: SignatureType::Secp256k1,
+            bytes: bytes.to_vec(),
+        };
+
+        let recovered = recover_secp_public_key(digest, &bytes)
+            .map_err(|e| anyhow!("failed to recover public key from signature: {e}"))?;
+
+        if recovered != self.public_key.serialize() {
+            return Err(anyhow!(
+                "external signer used a different key than {}",
+                self.addr
+            ));
+        }
+
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use fendermint_crypto::SecretKey;
+    use fvm_shared::address::Address;
+    use fvm_shared::chainid::ChainID;
+    use fvm_shared::crypto::signature::SECP_SIG_LEN;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::message::Message;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::{decode_signature, DigestSigner, ExternalSigner, SecretKeySigner, Signer};
+
+    /// Pretend the key lives on a device.
+    struct Device(SecretKey);
+
+    #[async_trait]
+    impl DigestSigner for Device {
+        async fn sign(&self, digest: &[u8; 32]) -> anyhow::Result<[u8; SECP_SIG_LEN]> {
+            let signature = fendermint_vm_message::signed::sign_secp256k1(&self.0, digest);
+            Ok(signature.bytes.try_into().expect("65 bytes"))
+        }
+    }
+
+    fn message(from: Address) -> Message {
+        Message {
+            version: 0,
+            from,
+            to: Address::new_id(100),
+            sequence: 0,
+            value: TokenAmount::from_atto(1),
+            method_num: 0,
+            params: Default::default(),
+            gas_limit: 10_000_000,
+            gas_fee_cap: TokenAmount::default(),
+            gas_premium: TokenAmount::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn signers_produce_valid_signatures() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let chain_id = ChainID::from(1);
+        let sk = SecretKey::random(&mut rng);
+
+        let local = SecretKeySigner::new_secp256k1(sk.clone());
+        let external = ExternalSigner::new_secp256k1(Device(sk), local.public_key());
+        assert_eq!(local.address(), external.address());
+
+        for signer in [&local as &dyn Signer, &external] {
+            let signed = signer
+                .sign_message(message(signer.address()), &chain_id)
+                .await
+                .expect("failed to sign");
+            signed.verify(&chain_id).expect("signature should be valid");
+        }
+    }
+
+    #[tokio::test]
+    async fn external_signer_checks_key() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let sk = SecretKey::random(&mut rng);
+        let other = SecretKey::random(&mut rng);
+
+        let external = ExternalSigner::new_secp256k1(Device(other), sk.public_key());
+        let res = external
+            .sign_message(message(external.address()), &ChainID::from(1))
+            .await;
+        assert!(
+            res.is_err(),
+            "signature from the wrong key should be rejected"
+        );
+    }
+
+    #[test]
+    fn remote_signature_decoding() {
+        let sig = [7u8; SECP_SIG_LEN];
+        assert_eq!(decode_signature(&hex::encode(sig)).unwrap(), sig);
+        assert_eq!(
+            decode_signature(&format!("0x{}", hex::encode(sig))).unwrap(),
+            sig
+        );
+        assert!(decode_signature(&hex::encode([7u8; 64])).is_err());
+        assert!(decode_signature("not hex").is_err());
+    }
+}
@@ -0,0 +1,305 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use futures::StreamExt;
+use tendermint::abci;
+use tendermint_rpc::event::{Event, EventData};
+use tendermint_rpc::query::{EventType, Query};
+use tendermint_rpc::{SubscriptionClient, Url, WebSocketClient, WebSocketClientUrl};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use fendermint_actor_blobs_shared::BLOBS_ACTOR_ID;
+use fvm_shared::address::Address;
+use fvm_shared::ActorID;
+
+use crate::client::ws_client;
+
+/// Number of decoded events buffered for a slow consumer before we stop reading the socket.
+const EVENT_BUFFER: usize = 1024;
+
+/// An event emitted by an actor, as indexed by CometBFT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmittedEvent {
+    /// Height of the block which emitted the event.
+    pub height: u64,
+    /// ID of the actor which emitted the event.
+    pub emitter_id: ActorID,
+    /// Delegated address of the emitter, if it has one.
+    pub emitter_deleg: Option<Address>,
+    /// The entries of the event, with their values decoded from hex.
+    pub entries: Vec<(String, Vec<u8>)>,
+}
+
+impl EmittedEvent {
+    /// Value of the first entry with a given key.
+    pub fn entry(&self, key: &str) -> Option<&[u8]> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_slice())
+    }
+
+    /// The Ethereum log topics, if the event was emitted as one.
+    pub fn topics(&self) -> Vec<&[u8]> {
+        ["t1", "t2", "t3", "t4"]
+            .into_iter()
+            .map_while(|key| self.entry(key))
+            .collect()
+    }
+
+    /// The Ethereum log data, if the event was emitted as one.
+    pub fn data(&self) -> Option<&[u8]> {
+        self.entry("d")
+    }
+}
+
+/// Decode the events of interest into a typed value.
+pub trait DecodeEvent: Sized {
+    /// Decode an event, or return `None` if it should be skipped.
+    fn decode_event(event: EmittedEvent) -> anyhow::Result<Option<Self>>;
+}
+
+impl DecodeEvent for EmittedEvent {
+    fn decode_event(event: EmittedEvent) -> anyhow::Result<Option<Self>> {
+        Ok(Some(event))
+    }
+}
+
+/// Query for events emitted by an actor in transactions.
+pub fn actor_events_query(emitter: ActorID) -> Query {
+    // `Query::from(EventType::Tx)` doesn't combine well with non-standard keys.
+    Query::eq("event.emitter.id", emitter.to_string())
+}
+
+/// Query for the lifecycle events of blobs, emitted by the blobs actor.
+pub fn blob_events_query() -> Query {
+    actor_events_query(BLOBS_ACTOR_ID)
+}
+
+/// Query for new blocks, which carry the events emitted outside of transactions, e.g. by cron.
+pub fn new_block_query() -> Query {
+    Query::from(EventType::NewBlock)
+}
+
+/// Subscribes to events through the CometBFT WebSocket endpoint.
+///
+/// The connection is expected to drop now and then, in which case it's re-established
+/// in the background and the queries are subscribed to again. Events emitted while
+/// disconnected are not recovered.
+#[derive(Clone)]
+pub struct SubscribeClient {
+    url: WebSocketClientUrl,
+    retry_delay: Duration,
+}
+
+impl SubscribeClient {
+    pub fn new(url: WebSocketClientUrl, retry_delay: Duration) -> Self {
+        Self { url, retry_delay }
+    }
+
+    /// Subscribe to a query and receive the decoded events emitted by actors.
+    ///
+    /// The subscription runs in a background task until the stream is dropped.
+    pub fn subscribe<T>(&self, query: Query) -> ReceiverStream<T>
+    where
+        T: DecodeEvent + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(EVENT_BUFFER);
+        let this = self.clone();
+        tokio::spawn(async move { this.run_subscription(query, tx).await });
+        ReceiverStream::new(rx)
+    }
+
+    /// Keep subscribing to the query until the consumer goes away.
+    async fn run_subscription<T>(self, query: Query, tx: mpsc::Sender<T>)
+    where
+        T: DecodeEvent,
+    {
+        loop {
+            let client = self.connect().await;
+
+            match client.subscribe(query.clone()).await {
+                Err(e) => {
+                    tracing::warn!(
+                        error = e.to_string(),
+                        query = query.to_string(),
+                        "failed to subscribe to CometBFT events"
+                    );
+                }
+                Ok(mut subscription) => {
+                    while let Some(res) = subscription.next().await {
+                        let event = match res {
+                            Ok(event) => event,
+                            Err(e) => {
+                                tracing::warn!(
+                                    error = e.to_string(),
+                                    query = query.to_string(),
+                                    "CometBFT subscription failed"
+                                );
+                                break;
+                            }
+                        };
+                        for event in to_emitted_events(&event) {
+                            match T::decode_event(event) {
+                                Ok(Some(event)) => {
+                                    if tx.send(event).await.is_err() {
+                                        let _ = client.close();
+                                        return;
+                                    }
+                                }
+                                Ok(None) => {}
+                                Err(e) => {
+                                    tracing::warn!(error = e.to_string(), "failed to decode event");
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            let _ = client.close();
+
+            if tx.is_closed() {
+                return;
+            }
+            tracing::info!(
+                query = query.to_string(),
+                "resubscribing to CometBFT events in {}s...",
+                self.retry_delay.as_secs()
+            );
+            tokio::time::sleep(self.retry_delay).await;
+        }
+    }
+
+    /// Try connecting repeatedly until it succeeds, then start the driver.
+    async fn connect(&self) -> WebSocketClient {
+        let url: Url = self.url.clone().into();
+        loop {
+            match ws_client(url.clone()).await {
+                Ok((client, driver)) => {
+                    tokio::spawn(async move { driver.run().await });
+                    return client;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        error = e.to_string(),
+                        url = url.to_string(),
+                        "failed to connect to CometBFT WebSocket; retrying in {}s...",
+                        self.retry_delay.as_secs()
+                    );
+                    tokio::time::sleep(self.retry_delay).await;
+                }
+            }
+        }
+    }
+}
+
+/// Collect the actor events from a transaction or a block.
+pub fn to_emitted_events(event: &Event) -> Vec<EmittedEvent> {
+    let (height, events) = match &event.data {
+        EventData::Tx { tx_result } => (
+            tx_result.height as u64,
+            tx_result.result.events.iter().collect::<Vec<_>>(),
+        ),
+        EventData::NewBlock {
+            block: Some(block),
+            result_begin_block,
+            result_end_block,
+        } => (
+            block.header().height.value(),
+            result_begin_block
+                .iter()
+                .flat_map(|r| r.events.iter())
+                .chain(result_end_block.iter().flat_map(|r| r.events.iter()))
+                .collect(),
+        ),
+        _ => return Vec::new(),
+    };
+
+    events
+        .into_iter()
+        .filter(|e| e.kind == "event")
+        .filter_map(|e| match to_emitted_event(height, e) {
+            Ok(event) => Some(event),
+            Err(e) => {
+                tracing::warn!(error = e.to_string(), "failed to parse actor event");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Parse the attributes that `to_events` in the app turns the actor events into.
+fn to_emitted_event(height: u64, event: &abci::Event) -> anyhow::Result<EmittedEvent> {
+    let mut emitter_id = None;
+    let mut emitter_deleg = None;
+    let mut entries = Vec::new();
+
+    for attr in &event.attributes {
+        match attr.key.as_str() {
+            "emitter.id" => {
+                emitter_id = Some(
+                    attr.value
+                        .parse::<ActorID>()
+                        .context("invalid emitter ID")?,
+                )
+            }
+            "emitter.deleg" => {
+                emitter_deleg = Some(
+                    attr.value
+                        .parse::<Address>()
+                        .context("invalid emitter address")?,
+                )
+            }
+            key => {
+                let value = hex::decode(&attr.value)
+                    .with_context(|| format!("failed to decode {key} as hex"))?;
+                entries.push((key.to_string(), value));
+            }
+        }
+    }
+
+    Ok(EmittedEvent {
+        height,
+        emitter_id: emitter_id.ok_or_else(|| anyhow!("cannot find the 'emitter.id' key"))?,
+        emitter_deleg,
+        entries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use tendermint::abci::{Event, EventAttribute};
+
+    use super::to_emitted_event;
+
+    #[test]
+    fn parse_emitted_event() {
+        let attr = |key: &str, value: &str| EventAttribute {
+            key: key.to_string(),
+            value: value.to_string(),
+            index: true,
+        };
+        let event = Event::new(
+            "event",
+            vec![
+                attr("emitter.id", "66"),
+                attr("t1", "aabb"),
+                attr("t2", "ccdd"),
+                attr("d", "0102"),
+            ],
+        );
+
+        let event = to_emitted_event(10, &event).expect("failed to parse event");
+
+        assert_eq!(event.height, 10);
+        assert_eq!(event.emitter_id, 66);
+        assert_eq!(event.emitter_deleg, None);
+        assert_eq!(event.topics(), vec![&[0xaa, 0xbb][..], &[0xcc, 0xdd][..]]);
+        assert_eq!(event.data(), Some(&[1, 2][..]));
+    }
+}
@@ -6,6 +6,7 @@ use std::marker::PhantomData;
 use anyhow::Context;
 use async_trait::async_trait;
 use bytes::Bytes;
+use ethers_core::types::Eip1559TransactionRequest;
 use fendermint_vm_message::query::{FvmQueryHeight, GasEstimate};
 use tendermint::abci::response::DeliverTx;
 use tendermint_rpc::endpoint::broadcast::{tx_async, tx_commit, tx_sync};
@@ -18,6 +19,7 @@ use fvm_shared::MethodNum;
 use fendermint_vm_actor_interface::eam;
 use fendermint_vm_message::chain::ChainMessage;
 
+use crate::eth::SignedEthTransaction;
 use crate::message::{GasParams, SignedMessageFactory};
 use crate::query::{QueryClient, QueryResponse};
 use crate::response::{decode_bytes, decode_fevm_create, decode_fevm_invoke};
@@ -47,7 +49,8 @@ pub trait TxClient<M: BroadcastMode = TxCommit>: BoundClient + Send + Sync {
         gas_params: GasParams,
     ) -> anyhow::Result<M::Response<()>> {
         let mf = self.message_factory_mut();
-        let msg = mf.transfer(to, value, gas_params)?;
+        let msg = mf.transfer(to, value, gas_params).await?;
+        let msg = self.fill_gas_limit(msg).await?;
         let fut = self.perform(msg, |_| Ok(()));
         let res = fut.await?;
         Ok(res)
@@ -63,7 +66,10 @@ pub trait TxClient<M: BroadcastMode = TxCommit>: BoundClient + Send + Sync {
         gas_params: GasParams,
     ) -> anyhow::Result<M::Response<RawBytes>> {
         let mf = self.message_factory_mut();
-        let msg = mf.transaction(to, method_num, params, value, gas_params)?;
+        let msg = mf
+            .transaction(to, method_num, params, value, gas_params)
+            .await?;
+        let msg = self.fill_gas_limit(msg).await?;
         let fut = self.perform(msg, decode_bytes);
         let res = fut.await?;
         Ok(res)
@@ -78,7 +84,10 @@ pub trait TxClient<M: BroadcastMode = TxCommit>: BoundClient + Send + Sync {
         gas_params: GasParams,
     ) -> anyhow::Result<M::Response<eam::CreateReturn>> {
         let mf = self.message_factory_mut();
-        let msg = mf.fevm_create(contract, constructor_args, value, gas_params)?;
+        let msg = mf
+            .fevm_create(contract, constructor_args, value, gas_params)
+            .await?;
+        let msg = self.fill_gas_limit(msg).await?;
         let fut = self.perform(msg, decode_fevm_create);
         let res = fut.await?;
         Ok(res)
@@ -93,12 +102,49 @@ pub trait TxClient<M: BroadcastMode = TxCommit>: BoundClient + Send + Sync {
         gas_params: GasParams,
     ) -> anyhow::Result<M::Response<Vec<u8>>> {
         let mf = self.message_factory_mut();
-        let msg = mf.fevm_invoke(contract, calldata, value, gas_params)?;
+        let msg = mf
+            .fevm_invoke(contract, calldata, value, gas_params)
+            .await?;
+        let msg = self.fill_gas_limit(msg).await?;
         let fut = self.perform(msg, decode_fevm_invoke);
         let res = fut.await?;
         Ok(res)
     }
 
+    /// Send an EIP-1559 transaction from an account with a delegated Ethereum address.
+    ///
+    /// Missing fields are filled in the same way as for FVM messages; leave the gas empty to estimate it.
+    async fn eth_transaction(
+        &mut self,
+        tx: Eip1559TransactionRequest,
+    ) -> anyhow::Result<M::Response<RawBytes>> {
+        let mf = self.message_factory_mut();
+        let msg = mf
+            .eth_transaction(tx)
+            .await?
+            .to_chain_message(mf.chain_id())?;
+        let msg = self.fill_gas_limit(msg).await?;
+        let fut = self.perform(msg, decode_bytes);
+        let res = fut.await?;
+        Ok(res)
+    }
+
+    /// Send a transaction signed by an Ethereum wallet, e.g. with [ethers], in RLP format.
+    async fn eth_raw_transaction(&mut self, raw: &[u8]) -> anyhow::Result<M::Response<RawBytes>> {
+        let chain_id = *self.message_factory_mut().chain_id();
+        let msg = SignedEthTransaction::decode(raw)?.to_chain_message(&chain_id)?;
+        let fut = self.perform(msg, decode_bytes);
+        let res = fut.await?;
+        Ok(res)
+    }
+
+    /// Estimate the gas limit of messages created with [`GasParams::auto`] and sign them again.
+    ///
+    /// Clients which can't simulate messages send them as they are.
+    async fn fill_gas_limit(&mut self, msg: ChainMessage) -> anyhow::Result<ChainMessage> {
+        Ok(msg)
+    }
+
     async fn perform<F, T>(&self, msg: ChainMessage, f: F) -> anyhow::Result<M::Response<T>>
     where
         F: FnOnce(&DeliverTx) -> anyhow::Result<T> + Sync + Send,
@@ -58,6 +58,7 @@ pub mod evm;
 pub mod gas_market;
 pub mod init;
 pub mod ipc;
+pub mod machine_registry;
 pub mod multisig;
 pub mod placeholder;
 pub mod recall_config;
@@ -0,0 +1,4 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+define_id!(MACHINE_REGISTRY { id: 71 });
@@ -18,16 +18,22 @@ use async_stm::atomically;
 use async_trait::async_trait;
 use fendermint_actor_blob_reader::{
     CloseReadRequestParams, GetOpenReadRequestsParams, GetReadRequestStatusParams,
-    Method::{CloseReadRequest, GetOpenReadRequests, GetReadRequestStatus, SetReadRequestPending},
+    Method::{
+        CloseReadRequest, ExpireReadRequests, GetOpenReadRequests, GetReadRequestStatus,
+        SetReadRequestPending,
+    },
     ReadRequestStatus, SetReadRequestPendingParams, BLOB_READER_ACTOR_ADDR,
 };
 use fendermint_actor_blobs_shared::{
     params::{
-        FinalizeBlobParams, GetAddedBlobsParams, GetBlobStatusParams, GetStatsReturn,
-        SetBlobPendingParams,
+        FinalizeBlobParams, GetAddedBlobsParams, GetBlobParams, GetBlobStatusParams,
+        GetStatsReturn, SetBlobPendingParams,
+    },
+    state::{BlobInfo, BlobStatus, SubscriptionId},
+    Method::{
+        DebitAccounts, FinalizeBlob, GetAddedBlobs, GetBlob, GetBlobStatus, GetStats,
+        SetBlobPending,
     },
-    state::{BlobStatus, SubscriptionId},
-    Method::{DebitAccounts, FinalizeBlob, GetAddedBlobs, GetBlobStatus, GetStats, SetBlobPending},
 };
 use fendermint_tracing::emit;
 use fendermint_vm_actor_interface::{blob_reader, blobs, ipc, system};
@@ -415,7 +421,8 @@ where
                 // Check if the read request is closed, i.e., not open or pending.
                 // If a request is not found in actor state but exists in the pool,
                 // it is considered closed.
-                if get_read_request_status(&mut state, item.id)?.is_none() {
+                let status = get_read_request_status(&mut state, item.id)?;
+                if !status.is_some_and(|status| status.is_active()) {
                     tracing::debug!(request_id = ?item.id, "read request already fulfilled on chain; removing from pool");
                     atomically(|| chain_env.read_request_pool.remove_task(item)).await;
                     continue;
@@ -569,6 +576,21 @@ where
                         return Ok(false);
                     }
 
+                    // The size is passed to the actor as the resolver-verified size, so it
+                    // must be the declared size the validators downloaded against
+                    let declared_size = with_state_transaction(&mut state, |state| {
+                        get_blob_size(state, blob.hash)
+                    })?;
+                    if declared_size.is_some_and(|size| size != blob.size) {
+                        tracing::debug!(
+                            hash = %blob.hash,
+                            declared = ?declared_size,
+                            message = blob.size,
+                            "blob size mismatch; rejecting proposal"
+                        );
+                        return Ok(false);
+                    }
+
                     // Remove from pool if locally resolved
                     let item = BlobPoolItem {
                         subscriber: blob.subscriber,
@@ -854,6 +876,10 @@ where
                     let msg =
                         create_implicit_message(to, method_num, Default::default(), gas_limit);
                     let (apply_ret, emitters) = state.execute_implicit(msg)?;
+
+                    // Expire read requests that were not fulfilled in time
+                    expire_read_requests(&mut state)?;
+
                     let ret = FvmApplyRet {
                         apply_ret,
                         from,
@@ -927,6 +953,9 @@ where
                         hash,
                         id: blob.id,
                         status,
+                        // The resolver only votes a blob as resolved if the downloaded size
+                        // matches this size
+                        size: Some(blob.size),
                     };
                     let params = RawBytes::serialize(params)?;
                     let msg = create_implicit_message(to, method_num, params, gas_limit);
@@ -1179,7 +1208,10 @@ fn get_added_blobs<DB>(
 where
     DB: Blockstore + Clone + 'static + Send + Sync,
 {
-    let params = GetAddedBlobsParams(size);
+    let params = GetAddedBlobsParams {
+        size,
+        start_key: None,
+    };
     let params = RawBytes::serialize(params)?;
     let msg = create_implicit_message(
         blobs::BLOBS_ACTOR_ADDR,
@@ -1190,7 +1222,9 @@ where
     let (apply_ret, _) = state.execute_implicit(msg)?;
 
     let data: bytes::Bytes = apply_ret.msg_receipt.return_data.to_vec().into();
-    fvm_ipld_encoding::from_slice::<Vec<AddedBlobItem>>(&data)
+    // The return is a tuple of blobs and the next key; only the first page is needed here
+    fvm_ipld_encoding::from_slice::<(Vec<AddedBlobItem>, Option<Hash>)>(&data)
+        .map(|(blobs, _)| blobs)
         .map_err(|e| anyhow!("error parsing added blobs: {e}"))
 }
 
@@ -1224,6 +1258,30 @@ where
         .map_err(|e| anyhow!("error parsing blob status: {e}"))
 }
 
+/// Returns the declared size of a blob from on-chain state, if the blob exists.
+fn get_blob_size<DB>(
+    state: &mut FvmExecState<ReadOnlyBlockstore<DB>>,
+    hash: Hash,
+) -> anyhow::Result<Option<u64>>
+where
+    DB: Blockstore + Clone + 'static + Send + Sync,
+{
+    let hash = fendermint_actor_blobs_shared::state::Hash(*hash.as_bytes());
+    let params = RawBytes::serialize(GetBlobParams(hash))?;
+    let msg = create_implicit_message(
+        blobs::BLOBS_ACTOR_ADDR,
+        GetBlob as u64,
+        params,
+        fvm_shared::BLOCK_GAS_LIMIT,
+    );
+    let (apply_ret, _) = state.execute_implicit(msg)?;
+
+    let data: bytes::Bytes = apply_ret.msg_receipt.return_data.to_vec().into();
+    let blob = fvm_ipld_encoding::from_slice::<Option<BlobInfo>>(&data)
+        .map_err(|e| anyhow!("error parsing blob: {e}"))?;
+    Ok(blob.map(|blob| blob.size))
+}
+
 /// Check if a blob is in added state, by reading its on-chain state.
 fn is_blob_added<DB>(
     state: &mut FvmExecState<ReadOnlyBlockstore<DB>>,
@@ -1399,6 +1457,34 @@ where
         .map_err(|e| anyhow!("error parsing read request status: {e}"))
 }
 
+fn expire_read_requests<DB>(state: &mut FvmExecState<DB>) -> anyhow::Result<()>
+where
+    DB: Blockstore + Clone + 'static + Send + Sync,
+{
+    let msg = create_implicit_message(
+        blob_reader::BLOB_READER_ACTOR_ADDR,
+        ExpireReadRequests as u64,
+        Default::default(),
+        fvm_shared::BLOCK_GAS_LIMIT,
+    );
+
+    let (apply_ret, _) = state.execute_implicit(msg)?;
+    if let Some(err) = apply_ret.failure_info {
+        tracing::error!("failed to expire read requests: {}", err);
+        return Ok(());
+    }
+
+    let return_data: bytes::Bytes = apply_ret.msg_receipt.return_data.to_vec().into();
+    let expired = fvm_ipld_encoding::from_slice::<Vec<fendermint_actor_blobs_shared::state::Hash>>(
+        &return_data,
+    )
+    .map_err(|e| anyhow!("error parsing expired read requests: {e}"))?;
+    if !expired.is_empty() {
+        tracing::debug!(count = expired.len(), "expired read requests");
+    }
+    Ok(())
+}
+
 fn set_read_request_pending<DB>(
     state: &mut FvmExecState<DB>,
     id: Hash,
@@ -21,7 +21,7 @@ use fendermint_vm_actor_interface::eam::EthAddress;
 use fendermint_vm_actor_interface::ipc::IPC_CONTRACTS;
 use fendermint_vm_actor_interface::{
     account, activity, adm, blob_reader, blobs, burntfunds, chainmetadata, cron, eam, gas_market,
-    init, ipc, recall_config, reward, system, EMPTY_ARR,
+    init, ipc, machine_registry, recall_config, reward, system, EMPTY_ARR,
 };
 use fendermint_vm_core::Timestamp;
 use fendermint_vm_genesis::{ActorMeta, Collateral, Genesis, Power, PowerScale, Validator};
@@ -427,10 +427,11 @@ impl GenesisBuilder {
             .context("failed to create chainmetadata actor")?;
 
         // Initialize the recall config actor.
-        let recall_config_state = fendermint_actor_recall_config::State {
-            admin: None,
-            config: fendermint_actor_recall_config_shared::RecallConfig::default(),
-        };
+        let recall_config_state = fendermint_actor_recall_config::State::new(
+            &state.store(),
+            fendermint_actor_recall_config_shared::RecallConfig::default(),
+            0,
+        )?;
         state
             .create_custom_actor(
                 fendermint_actor_recall_config::ACTOR_NAME,
@@ -464,6 +465,33 @@ impl GenesisBuilder {
             )
             .context("failed to create blob reader actor")?;
 
+        // Initialize the machine registry actor.
+        // The machine codes are listed in a fixed order to keep the genesis state deterministic.
+        let machine_registry_codes = ["bucket", "timehub"]
+            .into_iter()
+            .map(|name| {
+                let code = state
+                    .custom_actor_manifest
+                    .code_by_name(name)
+                    .unwrap_or_else(|| panic!("actor {} not in manifest", name));
+                (
+                    fil_actor_adm::Kind::from_str(name).expect("failed to parse adm machine name"),
+                    *code,
+                )
+            })
+            .collect();
+        let machine_registry_state =
+            fendermint_actor_machine_registry::State::new(&state.store(), machine_registry_codes)?;
+        state
+            .create_custom_actor(
+                fendermint_actor_machine_registry::MACHINE_REGISTRY_ACTOR_NAME,
+                machine_registry::MACHINE_REGISTRY_ACTOR_ID,
+                &machine_registry_state,
+                TokenAmount::zero(),
+                None,
+            )
+            .context("failed to create machine registry actor")?;
+
         let eam_state = fendermint_actor_eam::State::new(
             state.store(),
             PermissionModeParams::from(genesis.eam_permission_mode),
@@ -10,7 +10,9 @@ use crate::observe::{
 use async_stm::{atomically, atomically_or_err, queues::TQueueLike};
 use fendermint_vm_topdown::voting::VoteTally;
 use ipc_api::subnet_id::SubnetID;
-use ipc_ipld_resolver::{Client, ResolverIroh, ResolverIrohReadRequest, ValidatorKey, VoteRecord};
+use ipc_ipld_resolver::{
+    BlobAnnouncement, Client, ResolverIroh, ResolverIrohReadRequest, ValidatorKey, VoteRecord,
+};
 use ipc_observability::emit;
 
 use iroh::blobs::Hash;
@@ -104,10 +106,24 @@ fn start_resolve<V>(
         tracing::debug!(hash = %task.hash(), "starting iroh blob resolve");
         match task.task_type() {
             TaskType::ResolveBlob { source, size } => {
-                match client
+                let res = match client
                     .resolve_iroh(task.hash(), size, source.id.into())
                     .await
                 {
+                    // The source may be offline, but other validators may have the blob already.
+                    Ok(Err(e)) => {
+                        tracing::debug!(
+                            hash = %task.hash(),
+                            error = e.to_string(),
+                            "failed to resolve iroh blob from its source, trying subnet peers"
+                        );
+                        client
+                            .resolve_iroh_from_subnet(task.hash(), size, subnet_id.clone())
+                            .await
+                    }
+                    res => res,
+                };
+                match res {
                     Err(e) => {
                         tracing::error!(
                             error = e.to_string(),
@@ -119,14 +135,13 @@ fn start_resolve<V>(
                     Ok(Ok(())) => {
                         tracing::debug!(hash = %task.hash(), "iroh blob resolved");
                         atomically(|| task.set_resolved()).await;
-                        if add_own_vote(
+                        if announce_own_outcome(
                             task.hash(),
                             client,
                             vote_tally,
                             key,
                             subnet_id,
                             true,
-                            to_vote,
                         )
                         .await
                         {
@@ -144,14 +159,13 @@ fn start_resolve<V>(
                         // If we fail to re-enqueue the task, cast a "failure" vote.
                         // And emit a failure event.
                         if !reenqueue(task.clone(), queue, retry_delay).await
-                            && add_own_vote(
+                            && announce_own_outcome(
                                 task.hash(),
                                 client,
                                 vote_tally,
                                 key,
                                 subnet_id,
                                 false,
-                                to_vote,
                             )
                             .await
                         {
@@ -273,6 +287,74 @@ where
     }
 }
 
+/// Add our own vote about a blob to the tally, then announce the outcome to the other
+/// validators, telling them where to download the blob from if we resolved it.
+async fn announce_own_outcome<V>(
+    hash: Hash,
+    client: Client<V>,
+    vote_tally: VoteTally,
+    key: Keypair,
+    subnet_id: SubnetID,
+    resolved: bool,
+) -> bool
+where
+    V: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+    let location = if resolved {
+        match client.locate_iroh(hash).await {
+            Ok(location) => location,
+            Err(e) => {
+                tracing::warn!(
+                    hash = %hash,
+                    error = e.to_string(),
+                    "failed to locate resolved blob; announcing without location"
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let announcement = BlobAnnouncement {
+        hash,
+        resolved,
+        location,
+    };
+    match VoteRecord::signed(&key, subnet_id, announcement) {
+        Ok(announcement) => {
+            let validator_key = ValidatorKey::from(key.public());
+            let res = atomically_or_err(|| {
+                vote_tally.add_blob_vote(validator_key.clone(), hash.as_bytes().to_vec(), resolved)
+            })
+            .await;
+
+            match res {
+                Ok(added) => {
+                    if added {
+                        // Send our own announcement to peers
+                        if let Err(e) = client.publish_blob_announcement(announcement) {
+                            tracing::error!(
+                                error = e.to_string(),
+                                "failed to publish blob announcement"
+                            );
+                            return false;
+                        }
+                    }
+                    true
+                }
+                Err(e) => {
+                    tracing::error!(error = e.to_string(), "failed to handle own vote");
+                    false
+                }
+            }
+        }
+        Err(e) => {
+            tracing::error!(error = e.to_string(), "failed to sign blob announcement");
+            false
+        }
+    }
+}
+
 async fn reenqueue(task: ResolveTask, queue: ResolveQueue, retry_delay: Duration) -> bool {
     if atomically(|| task.add_attempt()).await {
         tracing::error!(
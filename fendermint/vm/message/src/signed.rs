@@ -107,13 +107,8 @@ impl SignedMessage {
         sk: &SecretKey,
         chain_id: &ChainID,
     ) -> Result<Self, SignedMessageError> {
-        let (signature, origin_kind) = match Self::signable(&message, chain_id)? {
-            Signable::Ethereum((hash, _)) => (sign_eth(sk, hash), OriginKind::EthereumEIP1559),
-            Signable::Regular(data) => (sign_regular(sk, &data), OriginKind::Fvm),
-            Signable::RegularFromEth((data, _)) => {
-                (sign_regular(sk, &data), OriginKind::EthereumEIP1559)
-            }
-        };
+        let (digest, origin_kind) = Self::signing_digest(&message, chain_id)?;
+        let signature = sign_secp256k1(sk, &digest);
         Ok(Self {
             origin_kind,
             message,
@@ -121,6 +116,25 @@ impl SignedMessage {
         })
     }
 
+    /// Calculate the hash a secp256k1 key has to sign, along with the kind of message it results in.
+    ///
+    /// This allows the signature to be created outside this process, e.g. by a hardware wallet,
+    /// and then put together with [`SignedMessage::new_unchecked`].
+    pub fn signing_digest(
+        message: &Message,
+        chain_id: &ChainID,
+    ) -> Result<([u8; 32], OriginKind), SignedMessageError> {
+        let digest = match Self::signable(message, chain_id)? {
+            // Sign a transaction pre-image in the same way Ethereum clients would sign it.
+            Signable::Ethereum((hash, _)) => (hash.0, OriginKind::EthereumEIP1559),
+            Signable::Regular(data) => (blake2b_256(&data), OriginKind::Fvm),
+            Signable::RegularFromEth((data, _)) => {
+                (blake2b_256(&data), OriginKind::EthereumEIP1559)
+            }
+        };
+        Ok(digest)
+    }
+
     /// Calculate the CID of an FVM message.
     pub fn cid(message: &Message) -> Result<Cid, fvm_ipld_encoding::Error> {
         crate::cid(message)
@@ -325,23 +339,16 @@ impl SignedMessage {
     }
 }
 
-/// Sign a transaction pre-image using Blake2b256, in a way that [Signature::verify] expects it.
-fn sign_regular(sk: &SecretKey, data: &[u8]) -> Signature {
-    let hash: [u8; 32] = blake2b_simd::Params::new()
+/// Hash a transaction pre-image using Blake2b256, in a way that [Signature::verify] expects it.
+fn blake2b_256(data: &[u8]) -> [u8; 32] {
+    blake2b_simd::Params::new()
         .hash_length(32)
         .to_state()
         .update(data)
         .finalize()
         .as_bytes()
         .try_into()
-        .unwrap();
-
-    sign_secp256k1(sk, &hash)
-}
-
-/// Sign a transaction pre-image in the same way Ethereum clients would sign it.
-fn sign_eth(sk: &SecretKey, hash: et::H256) -> Signature {
-    sign_secp256k1(sk, &hash.0)
+        .unwrap()
 }
 
 /// Turn a [`ChainID`] into bytes. Uses big-endian encoding.
@@ -0,0 +1,183 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use libp2p::PeerId;
+
+/// Length of the period over which the quota of a peer applies.
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Number of peers to track before forgetting the ones inactive for a day.
+const MAX_TRACKED_PEERS: usize = 10_000;
+
+/// Bytes of content exchanged with a peer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PeerBandwidth {
+    /// Bytes served to the peer.
+    pub sent: u64,
+    /// Bytes fetched from the peer.
+    pub received: u64,
+}
+
+struct PeerUsage {
+    total: PeerBandwidth,
+    /// Start of the current quota period.
+    period_start: Instant,
+    /// Bytes served to the peer in the current quota period.
+    period_sent: u64,
+    last_active: Instant,
+}
+
+/// Account for the bytes exchanged with each peer, optionally limiting how much
+/// we serve a peer per day, so that nodes open to the public can't be drained.
+///
+/// Peers are forgotten after a day of inactivity, once there are many of them.
+pub struct BandwidthTracker {
+    daily_quota: Option<u64>,
+    peers: HashMap<PeerId, PeerUsage>,
+}
+
+impl BandwidthTracker {
+    /// Create a tracker with a daily quota of bytes served per peer; 0 means no quota.
+    pub fn new(daily_quota: u64) -> Self {
+        Self {
+            daily_quota: (daily_quota > 0).then_some(daily_quota),
+            peers: Default::default(),
+        }
+    }
+
+    pub fn record_sent(&mut self, peer_id: PeerId, bytes: usize) {
+        self.record_sent_at(peer_id, bytes, Instant::now())
+    }
+
+    /// Same as [`BandwidthTracker::record_sent`] but allows passing in the time, for testing.
+    pub fn record_sent_at(&mut self, peer_id: PeerId, bytes: usize, now: Instant) {
+        let usage = self.usage_mut(peer_id, now);
+        usage.total.sent += bytes as u64;
+        usage.period_sent += bytes as u64;
+    }
+
+    pub fn record_received(&mut self, peer_id: PeerId, bytes: usize) {
+        let usage = self.usage_mut(peer_id, Instant::now());
+        usage.total.received += bytes as u64;
+    }
+
+    /// Check whether we can still serve the peer in the current quota period.
+    pub fn has_quota_left(&self, peer_id: &PeerId) -> bool {
+        self.has_quota_left_at(peer_id, Instant::now())
+    }
+
+    /// Same as [`BandwidthTracker::has_quota_left`] but allows passing in the time, for testing.
+    pub fn has_quota_left_at(&self, peer_id: &PeerId, now: Instant) -> bool {
+        let Some(quota) = self.daily_quota else {
+            return true;
+        };
+        match self.peers.get(peer_id) {
+            Some(usage) if now.duration_since(usage.period_start) < DAY => {
+                usage.period_sent < quota
+            }
+            _ => true,
+        }
+    }
+
+    /// Total bytes exchanged with a peer since we started tracking it.
+    pub fn bandwidth(&self, peer_id: &PeerId) -> PeerBandwidth {
+        self.peers
+            .get(peer_id)
+            .map(|usage| usage.total)
+            .unwrap_or_default()
+    }
+
+    /// Total bytes exchanged with every tracked peer.
+    pub fn bandwidth_of_peers(&self) -> Vec<(PeerId, PeerBandwidth)> {
+        self.peers
+            .iter()
+            .map(|(peer_id, usage)| (*peer_id, usage.total))
+            .collect()
+    }
+
+    /// Get the usage of a peer, starting a new quota period if the last one is over.
+    fn usage_mut(&mut self, peer_id: PeerId, now: Instant) -> &mut PeerUsage {
+        if !self.peers.contains_key(&peer_id) && self.peers.len() >= MAX_TRACKED_PEERS {
+            self.peers
+                .retain(|_, usage| now.duration_since(usage.last_active) < DAY);
+        }
+        let usage = self.peers.entry(peer_id).or_insert_with(|| PeerUsage {
+            total: PeerBandwidth::default(),
+            period_start: now,
+            period_sent: 0,
+            last_active: now,
+        });
+        if now.duration_since(usage.period_start) >= DAY {
+            usage.period_start = now;
+            usage.period_sent = 0;
+        }
+        usage.last_active = now;
+        usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use libp2p::PeerId;
+
+    use super::{BandwidthTracker, PeerBandwidth, DAY};
+
+    #[test]
+    fn account_per_peer() {
+        let mut tracker = BandwidthTracker::new(0);
+        let peer_a = PeerId::random();
+        let peer_b = PeerId::random();
+
+        tracker.record_sent(peer_a, 100);
+        tracker.record_sent(peer_a, 50);
+        tracker.record_received(peer_a, 10);
+        tracker.record_received(peer_b, 20);
+
+        assert_eq!(
+            tracker.bandwidth(&peer_a),
+            PeerBandwidth {
+                sent: 150,
+                received: 10
+            }
+        );
+        assert_eq!(
+            tracker.bandwidth(&peer_b),
+            PeerBandwidth {
+                sent: 0,
+                received: 20
+            }
+        );
+        assert_eq!(tracker.bandwidth_of_peers().len(), 2);
+        assert!(tracker.has_quota_left(&peer_a), "no quota");
+    }
+
+    #[test]
+    fn daily_quota() {
+        let mut tracker = BandwidthTracker::new(1000);
+        let peer_a = PeerId::random();
+        let peer_b = PeerId::random();
+        let now = Instant::now();
+
+        tracker.record_sent_at(peer_a, 600, now);
+        assert!(tracker.has_quota_left_at(&peer_a, now));
+        tracker.record_sent_at(peer_a, 600, now);
+        assert!(!tracker.has_quota_left_at(&peer_a, now), "quota exhausted");
+        assert!(
+            tracker.has_quota_left_at(&peer_b, now),
+            "others can be served"
+        );
+
+        let tomorrow = now + DAY + Duration::from_secs(1);
+        assert!(
+            tracker.has_quota_left_at(&peer_a, tomorrow),
+            "quota resets after a day"
+        );
+        tracker.record_sent_at(peer_a, 600, tomorrow);
+        assert!(tracker.has_quota_left_at(&peer_a, tomorrow));
+        assert_eq!(tracker.bandwidth(&peer_a).sent, 1800, "totals are kept");
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Request-response protocol to find subnet peers whose Iroh node has a blob,
+//! so that it can be downloaded from them when its original source is offline.
+use iroh::blobs::Hash;
+use iroh::net::NodeAddr;
+use libp2p::request_response::{self, ProtocolSupport};
+use libp2p::StreamProtocol;
+use serde::{Deserialize, Serialize};
+
+use crate::vote_record::SignedVoteRecord;
+
+/// Ask a peer whether its Iroh node has a blob, identified by the hash of its hash sequence.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlobRequest {
+    pub hash: Hash,
+}
+
+/// Where the blob can be downloaded from, if the peer has all of it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlobResponse {
+    pub location: Option<BlobLocation>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlobLocation {
+    /// Address of the Iroh node of the peer.
+    pub node_addr: NodeAddr,
+    /// Size of the user blob, to be checked against the expected size before downloading.
+    pub size: u64,
+}
+
+/// Outcome of a validator's attempt to resolve a blob, gossiped to the other validators
+/// of the subnet, so they can download it from the announcer instead of looking for it,
+/// and tally the outcome towards the finalization of the blob.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlobAnnouncement {
+    pub hash: Hash,
+    pub resolved: bool,
+    /// Where the blob can be downloaded from, if it was resolved.
+    pub location: Option<BlobLocation>,
+}
+
+pub type SignedBlobAnnouncement = SignedVoteRecord<BlobAnnouncement>;
+
+pub type Behaviour = request_response::json::Behaviour<BlobRequest, BlobResponse>;
+pub type Event = request_response::Event<BlobRequest, BlobResponse>;
+
+/// Create the behaviour with a protocol name specific to the network.
+pub fn new(network_name: &str) -> Behaviour {
+    let protocol_name = format!("/ipc/{network_name}/iroh-blob/1.0.0");
+    let protocol_name = StreamProtocol::try_from_owned(protocol_name).expect("valid protocol name");
+    request_response::json::Behaviour::new(
+        [(protocol_name, ProtocolSupport::Full)],
+        request_response::Config::default(),
+    )
+}
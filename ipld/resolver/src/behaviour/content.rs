@@ -8,9 +8,11 @@ use std::{
 };
 
 use crate::{
+    bandwidth::{BandwidthTracker, PeerBandwidth},
     limiter::{RateLimit, RateLimiter},
     observe,
 };
+use ipc_api::subnet_id::SubnetID;
 use ipc_observability::emit;
 use libipld::{store::StoreParams, Cid};
 use libp2p::{
@@ -23,7 +25,9 @@ use libp2p::{
     },
     Multiaddr, PeerId,
 };
-use libp2p_bitswap::{Bitswap, BitswapConfig, BitswapEvent, BitswapResponse, BitswapStore};
+use libp2p_bitswap::{
+    Bitswap, BitswapConfig, BitswapEvent, BitswapResponse, BitswapStore, InboundRequest,
+};
 use log::debug;
 use prometheus::Registry;
 
@@ -74,6 +78,77 @@ pub struct Config {
     ///
     /// 0 means no limit.
     pub rate_limit_period: Duration,
+    /// Number of requests a single remote peer can make in a time period.
+    ///
+    /// 0 means no limit.
+    pub request_rate_limit_per_peer: u32,
+    /// Number of requests the providers of a subnet can make together in a time period.
+    /// Peers which aren't known to provide any subnet share a single limit.
+    ///
+    /// 0 means no limit.
+    pub request_rate_limit_per_subnet: u32,
+    /// Length of the time period at which the request limits fill.
+    ///
+    /// 0 means no limit.
+    pub request_rate_limit_period: Duration,
+    /// Number of bytes a single remote peer can be served in a day.
+    ///
+    /// 0 means no limit.
+    pub peer_daily_quota_bytes: u64,
+}
+
+/// Limit the number of requests served per peer and per subnet.
+struct RequestLimiter {
+    per_peer: Option<RateLimit>,
+    per_subnet: Option<RateLimit>,
+    peers: RateLimiter<PeerId>,
+    /// Keyed by subnet ID; `None` is shared by peers not known to provide any subnet.
+    subnets: RateLimiter<Option<String>>,
+}
+
+impl RequestLimiter {
+    fn new(config: &Config) -> Self {
+        let period = config.request_rate_limit_period;
+        let limit = |requests: u32| {
+            if requests == 0 || period.is_zero() {
+                None
+            } else {
+                Some(RateLimit::new(requests, period))
+            }
+        };
+        Self {
+            per_peer: limit(config.request_rate_limit_per_peer),
+            per_subnet: limit(config.request_rate_limit_per_subnet),
+            peers: RateLimiter::new(period),
+            subnets: RateLimiter::new(period),
+        }
+    }
+
+    /// Count a request from a peer providing the given subnets.
+    ///
+    /// Returns the name of the exhausted limit if the request should be refused.
+    fn check(&mut self, peer_id: PeerId, subnet_ids: &[SubnetID]) -> Option<&'static str> {
+        if let Some(ref limit) = self.per_peer {
+            if !self.peers.add(limit, peer_id, 1) {
+                return Some("peer");
+            }
+        }
+        if let Some(ref limit) = self.per_subnet {
+            let mut keys = subnet_ids
+                .iter()
+                .map(|id| Some(id.to_string()))
+                .collect::<Vec<_>>();
+            if keys.is_empty() {
+                keys.push(None);
+            }
+            for key in keys {
+                if !self.subnets.add(limit, key, 1) {
+                    return Some("subnet");
+                }
+            }
+        }
+        None
+    }
 }
 
 /// Behaviour built on [`Bitswap`] to resolve IPLD content from [`Cid`] to raw bytes.
@@ -86,6 +161,11 @@ pub struct Behaviour<P: StoreParams> {
     rate_limiter: RateLimiter<Multiaddr>,
     rate_limit_period: Duration,
     rate_limit: Option<RateLimit>,
+    /// Subnets the peers are known to provide, to apply the per-subnet request limit.
+    peer_subnets: HashMap<PeerId, Vec<SubnetID>>,
+    request_limiter: RequestLimiter,
+    /// Bytes exchanged with each peer, with the daily quota of what we serve them.
+    bandwidth: BandwidthTracker,
     outbox: VecDeque<Event>,
 }
 
@@ -94,7 +174,13 @@ impl<P: StoreParams> Behaviour<P> {
     where
         S: BitswapStore<Params = P>,
     {
-        let bitswap = Bitswap::new(BitswapConfig::default(), store);
+        let request_limiter = RequestLimiter::new(&config);
+        let bitswap_config = BitswapConfig {
+            // Always intercepted, to account for the bytes exchanged with each peer.
+            intercept_requests: true,
+            ..Default::default()
+        };
+        let bitswap = Bitswap::new(bitswap_config, store);
         let rate_limit = if config.rate_limit_bytes == 0 || config.rate_limit_period.is_zero() {
             None
         } else {
@@ -109,6 +195,9 @@ impl<P: StoreParams> Behaviour<P> {
             rate_limiter: RateLimiter::new(config.rate_limit_period),
             rate_limit_period: config.rate_limit_period,
             rate_limit,
+            peer_subnets: Default::default(),
+            request_limiter,
+            bandwidth: BandwidthTracker::new(config.peer_daily_quota_bytes),
             outbox: Default::default(),
         }
     }
@@ -145,8 +234,58 @@ impl<P: StoreParams> Behaviour<P> {
         self.inner.sync(cid, peers, [].into_iter())
     }
 
+    /// Set the subnets a peer is known to provide, which share the per-subnet request limit.
+    pub fn set_peer_subnets(&mut self, peer_id: PeerId, subnet_ids: Vec<SubnetID>) {
+        if subnet_ids.is_empty() {
+            self.peer_subnets.remove(&peer_id);
+        } else {
+            self.peer_subnets.insert(peer_id, subnet_ids);
+        }
+    }
+
+    /// Serve a request from a remote peer, unless it exceeds any of the limits.
+    fn handle_request(&mut self, request: InboundRequest) {
+        let peer_id = request.peer_id;
+        let subnet_ids = self
+            .peer_subnets
+            .get(&peer_id)
+            .map(|ids| ids.as_slice())
+            .unwrap_or_default();
+
+        let mut limited = self.request_limiter.check(peer_id, subnet_ids);
+        if limited.is_none() && !self.bandwidth.has_quota_left(&peer_id) {
+            limited = Some("quota");
+        }
+        if limited.is_none() && !self.check_rate_limit(&peer_id, &request.request.cid) {
+            limited = Some("bytes");
+        }
+
+        match limited {
+            None => {
+                emit(observe::ContentEvent::RequestServed(peer_id));
+                self.inner.accept(request)
+            }
+            Some(limit) => {
+                debug!("rate limiting {peer_id} by the {limit} limit");
+                emit(observe::ContentEvent::RateLimited(peer_id, limit));
+                self.inner.reject(request)
+            }
+        }
+    }
+
+    /// Total bytes exchanged with each peer we have recently served or fetched content.
+    pub fn bandwidth_of_peers(&self) -> Vec<(PeerId, PeerBandwidth)> {
+        self.bandwidth.bandwidth_of_peers()
+    }
+
+    /// Cancel a running resolution, e.g. because it lost a race against another peer.
+    pub fn cancel(&mut self, query_id: QueryId) {
+        if self.inner.cancel(query_id) {
+            emit(observe::ResolveEvent::Completed);
+        }
+    }
+
     /// Check whether the peer has already exhaused their rate limit.
-    #[allow(dead_code)]
     fn check_rate_limit(&mut self, peer_id: &PeerId, cid: &Cid) -> bool {
         if let Some(ref rate_limit) = self.rate_limit {
             if let Some(addr) = self.peer_addresses.get(peer_id).cloned() {
@@ -221,49 +360,6 @@ impl<P: StoreParams> NetworkBehaviour for Behaviour<P> {
         connection_id: ConnectionId,
         event: THandlerOutEvent<Self>,
     ) {
-        // TODO: `request_response::handler` is now private, so we cannot pattern match on the handler event.
-        // By the looks of the only way to access the request event is to let it go right into the RR protocol
-        // wrapped by the Bitswap behaviour and let it raise an event, however we will not see that event here.
-        // I'm not sure what we can do without moving rate limiting into the bitswap library itself, because
-        // what we did here relied on the ability to redirect the channels inside the request, but if the event
-        // itself is private to the `request_response` protocol there's nothing I can do.
-        // match event {
-
-        //     request_response::handler::Event::Request {
-        //         request_id,
-        //         request,
-        //         sender,
-        //     } if self.rate_limit.is_some() => {
-        //         if !self.check_rate_limit(&peer_id, &request.cid) {
-        //             warn!("rate limiting {peer_id}");
-        //             stats::CONTENT_RATE_LIMITED.inc();
-        //             return;
-        //         }
-        //         // We need to hijack the response channel to record the size, otherwise it goes straight to the handler.
-        //         let (tx, rx) = libp2p::futures::channel::oneshot::channel();
-        //         let event = request_response::Event::Request {
-        //             request_id,
-        //             request,
-        //             sender: tx,
-        //         };
-
-        //         self.inner
-        //             .on_connection_handler_event(peer_id, connection_id, event);
-
-        //         let forward = Event::BitswapForward {
-        //             peer_id,
-        //             response_rx: rx,
-        //             response_tx: sender,
-        //         };
-        //         self.outbox.push_back(forward);
-        //     }
-        //     _ => self
-        //         .inner
-        //         .on_connection_handler_event(peer_id, connection_id, event),
-        // }
-
-        // debug!("BITSWAP CONNECTION HANDLER EVENT: {event:?}");
-
         self.inner
             .on_connection_handler_event(peer_id, connection_id, event)
     }
@@ -333,6 +429,16 @@ impl<P: StoreParams> NetworkBehaviour for Behaviour<P> {
             match ev {
                 ToSwarm::GenerateEvent(ev) => match ev {
                     BitswapEvent::Progress(_, _) => {}
+                    BitswapEvent::Request(request) => self.handle_request(request),
+                    BitswapEvent::BlockSent(peer_id, bytes) => {
+                        emit(observe::ContentEvent::BlockSent(peer_id, bytes));
+                        self.rate_limit_used(peer_id, bytes);
+                        self.bandwidth.record_sent(peer_id, bytes);
+                    }
+                    BitswapEvent::BlockReceived(peer_id, bytes) => {
+                        emit(observe::ContentEvent::BlockReceived(peer_id, bytes));
+                        self.bandwidth.record_received(peer_id, bytes);
+                    }
                     BitswapEvent::Complete(id, result) => {
                         emit(observe::ResolveEvent::Completed);
                         let out = Event::Complete(id, result);
@@ -378,9 +484,36 @@ fn select_non_ephemeral(mut addr: Multiaddr) -> Multiaddr {
 
 #[cfg(test)]
 mod tests {
-    use libp2p::Multiaddr;
+    use std::time::Duration;
+
+    use ipc_api::subnet_id::SubnetID;
+    use libp2p::{Multiaddr, PeerId};
+
+    use super::{select_non_ephemeral, Config, RequestLimiter};
 
-    use super::select_non_ephemeral;
+    #[test]
+    fn request_limits() {
+        let config = Config {
+            rate_limit_bytes: 0,
+            rate_limit_period: Duration::ZERO,
+            request_rate_limit_per_peer: 2,
+            request_rate_limit_per_subnet: 3,
+            request_rate_limit_period: Duration::from_secs(60),
+        };
+        let mut limiter = RequestLimiter::new(&config);
+        let subnet_id = SubnetID::new_root(1);
+        let (peer1, peer2) = (PeerId::random(), PeerId::random());
+        let subnets = [subnet_id];
+
+        assert_eq!(limiter.check(peer1, &subnets), None);
+        assert_eq!(limiter.check(peer1, &subnets), None);
+        assert_eq!(limiter.check(peer1, &subnets), Some("peer"));
+        assert_eq!(limiter.check(peer2, &subnets), None);
+        assert_eq!(limiter.check(peer2, &subnets), Some("subnet"));
+
+        // Peers without subnets share a separate limit.
+        assert_eq!(limiter.check(PeerId::random(), &[]), None);
+    }
 
     #[test]
     fn non_ephemeral_addr() {
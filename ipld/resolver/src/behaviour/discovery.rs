@@ -3,29 +3,44 @@
 // SPDX-License-Identifier: MIT
 use std::{
     cmp,
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     task::{Context, Poll},
     time::Duration,
 };
 
 use super::NetworkConfig;
 use crate::observe;
+use crate::peer_store::{PeerStore, PeerStoreConfig};
+use ipc_api::subnet_id::SubnetID;
 use ipc_observability::emit;
 use libp2p::{
     core::Endpoint,
     identify::Info,
-    kad::{self, store::MemoryStore},
+    kad::{
+        self,
+        store::{MemoryStore, RecordStore},
+    },
+    mdns,
     multiaddr::Protocol,
     swarm::{
         behaviour::toggle::{Toggle, ToggleConnectionHandler},
         derive_prelude::FromSwarm,
-        ConnectionDenied, ConnectionId, NetworkBehaviour, THandler, THandlerInEvent,
-        THandlerOutEvent, ToSwarm,
+        dial_opts::{DialOpts, PeerCondition},
+        CloseConnection, ConnectionDenied, ConnectionId, NetworkBehaviour, THandler,
+        THandlerInEvent, THandlerOutEvent, ToSwarm,
     },
     Multiaddr, PeerId, StreamProtocol,
 };
 use log::{debug, warn};
-use tokio::time::Interval;
+use tokio::time::{Instant, Interval};
+
+/// Minimum time between looking up the providers of the same subnet in the DHT.
+const MIN_PROVIDER_LOOKUP_INTERVAL: Duration = Duration::from_secs(30);
+/// Maximum number of Identify addresses being verified at the same time.
+const MAX_PENDING_VERIFICATIONS: usize = 100;
+/// Maximum number of addresses of the same peer being verified at the same time,
+/// so a peer can't make us dial lots of addresses on its behalf.
+const MAX_PENDING_VERIFICATIONS_PER_PEER: usize = 4;
 // NOTE: The Discovery behaviour is largely based on what exists in Forest. If it ain't broken...
 // NOTE: Not sure if emitting events is going to be useful yet, but for now it's an example of having one.
 
@@ -38,6 +53,9 @@ pub enum Event {
 
     /// Event emitted when a peer is removed from the routing table.
     Removed(PeerId),
+
+    /// Event emitted when a DHT lookup found peers which provide a subnet.
+    ProvidersFound(SubnetID, Vec<PeerId>),
 }
 
 /// Configuration for [`discovery::Behaviour`].
@@ -51,6 +69,23 @@ pub struct Config {
     pub target_connections: usize,
     /// Option to disable Kademlia, for example in a fixed static network.
     pub enable_kademlia: bool,
+    /// Option to discover peers on the local network with mDNS, e.g. in local devnets and CI
+    /// clusters, without having to configure static addresses.
+    pub enable_mdns: bool,
+    /// Option to remember discovered peers on disk, so they can be dialed again after a restart
+    /// without having to rediscover them from the static addresses.
+    pub peer_store: Option<PeerStoreConfig>,
+    /// Option to publish provider records for the subnets we serve into the DHT, and to look up
+    /// providers there when gossip told us about too few of them. Ignored unless Kademlia is enabled.
+    pub enable_provider_records: bool,
+    /// Interval between republishing our provider records; they expire after twice as long.
+    pub provider_record_interval: Duration,
+    /// Number of known providers of a subnet below which we look for more in the DHT.
+    pub min_providers: usize,
+    /// Option to dial the addresses peers claim to listen on through Identify before adding
+    /// them to Kademlia, so they can't poison the routing table with unreachable addresses.
+    /// Can be disabled in test networks where all addresses are reachable anyway.
+    pub verify_addresses: bool,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -61,6 +96,10 @@ pub enum ConfigError {
     InvalidBootstrapAddress(Multiaddr),
     #[error("no bootstrap address")]
     NoBootstrapAddress,
+    #[error("failed to start mDNS: {0}")]
+    Mdns(std::io::Error),
+    #[error("failed to load peer store: {0}")]
+    PeerStore(anyhow::Error),
 }
 
 /// Discovery behaviour, periodically running a random lookup with Kademlia to find new peers.
@@ -77,14 +116,44 @@ pub struct Behaviour {
     protocol_name: StreamProtocol,
     /// Kademlia behaviour, if enabled.
     inner: Toggle<kad::Behaviour<MemoryStore>>,
+    /// mDNS behaviour, if enabled.
+    ///
+    /// It doesn't use its connection handlers, so it's only driven through swarm events and polling.
+    mdns: Toggle<mdns::tokio::Behaviour>,
+    /// Address book of previously discovered peers, if enabled.
+    peer_store: Option<PeerStore>,
+    /// Interval between flushing the peer store to disk.
+    flush_interval: Option<Interval>,
     /// Number of current connections.
     num_connections: usize,
     /// Number of connections where further lookups are paused.
     target_connections: usize,
+    /// Whether lookups were paused at runtime, regardless of the number of connections.
+    lookups_paused: bool,
     /// Interval between random lookups.
     lookup_interval: Interval,
     /// Buffer incoming identify requests until we have finished the bootstrap.
     bootstrap_buffer: Option<Vec<(PeerId, Info)>>,
+    /// Network name, to derive the DHT keys of subnets.
+    network_name: String,
+    /// Whether we publish and look up subnet provider records in the DHT.
+    provider_records: bool,
+    /// Number of known providers of a subnet below which we look for more in the DHT.
+    min_providers: usize,
+    /// Subnets we announced in the DHT.
+    provided_subnets: Vec<SubnetID>,
+    /// Running provider lookups, with the subnet they are about and when they started.
+    provider_queries: HashMap<kad::QueryId, (SubnetID, Instant)>,
+    /// When we last looked up the providers of a subnet, to avoid flooding the DHT.
+    provider_lookups: HashMap<SubnetID, Instant>,
+    /// Whether Identify addresses are dialed before they are added to Kademlia.
+    verify_addresses: bool,
+    /// Dial-back connections in progress, with the address they are verifying.
+    verifications: HashMap<ConnectionId, (PeerId, Multiaddr)>,
+    /// Dial-back attempts to start.
+    verification_dials: VecDeque<DialOpts>,
+    /// Dial-back connections to close after they succeeded.
+    verified_connections: VecDeque<(PeerId, ConnectionId)>,
     /// Events to return when polled.
     outbox: VecDeque<Event>,
 }
@@ -113,6 +182,23 @@ impl Behaviour {
             }
         }
 
+        let peer_store = match dc.peer_store {
+            Some(config) => Some(PeerStore::load(config).map_err(ConfigError::PeerStore)?),
+            None => None,
+        };
+        // Peers remembered from a previous run, best first.
+        let stored_addresses = peer_store
+            .iter()
+            .flat_map(|store| store.peers())
+            .filter(|(peer_id, _)| *peer_id != local_peer_id)
+            .flat_map(|(peer_id, record)| {
+                record
+                    .addresses
+                    .iter()
+                    .map(move |addr| (peer_id, addr.clone()))
+            })
+            .collect::<Vec<_>>();
+
         let mut outbox = VecDeque::new();
         let protocol_name = format!("/ipc/{}/kad/1.0.0", nc.network_name);
         let protocol_name =
@@ -124,8 +210,14 @@ impl Behaviour {
             let mut kad_config = kad::Config::default();
             kad_config.set_protocol_names(vec![protocol_name.clone()]);
 
+            if dc.enable_provider_records {
+                kad_config.set_provider_publication_interval(Some(dc.provider_record_interval));
+                kad_config.set_provider_record_ttl(Some(dc.provider_record_interval * 2));
+            }
+
             // Disable inserting records into the memory store, so peers cannot send `PutRecord`
-            // messages to store content in the memory of our node.
+            // messages to store content in the memory of our node. Provider records are
+            // inserted manually, if enabled; the store limits how many of them we keep.
             kad_config.set_record_filtering(kad::StoreInserts::FilterBoth);
 
             let store = MemoryStore::new(local_peer_id);
@@ -138,8 +230,9 @@ impl Behaviour {
 
             // Bootstrap from the seeds. The first seed to stand up might have nobody to bootstrap from,
             // although ideally there would be at least another peer, so we can easily restart it and come back.
-            if !static_addresses.is_empty() {
-                for (peer_id, addr) in static_addresses.iter() {
+            // Remembered peers can be used to bootstrap as well, in case the seeds are unavailable.
+            if !static_addresses.is_empty() || !stored_addresses.is_empty() {
+                for (peer_id, addr) in static_addresses.iter().chain(stored_addresses.iter()) {
                     kademlia.add_address(peer_id, addr.clone());
                 }
                 kademlia
@@ -156,25 +249,176 @@ impl Behaviour {
             for (peer_id, _) in static_addresses.iter() {
                 outbox.push_back(Event::Added(*peer_id))
             }
+            for (peer_id, _) in stored_addresses.iter() {
+                if !outbox
+                    .iter()
+                    .any(|e| matches!(e, Event::Added(id) if id == peer_id))
+                {
+                    outbox.push_back(Event::Added(*peer_id))
+                }
+            }
+            None
+        };
+
+        let mdns_opt = if dc.enable_mdns {
+            Some(
+                mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id)
+                    .map_err(ConfigError::Mdns)?,
+            )
+        } else {
             None
         };
 
         Ok(Self {
+            provider_records: dc.enable_provider_records && kademlia_opt.is_some(),
             peer_id: nc.local_peer_id(),
             static_addresses,
             protocol_name,
             inner: kademlia_opt.into(),
+            mdns: mdns_opt.into(),
+            flush_interval: peer_store
+                .as_ref()
+                .map(|store| tokio::time::interval(store.flush_interval())),
+            peer_store,
             lookup_interval: tokio::time::interval(Duration::from_secs(1)),
             outbox,
             num_connections: 0,
             bootstrap_buffer,
             target_connections: dc.target_connections,
+            network_name: nc.network_name,
+            min_providers: dc.min_providers,
+            provided_subnets: Vec::new(),
+            provider_queries: Default::default(),
+            provider_lookups: Default::default(),
+            lookups_paused: false,
+            verify_addresses: dc.verify_addresses,
+            verifications: Default::default(),
+            verification_dials: Default::default(),
+            verified_connections: Default::default(),
         })
     }
 
+    /// Add a static address at runtime, e.g. when the bootstrap nodes are rotated.
+    pub fn add_static_address(&mut self, peer_id: PeerId, addr: Multiaddr) {
+        if peer_id == self.peer_id
+            || self
+                .static_addresses
+                .iter()
+                .any(|(id, a)| *id == peer_id && *a == addr)
+        {
+            return;
+        }
+        self.static_addresses.push((peer_id, addr.clone()));
+        if let Some(kademlia) = self.inner.as_mut() {
+            kademlia.add_address(&peer_id, addr);
+        } else {
+            self.outbox.push_back(Event::Added(peer_id));
+        }
+    }
+
+    /// Remove all static addresses of a peer at runtime.
+    pub fn remove_static_addresses(&mut self, peer_id: &PeerId) {
+        if !self.is_static(*peer_id) {
+            return;
+        }
+        self.static_addresses.retain(|(id, _)| id != peer_id);
+        if let Some(kademlia) = self.inner.as_mut() {
+            kademlia.remove_peer(peer_id);
+        }
+        self.outbox.push_back(Event::Removed(*peer_id));
+    }
+
+    /// Change the number of connections at which point we pause further lookups.
+    pub fn set_target_connections(&mut self, target_connections: usize) {
+        self.target_connections = target_connections;
+    }
+
+    /// Pause or resume lookups, e.g. while the network is being reconfigured.
+    pub fn set_lookups_paused(&mut self, paused: bool) {
+        self.lookups_paused = paused;
+    }
+
+    /// Key of the provider records of a subnet in the DHT.
+    fn provider_key(&self, subnet_id: &SubnetID) -> kad::RecordKey {
+        kad::RecordKey::new(&format!("/ipc/{}/subnet/{}", self.network_name, subnet_id))
+    }
+
+    /// Set the subnets we announce as a provider of in the DHT.
+    pub fn set_provided_subnets(&mut self, subnet_ids: Vec<SubnetID>) {
+        for subnet_id in std::mem::take(&mut self.provided_subnets) {
+            if !subnet_ids.contains(&subnet_id) {
+                self.stop_providing(&subnet_id);
+            }
+        }
+        for subnet_id in subnet_ids {
+            self.add_provided_subnet(subnet_id);
+        }
+    }
+
+    /// Announce ourselves as a provider of a subnet in the DHT; Kademlia republishes the record periodically.
+    pub fn add_provided_subnet(&mut self, subnet_id: SubnetID) {
+        if !self.provider_records || self.provided_subnets.contains(&subnet_id) {
+            return;
+        }
+        let key = self.provider_key(&subnet_id);
+        if let Some(kademlia) = self.inner.as_mut() {
+            if let Err(e) = kademlia.start_providing(key) {
+                warn!("failed to provide subnet {subnet_id}: {e}");
+                return;
+            }
+        }
+        self.provided_subnets.push(subnet_id);
+    }
+
+    /// Stop announcing ourselves as a provider of a subnet in the DHT.
+    pub fn remove_provided_subnet(&mut self, subnet_id: &SubnetID) {
+        if self.provided_subnets.contains(subnet_id) {
+            self.stop_providing(subnet_id);
+            self.provided_subnets.retain(|id| id != subnet_id);
+        }
+    }
+
+    fn stop_providing(&mut self, subnet_id: &SubnetID) {
+        let key = self.provider_key(subnet_id);
+        if let Some(kademlia) = self.inner.as_mut() {
+            kademlia.stop_providing(&key);
+        }
+    }
+
+    /// Look up the providers of a subnet in the DHT if we know too few of them through gossip,
+    /// unless we have recently done so. The results are emitted as [`Event::ProvidersFound`].
+    pub fn lookup_providers(&mut self, subnet_id: &SubnetID, num_known: usize) {
+        if !self.provider_records || self.lookups_paused || num_known >= self.min_providers {
+            return;
+        }
+        let now = Instant::now();
+        self.provider_lookups
+            .retain(|_, t| now.duration_since(*t) < MIN_PROVIDER_LOOKUP_INTERVAL);
+        if self.provider_lookups.contains_key(subnet_id) {
+            return;
+        }
+        let key = self.provider_key(subnet_id);
+        if let Some(kademlia) = self.inner.as_mut() {
+            emit(observe::DiscoveryEvent::ProviderLookup(subnet_id.clone()));
+            let query_id = kademlia.get_providers(key);
+            self.provider_queries
+                .insert(query_id, (subnet_id.clone(), now));
+            self.provider_lookups.insert(subnet_id.clone(), now);
+        }
+    }
+
+    /// Save the peer store to disk, if it's enabled, e.g. before shutting down.
+    pub fn flush_peer_store(&mut self) {
+        if let Some(store) = self.peer_store.as_mut() {
+            if let Err(e) = store.flush() {
+                warn!("failed to flush peer store: {e}");
+            }
+        }
+    }
+
     /// Lookup a peer, unless we already know their address, so that we have a chance to connect to them later.
     pub fn background_lookup(&mut self, peer_id: PeerId) {
-        if self.addresses_of_peer(peer_id).is_empty() {
+        if !self.lookups_paused && self.addresses_of_peer(peer_id).is_empty() {
             if let Some(kademlia) = self.inner.as_mut() {
                 emit(observe::DiscoveryEvent::BackgroundLookup(peer_id));
                 kademlia.get_closest_peers(peer_id);
@@ -202,6 +446,10 @@ impl Behaviour {
                 {
                     buffer.push((*peer_id, info))
                 }
+            } else if self.verify_addresses {
+                for addr in info.listen_addrs.iter().cloned() {
+                    self.verify_address(*peer_id, addr);
+                }
             } else {
                 for addr in info.listen_addrs.iter().cloned() {
                     self.add_address(peer_id, addr);
@@ -210,14 +458,48 @@ impl Behaviour {
         }
     }
 
-    /// Add a known address to Kademlia.
+    /// Dial an address a peer claims to listen on; it's added to Kademlia if the connection succeeds.
+    fn verify_address(&mut self, peer_id: PeerId, addr: Multiaddr) {
+        let mut pending_for_peer = 0;
+        for (id, a) in self.verifications.values() {
+            if *id == peer_id {
+                if *a == addr {
+                    return;
+                }
+                pending_for_peer += 1;
+            }
+        }
+
+        if pending_for_peer >= MAX_PENDING_VERIFICATIONS_PER_PEER
+            || self.verifications.len() >= MAX_PENDING_VERIFICATIONS
+            || self.addresses_of_peer(peer_id).contains(&addr)
+        {
+            return;
+        }
+
+        // Only dial this address, even if we are already connected to the peer.
+        let opts = DialOpts::peer_id(peer_id)
+            .addresses(vec![addr.clone()])
+            .condition(PeerCondition::Always)
+            .build();
+
+        self.verifications
+            .insert(opts.connection_id(), (peer_id, addr));
+        self.verification_dials.push_back(opts);
+    }
+
+    /// Add a known address to Kademlia, and remember it in the peer store.
     pub fn add_address(&mut self, peer_id: &PeerId, address: Multiaddr) {
+        if let Some(store) = self.peer_store.as_mut() {
+            store.add_address(*peer_id, address.clone());
+        }
         if let Some(kademlia) = self.inner.as_mut() {
             kademlia.add_address(peer_id, address);
         }
     }
 
-    fn addresses_of_peer(&mut self, peer_id: PeerId) -> Vec<Multiaddr> {
+    /// Addresses we know of a peer, from Kademlia, static configuration, the peer store and mDNS.
+    pub fn addresses_of_peer(&mut self, peer_id: PeerId) -> Vec<Multiaddr> {
         self.handle_pending_outbound_connection(
             ConnectionId::new_unchecked(0),
             Some(peer_id),
@@ -238,9 +520,19 @@ impl NetworkBehaviour for Behaviour {
     fn on_swarm_event(&mut self, event: FromSwarm) {
         match &event {
             FromSwarm::ConnectionEstablished(e) => {
+                if let Some((peer_id, addr)) = self.verifications.remove(&e.connection_id) {
+                    debug!("verified address {addr} of {peer_id}");
+                    emit(observe::DiscoveryEvent::AddressVerified(peer_id));
+                    self.add_address(&peer_id, addr);
+                    self.verified_connections
+                        .push_back((peer_id, e.connection_id));
+                }
                 if e.other_established == 0 {
                     emit(observe::DiscoveryEvent::ConnectionEstablished(e.peer_id));
                     self.num_connections += 1;
+                    if let Some(store) = self.peer_store.as_mut() {
+                        store.record_connected(&e.peer_id);
+                    }
                 }
             }
             FromSwarm::ConnectionClosed(e) => {
@@ -249,8 +541,17 @@ impl NetworkBehaviour for Behaviour {
                     self.num_connections -= 1;
                 }
             }
+            FromSwarm::DialFailure(e) => {
+                if let Some((peer_id, addr)) = self.verifications.remove(&e.connection_id) {
+                    debug!("failed to verify address {addr} of {peer_id}: {}", e.error);
+                    emit(observe::DiscoveryEvent::AddressRejected(peer_id));
+                } else if let (Some(peer_id), Some(store)) = (e.peer_id, self.peer_store.as_mut()) {
+                    store.record_dial_failure(&peer_id);
+                }
+            }
             _ => {}
         };
+        self.mdns.on_swarm_event(event);
         self.inner.on_swarm_event(event)
     }
 
@@ -312,6 +613,23 @@ impl NetworkBehaviour for Behaviour {
             );
         }
 
+        // Addresses remembered from previous runs, which Kademlia may have evicted.
+        if let (Some(peer_id), Some(store)) = (maybe_peer, self.peer_store.as_ref()) {
+            for addr in store.addresses_of(&peer_id) {
+                if !addrs.contains(&addr) {
+                    addrs.push(addr);
+                }
+            }
+        }
+
+        // Addresses of peers discovered on the local network.
+        addrs.extend(self.mdns.handle_pending_outbound_connection(
+            connection_id,
+            maybe_peer,
+            addresses,
+            effective_role,
+        )?);
+
         Ok(addrs)
     }
 
@@ -335,9 +653,20 @@ impl NetworkBehaviour for Behaviour {
             return Poll::Ready(ToSwarm::GenerateEvent(ev));
         }
 
+        // Dial back addresses to verify them, and close the connections once they are established.
+        if let Some((peer_id, connection_id)) = self.verified_connections.pop_front() {
+            return Poll::Ready(ToSwarm::CloseConnection {
+                peer_id,
+                connection: CloseConnection::One(connection_id),
+            });
+        }
+        if let Some(opts) = self.verification_dials.pop_front() {
+            return Poll::Ready(ToSwarm::Dial { opts });
+        }
+
         // Trigger periodic queries.
         if self.lookup_interval.poll_tick(cx).is_ready() {
-            if self.num_connections < self.target_connections {
+            if !self.lookups_paused && self.num_connections < self.target_connections {
                 if let Some(k) = self.inner.as_mut() {
                     debug!("looking up a random peer");
                     let random_peer_id = PeerId::random();
@@ -354,6 +683,58 @@ impl NetworkBehaviour for Behaviour {
             self.lookup_interval.reset();
         }
 
+        // Periodically save the peer store.
+        if let Some(interval) = self.flush_interval.as_mut() {
+            if interval.poll_tick(cx).is_ready() {
+                self.flush_peer_store();
+            }
+        }
+
+        // Poll mDNS.
+        while let Poll::Ready(ev) = self.mdns.poll(cx) {
+            match ev {
+                ToSwarm::GenerateEvent(mdns::Event::Discovered(peers)) => {
+                    for (peer_id, addr) in peers {
+                        if peer_id == self.peer_id {
+                            continue;
+                        }
+                        debug!("{peer_id} discovered with mDNS at {addr}");
+                        emit(observe::DiscoveryEvent::MdnsDiscovered(peer_id));
+                        self.add_address(&peer_id, addr);
+                        self.outbox.push_back(Event::Added(peer_id));
+                    }
+                }
+                ToSwarm::GenerateEvent(mdns::Event::Expired(peers)) => {
+                    for (peer_id, addr) in peers {
+                        debug!("{peer_id} mDNS record expired for {addr}");
+                        // Kademlia keeps track of the peer on its own, if it's enabled,
+                        // and the peer may still be reachable through another address.
+                        let still_known = self.inner.is_enabled()
+                            || self.is_static(peer_id)
+                            || self
+                                .mdns
+                                .as_ref()
+                                .is_some_and(|m| m.discovered_nodes().any(|id| *id == peer_id));
+                        if !still_known {
+                            self.outbox.push_back(Event::Removed(peer_id));
+                        }
+                    }
+                }
+                other => {
+                    return Poll::Ready(
+                        other
+                            .map_out(|_| unreachable!("already handled"))
+                            .map_in(|_| unreachable!("mDNS has no connection handler events")),
+                    );
+                }
+            }
+        }
+
+        // Emit events from mDNS before polling Kademlia.
+        if let Some(ev) = self.outbox.pop_front() {
+            return Poll::Ready(ToSwarm::GenerateEvent(ev));
+        }
+
         // Poll Kademlia.
         while let Poll::Ready(ev) = self.inner.poll(cx) {
             match ev {
@@ -368,11 +749,28 @@ impl NetworkBehaviour for Behaviour {
                         } => {
                             warn!("disallowed Kademlia requests from {source}",)
                         }
+                        // Store subnet provider records, if enabled, since we filter all inserts.
+                        kad::Event::InboundRequest {
+                            request:
+                                kad::InboundRequest::AddProvider {
+                                    record: Some(record),
+                                },
+                        } => {
+                            if self.provider_records {
+                                if let Some(kademlia) = self.inner.as_mut() {
+                                    if let Err(e) = kademlia.store_mut().add_provider(record) {
+                                        debug!("failed to store provider record: {e}");
+                                    }
+                                }
+                            }
+                        }
                         // Information only.
                         kad::Event::InboundRequest { .. } => {}
                         kad::Event::ModeChanged { .. } => {}
                         // Finish bootstrapping.
-                        kad::Event::OutboundQueryProgressed { result, step, .. } => match result {
+                        kad::Event::OutboundQueryProgressed {
+                            id, result, step, ..
+                        } => match result {
                             kad::QueryResult::Bootstrap(result) if step.last => {
                                 debug!("Bootstrapping finished with {result:?}");
                                 if let Some(buffer) = self.bootstrap_buffer.take() {
@@ -382,6 +780,49 @@ impl NetworkBehaviour for Behaviour {
                                     }
                                 }
                             }
+                            // Report the providers of a subnet as they are found.
+                            kad::QueryResult::GetProviders(result) => {
+                                let subnet_id = if step.last {
+                                    self.provider_queries
+                                        .remove(&id)
+                                        .map(|(subnet_id, started)| {
+                                            emit(observe::DiscoveryEvent::ProviderLookupFinished(
+                                                subnet_id.clone(),
+                                                started.elapsed(),
+                                            ));
+                                            subnet_id
+                                        })
+                                } else {
+                                    self.provider_queries
+                                        .get(&id)
+                                        .map(|(subnet_id, _)| subnet_id.clone())
+                                };
+                                match (subnet_id, result) {
+                                    (
+                                        Some(subnet_id),
+                                        Ok(kad::GetProvidersOk::FoundProviders {
+                                            providers, ..
+                                        }),
+                                    ) => {
+                                        let providers = providers
+                                            .into_iter()
+                                            .filter(|p| *p != self.peer_id)
+                                            .collect::<Vec<_>>();
+                                        for peer_id in providers.iter() {
+                                            emit(observe::DiscoveryEvent::ProviderFound(*peer_id));
+                                        }
+                                        if !providers.is_empty() {
+                                            self.outbox.push_back(Event::ProvidersFound(
+                                                subnet_id, providers,
+                                            ))
+                                        }
+                                    }
+                                    (Some(subnet_id), Err(e)) => {
+                                        debug!("failed to find providers of {subnet_id}: {e}")
+                                    }
+                                    _ => {}
+                                }
+                            }
                             _ => {}
                         },
                         // The config ensures peers are added to the table if there's room.
@@ -415,6 +856,11 @@ impl NetworkBehaviour for Behaviour {
             }
         }
 
+        // Emit events raised while handling Kademlia events.
+        if let Some(ev) = self.outbox.pop_front() {
+            return Poll::Ready(ToSwarm::GenerateEvent(ev));
+        }
+
         Poll::Pending
     }
 }
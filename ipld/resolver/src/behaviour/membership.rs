@@ -5,26 +5,29 @@ use std::marker::PhantomData;
 use std::task::{Context, Poll};
 use std::time::Duration;
 
+use super::blob::{BlobAnnouncement, SignedBlobAnnouncement};
 use super::NetworkConfig;
 use crate::hash::blake2b_256;
 use crate::observe;
 use crate::provider_cache::{ProviderDelta, SubnetProviderCache};
 use crate::provider_record::{ProviderRecord, SignedProviderRecord};
+use crate::reputation::Offence;
 use crate::vote_record::{SignedVoteRecord, VoteRecord};
 use crate::Timestamp;
 use anyhow::anyhow;
+use async_trait::async_trait;
 use ipc_api::subnet_id::SubnetID;
 use ipc_observability::emit;
 use libp2p::core::Endpoint;
 use libp2p::gossipsub::{
-    self, IdentTopic, MessageAuthenticity, MessageId, PublishError, Sha256Topic, SubscriptionError,
-    Topic, TopicHash,
+    self, IdentTopic, MessageAcceptance, MessageAuthenticity, MessageId, PublishError, Sha256Topic,
+    SubscriptionError, Topic, TopicHash, ValidationMode,
 };
-use libp2p::identity::Keypair;
+use libp2p::identity::{Keypair, PublicKey};
 use libp2p::swarm::derive_prelude::FromSwarm;
 use libp2p::swarm::{
-    ConnectionDenied, ConnectionId, NetworkBehaviour, THandler, THandlerInEvent, THandlerOutEvent,
-    ToSwarm,
+    CloseConnection, ConnectionDenied, ConnectionId, NetworkBehaviour, THandler, THandlerInEvent,
+    THandlerOutEvent, ToSwarm,
 };
 use libp2p::{Multiaddr, PeerId};
 use log::{debug, error, info, warn};
@@ -36,8 +39,12 @@ use tokio::time::{Instant, Interval};
 const PUBSUB_MEMBERSHIP: &str = "/ipc/membership";
 /// `Gossipsub` topic identifier for voting about content.
 const PUBSUB_VOTES: &str = "/ipc/ipld/votes";
+/// `Gossipsub` topic identifier for announcing the outcome of blob resolution.
+const PUBSUB_BLOBS: &str = "/ipc/iroh/blobs";
 /// `Gossipsub` topic identifier for pre-emptively published blocks of data.
 const PUBSUB_PREEMPTIVE: &str = "/ipc/ipld/pre-emptive";
+/// Interval between checking whether peers admitted by permissive gating proved their membership.
+const GATING_CHECK_INTERVAL: Duration = Duration::from_secs(10);
 
 /// Events emitted by the [`membership::Behaviour`] behaviour.
 #[derive(Debug)]
@@ -58,10 +65,63 @@ pub enum Event<V> {
     /// We received a [`VoteRecord`] in one of the subnets we are providing data for.
     ReceivedVote(Box<VoteRecord<V>>),
 
+    /// We received a [`BlobAnnouncement`] in one of the subnets we are providing data for.
+    ReceivedBlobAnnouncement(Box<VoteRecord<BlobAnnouncement>>),
+
     /// We received preemptive data published in a subnet we were interested in.
     ReceivedPreemptive(SubnetID, Vec<u8>),
+
+    /// A peer misbehaved on one of the topics, which should lower its reputation.
+    Offence(PeerId, Offence),
+
+    /// We received a provider record which should only be accepted if its signer is a validator
+    /// of the subnets it claims to provide. Pass it back to [`Behaviour::complete_provider_validation`].
+    ValidateProvider(Box<PendingProviderRecord>),
+}
+
+/// A provider record waiting for its signer to be checked against the validator sets of its subnets.
+#[derive(Debug)]
+pub struct PendingProviderRecord {
+    pub message_id: MessageId,
+    pub propagation_source: PeerId,
+    pub record: ProviderRecord,
+    pub signing_key: PublicKey,
 }
 
+/// Look up whether keys are part of the validator set of subnets, to check who can claim to provide them.
+#[async_trait]
+pub trait SubnetValidators: Send + Sync {
+    /// Check whether the owner of a key is a validator of a subnet.
+    ///
+    /// Return `None` if the validator set of the subnet is unknown, in which case anyone can provide it.
+    async fn is_validator(
+        &self,
+        subnet_id: &SubnetID,
+        public_key: &PublicKey,
+    ) -> anyhow::Result<Option<bool>>;
+}
+
+/// Which peers are admitted to connect to us, based on their subnet membership.
+///
+/// A peer proves its membership by publishing a provider record for one of the subnets
+/// we provide data for or pinned. Connections we initiate are always admitted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GatingMode {
+    /// Admit every peer.
+    #[default]
+    Disabled,
+    /// Admit every peer, but disconnect those which don't prove their membership within
+    /// the grace period. Bootstrap nodes should use this mode, so new nodes can join.
+    Permissive,
+    /// Refuse connections from peers which haven't proven their membership yet.
+    Strict,
+}
+
+/// Error returned when a connection is refused by the gating.
+#[derive(thiserror::Error, Debug)]
+#[error("peer {0} is not a member of any subnet of interest")]
+pub struct NotMember(PeerId);
+
 /// Configuration for [`membership::Behaviour`].
 #[derive(Clone, Debug)]
 pub struct Config {
@@ -75,6 +135,10 @@ pub struct Config {
     pub min_time_between_publish: Duration,
     /// Maximum age of provider records before the peer is removed without an update.
     pub max_provider_age: Duration,
+    /// Which peers are admitted to connect to us.
+    pub gating: GatingMode,
+    /// Time peers admitted by [`GatingMode::Permissive`] have to prove their membership.
+    pub gating_grace_period: Duration,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -104,6 +168,8 @@ pub struct Behaviour<V> {
     subnet_ids: Vec<SubnetID>,
     /// Voting topics we are currently subscribed to.
     voting_topics: HashSet<TopicHash>,
+    /// Blob announcement topics we are currently subscribed to.
+    blob_topics: HashSet<TopicHash>,
     /// Remember which subnet a topic was about.
     preemptive_topics: HashMap<TopicHash, SubnetID>,
     /// Caching the latest state of subnet providers.
@@ -121,6 +187,24 @@ pub struct Behaviour<V> {
     next_publish_timestamp: Timestamp,
     /// Maximum time a provider can be without an update before it's pruned from the cache.
     max_provider_age: Duration,
+    /// Gossipsub score below which we report peers for misbehaving.
+    graylist_threshold: f64,
+    /// Which peers are admitted to connect to us.
+    gating: GatingMode,
+    /// Time peers admitted by permissive gating have to prove their membership.
+    gating_grace_period: Duration,
+    /// Interval between checking the deadlines of peers admitted by permissive gating.
+    gating_interval: Option<Interval>,
+    /// Timestamp of the latest provider record of peers which are members of a subnet we care about.
+    ///
+    /// Unlike the provider cache, this includes peers which aren't routable.
+    members: HashMap<PeerId, Timestamp>,
+    /// Peers admitted by permissive gating, with the deadline to prove their membership.
+    unproven: HashMap<PeerId, Instant>,
+    /// Peers to disconnect from, because they didn't prove their membership in time.
+    to_disconnect: VecDeque<PeerId>,
+    /// Whether provider records have to be checked against the validator sets of their subnets.
+    validate_provider_records: bool,
     _phantom_vote: PhantomData<V>,
 }
 
@@ -137,6 +221,9 @@ where
         let mut gossipsub_config = gossipsub::ConfigBuilder::default();
         // Set the maximum message size to 2MB.
         gossipsub_config.max_transmit_size(2 << 20);
+        // Only accept messages signed by their author, and only forward them after we validated them.
+        gossipsub_config.validation_mode(ValidationMode::Strict);
+        gossipsub_config.validate_messages();
         gossipsub_config.message_id_fn(|msg: &gossipsub::Message| {
             let s = blake2b_256(&msg.data);
             MessageId::from(s)
@@ -152,10 +239,13 @@ where
         )
         .map_err(|s| ConfigError::InvalidGossipsubConfig(s.into()))?;
 
+        let score_thresholds = scoring::build_peer_score_thresholds();
+        let graylist_threshold = score_thresholds.graylist_threshold;
+
         gossipsub
             .with_peer_score(
                 scoring::build_peer_score_params(membership_topic.clone()),
-                scoring::build_peer_score_thresholds(),
+                score_thresholds,
             )
             .map_err(ConfigError::InvalidGossipsubConfig)?;
 
@@ -177,6 +267,7 @@ where
             membership_topic,
             subnet_ids: Default::default(),
             voting_topics: Default::default(),
+            blob_topics: Default::default(),
             preemptive_topics: Default::default(),
             provider_cache,
             publish_interval: interval,
@@ -184,6 +275,15 @@ where
             last_publish_timestamp: Timestamp::default(),
             next_publish_timestamp: Timestamp::now() + mc.publish_interval,
             max_provider_age: mc.max_provider_age,
+            graylist_threshold,
+            gating: mc.gating,
+            gating_grace_period: mc.gating_grace_period,
+            gating_interval: (mc.gating == GatingMode::Permissive)
+                .then(|| tokio::time::interval(GATING_CHECK_INTERVAL)),
+            members: Default::default(),
+            unproven: Default::default(),
+            to_disconnect: Default::default(),
+            validate_provider_records: false,
             _phantom_vote: PhantomData,
         };
 
@@ -244,19 +344,37 @@ where
         ))
     }
 
-    /// Subscribe to a voting topic.
+    /// Construct the topic used to gossip about the outcome of blob resolution.
+    ///
+    /// Replaces "/" with "_" to avoid clashes from prefix/suffix overlap.
+    fn blob_topic(&self, subnet_id: &SubnetID) -> Sha256Topic {
+        Topic::new(format!(
+            "{}/{}/{}",
+            PUBSUB_BLOBS,
+            self.network_name.replace('/', "_"),
+            subnet_id.to_string().replace('/', "_")
+        ))
+    }
+
+    /// Subscribe to the voting and blob announcement topics of a subnet.
     fn voting_subscribe(&mut self, subnet_id: &SubnetID) -> Result<(), SubscriptionError> {
         let topic = self.voting_topic(subnet_id);
         self.subscribe(&topic)?;
         self.voting_topics.insert(topic.hash());
+        let topic = self.blob_topic(subnet_id);
+        self.subscribe(&topic)?;
+        self.blob_topics.insert(topic.hash());
         Ok(())
     }
 
-    /// Unsubscribe from a voting topic.
+    /// Unsubscribe from the voting and blob announcement topics of a subnet.
     fn voting_unsubscribe(&mut self, subnet_id: &SubnetID) -> anyhow::Result<()> {
         let topic = self.voting_topic(subnet_id);
         self.unsubscribe(&topic)?;
         self.voting_topics.remove(&topic.hash());
+        let topic = self.blob_topic(subnet_id);
+        self.unsubscribe(&topic)?;
+        self.blob_topics.remove(&topic.hash());
         Ok(())
     }
 
@@ -362,6 +480,27 @@ where
         }
     }
 
+    /// Publish the outcome of resolving a blob by the validator running the agent to a subnet.
+    pub fn publish_blob_announcement(
+        &mut self,
+        announcement: SignedBlobAnnouncement,
+    ) -> anyhow::Result<()> {
+        let topic = self.blob_topic(&announcement.record().subnet_id);
+        let data = announcement.into_envelope().into_protobuf_encoding();
+        match self.inner.publish(topic, data) {
+            Err(e) => {
+                emit(observe::MembershipFailureEvent::PublishFailure(
+                    e.to_string(),
+                ));
+                Err(anyhow!(e))
+            }
+            Ok(_msg_id) => {
+                emit(observe::MembershipEvent::PublishSuccess);
+                Ok(())
+            }
+        }
+    }
+
     /// Publish arbitrary data to the pre-emptive topic of a subnet.
     ///
     /// We are not expected to be subscribed to this topic, only agents on the parent subnet are.
@@ -407,35 +546,230 @@ where
         self.provider_cache.providers_of_subnet(subnet_id)
     }
 
+    /// List the subnets a peer provides.
+    pub fn subnets_of_peer(&self, peer_id: &PeerId) -> Vec<SubnetID> {
+        self.provider_cache.subnets_of_peer(peer_id)
+    }
+
+    /// Timestamp of the latest provider record received from a peer.
+    pub fn provider_timestamp(&self, peer_id: &PeerId) -> Option<Timestamp> {
+        self.provider_cache.peer_timestamp(peer_id)
+    }
+
     /// Parse and handle a [`gossipsub::Message`]. If it's from the expected topic,
     /// then raise domain event to let the rest of the application know about a
     /// provider. Also update all the book keeping in the behaviour that we use
     /// to answer future queries about the topic.
-    fn handle_message(&mut self, msg: gossipsub::Message) {
-        if msg.topic == self.membership_topic.hash() {
-            match SignedProviderRecord::from_bytes(&msg.data).map(|r| r.into_record()) {
-                Ok(record) => self.handle_provider_record(record),
-                Err(e) => emit(
-                    observe::MembershipFailureEvent::GossipInvalidProviderRecord(
-                        msg.source,
-                        e.to_string(),
-                    ),
-                ),
+    ///
+    /// Tell Gossipsub whether the message is valid, so invalid ones are not propagated
+    /// and the peers forwarding them lose score.
+    fn handle_message(
+        &mut self,
+        message_id: MessageId,
+        propagation_source: PeerId,
+        msg: gossipsub::Message,
+    ) {
+        let acceptance = if msg.topic == self.membership_topic.hash() {
+            match SignedProviderRecord::from_bytes(&msg.data) {
+                Ok(signed)
+                    if self.validate_provider_records && !signed.record().subnet_ids.is_empty() =>
+                {
+                    // Validation is completed by `complete_provider_validation`.
+                    let signing_key = signed.signing_key().clone();
+                    self.outbox.push_back(Event::ValidateProvider(Box::new(
+                        PendingProviderRecord {
+                            message_id,
+                            propagation_source,
+                            record: signed.into_record(),
+                            signing_key,
+                        },
+                    )));
+                    return;
+                }
+                Ok(signed) => {
+                    self.handle_provider_record(signed.into_record());
+                    MessageAcceptance::Accept
+                }
+                Err(e) => {
+                    emit(
+                        observe::MembershipFailureEvent::GossipInvalidProviderRecord(
+                            msg.source,
+                            e.to_string(),
+                        ),
+                    );
+                    self.report_invalid_record(msg.source);
+                    MessageAcceptance::Reject
+                }
             }
         } else if self.voting_topics.contains(&msg.topic) {
             match SignedVoteRecord::from_bytes(&msg.data).map(|r| r.into_record()) {
-                Ok(record) => self.handle_vote_record(record),
-                Err(e) => emit(observe::MembershipFailureEvent::GossipInvalidVoteRecord(
-                    msg.source,
-                    e.to_string(),
-                )),
+                Ok(record) => {
+                    self.handle_vote_record(record);
+                    MessageAcceptance::Accept
+                }
+                Err(e) => {
+                    emit(observe::MembershipFailureEvent::GossipInvalidVoteRecord(
+                        msg.source,
+                        e.to_string(),
+                    ));
+                    self.report_invalid_record(msg.source);
+                    MessageAcceptance::Reject
+                }
+            }
+        } else if self.blob_topics.contains(&msg.topic) {
+            match SignedBlobAnnouncement::from_bytes(&msg.data).map(|r| r.into_record()) {
+                Ok(record) => {
+                    self.handle_blob_announcement(record);
+                    MessageAcceptance::Accept
+                }
+                Err(e) => {
+                    emit(observe::MembershipFailureEvent::GossipInvalidVoteRecord(
+                        msg.source,
+                        e.to_string(),
+                    ));
+                    self.report_invalid_record(msg.source);
+                    MessageAcceptance::Reject
+                }
             }
         } else if let Some(subnet_id) = self.preemptive_topics.get(&msg.topic) {
-            self.handle_preemptive_data(subnet_id.clone(), msg.data)
+            self.handle_preemptive_data(subnet_id.clone(), msg.data);
+            MessageAcceptance::Accept
         } else {
             emit(observe::MembershipFailureEvent::GossipUnknownTopic(
                 msg.source, msg.topic,
             ));
+            MessageAcceptance::Ignore
+        };
+
+        self.report_validation(&message_id, &propagation_source, acceptance);
+    }
+
+    /// Finish handling a provider record after the application checked whether its signer
+    /// is a validator of all the subnets it claims to provide.
+    pub fn complete_provider_validation(&mut self, pending: PendingProviderRecord, valid: bool) {
+        let acceptance = if valid {
+            self.handle_provider_record(pending.record);
+            MessageAcceptance::Accept
+        } else {
+            let peer_id = pending.record.peer_id;
+            emit(
+                observe::MembershipFailureEvent::GossipInvalidProviderRecord(
+                    Some(peer_id),
+                    "signer is not a validator of the provided subnets".to_string(),
+                ),
+            );
+            self.report_invalid_record(Some(peer_id));
+            MessageAcceptance::Reject
+        };
+        self.report_validation(&pending.message_id, &pending.propagation_source, acceptance);
+    }
+
+    /// Enable checking the signers of provider records against the validator sets of subnets.
+    pub fn set_validate_provider_records(&mut self, validate: bool) {
+        self.validate_provider_records = validate;
+    }
+
+    fn report_validation(
+        &mut self,
+        message_id: &MessageId,
+        propagation_source: &PeerId,
+        acceptance: MessageAcceptance,
+    ) {
+        // Fails if the message has already been evicted from the cache, which is fine.
+        let _ =
+            self.inner
+                .report_message_validation_result(message_id, propagation_source, acceptance);
+    }
+
+    /// Report the author of an invalid record, if known.
+    fn report_invalid_record(&mut self, source: Option<PeerId>) {
+        if let Some(peer_id) = source {
+            self.outbox
+                .push_back(Event::Offence(peer_id, Offence::InvalidRecord))
+        }
+    }
+
+    /// Report peers which Gossipsub scored so low that it ignores them.
+    fn report_graylisted_peers(&mut self) {
+        let graylisted = self
+            .inner
+            .all_peers()
+            .map(|(peer_id, _)| *peer_id)
+            .filter(|peer_id| {
+                self.inner
+                    .peer_score(peer_id)
+                    .is_some_and(|score| score < self.graylist_threshold)
+            })
+            .collect::<Vec<_>>();
+
+        for peer_id in graylisted {
+            debug!("peer {peer_id} is graylisted by gossipsub");
+            self.outbox
+                .push_back(Event::Offence(peer_id, Offence::GossipMisbehaviour))
+        }
+    }
+
+    /// Check whether a peer has proven its membership in a subnet we care about.
+    pub fn is_member(&self, peer_id: &PeerId) -> bool {
+        self.members.contains_key(peer_id)
+    }
+
+    /// Check whether any of the subnets is one we provide data for or pinned.
+    fn is_of_interest(&self, subnet_ids: &[SubnetID]) -> bool {
+        subnet_ids.iter().any(|subnet_id| {
+            self.subnet_ids.contains(subnet_id)
+                || self.preemptive_topics.values().any(|id| id == subnet_id)
+        })
+    }
+
+    /// Remember which peers proved their membership in a subnet we care about.
+    fn update_member(&mut self, record: &ProviderRecord) {
+        if self.gating == GatingMode::Disabled {
+            return;
+        }
+        match self.members.get(&record.peer_id) {
+            Some(timestamp) if *timestamp >= record.timestamp => {}
+            _ if self.is_of_interest(&record.subnet_ids) => {
+                self.members.insert(record.peer_id, record.timestamp);
+                self.unproven.remove(&record.peer_id);
+            }
+            _ => {
+                self.members.remove(&record.peer_id);
+            }
+        }
+    }
+
+    /// Check whether a peer is allowed to connect to us.
+    fn check_inbound(&self, peer_id: PeerId) -> Result<(), ConnectionDenied> {
+        if self.gating == GatingMode::Strict && !self.is_member(&peer_id) {
+            emit(observe::MembershipEvent::Gated(peer_id));
+            return Err(ConnectionDenied::new(NotMember(peer_id)));
+        }
+        Ok(())
+    }
+
+    /// Give a peer which connected to us the grace period to prove its membership, if it has to.
+    fn on_inbound_established(&mut self, peer_id: PeerId) {
+        if self.gating == GatingMode::Permissive && !self.is_member(&peer_id) {
+            self.unproven
+                .insert(peer_id, Instant::now() + self.gating_grace_period);
+        }
+    }
+
+    /// Disconnect peers admitted by permissive gating which haven't proven their membership in time.
+    fn check_unproven(&mut self) {
+        let now = Instant::now();
+        let expired = self
+            .unproven
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(peer_id, _)| *peer_id)
+            .collect::<Vec<_>>();
+        for peer_id in expired {
+            debug!("disconnecting {peer_id} because it didn't prove its membership");
+            emit(observe::MembershipEvent::Gated(peer_id));
+            self.unproven.remove(&peer_id);
+            self.to_disconnect.push_back(peer_id);
         }
     }
 
@@ -445,6 +779,7 @@ where
     /// reciprocate by publishing our own.
     fn handle_provider_record(&mut self, record: ProviderRecord) {
         debug!("received provider record: {record:?}");
+        self.update_member(&record);
         let (event, publish) = match self.provider_cache.add_provider(&record) {
             None => {
                 emit(observe::MembershipEvent::Skipped(record.peer_id));
@@ -472,6 +807,12 @@ where
         self.outbox.push_back(Event::ReceivedVote(Box::new(record)))
     }
 
+    /// Raise an event to tell we received a new blob announcement.
+    fn handle_blob_announcement(&mut self, record: VoteRecord<BlobAnnouncement>) {
+        self.outbox
+            .push_back(Event::ReceivedBlobAnnouncement(Box::new(record)))
+    }
+
     fn handle_preemptive_data(&mut self, subnet_id: SubnetID, data: Vec<u8>) {
         self.outbox
             .push_back(Event::ReceivedPreemptive(subnet_id, data))
@@ -515,6 +856,8 @@ where
     /// Remove any membership record that hasn't been updated for a long time.
     fn prune_membership(&mut self) {
         let cutoff_timestamp = Timestamp::now() - self.max_provider_age;
+        self.members
+            .retain(|_, timestamp| *timestamp >= cutoff_timestamp);
         let pruned = self.provider_cache.prune_providers(cutoff_timestamp);
         for peer_id in pruned {
             emit(observe::MembershipEvent::Removed(peer_id));
@@ -531,6 +874,17 @@ where
     type ToSwarm = Event<V>;
 
     fn on_swarm_event(&mut self, event: FromSwarm) {
+        match &event {
+            FromSwarm::ConnectionEstablished(e)
+                if e.other_established == 0 && e.endpoint.is_listener() =>
+            {
+                self.on_inbound_established(e.peer_id);
+            }
+            FromSwarm::ConnectionClosed(e) if e.remaining_established == 0 => {
+                self.unproven.remove(&e.peer_id);
+            }
+            _ => {}
+        }
         self.inner.on_swarm_event(event)
     }
 
@@ -561,6 +915,7 @@ where
         local_addr: &Multiaddr,
         remote_addr: &Multiaddr,
     ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.check_inbound(peer)?;
         self.inner.handle_established_inbound_connection(
             connection_id,
             peer,
@@ -604,12 +959,26 @@ where
             return Poll::Ready(ToSwarm::GenerateEvent(ev));
         }
 
+        // Disconnect peers which didn't prove their membership in time.
+        if let Some(interval) = self.gating_interval.as_mut() {
+            if interval.poll_tick(cx).is_ready() {
+                self.check_unproven();
+            }
+        }
+        if let Some(peer_id) = self.to_disconnect.pop_front() {
+            return Poll::Ready(ToSwarm::CloseConnection {
+                peer_id,
+                connection: CloseConnection::All,
+            });
+        }
+
         // Republish our current peer record snapshot and prune old records.
         if self.publish_interval.poll_tick(cx).is_ready() {
             if let Err(e) = self.publish_membership() {
                 warn!("failed to publish membership: {e}")
             };
             self.prune_membership();
+            self.report_graylisted_peers();
         }
 
         // Poll Gossipsub for events; this is where we can handle Gossipsub messages and
@@ -635,8 +1004,12 @@ where
                         gossipsub::Event::GossipsubNotSupported { peer_id } => {
                             debug!("peer {peer_id} doesn't support gossipsub");
                         }
-                        gossipsub::Event::Message { message, .. } => {
-                            self.handle_message(message);
+                        gossipsub::Event::Message {
+                            propagation_source,
+                            message_id,
+                            message,
+                        } => {
+                            self.handle_message(message_id, propagation_source, message);
                         }
                     }
                 }
@@ -668,3 +1041,121 @@ mod scoring {
         PeerScoreThresholds::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ipc_api::subnet_id::SubnetID;
+    use libp2p::identity::Keypair;
+    use libp2p::PeerId;
+
+    use super::{Behaviour, Config, GatingMode};
+    use crate::behaviour::NetworkConfig;
+    use crate::provider_record::ProviderRecord;
+    use crate::Timestamp;
+
+    fn subnet_id() -> SubnetID {
+        SubnetID::new_root(1)
+    }
+
+    fn new_behaviour(gating: GatingMode, gating_grace_period: Duration) -> Behaviour<()> {
+        let nc = NetworkConfig {
+            local_key: Keypair::generate_secp256k1(),
+            network_name: "test".into(),
+        };
+        let mc = Config {
+            static_subnets: vec![subnet_id()],
+            max_subnets: 10,
+            publish_interval: Duration::from_secs(60),
+            min_time_between_publish: Duration::from_secs(5),
+            max_provider_age: Duration::from_secs(300),
+            gating,
+            gating_grace_period,
+        };
+        Behaviour::new(nc, mc).expect("failed to create behaviour")
+    }
+
+    fn record(peer_id: PeerId, subnet_id: SubnetID) -> ProviderRecord {
+        ProviderRecord {
+            peer_id,
+            subnet_ids: vec![subnet_id],
+            timestamp: Timestamp::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn gating_disabled() {
+        let mut behaviour = new_behaviour(GatingMode::Disabled, Duration::ZERO);
+        let peer_id = PeerId::random();
+
+        assert!(behaviour.check_inbound(peer_id).is_ok());
+
+        behaviour.on_inbound_established(peer_id);
+        behaviour.check_unproven();
+        assert!(behaviour.to_disconnect.is_empty());
+
+        // Membership isn't even tracked.
+        behaviour.update_member(&record(peer_id, subnet_id()));
+        assert!(!behaviour.is_member(&peer_id));
+    }
+
+    #[tokio::test]
+    async fn gating_permissive() {
+        let mut behaviour = new_behaviour(GatingMode::Permissive, Duration::ZERO);
+        let member = PeerId::random();
+        let outsider = PeerId::random();
+        let late = PeerId::random();
+
+        behaviour.update_member(&record(member, subnet_id()));
+        behaviour.update_member(&record(outsider, SubnetID::new_root(2)));
+        assert!(behaviour.is_member(&member));
+        assert!(!behaviour.is_member(&outsider));
+
+        // Everyone is admitted.
+        for peer_id in [member, outsider, late] {
+            assert!(behaviour.check_inbound(peer_id).is_ok());
+            behaviour.on_inbound_established(peer_id);
+        }
+
+        // A peer proving its membership within the grace period stays.
+        behaviour.update_member(&record(late, subnet_id()));
+
+        // The grace period is over for the rest.
+        behaviour.check_unproven();
+        assert_eq!(behaviour.to_disconnect, vec![outsider]);
+        assert!(behaviour.unproven.is_empty());
+    }
+
+    #[tokio::test]
+    async fn gating_permissive_grace_period() {
+        let mut behaviour = new_behaviour(GatingMode::Permissive, Duration::from_secs(60));
+        let peer_id = PeerId::random();
+
+        behaviour.on_inbound_established(peer_id);
+        behaviour.check_unproven();
+        assert!(behaviour.to_disconnect.is_empty(), "still in grace period");
+        assert!(behaviour.unproven.contains_key(&peer_id));
+    }
+
+    #[tokio::test]
+    async fn gating_strict() {
+        let mut behaviour = new_behaviour(GatingMode::Strict, Duration::ZERO);
+        let peer_id = PeerId::random();
+
+        assert!(behaviour.check_inbound(peer_id).is_err());
+
+        behaviour.update_member(&record(peer_id, SubnetID::new_root(2)));
+        assert!(
+            behaviour.check_inbound(peer_id).is_err(),
+            "not a subnet of interest"
+        );
+
+        behaviour.update_member(&record(peer_id, subnet_id()));
+        assert!(behaviour.check_inbound(peer_id).is_ok());
+
+        // Admitted peers aren't put on probation.
+        behaviour.on_inbound_established(peer_id);
+        assert!(behaviour.unproven.is_empty());
+    }
+}
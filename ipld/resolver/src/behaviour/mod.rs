@@ -2,22 +2,26 @@
 // SPDX-License-Identifier: MIT
 use libipld::store::StoreParams;
 use libp2p::{
+    allow_block_list::{self, BlockedPeers},
+    autonat,
     connection_limits::{self, ConnectionLimits},
-    identify,
+    dcutr, identify,
     identity::{Keypair, PublicKey},
-    ping,
-    swarm::NetworkBehaviour,
-    PeerId,
+    multiaddr::Protocol,
+    ping, relay,
+    swarm::{behaviour::toggle::Toggle, NetworkBehaviour},
+    Multiaddr, PeerId,
 };
 use libp2p_bitswap::BitswapStore;
 
+pub mod blob;
 pub mod content;
 pub mod discovery;
 pub mod membership;
 
 pub use content::Config as ContentConfig;
 pub use discovery::Config as DiscoveryConfig;
-pub use membership::Config as MembershipConfig;
+pub use membership::{Config as MembershipConfig, GatingMode};
 use serde::{de::DeserializeOwned, Serialize};
 
 #[derive(Clone, Debug)]
@@ -37,12 +41,37 @@ impl NetworkConfig {
     }
 }
 
+/// Options to make nodes behind NAT reachable.
+#[derive(Clone, Debug, Default)]
+pub struct NatConfig {
+    /// Option to probe whether we are publicly reachable with AutoNAT,
+    /// asking connected peers to dial us back on our external addresses.
+    pub enable_autonat: bool,
+    /// Option to act as a relay for other nodes which are not publicly reachable.
+    /// Only makes sense for nodes which are publicly reachable themselves.
+    pub enable_relay_server: bool,
+    /// Relays to make a reservation with, so that others can reach us through them
+    /// if we are behind NAT. An empty list disables the relay client.
+    ///
+    /// The addresses must end with a `/p2p/<peer-id>` part.
+    pub relay_addresses: Vec<Multiaddr>,
+    /// Option to upgrade relayed connections to direct ones with DCUtR hole punching.
+    /// Requires at least one relay address.
+    pub enable_dcutr: bool,
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum ConfigError {
     #[error("Error in the discovery configuration")]
     Discovery(#[from] discovery::ConfigError),
     #[error("Error in the membership configuration")]
     Membership(#[from] membership::ConfigError),
+    #[error("Invalid relay address: {0}")]
+    InvalidRelayAddress(Multiaddr),
+    #[error("DCUtR requires at least one relay address")]
+    DcutrWithoutRelay,
+    #[error("Strict connection gating requires at least one static address to connect to")]
+    StrictGatingWithoutStaticAddresses,
     #[error("Invalid iroh address")]
     IrohAddr(#[from] std::net::AddrParseError),
     #[error("Unable to create iroh client")]
@@ -54,6 +83,9 @@ pub enum ConfigError {
 /// * Kademlia for peer discovery
 /// * Gossipsub to advertise subnet membership
 /// * Bitswap to resolve CIDs
+/// * a request-response protocol to find peers with Iroh blobs
+/// * AutoNAT, Relay and DCUtR to reach nodes behind NAT
+/// * a block list to ban misbehaving peers
 #[derive(NetworkBehaviour)]
 pub struct Behaviour<P, V>
 where
@@ -64,7 +96,13 @@ where
     discovery: discovery::Behaviour,
     membership: membership::Behaviour<V>,
     content: content::Behaviour<P>,
+    blob: blob::Behaviour,
     connection_limits: connection_limits::Behaviour,
+    block_list: allow_block_list::Behaviour<BlockedPeers>,
+    autonat: Toggle<autonat::Behaviour>,
+    relay_server: Toggle<relay::Behaviour>,
+    relay_client: Toggle<relay::client::Behaviour>,
+    dcutr: Toggle<dcutr::Behaviour>,
 }
 
 // Unfortunately by using `#[derive(NetworkBehaviour)]` we cannot easily inspects events
@@ -78,17 +116,63 @@ where
     P: StoreParams,
     V: Serialize + DeserializeOwned,
 {
+    /// Create the behaviour bundle.
+    ///
+    /// The relay client behaviour has to be created together with its transport,
+    /// so it's passed in if there are any relay addresses to use.
+    #[allow(clippy::too_many_arguments)]
     pub fn new<S>(
         nc: NetworkConfig,
         dc: DiscoveryConfig,
         mc: MembershipConfig,
         cc: ContentConfig,
+        nat: NatConfig,
         limits: ConnectionLimits,
         store: S,
+        relay_client: Option<relay::client::Behaviour>,
     ) -> Result<Self, ConfigError>
     where
         S: BitswapStore<Params = P>,
     {
+        let local_peer_id = nc.local_peer_id();
+
+        // Parse relay addresses.
+        let mut relays = Vec::new();
+        for multiaddr in nat.relay_addresses {
+            let mut addr = multiaddr.clone();
+            match addr.pop() {
+                Some(Protocol::P2p(peer_id)) => relays.push((peer_id, addr)),
+                _ => return Err(ConfigError::InvalidRelayAddress(multiaddr)),
+            }
+        }
+
+        if nat.enable_dcutr && relays.is_empty() {
+            return Err(ConfigError::DcutrWithoutRelay);
+        }
+
+        // Memberships are learned over gossip, which a node refusing everyone can only join by dialing out.
+        if mc.gating == GatingMode::Strict && dc.static_addresses.is_empty() {
+            return Err(ConfigError::StrictGatingWithoutStaticAddresses);
+        }
+
+        let autonat = nat.enable_autonat.then(|| {
+            let mut autonat = autonat::Behaviour::new(local_peer_id, Default::default());
+            // Relays are expected to be publicly reachable, so they are good candidates
+            // to probe our reachability, in addition to whoever we are connected to.
+            for (peer_id, addr) in relays.iter() {
+                autonat.add_server(*peer_id, Some(addr.clone()));
+            }
+            autonat
+        });
+
+        let relay_server = nat
+            .enable_relay_server
+            .then(|| relay::Behaviour::new(local_peer_id, Default::default()));
+
+        let dcutr = nat
+            .enable_dcutr
+            .then(|| dcutr::Behaviour::new(local_peer_id));
+
         Ok(Self {
             ping: Default::default(),
             identify: identify::Behaviour::new(identify::Config::new(
@@ -96,9 +180,15 @@ where
                 nc.local_public_key(),
             )),
             discovery: discovery::Behaviour::new(nc.clone(), dc)?,
+            blob: blob::new(&nc.network_name),
             membership: membership::Behaviour::new(nc, mc)?,
             content: content::Behaviour::new(cc, store),
             connection_limits: connection_limits::Behaviour::new(limits),
+            block_list: Default::default(),
+            autonat: autonat.into(),
+            relay_server: relay_server.into(),
+            relay_client: relay_client.into(),
+            dcutr: dcutr.into(),
         })
     }
 
@@ -113,4 +203,12 @@ where
     pub fn content_mut(&mut self) -> &mut content::Behaviour<P> {
         &mut self.content
     }
+
+    pub fn blob_mut(&mut self) -> &mut blob::Behaviour {
+        &mut self.blob
+    }
+
+    pub fn block_list_mut(&mut self) -> &mut allow_block_list::Behaviour<BlockedPeers> {
+        &mut self.block_list
+    }
 }
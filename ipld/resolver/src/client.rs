@@ -6,11 +6,16 @@ use ipc_api::subnet_id::SubnetID;
 use iroh::blobs::Hash;
 use iroh::net::NodeAddr;
 use libipld::Cid;
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
+use std::time::Duration;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::oneshot;
 
 use crate::{
-    service::{Request, ResolveReadRequestResult, ResolveResult},
+    bandwidth::PeerBandwidth,
+    behaviour::blob::{BlobLocation, SignedBlobAnnouncement},
+    service::{ProviderInfo, Request, ResolveReadRequestResult, ResolveResult},
     vote_record::SignedVoteRecord,
 };
 
@@ -72,12 +77,97 @@ impl<V> Client<V> {
         self.send_request(req)
     }
 
+    /// Ban a peer until it's explicitly unbanned, disconnecting it and refusing further connections.
+    pub fn ban_peer(&self, peer_id: PeerId) -> anyhow::Result<()> {
+        let req = Request::BanPeer(peer_id);
+        self.send_request(req)
+    }
+
+    /// Lift a manual or reputation based ban on a peer.
+    pub fn unban_peer(&self, peer_id: PeerId) -> anyhow::Result<()> {
+        let req = Request::UnbanPeer(peer_id);
+        self.send_request(req)
+    }
+
+    /// Add a node which never expires at runtime, e.g. a new bootstrap node, and connect to it.
+    ///
+    /// The address must end with a `/p2p/<peer-id>` part.
+    pub fn add_static_address(&self, addr: Multiaddr) -> anyhow::Result<()> {
+        let mut multiaddr = addr.clone();
+        match multiaddr.pop() {
+            Some(Protocol::P2p(peer_id)) => {
+                let req = Request::AddStaticAddress(peer_id, multiaddr);
+                self.send_request(req)
+            }
+            _ => Err(anyhow!(
+                "static address must end with /p2p/<peer-id>: {addr}"
+            )),
+        }
+    }
+
+    /// Remove the static addresses of a peer at runtime, e.g. a retired bootstrap node.
+    pub fn remove_static_addresses(&self, peer_id: PeerId) -> anyhow::Result<()> {
+        let req = Request::RemoveStaticAddresses(peer_id);
+        self.send_request(req)
+    }
+
+    /// Change the number of connections at which point peer discovery pauses.
+    pub fn set_target_connections(&self, target_connections: usize) -> anyhow::Result<()> {
+        let req = Request::SetTargetConnections(target_connections);
+        self.send_request(req)
+    }
+
+    /// Pause or resume peer discovery lookups, regardless of the number of connections.
+    pub fn set_lookups_paused(&self, paused: bool) -> anyhow::Result<()> {
+        let req = Request::SetLookupsPaused(paused);
+        self.send_request(req)
+    }
+
+    /// List the known providers of a subnet, with their addresses and the freshness of their
+    /// membership records, e.g. to find out why content from the subnet can't be resolved.
+    pub async fn list_providers(&self, subnet_id: SubnetID) -> anyhow::Result<Vec<ProviderInfo>> {
+        let (tx, rx) = oneshot::channel();
+        let req = Request::ListProviders(subnet_id, tx);
+        self.send_request(req)?;
+        let res = rx.await?;
+        Ok(res)
+    }
+
+    /// List the bytes of content served to and fetched from each peer recently active.
+    pub async fn list_bandwidth(&self) -> anyhow::Result<Vec<(PeerId, PeerBandwidth)>> {
+        let (tx, rx) = oneshot::channel();
+        let req = Request::ListBandwidth(tx);
+        self.send_request(req)?;
+        let res = rx.await?;
+        Ok(res)
+    }
+
     /// Publish a signed vote into a topic based on its subnet.
     pub fn publish_vote(&self, vote: SignedVoteRecord<V>) -> anyhow::Result<()> {
         let req = Request::PublishVote(Box::new(vote));
         self.send_request(req)
     }
 
+    /// Publish the signed outcome of resolving a blob into a topic based on its subnet.
+    pub fn publish_blob_announcement(
+        &self,
+        announcement: SignedBlobAnnouncement,
+    ) -> anyhow::Result<()> {
+        let req = Request::PublishBlobAnnouncement(Box::new(announcement));
+        self.send_request(req)
+    }
+
+    /// Stop the [`Service`] gracefully: new resolutions are refused, and the ones already
+    /// running get up to `timeout` to finish before the peer store is saved and the
+    /// connections are closed. Returns when the service has stopped.
+    pub async fn shutdown(&self, timeout: Duration) -> anyhow::Result<()> {
+        let (tx, rx) = oneshot::channel();
+        let req = Request::Shutdown(timeout, tx);
+        self.send_request(req)?;
+        rx.await?;
+        Ok(())
+    }
+
     /// Publish pre-emptively to a subnet that agents in the parent subnet
     /// would be subscribed to if they are interested in receiving data
     /// before they would have to use [`Client::resolve`] instead.
@@ -126,6 +216,19 @@ pub trait ResolverIroh {
         size: u64,
         node_addr: NodeAddr,
     ) -> anyhow::Result<ResolveResult>;
+
+    /// Send a hash for resolution from the Iroh nodes of peers providing a subnet,
+    /// e.g. when the original source of the blob is offline, await its completion,
+    /// then return the result, to be inspected by the caller.
+    async fn resolve_iroh_from_subnet(
+        &self,
+        hash: Hash,
+        size: u64,
+        subnet_id: SubnetID,
+    ) -> anyhow::Result<ResolveResult>;
+
+    /// Find out whether our own Iroh node has a blob, and where others can download it from.
+    async fn locate_iroh(&self, hash: Hash) -> anyhow::Result<Option<BlobLocation>>;
 }
 
 #[async_trait]
@@ -145,6 +248,26 @@ where
         let res = rx.await?;
         Ok(res)
     }
+
+    async fn resolve_iroh_from_subnet(
+        &self,
+        hash: Hash,
+        size: u64,
+        subnet_id: SubnetID,
+    ) -> anyhow::Result<ResolveResult> {
+        let (tx, rx) = oneshot::channel();
+        let req = Request::ResolveIrohFromSubnet(hash, size, subnet_id, tx);
+        self.send_request(req)?;
+        let res = rx.await?;
+        Ok(res)
+    }
+
+    async fn locate_iroh(&self, hash: Hash) -> anyhow::Result<Option<BlobLocation>> {
+        let (tx, rx) = oneshot::channel();
+        let req = Request::LocateIroh(hash, tx);
+        self.send_request(req)?;
+        rx.await?
+    }
 }
 
 /// Trait to limit the capabilities to reading data from Iroh.
@@ -1,11 +1,16 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: MIT
+mod bandwidth;
 mod behaviour;
 mod client;
 mod hash;
 mod limiter;
 mod observe;
+mod peer_store;
+mod reputation;
+mod resolve_queue;
 mod service;
+mod stats;
 mod timestamp;
 
 mod provider_cache;
@@ -19,8 +24,20 @@ mod arb;
 #[cfg(feature = "missing_blocks")]
 pub mod missing_blocks;
 
-pub use behaviour::{ContentConfig, DiscoveryConfig, MembershipConfig, NetworkConfig};
+#[cfg(feature = "sim")]
+pub mod sim;
+
+pub use bandwidth::PeerBandwidth;
+pub use behaviour::blob::{BlobAnnouncement, BlobLocation, SignedBlobAnnouncement};
+pub use behaviour::membership::SubnetValidators;
+pub use behaviour::{
+    ContentConfig, DiscoveryConfig, GatingMode, MembershipConfig, NatConfig, NetworkConfig,
+};
 pub use client::{Client, Resolver, ResolverIroh, ResolverIrohReadRequest};
-pub use service::{Config, ConnectionConfig, Event, NoKnownPeers, Service};
+pub use peer_store::PeerStoreConfig;
+pub use reputation::ReputationConfig;
+pub use service::{
+    Config, ConnectionConfig, Event, NoKnownPeers, ProviderInfo, QueueConfig, Service,
+};
 pub use timestamp::Timestamp;
 pub use vote_record::{ValidatorKey, VoteRecord};
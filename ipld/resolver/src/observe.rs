@@ -1,5 +1,8 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: MIT
+use crate::reputation::Offence;
+use crate::stats::Resolved;
+use ipc_api::subnet_id::SubnetID;
 use ipc_observability::{
     impl_traceable, impl_traceables, register_metrics, Recordable, TraceLevel, Traceable,
 };
@@ -8,8 +11,9 @@ use libipld::cid::Cid;
 use libp2p::gossipsub::TopicHash;
 use libp2p::PeerId;
 use prometheus::{
-    register_histogram, register_int_counter, register_int_gauge, Histogram, IntCounter, IntGauge,
-    Registry,
+    exponential_buckets, register_histogram, register_histogram_vec, register_int_counter,
+    register_int_counter_vec, register_int_gauge, Histogram, HistogramVec, IntCounter,
+    IntCounterVec, IntGauge, Registry,
 };
 use std::fmt;
 use std::time::Duration;
@@ -33,15 +37,66 @@ register_metrics! {
     IPLD_RESOLVER_IDENTIFY_RECEIVED: IntCounter =
         register_int_counter!("ipld_resolver_identify_received", "Number of Identify infos received");
 
+    IPLD_RESOLVER_CONNECTION_ESTABLISHED: IntCounterVec =
+        register_int_counter_vec!("ipld_resolver_connection_established", "Number of connections established", &["transport"]);
+
+    IPLD_RESOLVER_CONNECTION_CLOSED: IntCounterVec =
+        register_int_counter_vec!("ipld_resolver_connection_closed", "Number of connections closed", &["transport"]);
+
+    IPLD_RESOLVER_CONNECTION_SETUP_SECS: HistogramVec =
+        register_histogram_vec!("ipld_resolver_connection_setup_secs", "Time it took to establish a connection", &["transport"]);
+
     IPLD_RESOLVER_DISCOVERY_BACKGROUND_LOOKUP: IntCounter =
         register_int_counter!("ipld_resolver_discovery_background_lookup", "Number of background lookups started");
 
     IPLD_RESOLVER_DISCOVERY_CONNECTED_PEERS: IntGauge =
         register_int_gauge!("ipld_resolver_discovery_connected_peers", "Number of connections");
 
+    IPLD_RESOLVER_DISCOVERY_MDNS_DISCOVERED: IntCounter =
+        register_int_counter!("ipld_resolver_discovery_mdns_discovered", "Number of peer addresses discovered with mDNS");
+
+    IPLD_RESOLVER_DISCOVERY_PROVIDER_LOOKUP: IntCounter =
+        register_int_counter!("ipld_resolver_discovery_provider_lookup", "Number of subnet provider lookups started in the DHT");
+
+    IPLD_RESOLVER_DISCOVERY_PROVIDER_LOOKUP_SECS: HistogramVec =
+        register_histogram_vec!("ipld_resolver_discovery_provider_lookup_secs", "Time it took to look up the providers of a subnet in the DHT", &["subnet"]);
+
+    IPLD_RESOLVER_DISCOVERY_PROVIDER_FOUND: IntCounter =
+        register_int_counter!("ipld_resolver_discovery_provider_found", "Number of subnet providers found in the DHT");
+
+    IPLD_RESOLVER_DISCOVERY_ADDRESS_VERIFIED: IntCounter =
+        register_int_counter!("ipld_resolver_discovery_address_verified", "Number of Identify addresses added after a successful dial-back");
+
+    IPLD_RESOLVER_DISCOVERY_ADDRESS_REJECTED: IntCounter =
+        register_int_counter!("ipld_resolver_discovery_address_rejected", "Number of Identify addresses rejected because they could not be dialed");
+
+    IPLD_RESOLVER_NAT_PUBLIC: IntGauge =
+        register_int_gauge!("ipld_resolver_nat_public", "Whether AutoNAT found the node publicly reachable");
+
+    IPLD_RESOLVER_NAT_RELAY_RESERVATIONS: IntCounter =
+        register_int_counter!("ipld_resolver_nat_relay_reservations", "Number of reservations accepted by relays we use");
+
+    IPLD_RESOLVER_NAT_RELAY_RESERVATIONS_SERVED: IntCounter =
+        register_int_counter!("ipld_resolver_nat_relay_reservations_served", "Number of reservations we accepted as a relay");
+
+    IPLD_RESOLVER_NAT_HOLE_PUNCH_SUCCESS: IntCounter =
+        register_int_counter!("ipld_resolver_nat_hole_punch_success", "Number of relayed connections upgraded to direct ones");
+
+    IPLD_RESOLVER_NAT_HOLE_PUNCH_FAILURE: IntCounter =
+        register_int_counter!("ipld_resolver_nat_hole_punch_failure", "Number of failed hole punching attempts");
+
+    IPLD_RESOLVER_REPUTATION_PENALTIES: IntCounterVec =
+        register_int_counter_vec!("ipld_resolver_reputation_penalties", "Number of times peers were penalized", &["offence"]);
+
+    IPLD_RESOLVER_REPUTATION_BANNED_PEERS: IntGauge =
+        register_int_gauge!("ipld_resolver_reputation_banned_peers", "Number of currently banned peers");
+
     IPLD_RESOLVER_MEMBERSHIP_SKIPPED_PEERS: IntCounter =
         register_int_counter!("ipld_resolver_membership_skipped_peers", "Number of providers skipped");
 
+    IPLD_RESOLVER_MEMBERSHIP_GATED_PEERS: IntCounter =
+        register_int_counter!("ipld_resolver_membership_gated_peers", "Number of connections refused or closed because the peer isn't a member of a subnet of interest");
+
     IPLD_RESOLVER_MEMBERSHIP_ROUTABLE_PEERS: IntGauge =
         register_int_gauge!("ipld_resolver_membership_routable_peers", "Number of routable peers");
 
@@ -81,8 +136,35 @@ register_metrics! {
     IPLD_RESOLVER_CONTENT_CONNECTED_PEERS: Histogram =
         register_histogram!("ipld_resolver_content_connected_peers", "Number of connected peers in a resolution");
 
-    IPLD_RESOLVER_CONTENT_RATE_LIMITED: IntCounter =
-        register_int_counter!("ipld_resolver_content_rate_limited", "Number of rate limited requests");
+    IPLD_RESOLVER_CONTENT_RATE_LIMITED: IntCounterVec =
+        register_int_counter_vec!("ipld_resolver_content_rate_limited", "Number of rate limited requests", &["limit"]);
+
+    IPLD_RESOLVER_CONTENT_REQUESTS_SERVED: IntCounter =
+        register_int_counter!("ipld_resolver_content_requests_served", "Number of requests from remote peers which were served");
+
+    IPLD_RESOLVER_CONTENT_BYTES_SENT: IntCounter =
+        register_int_counter!("ipld_resolver_content_bytes_sent", "Number of bytes sent to remote peers in blocks");
+
+    IPLD_RESOLVER_RESOLVE_TOTAL: IntCounterVec =
+        register_int_counter_vec!("ipld_resolver_resolve_total", "Number of completed resolutions", &["kind", "subnet", "outcome"]);
+
+    IPLD_RESOLVER_RESOLVE_DURATION_SECS: HistogramVec =
+        register_histogram_vec!("ipld_resolver_resolve_duration_secs", "Time it took to complete resolutions", &["kind", "subnet"], exponential_buckets(0.1, 2.0, 14).unwrap());
+
+    IPLD_RESOLVER_RESOLVE_BYTES: HistogramVec =
+        register_histogram_vec!("ipld_resolver_resolve_bytes", "Number of bytes transferred by successful resolutions, where known", &["kind", "subnet"], exponential_buckets(1024.0, 4.0, 12).unwrap());
+
+    IPLD_RESOLVER_CONTENT_BYTES_RECEIVED: IntCounter =
+        register_int_counter!("ipld_resolver_content_bytes_received", "Number of bytes received from remote peers in blocks");
+
+    IPLD_RESOLVER_QUEUE_DEPTH: IntGauge =
+        register_int_gauge!("ipld_resolver_queue_depth", "Number of resolutions waiting in the queue");
+
+    IPLD_RESOLVER_QUEUE_WAIT_SECS: HistogramVec =
+        register_histogram_vec!("ipld_resolver_queue_wait_secs", "Time resolutions spent waiting in the queue", &["priority"]);
+
+    IPLD_RESOLVER_QUEUE_DROPPED: IntCounterVec =
+        register_int_counter_vec!("ipld_resolver_queue_dropped", "Number of resolutions dropped from the queue", &["priority", "reason"]);
 }
 
 const DOMAIN: &str = "IPLD";
@@ -91,11 +173,17 @@ impl_traceables!(TraceLevel::Info, DOMAIN, PingEvent);
 impl_traceables!(TraceLevel::Warn, DOMAIN, PingFailureEvent);
 impl_traceables!(TraceLevel::Info, DOMAIN, IdentifyEvent);
 impl_traceables!(TraceLevel::Warn, DOMAIN, IdentifyFailureEvent);
+impl_traceables!(TraceLevel::Debug, DOMAIN, ConnectionEvent);
 impl_traceables!(TraceLevel::Info, DOMAIN, DiscoveryEvent);
+impl_traceables!(TraceLevel::Info, DOMAIN, NatEvent);
+impl_traceables!(TraceLevel::Warn, DOMAIN, NatFailureEvent);
+impl_traceables!(TraceLevel::Info, DOMAIN, ReputationEvent);
 impl_traceables!(TraceLevel::Info, DOMAIN, MembershipEvent);
 impl_traceables!(TraceLevel::Warn, DOMAIN, MembershipFailureEvent);
+impl_traceables!(TraceLevel::Debug, DOMAIN, ContentEvent);
 impl_traceables!(TraceLevel::Info, DOMAIN, ResolveEvent);
 impl_traceables!(TraceLevel::Warn, DOMAIN, ResolveFailureEvent);
+impl_traceables!(TraceLevel::Debug, DOMAIN, QueueEvent);
 
 #[allow(dead_code)]
 pub enum PingEvent {
@@ -196,11 +284,55 @@ impl fmt::Debug for IdentifyFailureEvent {
     }
 }
 
+/// Connection events labelled with the transport they use.
+#[allow(dead_code)]
+pub enum ConnectionEvent {
+    Established(&'static str, Duration),
+    Closed(&'static str),
+}
+
+impl Recordable for ConnectionEvent {
+    fn record_metrics(&self) {
+        match self {
+            Self::Established(transport, setup) => {
+                IPLD_RESOLVER_CONNECTION_ESTABLISHED
+                    .with_label_values(&[*transport])
+                    .inc();
+                IPLD_RESOLVER_CONNECTION_SETUP_SECS
+                    .with_label_values(&[*transport])
+                    .observe(setup.as_secs_f64());
+            }
+            Self::Closed(transport) => IPLD_RESOLVER_CONNECTION_CLOSED
+                .with_label_values(&[*transport])
+                .inc(),
+        }
+    }
+}
+
+impl fmt::Debug for ConnectionEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionEvent::Established(transport, setup) => {
+                write!(f, "Connection::Established({:?}, {:?})", transport, setup)
+            }
+            ConnectionEvent::Closed(transport) => {
+                write!(f, "Connection::Closed({:?})", transport)
+            }
+        }
+    }
+}
+
 #[allow(dead_code)]
 pub enum DiscoveryEvent {
     BackgroundLookup(PeerId),
     ConnectionEstablished(PeerId),
     ConnectionClosed(PeerId),
+    MdnsDiscovered(PeerId),
+    ProviderLookup(SubnetID),
+    ProviderLookupFinished(SubnetID, Duration),
+    ProviderFound(PeerId),
+    AddressVerified(PeerId),
+    AddressRejected(PeerId),
 }
 
 impl Recordable for DiscoveryEvent {
@@ -209,6 +341,16 @@ impl Recordable for DiscoveryEvent {
             Self::BackgroundLookup(_) => IPLD_RESOLVER_DISCOVERY_BACKGROUND_LOOKUP.inc(),
             Self::ConnectionEstablished(_) => IPLD_RESOLVER_DISCOVERY_CONNECTED_PEERS.inc(),
             Self::ConnectionClosed(_) => IPLD_RESOLVER_DISCOVERY_CONNECTED_PEERS.dec(),
+            Self::MdnsDiscovered(_) => IPLD_RESOLVER_DISCOVERY_MDNS_DISCOVERED.inc(),
+            Self::ProviderLookup(_) => IPLD_RESOLVER_DISCOVERY_PROVIDER_LOOKUP.inc(),
+            Self::ProviderLookupFinished(subnet_id, duration) => {
+                IPLD_RESOLVER_DISCOVERY_PROVIDER_LOOKUP_SECS
+                    .with_label_values(&[&subnet_id.to_string()])
+                    .observe(duration.as_secs_f64())
+            }
+            Self::ProviderFound(_) => IPLD_RESOLVER_DISCOVERY_PROVIDER_FOUND.inc(),
+            Self::AddressVerified(_) => IPLD_RESOLVER_DISCOVERY_ADDRESS_VERIFIED.inc(),
+            Self::AddressRejected(_) => IPLD_RESOLVER_DISCOVERY_ADDRESS_REJECTED.inc(),
         }
     }
 }
@@ -225,6 +367,125 @@ impl fmt::Debug for DiscoveryEvent {
             DiscoveryEvent::ConnectionClosed(peer_id) => {
                 write!(f, "Discovery::ConnectionClosed({:?})", peer_id)
             }
+            DiscoveryEvent::MdnsDiscovered(peer_id) => {
+                write!(f, "Discovery::MdnsDiscovered({:?})", peer_id)
+            }
+            DiscoveryEvent::ProviderLookup(subnet_id) => {
+                write!(f, "Discovery::ProviderLookup({})", subnet_id)
+            }
+            DiscoveryEvent::ProviderLookupFinished(subnet_id, duration) => {
+                write!(
+                    f,
+                    "Discovery::ProviderLookupFinished({}, {:?})",
+                    subnet_id, duration
+                )
+            }
+            DiscoveryEvent::ProviderFound(peer_id) => {
+                write!(f, "Discovery::ProviderFound({:?})", peer_id)
+            }
+            DiscoveryEvent::AddressVerified(peer_id) => {
+                write!(f, "Discovery::AddressVerified({:?})", peer_id)
+            }
+            DiscoveryEvent::AddressRejected(peer_id) => {
+                write!(f, "Discovery::AddressRejected({:?})", peer_id)
+            }
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub enum NatEvent {
+    /// Whether the node is publicly reachable.
+    StatusChanged(bool),
+    RelayReservationAccepted(PeerId),
+    RelayReservationServed(PeerId),
+    HolePunchSuccess(PeerId),
+}
+
+impl Recordable for NatEvent {
+    fn record_metrics(&self) {
+        match self {
+            Self::StatusChanged(public) => IPLD_RESOLVER_NAT_PUBLIC.set(*public as i64),
+            Self::RelayReservationAccepted(_) => IPLD_RESOLVER_NAT_RELAY_RESERVATIONS.inc(),
+            Self::RelayReservationServed(_) => IPLD_RESOLVER_NAT_RELAY_RESERVATIONS_SERVED.inc(),
+            Self::HolePunchSuccess(_) => IPLD_RESOLVER_NAT_HOLE_PUNCH_SUCCESS.inc(),
+        }
+    }
+}
+
+impl fmt::Debug for NatEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NatEvent::StatusChanged(public) => {
+                write!(f, "Nat::StatusChanged({:?})", public)
+            }
+            NatEvent::RelayReservationAccepted(peer_id) => {
+                write!(f, "Nat::RelayReservationAccepted({:?})", peer_id)
+            }
+            NatEvent::RelayReservationServed(peer_id) => {
+                write!(f, "Nat::RelayReservationServed({:?})", peer_id)
+            }
+            NatEvent::HolePunchSuccess(peer_id) => {
+                write!(f, "Nat::HolePunchSuccess({:?})", peer_id)
+            }
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub enum NatFailureEvent {
+    HolePunchFailure(PeerId, String),
+}
+
+impl Recordable for NatFailureEvent {
+    fn record_metrics(&self) {
+        match self {
+            Self::HolePunchFailure(_, _) => IPLD_RESOLVER_NAT_HOLE_PUNCH_FAILURE.inc(),
+        }
+    }
+}
+
+impl fmt::Debug for NatFailureEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NatFailureEvent::HolePunchFailure(peer_id, reason) => {
+                write!(f, "Nat::HolePunchFailure({:?}, {:?})", peer_id, reason)
+            }
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub enum ReputationEvent {
+    Penalized(PeerId, Offence),
+    Banned(PeerId),
+    Unbanned(PeerId),
+}
+
+impl Recordable for ReputationEvent {
+    fn record_metrics(&self) {
+        match self {
+            Self::Penalized(_, offence) => IPLD_RESOLVER_REPUTATION_PENALTIES
+                .with_label_values(&[&format!("{:?}", offence)])
+                .inc(),
+            Self::Banned(_) => IPLD_RESOLVER_REPUTATION_BANNED_PEERS.inc(),
+            Self::Unbanned(_) => IPLD_RESOLVER_REPUTATION_BANNED_PEERS.dec(),
+        }
+    }
+}
+
+impl fmt::Debug for ReputationEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReputationEvent::Penalized(peer_id, offence) => {
+                write!(f, "Reputation::Penalized({:?}, {:?})", peer_id, offence)
+            }
+            ReputationEvent::Banned(peer_id) => {
+                write!(f, "Reputation::Banned({:?})", peer_id)
+            }
+            ReputationEvent::Unbanned(peer_id) => {
+                write!(f, "Reputation::Unbanned({:?})", peer_id)
+            }
         }
     }
 }
@@ -234,6 +495,7 @@ pub enum MembershipEvent {
     Added(PeerId),
     Removed(PeerId),
     Skipped(PeerId),
+    Gated(PeerId),
     PublishSuccess,
     RoutablePeers(i64),
 }
@@ -244,6 +506,7 @@ impl Recordable for MembershipEvent {
             Self::Added(_) => IPLD_RESOLVER_MEMBERSHIP_PROVIDER_PEERS.inc(),
             Self::Removed(_) => IPLD_RESOLVER_MEMBERSHIP_PROVIDER_PEERS.dec(),
             Self::Skipped(_) => IPLD_RESOLVER_MEMBERSHIP_SKIPPED_PEERS.inc(),
+            Self::Gated(_) => IPLD_RESOLVER_MEMBERSHIP_GATED_PEERS.inc(),
             Self::PublishSuccess => IPLD_RESOLVER_MEMBERSHIP_PUBLISH_SUCCESS.inc(),
             Self::RoutablePeers(num_routable) => {
                 IPLD_RESOLVER_MEMBERSHIP_ROUTABLE_PEERS.set(*num_routable)
@@ -264,6 +527,9 @@ impl fmt::Debug for MembershipEvent {
             MembershipEvent::Skipped(peer_id) => {
                 write!(f, "Membership::Skipped({:?})", peer_id)
             }
+            MembershipEvent::Gated(peer_id) => {
+                write!(f, "Membership::Gated({:?})", peer_id)
+            }
             MembershipEvent::PublishSuccess => {
                 write!(f, "Membership::PublishSuccess")
             }
@@ -326,6 +592,49 @@ impl fmt::Debug for MembershipFailureEvent {
     }
 }
 
+/// Serving content to remote peers.
+#[allow(dead_code)]
+pub enum ContentEvent {
+    RequestServed(PeerId),
+    BlockSent(PeerId, usize),
+    BlockReceived(PeerId, usize),
+    RateLimited(PeerId, &'static str),
+}
+
+impl Recordable for ContentEvent {
+    fn record_metrics(&self) {
+        match self {
+            Self::RequestServed(_) => IPLD_RESOLVER_CONTENT_REQUESTS_SERVED.inc(),
+            Self::BlockSent(_, bytes) => IPLD_RESOLVER_CONTENT_BYTES_SENT.inc_by(*bytes as u64),
+            Self::BlockReceived(_, bytes) => {
+                IPLD_RESOLVER_CONTENT_BYTES_RECEIVED.inc_by(*bytes as u64)
+            }
+            Self::RateLimited(_, limit) => IPLD_RESOLVER_CONTENT_RATE_LIMITED
+                .with_label_values(&[*limit])
+                .inc(),
+        }
+    }
+}
+
+impl fmt::Debug for ContentEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentEvent::RequestServed(peer_id) => {
+                write!(f, "Content::RequestServed({:?})", peer_id)
+            }
+            ContentEvent::BlockSent(peer_id, bytes) => {
+                write!(f, "Content::BlockSent({:?}, {:?})", peer_id, bytes)
+            }
+            ContentEvent::BlockReceived(peer_id, bytes) => {
+                write!(f, "Content::BlockReceived({:?}, {:?})", peer_id, bytes)
+            }
+            ContentEvent::RateLimited(peer_id, limit) => {
+                write!(f, "Content::RateLimited({:?}, {:?})", peer_id, limit)
+            }
+        }
+    }
+}
+
 #[allow(dead_code)]
 pub enum ResolveEvent {
     Started(Cid),
@@ -334,6 +643,7 @@ pub enum ResolveEvent {
     Peers(usize),
     NoPeers,
     ConnectedPeers(usize),
+    Finished(Resolved),
 }
 
 impl Recordable for ResolveEvent {
@@ -345,6 +655,26 @@ impl Recordable for ResolveEvent {
             Self::Peers(num) => IPLD_RESOLVER_CONTENT_RESOLVE_PEERS.observe(*num as f64),
             Self::NoPeers => IPLD_RESOLVER_CONTENT_RESOLVE_NO_PEERS.inc(),
             Self::ConnectedPeers(num) => IPLD_RESOLVER_CONTENT_CONNECTED_PEERS.observe(*num as f64),
+            Self::Finished(resolved) => {
+                let kind = resolved.kind.label();
+                let subnet = resolved.subnet_label();
+                let outcome = if resolved.success {
+                    "success"
+                } else {
+                    "failure"
+                };
+                IPLD_RESOLVER_RESOLVE_TOTAL
+                    .with_label_values(&[kind, &subnet, outcome])
+                    .inc();
+                IPLD_RESOLVER_RESOLVE_DURATION_SECS
+                    .with_label_values(&[kind, &subnet])
+                    .observe(resolved.duration.as_secs_f64());
+                if let Some(bytes) = resolved.bytes {
+                    IPLD_RESOLVER_RESOLVE_BYTES
+                        .with_label_values(&[kind, &subnet])
+                        .observe(bytes as f64);
+                }
+            }
         }
     }
 }
@@ -370,6 +700,9 @@ impl fmt::Debug for ResolveEvent {
             ResolveEvent::ConnectedPeers(count) => {
                 write!(f, "Resolve::ConnectedPeers({:?})", count)
             }
+            ResolveEvent::Finished(resolved) => {
+                write!(f, "Resolve::Finished({:?})", resolved)
+            }
         }
     }
 }
@@ -402,9 +735,47 @@ impl fmt::Debug for ResolveFailureEvent {
     }
 }
 
+#[allow(dead_code)]
+pub enum QueueEvent {
+    Depth(usize),
+    Started(&'static str, Duration),
+    Dropped(&'static str, &'static str),
+}
+
+impl Recordable for QueueEvent {
+    fn record_metrics(&self) {
+        match self {
+            Self::Depth(depth) => IPLD_RESOLVER_QUEUE_DEPTH.set(*depth as i64),
+            Self::Started(priority, wait) => IPLD_RESOLVER_QUEUE_WAIT_SECS
+                .with_label_values(&[*priority])
+                .observe(wait.as_secs_f64()),
+            Self::Dropped(priority, reason) => IPLD_RESOLVER_QUEUE_DROPPED
+                .with_label_values(&[*priority, *reason])
+                .inc(),
+        }
+    }
+}
+
+impl fmt::Debug for QueueEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueueEvent::Depth(depth) => {
+                write!(f, "Queue::Depth({:?})", depth)
+            }
+            QueueEvent::Started(priority, wait) => {
+                write!(f, "Queue::Started({}, {:?})", priority, wait)
+            }
+            QueueEvent::Dropped(priority, reason) => {
+                write!(f, "Queue::Dropped({}, {})", priority, reason)
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::stats::ResolveKind;
     use ipc_observability::emit;
 
     #[test]
@@ -425,12 +796,36 @@ mod tests {
         emit(PingFailureEvent::Failure(peer_id, err_str.clone()));
         emit(IdentifyEvent::Received(peer_id));
         emit(IdentifyFailureEvent::Failure(peer_id, err_str.clone()));
+        emit(ConnectionEvent::Established("quic", rtt));
+        emit(ConnectionEvent::Closed("quic"));
         emit(DiscoveryEvent::BackgroundLookup(peer_id));
         emit(DiscoveryEvent::ConnectionEstablished(peer_id));
         emit(DiscoveryEvent::ConnectionClosed(peer_id));
+        emit(DiscoveryEvent::MdnsDiscovered(peer_id));
+        emit(DiscoveryEvent::ProviderLookup(SubnetID::default()));
+        emit(DiscoveryEvent::ProviderLookupFinished(
+            SubnetID::default(),
+            rtt,
+        ));
+        emit(DiscoveryEvent::ProviderFound(peer_id));
+        emit(DiscoveryEvent::AddressVerified(peer_id));
+        emit(DiscoveryEvent::AddressRejected(peer_id));
+        emit(NatEvent::StatusChanged(true));
+        emit(ContentEvent::RequestServed(peer_id));
+        emit(ContentEvent::BlockSent(peer_id, 1024));
+        emit(ContentEvent::BlockReceived(peer_id, 1024));
+        emit(ContentEvent::RateLimited(peer_id, "peer"));
+        emit(NatEvent::RelayReservationAccepted(peer_id));
+        emit(NatEvent::RelayReservationServed(peer_id));
+        emit(NatEvent::HolePunchSuccess(peer_id));
+        emit(NatFailureEvent::HolePunchFailure(peer_id, err_str.clone()));
+        emit(ReputationEvent::Penalized(peer_id, Offence::InvalidRecord));
+        emit(ReputationEvent::Banned(peer_id));
+        emit(ReputationEvent::Unbanned(peer_id));
         emit(MembershipEvent::Added(peer_id));
         emit(MembershipEvent::Removed(peer_id));
         emit(MembershipEvent::Skipped(peer_id));
+        emit(MembershipEvent::Gated(peer_id));
         emit(MembershipEvent::PublishSuccess);
         emit(MembershipEvent::RoutablePeers(Default::default()));
         emit(MembershipFailureEvent::PublishFailure(err_str.clone()));
@@ -452,6 +847,16 @@ mod tests {
         emit(ResolveEvent::Peers(Default::default()));
         emit(ResolveEvent::NoPeers);
         emit(ResolveEvent::ConnectedPeers(Default::default()));
+        emit(ResolveEvent::Finished(Resolved {
+            kind: ResolveKind::Blob,
+            subnet_id: Some(SubnetID::default()),
+            success: true,
+            duration: rtt,
+            bytes: Some(1024),
+        }));
+        emit(QueueEvent::Depth(1));
+        emit(QueueEvent::Started("critical", rtt));
+        emit(QueueEvent::Dropped("prefetch", "full"));
         emit(ResolveFailureEvent::Failure(cid));
         emit(ResolveFailureEvent::Fallback(cid));
     }
@@ -0,0 +1,249 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! An address book of the peers discovered by the resolver, persisted across restarts,
+//! so a node doesn't have to start cold from its bootstrap nodes every time.
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use libp2p::{Multiaddr, PeerId};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::Timestamp;
+
+/// Maximum number of addresses remembered per peer; the most recent ones are kept.
+const MAX_ADDRESSES_PER_PEER: usize = 8;
+/// Reputation gained for each established connection.
+const CONNECTED_REWARD: i32 = 1;
+/// Reputation lost for each failed dial.
+const DIAL_FAILURE_PENALTY: i32 = 2;
+/// Reputation is capped so a long-lived peer that goes away is forgotten in reasonable time.
+const MAX_REPUTATION: i32 = 20;
+/// Peers at or below this reputation are forgotten.
+const MIN_REPUTATION: i32 = -10;
+
+/// Configuration for the [`PeerStore`].
+#[derive(Clone, Debug)]
+pub struct PeerStoreConfig {
+    /// File the address book is loaded from and flushed to.
+    pub path: PathBuf,
+    /// Maximum number of peers to remember.
+    pub max_peers: usize,
+    /// Peers not seen for longer than this are forgotten.
+    pub max_age: Duration,
+    /// Interval between flushes to disk.
+    pub flush_interval: Duration,
+}
+
+/// What we remember about a peer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PeerRecord {
+    /// Known addresses of the peer, oldest first.
+    pub addresses: Vec<Multiaddr>,
+    /// When we last learned an address of the peer or connected to it.
+    pub last_seen: Timestamp,
+    /// Increases with established connections and decreases with failed dials.
+    pub reputation: i32,
+}
+
+/// On-disk address book of peers.
+pub struct PeerStore {
+    config: PeerStoreConfig,
+    peers: HashMap<PeerId, PeerRecord>,
+    /// Whether there are changes that haven't been flushed yet.
+    dirty: bool,
+}
+
+impl PeerStore {
+    /// Load the address book, or start an empty one if the file doesn't exist yet.
+    pub fn load(config: PeerStoreConfig) -> anyhow::Result<Self> {
+        let peers = if config.path.exists() {
+            let bytes = std::fs::read(&config.path)
+                .with_context(|| format!("failed to read {}", config.path.display()))?;
+            let peers: Vec<(PeerId, PeerRecord)> = serde_json::from_slice(&bytes)
+                .with_context(|| format!("failed to parse {}", config.path.display()))?;
+            peers.into_iter().collect()
+        } else {
+            HashMap::new()
+        };
+        let mut store = Self {
+            config,
+            peers,
+            dirty: false,
+        };
+        store.prune();
+        Ok(store)
+    }
+
+    /// Peers in the address book, best first.
+    pub fn peers(&self) -> Vec<(PeerId, &PeerRecord)> {
+        let mut peers = self
+            .peers
+            .iter()
+            .map(|(id, r)| (*id, r))
+            .collect::<Vec<_>>();
+        peers.sort_by(|(_, a), (_, b)| {
+            b.reputation
+                .cmp(&a.reputation)
+                .then(b.last_seen.cmp(&a.last_seen))
+        });
+        peers
+    }
+
+    /// Known addresses of a peer.
+    pub fn addresses_of(&self, peer_id: &PeerId) -> Vec<Multiaddr> {
+        self.peers
+            .get(peer_id)
+            .map(|r| r.addresses.clone())
+            .unwrap_or_default()
+    }
+
+    /// Remember an address of a peer.
+    pub fn add_address(&mut self, peer_id: PeerId, addr: Multiaddr) {
+        let record = self.peers.entry(peer_id).or_insert_with(|| PeerRecord {
+            addresses: Vec::new(),
+            last_seen: Timestamp::now(),
+            reputation: 0,
+        });
+        record.addresses.retain(|a| *a != addr);
+        record.addresses.push(addr);
+        if record.addresses.len() > MAX_ADDRESSES_PER_PEER {
+            record.addresses.remove(0);
+        }
+        record.last_seen = Timestamp::now();
+        self.dirty = true;
+    }
+
+    /// Reward a known peer for a successful connection.
+    pub fn record_connected(&mut self, peer_id: &PeerId) {
+        if let Some(record) = self.peers.get_mut(peer_id) {
+            record.reputation = (record.reputation + CONNECTED_REWARD).min(MAX_REPUTATION);
+            record.last_seen = Timestamp::now();
+            self.dirty = true;
+        }
+    }
+
+    /// Penalize a known peer for a failed dial, forgetting it if it keeps failing.
+    pub fn record_dial_failure(&mut self, peer_id: &PeerId) {
+        if let Some(record) = self.peers.get_mut(peer_id) {
+            record.reputation -= DIAL_FAILURE_PENALTY;
+            if record.reputation <= MIN_REPUTATION {
+                self.peers.remove(peer_id);
+            }
+            self.dirty = true;
+        }
+    }
+
+    /// Write the address book to disk, if it changed since the last flush.
+    ///
+    /// The file is replaced atomically, so a crash during the flush leaves the previous version.
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.prune();
+        let peers = self.peers.iter().collect::<Vec<_>>();
+        let bytes = serde_json::to_vec(&peers)?;
+        if let Some(dir) = self.config.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = self.config.path.with_extension("tmp");
+        std::fs::write(&tmp, bytes)
+            .with_context(|| format!("failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.config.path)
+            .with_context(|| format!("failed to replace {}", self.config.path.display()))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Interval between flushes.
+    pub fn flush_interval(&self) -> Duration {
+        self.config.flush_interval
+    }
+
+    /// Forget peers that haven't been seen recently, then the worst ones over capacity.
+    fn prune(&mut self) {
+        let cutoff = Timestamp::now() - self.config.max_age;
+        let before = self.peers.len();
+        self.peers.retain(|_, r| r.last_seen >= cutoff);
+        if self.peers.len() > self.config.max_peers {
+            let keep = self
+                .peers()
+                .into_iter()
+                .take(self.config.max_peers)
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>();
+            self.peers.retain(|id, _| keep.contains(id));
+        }
+        if self.peers.len() != before {
+            self.dirty = true;
+        }
+    }
+}
+
+impl Drop for PeerStore {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            warn!("failed to flush peer store: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(dir: &tempfile::TempDir, max_peers: usize) -> PeerStoreConfig {
+        PeerStoreConfig {
+            path: dir.path().join("peers.json"),
+            max_peers,
+            max_age: Duration::from_secs(3600),
+            flush_interval: Duration::from_secs(60),
+        }
+    }
+
+    fn addr(port: u16) -> Multiaddr {
+        format!("/ip4/127.0.0.1/tcp/{port}").parse().unwrap()
+    }
+
+    #[test]
+    fn test_flush_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let peer_id = PeerId::random();
+        {
+            let mut store = PeerStore::load(config(&dir, 10)).unwrap();
+            assert!(store.peers().is_empty());
+            store.add_address(peer_id, addr(1));
+            store.add_address(peer_id, addr(2));
+            store.add_address(peer_id, addr(1));
+            store.record_connected(&peer_id);
+            store.flush().unwrap();
+        }
+        let store = PeerStore::load(config(&dir, 10)).unwrap();
+        assert_eq!(store.addresses_of(&peer_id), vec![addr(2), addr(1)]);
+        assert_eq!(store.peers()[0].1.reputation, CONNECTED_REWARD);
+    }
+
+    #[test]
+    fn test_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = PeerStore::load(config(&dir, 2)).unwrap();
+        let peers = (0..3).map(|_| PeerId::random()).collect::<Vec<_>>();
+        for (i, peer_id) in peers.iter().enumerate() {
+            store.add_address(*peer_id, addr(i as u16));
+        }
+        store.record_connected(&peers[0]);
+        store.record_connected(&peers[2]);
+        store.flush().unwrap();
+        assert!(store.addresses_of(&peers[1]).is_empty());
+        assert_eq!(store.peers().len(), 2);
+
+        // Peers that keep failing are forgotten.
+        for _ in 0..10 {
+            store.record_dial_failure(&peers[0]);
+        }
+        assert!(store.addresses_of(&peers[0]).is_empty());
+    }
+}
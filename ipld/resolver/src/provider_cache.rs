@@ -89,6 +89,11 @@ impl SubnetProviderCache {
         self.routable_peers.contains(peer_id)
     }
 
+    /// Timestamp of the latest record received from a peer, if it's a known provider.
+    pub fn peer_timestamp(&self, peer_id: &PeerId) -> Option<Timestamp> {
+        self.peer_timestamps.get(peer_id).cloned()
+    }
+
     /// Check whether we have received recent updates from a peer.
     pub fn has_timestamp(&self, peer_id: &PeerId) -> bool {
         self.peer_timestamps.contains_key(peer_id)
@@ -197,6 +202,15 @@ impl SubnetProviderCache {
         to_prune
     }
 
+    /// List the subnets a peer is known to provide.
+    pub fn subnets_of_peer(&self, peer_id: &PeerId) -> Vec<SubnetID> {
+        self.subnet_providers
+            .iter()
+            .filter(|(_, peer_ids)| peer_ids.contains(peer_id))
+            .map(|(subnet_id, _)| subnet_id.clone())
+            .collect()
+    }
+
     /// List any known providers of a subnet.
     pub fn providers_of_subnet(&self, subnet_id: &SubnetID) -> Vec<PeerId> {
         self.subnet_providers
@@ -0,0 +1,206 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Peer reputation, used to prefer well-behaved providers and to ban abusive peers.
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use libp2p::PeerId;
+
+use crate::Timestamp;
+
+/// Configuration for the peer [`Reputation`].
+#[derive(Clone, Debug)]
+pub struct ReputationConfig {
+    /// Peers whose score drops to or below this (negative) value are temporarily banned.
+    pub ban_threshold: i32,
+    /// How long a peer stays banned after reaching the threshold.
+    pub ban_duration: Duration,
+    /// Peers which are never allowed to connect, until they are explicitly allowed again.
+    pub denylist: Vec<PeerId>,
+}
+
+/// Something a peer did that lowers its score.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Offence {
+    /// A resolution attempt the peer took part in failed.
+    ///
+    /// This can happen to honest peers as well, e.g. if the content isn't available yet,
+    /// so the penalty is small.
+    FailedResolution,
+    /// The peer gossiped a record which could not be parsed or had an invalid signature.
+    InvalidRecord,
+    /// The peer was penalized by the Gossipsub scoring, e.g. for spamming or sending
+    /// messages to topics we don't know about.
+    GossipMisbehaviour,
+}
+
+impl Offence {
+    fn penalty(&self) -> i32 {
+        match self {
+            Offence::FailedResolution => 1,
+            Offence::InvalidRecord => 10,
+            Offence::GossipMisbehaviour => 20,
+        }
+    }
+}
+
+/// Scores of peers based on their behaviour.
+///
+/// Scores start at zero, decrease with each offence, and recover towards zero over time.
+/// Peers with a negative score are de-prioritized when choosing providers, and peers
+/// reaching the ban threshold are banned for a while.
+pub struct Reputation {
+    ban_threshold: i32,
+    ban_duration: Duration,
+    scores: HashMap<PeerId, i32>,
+    /// Peers banned because of their score, until the given time.
+    banned: HashMap<PeerId, Timestamp>,
+    /// Peers banned manually, without expiry.
+    denied: HashSet<PeerId>,
+}
+
+impl Reputation {
+    pub fn new(config: ReputationConfig) -> Self {
+        Self {
+            ban_threshold: config.ban_threshold,
+            ban_duration: config.ban_duration,
+            scores: Default::default(),
+            banned: Default::default(),
+            denied: config.denylist.into_iter().collect(),
+        }
+    }
+
+    /// Current score of a peer; zero if we haven't seen it misbehave.
+    pub fn score(&self, peer_id: &PeerId) -> i32 {
+        self.scores.get(peer_id).cloned().unwrap_or_default()
+    }
+
+    /// Check whether a peer is banned, either because of its score or manually.
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.denied.contains(peer_id) || self.banned.contains_key(peer_id)
+    }
+
+    /// Manually banned peers.
+    pub fn denied(&self) -> impl Iterator<Item = &PeerId> {
+        self.denied.iter()
+    }
+
+    /// Lower the score of a peer. Returns `true` if the peer got banned as a result.
+    pub fn penalize(&mut self, peer_id: PeerId, offence: Offence) -> bool {
+        let score = self.scores.entry(peer_id).or_default();
+        *score = score.saturating_sub(offence.penalty());
+        if *score > self.ban_threshold || self.is_banned(&peer_id) {
+            return false;
+        }
+        self.banned
+            .insert(peer_id, Timestamp::now() + self.ban_duration);
+        true
+    }
+
+    /// Ban a peer until it's explicitly allowed again. Returns `true` if it wasn't already banned.
+    pub fn deny(&mut self, peer_id: PeerId) -> bool {
+        let was_banned = self.is_banned(&peer_id);
+        self.denied.insert(peer_id);
+        !was_banned
+    }
+
+    /// Lift any ban on a peer and reset its score. Returns `true` if it was banned.
+    pub fn allow(&mut self, peer_id: &PeerId) -> bool {
+        let was_banned = self.is_banned(peer_id);
+        self.denied.remove(peer_id);
+        self.banned.remove(peer_id);
+        self.scores.remove(peer_id);
+        was_banned
+    }
+
+    /// Let scores recover by one point, and lift the bans which have expired.
+    ///
+    /// Returns the peers which are no longer banned at all. Their scores are reset,
+    /// so they don't get banned again for their first offence.
+    pub fn tick(&mut self) -> Vec<PeerId> {
+        self.scores.retain(|_, score| {
+            *score += 1;
+            *score < 0
+        });
+        let now = Timestamp::now();
+        let expired = self
+            .banned
+            .iter()
+            .filter(|(_, until)| **until <= now)
+            .map(|(peer_id, _)| *peer_id)
+            .collect::<Vec<_>>();
+        for peer_id in expired.iter() {
+            self.banned.remove(peer_id);
+            self.scores.remove(peer_id);
+        }
+        // Peers which have been denied in the meantime stay banned.
+        expired
+            .into_iter()
+            .filter(|peer_id| !self.denied.contains(peer_id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reputation(ban_duration: Duration) -> Reputation {
+        Reputation::new(ReputationConfig {
+            ban_threshold: -20,
+            ban_duration,
+            denylist: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_ban_on_threshold() {
+        let mut reputation = reputation(Duration::ZERO);
+        let peer_id = PeerId::random();
+
+        assert!(!reputation.penalize(peer_id, Offence::InvalidRecord));
+        assert_eq!(reputation.score(&peer_id), -10);
+        assert!(!reputation.is_banned(&peer_id));
+
+        assert!(reputation.penalize(peer_id, Offence::InvalidRecord));
+        assert!(reputation.is_banned(&peer_id));
+        // Already banned.
+        assert!(!reputation.penalize(peer_id, Offence::FailedResolution));
+
+        // The ban expires immediately.
+        assert_eq!(reputation.tick(), vec![peer_id]);
+        assert!(!reputation.is_banned(&peer_id));
+        assert_eq!(reputation.score(&peer_id), 0);
+    }
+
+    #[test]
+    fn test_recovery() {
+        let mut reputation = reputation(Duration::from_secs(3600));
+        let peer_id = PeerId::random();
+
+        reputation.penalize(peer_id, Offence::FailedResolution);
+        reputation.penalize(peer_id, Offence::FailedResolution);
+        assert_eq!(reputation.score(&peer_id), -2);
+        assert!(reputation.tick().is_empty());
+        assert_eq!(reputation.score(&peer_id), -1);
+        reputation.tick();
+        assert_eq!(reputation.score(&peer_id), 0);
+    }
+
+    #[test]
+    fn test_denylist() {
+        let mut reputation = reputation(Duration::from_secs(3600));
+        let peer_id = PeerId::random();
+
+        assert!(reputation.deny(peer_id));
+        assert!(!reputation.deny(peer_id));
+        assert!(reputation.is_banned(&peer_id));
+        // Manual bans don't expire.
+        assert!(reputation.tick().is_empty());
+        assert!(reputation.is_banned(&peer_id));
+
+        assert!(reputation.allow(&peer_id));
+        assert!(!reputation.is_banned(&peer_id));
+        assert!(!reputation.allow(&peer_id));
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+/// Priority of a resolution waiting in the queue.
+///
+/// Consensus-critical content, like checkpoints, must not be starved by bursts of blobs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Blobs which we download ahead of their finalization.
+    Prefetch,
+    /// Data needed to close read requests.
+    Normal,
+    /// Content needed for top-down or bottom-up checkpointing.
+    Critical,
+}
+
+impl Priority {
+    /// Label to use in metrics.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Priority::Prefetch => "prefetch",
+            Priority::Normal => "normal",
+            Priority::Critical => "critical",
+        }
+    }
+}
+
+/// An item waiting in the [`ResolveQueue`].
+pub struct Queued<T> {
+    pub item: T,
+    pub priority: Priority,
+    pub enqueued: Instant,
+    /// The item is failed instead of started if it's still queued by this time.
+    pub deadline: Instant,
+}
+
+/// Bounded queue of resolutions, handing out the highest priority first,
+/// and in the order of arrival within the same priority.
+pub struct ResolveQueue<T> {
+    capacity: usize,
+    next_seq: u64,
+    items: BTreeMap<(Reverse<Priority>, u64), Queued<T>>,
+}
+
+impl<T> ResolveQueue<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next_seq: 0,
+            items: Default::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Add an item to the queue.
+    ///
+    /// If the queue is full, the newest item with the lowest priority is evicted to make room,
+    /// unless the new item has an even lower priority, in which case it's rejected; either way
+    /// the item which didn't make it is returned.
+    pub fn push(&mut self, item: T, priority: Priority, deadline: Instant) -> Option<T> {
+        self.push_at(item, priority, deadline, Instant::now())
+    }
+
+    /// Same as [`ResolveQueue::push`] but allows passing in the time, for testing.
+    pub fn push_at(
+        &mut self,
+        item: T,
+        priority: Priority,
+        deadline: Instant,
+        now: Instant,
+    ) -> Option<T> {
+        let mut evicted = None;
+        if self.items.len() >= self.capacity {
+            match self.items.last_key_value() {
+                Some(((Reverse(lowest), _), _)) if *lowest < priority => {
+                    evicted = self.items.pop_last().map(|(_, queued)| queued.item);
+                }
+                _ => return Some(item),
+            }
+        }
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.items.insert(
+            (Reverse(priority), seq),
+            Queued {
+                item,
+                priority,
+                enqueued: now,
+                deadline,
+            },
+        );
+        evicted
+    }
+
+    /// Take the next item to work on.
+    pub fn pop(&mut self) -> Option<Queued<T>> {
+        self.items.pop_first().map(|(_, queued)| queued)
+    }
+
+    /// Remove the items whose deadline has passed.
+    pub fn remove_expired(&mut self, now: Instant) -> Vec<Queued<T>> {
+        let expired = self
+            .items
+            .iter()
+            .filter(|(_, queued)| queued.deadline <= now)
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+
+        expired
+            .into_iter()
+            .filter_map(|key| self.items.remove(&key))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{Priority, ResolveQueue};
+
+    #[test]
+    fn pop_by_priority_then_arrival() {
+        let mut queue = ResolveQueue::new(10);
+        let deadline = Instant::now() + Duration::from_secs(60);
+
+        assert!(queue.push(1, Priority::Prefetch, deadline).is_none());
+        assert!(queue.push(2, Priority::Critical, deadline).is_none());
+        assert!(queue.push(3, Priority::Prefetch, deadline).is_none());
+        assert!(queue.push(4, Priority::Normal, deadline).is_none());
+        assert!(queue.push(5, Priority::Critical, deadline).is_none());
+
+        let order = std::iter::from_fn(|| queue.pop().map(|q| q.item)).collect::<Vec<_>>();
+        assert_eq!(order, vec![2, 5, 4, 1, 3]);
+    }
+
+    #[test]
+    fn evict_lower_priority_when_full() {
+        let mut queue = ResolveQueue::new(2);
+        let deadline = Instant::now() + Duration::from_secs(60);
+
+        assert!(queue.push(1, Priority::Prefetch, deadline).is_none());
+        assert!(queue.push(2, Priority::Prefetch, deadline).is_none());
+        // Same priority is rejected.
+        assert_eq!(queue.push(3, Priority::Prefetch, deadline), Some(3));
+        // Higher priority evicts the newest lower priority item.
+        assert_eq!(queue.push(4, Priority::Critical, deadline), Some(2));
+        assert_eq!(queue.len(), 2);
+        // The remaining prefetch goes next, then there is nothing left to evict.
+        assert_eq!(queue.push(5, Priority::Critical, deadline), Some(1));
+        assert_eq!(queue.push(6, Priority::Critical, deadline), Some(6));
+    }
+
+    #[test]
+    fn remove_expired() {
+        let mut queue = ResolveQueue::new(10);
+        let now = Instant::now();
+
+        queue.push_at(1, Priority::Critical, now + Duration::from_secs(5), now);
+        queue.push_at(2, Priority::Prefetch, now + Duration::from_secs(1), now);
+        queue.push_at(3, Priority::Normal, now + Duration::from_secs(10), now);
+
+        let expired = queue.remove_expired(now + Duration::from_secs(5));
+        let mut expired = expired.into_iter().map(|q| q.item).collect::<Vec<_>>();
+        expired.sort();
+        assert_eq!(expired, vec![1, 2]);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.pop().map(|q| q.item), Some(3));
+    }
+}
@@ -1,16 +1,24 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: MIT
 
-use std::collections::HashMap;
-use std::time::Duration;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::bandwidth::PeerBandwidth;
+use crate::behaviour::blob::{BlobAnnouncement, BlobLocation, SignedBlobAnnouncement};
+use crate::behaviour::membership::{PendingProviderRecord, SubnetValidators};
 use crate::behaviour::{
-    content, discovery, membership, Behaviour, BehaviourEvent, ConfigError, ContentConfig,
-    DiscoveryConfig, MembershipConfig, NetworkConfig,
+    blob, content, discovery, membership, Behaviour, BehaviourEvent, ConfigError, ContentConfig,
+    DiscoveryConfig, MembershipConfig, NatConfig, NetworkConfig,
 };
 use crate::client::Client;
 use crate::observe;
+use crate::reputation::{Offence, Reputation, ReputationConfig};
+use crate::resolve_queue::{Priority, ResolveQueue};
+use crate::stats::{ResolveKind, ResolveStats, Resolved};
 use crate::vote_record::{SignedVoteRecord, VoteRecord};
+use crate::Timestamp;
 use anyhow::anyhow;
 use bloom::{BloomFilter, ASMS};
 use ipc_api::subnet_id::SubnetID;
@@ -19,18 +27,19 @@ use iroh::blobs::Hash;
 use iroh::client::blobs::ReadAtLen;
 use iroh::client::Iroh;
 use iroh::net::NodeAddr;
-use iroh_manager::{get_blob_hash_and_size, IrohManager};
+use iroh_manager::{get_blob_hash_and_size, stat_blob, IrohManager};
 use libipld::store::StoreParams;
 use libipld::Cid;
 use libp2p::connection_limits::ConnectionLimits;
 use libp2p::futures::StreamExt;
 use libp2p::swarm::SwarmEvent;
+use libp2p::{autonat, dcutr, identify, ping, relay, request_response};
 use libp2p::{
     core::{muxing::StreamMuxerBox, transport::Boxed},
     identity::Keypair,
+    multiaddr::Protocol,
     noise, yamux, Multiaddr, PeerId, Swarm, Transport,
 };
-use libp2p::{identify, ping};
 use libp2p_bitswap::{BitswapResponse, BitswapStore};
 use libp2p_mplex::MplexConfig;
 use log::{debug, error, info, warn};
@@ -42,6 +51,16 @@ use tokio::select;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot::Sender;
+use tokio::time::Interval;
+
+/// Interval at which peer scores recover and temporary bans expire.
+const REPUTATION_INTERVAL: Duration = Duration::from_secs(60);
+/// Interval at which the per-subnet resolution statistics are logged.
+const STATS_INTERVAL: Duration = Duration::from_secs(300);
+/// Maximum number of blobs for which we remember where others announced to have resolved them.
+const MAX_ANNOUNCED_BLOBS: usize = 1024;
+/// Peers whose average latencies fall into the same bucket are considered equally fast.
+const LATENCY_BUCKET: Duration = Duration::from_millis(100);
 
 /// Result of attempting to resolve a CID.
 pub type ResolveResult = anyhow::Result<()>;
@@ -55,11 +74,31 @@ type ResponseChannel = Sender<ResolveResult>;
 /// Channel to complete the read request with.
 type ReadRequestResponseChannel = Sender<ResolveReadRequestResult>;
 
+/// What we know about a provider of a subnet, to help debug why content can't be resolved.
+#[derive(Clone, Debug)]
+pub struct ProviderInfo {
+    pub peer_id: PeerId,
+    /// Addresses we could dial the peer on; empty if we couldn't connect to it.
+    pub addresses: Vec<Multiaddr>,
+    /// Timestamp of the latest membership record received from the peer.
+    ///
+    /// Providers are forgotten when this becomes older than the maximum provider age.
+    pub timestamp: Timestamp,
+    /// Whether we currently have a connection to the peer.
+    pub is_connected: bool,
+    /// Whether the peer is banned, which excludes it from resolution.
+    pub is_banned: bool,
+    /// Reputation score of the peer; negative scores are tried last.
+    pub score: i32,
+}
+
 /// State of a query. The fallback peers can be used
 /// if the current attempt fails.
 struct Query {
     cid: Cid,
     subnet_id: SubnetID,
+    /// Peers the current attempt was sent to.
+    peer_ids: Vec<PeerId>,
     fallback_peer_ids: Vec<PeerId>,
     response_channel: ResponseChannel,
 }
@@ -67,6 +106,74 @@ struct Query {
 /// Keeps track of where to send query responses to.
 type QueryMap = HashMap<content::QueryId, Query>;
 
+/// Identifies a resolution racing multiple peers.
+type RaceId = u64;
+
+/// Identifies a blob resolution from the peers of a subnet.
+type BlobQueryId = u64;
+
+/// State of a blob resolution from the peers of a subnet, while we are asking
+/// them whether they have the blob.
+struct BlobQuery {
+    hash: Hash,
+    size: u64,
+    subnet_id: SubnetID,
+    /// Number of peers yet to answer.
+    pending: usize,
+    /// Iroh nodes which have the blob.
+    node_addrs: Vec<NodeAddr>,
+    response_channel: ResponseChannel,
+}
+
+/// A query resolved from multiple peers in parallel, each in its own Bitswap query.
+/// The first one to complete wins; Bitswap checks every block against its CID.
+struct Race {
+    query: Query,
+    /// The Bitswap queries still running, with the peer each was sent to.
+    running: HashMap<content::QueryId, PeerId>,
+    started: Instant,
+}
+
+/// The service is draining before it stops.
+struct Shutdown {
+    /// Time after which we stop waiting for running resolutions.
+    deadline: tokio::time::Instant,
+    /// Notified once the service stopped.
+    done: Vec<Sender<()>>,
+}
+
+/// A resolution waiting for its turn in the [`ResolveQueue`].
+enum Resolution {
+    Cid(Cid, SubnetID, ResponseChannel),
+    Iroh(Hash, u64, NodeAddr, ResponseChannel),
+    IrohFromSubnet(Hash, u64, SubnetID, ResponseChannel),
+    IrohRead(Hash, u32, u32, ReadRequestResponseChannel),
+}
+
+impl Resolution {
+    /// CIDs are resolved for checkpointing, which the consensus depends on,
+    /// while blobs can wait without holding up the chain.
+    fn priority(&self) -> Priority {
+        match self {
+            Resolution::Cid(..) => Priority::Critical,
+            Resolution::IrohRead(..) => Priority::Normal,
+            Resolution::Iroh(..) | Resolution::IrohFromSubnet(..) => Priority::Prefetch,
+        }
+    }
+
+    /// Complete the resolution with an error without starting it.
+    fn fail(self, err: anyhow::Error) {
+        match self {
+            Resolution::Cid(_, _, tx)
+            | Resolution::Iroh(_, _, _, tx)
+            | Resolution::IrohFromSubnet(_, _, _, tx) => send_resolve_result(tx, Err(err)),
+            Resolution::IrohRead(_, _, _, tx) => {
+                let _ = tx.send(Err(err));
+            }
+        }
+    }
+}
+
 /// Error returned when we tried to get a CID from a subnet for
 /// which we currently have no peers to contact
 #[derive(thiserror::Error, Debug)]
@@ -85,9 +192,37 @@ pub struct ConnectionConfig {
     pub expected_peer_count: u32,
     /// Maximum number of peers to send Bitswap requests to in a single attempt.
     pub max_peers_per_query: u32,
+    /// Number of peers to resolve a CID from in parallel, each in a separate query,
+    /// cancelling the rest when the first one completes. The time it takes peers to
+    /// respond is used to prefer the fastest ones later.
+    ///
+    /// With 1, a single query is sent to up to `max_peers_per_query` peers at a time.
+    pub parallel_queries: u32,
     /// Maximum number of events in the push-based broadcast channel before a slow
     /// consumer gets an error because it's falling behind.
     pub event_buffer_capacity: u32,
+    /// Option to add QUIC to the transports, which sets up connections faster than TCP
+    /// and doesn't suffer from head-of-line blocking between streams.
+    ///
+    /// When enabled, `listen_addr` can be a QUIC address as well, e.g. `/ip4/0.0.0.0/udp/0/quic-v1`.
+    pub enable_quic: bool,
+    /// QUIC address to listen on in addition to `listen_addr`, e.g. when that is a TCP address.
+    /// Ignored unless QUIC is enabled.
+    pub quic_listen_addr: Option<Multiaddr>,
+    /// Option to keep TCP next to QUIC, to be able to connect to peers which don't support QUIC.
+    /// TCP is always used when QUIC is disabled.
+    pub tcp_fallback: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueueConfig {
+    /// Maximum number of resolutions waiting to be started. When the queue is full,
+    /// resolutions with a lower priority are dropped to make room for higher ones.
+    pub capacity: usize,
+    /// Maximum number of resolutions running at the same time.
+    pub max_concurrent: usize,
+    /// Maximum time a resolution can wait in the queue before it fails.
+    pub max_wait: Duration,
 }
 
 #[derive(Debug, Clone)]
@@ -97,6 +232,9 @@ pub struct Config {
     pub membership: MembershipConfig,
     pub connection: ConnectionConfig,
     pub content: ContentConfig,
+    pub nat: NatConfig,
+    pub reputation: ReputationConfig,
+    pub queue: QueueConfig,
     pub iroh_addr: Option<String>,
 }
 
@@ -112,8 +250,26 @@ pub(crate) enum Request<V> {
     Resolve(Cid, SubnetID, ResponseChannel),
     ResolveIroh(Hash, u64, NodeAddr, ResponseChannel),
     ResolveIrohRead(Hash, u32, u32, ReadRequestResponseChannel),
+    ResolveIrohFromSubnet(Hash, u64, SubnetID, ResponseChannel),
+    BlobResponse(
+        request_response::ResponseChannel<blob::BlobResponse>,
+        blob::BlobResponse,
+    ),
     RateLimitUsed(PeerId, usize),
     UpdateRateLimit(u32),
+    BanPeer(PeerId),
+    UnbanPeer(PeerId),
+    ListProviders(SubnetID, Sender<Vec<ProviderInfo>>),
+    ListBandwidth(Sender<Vec<(PeerId, PeerBandwidth)>>),
+    PublishBlobAnnouncement(Box<SignedBlobAnnouncement>),
+    LocateIroh(Hash, Sender<anyhow::Result<Option<BlobLocation>>>),
+    AddStaticAddress(PeerId, Multiaddr),
+    RemoveStaticAddresses(PeerId),
+    SetTargetConnections(usize),
+    SetLookupsPaused(bool),
+    ResolutionDone(Resolved),
+    ProviderRecordValidated(Box<PendingProviderRecord>, bool),
+    Shutdown(Duration, Sender<()>),
 }
 
 /// Events that arise from the subnets, pushed to the clients,
@@ -122,6 +278,8 @@ pub(crate) enum Request<V> {
 pub enum Event<V> {
     /// Received a vote about in a subnet about a CID.
     ReceivedVote(Box<VoteRecord<V>>),
+    /// Received the outcome of a validator resolving a blob in a subnet.
+    ReceivedBlobAnnouncement(Box<VoteRecord<BlobAnnouncement>>),
     /// Received raw pre-emptive data published to a pinned subnet.
     ReceivedPreemptive(SubnetID, Vec<u8>),
 }
@@ -134,9 +292,25 @@ where
 {
     peer_id: PeerId,
     listen_addr: Multiaddr,
+    /// Additional QUIC address to listen on.
+    quic_listen_addr: Option<Multiaddr>,
+    /// Relays to listen on for connections relayed to us.
+    relay_addresses: Vec<Multiaddr>,
     swarm: Swarm<Behaviour<P, V>>,
     /// To match finished queries to response channels.
     queries: QueryMap,
+    /// To match finished queries to the races they take part in.
+    race_ids: HashMap<content::QueryId, RaceId>,
+    races: HashMap<RaceId, Race>,
+    next_race_id: RaceId,
+    /// To match blob lookup responses to the blob resolutions they are part of.
+    blob_request_ids: HashMap<request_response::OutboundRequestId, BlobQueryId>,
+    blob_queries: HashMap<BlobQueryId, BlobQuery>,
+    next_blob_query_id: BlobQueryId,
+    /// Where other validators announced to have resolved blobs, so we can download
+    /// from them without asking around; the oldest entries are dropped first.
+    announced_blobs: HashMap<Hash, Vec<BlobLocation>>,
+    announced_order: VecDeque<Hash>,
     /// For receiving requests from the clients and self.
     request_rx: mpsc::UnboundedReceiver<Request<V>>,
     /// For creating new clients and sending messages to self.
@@ -147,8 +321,30 @@ where
     background_lookup_filter: BloomFilter,
     /// To limit the number of peers contacted in a Bitswap resolution attempt.
     max_peers_per_query: usize,
+    /// Number of peers to race when resolving a CID.
+    parallel_queries: usize,
+    /// Average time it took peers to win races, to try the fastest ones first.
+    latencies: HashMap<PeerId, Duration>,
+    /// Resolutions waiting to be started, so bursts of blobs can't starve checkpointing.
+    resolve_queue: ResolveQueue<Resolution>,
+    /// Number of resolutions started from the queue which haven't completed yet.
+    running_resolutions: usize,
+    max_concurrent_resolutions: usize,
+    max_queue_wait: Duration,
+    /// Scores of peers, to prefer well-behaved providers and ban abusive ones.
+    reputation: Reputation,
+    /// Interval at which scores recover and temporary bans expire.
+    reputation_interval: Interval,
+    /// Outcome of resolutions by subnet since the last summary.
+    stats: ResolveStats,
+    /// Interval at which the statistics are logged.
+    stats_interval: Interval,
     /// Iroh client
     iroh: IrohManager,
+    /// Validator sets to check the signers of provider records against, if enabled.
+    subnet_validators: Option<Arc<dyn SubnetValidators>>,
+    /// Set when a shutdown was requested; no new resolutions are accepted after that.
+    shutdown: Option<Shutdown>,
 }
 
 impl<P, V> Service<P, V>
@@ -161,7 +357,16 @@ where
     where
         S: BitswapStore<Params = P>,
     {
-        Self::new_with_transport(config, store, build_transport).await
+        let enable_quic = config.connection.enable_quic;
+        let tcp_fallback = config.connection.tcp_fallback;
+        Self::new_with_transport(config, store, move |local_key| {
+            if enable_quic {
+                build_quic_transport(local_key, tcp_fallback)
+            } else {
+                build_transport(local_key)
+            }
+        })
+        .await
     }
 
     /// Build a [`Service`] and a [`Client`] by passing in a transport factory function.
@@ -179,6 +384,19 @@ where
         let peer_id = config.network.local_peer_id();
         let transport = transport(config.network.local_key.clone());
 
+        // Relayed connections go through a separate transport, which dials the relay over the base one.
+        let (transport, relay_client) = if config.nat.relay_addresses.is_empty() {
+            (transport, None)
+        } else {
+            let (relay_transport, relay_client) = relay::client::new(peer_id);
+            let transport = build_relay_transport(relay_transport, &config.network.local_key)
+                .or_transport(transport)
+                .map(|either, _| either.into_inner())
+                .boxed();
+            (transport, Some(relay_client))
+        };
+        let relay_addresses = config.nat.relay_addresses.clone();
+
         // NOTE: Hardcoded values from Forest. Will leave them as is until we know we need to change.
         let limits = ConnectionLimits::default()
             .with_max_pending_incoming(Some(10))
@@ -192,8 +410,10 @@ where
             config.discovery,
             config.membership,
             config.content,
+            config.nat,
             limits,
             store,
+            relay_client,
         )?;
 
         let swarm_config = libp2p::swarm::Config::with_tokio_executor()
@@ -206,14 +426,33 @@ where
             swarm.add_external_address(addr)
         }
 
+        let reputation = Reputation::new(config.reputation);
+        for peer_id in reputation.denied() {
+            emit(observe::ReputationEvent::Banned(*peer_id));
+            swarm.behaviour_mut().block_list_mut().block_peer(*peer_id);
+        }
+
         let (request_tx, request_rx) = mpsc::unbounded_channel();
         let (event_tx, _) = broadcast::channel(config.connection.event_buffer_capacity as usize);
 
         let service = Self {
             peer_id,
             listen_addr: config.connection.listen_addr,
+            quic_listen_addr: config
+                .connection
+                .quic_listen_addr
+                .filter(|_| config.connection.enable_quic),
+            relay_addresses,
             swarm,
             queries: Default::default(),
+            race_ids: Default::default(),
+            races: Default::default(),
+            next_race_id: 0,
+            blob_request_ids: Default::default(),
+            blob_queries: Default::default(),
+            next_blob_query_id: 0,
+            announced_blobs: Default::default(),
+            announced_order: Default::default(),
             request_rx,
             request_tx,
             event_tx,
@@ -222,12 +461,34 @@ where
                 config.connection.expected_peer_count,
             ),
             max_peers_per_query: config.connection.max_peers_per_query as usize,
+            parallel_queries: config.connection.parallel_queries as usize,
+            resolve_queue: ResolveQueue::new(config.queue.capacity),
+            running_resolutions: 0,
+            max_concurrent_resolutions: config.queue.max_concurrent,
+            max_queue_wait: config.queue.max_wait,
+            latencies: Default::default(),
+            reputation,
+            reputation_interval: tokio::time::interval(REPUTATION_INTERVAL),
+            stats: Default::default(),
+            stats_interval: tokio::time::interval(STATS_INTERVAL),
             iroh: IrohManager::from_addr(config.iroh_addr),
+            subnet_validators: None,
+            shutdown: None,
         };
 
         Ok(service)
     }
 
+    /// Only accept provider records signed by a validator of the subnets they claim to provide,
+    /// and penalize the peers gossiping the rest.
+    ///
+    /// The records are signed with the network key, so this only works if validators use
+    /// their validator key as their network key as well.
+    pub fn set_subnet_validators(&mut self, subnet_validators: Arc<dyn SubnetValidators>) {
+        self.subnet_validators = Some(subnet_validators);
+        self.membership_mut().set_validate_provider_records(true);
+    }
+
     /// Create a new [`Client`] instance bound to this `Service`.
     ///
     /// The [`Client`] is geared towards request-response interactions,
@@ -282,14 +543,45 @@ where
         info!("running service on {}", self.listen_addr);
         Swarm::listen_on(&mut self.swarm, self.listen_addr.clone())?;
 
+        if let Some(addr) = self.quic_listen_addr.clone() {
+            info!("listening with QUIC on {addr}");
+            Swarm::listen_on(&mut self.swarm, addr)?;
+        }
+
+        // Make reservations with the relays, so others can reach us through them.
+        for addr in self.relay_addresses.iter() {
+            info!("listening through relay {addr}");
+            Swarm::listen_on(&mut self.swarm, addr.clone().with(Protocol::P2pCircuit))?;
+        }
+
         loop {
+            if self.is_drained() {
+                info!("resolutions drained; stopping service");
+                break;
+            }
+            // Only polled while shutting down.
+            let shutdown_deadline = self
+                .shutdown
+                .as_ref()
+                .map(|s| s.deadline)
+                .unwrap_or_else(tokio::time::Instant::now);
+
             select! {
                 swarm_event = self.swarm.next() => match swarm_event {
                     // Events raised by our behaviours.
                     Some(SwarmEvent::Behaviour(event)) => {
                         self.handle_behaviour_event(event)
                     },
-                    // Connection events are handled by the behaviours, passed directly from the Swarm.
+                    // Connection events are handled by the behaviours, passed directly from the Swarm,
+                    // we only record which transport they use.
+                    Some(SwarmEvent::ConnectionEstablished { endpoint, established_in, .. }) => {
+                        let transport = transport_label(endpoint.get_remote_address());
+                        emit(observe::ConnectionEvent::Established(transport, established_in))
+                    },
+                    Some(SwarmEvent::ConnectionClosed { endpoint, .. }) => {
+                        let transport = transport_label(endpoint.get_remote_address());
+                        emit(observe::ConnectionEvent::Closed(transport))
+                    },
                     Some(_) => { },
                     // The connection is closed.
                     None => { break; },
@@ -300,12 +592,65 @@ where
                     // This shouldn't happen because the service has a copy of the sender.
                     // All Client instances have been dropped.
                     None => { break; }
+                },
+                _ = self.stats_interval.tick() => {
+                    self.stats.log_summary(STATS_INTERVAL)
+                },
+                _ = tokio::time::sleep_until(shutdown_deadline), if self.shutdown.is_some() => {
+                    warn!(
+                        "stopping service with {} resolutions still running",
+                        self.running_resolutions
+                    );
+                    break;
+                },
+                _ = self.reputation_interval.tick() => {
+                    self.tick_reputation()
                 }
             }
         }
+
+        // Save what we learned about peers, then close all connections.
+        self.discovery_mut().flush_peer_store();
+        self.stats.log_summary(STATS_INTERVAL);
+        let shutdown = self.shutdown.take();
+        drop(self.swarm);
+
+        for done in shutdown.into_iter().flat_map(|s| s.done) {
+            let _ = done.send(());
+        }
         Ok(())
     }
 
+    /// Check whether a requested shutdown can go ahead, because no resolutions are running.
+    fn is_drained(&self) -> bool {
+        self.shutdown.is_some() && self.running_resolutions == 0
+    }
+
+    /// Stop accepting resolutions and fail the queued ones, so that the service can stop
+    /// once the running ones are done, or the timeout has passed.
+    fn start_shutdown(&mut self, timeout: Duration, done: Sender<()>) {
+        if let Some(shutdown) = self.shutdown.as_mut() {
+            shutdown.done.push(done);
+            return;
+        }
+        info!(
+            "shutting down service; waiting up to {timeout:?} for {} running resolutions",
+            self.running_resolutions
+        );
+        while let Some(queued) = self.resolve_queue.pop() {
+            emit(observe::QueueEvent::Dropped(
+                queued.priority.label(),
+                "shutdown",
+            ));
+            queued.item.fail(anyhow!("the resolver is shutting down"));
+        }
+        emit(observe::QueueEvent::Depth(0));
+        self.shutdown = Some(Shutdown {
+            deadline: tokio::time::Instant::now() + timeout,
+            done: vec![done],
+        });
+    }
+
     /// Handle events that the [`NetworkBehaviour`] macro generated for our [`Behaviour`], one for each field.
     fn handle_behaviour_event(&mut self, event: BehaviourEvent<P, V>) {
         match event {
@@ -314,7 +659,13 @@ where
             BehaviourEvent::Discovery(e) => self.handle_discovery_event(e),
             BehaviourEvent::Membership(e) => self.handle_membership_event(e),
             BehaviourEvent::Content(e) => self.handle_content_event(e),
+            BehaviourEvent::Blob(e) => self.handle_blob_event(e),
             BehaviourEvent::ConnectionLimits(_) => {}
+            BehaviourEvent::BlockList(e) => match e {},
+            BehaviourEvent::Autonat(e) => self.handle_autonat_event(e),
+            BehaviourEvent::RelayServer(e) => self.handle_relay_server_event(e),
+            BehaviourEvent::RelayClient(e) => self.handle_relay_client_event(e),
+            BehaviourEvent::Dcutr(e) => self.handle_dcutr_event(e),
         }
     }
 
@@ -351,6 +702,65 @@ where
         }
     }
 
+    fn handle_autonat_event(&mut self, event: autonat::Event) {
+        if let autonat::Event::StatusChanged { old, new } = event {
+            info!(
+                "NAT status of {} changed from {old:?} to {new:?}",
+                self.peer_id
+            );
+            emit(observe::NatEvent::StatusChanged(matches!(
+                new,
+                autonat::NatStatus::Public(_)
+            )));
+        }
+    }
+
+    fn handle_relay_server_event(&mut self, event: relay::Event) {
+        match event {
+            relay::Event::ReservationReqAccepted { src_peer_id, .. } => {
+                debug!("accepted relay reservation from {src_peer_id}");
+                emit(observe::NatEvent::RelayReservationServed(src_peer_id));
+            }
+            relay::Event::CircuitReqAccepted {
+                src_peer_id,
+                dst_peer_id,
+            } => {
+                debug!("relaying connection from {src_peer_id} to {dst_peer_id}");
+            }
+            other => {
+                debug!("relay server event: {other:?}");
+            }
+        }
+    }
+
+    fn handle_relay_client_event(&mut self, event: relay::client::Event) {
+        match event {
+            relay::client::Event::ReservationReqAccepted { relay_peer_id, .. } => {
+                info!("relay {relay_peer_id} accepted our reservation");
+                emit(observe::NatEvent::RelayReservationAccepted(relay_peer_id));
+            }
+            other => {
+                debug!("relay client event: {other:?}");
+            }
+        }
+    }
+
+    fn handle_dcutr_event(&mut self, event: dcutr::Event) {
+        match event.result {
+            Ok(_) => {
+                debug!(
+                    "upgraded connection to {} with hole punching",
+                    event.remote_peer_id
+                );
+                emit(observe::NatEvent::HolePunchSuccess(event.remote_peer_id));
+            }
+            Err(error) => emit(observe::NatFailureEvent::HolePunchFailure(
+                event.remote_peer_id,
+                error.to_string(),
+            )),
+        }
+    }
+
     fn handle_discovery_event(&mut self, event: discovery::Event) {
         match event {
             discovery::Event::Added(peer_id) => {
@@ -361,6 +771,25 @@ where
                 debug!("removing unroutable peer {peer_id} from {}", self.peer_id);
                 self.membership_mut().set_unroutable(peer_id)
             }
+            discovery::Event::ProvidersFound(subnet_id, peer_ids) => {
+                debug!(
+                    "found {} providers of {subnet_id} in the DHT",
+                    peer_ids.len()
+                );
+                // Connecting to them lets us exchange membership records over gossip.
+                for peer_id in peer_ids {
+                    if self.swarm.is_connected(&peer_id) || self.reputation.is_banned(&peer_id) {
+                        continue;
+                    }
+                    if self.discovery_mut().addresses_of_peer(peer_id).is_empty() {
+                        if self.background_lookup_filter.insert(&peer_id) {
+                            self.discovery_mut().background_lookup(peer_id)
+                        }
+                    } else if let Err(e) = self.swarm.dial(peer_id) {
+                        debug!("failed to dial provider {peer_id} of {subnet_id}: {e}");
+                    }
+                }
+            }
         }
     }
 
@@ -379,9 +808,13 @@ where
             }
             membership::Event::Updated(p, delta) => {
                 debug!("peer updated: {} with {:?}", p, delta.added);
+                let subnet_ids = self.membership_mut().subnets_of_peer(&p);
+                self.content_mut().set_peer_subnets(p, subnet_ids);
             }
             membership::Event::Removed(p) => {
                 debug!("removed peer {}", p);
+                self.content_mut().set_peer_subnets(p, Vec::new());
+                self.latencies.remove(&p);
             }
             membership::Event::ReceivedVote(vote) => {
                 let event = Event::ReceivedVote(vote);
@@ -389,21 +822,65 @@ where
                     debug!("dropped received vote because there are no subscribers")
                 }
             }
+            membership::Event::ReceivedBlobAnnouncement(announcement) => {
+                if let Some(location) = &announcement.content.location {
+                    self.add_announced_blob(announcement.content.hash, location.clone());
+                }
+                let event = Event::ReceivedBlobAnnouncement(announcement);
+                if self.event_tx.send(event).is_err() {
+                    debug!("dropped received blob announcement because there are no subscribers")
+                }
+            }
             membership::Event::ReceivedPreemptive(subnet_id, data) => {
                 let event = Event::ReceivedPreemptive(subnet_id, data);
                 if self.event_tx.send(event).is_err() {
                     debug!("dropped received preemptive data because there are no subscribers")
                 }
             }
+            membership::Event::Offence(peer_id, offence) => self.penalize(peer_id, offence),
+            membership::Event::ValidateProvider(pending) => self.validate_provider(pending),
         }
     }
 
+    /// Check in the background whether the signer of a provider record is a validator
+    /// of all the subnets in the record, then hand it back to the membership behaviour.
+    fn validate_provider(&mut self, pending: Box<PendingProviderRecord>) {
+        let Some(subnet_validators) = self.subnet_validators.clone() else {
+            self.membership_mut()
+                .complete_provider_validation(*pending, true);
+            return;
+        };
+        let request_tx = self.request_tx.clone();
+        tokio::task::spawn(async move {
+            let mut valid = true;
+            for subnet_id in pending.record.subnet_ids.iter() {
+                match subnet_validators
+                    .is_validator(subnet_id, &pending.signing_key)
+                    .await
+                {
+                    Ok(Some(false)) => {
+                        valid = false;
+                        break;
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        // Don't punish peers for our own failure to look up the validators.
+                        warn!("failed to look up validators of subnet {subnet_id}: {e}");
+                    }
+                }
+            }
+            let _ = request_tx.send(Request::ProviderRecordValidated(pending, valid));
+        });
+    }
+
     /// Handle Bitswap lookup result.
     fn handle_content_event(&mut self, event: content::Event) {
         match event {
             content::Event::Complete(query_id, result) => {
                 if let Some(query) = self.queries.remove(&query_id) {
                     self.resolve_query(query, result);
+                } else if let Some(race_id) = self.race_ids.remove(&query_id) {
+                    self.resolve_race(race_id, query_id, result);
                 } else {
                     warn!("query ID not found");
                 }
@@ -431,16 +908,22 @@ where
     fn handle_request(&mut self, request: Request<V>) {
         match request {
             Request::SetProvidedSubnets(ids) => {
+                self.discovery_mut().set_provided_subnets(ids.clone());
                 if let Err(e) = self.membership_mut().set_provided_subnets(ids) {
                     warn!("failed to publish set provided subnets: {e}")
                 }
             }
             Request::AddProvidedSubnet(id) => {
+                self.discovery_mut().add_provided_subnet(id.clone());
+                // Look for the other providers, in case gossip hasn't told us about them yet.
+                let num_known = self.membership_mut().providers_of_subnet(&id).len();
+                self.discovery_mut().lookup_providers(&id, num_known);
                 if let Err(e) = self.membership_mut().add_provided_subnet(id) {
                     warn!("failed to publish added provided subnet: {e}")
                 }
             }
             Request::RemoveProvidedSubnet(id) => {
+                self.discovery_mut().remove_provided_subnet(&id);
                 if let Err(e) = self.membership_mut().remove_provided_subnet(id) {
                     warn!("failed to publish removed provided subnet: {e}")
                 }
@@ -450,6 +933,24 @@ where
                     warn!("failed to publish vote: {e}")
                 }
             }
+            Request::PublishBlobAnnouncement(announcement) => {
+                if let Err(e) = self
+                    .membership_mut()
+                    .publish_blob_announcement(*announcement)
+                {
+                    warn!("failed to publish blob announcement: {e}")
+                }
+            }
+            Request::LocateIroh(hash, response_channel) => {
+                let mut iroh = self.iroh.clone();
+                tokio::spawn(async move {
+                    let res = match iroh.client().await {
+                        Ok(client) => find_blob(client, hash).await,
+                        Err(e) => Err(anyhow!("failed to create iroh client: {e}")),
+                    };
+                    let _ = response_channel.send(res);
+                });
+            }
             Request::PublishPreemptive(subnet_id, data) => {
                 if let Err(e) = self.membership_mut().publish_preemptive(subnet_id, data) {
                     warn!("failed to publish pre-emptive data: {e}")
@@ -466,27 +967,224 @@ where
                 }
             }
             Request::Resolve(cid, subnet_id, response_channel) => {
-                self.start_query(cid, subnet_id, response_channel)
+                self.enqueue(Resolution::Cid(cid, subnet_id, response_channel))
             }
             Request::ResolveIroh(hash, size, node_addr, response_channel) => {
-                self.start_iroh_query(hash, size, node_addr, response_channel)
+                self.enqueue(Resolution::Iroh(hash, size, node_addr, response_channel))
             }
             Request::ResolveIrohRead(hash, offset, len, response_channel) => {
-                self.start_iroh_read_query(hash, offset, len, response_channel)
+                self.enqueue(Resolution::IrohRead(hash, offset, len, response_channel))
+            }
+            Request::ResolveIrohFromSubnet(hash, size, subnet_id, response_channel) => self
+                .enqueue(Resolution::IrohFromSubnet(
+                    hash,
+                    size,
+                    subnet_id,
+                    response_channel,
+                )),
+            Request::AddStaticAddress(peer_id, addr) => {
+                info!("adding static address {addr} of {peer_id}");
+                self.discovery_mut().add_static_address(peer_id, addr);
+                if !self.swarm.is_connected(&peer_id) {
+                    if let Err(e) = self.swarm.dial(peer_id) {
+                        debug!("failed to dial static peer {peer_id}: {e}");
+                    }
+                }
+            }
+            Request::RemoveStaticAddresses(peer_id) => {
+                info!("removing static addresses of {peer_id}");
+                self.discovery_mut().remove_static_addresses(&peer_id)
+            }
+            Request::SetTargetConnections(target_connections) => {
+                info!("setting target connections to {target_connections}");
+                self.discovery_mut()
+                    .set_target_connections(target_connections)
+            }
+            Request::SetLookupsPaused(paused) => {
+                info!("setting lookups paused to {paused}");
+                self.discovery_mut().set_lookups_paused(paused)
+            }
+            Request::ResolutionDone(resolved) => {
+                self.running_resolutions = self.running_resolutions.saturating_sub(1);
+                self.stats.record(&resolved);
+                emit(observe::ResolveEvent::Finished(resolved));
+                self.start_queued();
+            }
+            Request::Shutdown(timeout, done) => self.start_shutdown(timeout, done),
+            Request::ProviderRecordValidated(pending, valid) => self
+                .membership_mut()
+                .complete_provider_validation(*pending, valid),
+            Request::BlobResponse(channel, response) => {
+                if self.blob_mut().send_response(channel, response).is_err() {
+                    debug!("failed to send blob response; the connection is closed")
+                }
             }
             Request::RateLimitUsed(peer_id, bytes) => {
                 self.content_mut().rate_limit_used(peer_id, bytes)
             }
             Request::UpdateRateLimit(bytes) => self.content_mut().update_rate_limit(bytes),
+            Request::BanPeer(peer_id) => {
+                if self.reputation.deny(peer_id) {
+                    info!("banning peer {peer_id}");
+                    self.ban(peer_id)
+                }
+            }
+            Request::UnbanPeer(peer_id) => {
+                if self.reputation.allow(&peer_id) {
+                    info!("unbanning peer {peer_id}");
+                    self.unban(peer_id)
+                }
+            }
+            Request::ListProviders(subnet_id, response_channel) => {
+                let providers = self.list_providers(&subnet_id);
+                let _ = response_channel.send(providers);
+            }
+            Request::ListBandwidth(response_channel) => {
+                let bandwidth = self.content_mut().bandwidth_of_peers();
+                let _ = response_channel.send(bandwidth);
+            }
+        }
+    }
+
+    /// Queue a resolution, failing whichever one doesn't fit, then start as many as we can.
+    fn enqueue(&mut self, resolution: Resolution) {
+        if self.shutdown.is_some() {
+            resolution.fail(anyhow!("the resolver is shutting down"));
+            return;
+        }
+        let priority = resolution.priority();
+        let deadline = Instant::now() + self.max_queue_wait;
+        if let Some(dropped) = self.resolve_queue.push(resolution, priority, deadline) {
+            emit(observe::QueueEvent::Dropped(
+                dropped.priority().label(),
+                "full",
+            ));
+            dropped.fail(anyhow!("the resolve queue is full"));
+        }
+        self.start_queued();
+    }
+
+    /// Start queued resolutions, the highest priority first, while there are free slots.
+    fn start_queued(&mut self) {
+        let now = Instant::now();
+        for expired in self.resolve_queue.remove_expired(now) {
+            emit(observe::QueueEvent::Dropped(
+                expired.priority.label(),
+                "deadline",
+            ));
+            expired
+                .item
+                .fail(anyhow!("deadline exceeded waiting in the resolve queue"));
+        }
+        while self.running_resolutions < self.max_concurrent_resolutions {
+            let Some(queued) = self.resolve_queue.pop() else {
+                break;
+            };
+            emit(observe::QueueEvent::Started(
+                queued.priority.label(),
+                now.saturating_duration_since(queued.enqueued),
+            ));
+            self.running_resolutions += 1;
+            match queued.item {
+                Resolution::Cid(cid, subnet_id, tx) => {
+                    let tx =
+                        self.track_completion(tx, ResolveKind::Cid, Some(subnet_id.clone()), None);
+                    self.start_query(cid, subnet_id, tx)
+                }
+                Resolution::Iroh(hash, size, node_addr, tx) => {
+                    let tx = self.track_completion(tx, ResolveKind::Blob, None, Some(size));
+                    self.start_iroh_query(hash, size, node_addr, tx)
+                }
+                Resolution::IrohFromSubnet(hash, size, subnet_id, tx) => {
+                    let tx = self.track_completion(
+                        tx,
+                        ResolveKind::Blob,
+                        Some(subnet_id.clone()),
+                        Some(size),
+                    );
+                    self.start_blob_query(hash, size, subnet_id, tx)
+                }
+                Resolution::IrohRead(hash, offset, len, tx) => {
+                    let tx = self.track_completion(tx, ResolveKind::Read, None, None);
+                    self.start_iroh_read_query(hash, offset, len, tx)
+                }
+            }
         }
+        emit(observe::QueueEvent::Depth(self.resolve_queue.len()));
+    }
+
+    /// Wrap the response channel of a resolution, to free up its slot and record its outcome
+    /// when it completes. The size is the number of bytes a successful resolution transfers,
+    /// if known up front.
+    fn track_completion<T: Outcome + Send + 'static>(
+        &self,
+        response_channel: Sender<T>,
+        kind: ResolveKind,
+        subnet_id: Option<SubnetID>,
+        size: Option<u64>,
+    ) -> Sender<T> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let request_tx = self.request_tx.clone();
+        let started = Instant::now();
+        tokio::task::spawn(async move {
+            // Forward, if the resolution sent a result and the listener is still open.
+            // A resolution which didn't send a result failed without telling why.
+            let (success, bytes) = match rx.await {
+                Ok(res) => {
+                    let success = res.is_success();
+                    let bytes = res.bytes().or(size).filter(|_| success);
+                    let _ = response_channel.send(res);
+                    (success, bytes)
+                }
+                Err(_) => (false, None),
+            };
+            let resolved = Resolved {
+                kind,
+                subnet_id,
+                success,
+                duration: started.elapsed(),
+                bytes,
+            };
+            let _ = request_tx.send(Request::ResolutionDone(resolved));
+        });
+        tx
+    }
+
+    /// Collect what we know about the providers of a subnet, the most recently updated first.
+    fn list_providers(&mut self, subnet_id: &SubnetID) -> Vec<ProviderInfo> {
+        let mut providers = self
+            .membership_mut()
+            .providers_of_subnet(subnet_id)
+            .into_iter()
+            .map(|peer_id| ProviderInfo {
+                peer_id,
+                addresses: self.discovery_mut().addresses_of_peer(peer_id),
+                timestamp: self
+                    .membership_mut()
+                    .provider_timestamp(&peer_id)
+                    .unwrap_or_default(),
+                is_connected: self.swarm.is_connected(&peer_id),
+                is_banned: self.reputation.is_banned(&peer_id),
+                score: self.reputation.score(&peer_id),
+            })
+            .collect::<Vec<_>>();
+        providers.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        providers
     }
 
     /// Start a CID resolution.
     fn start_query(&mut self, cid: Cid, subnet_id: SubnetID, response_channel: ResponseChannel) {
         let mut peers = self.membership_mut().providers_of_subnet(&subnet_id);
 
+        // Banned peers would refuse the connection anyway.
+        peers.retain(|id| !self.reputation.is_banned(id));
+
         emit(observe::ResolveEvent::Peers(peers.len()));
 
+        // Find more providers for future queries if gossip told us about too few.
+        self.discovery_mut()
+            .lookup_providers(&subnet_id, peers.len());
+
         if peers.is_empty() {
             emit(observe::ResolveEvent::NoPeers);
             send_resolve_result(response_channel, Err(anyhow!(NoKnownPeers(subnet_id))));
@@ -494,20 +1192,50 @@ where
             // Connect to them in a random order, so as not to overwhelm any specific peer.
             peers.shuffle(&mut rand::thread_rng());
 
+            // Leave peers which misbehaved recently as a last resort.
+            let (good, poor) = peers
+                .into_iter()
+                .partition::<Vec<_>, _>(|id| self.reputation.score(id) >= 0);
+
             // Prioritize peers we already have an established connection with.
-            let (connected, known) = peers
+            let (mut connected, mut known) = good
                 .into_iter()
                 .partition::<Vec<_>, _>(|id| self.swarm.is_connected(id));
 
             emit(observe::ResolveEvent::ConnectedPeers(connected.len()));
 
-            let peers = [connected, known].into_iter().flatten().collect();
+            // Try the peers which were fast before first. Peers with similar or unknown latencies
+            // stay in random order, so that the fastest one doesn't receive every query.
+            let latency_bucket = |id: &PeerId| {
+                self.latencies
+                    .get(id)
+                    .map(|latency| latency.as_millis() / LATENCY_BUCKET.as_millis())
+                    .unwrap_or(u128::MAX)
+            };
+            connected.sort_by_key(latency_bucket);
+            known.sort_by_key(latency_bucket);
+
+            let peers = [connected, known, poor].into_iter().flatten().collect();
+
+            if self.parallel_queries > 1 {
+                let query = Query {
+                    cid,
+                    subnet_id,
+                    response_channel,
+                    peer_ids: Vec::new(),
+                    fallback_peer_ids: peers,
+                };
+                self.start_race(query);
+                return;
+            }
+
             let (peers, fallback) = self.split_peers_for_query(peers);
 
             let query = Query {
                 cid,
                 subnet_id,
                 response_channel,
+                peer_ids: peers.clone(),
                 fallback_peer_ids: fallback,
             };
 
@@ -517,6 +1245,82 @@ where
         }
     }
 
+    /// Send the query to the next batch of fallback peers, one Bitswap query each.
+    fn start_race(&mut self, mut query: Query) {
+        let size = std::cmp::min(self.parallel_queries, query.fallback_peer_ids.len());
+        let peers = query.fallback_peer_ids.drain(..size).collect::<Vec<_>>();
+
+        let race_id = self.next_race_id;
+        self.next_race_id += 1;
+
+        let mut running = HashMap::new();
+        for peer_id in peers.iter() {
+            let query_id = self.content_mut().resolve(query.cid, vec![*peer_id]);
+            running.insert(query_id, *peer_id);
+            self.race_ids.insert(query_id, race_id);
+        }
+        query.peer_ids = peers;
+
+        let race = Race {
+            query,
+            running,
+            started: Instant::now(),
+        };
+        self.races.insert(race_id, race);
+    }
+
+    /// Handle the result of one of the queries in a race. The first success wins and
+    /// cancels the rest; if all of them fail, race the next batch of fallback peers.
+    fn resolve_race(&mut self, race_id: RaceId, query_id: content::QueryId, result: ResolveResult) {
+        let Some(mut race) = self.races.remove(&race_id) else {
+            return;
+        };
+        let Some(peer_id) = race.running.remove(&query_id) else {
+            return;
+        };
+        match result {
+            Ok(_) => {
+                self.record_latency(peer_id, race.started.elapsed());
+                for (query_id, _) in race.running.drain() {
+                    self.race_ids.remove(&query_id);
+                    self.content_mut().cancel(query_id);
+                }
+                emit(observe::ResolveEvent::Success(race.query.cid));
+                send_resolve_result(race.query.response_channel, result)
+            }
+            Err(e) => {
+                self.penalize(peer_id, Offence::FailedResolution);
+                if !race.running.is_empty() {
+                    // Wait for the others.
+                    self.races.insert(race_id, race);
+                } else if race.query.fallback_peer_ids.is_empty() {
+                    emit(observe::ResolveFailureEvent::Failure(race.query.cid));
+                    send_resolve_result(race.query.response_channel, Err(e))
+                } else {
+                    emit(observe::ResolveFailureEvent::Fallback(race.query.cid));
+                    debug!(
+                        "resolving {} from {} failed with {}, but there are {} fallback peers to try",
+                        race.query.cid,
+                        race.query.subnet_id,
+                        e,
+                        race.query.fallback_peer_ids.len()
+                    );
+                    self.start_race(race.query);
+                }
+            }
+        }
+    }
+
+    /// Update the moving average of the time it takes a peer to resolve content.
+    fn record_latency(&mut self, peer_id: PeerId, latency: Duration) {
+        let average = self
+            .latencies
+            .entry(peer_id)
+            .and_modify(|average| *average = (*average * 3 + latency) / 4)
+            .or_insert(latency);
+        debug!("resolved content from {peer_id} in {latency:?}, on average {average:?}");
+    }
+
     /// Start a CID resolution using iroh.
     fn start_iroh_query(
         &mut self,
@@ -529,7 +1333,7 @@ where
         tokio::spawn(async move {
             match iroh.client().await {
                 Ok(client) => {
-                    let res = download_blob(client, hash, size, node_addr).await;
+                    let res = download_blob(client, hash, size, vec![node_addr]).await;
                     match res {
                         Ok(_) => send_resolve_result(response_channel, Ok(())),
                         Err(e) => send_resolve_result(response_channel, Err(anyhow!(e))),
@@ -543,6 +1347,221 @@ where
         });
     }
 
+    /// Start resolving a blob from the Iroh nodes of the peers providing a subnet,
+    /// by asking each of them whether they have it.
+    fn start_blob_query(
+        &mut self,
+        hash: Hash,
+        size: u64,
+        subnet_id: SubnetID,
+        response_channel: ResponseChannel,
+    ) {
+        // If others announced they resolved the blob, download from them without asking around.
+        // Take the locations, so that if the download fails, retries fall back to the lookup.
+        if let Some(locations) = self.take_announced_blob(&hash) {
+            let node_addrs = locations
+                .into_iter()
+                .filter(|location| location.size == size)
+                .map(|location| location.node_addr)
+                .collect::<Vec<_>>();
+
+            if !node_addrs.is_empty() {
+                debug!("downloading blob {hash} from nodes which announced it");
+                self.spawn_blob_download(hash, size, node_addrs, response_channel);
+                return;
+            }
+        }
+
+        let mut peers = self.membership_mut().providers_of_subnet(&subnet_id);
+        peers.retain(|id| !self.reputation.is_banned(id));
+
+        self.discovery_mut()
+            .lookup_providers(&subnet_id, peers.len());
+
+        if peers.is_empty() {
+            send_resolve_result(response_channel, Err(anyhow!(NoKnownPeers(subnet_id))));
+            return;
+        }
+
+        peers.shuffle(&mut rand::thread_rng());
+        let (peers, _) = self.split_peers_for_query(peers);
+
+        let query_id = self.next_blob_query_id;
+        self.next_blob_query_id += 1;
+
+        for peer_id in peers.iter() {
+            let request_id = self
+                .blob_mut()
+                .send_request(peer_id, blob::BlobRequest { hash });
+            self.blob_request_ids.insert(request_id, query_id);
+        }
+
+        let query = BlobQuery {
+            hash,
+            size,
+            subnet_id,
+            pending: peers.len(),
+            node_addrs: Vec::new(),
+            response_channel,
+        };
+        self.blob_queries.insert(query_id, query);
+    }
+
+    /// Handle the blob lookup protocol.
+    fn handle_blob_event(&mut self, event: blob::Event) {
+        match event {
+            request_response::Event::Message { peer, message } => match message {
+                request_response::Message::Request {
+                    request, channel, ..
+                } => self.serve_blob_request(peer, request.hash, channel),
+                request_response::Message::Response {
+                    request_id,
+                    response,
+                } => self.handle_blob_response(peer, request_id, response.location),
+            },
+            request_response::Event::OutboundFailure {
+                peer,
+                request_id,
+                error,
+            } => {
+                debug!("failed to ask {peer} about a blob: {error}");
+                self.handle_blob_response(peer, request_id, None)
+            }
+            request_response::Event::InboundFailure { peer, error, .. } => {
+                debug!("failed to answer {peer} about a blob: {error}");
+            }
+            request_response::Event::ResponseSent { .. } => {}
+        }
+    }
+
+    /// Check whether our Iroh node has the complete blob, and tell the peer where to download it from.
+    fn serve_blob_request(
+        &mut self,
+        peer_id: PeerId,
+        hash: Hash,
+        channel: request_response::ResponseChannel<blob::BlobResponse>,
+    ) {
+        let mut iroh = self.iroh.clone();
+        let request_tx = self.request_tx.clone();
+        tokio::spawn(async move {
+            let location = match iroh.client().await {
+                Ok(client) => find_blob(client, hash).await.unwrap_or_else(|e| {
+                    debug!("failed to look up blob {hash} for {peer_id}: {e}");
+                    None
+                }),
+                Err(e) => {
+                    debug!("cannot look up blob {hash}; failed to create iroh client ({e})");
+                    None
+                }
+            };
+            let response = blob::BlobResponse { location };
+            let _ = request_tx.send(Request::BlobResponse(channel, response));
+        });
+    }
+
+    /// Collect the answers of peers; when everyone has answered, download the blob
+    /// from the ones that have it.
+    fn handle_blob_response(
+        &mut self,
+        peer_id: PeerId,
+        request_id: request_response::OutboundRequestId,
+        location: Option<blob::BlobLocation>,
+    ) {
+        let Some(query_id) = self.blob_request_ids.remove(&request_id) else {
+            return;
+        };
+        let Some(mut query) = self.blob_queries.remove(&query_id) else {
+            return;
+        };
+
+        query.pending -= 1;
+
+        match location {
+            Some(location) if location.size == query.size => {
+                query.node_addrs.push(location.node_addr)
+            }
+            Some(location) => {
+                debug!(
+                    "peer {peer_id} has blob {} with size {}, expected {}",
+                    query.hash, location.size, query.size
+                );
+            }
+            None => {}
+        }
+
+        if query.pending > 0 {
+            self.blob_queries.insert(query_id, query);
+            return;
+        }
+
+        if query.node_addrs.is_empty() {
+            let err = anyhow!(
+                "none of the peers of {} have blob {}",
+                query.subnet_id,
+                query.hash
+            );
+            send_resolve_result(query.response_channel, Err(err));
+            return;
+        }
+
+        self.spawn_blob_download(
+            query.hash,
+            query.size,
+            query.node_addrs,
+            query.response_channel,
+        );
+    }
+
+    /// Download a blob from any of the given Iroh nodes in the background.
+    fn spawn_blob_download(
+        &mut self,
+        hash: Hash,
+        size: u64,
+        node_addrs: Vec<NodeAddr>,
+        response_channel: ResponseChannel,
+    ) {
+        let mut iroh = self.iroh.clone();
+        tokio::spawn(async move {
+            match iroh.client().await {
+                Ok(client) => {
+                    let res = download_blob(client, hash, size, node_addrs).await;
+                    send_resolve_result(response_channel, res)
+                }
+                Err(e) => warn!("cannot resolve {hash}; failed to create iroh client ({e})"),
+            }
+        });
+    }
+
+    /// Remember where a blob was announced to be available, forgetting the oldest blobs over capacity.
+    fn add_announced_blob(&mut self, hash: Hash, location: BlobLocation) {
+        match self.announced_blobs.get_mut(&hash) {
+            Some(locations) => {
+                if !locations
+                    .iter()
+                    .any(|l| l.node_addr.node_id == location.node_addr.node_id)
+                {
+                    locations.push(location);
+                }
+            }
+            None => {
+                self.announced_blobs.insert(hash, vec![location]);
+                self.announced_order.push_back(hash);
+                while self.announced_order.len() > MAX_ANNOUNCED_BLOBS {
+                    if let Some(oldest) = self.announced_order.pop_front() {
+                        self.announced_blobs.remove(&oldest);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Take the locations where a blob was announced to be available, if any.
+    fn take_announced_blob(&mut self, hash: &Hash) -> Option<Vec<BlobLocation>> {
+        let locations = self.announced_blobs.remove(hash)?;
+        self.announced_order.retain(|h| h != hash);
+        Some(locations)
+    }
+
     /// Start a read request resolution using iorh.
     fn start_iroh_read_query(
         &mut self,
@@ -575,6 +1594,11 @@ where
     /// we know support the subnet, but weren't connected to when the we
     /// first attempted the resolution.
     fn resolve_query(&mut self, mut query: Query, result: ResolveResult) {
+        if result.is_err() {
+            for peer_id in std::mem::take(&mut query.peer_ids) {
+                self.penalize(peer_id, Offence::FailedResolution);
+            }
+        }
         match result {
             Ok(_) => {
                 emit(observe::ResolveEvent::Success(query.cid));
@@ -597,9 +1621,10 @@ where
                 // Try to resolve from the next batch of peers.
                 let peers = std::mem::take(&mut query.fallback_peer_ids);
                 let (peers, fallback) = self.split_peers_for_query(peers);
-                let query_id = self.content_mut().resolve(query.cid, peers);
+                let query_id = self.content_mut().resolve(query.cid, peers.clone());
 
                 // Leave the rest for later.
+                query.peer_ids = peers;
                 query.fallback_peer_ids = fallback;
 
                 self.queries.insert(query_id, query);
@@ -607,6 +1632,40 @@ where
         }
     }
 
+    /// Lower the score of a peer, banning it if it crossed the threshold.
+    fn penalize(&mut self, peer_id: PeerId, offence: Offence) {
+        emit(observe::ReputationEvent::Penalized(peer_id, offence));
+        if self.reputation.penalize(peer_id, offence) {
+            warn!("banning peer {peer_id} for its low reputation");
+            self.ban(peer_id)
+        }
+    }
+
+    /// Let scores recover and lift the expired bans.
+    fn tick_reputation(&mut self) {
+        for peer_id in self.reputation.tick() {
+            debug!("ban of peer {peer_id} expired");
+            self.unban(peer_id)
+        }
+    }
+
+    /// Disconnect a peer and refuse further connections.
+    fn ban(&mut self, peer_id: PeerId) {
+        emit(observe::ReputationEvent::Banned(peer_id));
+        self.swarm
+            .behaviour_mut()
+            .block_list_mut()
+            .block_peer(peer_id);
+    }
+
+    fn unban(&mut self, peer_id: PeerId) {
+        emit(observe::ReputationEvent::Unbanned(peer_id));
+        self.swarm
+            .behaviour_mut()
+            .block_list_mut()
+            .unblock_peer(peer_id);
+    }
+
     /// Split peers into a group we query now and a group we fall back on if the current batch fails.
     fn split_peers_for_query(&self, mut peers: Vec<PeerId>) -> (Vec<PeerId>, Vec<PeerId>) {
         let size = std::cmp::min(self.max_peers_per_query, peers.len());
@@ -625,6 +1684,36 @@ where
     fn content_mut(&mut self) -> &mut content::Behaviour<P> {
         self.swarm.behaviour_mut().content_mut()
     }
+    fn blob_mut(&mut self) -> &mut blob::Behaviour {
+        self.swarm.behaviour_mut().blob_mut()
+    }
+}
+
+/// Result of a resolution, inspected to record its outcome.
+trait Outcome {
+    fn is_success(&self) -> bool;
+    /// Number of bytes transferred, if it can be told from the result.
+    fn bytes(&self) -> Option<u64>;
+}
+
+impl Outcome for ResolveResult {
+    fn is_success(&self) -> bool {
+        self.is_ok()
+    }
+
+    fn bytes(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl Outcome for ResolveReadRequestResult {
+    fn is_success(&self) -> bool {
+        self.is_ok()
+    }
+
+    fn bytes(&self) -> Option<u64> {
+        self.as_ref().ok().map(|bz| bz.len() as u64)
+    }
 }
 
 /// Respond to the sender of the query, if they are still listening.
@@ -670,11 +1759,67 @@ pub fn build_transport(local_key: Keypair) -> Boxed<(PeerId, StreamMuxerBox)> {
         .boxed()
 }
 
+/// Build a transport with QUIC, optionally falling back to TCP for addresses which aren't QUIC.
+///
+/// QUIC has its own encryption and multiplexing, so it doesn't need to be upgraded.
+pub fn build_quic_transport(
+    local_key: Keypair,
+    tcp_fallback: bool,
+) -> Boxed<(PeerId, StreamMuxerBox)> {
+    let quic_transport = libp2p::quic::tokio::Transport::new(libp2p::quic::Config::new(&local_key))
+        .map(|(peer_id, conn), _| (peer_id, StreamMuxerBox::new(conn)));
+
+    let transport = if tcp_fallback {
+        quic_transport
+            .or_transport(build_transport(local_key))
+            .map(|either, _| either.into_inner())
+            .boxed()
+    } else {
+        quic_transport.boxed()
+    };
+
+    libp2p::dns::tokio::Transport::system(transport)
+        .unwrap()
+        .boxed()
+}
+
+/// Name of the transport used to reach an address, to label metrics with.
+fn transport_label(addr: &Multiaddr) -> &'static str {
+    // Relayed addresses contain the address of the relay, so check them first.
+    if addr.iter().any(|p| matches!(p, Protocol::P2pCircuit)) {
+        return "relay";
+    }
+    for protocol in addr.iter() {
+        match protocol {
+            Protocol::Quic | Protocol::QuicV1 => return "quic",
+            Protocol::Tcp(_) => return "tcp",
+            Protocol::Memory(_) => return "memory",
+            _ => {}
+        }
+    }
+    "other"
+}
+
+/// Secure and multiplex connections relayed through the relay client transport, the same way as direct ones.
+fn build_relay_transport(
+    relay_transport: relay::client::Transport,
+    local_key: &Keypair,
+) -> Boxed<(PeerId, StreamMuxerBox)> {
+    let auth_config = noise::Config::new(local_key).expect("Noise key generation failed");
+    relay_transport
+        .upgrade(libp2p::core::upgrade::Version::V1)
+        .authenticate(auth_config)
+        .multiplex(yamux::Config::default())
+        .timeout(Duration::from_secs(20))
+        .boxed()
+}
+
+/// Download a blob from any of the Iroh nodes, which verify the content against the hash.
 async fn download_blob(
     iroh: Iroh,
     seq_hash: Hash,
     size: u64,
-    node_addr: NodeAddr,
+    nodes: Vec<NodeAddr>,
 ) -> anyhow::Result<()> {
     // Download top-level blob
     // Use an explicit tag so we can keep track of it
@@ -684,7 +1829,7 @@ async fn download_blob(
             seq_hash,
             iroh::client::blobs::DownloadOptions {
                 format: iroh::blobs::BlobFormat::HashSeq,
-                nodes: vec![node_addr.clone()],
+                nodes,
                 tag: iroh::blobs::util::SetTagOption::Named(tag),
                 mode: iroh::client::blobs::DownloadMode::Queued,
             },
@@ -711,6 +1856,15 @@ async fn download_blob(
     Ok(())
 }
 
+/// Return the address of our Iroh node and the size of the blob, if it's available in full.
+async fn find_blob(iroh: Iroh, seq_hash: Hash) -> anyhow::Result<Option<blob::BlobLocation>> {
+    let Some(size) = stat_blob(&iroh, seq_hash).await? else {
+        return Ok(None);
+    };
+    let node_addr = iroh.net().node_addr().await?;
+    Ok(Some(blob::BlobLocation { node_addr, size }))
+}
+
 async fn read_blob(iroh: Iroh, hash: Hash, offset: u32, len: u32) -> anyhow::Result<bytes::Bytes> {
     let (hash, _) = get_blob_hash_and_size(&iroh, hash).await?;
     let len = ReadAtLen::AtMost(len as u64);
@@ -721,3 +1875,22 @@ async fn read_blob(iroh: Iroh, hash: Hash, offset: u32, len: u32) -> anyhow::Res
     debug!("read blob {}: {:?}", hash, res);
     Ok(res)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::transport_label;
+
+    #[test]
+    fn test_transport_label() {
+        for (addr, label) in [
+            ("/ip4/127.0.0.1/tcp/26655", "tcp"),
+            ("/ip4/127.0.0.1/udp/26655/quic-v1", "quic"),
+            ("/dns4/example.com/udp/26655/quic", "quic"),
+            ("/ip4/127.0.0.1/tcp/26655/p2p/12D3KooWC1EaEEpghwnPdd89LaPTKEweD1PRLz4aRBkJEA9UiUuS/p2p-circuit", "relay"),
+            ("/memory/1234", "memory"),
+            ("/ip4/127.0.0.1", "other"),
+        ] {
+            assert_eq!(transport_label(&addr.parse().unwrap()), label, "{addr}");
+        }
+    }
+}
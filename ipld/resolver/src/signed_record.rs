@@ -25,6 +25,8 @@ pub struct SignedRecord<R> {
     record: R,
     /// The [`SignedEnvelope`] from which the record was deserialized from.
     envelope: SignedEnvelope,
+    /// The key which signed the envelope.
+    signing_key: PublicKey,
 }
 
 // Based on `libp2p_core::peer_record::PeerRecord`
@@ -42,7 +44,11 @@ where
             R::payload_type().as_bytes().to_vec(),
             payload,
         )?;
-        Ok(Self { record, envelope })
+        Ok(Self {
+            record,
+            envelope,
+            signing_key: key.public(),
+        })
     }
 
     pub fn from_signed_envelope(envelope: SignedEnvelope) -> Result<Self, FromEnvelopeError> {
@@ -55,7 +61,13 @@ where
             return Err(FromEnvelopeError::MismatchedSignature);
         }
 
-        Ok(Self { record, envelope })
+        let signing_key = signing_key.clone();
+
+        Ok(Self {
+            record,
+            envelope,
+            signing_key,
+        })
     }
 
     /// Deserialize then check the domain tags and the signature.
@@ -73,6 +85,10 @@ where
         &self.envelope
     }
 
+    pub fn signing_key(&self) -> &PublicKey {
+        &self.signing_key
+    }
+
     pub fn into_record(self) -> R {
         self.record
     }
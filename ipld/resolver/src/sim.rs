@@ -0,0 +1,425 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Harness to run a network of resolver services in memory, so that discovery,
+//! membership and content resolution can be tested without real sockets.
+//!
+//! Nodes talk over the libp2p [`MemoryTransport`], and the topology can be changed
+//! during the test by partitioning nodes (they ban each other), by stopping and
+//! restarting them (churn), and by giving nodes a slow link, which delays the setup
+//! of every connection they make or accept.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::IPLD_RAW;
+use ipc_api::subnet_id::SubnetID;
+use libipld::multihash::{Code, MultihashDigest};
+use libipld::Cid;
+use libp2p::core::muxing::StreamMuxerBox;
+use libp2p::core::transport::{Boxed, MemoryTransport};
+use libp2p::identity::Keypair;
+use libp2p::multiaddr::Protocol;
+use libp2p::{plaintext, yamux, Multiaddr, PeerId, Transport};
+use libp2p_bitswap::BitswapStore;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::missing_blocks::missing_blocks;
+use crate::{
+    Client, Config, ConnectionConfig, ContentConfig, DiscoveryConfig, Event, GatingMode,
+    MembershipConfig, NatConfig, NetworkConfig, QueueConfig, ReputationConfig, Resolver, Service,
+};
+
+/// Time nodes get to finish their resolutions when they are stopped.
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// In-memory blockstore shared between a node and the test.
+#[derive(Debug, Clone, Default)]
+pub struct SimBlockstore {
+    blocks: Arc<RwLock<HashMap<Cid, Vec<u8>>>>,
+}
+
+impl SimBlockstore {
+    /// Store some raw data and return its CID.
+    pub fn insert_raw(&self, data: &[u8]) -> anyhow::Result<Cid> {
+        let cid = Cid::new_v1(IPLD_RAW, Code::Sha2_256.digest(data));
+        self.put_keyed(&cid, data)?;
+        Ok(cid)
+    }
+}
+
+impl Blockstore for SimBlockstore {
+    fn has(&self, k: &Cid) -> anyhow::Result<bool> {
+        Ok(self.blocks.read().unwrap().contains_key(k))
+    }
+
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self.blocks.read().unwrap().get(k).cloned())
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.blocks.write().unwrap().insert(*k, block.into());
+        Ok(())
+    }
+}
+
+pub type SimStoreParams = libipld::DefaultParams;
+
+impl BitswapStore for SimBlockstore {
+    type Params = SimStoreParams;
+
+    fn contains(&mut self, cid: &Cid) -> anyhow::Result<bool> {
+        Blockstore::has(self, cid)
+    }
+
+    fn get(&mut self, cid: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        Blockstore::get(self, cid)
+    }
+
+    fn insert(&mut self, block: &libipld::Block<Self::Params>) -> anyhow::Result<()> {
+        Blockstore::put_keyed(self, block.cid(), block.data())
+    }
+
+    fn missing_blocks(&mut self, cid: &Cid) -> anyhow::Result<Vec<Cid>> {
+        missing_blocks::<Self, Self::Params>(self, cid)
+    }
+}
+
+/// How the nodes find each other when the simulation starts.
+#[derive(Debug, Clone, Copy)]
+pub enum Topology {
+    /// Every node bootstraps from the first one.
+    Star(usize),
+    /// Every node bootstraps from the one before it.
+    Chain(usize),
+}
+
+impl Topology {
+    fn size(&self) -> usize {
+        match self {
+            Topology::Star(size) | Topology::Chain(size) => *size,
+        }
+    }
+
+    fn bootstrap_of(&self, idx: usize) -> Option<usize> {
+        match self {
+            _ if idx == 0 => None,
+            Topology::Star(_) => Some(0),
+            Topology::Chain(_) => Some(idx - 1),
+        }
+    }
+}
+
+/// A resolver service in the simulation.
+pub struct SimNode<V> {
+    pub config: Config,
+    pub client: Client<V>,
+    pub events: broadcast::Receiver<Event<V>>,
+    pub store: SimBlockstore,
+    /// Delay added to the setup of every connection of the node.
+    link_delay: Duration,
+    handle: Option<JoinHandle<anyhow::Result<()>>>,
+}
+
+impl<V> SimNode<V> {
+    pub fn peer_id(&self) -> PeerId {
+        self.config.network.local_peer_id()
+    }
+
+    /// Address others can dial the node on.
+    pub fn addr(&self) -> Multiaddr {
+        let mut addr = self.config.connection.listen_addr.clone();
+        addr.push(Protocol::P2p(self.peer_id()));
+        addr
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.handle.as_ref().is_some_and(|h| !h.is_finished())
+    }
+}
+
+/// Outcome of resolving the same content on a number of nodes.
+#[derive(Debug, Clone, Default)]
+pub struct ResolutionReport {
+    pub attempts: usize,
+    pub successes: usize,
+    /// Why the failed attempts failed, by node index.
+    pub failures: Vec<(usize, String)>,
+}
+
+impl ResolutionReport {
+    pub fn success_rate(&self) -> f64 {
+        if self.attempts == 0 {
+            return 0.0;
+        }
+        self.successes as f64 / self.attempts as f64
+    }
+
+    /// Fail with the reasons of the failed attempts if the success rate is below the minimum.
+    pub fn ensure_success_rate(&self, min: f64) -> anyhow::Result<()> {
+        if self.success_rate() < min {
+            return Err(anyhow!(
+                "success rate {:.2} below {min:.2}; failures: {:?}",
+                self.success_rate(),
+                self.failures
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A network of resolver services running in memory.
+pub struct Simulation<V> {
+    nodes: Vec<SimNode<V>>,
+    network_name: String,
+}
+
+impl<V> Simulation<V>
+where
+    V: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    /// Start the nodes of a topology and give them some time to connect.
+    pub async fn start(topology: Topology) -> anyhow::Result<Self> {
+        // Each simulation gets its own network, so they can run in parallel.
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let mut sim = Self {
+            nodes: Vec::new(),
+            network_name: format!("sim-{}", COUNTER.fetch_add(1, Ordering::Relaxed)),
+        };
+        for idx in 0..topology.size() {
+            sim.add_node(topology.bootstrap_of(idx), Duration::ZERO)
+                .await?;
+        }
+        sim.settle(Duration::from_secs(1)).await;
+        Ok(sim)
+    }
+
+    pub fn nodes(&self) -> &[SimNode<V>] {
+        &self.nodes
+    }
+
+    pub fn node(&self, idx: usize) -> &SimNode<V> {
+        &self.nodes[idx]
+    }
+
+    pub fn node_mut(&mut self, idx: usize) -> &mut SimNode<V> {
+        &mut self.nodes[idx]
+    }
+
+    /// Start a new node, optionally bootstrapping from an existing one, with a delay added
+    /// to the setup of each of its connections. Returns its index.
+    pub async fn add_node(
+        &mut self,
+        bootstrap: Option<usize>,
+        link_delay: Duration,
+    ) -> anyhow::Result<usize> {
+        let bootstrap_addr = bootstrap.map(|idx| self.nodes[idx].addr());
+        let config = self.make_config(bootstrap_addr);
+        let store = SimBlockstore::default();
+        let (client, events, handle) = start_service(&config, &store, link_delay).await?;
+        self.nodes.push(SimNode {
+            config,
+            client,
+            events,
+            store,
+            link_delay,
+            handle: Some(handle),
+        });
+        Ok(self.nodes.len() - 1)
+    }
+
+    /// Stop a node gracefully, e.g. to simulate churn.
+    pub async fn stop_node(&mut self, idx: usize) -> anyhow::Result<()> {
+        let node = &mut self.nodes[idx];
+        node.client.shutdown(STOP_TIMEOUT).await?;
+        if let Some(handle) = node.handle.take() {
+            handle.await??;
+        }
+        Ok(())
+    }
+
+    /// Start a stopped node again, with the same identity, address and store.
+    pub async fn restart_node(&mut self, idx: usize) -> anyhow::Result<()> {
+        let node = &mut self.nodes[idx];
+        if node.is_running() {
+            return Err(anyhow!("node {idx} is still running"));
+        }
+        let (client, events, handle) =
+            start_service(&node.config, &node.store, node.link_delay).await?;
+        node.client = client;
+        node.events = events;
+        node.handle = Some(handle);
+        Ok(())
+    }
+
+    /// Split the network into two groups which refuse to talk to each other.
+    pub fn partition(&self, group_a: &[usize], group_b: &[usize]) -> anyhow::Result<()> {
+        self.for_each_link(group_a, group_b, |client, peer_id| client.ban_peer(peer_id))
+    }
+
+    /// Let two groups talk to each other again.
+    pub fn heal(&self, group_a: &[usize], group_b: &[usize]) -> anyhow::Result<()> {
+        self.for_each_link(group_a, group_b, |client, peer_id| {
+            client.unban_peer(peer_id)
+        })
+    }
+
+    /// Give the nodes time to exchange gossip and discover each other.
+    pub async fn settle(&self, duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+
+    /// Make each of the nodes resolve a CID from a subnet, recording how many succeeded.
+    pub async fn resolve_on(
+        &self,
+        resolvers: &[usize],
+        cid: Cid,
+        subnet_id: &SubnetID,
+        timeout: Duration,
+    ) -> ResolutionReport {
+        let mut report = ResolutionReport::default();
+        for idx in resolvers {
+            report.attempts += 1;
+            let node = &self.nodes[*idx];
+            let res = tokio::time::timeout(timeout, node.client.resolve(cid, subnet_id.clone()))
+                .await
+                .map_err(|_| anyhow!("timeout"))
+                .and_then(|res| res)
+                .and_then(|res| res)
+                .and_then(|()| {
+                    node.store
+                        .has(&cid)?
+                        .then_some(())
+                        .ok_or_else(|| anyhow!("not in the store"))
+                });
+            match res {
+                Ok(()) => report.successes += 1,
+                Err(e) => report.failures.push((*idx, e.to_string())),
+            }
+        }
+        report
+    }
+
+    fn for_each_link<F>(&self, group_a: &[usize], group_b: &[usize], f: F) -> anyhow::Result<()>
+    where
+        F: Fn(&Client<V>, PeerId) -> anyhow::Result<()>,
+    {
+        for a in group_a {
+            for b in group_b {
+                f(&self.nodes[*a].client, self.nodes[*b].peer_id())?;
+                f(&self.nodes[*b].client, self.nodes[*a].peer_id())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn make_config(&self, bootstrap_addr: Option<Multiaddr>) -> Config {
+        // Leave room for nodes added later.
+        let capacity = (self.nodes.len() as u32 + 1).max(10);
+        Config {
+            connection: ConnectionConfig {
+                listen_addr: Multiaddr::from(Protocol::Memory(rand::random::<u64>())),
+                external_addresses: vec![],
+                expected_peer_count: capacity,
+                max_incoming: capacity,
+                max_peers_per_query: capacity,
+                parallel_queries: 1,
+                event_buffer_capacity: capacity,
+                enable_quic: false,
+                quic_listen_addr: None,
+                tcp_fallback: true,
+            },
+            network: NetworkConfig {
+                local_key: Keypair::generate_secp256k1(),
+                network_name: self.network_name.clone(),
+            },
+            discovery: DiscoveryConfig {
+                static_addresses: bootstrap_addr.into_iter().collect(),
+                target_connections: capacity as usize,
+                enable_kademlia: true,
+                enable_mdns: false,
+                peer_store: None,
+                enable_provider_records: true,
+                provider_record_interval: Duration::from_secs(60),
+                min_providers: 1,
+                verify_addresses: false,
+            },
+            membership: MembershipConfig {
+                static_subnets: vec![],
+                max_subnets: 10,
+                publish_interval: Duration::from_secs(5),
+                min_time_between_publish: Duration::from_secs(1),
+                max_provider_age: Duration::from_secs(60),
+                gating: GatingMode::Disabled,
+                gating_grace_period: Duration::from_secs(120),
+            },
+            content: ContentConfig {
+                rate_limit_bytes: 1 << 20,
+                rate_limit_period: Duration::from_secs(60),
+                request_rate_limit_per_peer: 0,
+                request_rate_limit_per_subnet: 0,
+                request_rate_limit_period: Duration::ZERO,
+                peer_daily_quota_bytes: 0,
+            },
+            nat: NatConfig::default(),
+            reputation: ReputationConfig {
+                ban_threshold: -100,
+                ban_duration: Duration::from_secs(60),
+                denylist: vec![],
+            },
+            queue: QueueConfig {
+                capacity: 100,
+                max_concurrent: 10,
+                max_wait: Duration::from_secs(60),
+            },
+            iroh_addr: None,
+        }
+    }
+}
+
+/// Create a service and run it in the background.
+async fn start_service<V>(
+    config: &Config,
+    store: &SimBlockstore,
+    link_delay: Duration,
+) -> anyhow::Result<(
+    Client<V>,
+    broadcast::Receiver<Event<V>>,
+    JoinHandle<anyhow::Result<()>>,
+)>
+where
+    V: Serialize + DeserializeOwned + Clone + Send + 'static,
+{
+    let service = Service::<SimStoreParams, V>::new_with_transport(
+        config.clone(),
+        store.clone(),
+        |local_key| build_transport(local_key, link_delay),
+    )
+    .await
+    .context("failed to create service")?;
+
+    let client = service.client();
+    let events = service.subscribe();
+    let handle = tokio::task::spawn(service.run());
+    Ok((client, events, handle))
+}
+
+/// Memory transport without encryption, delaying the setup of each connection.
+fn build_transport(local_key: Keypair, link_delay: Duration) -> Boxed<(PeerId, StreamMuxerBox)> {
+    MemoryTransport::default()
+        .upgrade(libp2p::core::upgrade::Version::V1)
+        .authenticate(plaintext::Config::new(&local_key))
+        .multiplex(yamux::Config::default())
+        .and_then(move |out, _| async move {
+            if !link_delay.is_zero() {
+                tokio::time::sleep(link_delay).await;
+            }
+            Ok::<_, std::io::Error>(out)
+        })
+        .boxed()
+}
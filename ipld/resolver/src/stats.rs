@@ -0,0 +1,163 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Statistics about resolutions, broken down by subnet, so operators can tell
+//! which child subnets have trouble resolving content.
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use ipc_api::subnet_id::SubnetID;
+use log::info;
+
+/// Label used for resolutions which weren't looking for providers of a subnet,
+/// e.g. blobs downloaded from a known Iroh node.
+const NO_SUBNET: &str = "direct";
+
+/// What kind of content was resolved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolveKind {
+    /// A CID resolved with Bitswap.
+    Cid,
+    /// A blob downloaded with Iroh.
+    Blob,
+    /// A range of a blob read from the local Iroh node.
+    Read,
+}
+
+impl ResolveKind {
+    /// Label to use in metrics.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ResolveKind::Cid => "cid",
+            ResolveKind::Blob => "blob",
+            ResolveKind::Read => "read",
+        }
+    }
+}
+
+/// Outcome of a resolution, recorded when it completes.
+#[derive(Clone, Debug)]
+pub struct Resolved {
+    pub kind: ResolveKind,
+    /// The subnet whose providers the content was resolved from.
+    pub subnet_id: Option<SubnetID>,
+    pub success: bool,
+    /// Time from starting the resolution until it completed, not counting the time spent queueing.
+    pub duration: Duration,
+    /// Number of bytes transferred, if known.
+    pub bytes: Option<u64>,
+}
+
+impl Resolved {
+    /// Label of the subnet to use in metrics and logs.
+    pub fn subnet_label(&self) -> String {
+        self.subnet_id
+            .as_ref()
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| NO_SUBNET.to_string())
+    }
+}
+
+/// Resolutions of a subnet since the last summary.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SubnetStats {
+    pub succeeded: u64,
+    pub failed: u64,
+    pub bytes: u64,
+    pub total_duration: Duration,
+    pub max_duration: Duration,
+}
+
+impl SubnetStats {
+    pub fn mean_duration(&self) -> Duration {
+        let count = self.succeeded + self.failed;
+        if count == 0 {
+            Duration::ZERO
+        } else {
+            self.total_duration.div_f64(count as f64)
+        }
+    }
+}
+
+/// Accumulates the outcome of resolutions by subnet between periodic summaries.
+#[derive(Default)]
+pub struct ResolveStats {
+    subnets: BTreeMap<String, SubnetStats>,
+}
+
+impl ResolveStats {
+    pub fn record(&mut self, resolved: &Resolved) {
+        let stats = self.subnets.entry(resolved.subnet_label()).or_default();
+        if resolved.success {
+            stats.succeeded += 1;
+        } else {
+            stats.failed += 1;
+        }
+        stats.bytes += resolved.bytes.unwrap_or_default();
+        stats.total_duration += resolved.duration;
+        stats.max_duration = stats.max_duration.max(resolved.duration);
+    }
+
+    /// Take the statistics gathered since the last summary.
+    pub fn take(&mut self) -> BTreeMap<String, SubnetStats> {
+        std::mem::take(&mut self.subnets)
+    }
+
+    /// Log a line for each subnet which had resolutions since the last summary, then start over.
+    pub fn log_summary(&mut self, period: Duration) {
+        for (subnet, stats) in self.take() {
+            info!(
+                "resolutions from {subnet} in the last {period:?}: {} succeeded, {} failed, {} bytes, {:?} mean, {:?} max",
+                stats.succeeded,
+                stats.failed,
+                stats.bytes,
+                stats.mean_duration(),
+                stats.max_duration
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ipc_api::subnet_id::SubnetID;
+
+    use super::{ResolveKind, ResolveStats, Resolved, NO_SUBNET};
+
+    fn resolved(subnet_id: Option<SubnetID>, success: bool, secs: u64) -> Resolved {
+        Resolved {
+            kind: ResolveKind::Blob,
+            subnet_id,
+            success,
+            duration: Duration::from_secs(secs),
+            bytes: success.then_some(100),
+        }
+    }
+
+    #[test]
+    fn record_by_subnet() {
+        let subnet_id = SubnetID::default();
+        let mut stats = ResolveStats::default();
+
+        stats.record(&resolved(Some(subnet_id.clone()), true, 1));
+        stats.record(&resolved(Some(subnet_id.clone()), true, 2));
+        stats.record(&resolved(Some(subnet_id.clone()), false, 6));
+        stats.record(&resolved(None, true, 1));
+
+        let summary = stats.take();
+        assert_eq!(summary.len(), 2);
+
+        let subnet = &summary[&subnet_id.to_string()];
+        assert_eq!(subnet.succeeded, 2);
+        assert_eq!(subnet.failed, 1);
+        assert_eq!(subnet.bytes, 200);
+        assert_eq!(subnet.mean_duration(), Duration::from_secs(3));
+        assert_eq!(subnet.max_duration, Duration::from_secs(6));
+
+        let direct = &summary[NO_SUBNET];
+        assert_eq!(direct.succeeded, 1);
+
+        assert!(stats.take().is_empty(), "taking starts over");
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Scenarios run on the in-memory network simulation.
+//!
+//! Run the tests as follows:
+//! ```ignore
+//! RUST_LOG=debug cargo test -p ipc_ipld_resolver --test sim
+//! ```
+use std::time::Duration;
+
+use fvm_shared::{address::Address, ActorID};
+use ipc_api::subnet_id::SubnetID;
+use ipc_ipld_resolver::sim::{Simulation, Topology};
+use serde::{Deserialize, Serialize};
+
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+struct TestVote(u64);
+
+fn init_log() {
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+fn make_subnet_id(actor_id: ActorID) -> SubnetID {
+    let act = Address::new_id(actor_id);
+    let root = SubnetID::new_root(0);
+    SubnetID::new_from_parent(&root, act)
+}
+
+/// Every node in a chain can resolve from a provider at the other end, even over a slow link.
+#[tokio::test]
+async fn chain_resolves_from_provider() {
+    init_log();
+
+    let mut sim = Simulation::<TestVote>::start(Topology::Chain(3))
+        .await
+        .expect("failed to start simulation");
+
+    let slow_idx = sim
+        .add_node(Some(2), Duration::from_millis(200))
+        .await
+        .expect("failed to add slow node");
+
+    let subnet_id = make_subnet_id(1001);
+    let provider_idx = 0;
+    let cid = sim
+        .node(provider_idx)
+        .store
+        .insert_raw(b"chain")
+        .expect("failed to insert data");
+
+    sim.node(provider_idx)
+        .client
+        .add_provided_subnet(subnet_id.clone())
+        .expect("failed to add provided subnet");
+
+    sim.settle(Duration::from_secs(3)).await;
+
+    sim.resolve_on(&[1, 2, slow_idx], cid, &subnet_id, RESOLVE_TIMEOUT)
+        .await
+        .ensure_success_rate(1.0)
+        .expect("all nodes should resolve");
+}
+
+/// A node cut off from the provider can't resolve, until the partition heals.
+#[tokio::test]
+async fn partitioned_node_resolves_after_healing() {
+    init_log();
+
+    let sim = Simulation::<TestVote>::start(Topology::Star(3))
+        .await
+        .expect("failed to start simulation");
+
+    let subnet_id = make_subnet_id(1002);
+    let provider_idx = 1;
+    let resolver_idx = 2;
+    let cid = sim
+        .node(provider_idx)
+        .store
+        .insert_raw(b"partition")
+        .expect("failed to insert data");
+
+    sim.node(provider_idx)
+        .client
+        .add_provided_subnet(subnet_id.clone())
+        .expect("failed to add provided subnet");
+
+    sim.settle(Duration::from_secs(3)).await;
+
+    sim.partition(&[0, provider_idx], &[resolver_idx])
+        .expect("failed to partition");
+    sim.settle(Duration::from_secs(1)).await;
+
+    let report = sim
+        .resolve_on(&[resolver_idx], cid, &subnet_id, RESOLVE_TIMEOUT)
+        .await;
+    assert_eq!(
+        report.successes, 0,
+        "should not resolve across the partition"
+    );
+
+    sim.heal(&[0, provider_idx], &[resolver_idx])
+        .expect("failed to heal");
+    sim.settle(Duration::from_secs(1)).await;
+
+    sim.resolve_on(&[resolver_idx], cid, &subnet_id, RESOLVE_TIMEOUT)
+        .await
+        .ensure_success_rate(1.0)
+        .expect("should resolve after healing");
+}
+
+/// A provider which restarts is found again and serves its content.
+#[tokio::test]
+async fn restarted_provider_serves_content() {
+    init_log();
+
+    let mut sim = Simulation::<TestVote>::start(Topology::Star(3))
+        .await
+        .expect("failed to start simulation");
+
+    let subnet_id = make_subnet_id(1003);
+    let provider_idx = 1;
+    let resolver_idx = 2;
+    let cid = sim
+        .node(provider_idx)
+        .store
+        .insert_raw(b"churn")
+        .expect("failed to insert data");
+
+    sim.stop_node(provider_idx)
+        .await
+        .expect("failed to stop provider");
+    assert!(!sim.node(provider_idx).is_running());
+
+    sim.restart_node(provider_idx)
+        .await
+        .expect("failed to restart provider");
+
+    sim.node(provider_idx)
+        .client
+        .add_provided_subnet(subnet_id.clone())
+        .expect("failed to add provided subnet");
+
+    sim.settle(Duration::from_secs(3)).await;
+
+    sim.resolve_on(&[resolver_idx], cid, &subnet_id, RESOLVE_TIMEOUT)
+        .await
+        .ensure_success_rate(1.0)
+        .expect("should resolve from the restarted provider");
+}
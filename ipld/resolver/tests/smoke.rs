@@ -18,28 +18,34 @@
 // (although these might be orthogonal).
 
 use anyhow::anyhow;
+use async_trait::async_trait;
 use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::IPLD_RAW;
 use fvm_ipld_hamt::Hamt;
 use fvm_shared::{address::Address, ActorID};
 use ipc_api::subnet_id::SubnetID;
 use ipc_ipld_resolver::{
-    Client, Config, ConnectionConfig, ContentConfig, DiscoveryConfig, Event, MembershipConfig,
-    NetworkConfig, Resolver, Service, VoteRecord,
+    BlobAnnouncement, Client, Config, ConnectionConfig, ContentConfig, DiscoveryConfig, Event,
+    GatingMode, MembershipConfig, NatConfig, NetworkConfig, QueueConfig, ReputationConfig,
+    Resolver, Service, SubnetValidators, VoteRecord,
 };
 use libp2p::{
     core::{
         muxing::StreamMuxerBox,
         transport::{Boxed, MemoryTransport},
     },
-    identity::Keypair,
+    identity::{Keypair, PublicKey},
     multiaddr::Protocol,
     plaintext, yamux, Multiaddr, PeerId, Transport,
 };
 use multihash::{Code, MultihashDigest};
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::{
-    sync::atomic::{AtomicU64, Ordering},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     time::Duration,
 };
 
@@ -105,6 +111,11 @@ impl ClusterBuilder {
 
     /// Add a node with randomized address, optionally bootstrapping from an existing node.
     async fn add_node(&mut self, bootstrap: Option<usize>) {
+        self.add_node_with(bootstrap, |_| {}).await
+    }
+
+    /// Add a node like [`ClusterBuilder::add_node`], adjusting its config before it's started.
+    async fn add_node_with(&mut self, bootstrap: Option<usize>, f: impl FnOnce(&mut Config)) {
         let bootstrap_addr = bootstrap.map(|i| {
             let config = &self.agents[i].config;
             let peer_id = config.network.local_peer_id();
@@ -112,7 +123,8 @@ impl ClusterBuilder {
             addr.push(Protocol::P2p(peer_id));
             addr
         });
-        let config = make_config(&mut self.rng, self.size, bootstrap_addr);
+        let mut config = make_config(&mut self.rng, self.size, bootstrap_addr);
+        f(&mut config);
         let (service, store) = make_service(config.clone()).await;
         let client = service.client();
         let events = service.subscribe();
@@ -182,6 +194,20 @@ async fn single_bootstrap_single_provider_resolve_one() {
     // TODO: Wait on some condition instead of sleep.
     tokio::time::sleep(Duration::from_secs(3)).await;
 
+    // The resolver should know about the provider by now.
+    let providers = cluster.agents[resolver_idx]
+        .client
+        .list_providers(subnet_id.clone())
+        .await
+        .expect("failed to list providers");
+    let provider_id = cluster.agents[provider_idx]
+        .config
+        .network
+        .local_key
+        .public()
+        .to_peer_id();
+    assert!(providers.iter().any(|p| p.peer_id == provider_id));
+
     // Ask for the CID to be resolved from another peer.
     tokio::time::timeout(
         Duration::from_secs(3),
@@ -198,6 +224,168 @@ async fn single_bootstrap_single_provider_resolve_one() {
     check_test_data(&mut cluster.agents[resolver_idx], &cid).expect("failed to resolve from store");
 }
 
+/// Start three providers, only some of which hold the content, and resolve it
+/// from a node racing two of them at a time.
+#[tokio::test]
+async fn single_bootstrap_parallel_queries_resolve() {
+    init_log();
+
+    let bootstrap_idx = 0;
+    let holder_idxs = [1, 2];
+    let late_idx = 3;
+    let resolver_idx = 4;
+
+    let mut builder = ClusterBuilder::new(5);
+    for i in 0..builder.size as usize {
+        builder
+            .add_node_with(if i == 0 { None } else { Some(bootstrap_idx) }, |config| {
+                if i == resolver_idx {
+                    config.connection.parallel_queries = 2;
+                }
+            })
+            .await;
+    }
+    let mut cluster = builder.run();
+    cluster.await_connect().await;
+
+    // The same data results in the same CID on both holders.
+    let mut cid = None;
+    for i in holder_idxs {
+        cid = Some(insert_test_data(&mut cluster.agents[i]).expect("failed to insert data"));
+    }
+    let cid = cid.unwrap();
+
+    let subnet_id = make_subnet_id(1001);
+
+    for i in [holder_idxs[0], holder_idxs[1], late_idx] {
+        cluster.agents[i]
+            .client
+            .add_provided_subnet(subnet_id.clone())
+            .expect("failed to add provided subnet");
+    }
+
+    // Wait a little for the gossip to spread and peer lookups to happen, then another round of gossip.
+    // TODO: Wait on some condition instead of sleep.
+    tokio::time::sleep(Duration::from_secs(3)).await;
+
+    let client = cluster.agents[resolver_idx].client.clone();
+    let resolve = |cid| {
+        timeout(
+            Duration::from_secs(10),
+            client.resolve(cid, subnet_id.clone()),
+        )
+    };
+
+    // Any two providers include a holder, so the fastest holder wins and the other query
+    // is cancelled, unless it already failed because it went to the provider without the data.
+    resolve(cid)
+        .await
+        .expect("timeout resolving content")
+        .expect("failed to send request")
+        .expect("failed to resolve content");
+
+    check_test_data(&mut cluster.agents[resolver_idx], &cid).expect("failed to resolve from store");
+
+    // Only the provider which had nothing before holds this. Unless it's in the first batch,
+    // both queries of that batch fail, and the content is resolved from the fallback batch.
+    let data = b"late content";
+    let late_cid = Cid::new_v1(IPLD_RAW, Code::Sha2_256.digest(data));
+    cluster.agents[late_idx]
+        .store
+        .put_keyed(&late_cid, data)
+        .expect("failed to insert data");
+
+    resolve(late_cid)
+        .await
+        .expect("timeout resolving content")
+        .expect("failed to send request")
+        .expect("failed to resolve content from the fallback batch");
+
+    assert!(cluster.agents[resolver_idx]
+        .store
+        .has(&late_cid)
+        .expect("failed to read store"));
+
+    // Nobody holds this, so every batch fails and the error is returned.
+    let missing_cid = Cid::new_v1(IPLD_RAW, Code::Sha2_256.digest(b"missing content"));
+    let res = resolve(missing_cid)
+        .await
+        .expect("timeout resolving content")
+        .expect("failed to send request");
+    assert!(res.is_err(), "nobody has the content");
+}
+
+/// Validator set with a single member for every subnet.
+struct SingleValidator(PublicKey);
+
+#[async_trait]
+impl SubnetValidators for SingleValidator {
+    async fn is_validator(
+        &self,
+        _subnet_id: &SubnetID,
+        public_key: &PublicKey,
+    ) -> anyhow::Result<Option<bool>> {
+        Ok(Some(*public_key == self.0))
+    }
+}
+
+/// Start two providers, only one of which is a validator of the subnet, and check
+/// that a node validating provider records only learns about the validator.
+#[tokio::test]
+async fn single_bootstrap_reject_non_validator_provider() {
+    init_log();
+
+    let bootstrap_idx = 0;
+    let validator_idx = 1;
+    let other_idx = 2;
+    let resolver_idx = 3;
+
+    let mut builder = ClusterBuilder::new(4);
+    for i in 0..builder.size {
+        builder
+            .add_node(if i == 0 { None } else { Some(bootstrap_idx) })
+            .await;
+    }
+
+    let validator_key = builder.agents[validator_idx]
+        .config
+        .network
+        .local_key
+        .public();
+
+    builder.services[resolver_idx]
+        .set_subnet_validators(Arc::new(SingleValidator(validator_key.clone())));
+
+    let cluster = builder.run();
+    cluster.await_connect().await;
+
+    let subnet_id = make_subnet_id(1001);
+
+    for i in [validator_idx, other_idx] {
+        cluster.agents[i]
+            .client
+            .add_provided_subnet(subnet_id.clone())
+            .expect("failed to add provided subnet");
+    }
+
+    // Wait a little for the gossip to spread and peer lookups to happen, then another round of gossip.
+    // TODO: Wait on some condition instead of sleep.
+    tokio::time::sleep(Duration::from_secs(3)).await;
+
+    let providers = cluster.agents[resolver_idx]
+        .client
+        .list_providers(subnet_id)
+        .await
+        .expect("failed to list providers");
+
+    let other_id = cluster.agents[other_idx].config.network.local_peer_id();
+
+    assert!(providers
+        .iter()
+        .any(|p| p.peer_id == validator_key.to_peer_id()));
+    assert!(!providers.iter().any(|p| p.peer_id == other_id));
+}
+
 /// Start two agents, subscribe to the same subnet, publish and receive a vote.
 #[tokio::test]
 async fn single_bootstrap_publish_receive_vote() {
@@ -244,6 +432,54 @@ async fn single_bootstrap_publish_receive_vote() {
     }
 }
 
+/// Start two agents, subscribe to the same subnet, publish and receive a blob announcement.
+#[tokio::test]
+async fn single_bootstrap_publish_receive_blob_announcement() {
+    init_log();
+
+    let mut cluster = make_cluster_with_bootstrap(2, 0).await;
+
+    let subnet_id = make_subnet_id(1001);
+
+    for i in 0..cluster.size() {
+        cluster.agents[i]
+            .client
+            .add_provided_subnet(subnet_id.clone())
+            .expect("failed to add provided subnet");
+    }
+
+    // TODO: Wait on some condition instead of sleep.
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    // Announce that we failed to resolve some random blob.
+    let validator_key = Keypair::generate_secp256k1();
+    let announcement = BlobAnnouncement {
+        hash: iroh::blobs::Hash::new(b"foo"),
+        resolved: false,
+        location: None,
+    };
+    let announcement = VoteRecord::signed(&validator_key, subnet_id, announcement)
+        .expect("failed to sign announcement");
+
+    cluster.agents[0]
+        .client
+        .publish_blob_announcement(announcement.clone())
+        .expect("failed to send announcement");
+
+    let event = timeout(Duration::from_secs(2), cluster.agents[1].events.recv())
+        .await
+        .expect("timeout receiving announcement")
+        .expect("error receiving announcement");
+
+    if let Event::ReceivedBlobAnnouncement(a) = event {
+        assert_eq!(a.public_key, announcement.record().public_key);
+        assert_eq!(a.content.hash, announcement.record().content.hash);
+        assert!(!a.content.resolved);
+    } else {
+        panic!("unexpected {event:?}")
+    }
+}
+
 /// Start two agents, pin a subnet, publish preemptively and receive.
 #[tokio::test]
 async fn single_bootstrap_publish_receive_preemptive() {
@@ -283,6 +519,27 @@ async fn single_bootstrap_publish_receive_preemptive() {
     }
 }
 
+/// Shut down an agent and check that it stops serving requests.
+#[tokio::test]
+async fn shutdown_stops_service() {
+    init_log();
+
+    let cluster = make_cluster_with_bootstrap(2, 0).await;
+    let client = &cluster.agents[1].client;
+
+    timeout(
+        Duration::from_secs(5),
+        client.shutdown(Duration::from_secs(1)),
+    )
+    .await
+    .expect("timeout shutting down")
+    .expect("failed to shut down");
+
+    let cid = Cid::new_v1(IPLD_RAW, Code::Sha2_256.digest(b"foo"));
+    let res = client.resolve(cid, make_subnet_id(1001)).await;
+    assert!(res.is_err(), "service should be disconnected");
+}
+
 #[tokio::test]
 async fn can_register_metrics() {
     let mut rng = StdRng::seed_from_u64(0);
@@ -325,7 +582,11 @@ fn make_config(rng: &mut StdRng, cluster_size: u32, bootstrap_addr: Option<Multi
             expected_peer_count: cluster_size,
             max_incoming: cluster_size,
             max_peers_per_query: cluster_size,
+            parallel_queries: 1,
             event_buffer_capacity: cluster_size,
+            enable_quic: false,
+            quic_listen_addr: None,
+            tcp_fallback: true,
         },
         network: NetworkConfig {
             local_key: Keypair::generate_secp256k1(),
@@ -335,6 +596,12 @@ fn make_config(rng: &mut StdRng, cluster_size: u32, bootstrap_addr: Option<Multi
             static_addresses: bootstrap_addr.iter().cloned().collect(),
             target_connections: cluster_size.try_into().unwrap(),
             enable_kademlia: true,
+            enable_mdns: false,
+            peer_store: None,
+            enable_provider_records: true,
+            provider_record_interval: Duration::from_secs(60),
+            min_providers: 1,
+            verify_addresses: false,
         },
         membership: MembershipConfig {
             static_subnets: vec![],
@@ -342,10 +609,27 @@ fn make_config(rng: &mut StdRng, cluster_size: u32, bootstrap_addr: Option<Multi
             publish_interval: Duration::from_secs(5),
             min_time_between_publish: Duration::from_secs(1),
             max_provider_age: Duration::from_secs(60),
+            gating: GatingMode::Disabled,
+            gating_grace_period: Duration::from_secs(120),
         },
         content: ContentConfig {
             rate_limit_bytes: 1 << 20,
             rate_limit_period: Duration::from_secs(60),
+            request_rate_limit_per_peer: 0,
+            request_rate_limit_per_subnet: 0,
+            request_rate_limit_period: Duration::ZERO,
+            peer_daily_quota_bytes: 0,
+        },
+        nat: NatConfig::default(),
+        reputation: ReputationConfig {
+            ban_threshold: -100,
+            ban_duration: Duration::from_secs(60),
+            denylist: vec![],
+        },
+        queue: QueueConfig {
+            capacity: 100,
+            max_concurrent: 10,
+            max_wait: Duration::from_secs(60),
         },
         iroh_addr: None,
     };
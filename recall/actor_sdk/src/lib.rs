@@ -18,12 +18,65 @@ pub fn hash_rm(hash: [u8; 32]) -> Result<(), ErrorNumber> {
     unsafe { sys::hash_rm(hash.as_ptr()) }
 }
 
+/// Deletes many blobs with a single syscall.
+pub fn hash_rm_many(hashes: &[[u8; 32]]) -> Result<(), ErrorNumber> {
+    unsafe { sys::hash_rm_many(hashes.as_ptr() as *const u8, hashes.len() as u32) }
+}
+
+/// Reads up to `len` bytes of a locally available blob, starting at `offset`.
+/// The returned bytes are shorter than `len` if the blob ends first.
+pub fn hash_get(hash: [u8; 32], offset: u64, len: u32) -> Result<Vec<u8>, ErrorNumber> {
+    let mut buf = vec![0u8; len as usize];
+    let n = unsafe { sys::hash_get(hash.as_ptr(), offset, len, buf.as_mut_ptr())? };
+    buf.truncate(n as usize);
+    Ok(buf)
+}
+
+/// Returns the size of a blob if it's available on the local Iroh node.
+pub fn hash_stat(hash: [u8; 32]) -> Result<Option<u64>, ErrorNumber> {
+    match unsafe { sys::hash_stat(hash.as_ptr()) } {
+        Ok(size) => Ok(Some(size)),
+        Err(ErrorNumber::NotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Pins a blob in the local Iroh store so that Iroh's GC never removes it.
+pub fn hash_pin(hash: [u8; 32]) -> Result<(), ErrorNumber> {
+    unsafe { sys::hash_pin(hash.as_ptr()) }
+}
+
+/// Removes a blob's pin from the local Iroh store.
+pub fn hash_unpin(hash: [u8; 32]) -> Result<(), ErrorNumber> {
+    unsafe { sys::hash_unpin(hash.as_ptr()) }
+}
+
+/// Tags a blob in the local Iroh store under the calling actor's namespace.
+///
+/// The `label` tells apart the references an actor holds to the same blob, e.g. object keys,
+/// so that removing one of them doesn't untag content the others still use.
+pub fn hash_tag(hash: [u8; 32], label: [u8; 32]) -> Result<(), ErrorNumber> {
+    unsafe { sys::hash_tag(hash.as_ptr(), label.as_ptr()) }
+}
+
+/// Removes the calling actor's tag with the given label from a blob in the local Iroh store.
+pub fn hash_untag(hash: [u8; 32], label: [u8; 32]) -> Result<(), ErrorNumber> {
+    unsafe { sys::hash_untag(hash.as_ptr(), label.as_ptr()) }
+}
+
 mod sys {
     use fvm_sdk::sys::fvm_syscalls;
 
     fvm_syscalls! {
         module = "recall";
         pub fn hash_rm(hash_ptr: *const u8) -> Result<()>;
+        pub fn hash_rm_many(hashes_ptr: *const u8, count: u32) -> Result<()>;
+        pub fn hash_get(hash_ptr: *const u8, offset: u64, len: u32, obuf_ptr: *mut u8) -> Result<u32>;
+        pub fn hash_stat(hash_ptr: *const u8) -> Result<u64>;
+        pub fn hash_pin(hash_ptr: *const u8) -> Result<()>;
+        pub fn hash_unpin(hash_ptr: *const u8) -> Result<()>;
+        pub fn hash_tag(hash_ptr: *const u8, label_ptr: *const u8) -> Result<()>;
+        pub fn hash_untag(hash_ptr: *const u8, label_ptr: *const u8) -> Result<()>;
     }
 }
 
@@ -124,6 +124,37 @@ where
         self.amt.count()
     }
 
+    /// Iterates and runs a function over all values in the vec, in index order.
+    pub fn for_each<F>(&self, mut f: F) -> Result<(), ActorError>
+    where
+        F: FnMut(u64, &V) -> Result<(), ActorError>,
+    {
+        match self.amt.for_each(|i, v| f(i, v).map_err(|e| anyhow!(e))) {
+            Ok(()) => Ok(()),
+            Err(amt_err) => self.map_amt_error(amt_err),
+        }
+    }
+
+    /// Iterates and runs a function over all values in the vec starting at an index up to a limit.
+    /// Returns the number of values traversed and the next index if there are more items.
+    pub fn for_each_ranged<F>(
+        &self,
+        start_at: Option<u64>,
+        limit: Option<u64>,
+        mut f: F,
+    ) -> Result<(u64, Option<u64>), ActorError>
+    where
+        F: FnMut(u64, &V) -> Result<(), ActorError>,
+    {
+        match self
+            .amt
+            .for_each_ranged(start_at, limit, |i, v| f(i, v).map_err(|e| anyhow!(e)))
+        {
+            Ok((traversed, next)) => Ok((traversed, next)),
+            Err(amt_err) => self.map_amt_error(amt_err),
+        }
+    }
+
     /// Iterates and runs a function over values in the vec starting at an index up to a limit.
     /// Returns the index if there are more items.
     pub fn for_each_while_ranged<F>(
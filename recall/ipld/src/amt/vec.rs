@@ -124,6 +124,11 @@ where
         Ok(Root::from_cid(cid))
     }
 
+    pub fn flush_tracked(&mut self) -> Result<TrackedFlushResult<V>, ActorError> {
+        let root = self.flush()?;
+        Ok(TrackedFlushResult { root })
+    }
+
     pub fn flush_empty(store: BS) -> Result<Root<V>, ActorError> {
         let cid = Vec::<BS, V>::flush_empty(store, DEFAULT_AMT_CONFIG)?;
         Ok(Root::from_cid(cid))
@@ -137,6 +142,29 @@ where
         self.vec.count()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+
+    pub fn for_each<F>(&self, mut f: F) -> Result<(), ActorError>
+    where
+        F: FnMut(u64, &V) -> Result<(), ActorError>,
+    {
+        self.vec.for_each(&mut f)
+    }
+
+    pub fn for_each_ranged<F>(
+        &self,
+        start_at: Option<u64>,
+        limit: Option<u64>,
+        mut f: F,
+    ) -> Result<(u64, Option<u64>), ActorError>
+    where
+        F: FnMut(u64, &V) -> Result<(), ActorError>,
+    {
+        self.vec.for_each_ranged(start_at, limit, &mut f)
+    }
+
     pub fn for_each_while_ranged<F>(
         &self,
         start_at: Option<u64>,
@@ -149,3 +177,66 @@ where
         self.vec.for_each_while_ranged(start_at, limit, &mut f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    #[test]
+    fn test_set_get_delete() {
+        let store = MemoryBlockstore::new();
+        let root = Root::<String>::new(&store).unwrap();
+        let mut amt = root.amt(&store).unwrap();
+        assert!(amt.is_empty());
+
+        amt.set(3, "three".into()).unwrap();
+        let root = amt.set_and_flush(7, "seven".into()).unwrap();
+
+        let mut amt = root.amt(&store).unwrap();
+        assert_eq!(amt.count(), 2);
+        assert_eq!(amt.get(3).unwrap(), Some("three".into()));
+        assert_eq!(amt.get(4).unwrap(), None);
+        assert!(amt.get_or_err(4).is_err());
+
+        let root = amt.delete_and_flush(3).unwrap();
+        let amt = root.amt(&store).unwrap();
+        assert_eq!(amt.count(), 1);
+        assert_eq!(amt.get(3).unwrap(), None);
+    }
+
+    #[test]
+    fn test_for_each_ranged() {
+        let store = MemoryBlockstore::new();
+        let root = Root::<u64>::new(&store).unwrap();
+        let mut amt = root.amt(&store).unwrap();
+        for i in [1, 2, 5, 8, 13] {
+            amt.set(i, i * 10).unwrap();
+        }
+        let root = amt.flush().unwrap();
+        let amt = root.amt(&store).unwrap();
+
+        let mut all = vec![];
+        amt.for_each(|i, v| {
+            all.push((i, *v));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(all, vec![(1, 10), (2, 20), (5, 50), (8, 80), (13, 130)]);
+
+        let mut page = vec![];
+        let (traversed, next) = amt
+            .for_each_ranged(Some(2), Some(2), |i, _| {
+                page.push(i);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(traversed, 2);
+        assert_eq!(page, vec![2, 5]);
+        assert_eq!(next, Some(8));
+
+        let (traversed, next) = amt.for_each_ranged(next, None, |_, _| Ok(())).unwrap();
+        assert_eq!(traversed, 2);
+        assert_eq!(next, None);
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Export and import of HAMT-backed state as CAR files.
+//!
+//! See https://ipld.io/specs/transport/car/carv1/
+
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Display;
+
+use anyhow::{anyhow, Context};
+use cid::multihash::{Code, MultihashDigest};
+use cid::Cid;
+use futures::io::{AsyncRead, AsyncWrite};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_car::{load_car, CarHeader};
+use fvm_ipld_encoding::{CborStore, DAG_CBOR};
+use libipld::Ipld;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::hamt::{MapKey, Root};
+
+/// Writes a HAMT and everything its values link to into a CAR file.
+///
+/// The CAR root is a block holding the encoded [`Root`], so its name and config can be
+/// restored by [`import_car`]. Blocks are written in breadth-first order as they're read
+/// from the store, so the export is deterministic for a given root.
+pub async fn export_car<BS, K, V, W>(
+    store: &BS,
+    root: &Root<K, V>,
+    writer: &mut W,
+) -> anyhow::Result<()>
+where
+    BS: Blockstore,
+    K: MapKey + Display,
+    V: DeserializeOwned + Serialize + PartialEq + Clone,
+    W: AsyncWrite + Unpin,
+{
+    let root_bytes = fvm_ipld_encoding::to_vec(root)?;
+    let root_cid = Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(&root_bytes));
+
+    let mut walker = BlockWalker::new(store, *root.cid());
+    let mut blocks = futures::stream::iter(
+        std::iter::once((root_cid, root_bytes)).chain(std::iter::from_fn(|| walker.next_block())),
+    );
+    CarHeader::new(vec![root_cid], 1)
+        .write_stream_async(writer, &mut blocks)
+        .await
+        .context("failed to write CAR file")?;
+    drop(blocks);
+
+    match walker.error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Loads a CAR file written by [`export_car`] into the store, returning the HAMT root.
+pub async fn import_car<BS, K, V, R>(store: &BS, reader: R) -> anyhow::Result<Root<K, V>>
+where
+    BS: Blockstore,
+    K: MapKey + Display,
+    V: DeserializeOwned + Serialize + PartialEq + Clone,
+    R: AsyncRead + Send + Unpin,
+{
+    let roots = load_car(store, reader)
+        .await
+        .context("failed to load CAR file")?;
+    let [root_cid] = roots.as_slice() else {
+        return Err(anyhow!("expected a single CAR root, got {}", roots.len()));
+    };
+    store
+        .get_cbor::<Root<K, V>>(root_cid)?
+        .ok_or_else(|| anyhow!("CAR root {} not found", root_cid))
+}
+
+/// Visits every block reachable from a root, each once.
+///
+/// The walk stops at the first error, which is kept for the caller to check once the
+/// blocks have been consumed.
+struct BlockWalker<'a, BS> {
+    store: &'a BS,
+    queue: VecDeque<Cid>,
+    seen: HashSet<Cid>,
+    error: Option<anyhow::Error>,
+}
+
+impl<'a, BS: Blockstore> BlockWalker<'a, BS> {
+    fn new(store: &'a BS, root: Cid) -> Self {
+        Self {
+            store,
+            queue: VecDeque::from([root]),
+            seen: HashSet::from([root]),
+            error: None,
+        }
+    }
+
+    fn next_block(&mut self) -> Option<(Cid, Vec<u8>)> {
+        if self.error.is_some() {
+            return None;
+        }
+        let cid = self.queue.pop_front()?;
+        match self.load(&cid) {
+            Ok(bytes) => Some((cid, bytes)),
+            Err(e) => {
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+
+    fn load(&mut self, cid: &Cid) -> anyhow::Result<Vec<u8>> {
+        let bytes = self
+            .store
+            .get(cid)?
+            .ok_or_else(|| anyhow!("block {} not found", cid))?;
+        // Raw blocks, e.g., Wasm bytecode, don't link to anything
+        if cid.codec() == DAG_CBOR {
+            let ipld = fvm_ipld_encoding::from_slice::<Ipld>(&bytes)
+                .with_context(|| format!("failed to decode block {}", cid))?;
+            self.push_links(ipld);
+        }
+        Ok(bytes)
+    }
+
+    fn push_links(&mut self, ipld: Ipld) {
+        match ipld {
+            Ipld::List(items) => {
+                for item in items {
+                    self.push_links(item);
+                }
+            }
+            Ipld::Map(map) => {
+                for value in map.into_values() {
+                    self.push_links(value);
+                }
+            }
+            Ipld::Link(cid) => {
+                if self.seen.insert(cid) {
+                    self.queue.push_back(cid);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hamt::RootConfig;
+    use futures::executor::block_on;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    #[test]
+    fn test_export_import() {
+        let store = MemoryBlockstore::new();
+        let config = RootConfig {
+            bit_width: 4,
+            ..Default::default()
+        };
+        let mut hamt = Root::<u64, String>::new_with_config(&store, "test", config)
+            .unwrap()
+            .hamt(&store, 0)
+            .unwrap();
+        for i in 0..500 {
+            hamt.set(&i, i.to_string()).unwrap();
+        }
+        let root = hamt.flush().unwrap();
+
+        let mut car = Vec::new();
+        block_on(export_car(&store, &root, &mut car)).unwrap();
+
+        // The export is deterministic
+        let mut again = Vec::new();
+        block_on(export_car(&store, &root, &mut again)).unwrap();
+        assert_eq!(car, again);
+
+        let imported_store = MemoryBlockstore::new();
+        let imported: Root<u64, String> =
+            block_on(import_car(&imported_store, car.as_slice())).unwrap();
+        assert_eq!(imported, root);
+        let hamt = imported.hamt(&imported_store, 500).unwrap();
+        for i in 0..500 {
+            assert_eq!(hamt.get(&i).unwrap(), Some(i.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_export_missing_block() {
+        let store = MemoryBlockstore::new();
+        let root = Root::<u64, String>::new(&MemoryBlockstore::new(), "test").unwrap();
+        let mut car = Vec::new();
+        assert!(block_on(export_car(&store, &root, &mut car)).is_err());
+    }
+}
@@ -3,11 +3,16 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+pub mod cached;
 mod core;
+pub mod diff;
 pub mod map;
 
-pub use core::DEFAULT_HAMT_CONFIG;
-pub use core::MapKey;
+pub use cached::CachedHamt;
 pub use core::Map;
+pub use core::MapIter;
+pub use core::MapKey;
+pub use core::DEFAULT_HAMT_CONFIG;
+pub use diff::{diff, Change};
 pub use fvm_ipld_hamt::{BytesKey, Error};
-pub use map::Root;
+pub use map::{Root, RootConfig};
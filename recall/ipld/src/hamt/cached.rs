@@ -0,0 +1,234 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Display;
+
+use fil_actors_runtime::{ActorError, AsActorError};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::error::ExitCode;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::core::MapKey;
+use super::map::{Hamt, TrackedFlushResult};
+
+/// The default number of recently read values kept by a [`CachedHamt`].
+pub const DEFAULT_READ_CACHE_CAPACITY: usize = 256;
+
+/// A write-back layer over a [`Hamt`] for the duration of an actor transaction.
+///
+/// Writes and deletes are recorded as dirty keys and are only applied to the underlying HAMT
+/// by [`CachedHamt::commit`], which flushes once. Recently read values, including misses, are
+/// cached so repeated reads of the same key don't walk the HAMT again.
+/// Changes that are not committed are discarded when the map is dropped.
+pub struct CachedHamt<BS, K, V>
+where
+    BS: Blockstore,
+    K: MapKey + Display,
+    V: DeserializeOwned + Serialize + PartialEq + Clone,
+{
+    hamt: Hamt<BS, K, V>,
+    /// Pending writes by key bytes. `None` marks a pending delete.
+    dirty: BTreeMap<Vec<u8>, Option<V>>,
+    reads: RefCell<ReadCache<V>>,
+    size: u64,
+}
+
+impl<BS, K, V> CachedHamt<BS, K, V>
+where
+    BS: Blockstore,
+    K: MapKey + Display,
+    V: DeserializeOwned + Serialize + PartialEq + Clone,
+{
+    pub fn new(hamt: Hamt<BS, K, V>, read_cache_capacity: usize) -> Self {
+        let size = hamt.len();
+        Self {
+            hamt,
+            dirty: BTreeMap::new(),
+            reads: RefCell::new(ReadCache::new(read_cache_capacity)),
+            size,
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Result<Option<V>, ActorError> {
+        let k = key_bytes(key)?;
+        if let Some(value) = self.dirty.get(&k) {
+            return Ok(value.clone());
+        }
+        if let Some(value) = self.reads.borrow().get(&k) {
+            return Ok(value);
+        }
+        let value = self.hamt.get(key)?;
+        self.reads.borrow_mut().insert(k, value.clone());
+        Ok(value)
+    }
+
+    pub fn get_or_err(&self, key: &K) -> Result<V, ActorError> {
+        self.get(key)?
+            .ok_or_else(|| ActorError::not_found(format!("{} not found", key)))
+    }
+
+    pub fn contains_key(&self, key: &K) -> Result<bool, ActorError> {
+        Ok(self.get(key)?.is_some())
+    }
+
+    /// Sets a value without touching the underlying HAMT.
+    /// Returns any value previously associated with the key.
+    pub fn set(&mut self, key: &K, value: V) -> Result<Option<V>, ActorError> {
+        let previous = self.get(key)?;
+        if previous.is_none() {
+            self.size += 1;
+        }
+        self.dirty.insert(key_bytes(key)?, Some(value));
+        Ok(previous)
+    }
+
+    /// Deletes a value without touching the underlying HAMT.
+    /// Returns the deleted value, if any.
+    pub fn delete(&mut self, key: &K) -> Result<Option<V>, ActorError> {
+        let previous = self.get(key)?;
+        if previous.is_some() {
+            self.size -= 1;
+            self.dirty.insert(key_bytes(key)?, None);
+        }
+        Ok(previous)
+    }
+
+    /// Returns the number of entries, including uncommitted changes.
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Returns the number of keys with uncommitted changes.
+    pub fn dirty_count(&self) -> usize {
+        self.dirty.len()
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Applies all pending changes to the underlying HAMT and flushes it once.
+    pub fn commit(&mut self) -> Result<TrackedFlushResult<K, V>, ActorError> {
+        let dirty = std::mem::take(&mut self.dirty);
+        let mut reads = self.reads.borrow_mut();
+        for (k, value) in dirty {
+            let key =
+                K::from_bytes(&k).context_code(ExitCode::USR_ASSERTION_FAILED, "invalid key")?;
+            match &value {
+                Some(value) => {
+                    self.hamt.set(&key, value.clone())?;
+                }
+                None => {
+                    self.hamt.delete(&key)?;
+                }
+            }
+            reads.insert(k, value);
+        }
+        self.hamt.flush_tracked()
+    }
+}
+
+fn key_bytes<K: MapKey>(key: &K) -> Result<Vec<u8>, ActorError> {
+    key.to_bytes()
+        .context_code(ExitCode::USR_ASSERTION_FAILED, "invalid key")
+}
+
+/// A bounded cache of recently read values, evicting the oldest entries first.
+struct ReadCache<V> {
+    entries: HashMap<Vec<u8>, Option<V>>,
+    order: VecDeque<Vec<u8>>,
+    capacity: usize,
+}
+
+impl<V: Clone> ReadCache<V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    fn get(&self, key: &[u8]) -> Option<Option<V>> {
+        self.entries.get(key).cloned()
+    }
+
+    fn insert(&mut self, key: Vec<u8>, value: Option<V>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.insert(key.clone(), value).is_some() {
+            return;
+        }
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hamt::Root;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    #[test]
+    fn test_commit() {
+        let store = MemoryBlockstore::new();
+        let root = Root::<String, u64>::new(&store, "test").unwrap();
+        let mut cached = root.cached_hamt(&store, 0).unwrap();
+
+        assert_eq!(cached.set(&"a".into(), 1).unwrap(), None);
+        assert_eq!(cached.set(&"b".into(), 2).unwrap(), None);
+        assert_eq!(cached.set(&"a".into(), 3).unwrap(), Some(1));
+        assert_eq!(cached.delete(&"b".into()).unwrap(), Some(2));
+        assert_eq!(cached.delete(&"c".into()).unwrap(), None);
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached.dirty_count(), 2);
+        assert_eq!(cached.get(&"a".into()).unwrap(), Some(3));
+
+        // Nothing reaches the store before the commit
+        let hamt = root.hamt(&store, 0).unwrap();
+        assert_eq!(hamt.get(&"a".into()).unwrap(), None);
+
+        let result = cached.commit().unwrap();
+        assert!(!cached.is_dirty());
+        assert_eq!(result.size, 1);
+        let hamt = result.root.hamt(&store, result.size).unwrap();
+        assert_eq!(hamt.get(&"a".into()).unwrap(), Some(3));
+        assert_eq!(hamt.get(&"b".into()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_read_cache() {
+        let store = MemoryBlockstore::new();
+        let mut hamt = Root::<String, u64>::new(&store, "test")
+            .unwrap()
+            .hamt(&store, 0)
+            .unwrap();
+        for (i, key) in ["a", "b", "c"].into_iter().enumerate() {
+            hamt.set(&key.into(), i as u64).unwrap();
+        }
+        let root = hamt.flush().unwrap();
+
+        let cached = CachedHamt::new(root.hamt(&store, 3).unwrap(), 2);
+        for key in ["a", "b", "c", "d"] {
+            cached.get(&key.into()).unwrap();
+        }
+        let reads = cached.reads.borrow();
+        assert_eq!(reads.entries.len(), 2);
+        assert_eq!(reads.get(b"c".as_slice()), Some(Some(2)));
+        assert_eq!(reads.get(b"d".as_slice()), Some(None));
+        assert_eq!(reads.get(b"a".as_slice()), None);
+    }
+}
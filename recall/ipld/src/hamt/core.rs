@@ -295,6 +295,30 @@ where
         self.hamt.iter()
     }
 
+    /// Returns an iterator over decoded key-value pairs, starting at a key (inclusive)
+    /// or at the first key in iteration order.
+    pub fn iter_from(&self, starting_key: Option<&K>) -> Result<MapIter<BS, K, V>, ActorError> {
+        let inner = match starting_key {
+            Some(key) => {
+                let k = key
+                    .to_bytes()
+                    .context_code(ExitCode::USR_ASSERTION_FAILED, "invalid key")?;
+                self.hamt.iter_from(&BytesKey::from(k)).map_err(|error| {
+                    ActorError::illegal_state(format!(
+                        "error traversing HAMT {}: {}",
+                        self.name, error
+                    ))
+                })?
+            }
+            None => self.hamt.iter(),
+        };
+        Ok(MapIter {
+            inner: inner.fuse(),
+            name: &self.name,
+            key_type: Default::default(),
+        })
+    }
+
     pub fn is_empty(&self) -> bool {
         self.hamt.is_empty()
     }
@@ -326,6 +350,41 @@ impl MapKey for Vec<u8> {
     }
 }
 
+/// An iterator over the decoded key-value pairs of a [`Map`].
+pub struct MapIter<'a, BS, K, V>
+where
+    BS: Blockstore,
+    K: MapKey,
+    V: DeserializeOwned + Serialize,
+{
+    inner: std::iter::Fuse<hamt::Iter<'a, BS, V, hamt::BytesKey, Hasher>>,
+    name: &'a str,
+    key_type: PhantomData<K>,
+}
+
+impl<'a, BS, K, V> Iterator for MapIter<'a, BS, K, V>
+where
+    BS: Blockstore,
+    K: MapKey,
+    V: DeserializeOwned + Serialize,
+{
+    type Item = Result<(K, &'a V), ActorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = self.inner.next()?;
+        Some(
+            res.map_err(|error| {
+                ActorError::illegal_state(format!("error traversing HAMT {}: {}", self.name, error))
+            })
+            .and_then(|(k, v)| {
+                let key =
+                    K::from_bytes(k).context_code(ExitCode::USR_ILLEGAL_STATE, "invalid key")?;
+                Ok((key, v))
+            }),
+        )
+    }
+}
+
 impl MapKey for String {
     fn from_bytes(b: &[u8]) -> Result<Self, String> {
         String::from_utf8(b.to_vec()).map_err(|e| e.to_string())
@@ -413,4 +472,34 @@ mod tests {
         assert!(res.is_err());
         assert_eq!(res.unwrap_err(), ActorError::forbidden("test".to_string()));
     }
+
+    #[test]
+    fn iter_from_matches_for_each_ranged() {
+        let bs = MemoryBlockstore::new();
+        let mut m = Map::<_, u64, String>::empty(bs, DEFAULT_HAMT_CONFIG, "empty".into());
+        for i in 0..100 {
+            m.set(&i, i.to_string()).unwrap();
+        }
+        let mut all = vec![];
+        m.for_each(|k, _| {
+            all.push(k);
+            Ok(())
+        })
+        .unwrap();
+
+        let start = all[40];
+        let keys: Vec<u64> = m
+            .iter_from(Some(&start))
+            .unwrap()
+            .map(|res| res.unwrap().0)
+            .collect();
+        assert_eq!(keys, all[40..]);
+
+        let keys: Vec<u64> = m
+            .iter_from(None)
+            .unwrap()
+            .map(|res| res.unwrap().0)
+            .collect();
+        assert_eq!(keys, all);
+    }
 }
@@ -0,0 +1,262 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use cid::Cid;
+use fil_actors_runtime::{ActorError, AsActorError};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::CborStore;
+use fvm_shared::error::ExitCode;
+use libipld::Ipld;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::core::MapKey;
+use super::map::Root;
+
+/// A difference between two versions of a HAMT.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change<K, V> {
+    Added(K, V),
+    Removed(K, V),
+    Modified { key: K, old: V, new: V },
+}
+
+/// Returns the key-value pairs that were added, removed, or modified between two HAMT roots.
+///
+/// The tries are compared node by node, so subtrees that are shared by both roots are skipped
+/// without being loaded. Changes are returned in iteration order.
+/// Both roots must have the same config.
+pub fn diff<BS, K, V>(
+    store: BS,
+    old: &Root<K, V>,
+    new: &Root<K, V>,
+) -> Result<Vec<Change<K, V>>, ActorError>
+where
+    BS: Blockstore,
+    K: MapKey + Display,
+    V: DeserializeOwned + Serialize + PartialEq + Clone,
+{
+    if old.config() != new.config() {
+        return Err(ActorError::illegal_argument(
+            "cannot diff HAMTs with different configs".into(),
+        ));
+    }
+    let slots = 1u32 << old.config().bit_width;
+    let mut changes = Vec::new();
+    diff_nodes(&store, slots, old.cid(), new.cid(), &mut changes)?;
+    Ok(changes)
+}
+
+/// A HAMT node decoded as raw IPLD, so it can be compared without decoding values.
+struct Node {
+    bitfield: Vec<u8>,
+    pointers: Vec<Ipld>,
+}
+
+impl Node {
+    fn load<BS: Blockstore>(store: &BS, cid: &Cid) -> Result<Self, ActorError> {
+        let ipld = store
+            .get_cbor::<Ipld>(cid)
+            .with_context_code(ExitCode::USR_ILLEGAL_STATE, || {
+                format!("failed to load HAMT node {cid}")
+            })?
+            .ok_or_else(|| ActorError::illegal_state(format!("HAMT node {cid} not found")))?;
+        match ipld {
+            Ipld::List(fields) => match <[Ipld; 2]>::try_from(fields) {
+                Ok([Ipld::Bytes(bitfield), Ipld::List(pointers)]) => {
+                    Ok(Self { bitfield, pointers })
+                }
+                _ => Err(ActorError::illegal_state(format!(
+                    "invalid HAMT node {cid}"
+                ))),
+            },
+            _ => Err(ActorError::illegal_state(format!(
+                "invalid HAMT node {cid}"
+            ))),
+        }
+    }
+
+    /// Returns the pointer in the given slot, if any.
+    /// Pointers are stored densely, in the order of the bits set in the bitfield.
+    fn pointer(&self, slot: u32) -> Option<&Ipld> {
+        if !self.is_set(slot) {
+            return None;
+        }
+        let position = (0..slot).filter(|i| self.is_set(*i)).count();
+        self.pointers.get(position)
+    }
+
+    /// The bitfield is encoded as a big-endian integer with leading zero bytes removed.
+    fn is_set(&self, slot: u32) -> bool {
+        let byte = (slot / 8) as usize;
+        if byte >= self.bitfield.len() {
+            return false;
+        }
+        self.bitfield[self.bitfield.len() - 1 - byte] & (1 << (slot % 8)) != 0
+    }
+}
+
+fn diff_nodes<BS, K, V>(
+    store: &BS,
+    slots: u32,
+    old: &Cid,
+    new: &Cid,
+    changes: &mut Vec<Change<K, V>>,
+) -> Result<(), ActorError>
+where
+    BS: Blockstore,
+    K: MapKey,
+    V: DeserializeOwned,
+{
+    if old == new {
+        return Ok(());
+    }
+    let old = Node::load(store, old)?;
+    let new = Node::load(store, new)?;
+    for slot in 0..slots {
+        match (old.pointer(slot), new.pointer(slot)) {
+            (None, None) => {}
+            (Some(Ipld::Link(old)), Some(Ipld::Link(new))) => {
+                diff_nodes(store, slots, old, new, changes)?;
+            }
+            (old, new) if old == new => {}
+            (old, new) => {
+                let mut old_entries = BTreeMap::new();
+                let mut new_entries = BTreeMap::new();
+                if let Some(old) = old {
+                    collect_entries(store, old, &mut old_entries)?;
+                }
+                if let Some(new) = new {
+                    collect_entries(store, new, &mut new_entries)?;
+                }
+                diff_entries(old_entries, new_entries, changes)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Collects the raw entries of a pointer, loading any linked nodes.
+fn collect_entries<BS: Blockstore>(
+    store: &BS,
+    pointer: &Ipld,
+    entries: &mut BTreeMap<Vec<u8>, Ipld>,
+) -> Result<(), ActorError> {
+    match pointer {
+        Ipld::Link(cid) => {
+            let node = Node::load(store, cid)?;
+            for pointer in &node.pointers {
+                collect_entries(store, pointer, entries)?;
+            }
+        }
+        Ipld::List(bucket) => {
+            for entry in bucket {
+                match entry {
+                    Ipld::List(pair) => match pair.as_slice() {
+                        [Ipld::Bytes(key), value] => {
+                            entries.insert(key.clone(), value.clone());
+                        }
+                        _ => return Err(ActorError::illegal_state("invalid HAMT entry".into())),
+                    },
+                    _ => return Err(ActorError::illegal_state("invalid HAMT entry".into())),
+                }
+            }
+        }
+        _ => return Err(ActorError::illegal_state("invalid HAMT pointer".into())),
+    }
+    Ok(())
+}
+
+fn diff_entries<K, V>(
+    mut old: BTreeMap<Vec<u8>, Ipld>,
+    new: BTreeMap<Vec<u8>, Ipld>,
+    changes: &mut Vec<Change<K, V>>,
+) -> Result<(), ActorError>
+where
+    K: MapKey,
+    V: DeserializeOwned,
+{
+    for (key, new_value) in new {
+        match old.remove(&key) {
+            Some(old_value) if old_value == new_value => {}
+            Some(old_value) => changes.push(Change::Modified {
+                key: decode_key(&key)?,
+                old: decode_value(&old_value)?,
+                new: decode_value(&new_value)?,
+            }),
+            None => changes.push(Change::Added(decode_key(&key)?, decode_value(&new_value)?)),
+        }
+    }
+    for (key, old_value) in old {
+        changes.push(Change::Removed(
+            decode_key(&key)?,
+            decode_value(&old_value)?,
+        ));
+    }
+    Ok(())
+}
+
+fn decode_key<K: MapKey>(key: &[u8]) -> Result<K, ActorError> {
+    K::from_bytes(key).context_code(ExitCode::USR_ILLEGAL_STATE, "invalid key")
+}
+
+/// Values are decoded through their CBOR encoding, exactly as the HAMT would decode them.
+fn decode_value<V: DeserializeOwned>(value: &Ipld) -> Result<V, ActorError> {
+    let bytes = fvm_ipld_encoding::to_vec(value)
+        .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to encode HAMT value")?;
+    fvm_ipld_encoding::from_slice(&bytes)
+        .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to decode HAMT value")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    #[test]
+    fn test_diff() {
+        let store = MemoryBlockstore::new();
+        let mut hamt = Root::<u64, String>::new(&store, "test")
+            .unwrap()
+            .hamt(&store, 0)
+            .unwrap();
+        // Enough entries for the trie to have more than one level
+        for i in 0..200 {
+            hamt.set(&i, i.to_string()).unwrap();
+        }
+        let old = hamt.flush().unwrap();
+        assert!(diff(&store, &old, &old).unwrap().is_empty());
+
+        hamt.set(&500, "500".into()).unwrap();
+        hamt.delete(&10).unwrap();
+        hamt.set(&20, "twenty".into()).unwrap();
+        let new = hamt.flush().unwrap();
+
+        let mut changes = diff(&store, &old, &new).unwrap();
+        changes.sort_by_key(|change| match change {
+            Change::Added(key, _) | Change::Removed(key, _) => *key,
+            Change::Modified { key, .. } => *key,
+        });
+        assert_eq!(
+            changes,
+            vec![
+                Change::Removed(10, "10".into()),
+                Change::Modified {
+                    key: 20,
+                    old: "20".into(),
+                    new: "twenty".into()
+                },
+                Change::Added(500, "500".into()),
+            ]
+        );
+
+        // The reverse diff swaps additions and removals
+        let changes = diff(&store, &new, &old).unwrap();
+        assert_eq!(changes.len(), 3);
+        assert!(changes.contains(&Change::Added(10, "10".into())));
+        assert!(changes.contains(&Change::Removed(500, "500".into())));
+    }
+}
@@ -8,16 +8,54 @@ use std::marker::PhantomData;
 
 use cid::Cid;
 use fil_actors_runtime::ActorError;
+use fil_actors_runtime::AsActorError;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
-use fvm_ipld_hamt::{BytesKey, Iter};
-use serde::de::DeserializeOwned;
-use serde::Serialize;
+use fvm_ipld_hamt::{BytesKey, HashAlgorithm, Iter};
+use fvm_shared::error::ExitCode;
+use serde::de::{self, DeserializeOwned, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use super::core::{Map, MapKey, DEFAULT_HAMT_CONFIG};
+use super::cached::{CachedHamt, DEFAULT_READ_CACHE_CAPACITY};
+use super::core::{Config, Map, MapIter, MapKey, DEFAULT_HAMT_CONFIG};
 use crate::Hasher;
 
-#[derive(Clone, PartialEq, Debug, Serialize_tuple, Deserialize_tuple)]
+/// The parameters of a HAMT, persisted with its [`Root`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct RootConfig {
+    /// The number of hash bits consumed at each level, i.e., each node has `2^bit_width` slots.
+    pub bit_width: u32,
+    /// The depth above which nodes only hold links, which keeps the top of large maps small.
+    pub min_data_depth: u32,
+    /// The maximum number of entries in a bucket before it's split into a new node.
+    pub max_array_width: u32,
+}
+
+impl Default for RootConfig {
+    fn default() -> Self {
+        Self {
+            bit_width: DEFAULT_HAMT_CONFIG.bit_width,
+            min_data_depth: DEFAULT_HAMT_CONFIG.min_data_depth,
+            max_array_width: DEFAULT_HAMT_CONFIG.max_array_width as u32,
+        }
+    }
+}
+
+impl From<RootConfig> for Config {
+    fn from(config: RootConfig) -> Self {
+        Config {
+            bit_width: config.bit_width,
+            min_data_depth: config.min_data_depth,
+            max_array_width: config.max_array_width as usize,
+        }
+    }
+}
+
+/// The root of a HAMT.
+///
+/// Roots with the default config are encoded as `[cid, name]`, as they were before the
+/// config was persisted, and roots with any other config as `[cid, name, config]`.
+#[derive(Clone, PartialEq, Debug)]
 pub struct Root<K, V>
 where
     K: MapKey + Display,
@@ -25,12 +63,60 @@ where
 {
     cid: Cid,
     name: String,
-    #[serde(skip)]
+    config: RootConfig,
     key_type: PhantomData<K>,
-    #[serde(skip)]
     value_type: PhantomData<V>,
 }
 
+impl<K, V> Serialize for Root<K, V>
+where
+    K: MapKey + Display,
+    V: DeserializeOwned + Serialize + PartialEq + Clone,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.config == RootConfig::default() {
+            (&self.cid, &self.name).serialize(serializer)
+        } else {
+            (&self.cid, &self.name, &self.config).serialize(serializer)
+        }
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for Root<K, V>
+where
+    K: MapKey + Display,
+    V: DeserializeOwned + Serialize + PartialEq + Clone,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RootVisitor<K, V>(PhantomData<(K, V)>);
+
+        impl<'de, K, V> Visitor<'de> for RootVisitor<K, V>
+        where
+            K: MapKey + Display,
+            V: DeserializeOwned + Serialize + PartialEq + Clone,
+        {
+            type Value = Root<K, V>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a HAMT root tuple")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let cid = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let name = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let config = seq.next_element()?.unwrap_or_default();
+                Ok(Root::from_cid_with_config(cid, name, config))
+            }
+        }
+
+        deserializer.deserialize_seq(RootVisitor(PhantomData))
+    }
+}
+
 impl<K, V> Root<K, V>
 where
     K: MapKey + Display,
@@ -40,17 +126,43 @@ where
         Hamt::<BS, K, V>::flush_empty(store, name.to_owned())
     }
 
+    /// Creates an empty HAMT tuned for the expected size of the collection,
+    /// e.g., a narrower `bit_width` for small maps.
+    pub fn new_with_config<BS: Blockstore>(
+        store: BS,
+        name: &str,
+        config: RootConfig,
+    ) -> Result<Self, ActorError> {
+        let cid = Map::<BS, K, V>::flush_empty(store, config.into())?;
+        Ok(Self::from_cid_with_config(cid, name.to_owned(), config))
+    }
+
     pub fn from_cid(cid: Cid, name: String) -> Self {
+        Self::from_cid_with_config(cid, name, RootConfig::default())
+    }
+
+    pub fn from_cid_with_config(cid: Cid, name: String, config: RootConfig) -> Self {
         Self {
             cid,
             name,
+            config,
             key_type: Default::default(),
             value_type: Default::default(),
         }
     }
 
     pub fn hamt<BS: Blockstore>(&self, store: BS, size: u64) -> Result<Hamt<BS, K, V>, ActorError> {
-        Hamt::load(store, &self.cid, self.name.clone(), size)
+        Hamt::load(store, &self.cid, self.name.clone(), self.config, size)
+    }
+
+    /// Loads the HAMT behind a write-back cache, see [`CachedHamt`].
+    pub fn cached_hamt<BS: Blockstore>(
+        &self,
+        store: BS,
+        size: u64,
+    ) -> Result<CachedHamt<BS, K, V>, ActorError> {
+        let hamt = self.hamt(store, size)?;
+        Ok(CachedHamt::new(hamt, DEFAULT_READ_CACHE_CAPACITY))
     }
 
     pub fn cid(&self) -> &Cid {
@@ -60,6 +172,10 @@ where
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    pub fn config(&self) -> RootConfig {
+        self.config
+    }
 }
 
 pub struct Hamt<BS, K, V>
@@ -69,6 +185,7 @@ where
     V: DeserializeOwned + Serialize + PartialEq + Clone,
 {
     map: Map<BS, K, V>,
+    config: RootConfig,
     size: u64,
 }
 
@@ -88,9 +205,19 @@ where
     K: MapKey + Display,
     V: DeserializeOwned + Serialize + PartialEq + Clone,
 {
-    fn load(store: BS, root: &Cid, name: String, size: u64) -> Result<Self, ActorError> {
-        let map = Map::<BS, K, V>::load(store, root, DEFAULT_HAMT_CONFIG, name)?;
-        Ok(Self { map, size })
+    fn load(
+        store: BS,
+        root: &Cid,
+        name: String,
+        config: RootConfig,
+        size: u64,
+    ) -> Result<Self, ActorError> {
+        let map = Map::<BS, K, V>::load(store, root, config.into(), name)?;
+        Ok(Self { map, config, size })
+    }
+
+    fn root(&self, cid: Cid) -> Root<K, V> {
+        Root::from_cid_with_config(cid, self.map.name(), self.config)
     }
 
     pub fn get(&self, key: &K) -> Result<Option<V>, ActorError> {
@@ -116,7 +243,7 @@ where
     pub fn set_and_flush(&mut self, key: &K, value: V) -> Result<Root<K, V>, ActorError> {
         self.set(key, value)?;
         let cid = self.map.flush()?;
-        Ok(Root::from_cid(cid, self.map.name()))
+        Ok(self.root(cid))
     }
 
     pub fn set_and_flush_tracked(
@@ -163,7 +290,7 @@ where
     pub fn delete_and_flush(&mut self, key: &K) -> Result<(Root<K, V>, Option<V>), ActorError> {
         let deleted = self.delete(key)?;
         let cid = self.map.flush()?;
-        Ok((Root::from_cid(cid, self.map.name()), deleted))
+        Ok((self.root(cid), deleted))
     }
 
     pub fn delete_and_flush_tracked(
@@ -182,7 +309,7 @@ where
 
     pub fn flush(&mut self) -> Result<Root<K, V>, ActorError> {
         let cid = self.map.flush()?;
-        Ok(Root::from_cid(cid, self.map.name()))
+        Ok(self.root(cid))
     }
 
     pub fn flush_empty(store: BS, name: String) -> Result<Root<K, V>, ActorError> {
@@ -202,6 +329,10 @@ where
         self.map.is_empty()
     }
 
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
     pub fn for_each<F>(&self, mut f: F) -> Result<(), ActorError>
     where
         F: FnMut(K, &V) -> Result<(), ActorError>,
@@ -236,4 +367,94 @@ where
     pub fn iter(&self) -> Iter<BS, V, BytesKey, Hasher> {
         self.map.iter()
     }
+
+    /// Returns an iterator over key-value pairs, starting at a key (inclusive)
+    /// or at the first key in iteration order.
+    pub fn iter_from(&self, starting_key: Option<&K>) -> Result<MapIter<BS, K, V>, ActorError> {
+        self.map.iter_from(starting_key)
+    }
+
+    /// Returns an estimate of the number of entries from a key (inclusive) to the end of the
+    /// iteration order, without traversing the map.
+    ///
+    /// Entries are iterated in the order of their key hashes, so the estimate is the share of
+    /// the hash space at or after the key's hash, scaled by the tracked size of the map.
+    pub fn approx_count_from(&self, starting_key: Option<&K>) -> Result<u64, ActorError> {
+        let Some(key) = starting_key else {
+            return Ok(self.size);
+        };
+        let k = BytesKey::from(
+            key.to_bytes()
+                .context_code(ExitCode::USR_ASSERTION_FAILED, "invalid key")?,
+        );
+        let digest = Hasher::hash(&k);
+        let mut prefix = [0u8; 8];
+        prefix.copy_from_slice(&digest[..8]);
+        let remaining = u64::MAX - u64::from_be_bytes(prefix);
+        Ok(((self.size as u128 * remaining as u128) / u64::MAX as u128) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    #[test]
+    fn test_root_config() {
+        let store = MemoryBlockstore::new();
+        let config = RootConfig {
+            bit_width: 3,
+            min_data_depth: 0,
+            max_array_width: 2,
+        };
+        let root = Root::<u64, u64>::new_with_config(&store, "test", config).unwrap();
+        let mut hamt = root.hamt(&store, 0).unwrap();
+        for i in 0..100 {
+            hamt.set(&i, i).unwrap();
+        }
+        let root = hamt.flush().unwrap();
+        assert_eq!(root.config(), config);
+
+        // The config survives a round trip through state
+        let bytes = fvm_ipld_encoding::to_vec(&root).unwrap();
+        let decoded: Root<u64, u64> = fvm_ipld_encoding::from_slice(&bytes).unwrap();
+        assert_eq!(decoded, root);
+        let hamt = decoded.hamt(&store, 100).unwrap();
+        assert_eq!(hamt.get(&42).unwrap(), Some(42));
+
+        // Roots with the default config keep their original encoding
+        let root = Root::<u64, u64>::new(&store, "test").unwrap();
+        let bytes = fvm_ipld_encoding::to_vec(&root).unwrap();
+        assert_eq!(
+            bytes,
+            fvm_ipld_encoding::to_vec(&(root.cid(), root.name())).unwrap()
+        );
+        let decoded: Root<u64, u64> = fvm_ipld_encoding::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.config(), RootConfig::default());
+    }
+
+    #[test]
+    fn test_approx_count_from() {
+        let store = MemoryBlockstore::new();
+        let mut hamt = Root::<u64, u64>::new(&store, "test")
+            .unwrap()
+            .hamt(&store, 0)
+            .unwrap();
+        for i in 0..1000 {
+            hamt.set(&i, i).unwrap();
+        }
+        assert_eq!(hamt.approx_count_from(None).unwrap(), 1000);
+
+        let keys: Vec<u64> = hamt
+            .iter_from(None)
+            .unwrap()
+            .map(|res| res.unwrap().0)
+            .collect();
+        for position in [100, 500, 900] {
+            let actual = (keys.len() - position) as i64;
+            let estimate = hamt.approx_count_from(Some(&keys[position])).unwrap() as i64;
+            assert!((estimate - actual).abs() < 100, "{estimate} vs {actual}");
+        }
+    }
 }
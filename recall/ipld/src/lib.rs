@@ -9,8 +9,12 @@ use crate::hash_algorithm::FvmHashSha256;
 use fvm_ipld_hamt::Sha256;
 
 pub mod amt;
+#[cfg(feature = "car")]
+pub mod car;
 pub mod hamt;
 mod hash_algorithm;
+pub mod ordered_set_map;
+pub mod recording;
 
 #[cfg(feature = "fil-actor")]
 type Hasher = FvmHashSha256;
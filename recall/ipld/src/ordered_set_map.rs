@@ -0,0 +1,386 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::fmt::Display;
+use std::marker::PhantomData;
+
+use fil_actors_runtime::{ActorError, AsActorError};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_shared::error::ExitCode;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::amt;
+use crate::hamt::{self, BytesKey, MapKey};
+
+/// The set of entries stored under a single key, along with the number of entries.
+#[derive(Clone, PartialEq, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct SetRoot<E>
+where
+    E: MapKey + Display + DeserializeOwned + Serialize + PartialEq + Clone,
+{
+    root: hamt::Root<E, ()>,
+    size: u64,
+}
+
+impl<E> SetRoot<E>
+where
+    E: MapKey + Display + DeserializeOwned + Serialize + PartialEq + Clone,
+{
+    fn new<BS: Blockstore>(store: BS, name: &str) -> Result<Self, ActorError> {
+        Ok(Self {
+            root: hamt::Root::new(store, name)?,
+            size: 0,
+        })
+    }
+
+    fn hamt<BS: Blockstore>(&self, store: BS) -> Result<hamt::map::Hamt<BS, E, ()>, ActorError> {
+        self.root.hamt(store, self.size)
+    }
+}
+
+impl<E> From<hamt::map::TrackedFlushResult<E, ()>> for SetRoot<E>
+where
+    E: MapKey + Display + DeserializeOwned + Serialize + PartialEq + Clone,
+{
+    fn from(result: hamt::map::TrackedFlushResult<E, ()>) -> Self {
+        Self {
+            root: result.root,
+            size: result.size,
+        }
+    }
+}
+
+/// The root of an [`OrderedSetMap`].
+///
+/// This is an AMT of HAMT roots, so it can be embedded in actor state like any other AMT root.
+pub type Root<E> = amt::Root<SetRoot<E>>;
+
+/// A key of an [`OrderedSetMap`]. Sets are ordered by the key's index.
+pub trait OrderedKey: Copy + Display {
+    fn to_index(self) -> u64;
+    fn from_index(index: u64) -> Self;
+}
+
+impl OrderedKey for u64 {
+    fn to_index(self) -> u64 {
+        self
+    }
+
+    fn from_index(index: u64) -> Self {
+        index
+    }
+}
+
+/// Signed keys, like chain epochs, are expected to be non-negative.
+impl OrderedKey for i64 {
+    fn to_index(self) -> u64 {
+        self as u64
+    }
+
+    fn from_index(index: u64) -> Self {
+        index as i64
+    }
+}
+
+/// An ordered map from integer keys to sets of entries, e.g., from an epoch to
+/// the things that expire at that epoch.
+///
+/// Keys are stored in an AMT, so iteration starts from the smallest key, and each key's
+/// entries are stored in a HAMT, so any number of entries can share a key.
+/// Keys whose sets become empty are removed.
+pub struct OrderedSetMap<BS, K, E>
+where
+    BS: Blockstore + Clone,
+    K: OrderedKey,
+    E: MapKey + Display + DeserializeOwned + Serialize + PartialEq + Clone,
+{
+    store: BS,
+    sets: amt::vec::Amt<BS, SetRoot<E>>,
+    name: String,
+    key_type: PhantomData<K>,
+}
+
+impl<BS, K, E> OrderedSetMap<BS, K, E>
+where
+    BS: Blockstore + Clone,
+    K: OrderedKey,
+    E: MapKey + Display + DeserializeOwned + Serialize + PartialEq + Clone,
+{
+    /// Creates an empty map and flushes it to the store.
+    pub fn flush_empty(store: BS) -> Result<Root<E>, ActorError> {
+        Root::new(store)
+    }
+
+    /// Loads a map from the store.
+    /// The name adorns the sets created for new keys, which are named `{name}.{key}`.
+    pub fn load(store: BS, root: &Root<E>, name: &str) -> Result<Self, ActorError> {
+        let sets = root.amt(store.clone())?;
+        Ok(Self {
+            store,
+            sets,
+            name: name.to_owned(),
+            key_type: Default::default(),
+        })
+    }
+
+    /// Returns the number of keys with at least one entry.
+    pub fn len(&self) -> u64 {
+        self.sets.count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sets.is_empty()
+    }
+
+    /// Returns whether the set under `key` contains `entry`.
+    pub fn contains(&self, key: K, entry: &E) -> Result<bool, ActorError> {
+        match self.sets.get(key.to_index())? {
+            Some(set) => set.hamt(&self.store)?.contains_key(entry),
+            None => Ok(false),
+        }
+    }
+
+    /// Adds `entry` to the set under `key`.
+    pub fn add(&mut self, key: K, entry: &E) -> Result<(), ActorError> {
+        let index = key.to_index();
+        let set = match self.sets.get(index)? {
+            Some(set) => set,
+            None => SetRoot::<E>::new(&self.store, &format!("{}.{}", self.name, key))?,
+        };
+        let set = set.hamt(&self.store)?.set_and_flush_tracked(entry, ())?;
+        self.sets.set(index, set.into())
+    }
+
+    /// Removes `entry` from the set under `key`, removing the key if its set becomes empty.
+    /// Returns whether the entry was present.
+    pub fn remove(&mut self, key: K, entry: &E) -> Result<bool, ActorError> {
+        let index = key.to_index();
+        let Some(set) = self.sets.get(index)? else {
+            return Ok(false);
+        };
+        let (set, removed) = set.hamt(&self.store)?.delete_and_flush_tracked(entry)?;
+        if removed.is_none() {
+            return Ok(false);
+        }
+        if set.size == 0 {
+            self.sets.delete(index)?;
+        } else {
+            self.sets.set(index, set.into())?;
+        }
+        Ok(true)
+    }
+
+    /// Calls `f` for each entry under keys from `start` (or the smallest key) up to and
+    /// including `end`, in key order.
+    ///
+    /// If `limit` is given, at most that many entries are visited. Returns the number of visited
+    /// entries and the key, and the entry under that key, to resume from, if there are more
+    /// entries up to `end`.
+    /// A partially visited key is resumed from its next unvisited entry, so entries that callers
+    /// leave in place are not visited again until iteration starts over. If that entry has been
+    /// removed in the meantime, the key is resumed from its start.
+    pub fn for_each_up_to<F>(
+        &self,
+        start: Option<(K, Option<E>)>,
+        end: K,
+        limit: Option<u64>,
+        mut f: F,
+    ) -> Result<(u64, Option<(K, Option<E>)>), ActorError>
+    where
+        F: FnMut(K, E) -> Result<(), ActorError>,
+    {
+        let end = end.to_index();
+        let (start, start_entry) = match start {
+            Some((key, entry)) => (Some(key.to_index()), entry),
+            None => (None, None),
+        };
+        let mut remaining = limit;
+        let mut count = 0;
+        let mut next = None;
+        self.sets.for_each_while_ranged(start, None, |index, set| {
+            if index > end {
+                return Ok(false);
+            }
+            let key = K::from_index(index);
+            if remaining == Some(0) {
+                next = Some((key, None));
+                return Ok(false);
+            }
+            let hamt = set.hamt(&self.store)?;
+            let starting_entry = match &start_entry {
+                Some(entry) if Some(index) == start && hamt.contains_key(entry)? => {
+                    Some(BytesKey::from(entry.to_bytes().context_code(
+                        ExitCode::USR_ASSERTION_FAILED,
+                        "invalid entry",
+                    )?))
+                }
+                _ => None,
+            };
+            let (visited, next_entry) = hamt.for_each_ranged(
+                starting_entry.as_ref(),
+                remaining.map(|r| r as usize),
+                |entry, _| {
+                    f(key, entry)?;
+                    Ok(true)
+                },
+            )?;
+            count += visited as u64;
+            remaining = remaining.map(|r| r.saturating_sub(visited as u64));
+            if next_entry.is_some() {
+                next = Some((key, next_entry));
+                return Ok(false);
+            }
+            Ok(true)
+        })?;
+        Ok((count, next))
+    }
+
+    /// Removes all keys below `watermark`, along with their entries.
+    /// Returns the number of removed keys.
+    pub fn remove_below(&mut self, watermark: K) -> Result<u64, ActorError> {
+        let watermark = watermark.to_index();
+        let mut indexes = Vec::new();
+        self.sets.for_each_while_ranged(None, None, |index, _| {
+            if index >= watermark {
+                return Ok(false);
+            }
+            indexes.push(index);
+            Ok(true)
+        })?;
+        for index in &indexes {
+            self.sets.delete(*index)?;
+        }
+        Ok(indexes.len() as u64)
+    }
+
+    /// Flushes the map's contents to the store.
+    pub fn flush(&mut self) -> Result<Root<E>, ActorError> {
+        self.sets.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    fn new_map(store: &MemoryBlockstore) -> OrderedSetMap<&MemoryBlockstore, i64, String> {
+        let root = OrderedSetMap::<_, i64, String>::flush_empty(store).unwrap();
+        OrderedSetMap::load(store, &root, "test").unwrap()
+    }
+
+    #[test]
+    fn test_add_remove() {
+        let store = MemoryBlockstore::new();
+        let mut map = new_map(&store);
+        map.add(10, &"a".into()).unwrap();
+        map.add(10, &"b".into()).unwrap();
+        map.add(20, &"a".into()).unwrap();
+        assert_eq!(map.len(), 2);
+        assert!(map.contains(10, &"b".into()).unwrap());
+        assert!(!map.contains(20, &"b".into()).unwrap());
+
+        assert!(map.remove(10, &"a".into()).unwrap());
+        assert!(!map.remove(10, &"a".into()).unwrap());
+        assert!(!map.remove(30, &"a".into()).unwrap());
+        assert_eq!(map.len(), 2);
+
+        // Removing the last entry removes the key
+        assert!(map.remove(10, &"b".into()).unwrap());
+        assert_eq!(map.len(), 1);
+
+        // Adding an entry twice counts it once
+        map.add(30, &"a".into()).unwrap();
+        map.add(30, &"a".into()).unwrap();
+        assert!(map.remove(30, &"a".into()).unwrap());
+        assert_eq!(map.len(), 1);
+
+        // Changes survive a flush and reload
+        let root = map.flush().unwrap();
+        let map = OrderedSetMap::<_, i64, String>::load(&store, &root, "test").unwrap();
+        assert_eq!(map.len(), 1);
+        assert!(map.contains(20, &"a".into()).unwrap());
+    }
+
+    #[test]
+    fn test_for_each_up_to() {
+        let store = MemoryBlockstore::new();
+        let mut map = new_map(&store);
+        for (key, entry) in [(7, "x"), (2, "a"), (2, "b"), (2, "c"), (4, "d"), (9, "e")] {
+            map.add(key, &entry.into()).unwrap();
+        }
+
+        let mut visited = vec![];
+        let (count, next) = map
+            .for_each_up_to(None, 7, None, |key, entry| {
+                visited.push((key, entry));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(count, 5);
+        assert_eq!(next, None);
+        assert_eq!(
+            visited.iter().map(|(key, _)| *key).collect::<Vec<_>>(),
+            vec![2, 2, 2, 4, 7]
+        );
+
+        // A partially visited key is resumed from its next unvisited entry
+        let mut first = vec![];
+        let (count, next) = map
+            .for_each_up_to(None, 7, Some(2), |_, entry| {
+                first.push(entry);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(count, 2);
+        let (key, entry) = next.unwrap();
+        assert_eq!(key, 2);
+        let entry = entry.unwrap();
+        assert!(!first.contains(&entry));
+
+        // Visited entries are not visited again, even if they are left in place
+        let mut rest = vec![];
+        let (count, next) = map
+            .for_each_up_to(Some((key, Some(entry.clone()))), 9, Some(4), |_, entry| {
+                rest.push(entry);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(count, 4);
+        assert_eq!(next, None);
+        assert_eq!(rest[0], entry);
+        assert!(rest.iter().all(|entry| !first.contains(entry)));
+
+        // A fully visited key resumes from the next one
+        let (count, next) = map
+            .for_each_up_to(Some((2, None)), 9, Some(3), |_, _| Ok(()))
+            .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(next, Some((4, None)));
+
+        // A removed entry resumes its key from the start
+        map.remove(2, &entry).unwrap();
+        let (count, next) = map
+            .for_each_up_to(Some((2, Some(entry))), 2, None, |_, _| Ok(()))
+            .unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn test_remove_below() {
+        let store = MemoryBlockstore::new();
+        let mut map = new_map(&store);
+        for key in [1, 3, 5, 8] {
+            map.add(key, &"a".into()).unwrap();
+            map.add(key, &"b".into()).unwrap();
+        }
+        assert_eq!(map.remove_below(5).unwrap(), 2);
+        assert_eq!(map.len(), 2);
+        assert!(!map.contains(3, &"a".into()).unwrap());
+        assert!(map.contains(5, &"a".into()).unwrap());
+        assert_eq!(map.remove_below(0).unwrap(), 0);
+    }
+}
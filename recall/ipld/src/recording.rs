@@ -0,0 +1,89 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+
+/// A blockstore that records every block read through it, in the order it was read.
+///
+/// Reading a value through a recording store collects the IPLD path from the root to the
+/// value, which can be handed to a client as an inclusion proof.
+/// Writes are passed through and not recorded.
+pub struct RecordingBlockstore<BS: Blockstore> {
+    inner: BS,
+    blocks: RefCell<Vec<(Cid, Vec<u8>)>>,
+}
+
+impl<BS: Blockstore> RecordingBlockstore<BS> {
+    pub fn new(inner: BS) -> Self {
+        Self {
+            inner,
+            blocks: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns the recorded blocks, without duplicates, in the order they were first read.
+    pub fn into_blocks(self) -> Vec<(Cid, Vec<u8>)> {
+        let mut blocks = self.blocks.into_inner();
+        let mut seen = HashSet::new();
+        blocks.retain(|(cid, _)| seen.insert(*cid));
+        blocks
+    }
+}
+
+impl<BS: Blockstore> Blockstore for RecordingBlockstore<BS> {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        let block = self.inner.get(k)?;
+        if let Some(data) = &block {
+            self.blocks.borrow_mut().push((*k, data.clone()));
+        }
+        Ok(block)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.inner.put_keyed(k, block)
+    }
+
+    fn has(&self, k: &Cid) -> anyhow::Result<bool> {
+        self.inner.has(k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hamt::{Map, DEFAULT_HAMT_CONFIG};
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    #[test]
+    fn records_path_to_value() {
+        let bs = MemoryBlockstore::new();
+        let mut m = Map::<_, u64, String>::empty(&bs, DEFAULT_HAMT_CONFIG, "proof".into());
+        for i in 0..1000u64 {
+            m.set(&i, i.to_string()).unwrap();
+        }
+        let root = m.flush().unwrap();
+
+        let recording = RecordingBlockstore::new(&bs);
+        let m = Map::<_, u64, String>::load(&recording, &root, DEFAULT_HAMT_CONFIG, "proof".into())
+            .unwrap();
+        assert_eq!(m.get(&777).unwrap(), Some(&"777".to_string()));
+        drop(m);
+        let blocks = recording.into_blocks();
+
+        // The path starts at the root, and each block is linked from the one before it
+        assert!(blocks.len() > 1);
+        assert_eq!(blocks[0].0, root);
+        for pair in blocks.windows(2) {
+            let link = pair[1].0.to_bytes();
+            assert!(pair[0].1.windows(link.len()).any(|w| w == link.as_slice()));
+        }
+        // Every block matches the store
+        for (cid, data) in &blocks {
+            assert_eq!(&bs.get(cid).unwrap().unwrap(), data);
+        }
+    }
+}
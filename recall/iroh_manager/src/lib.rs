@@ -3,25 +3,97 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::net::ToSocketAddrs;
+use std::time::Duration;
 
 use anyhow::anyhow;
+use ipc_observability::emit;
 use iroh::blobs::hashseq::HashSeq;
-use iroh::blobs::Hash;
+use iroh::blobs::{Hash, HashAndFormat, Tag};
 use iroh::client::blobs::BlobStatus;
 use iroh::client::Iroh;
 use num_traits::Zero;
 
+use crate::observe::IrohReconnected;
+
+pub use crate::pool::IrohPool;
+
+pub mod observe;
+mod pool;
+
+/// Default time to wait for a connection to the Iroh node.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Default time to wait for a single Iroh request.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Default number of connections in an [`IrohPool`].
+pub const DEFAULT_POOL_SIZE: usize = 4;
+/// Default time between health checks of an [`IrohPool`]'s connections.
+pub const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Configuration for connecting to an Iroh node's RPC endpoint.
+#[derive(Clone, Debug)]
+pub struct IrohConfig {
+    /// The RPC address of the Iroh node.
+    pub addr: String,
+    /// How long to wait for a connection before giving up.
+    pub connect_timeout: Duration,
+    /// How long to wait for a single request before giving up.
+    pub request_timeout: Duration,
+    /// The number of connections to keep when used with an [`IrohPool`].
+    pub pool_size: usize,
+    /// How often an [`IrohPool`] checks its connections.
+    pub health_check_interval: Duration,
+}
+
+impl IrohConfig {
+    /// Returns a config for the address with default timeouts.
+    pub fn new(addr: String) -> Self {
+        Self {
+            addr,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            pool_size: DEFAULT_POOL_SIZE,
+            health_check_interval: DEFAULT_HEALTH_CHECK_INTERVAL,
+        }
+    }
+}
+
 /// Helper for managing Iroh connections.
 #[derive(Clone, Debug)]
 pub struct IrohManager {
     addr: Option<String>,
+    connect_timeout: Duration,
+    request_timeout: Duration,
     client: Option<Iroh>,
+    /// Whether a client was connected before, so new connections can be counted as reconnects.
+    connected: bool,
 }
 
 impl IrohManager {
     /// Returns a manager for the address.
     pub fn from_addr(addr: Option<String>) -> IrohManager {
-        Self { addr, client: None }
+        Self {
+            addr,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            client: None,
+            connected: false,
+        }
+    }
+
+    /// Returns a manager for the config.
+    pub fn new(config: IrohConfig) -> IrohManager {
+        Self {
+            addr: Some(config.addr),
+            connect_timeout: config.connect_timeout,
+            request_timeout: config.request_timeout,
+            client: None,
+            connected: false,
+        }
+    }
+
+    /// Returns how long to wait for a single request.
+    pub fn request_timeout(&self) -> Duration {
+        self.request_timeout
     }
 
     /// Returns the Iroh client.
@@ -34,17 +106,42 @@ impl IrohManager {
             let addr = addr.to_socket_addrs()?.next().ok_or(anyhow!(
                 "failed to convert iroh node address to a socket address"
             ))?;
-            match Iroh::connect_addr(addr).await {
-                Ok(client) => {
+            match tokio::time::timeout(self.connect_timeout, Iroh::connect_addr(addr)).await {
+                Ok(Ok(client)) => {
+                    if self.connected {
+                        emit(IrohReconnected {
+                            addr: &addr.to_string(),
+                        });
+                    }
+                    self.connected = true;
                     self.client = Some(client.clone());
                     Ok(client)
                 }
-                Err(e) => Err(e),
+                Ok(Err(e)) => Err(e),
+                Err(_) => Err(anyhow!(
+                    "timed out connecting to iroh node at {} after {:?}",
+                    addr,
+                    self.connect_timeout
+                )),
             }
         } else {
             Err(anyhow!("iroh node address is not configured"))
         }
     }
+
+    /// Checks that the cached client is still connected, and drops it if it isn't,
+    /// so the next call to [`IrohManager::client`] reconnects.
+    pub async fn check_connection(&mut self) {
+        let Some(client) = self.client.clone() else {
+            return;
+        };
+        let alive = tokio::time::timeout(self.request_timeout, client.net().node_id())
+            .await
+            .is_ok_and(|res| res.is_ok());
+        if !alive {
+            self.client = None;
+        }
+    }
 }
 
 /// Returns the user blob hash and size from the hash sequence.
@@ -71,6 +168,81 @@ pub async fn get_blob_hash_and_size(
         return Err(anyhow!("hash sequence object {} has zero size", seq_hash));
     }
 
+    // Get the user blob status at index 0 (it needs to be available)
+    let blob_hash = get_user_blob_hash(iroh, seq_hash).await?;
+    let status = iroh
+        .blobs()
+        .status(blob_hash)
+        .await
+        .map_err(|e| anyhow!("failed to read object: {} {}", blob_hash, e))?;
+
+    // Finally, get the size from the status
+    let BlobStatus::Complete { size } = status else {
+        return Err(anyhow!("object {} is not available", blob_hash));
+    };
+
+    Ok((blob_hash, size))
+}
+
+/// Returns the user blob size if both the hash sequence and the user blob are available
+/// locally, or `None` if either is missing or incomplete.
+pub async fn stat_blob(iroh: &Iroh, seq_hash: Hash) -> Result<Option<u64>, anyhow::Error> {
+    let status = iroh.blobs().status(seq_hash).await.map_err(|e| {
+        anyhow!(
+            "failed to get status for hash sequence object: {} {}",
+            seq_hash,
+            e
+        )
+    })?;
+    match status {
+        BlobStatus::Complete { size } if !size.is_zero() => {}
+        _ => return Ok(None),
+    }
+
+    let blob_hash = get_user_blob_hash(iroh, seq_hash).await?;
+    let status = iroh
+        .blobs()
+        .status(blob_hash)
+        .await
+        .map_err(|e| anyhow!("failed to read object: {} {}", blob_hash, e))?;
+    match status {
+        BlobStatus::Complete { size } => Ok(Some(size)),
+        _ => Ok(None),
+    }
+}
+
+/// The prefix of tags used to pin hash sequences.
+pub const PIN_TAG_PREFIX: &str = "pinned-seq-";
+
+/// Returns the tag used to pin a hash sequence.
+pub fn pin_tag(seq_hash: Hash) -> Tag {
+    Tag(format!("{PIN_TAG_PREFIX}{seq_hash}").into())
+}
+
+/// Pins a hash sequence and everything it references, so Iroh's GC never removes it.
+///
+/// The pin is a named tag, which can be set before the content is available locally.
+pub async fn pin_blob(iroh: &Iroh, seq_hash: Hash) -> Result<(), anyhow::Error> {
+    tag_blob(iroh, seq_hash, pin_tag(seq_hash)).await
+}
+
+/// Sets a named tag on a hash sequence and everything it references.
+/// The tag can be set before the content is available locally.
+pub async fn tag_blob(iroh: &Iroh, seq_hash: Hash, tag: Tag) -> Result<(), anyhow::Error> {
+    let batch = iroh.blobs().batch().await?;
+    let temp_tag = batch.temp_tag(HashAndFormat::hash_seq(seq_hash)).await?;
+    batch.persist_to(temp_tag, tag).await?;
+    Ok(())
+}
+
+/// Removes the pin from a hash sequence.
+/// The content is left to Iroh's GC unless other tags still reference it.
+pub async fn unpin_blob(iroh: &Iroh, seq_hash: Hash) -> Result<(), anyhow::Error> {
+    iroh.tags().delete(pin_tag(seq_hash)).await
+}
+
+/// Reads the hash sequence and returns the user blob hash at index 0.
+async fn get_user_blob_hash(iroh: &Iroh, seq_hash: Hash) -> Result<Hash, anyhow::Error> {
     // Read the bytes and create a hash sequence
     let res = iroh
         .blobs()
@@ -80,23 +252,11 @@ pub async fn get_blob_hash_and_size(
     let hash_seq = HashSeq::try_from(res)
         .map_err(|e| anyhow!("failed to parse hash sequence object: {} {}", seq_hash, e))?;
 
-    // Get the user blob status at index 0 (it needs to be available)
     let blob_hash = hash_seq.get(0).ok_or_else(|| {
         anyhow!(
             "failed to get hash with index 0 from hash sequence object: {}",
             seq_hash
         )
     })?;
-    let status = iroh
-        .blobs()
-        .status(blob_hash)
-        .await
-        .map_err(|e| anyhow!("failed to read object: {} {}", blob_hash, e))?;
-
-    // Finally, get the size from the status
-    let BlobStatus::Complete { size } = status else {
-        return Err(anyhow!("object {} is not available", blob_hash));
-    };
-
-    Ok((blob_hash, size))
+    Ok(blob_hash)
 }
@@ -0,0 +1,45 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use ipc_observability::{
+    impl_traceable, impl_traceables, lazy_static, register_metrics, Recordable, TraceLevel,
+    Traceable,
+};
+use prometheus::{register_int_counter, IntCounter, Registry};
+
+register_metrics! {
+    IROH_RECONNECTS: IntCounter
+        = register_int_counter!("iroh_reconnects", "Iroh client reconnects after a lost connection");
+}
+
+impl_traceables!(TraceLevel::Info, "IrohManager", IrohReconnected<'a>);
+
+#[derive(Debug)]
+pub struct IrohReconnected<'a> {
+    pub addr: &'a str,
+}
+
+impl Recordable for IrohReconnected<'_> {
+    fn record_metrics(&self) {
+        IROH_RECONNECTS.inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ipc_observability::emit;
+
+    #[test]
+    fn test_metrics() {
+        let registry = Registry::new();
+        register_metrics(&registry).unwrap();
+    }
+
+    #[test]
+    fn test_emit() {
+        emit(IrohReconnected {
+            addr: "127.0.0.1:4919",
+        });
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use iroh::client::Iroh;
+use tokio::sync::Mutex;
+use tokio::time::MissedTickBehavior;
+
+use crate::{IrohConfig, IrohManager};
+
+/// A fixed-size pool of Iroh connections.
+///
+/// Requests are spread over the connections round-robin, so concurrent requests don't
+/// serialize on a single RPC connection. Dead connections are dropped by health checks,
+/// or after a failed request, and replaced on their next use.
+#[derive(Debug)]
+pub struct IrohPool {
+    connections: Vec<Mutex<IrohManager>>,
+    next: AtomicUsize,
+    request_timeout: Duration,
+    health_check_interval: Duration,
+}
+
+impl IrohPool {
+    /// Returns a pool for the config. Connections are made lazily.
+    pub fn new(config: IrohConfig) -> Self {
+        let size = config.pool_size.max(1);
+        Self {
+            connections: (0..size)
+                .map(|_| Mutex::new(IrohManager::new(config.clone())))
+                .collect(),
+            next: AtomicUsize::new(0),
+            request_timeout: config.request_timeout,
+            health_check_interval: config.health_check_interval,
+        }
+    }
+
+    /// Returns the number of connections in the pool.
+    pub fn size(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// Returns how long to wait for a single request.
+    pub fn request_timeout(&self) -> Duration {
+        self.request_timeout
+    }
+
+    /// Returns a client from the next connection and the connection's index,
+    /// connecting first if needed.
+    pub async fn client(&self) -> anyhow::Result<(usize, Iroh)> {
+        let index = self.next_index();
+        let client = self.connections[index].lock().await.client().await?;
+        Ok((index, client))
+    }
+
+    /// Checks the connection at `index`, dropping it if it's dead so it's replaced on next use.
+    pub async fn check_connection(&self, index: usize) {
+        if let Some(connection) = self.connections.get(index) {
+            connection.lock().await.check_connection().await;
+        }
+    }
+
+    /// Checks all connections in the pool every health check interval, until the task is dropped.
+    pub async fn run_health_checks(self: Arc<Self>) {
+        let mut ticker = tokio::time::interval(self.health_check_interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            for index in 0..self.size() {
+                self.check_connection(index).await;
+            }
+        }
+    }
+
+    fn next_index(&self) -> usize {
+        self.next.fetch_add(1, Ordering::Relaxed) % self.connections.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_robin() {
+        let mut config = IrohConfig::new("127.0.0.1:4919".into());
+        config.pool_size = 3;
+        let pool = IrohPool::new(config);
+        assert_eq!(pool.size(), 3);
+        let indexes: Vec<usize> = (0..6).map(|_| pool.next_index()).collect();
+        assert_eq!(indexes, vec![0, 1, 2, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_min_size() {
+        let mut config = IrohConfig::new("127.0.0.1:4919".into());
+        config.pool_size = 0;
+        let pool = IrohPool::new(config);
+        assert_eq!(pool.size(), 1);
+        assert_eq!(pool.next_index(), 0);
+    }
+}
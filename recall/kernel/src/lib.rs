@@ -75,6 +75,41 @@ where
             recall_syscalls::HASHRM_SYSCALL_FUNCTION_NAME,
             recall_syscalls::hash_rm,
         )?;
+        linker.link_syscall(
+            recall_syscalls::MODULE_NAME,
+            recall_syscalls::HASHRMMANY_SYSCALL_FUNCTION_NAME,
+            recall_syscalls::hash_rm_many,
+        )?;
+        linker.link_syscall(
+            recall_syscalls::MODULE_NAME,
+            recall_syscalls::HASHGET_SYSCALL_FUNCTION_NAME,
+            recall_syscalls::hash_get,
+        )?;
+        linker.link_syscall(
+            recall_syscalls::MODULE_NAME,
+            recall_syscalls::HASHSTAT_SYSCALL_FUNCTION_NAME,
+            recall_syscalls::hash_stat,
+        )?;
+        linker.link_syscall(
+            recall_syscalls::MODULE_NAME,
+            recall_syscalls::HASHPIN_SYSCALL_FUNCTION_NAME,
+            recall_syscalls::hash_pin,
+        )?;
+        linker.link_syscall(
+            recall_syscalls::MODULE_NAME,
+            recall_syscalls::HASHUNPIN_SYSCALL_FUNCTION_NAME,
+            recall_syscalls::hash_unpin,
+        )?;
+        linker.link_syscall(
+            recall_syscalls::MODULE_NAME,
+            recall_syscalls::HASHTAG_SYSCALL_FUNCTION_NAME,
+            recall_syscalls::hash_tag,
+        )?;
+        linker.link_syscall(
+            recall_syscalls::MODULE_NAME,
+            recall_syscalls::HASHUNTAG_SYSCALL_FUNCTION_NAME,
+            recall_syscalls::hash_untag,
+        )?;
 
         Ok(())
     }
@@ -0,0 +1,259 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use futures::{stream, StreamExt};
+use ipc_observability::emit;
+use iroh::blobs::Hash;
+use rocksdb::{IteratorMode, Options, DB};
+use tokio::sync::Notify;
+
+use crate::observe::DeletionQueueDepth;
+use crate::{delete_hash, MAX_CONCURRENT_DELETES};
+
+/// The maximum number of queued deletes attempted in one pass of the worker.
+const MAX_DELETES_PER_PASS: usize = 1000;
+/// How long the worker sleeps between passes when it isn't woken by new deletes.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// The delay before the first retry of a failed delete.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// The maximum delay between retries of a failed delete.
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// A delete waiting in the queue.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Entry {
+    /// Number of failed attempts so far.
+    attempts: u32,
+    /// Unix time in milliseconds before which the delete shouldn't be retried.
+    retry_at: u64,
+}
+
+impl Entry {
+    fn to_bytes(self) -> [u8; 12] {
+        let mut bytes = [0u8; 12];
+        bytes[..4].copy_from_slice(&self.attempts.to_be_bytes());
+        bytes[4..].copy_from_slice(&self.retry_at.to_be_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 12 {
+            return None;
+        }
+        Some(Self {
+            attempts: u32::from_be_bytes(bytes[..4].try_into().ok()?),
+            retry_at: u64::from_be_bytes(bytes[4..].try_into().ok()?),
+        })
+    }
+
+    /// Returns the entry to store after another failed attempt at `now`.
+    fn failed(self, now: u64) -> Self {
+        let backoff = INITIAL_BACKOFF
+            .saturating_mul(1 << self.attempts.min(16))
+            .min(MAX_BACKOFF);
+        Self {
+            attempts: self.attempts.saturating_add(1),
+            retry_at: now.saturating_add(backoff.as_millis() as u64),
+        }
+    }
+}
+
+/// A persistent queue of Iroh deletes.
+///
+/// Deletes are written to a local RocksDB database before they are attempted, and a background
+/// worker retries failed deletes with exponential backoff until they succeed, so failures don't
+/// leak storage on the Iroh node and queued deletes survive restarts.
+pub struct DeletionQueue {
+    db: DB,
+    depth: AtomicU64,
+    notify: Notify,
+}
+
+impl DeletionQueue {
+    /// Opens or creates the queue database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        let db = DB::open(&opts, path).context("failed to open deletion queue database")?;
+        let depth = db.iterator(IteratorMode::Start).count() as u64;
+        Ok(Self {
+            db,
+            depth: AtomicU64::new(depth),
+            notify: Notify::new(),
+        })
+    }
+
+    /// Returns the number of queued deletes.
+    pub fn len(&self) -> u64 {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Queues deletes for the hashes and wakes the worker.
+    /// Hashes that are already queued keep their retry schedule.
+    pub fn push(&self, hashes: &[Hash]) -> anyhow::Result<()> {
+        let entry = Entry {
+            attempts: 0,
+            retry_at: 0,
+        };
+        for hash in hashes {
+            if self.db.get_pinned(hash.as_bytes())?.is_none() {
+                self.db.put(hash.as_bytes(), entry.to_bytes())?;
+                self.depth.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        emit(DeletionQueueDepth(self.len()));
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    /// Returns up to `limit` queued deletes that are due at `now`.
+    fn due(&self, now: u64, limit: usize) -> anyhow::Result<Vec<(Hash, Entry)>> {
+        let mut due = Vec::new();
+        for item in self.db.iterator(IteratorMode::Start) {
+            let (key, value) = item?;
+            let Ok(key) = <[u8; 32]>::try_from(key.as_ref()) else {
+                continue;
+            };
+            let Some(entry) = Entry::from_bytes(&value) else {
+                continue;
+            };
+            if entry.retry_at <= now {
+                due.push((Hash::from_bytes(key), entry));
+                if due.len() >= limit {
+                    break;
+                }
+            }
+        }
+        Ok(due)
+    }
+
+    fn remove(&self, hash: &Hash) -> anyhow::Result<()> {
+        self.db.delete(hash.as_bytes())?;
+        self.depth.fetch_sub(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn reschedule(&self, hash: &Hash, entry: Entry) -> anyhow::Result<()> {
+        self.db.put(hash.as_bytes(), entry.to_bytes())?;
+        Ok(())
+    }
+
+    /// Runs the worker that attempts due deletes, forever.
+    pub async fn run(self: Arc<Self>) {
+        loop {
+            if let Err(e) = self.process_due().await {
+                tracing::error!(error = e.to_string(), "failed to process deletion queue");
+            }
+            emit(DeletionQueueDepth(self.len()));
+            tokio::select! {
+                _ = self.notify.notified() => {}
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            }
+        }
+    }
+
+    async fn process_due(&self) -> anyhow::Result<()> {
+        let due = self.due(unix_millis(), MAX_DELETES_PER_PASS)?;
+        if due.is_empty() {
+            return Ok(());
+        }
+        let results: Vec<_> = stream::iter(due)
+            .map(|(hash, entry)| async move { (hash, entry, delete_hash(hash).await) })
+            .buffer_unordered(MAX_CONCURRENT_DELETES)
+            .collect()
+            .await;
+        let now = unix_millis();
+        for (hash, entry, deleted) in results {
+            if deleted {
+                self.remove(&hash)?;
+            } else {
+                self.reschedule(&hash, entry.failed(now))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_roundtrip() {
+        let entry = Entry {
+            attempts: 3,
+            retry_at: 1_738_787_063_000,
+        };
+        assert_eq!(Entry::from_bytes(&entry.to_bytes()), Some(entry));
+        assert_eq!(Entry::from_bytes(&[0u8; 4]), None);
+    }
+
+    #[test]
+    fn test_entry_backoff() {
+        let entry = Entry {
+            attempts: 0,
+            retry_at: 0,
+        };
+        let entry = entry.failed(1000);
+        assert_eq!(entry.attempts, 1);
+        assert_eq!(entry.retry_at, 2000);
+        let entry = entry.failed(1000);
+        assert_eq!(entry.retry_at, 3000);
+
+        // The backoff is capped
+        let entry = Entry {
+            attempts: 30,
+            retry_at: 0,
+        }
+        .failed(0);
+        assert_eq!(entry.retry_at, MAX_BACKOFF.as_millis() as u64);
+    }
+
+    #[test]
+    fn test_push_and_due() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = DeletionQueue::open(dir.path()).unwrap();
+        let hashes: Vec<Hash> = (0u8..3).map(|i| Hash::new([i])).collect();
+
+        queue.push(&hashes).unwrap();
+        // Pushing again doesn't duplicate
+        queue.push(&hashes[..1]).unwrap();
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.due(0, 10).unwrap().len(), 3);
+        assert_eq!(queue.due(0, 2).unwrap().len(), 2);
+
+        let entry = Entry {
+            attempts: 0,
+            retry_at: 0,
+        };
+        queue.reschedule(&hashes[0], entry.failed(0)).unwrap();
+        let due = queue.due(0, 10).unwrap();
+        assert_eq!(due.len(), 2);
+        assert!(!due.iter().any(|(hash, _)| hash == &hashes[0]));
+
+        queue.remove(&hashes[1]).unwrap();
+        assert_eq!(queue.len(), 2);
+        drop(queue);
+
+        // Queued deletes survive reopening
+        let queue = DeletionQueue::open(dir.path()).unwrap();
+        assert_eq!(queue.len(), 2);
+    }
+}
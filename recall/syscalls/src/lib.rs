@@ -2,25 +2,95 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::future::Future;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Instant;
 
-use fvm::kernel::{ExecutionError, Result, SyscallError};
+use anyhow::anyhow;
+use futures::{stream, StreamExt};
+use fvm::gas::Gas;
+use fvm::kernel::{ExecutionError, Kernel, MessageOps, Result, SyscallError};
 use fvm::syscalls::Context;
 use fvm_shared::error::ErrorNumber;
-use iroh::blobs::Hash;
-use iroh_manager::IrohManager;
-use once_cell::sync::Lazy;
+use fvm_shared::ActorID;
+use ipc_observability::emit;
+use iroh::blobs::{Hash, Tag};
+use iroh::client::blobs::ReadAtLen;
+use iroh::client::Iroh;
+use iroh_manager::{
+    get_blob_hash_and_size, pin_blob, stat_blob, tag_blob, unpin_blob, IrohConfig, IrohPool,
+};
+use once_cell::sync::{Lazy, OnceCell};
 use recall_kernel_ops::RecallOps;
-use tokio::{spawn, sync::Mutex};
+use tokio::{runtime::Handle, spawn, sync::Semaphore, task::block_in_place};
+
+pub use crate::deletion_queue::DeletionQueue;
+use crate::observe::{IrohBytesRead, IrohDeleted, IrohRequested};
+
+mod deletion_queue;
+pub mod observe;
+pub mod sweeper;
 
 pub const MODULE_NAME: &str = "recall";
 pub const HASHRM_SYSCALL_FUNCTION_NAME: &str = "hash_rm";
+pub const HASHRMMANY_SYSCALL_FUNCTION_NAME: &str = "hash_rm_many";
+pub const HASHGET_SYSCALL_FUNCTION_NAME: &str = "hash_get";
+pub const HASHSTAT_SYSCALL_FUNCTION_NAME: &str = "hash_stat";
+pub const HASHPIN_SYSCALL_FUNCTION_NAME: &str = "hash_pin";
+pub const HASHUNPIN_SYSCALL_FUNCTION_NAME: &str = "hash_unpin";
+pub const HASHTAG_SYSCALL_FUNCTION_NAME: &str = "hash_tag";
+pub const HASHUNTAG_SYSCALL_FUNCTION_NAME: &str = "hash_untag";
+
+/// The prefix of tags set by actors with `hash_tag`.
+pub const ACTOR_TAG_PREFIX: &str = "actor-";
 
-const ENV_IROH_ADDR: &str = "IROH_RPC_ADDR";
-static IROH_INSTANCE: Lazy<Arc<Mutex<IrohManager>>> = Lazy::new(|| {
-    let iroh_addr = std::env::var(ENV_IROH_ADDR).ok();
-    Arc::new(Mutex::new(IrohManager::from_addr(iroh_addr)))
-});
+/// The maximum number of bytes that can be read with a single `hash_get`.
+pub const MAX_HASH_GET_LEN: u32 = 64 * 1024;
+/// Base gas charged for every `hash_get`.
+const HASH_GET_BASE_GAS: u64 = 500_000;
+/// Gas charged for every requested byte of a `hash_get`.
+const HASH_GET_GAS_PER_BYTE: u64 = 100;
+/// Gas charged for every `hash_stat`.
+const HASH_STAT_GAS: u64 = 300_000;
+/// The maximum number of hashes that can be deleted with a single `hash_rm_many`.
+pub const MAX_HASH_RM_MANY_COUNT: u32 = 10_000;
+/// The prefix of tags set on blobs downloaded by the resolver.
+const STORED_TAG_PREFIX: &str = "stored-seq-";
+/// The maximum number of Iroh deletes in flight across all `hash_rm` and `hash_rm_many` calls.
+const MAX_CONCURRENT_DELETES: usize = 32;
+
+static IROH_INSTANCE: OnceCell<Arc<IrohPool>> = OnceCell::new();
+static DELETE_PERMITS: Lazy<Arc<Semaphore>> =
+    Lazy::new(|| Arc::new(Semaphore::new(MAX_CONCURRENT_DELETES)));
+static DELETION_QUEUE: OnceCell<Arc<DeletionQueue>> = OnceCell::new();
+
+/// Configures the Iroh node used by the syscalls.
+///
+/// This must be called once by the node before executing any blocks. Until then, syscalls that
+/// need Iroh fail, and deletes are queued or dropped.
+/// Requests are spread over a pool of connections, which are health checked in the background.
+pub fn init_iroh(config: IrohConfig) -> anyhow::Result<()> {
+    let pool = Arc::new(IrohPool::new(config));
+    IROH_INSTANCE
+        .set(pool.clone())
+        .map_err(|_| anyhow!("iroh is already configured"))?;
+    spawn(pool.run_health_checks());
+    Ok(())
+}
+
+/// Opens the persistent deletion queue at `path` and starts its worker on the current runtime.
+///
+/// Once initialized, `hash_rm` and `hash_rm_many` queue their deletes, which are retried until
+/// they succeed. Without it, deletes are attempted once and failures are only logged.
+pub fn init_deletion_queue(path: impl AsRef<Path>) -> anyhow::Result<()> {
+    let queue = Arc::new(DeletionQueue::open(path)?);
+    DELETION_QUEUE
+        .set(queue.clone())
+        .map_err(|_| anyhow!("deletion queue is already initialized"))?;
+    spawn(queue.run());
+    Ok(())
+}
 
 fn hash_source(bytes: &[u8]) -> Result<[u8; 32]> {
     bytes
@@ -31,26 +101,326 @@ fn hash_source(bytes: &[u8]) -> Result<[u8; 32]> {
 pub fn hash_rm(context: Context<'_, impl RecallOps>, hash_offset: u32) -> Result<()> {
     let hash_bytes = context.memory.try_slice(hash_offset, 32)?;
     let hash = Hash::from_bytes(hash_source(hash_bytes)?);
-    let iroh = IROH_INSTANCE.clone();
+    submit_deletes(vec![hash]);
+    Ok(())
+}
+
+/// Deletes `count` hashes laid out contiguously at `hashes_off`.
+///
+/// All deletes are submitted together, and they share a global concurrency limit with `hash_rm`.
+pub fn hash_rm_many(
+    context: Context<'_, impl RecallOps>,
+    hashes_off: u32,
+    count: u32,
+) -> Result<()> {
+    if count > MAX_HASH_RM_MANY_COUNT {
+        return Err(ExecutionError::Syscall(SyscallError::new(
+            ErrorNumber::IllegalArgument,
+            format!(
+                "hash_rm_many count {} exceeds maximum {}",
+                count, MAX_HASH_RM_MANY_COUNT
+            ),
+        )));
+    }
+    let hashes_bytes = context.memory.try_slice(hashes_off, count * 32)?;
+    let hashes = hashes_bytes
+        .chunks_exact(32)
+        .map(|bytes| hash_source(bytes).map(Hash::from_bytes))
+        .collect::<Result<Vec<_>>>()?;
+    if !hashes.is_empty() {
+        submit_deletes(hashes);
+    }
+    Ok(())
+}
+
+/// Pins a blob in the local Iroh store so that Iroh's GC never removes it.
+///
+/// The pin is set in the background and doesn't require the blob to be available yet;
+/// content that is downloaded later is protected by it.
+pub fn hash_pin(context: Context<'_, impl RecallOps>, hash_offset: u32) -> Result<()> {
+    let hash_bytes = context.memory.try_slice(hash_offset, 32)?;
+    let hash = Hash::from_bytes(hash_source(hash_bytes)?);
+    // Don't block the chain with this.
+    spawn(async move {
+        match with_iroh("pin", |iroh| async move { pin_blob(&iroh, hash).await }).await {
+            Ok(()) => tracing::debug!(hash = ?hash, "pinned blob in Iroh"),
+            Err(e) => {
+                tracing::warn!(hash = ?hash, error = e.to_string(), "pinning blob in Iroh failed")
+            }
+        }
+    });
+    Ok(())
+}
 
+/// Removes the pin set by `hash_pin`, leaving the blob to Iroh's GC
+/// unless something else still references it.
+pub fn hash_unpin(context: Context<'_, impl RecallOps>, hash_offset: u32) -> Result<()> {
+    let hash_bytes = context.memory.try_slice(hash_offset, 32)?;
+    let hash = Hash::from_bytes(hash_source(hash_bytes)?);
     // Don't block the chain with this.
     spawn(async move {
-        let iroh_client = match iroh.lock().await.client().await {
-            Ok(client) => client,
+        match with_iroh("unpin", |iroh| async move { unpin_blob(&iroh, hash).await }).await {
+            Ok(()) => tracing::debug!(hash = ?hash, "unpinned blob in Iroh"),
             Err(e) => {
-                tracing::error!(hash = ?hash, error = e.to_string(), "failed to initialize Iroh client");
-                return;
+                tracing::warn!(hash = ?hash, error = e.to_string(), "unpinning blob in Iroh failed")
             }
-        };
-        // Deleting the tag will trigger deletion of the blob if it was the last reference.
-        // TODO: this needs to be tagged with a "user id"
-        let tag = iroh::blobs::Tag(format!("stored-seq-{hash}").into());
-        match iroh_client.tags().delete(tag.clone()).await {
-            Ok(_) => tracing::debug!(tag = ?tag, hash = ?hash, "removed content from Iroh"),
+        }
+    });
+    Ok(())
+}
+
+/// Returns the tag an actor sets on a blob with `hash_tag`.
+///
+/// Tags are namespaced by actor ID, so listing the tags with the `actor-{id}-` prefix
+/// attributes local disk usage to the actor. The label keeps the references an actor
+/// holds to the same blob apart, so each of them can be removed on its own.
+pub fn actor_tag(actor_id: ActorID, hash: Hash, label: &[u8; 32]) -> Tag {
+    Tag(format!("{ACTOR_TAG_PREFIX}{actor_id}-{hash}-{}", hex::encode(label)).into())
+}
+
+/// Tags a blob in the local Iroh store under the calling actor's namespace.
+///
+/// The tag is set in the background. Tags are for attributing disk usage to actors;
+/// like pins, they keep content from Iroh's GC, so actors must remove them with `hash_untag`.
+pub fn hash_tag(
+    context: Context<'_, impl Kernel + MessageOps>,
+    hash_offset: u32,
+    label_offset: u32,
+) -> Result<()> {
+    let actor_id = context.kernel.msg_context()?.receiver;
+    let hash_bytes = context.memory.try_slice(hash_offset, 32)?;
+    let hash = Hash::from_bytes(hash_source(hash_bytes)?);
+    let label = hash_source(context.memory.try_slice(label_offset, 32)?)?;
+    let tag = actor_tag(actor_id, hash, &label);
+    // Don't block the chain with this.
+    spawn(async move {
+        let res = with_iroh("tag", |iroh| {
+            let tag = tag.clone();
+            async move { tag_blob(&iroh, hash, tag).await }
+        })
+        .await;
+        match res {
+            Ok(()) => tracing::debug!(tag = ?tag, hash = ?hash, "tagged blob in Iroh"),
             Err(e) => {
-                tracing::warn!(tag = ?tag, hash = ?hash, error = e.to_string(), "deleting tag from Iroh failed");
+                tracing::warn!(tag = ?tag, hash = ?hash, error = e.to_string(), "tagging blob in Iroh failed")
             }
         }
     });
     Ok(())
 }
+
+/// Removes the tag set by the calling actor with `hash_tag`.
+pub fn hash_untag(
+    context: Context<'_, impl Kernel + MessageOps>,
+    hash_offset: u32,
+    label_offset: u32,
+) -> Result<()> {
+    let actor_id = context.kernel.msg_context()?.receiver;
+    let hash_bytes = context.memory.try_slice(hash_offset, 32)?;
+    let hash = Hash::from_bytes(hash_source(hash_bytes)?);
+    let label = hash_source(context.memory.try_slice(label_offset, 32)?)?;
+    let tag = actor_tag(actor_id, hash, &label);
+    // Don't block the chain with this.
+    spawn(async move {
+        let res = with_iroh("untag", |iroh| {
+            let tag = tag.clone();
+            async move { iroh.tags().delete(tag).await }
+        })
+        .await;
+        match res {
+            Ok(()) => tracing::debug!(tag = ?tag, hash = ?hash, "untagged blob in Iroh"),
+            Err(e) => {
+                tracing::warn!(tag = ?tag, hash = ?hash, error = e.to_string(), "untagging blob in Iroh failed")
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Submits deletes to the deletion queue if it's initialized,
+/// or attempts them once in the background otherwise.
+fn submit_deletes(hashes: Vec<Hash>) {
+    if let Some(queue) = DELETION_QUEUE.get() {
+        match queue.push(&hashes) {
+            Ok(()) => return,
+            Err(e) => {
+                tracing::error!(
+                    count = hashes.len(),
+                    error = e.to_string(),
+                    "failed to queue deletes; attempting them once"
+                );
+            }
+        }
+    }
+
+    // Don't block the chain with this.
+    spawn(async move {
+        stream::iter(hashes)
+            .for_each_concurrent(MAX_CONCURRENT_DELETES, |hash| async move {
+                delete_hash(hash).await;
+            })
+            .await;
+    });
+}
+
+/// Runs a request against the configured Iroh node, giving up after the request timeout.
+/// The request latency is recorded under `operation`.
+///
+/// If the request fails, its pooled connection is checked and dropped if it was lost,
+/// so it's replaced on next use.
+async fn with_iroh<T, F, Fut>(operation: &str, f: F) -> anyhow::Result<T>
+where
+    F: FnOnce(Iroh) -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let pool = iroh_pool()?;
+    let (index, client) = pool.client().await?;
+    let timeout = pool.request_timeout();
+    let start = Instant::now();
+    let res = match tokio::time::timeout(timeout, f(client)).await {
+        Ok(res) => res,
+        Err(_) => Err(anyhow!("iroh request timed out after {:?}", timeout)),
+    };
+    emit(IrohRequested {
+        operation,
+        status: if res.is_ok() { "success" } else { "error" },
+        latency: start.elapsed().as_secs_f64(),
+    });
+    if res.is_err() {
+        pool.check_connection(index).await;
+    }
+    res
+}
+
+/// Returns a client for the configured Iroh node.
+async fn iroh_client() -> anyhow::Result<Iroh> {
+    iroh_pool()?.client().await.map(|(_, client)| client)
+}
+
+fn iroh_pool() -> anyhow::Result<&'static Arc<IrohPool>> {
+    IROH_INSTANCE
+        .get()
+        .ok_or_else(|| anyhow!("iroh is not configured"))
+}
+
+/// Deletes the blob's tag from Iroh. Returns whether the delete succeeded.
+async fn delete_hash(hash: Hash) -> bool {
+    let Ok(_permit) = DELETE_PERMITS.acquire().await else {
+        return false;
+    };
+    // Deleting the tag will trigger deletion of the blob if it was the last reference.
+    // TODO: this needs to be tagged with a "user id"
+    let tag = iroh::blobs::Tag(format!("{STORED_TAG_PREFIX}{hash}").into());
+    let res = with_iroh("delete", |iroh| {
+        let tag = tag.clone();
+        async move { iroh.tags().delete(tag).await }
+    })
+    .await;
+    emit(IrohDeleted {
+        success: res.is_ok(),
+    });
+    match res {
+        Ok(_) => {
+            tracing::debug!(tag = ?tag, hash = ?hash, "removed content from Iroh");
+            true
+        }
+        Err(e) => {
+            tracing::warn!(tag = ?tag, hash = ?hash, error = e.to_string(), "deleting tag from Iroh failed");
+            false
+        }
+    }
+}
+
+/// Copies up to `len` bytes of a blob, starting at `offset`, into the actor's memory at `obuf_off`.
+/// Returns the number of bytes copied, which is less than `len` if the blob ends first.
+///
+/// The hash is the blob hash as tracked by the blobs actor, i.e., the hash sequence that wraps
+/// the user data. The read is synchronous and only succeeds if the blob is available on the
+/// local Iroh node, so it should only be used for blobs that the chain has already resolved.
+/// Gas is charged for the requested length up front, and `len` is capped at [`MAX_HASH_GET_LEN`].
+pub fn hash_get(
+    context: Context<'_, impl Kernel>,
+    hash_off: u32,
+    offset: u64,
+    len: u32,
+    obuf_off: u32,
+) -> Result<u32> {
+    if len > MAX_HASH_GET_LEN {
+        return Err(ExecutionError::Syscall(SyscallError::new(
+            ErrorNumber::IllegalArgument,
+            format!(
+                "hash_get length {} exceeds maximum {}",
+                len, MAX_HASH_GET_LEN
+            ),
+        )));
+    }
+    let t = context.kernel.charge_gas(
+        "OnHashGet",
+        Gas::new(HASH_GET_BASE_GAS + HASH_GET_GAS_PER_BYTE * len as u64),
+    )?;
+
+    let hash_bytes = context.memory.try_slice(hash_off, 32)?;
+    let hash = Hash::from_bytes(hash_source(hash_bytes)?);
+    let obuf = context.memory.try_slice_mut(obuf_off, len)?;
+
+    let bytes = block_in_place(|| Handle::current().block_on(read_hash(hash, offset, len)))
+        .map_err(|e| {
+            tracing::debug!(hash = ?hash, error = e.to_string(), "failed to read blob from Iroh");
+            ExecutionError::Syscall(SyscallError::new(ErrorNumber::NotFound, e))
+        })?;
+    let n = bytes.len().min(len as usize);
+    obuf[..n].copy_from_slice(&bytes[..n]);
+    emit(IrohBytesRead(n as u64));
+
+    t.stop();
+    Ok(n as u32)
+}
+
+async fn read_hash(seq_hash: Hash, offset: u64, len: u32) -> anyhow::Result<Vec<u8>> {
+    with_iroh("read", |iroh| async move {
+        let (hash, _) = get_blob_hash_and_size(&iroh, seq_hash).await?;
+        let bytes = iroh
+            .blobs()
+            .read_at_to_bytes(hash, offset, ReadAtLen::AtMost(len as u64))
+            .await?;
+        Ok(bytes.to_vec())
+    })
+    .await
+}
+
+/// Returns the size of a blob if it's available on the local Iroh node.
+/// Fails with [`ErrorNumber::NotFound`] if the blob or its hash sequence is missing or incomplete.
+///
+/// Like `hash_get`, the answer depends on the local node, so actors should only act on it in
+/// ways that don't affect consensus state, e.g., to skip redundant work.
+pub fn hash_stat(context: Context<'_, impl Kernel>, hash_off: u32) -> Result<u64> {
+    let t = context
+        .kernel
+        .charge_gas("OnHashStat", Gas::new(HASH_STAT_GAS))?;
+
+    let hash_bytes = context.memory.try_slice(hash_off, 32)?;
+    let hash = Hash::from_bytes(hash_source(hash_bytes)?);
+
+    let size = block_in_place(|| Handle::current().block_on(stat_hash(hash)))
+        .map_err(|e| {
+            tracing::debug!(hash = ?hash, error = e.to_string(), "failed to stat blob in Iroh");
+            ExecutionError::Syscall(SyscallError::new(ErrorNumber::IllegalOperation, e))
+        })?
+        .ok_or_else(|| {
+            ExecutionError::Syscall(SyscallError::new(
+                ErrorNumber::NotFound,
+                format!("blob {} is not available", hash),
+            ))
+        })?;
+
+    t.stop();
+    Ok(size)
+}
+
+async fn stat_hash(seq_hash: Hash) -> anyhow::Result<Option<u64>> {
+    with_iroh(
+        "stat",
+        |iroh| async move { stat_blob(&iroh, seq_hash).await },
+    )
+    .await
+}
@@ -0,0 +1,128 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use ipc_observability::{
+    impl_traceable, impl_traceables, lazy_static, register_metrics, Recordable, TraceLevel,
+    Traceable,
+};
+use prometheus::{
+    register_histogram_vec, register_int_counter, register_int_gauge, HistogramVec, IntCounter,
+    IntGauge, Registry,
+};
+
+use crate::sweeper::SweepReport;
+
+register_metrics! {
+    IROH_DELETION_QUEUE_DEPTH: IntGauge
+        = register_int_gauge!(
+            "iroh_deletion_queue_depth",
+            "Iroh deletes waiting in the local deletion queue"
+        );
+    IROH_DELETES_ISSUED: IntCounter
+        = register_int_counter!("iroh_deletes_issued", "Iroh deletes issued by the syscalls");
+    IROH_DELETES_FAILED: IntCounter
+        = register_int_counter!("iroh_deletes_failed", "Iroh deletes that failed");
+    IROH_BYTES_READ: IntCounter
+        = register_int_counter!("iroh_bytes_read", "Blob bytes read from Iroh by hash_get");
+    IROH_RPC_LATENCY_SECS: HistogramVec
+        = register_histogram_vec!("iroh_rpc_latency_secs", "Iroh RPC request latency", &["operation", "status"]);
+    IROH_SWEEP_ORPHANS: IntCounter
+        = register_int_counter!("iroh_sweep_orphans", "Local Iroh blobs found by the sweeper that are not committed on chain");
+    IROH_SWEEP_REFETCHED: IntCounter
+        = register_int_counter!("iroh_sweep_refetched", "Committed blobs re-fetched by the sweeper because they were missing locally");
+    IROH_SWEEP_FAILED: IntCounter
+        = register_int_counter!("iroh_sweep_failed", "Blobs the sweeper failed to check, pin, or re-fetch");
+}
+
+impl_traceables!(
+    TraceLevel::Debug,
+    "RecallSyscalls",
+    DeletionQueueDepth,
+    IrohDeleted,
+    IrohBytesRead,
+    IrohRequested<'a>,
+    IrohSweepCompleted
+);
+
+#[derive(Debug)]
+pub struct DeletionQueueDepth(pub u64);
+
+impl Recordable for DeletionQueueDepth {
+    fn record_metrics(&self) {
+        IROH_DELETION_QUEUE_DEPTH.set(self.0 as i64);
+    }
+}
+
+#[derive(Debug)]
+pub struct IrohDeleted {
+    pub success: bool,
+}
+
+impl Recordable for IrohDeleted {
+    fn record_metrics(&self) {
+        IROH_DELETES_ISSUED.inc();
+        if !self.success {
+            IROH_DELETES_FAILED.inc();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct IrohBytesRead(pub u64);
+
+impl Recordable for IrohBytesRead {
+    fn record_metrics(&self) {
+        IROH_BYTES_READ.inc_by(self.0);
+    }
+}
+
+#[derive(Debug)]
+pub struct IrohRequested<'a> {
+    pub operation: &'a str,
+    pub status: &'a str,
+    pub latency: f64,
+}
+
+impl Recordable for IrohRequested<'_> {
+    fn record_metrics(&self) {
+        IROH_RPC_LATENCY_SECS
+            .with_label_values(&[self.operation, self.status])
+            .observe(self.latency);
+    }
+}
+
+#[derive(Debug)]
+pub struct IrohSweepCompleted(pub SweepReport);
+
+impl Recordable for IrohSweepCompleted {
+    fn record_metrics(&self) {
+        IROH_SWEEP_ORPHANS.inc_by(self.0.orphaned);
+        IROH_SWEEP_REFETCHED.inc_by(self.0.refetched);
+        IROH_SWEEP_FAILED.inc_by(self.0.failed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ipc_observability::emit;
+
+    #[test]
+    fn test_metrics() {
+        let registry = Registry::new();
+        register_metrics(&registry).unwrap();
+    }
+
+    #[test]
+    fn test_emit() {
+        emit(DeletionQueueDepth(1));
+        emit(IrohDeleted { success: false });
+        emit(IrohBytesRead(1024));
+        emit(IrohRequested {
+            operation: "read",
+            status: "success",
+            latency: 0.1,
+        });
+        emit(IrohSweepCompleted(SweepReport::default()));
+    }
+}
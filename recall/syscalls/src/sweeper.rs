@@ -0,0 +1,237 @@
+// Copyright 2025 Recall Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Reconciles the local Iroh store with the blobs committed in the blobs actor's state.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use futures::TryStreamExt;
+use ipc_observability::emit;
+use iroh::blobs::util::SetTagOption;
+use iroh::blobs::{BlobFormat, Hash, Tag};
+use iroh::client::blobs::{DownloadMode, DownloadOptions};
+use iroh::client::Iroh;
+use iroh::net::{NodeAddr, NodeId};
+use iroh_manager::{pin_blob, PIN_TAG_PREFIX};
+use tokio::task::block_in_place;
+use tokio::time::MissedTickBehavior;
+
+use crate::observe::IrohSweepCompleted;
+use crate::{
+    iroh_client, stat_hash, submit_deletes, with_iroh, ACTOR_TAG_PREFIX, STORED_TAG_PREFIX,
+};
+
+/// The number of committed blobs to list per page.
+const LIST_PAGE_SIZE: u32 = 1000;
+/// The maximum number of missing blobs to re-fetch in a single sweep.
+const MAX_REFETCHES_PER_SWEEP: usize = 100;
+/// The maximum number of subscribers whose sources are used to re-fetch a single blob.
+pub const MAX_REFETCH_SOURCES: u32 = 10;
+
+/// A blob as committed in the blobs actor's state.
+#[derive(Clone, Debug)]
+pub struct CommittedBlob {
+    /// The blob hash, i.e., the hash sequence that wraps the user data.
+    pub hash: Hash,
+    /// Whether the blob was finalized as resolved.
+    pub resolved: bool,
+}
+
+/// Read access to the blobs committed on chain.
+pub trait BlobCatalog: Send + Sync {
+    /// Returns a page of committed blobs starting at `start_key` (inclusive),
+    /// and the key to continue listing from, if there are more blobs.
+    fn list_blobs(
+        &self,
+        start_key: Option<Hash>,
+        limit: u32,
+    ) -> anyhow::Result<(Vec<CommittedBlob>, Option<Hash>)>;
+
+    /// Returns the Iroh nodes a committed blob was ingested from.
+    fn blob_sources(&self, hash: Hash) -> anyhow::Result<Vec<NodeId>>;
+}
+
+/// The outcome of a single sweep.
+#[derive(Clone, Debug, Default)]
+pub struct SweepReport {
+    /// Committed blobs that were checked.
+    pub checked: u64,
+    /// Local blobs that were not committed and were queued for deletion.
+    pub orphaned: u64,
+    /// Resolved blobs that were missing locally and were re-fetched.
+    pub refetched: u64,
+    /// Resolved blobs that were not pinned locally and were pinned.
+    pub pinned: u64,
+    /// Blobs that could not be checked, pinned, or re-fetched.
+    pub failed: u64,
+}
+
+/// The local tags of a blob.
+#[derive(Debug, Default)]
+struct LocalTags {
+    pinned: bool,
+    /// Tags set by actors with `hash_tag`.
+    actor_tags: Vec<Tag>,
+}
+
+/// Sweeps the local Iroh store every `interval`, until the task is dropped.
+///
+/// The first sweep happens one interval after starting, so a node has time to catch up
+/// with the chain before its store is compared against it.
+pub async fn run_sweeper<C: BlobCatalog>(catalog: C, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        match sweep(&catalog).await {
+            Ok(report) => emit(IrohSweepCompleted(report)),
+            Err(e) => tracing::warn!(error = e.to_string(), "failed to sweep the Iroh store"),
+        }
+    }
+}
+
+/// Compares the local Iroh store with the committed blobs once.
+///
+/// Local blobs that the chain doesn't know about are queued for deletion, along with any actor
+/// tags on them, and resolved blobs that are missing locally are re-fetched from their sources.
+/// Pinned blobs are never deleted, because the chain may still hold them in the trash;
+/// they are unpinned and deleted when the trash window ends.
+pub async fn sweep(catalog: &impl BlobCatalog) -> anyhow::Result<SweepReport> {
+    // List local blobs before committed ones, so blobs downloaded during the sweep
+    // are never mistaken for orphans.
+    let mut local = local_blobs().await?;
+    let mut report = SweepReport::default();
+    let mut missing = Vec::new();
+
+    let mut start_key = None;
+    loop {
+        let (blobs, next_key) = block_in_place(|| catalog.list_blobs(start_key, LIST_PAGE_SIZE))?;
+        for blob in blobs {
+            report.checked += 1;
+            let tags = local.remove(&blob.hash).unwrap_or_default();
+            if !blob.resolved {
+                continue;
+            }
+            if !tags.pinned {
+                let hash = blob.hash;
+                match with_iroh("pin", |iroh| async move { pin_blob(&iroh, hash).await }).await {
+                    Ok(()) => report.pinned += 1,
+                    Err(e) => {
+                        tracing::warn!(hash = ?hash, error = e.to_string(), "failed to pin blob");
+                        report.failed += 1;
+                    }
+                }
+            }
+            match stat_hash(blob.hash).await {
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    if missing.len() < MAX_REFETCHES_PER_SWEEP {
+                        missing.push(blob.hash);
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(hash = ?blob.hash, error = e.to_string(), "failed to stat blob");
+                    report.failed += 1;
+                }
+            }
+        }
+        match next_key {
+            Some(key) => start_key = Some(key),
+            None => break,
+        }
+    }
+
+    // Whatever is left locally is not committed
+    let mut orphans = Vec::new();
+    for (hash, tags) in local {
+        if tags.pinned {
+            continue;
+        }
+        for tag in tags.actor_tags {
+            let res = with_iroh("untag", |iroh| {
+                let tag = tag.clone();
+                async move { iroh.tags().delete(tag).await }
+            })
+            .await;
+            if let Err(e) = res {
+                tracing::warn!(tag = ?tag, error = e.to_string(), "failed to delete actor tag");
+                report.failed += 1;
+            }
+        }
+        orphans.push(hash);
+    }
+    report.orphaned = orphans.len() as u64;
+    if !orphans.is_empty() {
+        submit_deletes(orphans);
+    }
+
+    for hash in missing {
+        match refetch(catalog, hash).await {
+            Ok(()) => report.refetched += 1,
+            Err(e) => {
+                tracing::warn!(hash = ?hash, error = e.to_string(), "failed to re-fetch blob");
+                report.failed += 1;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Returns the blobs tagged by the resolver or actors, or pinned on the local Iroh node.
+async fn local_blobs() -> anyhow::Result<HashMap<Hash, LocalTags>> {
+    with_iroh("list_tags", |iroh| async move {
+        let mut local: HashMap<Hash, LocalTags> = HashMap::new();
+        let mut tags = iroh.tags().list().await?;
+        while let Some(tag) = tags.try_next().await? {
+            let name = String::from_utf8_lossy(tag.name.as_ref());
+            if name.starts_with(PIN_TAG_PREFIX) {
+                local.entry(tag.hash).or_default().pinned = true;
+            } else if name.starts_with(ACTOR_TAG_PREFIX) {
+                local
+                    .entry(tag.hash)
+                    .or_default()
+                    .actor_tags
+                    .push(tag.name.clone());
+            } else if name.starts_with(STORED_TAG_PREFIX) {
+                local.entry(tag.hash).or_default();
+            }
+        }
+        Ok(local)
+    })
+    .await
+}
+
+/// Downloads a blob from its sources.
+///
+/// Downloads can take much longer than a single request, so they aren't subject to the
+/// request timeout.
+async fn refetch(catalog: &impl BlobCatalog, hash: Hash) -> anyhow::Result<()> {
+    let sources = block_in_place(|| catalog.blob_sources(hash))?;
+    if sources.is_empty() {
+        return Err(anyhow::anyhow!("blob {} has no sources", hash));
+    }
+    let iroh = iroh_client().await?;
+    download(&iroh, hash, sources).await?;
+    tracing::debug!(hash = ?hash, "re-fetched missing blob");
+    Ok(())
+}
+
+async fn download(iroh: &Iroh, seq_hash: Hash, sources: Vec<NodeId>) -> anyhow::Result<()> {
+    let tag = Tag(format!("{STORED_TAG_PREFIX}{seq_hash}").into());
+    iroh.blobs()
+        .download_with_opts(
+            seq_hash,
+            DownloadOptions {
+                format: BlobFormat::HashSeq,
+                nodes: sources.into_iter().map(NodeAddr::from).collect(),
+                tag: SetTagOption::Named(tag),
+                mode: DownloadMode::Queued,
+            },
+        )
+        .await?
+        .await?;
+    Ok(())
+}